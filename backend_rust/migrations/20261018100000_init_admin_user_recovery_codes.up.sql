@@ -0,0 +1,21 @@
+ALTER TABLE temporary_tokens
+DROP CONSTRAINT IF EXISTS temporary_tokens_purpose_check;
+
+ALTER TABLE temporary_tokens
+ADD CONSTRAINT temporary_tokens_purpose_check
+CHECK (purpose IN ('two_fa', 'password_reset', 'two_fa_enrollment'));
+
+CREATE TABLE admin_user_recovery_codes (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    code_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    used_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_admin_user_recovery_codes_user
+        FOREIGN KEY (user_id) REFERENCES admin_users(id) ON DELETE CASCADE,
+    CONSTRAINT uq_admin_user_recovery_codes_user_hash
+        UNIQUE (user_id, code_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_user_recovery_codes_unused ON admin_user_recovery_codes (user_id) WHERE used_at IS NULL;
@@ -0,0 +1,22 @@
+CREATE TABLE admin_api_keys (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    allowed_ips INET[] NOT NULL DEFAULT '{}',
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    last_used_ip INET,
+    created_by BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_admin_api_keys_created_by
+        FOREIGN KEY (created_by) REFERENCES admin_users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_api_keys_created_by ON admin_api_keys (created_by);
+
+INSERT INTO permissions (name, "group", description) VALUES
+('api_keys:manage', 'api_keys', 'Управление API-ключами');
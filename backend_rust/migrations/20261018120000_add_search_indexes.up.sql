@@ -0,0 +1,6 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_products_name_trgm ON products USING GIN (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_products_name_fts ON products USING GIN (to_tsvector('simple', name));
+CREATE INDEX IF NOT EXISTS idx_products_external_id_trgm ON products USING GIN (external_id gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_payment_invoices_gateway_invoice_id_trgm ON payment_invoices USING GIN (gateway_invoice_id gin_trgm_ops);
@@ -0,0 +1,30 @@
+ALTER TABLE broadcasts DROP CONSTRAINT broadcasts_status_check;
+ALTER TABLE broadcasts ADD CONSTRAINT broadcasts_status_check
+    CHECK (status IN ('pending', 'scheduled', 'in_progress', 'paused', 'completed', 'cancelled', 'failed'));
+
+CREATE TABLE broadcast_recipients (
+    id BIGSERIAL PRIMARY KEY,
+    broadcast_id BIGINT NOT NULL,
+    customer_id BIGINT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'blocked', 'failed')),
+    error_message TEXT,
+    sent_at TIMESTAMPTZ,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_broadcast_recipients_broadcast
+        FOREIGN KEY (broadcast_id) REFERENCES broadcasts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_broadcast_recipients_customer
+        FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    CONSTRAINT uq_broadcast_recipients_customer
+        UNIQUE (broadcast_id, customer_id)
+);
+
+CREATE INDEX idx_broadcast_recipients_pending ON broadcast_recipients (broadcast_id, id) WHERE status = 'pending';
+CREATE INDEX idx_broadcast_recipients_broadcast_status ON broadcast_recipients (broadcast_id, status);
+
+CREATE TRIGGER set_updated_at_broadcast_recipients
+    BEFORE UPDATE ON broadcast_recipients
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
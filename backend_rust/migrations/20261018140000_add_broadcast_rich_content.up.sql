@@ -0,0 +1,12 @@
+ALTER TABLE broadcasts
+    ADD COLUMN content_entities JSONB NOT NULL DEFAULT '[]'::jsonb,
+    ADD COLUMN content_media JSONB NOT NULL DEFAULT '[]'::jsonb,
+    ADD COLUMN content_buttons JSONB NOT NULL DEFAULT '[]'::jsonb;
+
+ALTER TABLE broadcasts DROP CONSTRAINT chk_broadcast_has_content;
+ALTER TABLE broadcasts ADD CONSTRAINT chk_broadcast_has_content
+    CHECK (
+        content_text IS NOT NULL
+        OR content_image_id IS NOT NULL
+        OR jsonb_array_length(content_media) > 0
+    );
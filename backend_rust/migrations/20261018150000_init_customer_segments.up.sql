@@ -0,0 +1,91 @@
+-- Customers with purchase/deposit aggregates, used for audience filters.
+-- Recreate the view when columns are added to `customers`.
+CREATE VIEW customer_audience AS
+SELECT
+    c.*,
+    COALESCE(o.orders_count, 0) AS orders_count,
+    COALESCE(o.orders_total, 0) AS orders_total,
+    o.last_order_at,
+    (EXTRACT(EPOCH FROM NOW() - o.last_order_at) / 86400)::INTEGER AS days_since_last_order,
+    COALESCE(d.deposits_count, 0) AS deposits_count,
+    COALESCE(d.deposits_total, 0) AS deposits_total,
+    d.last_deposit_at,
+    (EXTRACT(EPOCH FROM NOW() - d.last_deposit_at) / 86400)::INTEGER AS days_since_last_deposit,
+    (EXTRACT(EPOCH FROM NOW() - c.created_at) / 86400)::INTEGER AS days_since_registration
+FROM customers c
+LEFT JOIN (
+    SELECT
+        customer_id,
+        COUNT(*) AS orders_count,
+        SUM(amount) AS orders_total,
+        MAX(created_at) AS last_order_at
+    FROM orders
+    WHERE status IN ('paid', 'fulfilled')
+    GROUP BY customer_id
+) o ON o.customer_id = c.id
+LEFT JOIN (
+    SELECT
+        customer_id,
+        COUNT(*) AS deposits_count,
+        SUM(amount) AS deposits_total,
+        MAX(created_at) AS last_deposit_at
+    FROM transactions
+    WHERE type = 'deposit' AND customer_id IS NOT NULL
+    GROUP BY customer_id
+) d ON d.customer_id = c.id;
+
+CREATE TABLE customer_segments (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    filters JSONB NOT NULL,
+    created_by BIGINT NOT NULL,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT uq_customer_segments_name UNIQUE (name),
+    CONSTRAINT fk_customer_segments_created_by
+        FOREIGN KEY (created_by) REFERENCES admin_users(id) ON DELETE RESTRICT
+);
+
+CREATE TRIGGER set_updated_at_customer_segments
+    BEFORE UPDATE ON customer_segments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE broadcasts
+    ADD COLUMN segment_id BIGINT,
+    ADD CONSTRAINT fk_broadcasts_segment
+        FOREIGN KEY (segment_id) REFERENCES customer_segments(id) ON DELETE SET NULL;
+
+-- Alternative contents of a broadcast, the broadcast's own content is variant "A"
+CREATE TABLE broadcast_variants (
+    id BIGSERIAL PRIMARY KEY,
+    broadcast_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    content_text TEXT,
+    content_image_id UUID,
+    content_entities JSONB NOT NULL DEFAULT '[]'::jsonb,
+    content_media JSONB NOT NULL DEFAULT '[]'::jsonb,
+    content_buttons JSONB NOT NULL DEFAULT '[]'::jsonb,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_broadcast_variants_broadcast
+        FOREIGN KEY (broadcast_id) REFERENCES broadcasts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_broadcast_variants_content_image
+        FOREIGN KEY (content_image_id) REFERENCES images(id) ON DELETE SET NULL,
+    CONSTRAINT uq_broadcast_variants_name UNIQUE (broadcast_id, name),
+    CONSTRAINT chk_broadcast_variant_has_content
+        CHECK (
+            content_text IS NOT NULL
+            OR content_image_id IS NOT NULL
+            OR jsonb_array_length(content_media) > 0
+        )
+);
+
+ALTER TABLE broadcast_recipients
+    ADD COLUMN variant_id BIGINT,
+    ADD CONSTRAINT fk_broadcast_recipients_variant
+        FOREIGN KEY (variant_id) REFERENCES broadcast_variants(id) ON DELETE SET NULL;
@@ -0,0 +1,27 @@
+-- External products that left the supplier catalog are hidden instead of deleted,
+-- so they come back with the same id once the supplier lists them again.
+ALTER TABLE products ADD COLUMN hidden_at TIMESTAMPTZ;
+
+CREATE TABLE product_sync_runs (
+    id BIGSERIAL PRIMARY KEY,
+    provider_name TEXT NOT NULL,
+    trigger TEXT NOT NULL CHECK (trigger IN ('schedule', 'manual')),
+    status TEXT NOT NULL DEFAULT 'running'
+        CHECK (status IN ('running', 'succeeded', 'partial', 'failed')),
+
+    added INTEGER NOT NULL DEFAULT 0,
+    updated INTEGER NOT NULL DEFAULT 0,
+    hidden INTEGER NOT NULL DEFAULT 0,
+    restored INTEGER NOT NULL DEFAULT 0,
+    errors JSONB NOT NULL DEFAULT '[]',
+
+    triggered_by BIGINT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_product_sync_runs_triggered_by
+        FOREIGN KEY (triggered_by) REFERENCES admin_users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_product_sync_runs_provider_started
+    ON product_sync_runs (provider_name, started_at DESC);
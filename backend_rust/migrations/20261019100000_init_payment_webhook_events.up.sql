@@ -0,0 +1,13 @@
+CREATE TABLE payment_webhook_events (
+    id BIGSERIAL PRIMARY KEY,
+    gateway TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    gateway_invoice_id TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ,
+    error TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_webhook_events_gateway_event_id ON payment_webhook_events (gateway, event_id);
+CREATE INDEX IF NOT EXISTS idx_payment_webhook_events_gateway_invoice_id ON payment_webhook_events (gateway, gateway_invoice_id);
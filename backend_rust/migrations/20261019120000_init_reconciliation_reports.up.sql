@@ -0,0 +1,33 @@
+ALTER TABLE transactions ADD COLUMN payment_invoice_id BIGINT;
+ALTER TABLE transactions ADD CONSTRAINT fk_transactions_payment_invoice
+    FOREIGN KEY (payment_invoice_id) REFERENCES payment_invoices(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_transactions_payment_invoice_id
+    ON transactions (payment_invoice_id) WHERE payment_invoice_id IS NOT NULL;
+
+-- Deposits were not linked to their invoices before, match them by what was copied over
+UPDATE transactions t
+SET payment_invoice_id = i.id
+FROM payment_invoices i
+WHERE t.type = 'deposit'
+  AND t.payment_invoice_id IS NULL
+  AND i.status IN ('completed', 'refunded')
+  AND t.customer_id = i.customer_id
+  AND t.payment_gateway = i.gateway
+  AND t.amount = i.original_amount
+  AND t.details IS NOT DISTINCT FROM i.payment_details;
+
+CREATE TABLE reconciliation_reports (
+    id BIGSERIAL PRIMARY KEY,
+    day DATE NOT NULL,
+    transactions_checked INTEGER NOT NULL,
+    invoices_checked INTEGER NOT NULL,
+    customers_checked INTEGER NOT NULL,
+    discrepancies JSONB NOT NULL DEFAULT '[]',
+    triggered_by BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_reconciliation_reports_triggered_by
+        FOREIGN KEY (triggered_by) REFERENCES admin_users(id) ON DELETE SET NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reconciliation_reports_day ON reconciliation_reports (day);
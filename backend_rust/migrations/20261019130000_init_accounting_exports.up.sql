@@ -0,0 +1,31 @@
+CREATE TABLE accounting_exports (
+    id BIGSERIAL PRIMARY KEY,
+    entity TEXT NOT NULL
+        CHECK (entity IN ('transactions', 'orders', 'payment_invoices', 'stock_movements')),
+    format TEXT NOT NULL CHECK (format IN ('csv', 'xlsx')),
+    -- List filters as sent by the admin panel, parsed again when the export is built
+    query TEXT NOT NULL DEFAULT '',
+    date_from TIMESTAMPTZ NOT NULL,
+    date_to TIMESTAMPTZ NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'processing', 'completed', 'failed')),
+
+    rows_count BIGINT,
+    file_name TEXT,
+    download_token UUID,
+    error TEXT,
+
+    created_by BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_accounting_exports_created_by
+        FOREIGN KEY (created_by) REFERENCES admin_users(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_accounting_exports_download_token
+    ON accounting_exports (download_token) WHERE download_token IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_accounting_exports_queue
+    ON accounting_exports (created_at) WHERE status IN ('pending', 'processing');
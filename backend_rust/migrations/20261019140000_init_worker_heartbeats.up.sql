@@ -0,0 +1,6 @@
+CREATE TABLE worker_heartbeats (
+    worker TEXT PRIMARY KEY,
+    last_run_at TIMESTAMPTZ NOT NULL,
+    last_success_at TIMESTAMPTZ,
+    last_run_errors INTEGER NOT NULL DEFAULT 0
+);
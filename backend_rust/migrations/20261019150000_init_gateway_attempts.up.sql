@@ -0,0 +1,9 @@
+CREATE TABLE gateway_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    gateway TEXT NOT NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('success', 'no_requisites', 'error')),
+    latency_ms INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_gateway_attempts_gateway_created_at ON gateway_attempts (gateway, created_at);
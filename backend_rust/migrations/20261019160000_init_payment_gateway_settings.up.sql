@@ -0,0 +1,13 @@
+CREATE TABLE payment_gateway_settings (
+    gateway TEXT PRIMARY KEY,
+    invoice_ttl_minutes INTEGER NOT NULL DEFAULT 1440 CHECK (invoice_ttl_minutes > 0),
+    min_amount NUMERIC NOT NULL DEFAULT 1 CHECK (min_amount > 0),
+    max_amount NUMERIC NOT NULL DEFAULT 1000000,
+    amount_step NUMERIC NOT NULL DEFAULT 1 CHECK (amount_step > 0),
+    max_open_invoices INTEGER CHECK (max_open_invoices > 0),
+    daily_deposit_limit NUMERIC CHECK (daily_deposit_limit > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (min_amount <= max_amount)
+);
+
+INSERT INTO payment_gateway_settings (gateway) VALUES ('platform_card'), ('platform_sbp'), ('mock');
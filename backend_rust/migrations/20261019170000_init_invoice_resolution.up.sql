@@ -0,0 +1,5 @@
+-- Last receipt the customer submitted, kept so operators can re-send it to the gateway
+ALTER TABLE payment_invoices ADD COLUMN receipt_url TEXT;
+
+INSERT INTO permissions (name, "group", description) VALUES
+('invoices:resolve', 'invoices', 'Ручное закрытие инвойсов');
@@ -0,0 +1,50 @@
+-- Append-only history of an invoice: status transitions, redacted gateway responses
+-- and webhooks, notifications sent to the customer
+CREATE TABLE payment_invoice_events (
+    id BIGSERIAL PRIMARY KEY,
+    invoice_id BIGINT NOT NULL REFERENCES payment_invoices(id),
+    kind TEXT NOT NULL CHECK (kind IN ('status_changed', 'gateway_response', 'webhook', 'notification_sent')),
+    from_status TEXT,
+    to_status TEXT,
+    payload JSONB,
+    -- Events with a key are recorded once per invoice, e.g. a notification or a repeated gateway response
+    dedup_key TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_payment_invoice_events_invoice_id ON payment_invoice_events (invoice_id, id);
+CREATE UNIQUE INDEX uq_payment_invoice_events_dedup_key
+    ON payment_invoice_events (invoice_id, dedup_key) WHERE dedup_key IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION prevent_payment_invoice_events_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'payment_invoice_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_payment_invoice_events_append_only
+    BEFORE UPDATE OR DELETE ON payment_invoice_events
+    FOR EACH ROW
+    EXECUTE FUNCTION prevent_payment_invoice_events_change();
+
+-- Logged by the database so bulk updates like expiring old invoices are covered too
+CREATE OR REPLACE FUNCTION log_payment_invoice_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO payment_invoice_events (invoice_id, kind, to_status)
+        VALUES (NEW.id, 'status_changed', NEW.status);
+    ELSIF OLD.status IS DISTINCT FROM NEW.status THEN
+        INSERT INTO payment_invoice_events (invoice_id, kind, from_status, to_status)
+        VALUES (NEW.id, 'status_changed', OLD.status, NEW.status);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_log_payment_invoice_status_change
+    AFTER INSERT OR UPDATE OF status ON payment_invoices
+    FOR EACH ROW
+    EXECUTE FUNCTION log_payment_invoice_status_change();
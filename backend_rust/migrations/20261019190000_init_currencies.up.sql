@@ -0,0 +1,55 @@
+-- Units of `currency` for one unit of `base_currency`, refreshed by the exchange rates worker
+CREATE TABLE exchange_rates (
+    base_currency TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    rate NUMERIC NOT NULL CHECK (rate > 0),
+    source TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (base_currency, currency),
+    CHECK (base_currency <> currency)
+);
+
+-- NULL while the customer sees amounts in the store base currency
+ALTER TABLE customers ADD COLUMN display_currency TEXT;
+
+-- `c.*` is expanded when the view is created
+DROP VIEW customer_audience;
+CREATE VIEW customer_audience AS
+SELECT
+    c.*,
+    COALESCE(o.orders_count, 0) AS orders_count,
+    COALESCE(o.orders_total, 0) AS orders_total,
+    o.last_order_at,
+    (EXTRACT(EPOCH FROM NOW() - o.last_order_at) / 86400)::INTEGER AS days_since_last_order,
+    COALESCE(d.deposits_count, 0) AS deposits_count,
+    COALESCE(d.deposits_total, 0) AS deposits_total,
+    d.last_deposit_at,
+    (EXTRACT(EPOCH FROM NOW() - d.last_deposit_at) / 86400)::INTEGER AS days_since_last_deposit,
+    (EXTRACT(EPOCH FROM NOW() - c.created_at) / 86400)::INTEGER AS days_since_registration
+FROM customers c
+LEFT JOIN (
+    SELECT
+        customer_id,
+        COUNT(*) AS orders_count,
+        SUM(amount) AS orders_total,
+        MAX(created_at) AS last_order_at
+    FROM orders
+    WHERE status IN ('paid', 'fulfilled')
+    GROUP BY customer_id
+) o ON o.customer_id = c.id
+LEFT JOIN (
+    SELECT
+        customer_id,
+        COUNT(*) AS deposits_count,
+        SUM(amount) AS deposits_total,
+        MAX(created_at) AS last_deposit_at
+    FROM transactions
+    WHERE type = 'deposit' AND customer_id IS NOT NULL
+    GROUP BY customer_id
+) d ON d.customer_id = c.id;
+
+-- All current gateways charge in rubles
+ALTER TABLE payment_gateway_settings ADD COLUMN currency TEXT NOT NULL DEFAULT 'RUB';
+
+-- Currency of `amount`; `original_amount` is credited in the store base currency
+ALTER TABLE payment_invoices ADD COLUMN currency TEXT NOT NULL DEFAULT 'RUB';
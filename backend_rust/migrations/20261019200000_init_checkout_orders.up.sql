@@ -0,0 +1,7 @@
+-- Order paid directly by the invoice (pay-for-product checkout); completing the invoice
+-- credits the deposit and then buys the order from the balance
+ALTER TABLE payment_invoices
+    ADD COLUMN checkout_order_id BIGINT REFERENCES orders(id) ON DELETE RESTRICT;
+
+CREATE INDEX IF NOT EXISTS idx_payment_invoices_checkout_order_id
+    ON payment_invoices (checkout_order_id) WHERE checkout_order_id IS NOT NULL;
@@ -0,0 +1,6 @@
+ALTER TABLE broadcast_recipients DROP CONSTRAINT broadcast_recipients_status_check;
+ALTER TABLE broadcast_recipients ADD CONSTRAINT broadcast_recipients_status_check
+    CHECK (status IN ('pending', 'dispatched', 'sent', 'blocked', 'failed'));
+
+CREATE INDEX idx_broadcast_recipients_dispatched
+    ON broadcast_recipients (broadcast_id, updated_at) WHERE status = 'dispatched';
@@ -0,0 +1,9 @@
+-- Export files are kept in the database so every API replica can serve what the worker built
+CREATE TABLE accounting_export_files (
+    export_id BIGINT PRIMARY KEY,
+    content BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_accounting_export_files_export
+        FOREIGN KEY (export_id) REFERENCES accounting_exports(id) ON DELETE CASCADE
+);
@@ -0,0 +1,22 @@
+-- Manual syncs are queued for the sync worker instead of running inside the request
+ALTER TABLE product_sync_runs DROP CONSTRAINT IF EXISTS product_sync_runs_status_check;
+ALTER TABLE product_sync_runs ADD CONSTRAINT product_sync_runs_status_check
+    CHECK (status IN ('queued', 'running', 'succeeded', 'partial', 'failed'));
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_product_sync_runs_queued
+    ON product_sync_runs (provider_name) WHERE status = 'queued';
+
+-- Catalog values the sync last wrote to each external product. A product field that no
+-- longer matches its snapshot was changed by an admin and is not overwritten by the sync.
+CREATE TABLE product_sync_snapshots (
+    product_id BIGINT PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- NULL while the supplier doesn't publish a price, the admin owns it then
+    base_price NUMERIC(12, 2),
+    category_id BIGINT NOT NULL,
+    details JSONB,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_product_sync_snapshots_product
+        FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+CREATE TABLE admin_user_pending_two_fa_secrets (
+    user_id BIGINT PRIMARY KEY,
+    two_fa_secret TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_admin_user_pending_two_fa_secrets_user
+        FOREIGN KEY (user_id) REFERENCES admin_users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+-- Invoices reserved before the gateway answers, and mock gateway invoices, have no USDT amount
+ALTER TABLE payment_invoices
+    DROP CONSTRAINT IF EXISTS payment_invoices_amount_in_usdt_check,
+    ADD CONSTRAINT payment_invoices_amount_in_usdt_check CHECK (amount_in_usdt >= 0);
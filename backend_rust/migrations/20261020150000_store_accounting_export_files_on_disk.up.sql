@@ -0,0 +1,5 @@
+-- Export files are written under ACCOUNTING_EXPORT_PATH and streamed from there.
+-- Exports whose files only lived in the table can't be downloaded anymore.
+UPDATE accounting_exports SET expires_at = NOW() WHERE status = 'completed';
+
+DROP TABLE IF EXISTS accounting_export_files;
@@ -159,6 +159,7 @@ async fn create_initial_store_balance(transaction_repo: &Arc<TransactionReposito
                 store_balance_delta: Decimal::from(initial_store_balance),
                 r#type: TransactionType::Deposit,
                 bot_id: None,
+                payment_invoice_id: None,
             })
             .await
             .expect("Failed to create initial store balance transaction")
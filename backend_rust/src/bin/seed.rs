@@ -224,8 +224,11 @@ pub async fn seed_products(
                 page_size: 1000,
             },
             filters: vec![],
+            groups: vec![],
             order_by: None,
             order_dir: OrderDir::Desc,
+            cursor: None,
+            search: None,
         })
         .await
         .unwrap();
@@ -1,15 +1,21 @@
+use axum_client_ip::ClientIpSource;
 use dotenvy::dotenv;
 use serde::Deserialize;
+use shared_dtos::currency::Currency;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub backend_port: u16,
+    /// Internal port of `/metrics`, kept off `backend_port` so it isn't public
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
     pub database_host: String,
     pub database_port: u16,
     pub database_user: String,
     pub database_password: String,
     pub database_name: String,
     pub cors_origins: String,
+    pub client_ip_source: Option<ClientIpSource>,
     pub jwt_secret: String,
     pub totp_encode_secret: String,
     pub two_fa_token_ttl_minutes: i64,
@@ -27,14 +33,189 @@ pub struct Config {
     pub payment_notification_minutes: u64,
     pub subscription_expiry_notification_window_hours: i64,
     pub subscription_expiry_notification_poll_interval_seconds: u64,
+    #[serde(default = "default_broadcast_batch_size")]
+    pub broadcast_batch_size: i64,
+    #[serde(default = "default_broadcast_send_interval_ms")]
+    pub broadcast_send_interval_ms: u64,
+    #[serde(default = "default_external_products_sync_interval_minutes")]
+    pub external_products_sync_interval_minutes: u64,
+    /// `provider=Category/{attribute}` or `provider/supplier category=...`, comma separated
+    #[serde(default)]
+    pub external_products_category_map: Option<String>,
+    /// `provider=percent`, comma separated; supplier prices are used as is otherwise
+    #[serde(default)]
+    pub external_products_price_markup: Option<String>,
+    /// `provider=price`, comma separated; used for new products when the supplier has no price
+    #[serde(default)]
+    pub external_products_default_price: Option<String>,
+    #[serde(default)]
+    pub run_mode: RunMode,
+    #[serde(default = "default_worker_lock_retry_interval_seconds")]
+    pub worker_lock_retry_interval_seconds: u64,
+    #[serde(default = "default_worker_lock_renew_interval_seconds")]
+    pub worker_lock_renew_interval_seconds: u64,
     pub platform_payment_system_base_url: String,
     pub platform_payment_system_login: String,
     pub platform_payment_system_password: String,
     pub platform_payment_system_2fa_key: String,
+    /// HMAC-SHA256 key of platform status webhooks; webhooks are rejected when unset
+    #[serde(default)]
+    pub platform_payment_system_webhook_secret: Option<String>,
+    /// How often pending invoices are polled from the platform once webhooks are enabled
+    #[serde(default = "default_payment_status_poll_interval_seconds")]
+    pub payment_status_poll_interval_seconds: u64,
+    /// How long bot responses are kept for replay by `Idempotency-Key`
+    #[serde(default = "default_idempotency_key_ttl_hours")]
+    pub idempotency_key_ttl_hours: i64,
+    /// How long a background export can be downloaded
+    #[serde(default = "default_accounting_export_ttl_hours")]
+    pub accounting_export_ttl_hours: i64,
+    /// Larger exports are queued instead of being streamed right away
+    #[serde(default = "default_accounting_export_sync_row_limit")]
+    pub accounting_export_sync_row_limit: i64,
+    /// Directory background export files are written to, shared by every replica
+    #[serde(default = "default_accounting_export_path")]
+    pub accounting_export_path: String,
+    /// Redis is only checked by `/readyz` when both are set
+    #[serde(default)]
+    pub redis_host: Option<String>,
+    #[serde(default)]
+    pub redis_port: Option<u16>,
+    /// Recent `init_order` attempts the gateway circuit breaker looks at
+    #[serde(default = "default_gateway_circuit_window_minutes")]
+    pub gateway_circuit_window_minutes: i64,
+    #[serde(default = "default_gateway_circuit_min_attempts")]
+    pub gateway_circuit_min_attempts: i64,
+    #[serde(default = "default_gateway_circuit_failure_rate_percent")]
+    pub gateway_circuit_failure_rate_percent: i64,
+    /// How long a tripped gateway stays hidden after its last failure
+    #[serde(default = "default_gateway_circuit_cooldown_seconds")]
+    pub gateway_circuit_cooldown_seconds: i64,
+    /// How long gateway attempts are kept for stats and the circuit breaker
+    #[serde(default = "default_gateway_attempts_retention_days")]
+    pub gateway_attempts_retention_days: i64,
+    /// Currency balances, prices and orders are kept in; changing it doesn't convert existing data
+    #[serde(default = "default_store_base_currency")]
+    pub store_base_currency: Currency,
+    #[serde(default)]
+    pub exchange_rate_source: ExchangeRateSourceKind,
+    /// `CURRENCY=rate` per one base unit, comma separated; used until the source has been fetched
+    #[serde(default)]
+    pub exchange_rates_static: Option<String>,
+    #[serde(default = "default_exchange_rates_refresh_interval_seconds")]
+    pub exchange_rates_refresh_interval_seconds: u64,
+    #[serde(default = "default_exchange_rates_cbr_url")]
+    pub exchange_rates_cbr_url: String,
     pub files_fm_upload_token: String,
     pub files_fm_folder_hash: String,
 }
 
+fn default_metrics_port() -> u16 {
+    9100
+}
+
+fn default_broadcast_batch_size() -> i64 {
+    100
+}
+
+// Because of the telegram rate limit https://core.telegram.org/bots/faq#my-bot-is-hitting-limits-how-do-i-avoid-this
+fn default_broadcast_send_interval_ms() -> u64 {
+    100
+}
+
+fn default_external_products_sync_interval_minutes() -> u64 {
+    5
+}
+
+fn default_payment_status_poll_interval_seconds() -> u64 {
+    300
+}
+
+fn default_idempotency_key_ttl_hours() -> i64 {
+    24
+}
+
+fn default_accounting_export_ttl_hours() -> i64 {
+    24
+}
+
+fn default_accounting_export_sync_row_limit() -> i64 {
+    10_000
+}
+
+fn default_accounting_export_path() -> String {
+    "exports".to_string()
+}
+
+fn default_gateway_circuit_window_minutes() -> i64 {
+    15
+}
+
+fn default_gateway_circuit_min_attempts() -> i64 {
+    5
+}
+
+fn default_gateway_circuit_failure_rate_percent() -> i64 {
+    50
+}
+
+fn default_gateway_circuit_cooldown_seconds() -> i64 {
+    300
+}
+
+fn default_gateway_attempts_retention_days() -> i64 {
+    30
+}
+
+fn default_store_base_currency() -> Currency {
+    Currency::Rub
+}
+
+fn default_exchange_rates_refresh_interval_seconds() -> u64 {
+    3600
+}
+
+fn default_exchange_rates_cbr_url() -> String {
+    "https://www.cbr-xml-daily.ru/daily_json.js".to_string()
+}
+
+fn default_worker_lock_retry_interval_seconds() -> u64 {
+    15
+}
+
+fn default_worker_lock_renew_interval_seconds() -> u64 {
+    10
+}
+
+/// Which parts of the backend this process runs (`RUN_MODE=all|api|worker`)
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    #[default]
+    All,
+    Api,
+    Worker,
+}
+
+/// Where the exchange rates worker takes rates from (`EXCHANGE_RATE_SOURCE=static|cbr`)
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeRateSourceKind {
+    #[default]
+    Static,
+    Cbr,
+}
+
+impl RunMode {
+    pub fn runs_api(self) -> bool {
+        matches!(self, Self::All | Self::Api)
+    }
+
+    pub fn runs_workers(self) -> bool {
+        matches!(self, Self::All | Self::Worker)
+    }
+}
+
 impl Config {
     pub fn from_env() -> Self {
         dotenv().ok();
@@ -13,6 +13,7 @@ use validator::ValidationErrors;
 use crate::{
     errors::{auth::AuthError, repository::RepositoryError, totp_encryptor::TotpEncryptorError},
     infrastructure::external::payment::autosales_platform::dto::AutosalesPlatformError,
+    models::payment_invoice::InvoiceLimitExceeded,
 };
 
 #[derive(Debug, Error)]
@@ -45,7 +46,7 @@ impl From<RepositoryError> for ApiError {
             }
             RepositoryError::Validation(_) => ApiError::BadRequest("Validation error".to_string()),
             RepositoryError::OptimisticLockViolation => {
-                ApiError::InternalServerError("Optimistic lock violation".to_string())
+                ApiError::Conflict("Record was changed concurrently".to_string())
             }
             RepositoryError::QueryFailed(err) => ApiError::InternalServerError(err.to_string()),
         }
@@ -95,6 +96,19 @@ impl From<AutosalesPlatformError> for ApiError {
     }
 }
 
+impl From<InvoiceLimitExceeded> for ApiError {
+    fn from(err: InvoiceLimitExceeded) -> Self {
+        match err {
+            InvoiceLimitExceeded::OpenInvoices => {
+                ApiError::Conflict("Too many open invoices".to_string())
+            }
+            InvoiceLimitExceeded::DailyDeposit => {
+                ApiError::Conflict("Daily deposit limit exceeded".to_string())
+            }
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         tracing::error!("Error occurred: {}", self.to_string());
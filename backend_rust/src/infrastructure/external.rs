@@ -1,2 +1,3 @@
+pub mod exchange_rates;
 pub mod payment;
 pub mod products;
@@ -0,0 +1,74 @@
+pub mod cbr;
+
+use std::{collections::HashMap, str::FromStr};
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use shared_dtos::currency::Currency;
+
+pub const STATIC_SOURCE_NAME: &str = "static";
+
+/// Where exchange rates come from. Rates are units of a currency for one unit of `base`;
+/// the base itself and currencies the source doesn't know are left out.
+#[async_trait]
+pub trait ExchangeRateSource: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn fetch_rates(&self, base: Currency) -> Result<HashMap<Currency, Decimal>, String>;
+}
+
+/// Rates from `EXCHANGE_RATES_STATIC`, given against the store base currency
+#[derive(Debug, Clone, Default)]
+pub struct StaticExchangeRateSource {
+    rates: HashMap<Currency, Decimal>,
+}
+
+impl StaticExchangeRateSource {
+    /// Parses `USD=0.011,EUR=0.0095`; unknown currencies and non-positive rates are skipped
+    pub fn parse(raw: &str) -> Self {
+        let rates = raw
+            .split(',')
+            .filter_map(|entry| entry.split_once('='))
+            .filter_map(|(code, rate)| {
+                let currency = Currency::from_code(code)?;
+                let rate = Decimal::from_str(rate.trim()).ok()?;
+                (rate > Decimal::ZERO).then_some((currency, rate))
+            })
+            .collect();
+        Self { rates }
+    }
+
+    pub fn rates(&self) -> &HashMap<Currency, Decimal> {
+        &self.rates
+    }
+}
+
+#[async_trait]
+impl ExchangeRateSource for StaticExchangeRateSource {
+    fn name(&self) -> &'static str {
+        STATIC_SOURCE_NAME
+    }
+
+    async fn fetch_rates(&self, base: Currency) -> Result<HashMap<Currency, Decimal>, String> {
+        let mut rates = self.rates.clone();
+        rates.remove(&base);
+        Ok(rates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn test_static_source_skips_invalid_entries() {
+        let source =
+            StaticExchangeRateSource::parse(" usd = 0.011,EUR=0.0095,GBP=0.008,USDT=-1,RUB");
+
+        assert_eq!(
+            source.rates(),
+            &HashMap::from([(Currency::Usd, dec!(0.011)), (Currency::Eur, dec!(0.0095))])
+        );
+    }
+}
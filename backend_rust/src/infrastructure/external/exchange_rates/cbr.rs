@@ -0,0 +1,126 @@
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use shared_dtos::currency::Currency;
+
+use crate::{infrastructure::external::exchange_rates::ExchangeRateSource, telemetry};
+
+pub const CBR_SOURCE_NAME: &str = "cbr";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CbrDailyResponse {
+    valute: HashMap<String, CbrValute>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CbrValute {
+    nominal: Decimal,
+    /// Rubles for `nominal` units of the currency
+    value: Decimal,
+}
+
+/// Official daily rates of the Central Bank of Russia. USDT is quoted as USD.
+pub struct CbrExchangeRateSource {
+    client: Arc<reqwest::Client>,
+    url: String,
+}
+
+impl CbrExchangeRateSource {
+    pub fn new(client: Arc<reqwest::Client>, url: String) -> Self {
+        Self { client, url }
+    }
+
+    async fn fetch_daily(&self) -> Result<CbrDailyResponse, String> {
+        let response = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .map_err(|e| format!("CBR: {e}"))?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| format!("CBR: {e}"))?;
+        if !status.is_success() {
+            return Err(format!(
+                "CBR: Request failed with status code: {status}, body: {body}"
+            ));
+        }
+        serde_json::from_str(&body).map_err(|e| format!("CBR: Failed to parse response: {e}"))
+    }
+}
+
+#[async_trait]
+impl ExchangeRateSource for CbrExchangeRateSource {
+    fn name(&self) -> &'static str {
+        CBR_SOURCE_NAME
+    }
+
+    async fn fetch_rates(&self, base: Currency) -> Result<HashMap<Currency, Decimal>, String> {
+        let started_at = Instant::now();
+        let result = self
+            .fetch_daily()
+            .await
+            .and_then(|daily| rates_for_base(&daily, base));
+        telemetry::record_gateway_request(CBR_SOURCE_NAME, "daily", started_at, result.is_ok());
+        result
+    }
+}
+
+/// Turns "rubles per unit" quotes into units per one `base`
+fn rates_for_base(
+    daily: &CbrDailyResponse,
+    base: Currency,
+) -> Result<HashMap<Currency, Decimal>, String> {
+    let rubles_per_unit = |currency: Currency| -> Option<Decimal> {
+        let code = match currency {
+            Currency::Rub => return Some(Decimal::ONE),
+            Currency::Usdt => Currency::Usd.code(),
+            other => other.code(),
+        };
+        let valute = daily.valute.get(code)?;
+        (valute.nominal > Decimal::ZERO && valute.value > Decimal::ZERO)
+            .then(|| valute.value / valute.nominal)
+    };
+
+    let base_in_rubles = rubles_per_unit(base)
+        .ok_or_else(|| format!("CBR: No rate for the base currency {base}"))?;
+    Ok(Currency::ALL
+        .into_iter()
+        .filter(|currency| *currency != base)
+        .filter_map(|currency| {
+            rubles_per_unit(currency).map(|rubles| (currency, base_in_rubles / rubles))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn test_rates_for_base_cross_converts_quotes() {
+        let daily: CbrDailyResponse = serde_json::from_str(
+            r#"{"Date":"2026-10-19T11:30:00+03:00","Valute":{
+                "USD":{"CharCode":"USD","Nominal":1,"Value":80.0},
+                "EUR":{"CharCode":"EUR","Nominal":1,"Value":100.0},
+                "JPY":{"CharCode":"JPY","Nominal":100,"Value":55.0}
+            }}"#,
+        )
+        .unwrap();
+
+        let from_rub = rates_for_base(&daily, Currency::Rub).unwrap();
+        assert_eq!(from_rub[&Currency::Usd], dec!(0.0125));
+        assert_eq!(from_rub[&Currency::Usdt], dec!(0.0125));
+        assert_eq!(from_rub[&Currency::Eur], dec!(0.01));
+
+        let from_usd = rates_for_base(&daily, Currency::Usd).unwrap();
+        assert_eq!(from_usd[&Currency::Rub], dec!(80));
+        assert_eq!(from_usd[&Currency::Eur], dec!(0.8));
+        assert!(!from_usd.contains_key(&Currency::Usd));
+    }
+}
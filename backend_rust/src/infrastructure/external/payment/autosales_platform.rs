@@ -1,20 +1,26 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
 use reqwest::Response;
 use serde::{Serialize, de::DeserializeOwned};
+use sha2::Sha256;
 use tokio::sync::RwLock;
 use totp_rs::Algorithm;
 
-use crate::infrastructure::external::payment::autosales_platform::dto::{
-    AutosalesPlatformAuthStep1Data, AutosalesPlatformAuthStep1Request,
-    AutosalesPlatformAuthStep1Response, AutosalesPlatformAuthStep2Data,
-    AutosalesPlatformAuthStep2Request, AutosalesPlatformAuthStep2Response, AutosalesPlatformError,
-    AutosalesPlatformInitializeOrderRequest, AutosalesPlatformObjectTokenPayload,
-    AutosalesPlatformOrderInitializedData, AutosalesPlatformOrderInitializedDataRequisite,
-    AutosalesPlatformOrderStatus, AutosalesPlatformOrderStatusData, AutosalesPlatformRequest,
-    AutosalesPlatformResponse, AutosalesPlatformSendReceiptRequest,
+use crate::{
+    infrastructure::external::payment::autosales_platform::dto::{
+        AutosalesPlatformAuthStep1Data, AutosalesPlatformAuthStep1Request,
+        AutosalesPlatformAuthStep1Response, AutosalesPlatformAuthStep2Data,
+        AutosalesPlatformAuthStep2Request, AutosalesPlatformAuthStep2Response,
+        AutosalesPlatformError, AutosalesPlatformInitializeOrderRequest,
+        AutosalesPlatformObjectTokenPayload, AutosalesPlatformOrderInitializedData,
+        AutosalesPlatformOrderInitializedDataRequisite, AutosalesPlatformOrderStatus,
+        AutosalesPlatformOrderStatusData, AutosalesPlatformRequest, AutosalesPlatformResponse,
+        AutosalesPlatformSendReceiptRequest, AutosalesPlatformWebhookPayload,
+    },
+    telemetry,
 };
 
 pub mod dto;
@@ -36,6 +42,12 @@ pub trait AutosalesPlatformPaymentsProviderTrait {
         &self,
         object_token: String,
     ) -> Result<AutosalesPlatformOrderStatus, AutosalesPlatformError>;
+    /// Checks the hex HMAC-SHA256 `signature` of the raw webhook `body` and parses it
+    fn verify_webhook(
+        &self,
+        body: &[u8],
+        signature: &str,
+    ) -> Result<AutosalesPlatformWebhookPayload, String>;
 }
 
 struct AutosalesToken {
@@ -49,6 +61,7 @@ pub struct AutosalesPlatformPaymentsProvider {
     login: String,
     password: String,
     two_fa: String,
+    webhook_secret: Option<String>,
     token: Arc<RwLock<Option<AutosalesToken>>>,
 }
 
@@ -59,6 +72,7 @@ impl AutosalesPlatformPaymentsProvider {
         login: String,
         password: String,
         two_fa: String,
+        webhook_secret: Option<String>,
     ) -> AutosalesPlatformPaymentsProvider {
         AutosalesPlatformPaymentsProvider {
             client,
@@ -67,6 +81,7 @@ impl AutosalesPlatformPaymentsProvider {
             login,
             password,
             two_fa,
+            webhook_secret,
         }
     }
 
@@ -102,14 +117,24 @@ impl AutosalesPlatformPaymentsProvider {
         T: DeserializeOwned + Send + 'static,
         B: Serialize + ?Sized,
     {
-        let response = self
+        let started_at = Instant::now();
+        let result = match self
             .client
             .post(format!("{}{endpoint}", self.url))
             .form(payload)
             .send()
             .await
-            .map_err(|e| format!("[Autosales platform payments provider] {e}"))?;
-        Self::parse_response(response).await
+        {
+            Ok(response) => Self::parse_response(response).await,
+            Err(e) => Err(format!("[Autosales platform payments provider] {e}")),
+        };
+        telemetry::record_gateway_request(
+            "autosales_platform",
+            endpoint,
+            started_at,
+            result.is_ok(),
+        );
+        result
     }
 
     async fn parse_response<T>(response: Response) -> Result<T, String>
@@ -276,4 +301,71 @@ impl AutosalesPlatformPaymentsProviderTrait for AutosalesPlatformPaymentsProvide
         .await
         .map(|r| r.status)
     }
+
+    fn verify_webhook(
+        &self,
+        body: &[u8],
+        signature: &str,
+    ) -> Result<AutosalesPlatformWebhookPayload, String> {
+        let secret = self
+            .webhook_secret
+            .as_deref()
+            .ok_or_else(|| "Webhooks are not enabled".to_string())?;
+        let signature = hex::decode(signature.trim()).map_err(|_| "Invalid signature")?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .map_err(|e| format!("[Autosales platform payments provider] {e}"))?;
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| "Invalid signature".to_string())?;
+
+        serde_json::from_slice(body).map_err(|e| format!("Invalid webhook payload: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(webhook_secret: Option<&str>) -> AutosalesPlatformPaymentsProvider {
+        AutosalesPlatformPaymentsProvider::new(
+            Arc::new(reqwest::Client::new()),
+            "http://localhost/".to_string(),
+            "login".to_string(),
+            "password".to_string(),
+            "2fa".to_string(),
+            webhook_secret.map(str::to_string),
+        )
+    }
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn test_verify_webhook_checks_signature() {
+        let body = br#"{"event_id":"ev-1","object_token":"gw-1","status":"merch_success","appeal_fake_status":null}"#;
+        let provider = provider(Some("secret"));
+
+        let payload = provider
+            .verify_webhook(body, &sign("secret", body))
+            .unwrap();
+        assert_eq!(payload.event_id, "ev-1");
+        assert_eq!(payload.object_token, "gw-1");
+
+        assert!(provider.verify_webhook(body, &sign("other", body)).is_err());
+        assert!(provider.verify_webhook(body, "not hex").is_err());
+    }
+
+    #[test]
+    fn test_verify_webhook_rejects_when_disabled() {
+        let body = br#"{}"#;
+
+        assert!(
+            provider(None)
+                .verify_webhook(body, &sign("secret", body))
+                .is_err()
+        );
+    }
 }
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Deserializer, Serialize};
+use shared_dtos::invoice::InvoiceStatus;
+use utoipa::ToSchema;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -148,7 +150,7 @@ pub struct AutosalesPlatformOrderInitializedData {
 pub type AutosalesPlatformOrderInitializedResponse =
     AutosalesPlatformResponse<AutosalesPlatformOrderInitializedData>;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct AutosalesPlatformOrderStatusRequisiteData {
     pub country: String,
     pub bank_img: String,
@@ -159,7 +161,7 @@ pub struct AutosalesPlatformOrderStatusRequisiteData {
     pub r#type: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AutosalesPlatformOrderStatusType {
     MerchProcess,
@@ -179,7 +181,7 @@ pub enum AutosalesPlatformOrderStatusType {
     MerchCheckDown,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct AutosalesPlatformOrderStatus {
     pub token: String,
     pub status: AutosalesPlatformOrderStatusType,
@@ -233,3 +235,44 @@ pub struct AutosalesPlatformRequest<T: Serialize> {
     #[serde(flatten)]
     pub payload: T,
 }
+
+/// Order status change pushed by the platform, signed with the webhook secret
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct AutosalesPlatformWebhookPayload {
+    /// Unique per delivery attempt group; retries of the same event reuse it
+    pub event_id: String,
+    pub object_token: String,
+    pub status: AutosalesPlatformOrderStatusType,
+    pub appeal_fake_status: Option<String>,
+}
+
+/// The platform marks appeals with a fake receipt by a non-zero `appeal_fake_status`
+pub fn is_fake_appeal(appeal_fake_status: Option<&str>) -> bool {
+    appeal_fake_status.is_some_and(|status| !status.is_empty() && status != "0")
+}
+
+impl From<AutosalesPlatformOrderStatusType> for InvoiceStatus {
+    fn from(status: AutosalesPlatformOrderStatusType) -> Self {
+        match status {
+            AutosalesPlatformOrderStatusType::MerchInitialized
+            | AutosalesPlatformOrderStatusType::MerchProcess => InvoiceStatus::Pending,
+            AutosalesPlatformOrderStatusType::TraderSuccess
+            | AutosalesPlatformOrderStatusType::MerchSuccess
+            | AutosalesPlatformOrderStatusType::SystemTimerEndMerchProcessSuccess
+            | AutosalesPlatformOrderStatusType::SystemTimerEndMerchCheckDownSuccess
+            | AutosalesPlatformOrderStatusType::AdminAppealSuccess => InvoiceStatus::Completed,
+            AutosalesPlatformOrderStatusType::MerchCheckDown => InvoiceStatus::ReceiptSubmitted,
+            AutosalesPlatformOrderStatusType::TraderCheckQuery => InvoiceStatus::AwaitingReceipt,
+            AutosalesPlatformOrderStatusType::TraderAppeal => InvoiceStatus::Disputed,
+            AutosalesPlatformOrderStatusType::SystemTimerEndMerchInitializedCancel => {
+                InvoiceStatus::Cancelled
+            }
+            AutosalesPlatformOrderStatusType::OrderCancel => InvoiceStatus::Cancelled,
+            AutosalesPlatformOrderStatusType::MerchCancel => InvoiceStatus::Cancelled,
+            AutosalesPlatformOrderStatusType::SystemTimerEndTraderCheckQueryCancel => {
+                InvoiceStatus::Cancelled
+            }
+            AutosalesPlatformOrderStatusType::AdminAppealCancel => InvoiceStatus::Failed,
+        }
+    }
+}
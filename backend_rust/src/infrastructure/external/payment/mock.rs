@@ -1,13 +1,16 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use async_trait::async_trait;
 use reqwest::Response;
 use serde::{Serialize, de::DeserializeOwned};
 use uuid::Uuid;
 
-use crate::infrastructure::external::payment::mock::dto::{
-    MockProviderCreateInvoiceRequest, MockProviderCreateInvoiceResponse, MockProviderInvoiceStatus,
-    MockProviderInvoiceWebhookPayload,
+use crate::{
+    infrastructure::external::payment::mock::dto::{
+        MockProviderCreateInvoiceRequest, MockProviderCreateInvoiceResponse,
+        MockProviderInvoiceStatus, MockProviderInvoiceWebhookPayload,
+    },
+    telemetry,
 };
 
 pub mod dto;
@@ -39,13 +42,18 @@ impl MockPaymentsProvider {
     where
         T: DeserializeOwned + Send + 'static,
     {
-        let response = self
+        let started_at = Instant::now();
+        let result = match self
             .client
             .get(format!("{}/{endpoint}", self.url))
             .send()
             .await
-            .map_err(|e| format!("Mock payments provider: {e}"))?;
-        Self::parse_response(response).await
+        {
+            Ok(response) => Self::parse_response(response).await,
+            Err(e) => Err(format!("Mock payments provider: {e}")),
+        };
+        record_request(endpoint, started_at, result.is_ok());
+        result
     }
 
     async fn post<T, B>(&self, endpoint: &str, payload: &B) -> Result<T, String>
@@ -53,14 +61,19 @@ impl MockPaymentsProvider {
         T: DeserializeOwned + Send + 'static,
         B: Serialize + ?Sized,
     {
-        let response = self
+        let started_at = Instant::now();
+        let result = match self
             .client
             .post(format!("{}/{endpoint}", self.url))
             .json(payload)
             .send()
             .await
-            .map_err(|e| format!("Mock payments provider: {e}"))?;
-        Self::parse_response(response).await
+        {
+            Ok(response) => Self::parse_response(response).await,
+            Err(e) => Err(format!("Mock payments provider: {e}")),
+        };
+        record_request(endpoint, started_at, result.is_ok());
+        result
     }
 
     async fn parse_response<T>(response: Response) -> Result<T, String>
@@ -113,3 +126,9 @@ impl MockPaymentsProviderTrait for MockPaymentsProvider {
         }
     }
 }
+
+/// Labels by the first path segment, invoice ids in paths would explode cardinality
+fn record_request(endpoint: &str, started_at: Instant, ok: bool) {
+    let endpoint = endpoint.split('/').next().unwrap_or(endpoint);
+    telemetry::record_gateway_request("mock", endpoint, started_at, ok);
+}
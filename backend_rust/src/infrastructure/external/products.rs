@@ -1 +1,97 @@
+#[cfg(feature = "contms-provider")]
 pub mod contms;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use shared_dtos::{
+    product::{ProductDetails, ProductType},
+    user_subscription::UserSubscriptionDetails,
+};
+
+/// Product offered by an external supplier, as returned by its catalog
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalCatalogItem {
+    pub external_id: String,
+    pub name: String,
+    /// Supplier-side category name; the sync maps it to a local category tree
+    pub category: String,
+    /// Supplier attributes (country, type, ...) usable in category templates
+    pub attributes: BTreeMap<String, String>,
+    pub r#type: ProductType,
+    /// Supplier price; `None` when the supplier doesn't publish one
+    pub base_price: Option<Decimal>,
+    pub subscription_period_days: Option<i16>,
+    pub details: Option<ProductDetails>,
+}
+
+/// Access granted by a supplier for a purchased product
+#[derive(Debug, Clone)]
+pub struct ExternalSubscription {
+    pub expires_at: DateTime<Utc>,
+    pub details: UserSubscriptionDetails,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalSubscriptionStatus {
+    Active { expires_at: DateTime<Utc> },
+    Expired,
+}
+
+/// A supplier of products that are fulfilled outside of our stock. Products of a
+/// provider are stored with `provider_name = name()` and `external_id` from its catalog.
+#[async_trait]
+pub trait ExternalProductProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Local category path for catalog items, `/`-separated with `{category}` and
+    /// `{attribute}` placeholders. Overridden by `external_products_category_map`.
+    fn category_template(&self) -> &'static str {
+        "{category}"
+    }
+    async fn list_catalog(&self) -> Result<Vec<ExternalCatalogItem>, String>;
+    async fn fulfill_purchase(
+        &self,
+        external_id: &str,
+        details: Option<&ProductDetails>,
+        period: Duration,
+    ) -> Result<ExternalSubscription, String>;
+    async fn renew(
+        &self,
+        subscription: &UserSubscriptionDetails,
+        period: Duration,
+    ) -> Result<DateTime<Utc>, String>;
+    async fn revoke(&self, subscription: &UserSubscriptionDetails) -> Result<(), String>;
+    async fn check_status(
+        &self,
+        subscription: &UserSubscriptionDetails,
+    ) -> Result<ExternalSubscriptionStatus, String>;
+}
+
+/// Enabled providers keyed by `provider_name`
+#[derive(Default, Clone)]
+pub struct ExternalProductProviderRegistry {
+    providers: HashMap<&'static str, Arc<dyn ExternalProductProvider>>,
+}
+
+impl ExternalProductProviderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, provider: Arc<dyn ExternalProductProvider>) {
+        self.providers.insert(provider.name(), provider);
+    }
+
+    pub fn get(&self, provider_name: &str) -> Option<Arc<dyn ExternalProductProvider>> {
+        self.providers.get(provider_name).cloned()
+    }
+
+    pub fn providers(&self) -> impl Iterator<Item = &Arc<dyn ExternalProductProvider>> {
+        self.providers.values()
+    }
+}
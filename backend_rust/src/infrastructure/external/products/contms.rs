@@ -1,33 +1,30 @@
 pub mod dto;
 
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
 
 use async_trait::async_trait;
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
 use reqwest::Response;
 use serde::de::DeserializeOwned;
+use shared_dtos::{
+    product::{ProductDetails, ProductType},
+    user_subscription::UserSubscriptionDetails,
+};
 
-use crate::infrastructure::external::products::contms::dto::{
-    ContmsAvailableResponse, ContmsProxyResponse, ContmsRenewResponse, ContmsRequestAction,
-    ContmsStatusProxyResponse, ContmsStatusResponse, ContmsUpResponse, ContmsUserResponse,
-    UpProxyRequest,
+use crate::{
+    infrastructure::external::products::{
+        ExternalCatalogItem, ExternalProductProvider, ExternalSubscription,
+        ExternalSubscriptionStatus,
+        contms::dto::{
+            ContmsAvailableResponse, ContmsDownResponse, ContmsProxyResponse, ContmsRenewResponse,
+            ContmsRequestAction, ContmsStatusResponse, ContmsUpResponse, UpProxyRequest,
+        },
+    },
+    telemetry,
 };
 
-#[async_trait]
-pub trait ContmsProductsProviderTrait {
-    async fn get_products(&self) -> Result<Vec<ContmsProxyResponse>, String>;
-    async fn subscribe_to_proxy(
-        &self,
-        product_name: &str,
-        duration: Duration,
-    ) -> Result<ContmsUserResponse, String>;
-    async fn renew_subscription(
-        &self,
-        subscription_id: &str,
-        duration: Duration,
-    ) -> Result<ContmsStatusProxyResponse, String>;
-    async fn unsubscribe_from_proxy(&self, subscription_id: &str) -> Result<(), String>;
-}
+pub const CONTMS_PROVIDER_NAME: &str = "contms";
+const CONTMS_CATEGORY: &str = "Прокси";
 
 pub struct ContmsProductsProvider {
     pub client: Arc<reqwest::Client>,
@@ -43,14 +40,18 @@ impl ContmsProductsProvider {
     where
         T: DeserializeOwned + Send + 'static,
     {
-        let response = self
-            .client
-            .post(&self.url)
-            .json(payload)
-            .send()
-            .await
-            .map_err(|e| format!("Contms: {e}"))?;
-        Self::parse_response(response).await
+        let started_at = Instant::now();
+        let result = match self.client.post(&self.url).json(payload).send().await {
+            Ok(response) => Self::parse_response(response).await,
+            Err(e) => Err(format!("Contms: {e}")),
+        };
+        telemetry::record_gateway_request(
+            CONTMS_PROVIDER_NAME,
+            payload.name(),
+            started_at,
+            result.is_ok(),
+        );
+        result
     }
 
     async fn parse_response<T>(response: Response) -> Result<T, String>
@@ -77,50 +78,122 @@ impl ContmsProductsProvider {
 }
 
 #[async_trait]
-impl ContmsProductsProviderTrait for ContmsProductsProvider {
-    async fn get_products(&self) -> Result<Vec<ContmsProxyResponse>, String> {
-        self.request::<ContmsAvailableResponse>(&ContmsRequestAction::Available)
-            .await
-            .map(|resp| resp.proxy)
+impl ExternalProductProvider for ContmsProductsProvider {
+    fn name(&self) -> &'static str {
+        CONTMS_PROVIDER_NAME
     }
 
-    async fn subscribe_to_proxy(
+    fn category_template(&self) -> &'static str {
+        "{category}/{ip_version}/{type}"
+    }
+
+    async fn list_catalog(&self) -> Result<Vec<ExternalCatalogItem>, String> {
+        let proxies = self
+            .request::<ContmsAvailableResponse>(&ContmsRequestAction::Available)
+            .await?
+            .proxy;
+        Ok(proxies.into_iter().map(catalog_item).collect())
+    }
+
+    async fn fulfill_purchase(
         &self,
-        product_name: &str,
-        duration: Duration,
-    ) -> Result<ContmsUserResponse, String> {
-        self.request::<ContmsUpResponse>(&ContmsRequestAction::Up {
-            proxy: UpProxyRequest {
-                expires: duration,
-                name: product_name.to_string(),
+        external_id: &str,
+        details: Option<&ProductDetails>,
+        period: Duration,
+    ) -> Result<ExternalSubscription, String> {
+        let Some(ProductDetails::ContMs { host, port }) = details else {
+            return Err("Contms: Invalid product details".to_string());
+        };
+        let user = self
+            .request::<ContmsUpResponse>(&ContmsRequestAction::Up {
+                proxy: UpProxyRequest {
+                    expires: period,
+                    name: external_id.to_string(),
+                },
+            })
+            .await?
+            .user;
+
+        Ok(ExternalSubscription {
+            expires_at: parse_expires(user.expires)?,
+            details: UserSubscriptionDetails::ContMs {
+                host: host.clone(),
+                port: *port,
+                username: user.name,
+                password: user.pass,
             },
         })
-        .await
-        .map(|resp| resp.user)
     }
 
-    async fn renew_subscription(
+    async fn renew(
         &self,
-        subscription_id: &str,
-        duration: Duration,
-    ) -> Result<ContmsStatusProxyResponse, String> {
+        subscription: &UserSubscriptionDetails,
+        period: Duration,
+    ) -> Result<DateTime<Utc>, String> {
+        let UserSubscriptionDetails::ContMs { username, .. } = subscription;
         self.request::<ContmsRenewResponse>(&ContmsRequestAction::Renew {
-            user: subscription_id.to_string(),
-            expires: duration,
+            user: username.clone(),
+            expires: period,
         })
         .await?;
-        self.request::<ContmsStatusResponse>(&ContmsRequestAction::Status {
-            user: subscription_id.to_string(),
-        })
-        .await
-        .map(|r| r.proxy)
+        let status = self
+            .request::<ContmsStatusResponse>(&ContmsRequestAction::Status {
+                user: username.clone(),
+            })
+            .await?;
+        parse_expires(status.proxy.expires)
     }
 
-    async fn unsubscribe_from_proxy(&self, subscription_id: &str) -> Result<(), String> {
-        self.request::<ContmsUpResponse>(&ContmsRequestAction::Down {
-            user: subscription_id.to_string(),
+    async fn revoke(&self, subscription: &UserSubscriptionDetails) -> Result<(), String> {
+        let UserSubscriptionDetails::ContMs { username, .. } = subscription;
+        self.request::<ContmsDownResponse>(&ContmsRequestAction::Down {
+            user: username.clone(),
         })
         .await?;
         Ok(())
     }
+
+    async fn check_status(
+        &self,
+        subscription: &UserSubscriptionDetails,
+    ) -> Result<ExternalSubscriptionStatus, String> {
+        let UserSubscriptionDetails::ContMs { username, .. } = subscription;
+        let status = self
+            .request::<ContmsStatusResponse>(&ContmsRequestAction::Status {
+                user: username.clone(),
+            })
+            .await?;
+        let expires_at = parse_expires(status.proxy.expires)?;
+        if expires_at > Utc::now() {
+            Ok(ExternalSubscriptionStatus::Active { expires_at })
+        } else {
+            Ok(ExternalSubscriptionStatus::Expired)
+        }
+    }
+}
+
+fn catalog_item(proxy: ContmsProxyResponse) -> ExternalCatalogItem {
+    ExternalCatalogItem {
+        external_id: proxy.name.clone(),
+        name: proxy.name,
+        category: CONTMS_CATEGORY.to_string(),
+        attributes: BTreeMap::from([
+            ("type".to_string(), proxy.r#type.to_uppercase()),
+            ("ip_version".to_string(), format!("IPv{}", proxy.ipv)),
+        ]),
+        r#type: ProductType::Subscription,
+        // Contms doesn't publish prices, they are set by the admin
+        base_price: None,
+        subscription_period_days: Some(30), // TODO 1 month?
+        details: Some(ProductDetails::ContMs {
+            host: proxy.host,
+            port: proxy.port,
+        }),
+    }
+}
+
+// Contms returns expiration as unix milliseconds
+fn parse_expires(expires: i64) -> Result<DateTime<Utc>, String> {
+    DateTime::from_timestamp_millis(expires)
+        .ok_or_else(|| format!("Contms: Failed to parse expires {expires}"))
 }
@@ -81,3 +81,15 @@ pub enum ContmsRequestAction {
     #[serde(rename = "status")]
     Status { user: String },
 }
+
+impl ContmsRequestAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Available => "available",
+            Self::Up { .. } => "up",
+            Self::Down { .. } => "down",
+            Self::Renew { .. } => "renew",
+            Self::Status { .. } => "status",
+        }
+    }
+}
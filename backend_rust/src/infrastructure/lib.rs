@@ -1 +1,2 @@
+pub mod advisory_lock;
 pub mod query;
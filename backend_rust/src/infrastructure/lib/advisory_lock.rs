@@ -0,0 +1,98 @@
+use sqlx::{Connection, PgConnection, PgPool};
+
+/// Session-level Postgres advisory lock held on a dedicated connection (not taken
+/// from the pool), so it lives exactly as long as the connection does. If the
+/// process dies or the connection drops, Postgres releases the lock.
+pub struct AdvisoryLock {
+    name: String,
+    conn: PgConnection,
+}
+
+impl AdvisoryLock {
+    pub async fn try_acquire(pool: &PgPool, name: &str) -> sqlx::Result<Option<Self>> {
+        let mut conn = PgConnection::connect_with(&pool.connect_options()).await?;
+        let acquired = sqlx::query_scalar!(
+            r#"SELECT pg_try_advisory_lock(hashtextextended($1, 0)) AS "acquired!""#,
+            name
+        )
+        .fetch_one(&mut conn)
+        .await?;
+
+        if acquired {
+            Ok(Some(Self {
+                name: name.to_string(),
+                conn,
+            }))
+        } else {
+            conn.close().await?;
+            Ok(None)
+        }
+    }
+
+    /// Pings the lock connection; an error means the lock can no longer be trusted
+    pub async fn renew(&mut self) -> sqlx::Result<()> {
+        self.conn.ping().await
+    }
+
+    pub async fn release(mut self) -> sqlx::Result<()> {
+        sqlx::query_scalar!(
+            r#"SELECT pg_advisory_unlock(hashtextextended($1, 0)) AS "released!""#,
+            self.name
+        )
+        .fetch_one(&mut self.conn)
+        .await?;
+        self.conn.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_lock_is_exclusive_until_released(pool: PgPool) {
+        let lock = AdvisoryLock::try_acquire(&pool, "test_lock")
+            .await
+            .unwrap()
+            .expect("first instance should get the lock");
+
+        assert!(
+            AdvisoryLock::try_acquire(&pool, "test_lock")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            AdvisoryLock::try_acquire(&pool, "other_lock")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        lock.release().await.unwrap();
+
+        assert!(
+            AdvisoryLock::try_acquire(&pool, "test_lock")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_lock_is_freed_when_connection_drops(pool: PgPool) {
+        let mut lock = AdvisoryLock::try_acquire(&pool, "test_lock")
+            .await
+            .unwrap()
+            .unwrap();
+        lock.renew().await.unwrap();
+        lock.conn.close().await.unwrap();
+
+        assert!(
+            AdvisoryLock::try_acquire(&pool, "test_lock")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+}
@@ -1,22 +1,32 @@
 use shared_dtos::list_query::{FilterValue, Operator, OrderDir, Pagination, ScalarValue};
 use sqlx::{Postgres, QueryBuilder};
 
-use crate::models::common::{AllowedField, ListQuery};
+use uuid::Uuid;
 
-pub fn apply_list_query<'a, F: AllowedField, O: AllowedField>(
+use crate::models::common::{
+    AllowedField, Cursor, CursorField, FilterExpr, ListQuery, PaginatedResult, SearchField,
+    SearchMode,
+};
+
+pub fn apply_list_query<'a, F: AllowedField + SearchField, O: AllowedField + CursorField>(
     qb: &mut QueryBuilder<'a, Postgres>,
     list: &'a ListQuery<F, O>,
 ) {
     apply_filters(qb, list);
-    apply_order_by(qb, list);
-    apply_pagination(qb, &list.pagination);
+    match (&list.cursor, O::cursor_field()) {
+        (Some(cursor), Some((_, column))) => apply_keyset_pagination(qb, list, cursor, column),
+        _ => {
+            apply_order_by(qb, list);
+            apply_pagination(qb, &list.pagination);
+        }
+    }
 }
 
-pub fn apply_filters<'a, F: AllowedField, O: AllowedField>(
+pub fn apply_filters<'a, F: AllowedField + SearchField, O: AllowedField>(
     qb: &mut QueryBuilder<'a, Postgres>,
     list: &'a ListQuery<F, O>,
 ) {
-    if list.filters.is_empty() {
+    if !has_conditions(list) {
         return;
     }
 
@@ -26,6 +36,76 @@ pub fn apply_filters<'a, F: AllowedField, O: AllowedField>(
         let field_col = filter.field.as_ref();
         push_filter_clause(qb, field_col, &filter.op, &filter.value);
     }
+
+    for group in &list.groups {
+        qb.push(" AND ");
+        push_filter_expr(qb, group);
+    }
+
+    if let Some(search) = &list.search {
+        push_search_clause(qb, F::search_fields(), search);
+    }
+}
+
+fn has_conditions<F: AllowedField, O: AllowedField>(list: &ListQuery<F, O>) -> bool {
+    !list.filters.is_empty() || !list.groups.is_empty() || list.search.is_some()
+}
+
+/// `%term%` for `ILIKE ... ESCAPE '\'`, with wildcards in the user input matched literally
+fn contains_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+fn push_search_clause(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    fields: &[(&str, SearchMode)],
+    term: &str,
+) {
+    let pattern = contains_pattern(term);
+
+    qb.push(" AND (FALSE");
+    for (column, mode) in fields {
+        match mode {
+            SearchMode::Id => {
+                if let Ok(id) = term.parse::<i64>() {
+                    qb.push(" OR ").push(column).push(" = ").push_bind(id);
+                }
+            }
+            SearchMode::Uuid => {
+                if let Ok(id) = term.parse::<Uuid>() {
+                    qb.push(" OR ").push(column).push(" = ").push_bind(id);
+                }
+            }
+            SearchMode::Contains => {
+                qb.push(" OR ")
+                    .push(column)
+                    .push(" ILIKE ")
+                    .push_bind(pattern.clone())
+                    .push(" ESCAPE '\\'");
+            }
+            SearchMode::Fuzzy => {
+                qb.push(" OR ")
+                    .push(column)
+                    .push(" ILIKE ")
+                    .push_bind(pattern.clone())
+                    .push(" ESCAPE '\\'");
+                qb.push(" OR ")
+                    .push(column)
+                    .push(" % ")
+                    .push_bind(term.to_string());
+                qb.push(" OR to_tsvector('simple', ")
+                    .push(column)
+                    .push(") @@ plainto_tsquery('simple', ")
+                    .push_bind(term.to_string())
+                    .push(")");
+            }
+        }
+    }
+    qb.push(")");
 }
 
 pub fn apply_order_by<'a, F: AllowedField, O: AllowedField>(
@@ -48,10 +128,82 @@ fn push_filter_clause<'a>(
     field_col: &str,
     op: &Operator,
     value: &'a FilterValue,
+) {
+    if is_supported_filter(op, value) {
+        qb.push(" AND ");
+        push_filter_condition(qb, field_col, op, value);
+    }
+}
+
+// Unsupported filters constrain nothing, so inside a group they become TRUE
+fn push_filter_expr<'a, F: AllowedField>(
+    qb: &mut QueryBuilder<'a, Postgres>,
+    expr: &'a FilterExpr<F>,
+) {
+    match expr {
+        FilterExpr::Filter(filter) => {
+            if is_supported_filter(&filter.op, &filter.value) {
+                push_filter_condition(qb, filter.field.as_ref(), &filter.op, &filter.value);
+            } else {
+                qb.push("TRUE");
+            }
+        }
+        FilterExpr::And(exprs) => push_filter_exprs(qb, exprs, " AND ", "TRUE"),
+        FilterExpr::Or(exprs) => push_filter_exprs(qb, exprs, " OR ", "FALSE"),
+        FilterExpr::Not(expr) => {
+            qb.push("NOT (");
+            push_filter_expr(qb, expr);
+            qb.push(")");
+        }
+    }
+}
+
+fn push_filter_exprs<'a, F: AllowedField>(
+    qb: &mut QueryBuilder<'a, Postgres>,
+    exprs: &'a [FilterExpr<F>],
+    separator: &str,
+    empty: &str,
+) {
+    if exprs.is_empty() {
+        qb.push(empty);
+        return;
+    }
+    qb.push("(");
+    for (i, expr) in exprs.iter().enumerate() {
+        if i > 0 {
+            qb.push(separator);
+        }
+        push_filter_expr(qb, expr);
+    }
+    qb.push(")");
+}
+
+fn is_supported_filter(op: &Operator, value: &FilterValue) -> bool {
+    match (op, value) {
+        (Operator::In, FilterValue::Array(values)) => !values.is_empty(),
+        (Operator::Between, FilterValue::Array(values)) => values.len() == 2,
+        (Operator::IsNull | Operator::NotNull, _) => true,
+        (Operator::Eq | Operator::Ne, FilterValue::Scalar(_)) => true,
+        (
+            Operator::Gt | Operator::Lt | Operator::Ge | Operator::Le,
+            FilterValue::Scalar(
+                ScalarValue::Int(_) | ScalarValue::Float(_) | ScalarValue::DateTime(_),
+            ),
+        ) => true,
+        (Operator::Like | Operator::Contains, FilterValue::Scalar(ScalarValue::Text(_))) => true,
+        (_, _) => false,
+    }
+}
+
+fn push_filter_condition<'a>(
+    qb: &mut QueryBuilder<'a, Postgres>,
+    field_col: &str,
+    op: &Operator,
+    value: &'a FilterValue,
 ) {
     match (op, value) {
         (Operator::In, FilterValue::Array(values)) => {
-            qb.push(" AND ").push(field_col).push(" IN (");
+            qb.push(field_col).push(" IN (");
             let mut sep = qb.separated(", ");
             for v in values {
                 match v {
@@ -66,47 +218,47 @@ fn push_filter_clause<'a>(
             qb.push(")");
         }
 
-        (Operator::Eq | Operator::Ne, FilterValue::Scalar(scalar)) => {
-            let op_str = if *op == Operator::Eq { "=" } else { "!=" };
-            qb.push(" AND ")
-                .push(field_col)
-                .push(" ")
-                .push(op_str)
-                .push(" ");
-            push_bind_scalar(qb, scalar);
+        (Operator::Between, FilterValue::Array(values)) => {
+            if let [from, to] = values.as_slice() {
+                qb.push(field_col).push(" BETWEEN ");
+                push_bind_scalar(qb, from);
+                qb.push(" AND ");
+                push_bind_scalar(qb, to);
+            }
+        }
+
+        (Operator::IsNull, _) => {
+            qb.push(field_col).push(" IS NULL");
+        }
+        (Operator::NotNull, _) => {
+            qb.push(field_col).push(" IS NOT NULL");
         }
 
         (
-            op @ (Operator::Gt | Operator::Lt | Operator::Ge | Operator::Le),
+            Operator::Eq | Operator::Ne | Operator::Gt | Operator::Lt | Operator::Ge | Operator::Le,
             FilterValue::Scalar(scalar),
         ) => {
             let op_str = match op {
+                Operator::Eq => "=",
+                Operator::Ne => "!=",
                 Operator::Gt => ">",
                 Operator::Lt => "<",
                 Operator::Ge => ">=",
                 Operator::Le => "<=",
                 _ => return,
             };
-            match scalar {
-                ScalarValue::Int(_) | ScalarValue::Float(_) | ScalarValue::DateTime(_) => {
-                    qb.push(" AND ")
-                        .push(field_col)
-                        .push(" ")
-                        .push(op_str)
-                        .push(" ");
-                    push_bind_scalar(qb, scalar);
-                }
-                _ => {}
-            }
+            qb.push(field_col).push(" ").push(op_str).push(" ");
+            push_bind_scalar(qb, scalar);
         }
 
         (Operator::Like, FilterValue::Scalar(ScalarValue::Text(text))) => {
-            qb.push(" AND ").push(field_col).push(" LIKE ");
+            qb.push(field_col).push(" LIKE ");
             qb.push_bind(text.clone());
         }
         (Operator::Contains, FilterValue::Scalar(ScalarValue::Text(text))) => {
-            qb.push(" AND ").push(field_col).push(" ILIKE ");
-            qb.push_bind(format!("%{}%", text));
+            qb.push(field_col).push(" ILIKE ");
+            qb.push_bind(contains_pattern(text));
+            qb.push(" ESCAPE '\\'");
         }
         (_, _) => {}
     };
@@ -123,6 +275,52 @@ pub fn apply_pagination(qb: &mut QueryBuilder<'_, Postgres>, pagination: &Pagina
     qb.push_bind(offset);
 }
 
+// Fetches one extra row so `into_paginated_result` can tell whether there is a next page
+pub fn apply_keyset_pagination<F: AllowedField, O: AllowedField>(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    list: &ListQuery<F, O>,
+    cursor: &Cursor,
+    column: &str,
+) {
+    let (cmp, dir) = match list.order_dir {
+        OrderDir::Asc => (" > ", " ASC"),
+        OrderDir::Desc => (" < ", " DESC"),
+    };
+
+    if let Some(after) = cursor.after {
+        qb.push(if !has_conditions(list) {
+            " WHERE "
+        } else {
+            " AND "
+        });
+        qb.push(column).push(cmp).push_bind(after);
+    }
+
+    qb.push(" ORDER BY ").push(column).push(dir);
+    qb.push(" LIMIT ");
+    qb.push_bind(list.pagination.page_size as i64 + 1);
+}
+
+pub fn into_paginated_result<T, F: AllowedField, O: AllowedField + CursorField>(
+    list: &ListQuery<F, O>,
+    mut items: Vec<T>,
+    total: i64,
+    key: impl Fn(&T) -> i64,
+) -> PaginatedResult<T> {
+    let mut next_cursor = None;
+    let page_size = list.pagination.page_size as usize;
+    if list.cursor.is_some() && O::cursor_field().is_some() && items.len() > page_size {
+        items.truncate(page_size);
+        next_cursor = items.last().map(|item| Cursor::encode(key(item)));
+    }
+
+    PaginatedResult {
+        items,
+        total,
+        next_cursor,
+    }
+}
+
 pub fn push_bind_scalar<'a>(qb: &mut QueryBuilder<'a, Postgres>, scalar: &'a ScalarValue) {
     match scalar {
         ScalarValue::Int(v) => qb.push_bind(v),
@@ -158,12 +356,19 @@ mod tests {
         order_fields: {
             TestOrderFields,
             [
+                Id => "id",
                 Name => "name",
                 Age => "age",
                 Active => "active",
                 CreatedAt => "created_at",
             ]
-        }
+        },
+        search_fields: [
+            "name" => Fuzzy,
+            "code" => Contains,
+            "id" => Id,
+        ],
+        cursor_field: Id => "test.id"
     }
 
     #[test]
@@ -291,6 +496,67 @@ mod tests {
         assert_eq!(sql, "SELECT * FROM test WHERE 1=1");
     }
 
+    #[test]
+    fn test_filter_null_and_between_clauses() {
+        let mut qb = QueryBuilder::new("SELECT * FROM test");
+        let mut query = TestListQuery::default();
+        query.filters.push(Filter {
+            field: TestFilterFields::Name,
+            op: Operator::IsNull,
+            value: FilterValue::default(),
+        });
+        query.filters.push(Filter {
+            field: TestFilterFields::Age,
+            op: Operator::Between,
+            value: FilterValue::Array(vec![ScalarValue::Int(18), ScalarValue::Int(30)]),
+        });
+
+        apply_filters(&mut qb, &query);
+        let sql = qb.into_sql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM test WHERE 1=1 AND name IS NULL AND age BETWEEN $1 AND $2"
+        );
+    }
+
+    #[test]
+    fn test_apply_filters_nested_groups() {
+        let mut qb = QueryBuilder::new("SELECT * FROM test");
+        let mut query = TestListQuery::default();
+        query.filters.push(Filter {
+            field: TestFilterFields::Active,
+            op: Operator::Eq,
+            value: FilterValue::Scalar(ScalarValue::Bool(true)),
+        });
+        query.groups.push(FilterExpr::Or(vec![
+            FilterExpr::Filter(Filter {
+                field: TestFilterFields::Age,
+                op: Operator::Lt,
+                value: FilterValue::Scalar(ScalarValue::Int(18)),
+            }),
+            FilterExpr::Not(Box::new(FilterExpr::And(vec![
+                FilterExpr::Filter(Filter {
+                    field: TestFilterFields::Name,
+                    op: Operator::NotNull,
+                    value: FilterValue::default(),
+                }),
+                // Booleans don't support Greater Than
+                FilterExpr::Filter(Filter {
+                    field: TestFilterFields::Active,
+                    op: Operator::Gt,
+                    value: FilterValue::Scalar(ScalarValue::Bool(true)),
+                }),
+            ]))),
+        ]));
+
+        apply_filters(&mut qb, &query);
+        let sql = qb.into_sql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM test WHERE 1=1 AND active = $1 AND (age < $2 OR NOT ((name IS NOT NULL AND TRUE)))"
+        );
+    }
+
     #[test]
     fn test_apply_list_query_full() {
         let mut qb = QueryBuilder::new("SELECT * FROM test");
@@ -322,10 +588,107 @@ mod tests {
         let sql = qb.into_sql();
         assert_eq!(
             sql,
-            "SELECT * FROM test WHERE 1=1 AND active = $1 AND name ILIKE $2 ORDER BY created_at ASC LIMIT $3 OFFSET $4"
+            "SELECT * FROM test WHERE 1=1 AND active = $1 AND name ILIKE $2 ESCAPE '\\' ORDER BY created_at ASC LIMIT $3 OFFSET $4"
         );
     }
 
+    #[test]
+    fn test_apply_list_query_keyset_first_page() {
+        let mut qb = QueryBuilder::new("SELECT * FROM test");
+        let query = TestListQuery {
+            cursor: Some(Cursor::default()),
+            ..Default::default()
+        };
+
+        apply_list_query(&mut qb, &query);
+        let sql = qb.into_sql();
+        assert_eq!(sql, "SELECT * FROM test ORDER BY test.id DESC LIMIT $1");
+    }
+
+    #[test]
+    fn test_apply_list_query_keyset_with_filters() {
+        let mut qb = QueryBuilder::new("SELECT * FROM test");
+        let mut query = TestListQuery {
+            cursor: Some(Cursor { after: Some(100) }),
+            order_dir: OrderDir::Asc,
+            ..Default::default()
+        };
+        query.filters.push(Filter {
+            field: TestFilterFields::Active,
+            op: Operator::Eq,
+            value: FilterValue::Scalar(ScalarValue::Bool(true)),
+        });
+
+        apply_list_query(&mut qb, &query);
+        let sql = qb.into_sql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM test WHERE 1=1 AND active = $1 AND test.id > $2 ORDER BY test.id ASC LIMIT $3"
+        );
+    }
+
+    #[test]
+    fn test_apply_filters_search() {
+        let mut qb = QueryBuilder::new("SELECT * FROM test");
+        let query = TestListQuery {
+            search: Some("42".to_string()),
+            ..Default::default()
+        };
+
+        apply_filters(&mut qb, &query);
+        let sql = qb.into_sql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM test WHERE 1=1 AND (FALSE OR name ILIKE $1 ESCAPE '\\' OR name % $2 OR to_tsvector('simple', name) @@ plainto_tsquery('simple', $3) OR code ILIKE $4 ESCAPE '\\' OR id = $5)"
+        );
+    }
+
+    #[test]
+    fn test_contains_pattern_escapes_wildcards() {
+        assert_eq!(contains_pattern("50%_off\\"), "%50\\%\\_off\\\\%");
+    }
+
+    #[test]
+    fn test_apply_filters_search_skips_unparsable_ids() {
+        let mut qb = QueryBuilder::new("SELECT * FROM test");
+        let query = TestListQuery {
+            search: Some("abc".to_string()),
+            cursor: Some(Cursor { after: Some(5) }),
+            ..Default::default()
+        };
+
+        apply_list_query(&mut qb, &query);
+        let sql = qb.into_sql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM test WHERE 1=1 AND (FALSE OR name ILIKE $1 ESCAPE '\\' OR name % $2 OR to_tsvector('simple', name) @@ plainto_tsquery('simple', $3) OR code ILIKE $4 ESCAPE '\\') AND test.id < $5 ORDER BY test.id DESC LIMIT $6"
+        );
+    }
+
+    #[test]
+    fn test_into_paginated_result_sets_next_cursor() {
+        let query = TestListQuery {
+            cursor: Some(Cursor::default()),
+            pagination: Pagination {
+                page: 1,
+                page_size: 2,
+            },
+            ..Default::default()
+        };
+
+        let page = into_paginated_result(&query, vec![30, 20, 10], 3, |id| *id);
+        assert_eq!(page.items, vec![30, 20]);
+        assert_eq!(
+            Cursor::decode(page.next_cursor.as_deref().unwrap())
+                .unwrap()
+                .after,
+            Some(20)
+        );
+
+        let last_page = into_paginated_result(&query, vec![10], 3, |id| *id);
+        assert!(last_page.next_cursor.is_none());
+    }
+
     #[test]
     fn test_push_bind_scalar_all_types() {
         let text = ScalarValue::Text("text".to_string());
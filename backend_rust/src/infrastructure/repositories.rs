@@ -1,5 +1,8 @@
+pub mod accounting_export;
 pub mod active_token;
+pub mod admin_api_key;
 pub mod admin_user;
+pub mod admin_user_recovery_code;
 pub mod admin_user_with_roles;
 pub mod analytics;
 pub mod audit_log;
@@ -7,14 +10,23 @@ pub mod bot;
 pub mod broadcast;
 pub mod category;
 pub mod customer;
+pub mod customer_segment;
 pub mod dashboard;
 pub mod effective_permission;
+pub mod exchange_rate;
+pub mod gateway_attempt;
+pub mod idempotency_key;
 pub mod image;
 pub mod order;
 pub mod order_item;
+pub mod payment_gateway_settings;
 pub mod payment_invoice;
+pub mod payment_invoice_event;
+pub mod payment_webhook_event;
 pub mod permission;
+pub mod product_sync;
 pub mod products;
+pub mod reconciliation;
 pub mod role;
 pub mod role_permission;
 pub mod settings;
@@ -25,3 +37,4 @@ pub mod transaction;
 pub mod user_permission;
 pub mod user_role;
 pub mod user_subscription;
+pub mod worker_heartbeat;
@@ -0,0 +1,370 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared_dtos::accounting_export::{
+    AccountingExportEntity, AccountingExportFormat, AccountingExportStatus,
+};
+use sqlx::{PgPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    errors::repository::RepositoryResult,
+    infrastructure::lib::query::{apply_filters, apply_list_query},
+    models::{
+        accounting_export::{
+            AccountingExportListQuery, AccountingExportRow, CompletedAccountingExport,
+            NewAccountingExport,
+        },
+        common::PaginatedResult,
+    },
+};
+
+#[async_trait]
+pub trait AccountingExportRepositoryTrait {
+    async fn get_list(
+        &self,
+        query: AccountingExportListQuery,
+    ) -> RepositoryResult<PaginatedResult<AccountingExportRow>>;
+    async fn get_by_id(&self, id: i64) -> RepositoryResult<AccountingExportRow>;
+    /// Completed export whose download link has not expired yet
+    async fn get_by_download_token(
+        &self,
+        token: Uuid,
+    ) -> RepositoryResult<Option<AccountingExportRow>>;
+    async fn create(&self, export: NewAccountingExport) -> RepositoryResult<AccountingExportRow>;
+    /// Marks the oldest pending export as processing, retrying exports stuck since `stale_before`
+    async fn claim_next(
+        &self,
+        stale_before: DateTime<Utc>,
+    ) -> RepositoryResult<Option<AccountingExportRow>>;
+    async fn complete(
+        &self,
+        id: i64,
+        result: CompletedAccountingExport,
+    ) -> RepositoryResult<AccountingExportRow>;
+    async fn fail(
+        &self,
+        id: i64,
+        error: &str,
+        expires_at: DateTime<Utc>,
+    ) -> RepositoryResult<AccountingExportRow>;
+    /// Deletes expired exports and returns them, their files are removed by the caller
+    async fn delete_expired(&self) -> RepositoryResult<Vec<AccountingExportRow>>;
+}
+
+#[derive(Clone)]
+pub struct AccountingExportRepository {
+    pool: Arc<PgPool>,
+}
+
+impl AccountingExportRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AccountingExportRepositoryTrait for AccountingExportRepository {
+    async fn get_list(
+        &self,
+        query: AccountingExportListQuery,
+    ) -> RepositoryResult<PaginatedResult<AccountingExportRow>> {
+        let mut count_qb = QueryBuilder::new("SELECT COUNT(*) FROM accounting_exports");
+        apply_filters(&mut count_qb, &query);
+
+        let count_query = count_qb.build_query_scalar();
+        let total: i64 = count_query.fetch_one(&*self.pool).await?;
+
+        let mut query_builder = QueryBuilder::new("SELECT * FROM accounting_exports");
+        apply_list_query(&mut query_builder, &query);
+        let items_query = query_builder.build_query_as::<AccountingExportRow>();
+        let items = items_query.fetch_all(&*self.pool).await?;
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
+    }
+
+    async fn get_by_id(&self, id: i64) -> RepositoryResult<AccountingExportRow> {
+        let result = sqlx::query_as!(
+            AccountingExportRow,
+            r#"
+            SELECT
+                id, entity as "entity: _", format as "format: _", query, date_from, date_to,
+                status as "status: _", rows_count, file_name, download_token, error,
+                created_by, created_at, started_at, finished_at, expires_at
+            FROM accounting_exports
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn get_by_download_token(
+        &self,
+        token: Uuid,
+    ) -> RepositoryResult<Option<AccountingExportRow>> {
+        let result = sqlx::query_as!(
+            AccountingExportRow,
+            r#"
+            SELECT
+                id, entity as "entity: _", format as "format: _", query, date_from, date_to,
+                status as "status: _", rows_count, file_name, download_token, error,
+                created_by, created_at, started_at, finished_at, expires_at
+            FROM accounting_exports
+            WHERE download_token = $1
+              AND status = 'completed'
+              AND expires_at > NOW()
+            "#,
+            token
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn create(&self, export: NewAccountingExport) -> RepositoryResult<AccountingExportRow> {
+        let result = sqlx::query_as!(
+            AccountingExportRow,
+            r#"
+            INSERT INTO accounting_exports (entity, format, query, date_from, date_to, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING
+                id, entity as "entity: _", format as "format: _", query, date_from, date_to,
+                status as "status: _", rows_count, file_name, download_token, error,
+                created_by, created_at, started_at, finished_at, expires_at
+            "#,
+            export.entity as AccountingExportEntity,
+            export.format as AccountingExportFormat,
+            export.query,
+            export.date_from,
+            export.date_to,
+            export.created_by
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn claim_next(
+        &self,
+        stale_before: DateTime<Utc>,
+    ) -> RepositoryResult<Option<AccountingExportRow>> {
+        let result = sqlx::query_as!(
+            AccountingExportRow,
+            r#"
+            UPDATE accounting_exports
+            SET status = 'processing', started_at = NOW()
+            WHERE id = (
+                SELECT id FROM accounting_exports
+                WHERE status = 'pending'
+                   OR (status = 'processing' AND started_at < $1)
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING
+                id, entity as "entity: _", format as "format: _", query, date_from, date_to,
+                status as "status: _", rows_count, file_name, download_token, error,
+                created_by, created_at, started_at, finished_at, expires_at
+            "#,
+            stale_before
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn complete(
+        &self,
+        id: i64,
+        result: CompletedAccountingExport,
+    ) -> RepositoryResult<AccountingExportRow> {
+        let result = sqlx::query_as!(
+            AccountingExportRow,
+            r#"
+            UPDATE accounting_exports
+            SET status = $2, rows_count = $3, file_name = $4, download_token = $5,
+                expires_at = $6, finished_at = NOW()
+            WHERE id = $1
+            RETURNING
+                id, entity as "entity: _", format as "format: _", query, date_from, date_to,
+                status as "status: _", rows_count, file_name, download_token, error,
+                created_by, created_at, started_at, finished_at, expires_at
+            "#,
+            id,
+            AccountingExportStatus::Completed as AccountingExportStatus,
+            result.rows_count,
+            result.file_name,
+            result.download_token,
+            result.expires_at
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn fail(
+        &self,
+        id: i64,
+        error: &str,
+        expires_at: DateTime<Utc>,
+    ) -> RepositoryResult<AccountingExportRow> {
+        let result = sqlx::query_as!(
+            AccountingExportRow,
+            r#"
+            UPDATE accounting_exports
+            SET status = $2, error = $3, expires_at = $4, finished_at = NOW()
+            WHERE id = $1
+            RETURNING
+                id, entity as "entity: _", format as "format: _", query, date_from, date_to,
+                status as "status: _", rows_count, file_name, download_token, error,
+                created_by, created_at, started_at, finished_at, expires_at
+            "#,
+            id,
+            AccountingExportStatus::Failed as AccountingExportStatus,
+            error,
+            expires_at
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn delete_expired(&self) -> RepositoryResult<Vec<AccountingExportRow>> {
+        let result = sqlx::query_as!(
+            AccountingExportRow,
+            r#"
+            DELETE FROM accounting_exports
+            WHERE expires_at <= NOW()
+            RETURNING
+                id, entity as "entity: _", format as "format: _", query, date_from, date_to,
+                status as "status: _", rows_count, file_name, download_token, error,
+                created_by, created_at, started_at, finished_at, expires_at
+            "#
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn new_export() -> NewAccountingExport {
+        NewAccountingExport {
+            entity: AccountingExportEntity::Transactions,
+            format: AccountingExportFormat::Csv,
+            query: "filters[0][field]=type&filters[0][op]=eq&filters[0][value]=deposit".to_string(),
+            date_from: Utc::now() - Duration::days(30),
+            date_to: Utc::now(),
+            created_by: 1,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_claim_complete_and_download(pool: PgPool) {
+        let repo = AccountingExportRepository::new(Arc::new(pool));
+
+        let export = repo.create(new_export()).await.unwrap();
+        assert_eq!(export.status, AccountingExportStatus::Pending);
+
+        let claimed = repo
+            .claim_next(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.id, export.id);
+        assert_eq!(claimed.status, AccountingExportStatus::Processing);
+        // Claimed exports are not handed out twice while they are being built
+        assert!(
+            repo.claim_next(Utc::now() - Duration::hours(1))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        // ...unless the worker building them died
+        let reclaimed = repo
+            .claim_next(Utc::now() + Duration::seconds(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reclaimed.id, export.id);
+
+        let token = Uuid::new_v4();
+        let completed = repo
+            .complete(
+                export.id,
+                CompletedAccountingExport {
+                    rows_count: 42,
+                    file_name: "1.csv".to_string(),
+                    download_token: token,
+                    expires_at: Utc::now() + Duration::hours(1),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(completed.status, AccountingExportStatus::Completed);
+        assert_eq!(completed.rows_count, Some(42));
+
+        let downloaded = repo.get_by_download_token(token).await.unwrap().unwrap();
+        assert_eq!(downloaded.id, export.id);
+        assert_eq!(downloaded.file_name.as_deref(), Some("1.csv"));
+        assert!(
+            repo.get_by_download_token(Uuid::new_v4())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_delete_expired_returns_deleted_exports(pool: PgPool) {
+        let repo = AccountingExportRepository::new(Arc::new(pool));
+
+        let expired = repo.create(new_export()).await.unwrap();
+        let token = Uuid::new_v4();
+        repo.complete(
+            expired.id,
+            CompletedAccountingExport {
+                rows_count: 1,
+                file_name: "expired.csv".to_string(),
+                download_token: token,
+                expires_at: Utc::now() - Duration::seconds(1),
+            },
+        )
+        .await
+        .unwrap();
+        let failed = repo.create(new_export()).await.unwrap();
+        repo.fail(failed.id, "boom", Utc::now() - Duration::seconds(1))
+            .await
+            .unwrap();
+        let pending = repo.create(new_export()).await.unwrap();
+
+        assert!(repo.get_by_download_token(token).await.unwrap().is_none());
+        let mut deleted = repo.delete_expired().await.unwrap();
+        deleted.sort_by_key(|export| export.id);
+        assert_eq!(
+            deleted.iter().map(|export| export.id).collect::<Vec<_>>(),
+            vec![expired.id, failed.id]
+        );
+        // The caller needs the file names to remove the files
+        assert_eq!(deleted[0].file_name.as_deref(), Some("expired.csv"));
+        assert_eq!(deleted[1].file_name, None);
+        assert!(repo.get_by_id(expired.id).await.is_err());
+        assert_eq!(
+            repo.get_by_id(pending.id).await.unwrap().status,
+            AccountingExportStatus::Pending
+        );
+        assert!(repo.get_by_id(failed.id).await.is_err());
+    }
+}
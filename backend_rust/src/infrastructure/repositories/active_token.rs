@@ -19,6 +19,11 @@ pub trait ActiveTokenRepositoryTrait {
         token_type: TokenType,
     ) -> RepositoryResult<ActiveTokenRow>;
     async fn revoke_token(&self, jti: Uuid) -> RepositoryResult<()>;
+    async fn revoke_all_for_user(
+        &self,
+        user_id: i64,
+        except: Option<Uuid>,
+    ) -> RepositoryResult<u64>;
     async fn delete_expired(&self) -> RepositoryResult<u64>;
 }
 
@@ -93,6 +98,28 @@ impl ActiveTokenRepositoryTrait for ActiveTokenRepository {
         Ok(())
     }
 
+    async fn revoke_all_for_user(
+        &self,
+        user_id: i64,
+        except: Option<Uuid>,
+    ) -> RepositoryResult<u64> {
+        let res = sqlx::query!(
+            r#"
+        UPDATE active_tokens
+        SET revoked_at = NOW()
+        WHERE user_id = $1
+          AND revoked_at IS NULL
+          AND ($2::UUID IS NULL OR jti <> $2)
+        "#,
+            user_id,
+            except
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
     async fn delete_expired(&self) -> RepositoryResult<u64> {
         let res = sqlx::query!(
             r#"
@@ -262,4 +289,43 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[sqlx::test]
+    async fn test_revoke_all_for_user(pool: PgPool) {
+        let repo = ActiveTokenRepository::new(Arc::new(pool.clone()));
+        let user = create_test_user(&pool, "user_for_revoke_all").await;
+        let other = create_test_user(&pool, "other_for_revoke_all").await;
+
+        let kept =
+            create_token_with_expiry(&pool, user.id, TokenType::Access, Duration::minutes(10))
+                .await;
+        let revoked =
+            create_token_with_expiry(&pool, user.id, TokenType::Access, Duration::minutes(10))
+                .await;
+        let foreign =
+            create_token_with_expiry(&pool, other.id, TokenType::Access, Duration::minutes(10))
+                .await;
+
+        let count = repo
+            .revoke_all_for_user(user.id, Some(kept.jti))
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        assert!(
+            repo.get_active_token(kept.jti, TokenType::Access)
+                .await
+                .is_ok()
+        );
+        assert!(
+            repo.get_active_token(revoked.jti, TokenType::Access)
+                .await
+                .is_err()
+        );
+        assert!(
+            repo.get_active_token(foreign.jti, TokenType::Access)
+                .await
+                .is_ok()
+        );
+    }
 }
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::{PgPool, types::ipnetwork::IpNetwork};
+
+use crate::{
+    errors::repository::RepositoryResult,
+    models::admin_api_key::{AdminApiKeyRow, NewAdminApiKey},
+};
+
+#[async_trait]
+pub trait AdminApiKeyRepositoryTrait {
+    async fn get_list(&self) -> RepositoryResult<Vec<AdminApiKeyRow>>;
+    async fn get_by_id(&self, id: i64) -> RepositoryResult<AdminApiKeyRow>;
+    async fn get_active_by_hash(&self, key_hash: &str) -> RepositoryResult<AdminApiKeyRow>;
+    async fn create(&self, key: NewAdminApiKey) -> RepositoryResult<AdminApiKeyRow>;
+    async fn revoke(&self, id: i64) -> RepositoryResult<AdminApiKeyRow>;
+    async fn touch(&self, id: i64, ip: Option<IpNetwork>) -> RepositoryResult<()>;
+}
+
+#[derive(Clone)]
+pub struct AdminApiKeyRepository {
+    pool: Arc<PgPool>,
+}
+
+impl AdminApiKeyRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AdminApiKeyRepositoryTrait for AdminApiKeyRepository {
+    async fn get_list(&self) -> RepositoryResult<Vec<AdminApiKeyRow>> {
+        let result = sqlx::query_as!(
+            AdminApiKeyRow,
+            "SELECT * FROM admin_api_keys ORDER BY created_at DESC"
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn get_by_id(&self, id: i64) -> RepositoryResult<AdminApiKeyRow> {
+        let result = sqlx::query_as!(
+            AdminApiKeyRow,
+            "SELECT * FROM admin_api_keys WHERE id = $1",
+            id
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn get_active_by_hash(&self, key_hash: &str) -> RepositoryResult<AdminApiKeyRow> {
+        let result = sqlx::query_as!(
+            AdminApiKeyRow,
+            r#"
+            SELECT k.*
+            FROM admin_api_keys k
+            JOIN admin_users u ON u.id = k.created_by
+            WHERE k.key_hash = $1
+              AND k.revoked_at IS NULL
+              AND (k.expires_at IS NULL OR k.expires_at > NOW())
+              AND u.deleted_at IS NULL
+            "#,
+            key_hash
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn create(&self, key: NewAdminApiKey) -> RepositoryResult<AdminApiKeyRow> {
+        let result = sqlx::query_as!(
+            AdminApiKeyRow,
+            r#"
+            INSERT INTO admin_api_keys (name, key_prefix, key_hash, permissions, allowed_ips, expires_at, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+            key.name,
+            key.key_prefix,
+            key.key_hash,
+            &key.permissions[..],
+            &key.allowed_ips[..],
+            key.expires_at,
+            key.created_by
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn revoke(&self, id: i64) -> RepositoryResult<AdminApiKeyRow> {
+        let result = sqlx::query_as!(
+            AdminApiKeyRow,
+            r#"
+            UPDATE admin_api_keys
+            SET revoked_at = COALESCE(revoked_at, NOW())
+            WHERE id = $1
+            RETURNING *
+            "#,
+            id
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn touch(&self, id: i64, ip: Option<IpNetwork>) -> RepositoryResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE admin_api_keys
+            SET last_used_at = NOW(), last_used_ip = COALESCE($2, last_used_ip)
+            WHERE id = $1
+            "#,
+            id,
+            ip
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::repository::RepositoryError;
+    use chrono::{Duration, Utc};
+    use sqlx::PgPool;
+
+    fn new_key(hash: &str) -> NewAdminApiKey {
+        NewAdminApiKey {
+            name: "accounting".to_string(),
+            key_prefix: "ak_test".to_string(),
+            key_hash: hash.to_string(),
+            permissions: vec!["orders:read".to_string()],
+            allowed_ips: vec!["10.0.0.0/8".parse().unwrap()],
+            expires_at: None,
+            created_by: 1,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_create_and_get_active(pool: PgPool) {
+        let repo = AdminApiKeyRepository::new(Arc::new(pool));
+        let created = repo.create(new_key("hash_active")).await.unwrap();
+
+        let found = repo.get_active_by_hash("hash_active").await.unwrap();
+        assert_eq!(found.id, created.id);
+        assert_eq!(found.permissions, vec!["orders:read".to_string()]);
+        assert_eq!(found.allowed_ips.len(), 1);
+
+        repo.touch(created.id, Some("10.1.2.3".parse().unwrap()))
+            .await
+            .unwrap();
+        let touched = repo.get_by_id(created.id).await.unwrap();
+        assert!(touched.last_used_at.is_some());
+        assert_eq!(touched.last_used_ip, Some("10.1.2.3".parse().unwrap()));
+    }
+
+    #[sqlx::test]
+    async fn test_revoked_and_expired_keys_are_inactive(pool: PgPool) {
+        let repo = AdminApiKeyRepository::new(Arc::new(pool));
+        let revoked = repo.create(new_key("hash_revoked")).await.unwrap();
+        repo.revoke(revoked.id).await.unwrap();
+
+        let mut expired = new_key("hash_expired");
+        expired.expires_at = Some(Utc::now() - Duration::minutes(1));
+        repo.create(expired).await.unwrap();
+
+        assert!(matches!(
+            repo.get_active_by_hash("hash_revoked").await,
+            Err(RepositoryError::NotFound(_))
+        ));
+        assert!(matches!(
+            repo.get_active_by_hash("hash_expired").await,
+            Err(RepositoryError::NotFound(_))
+        ));
+    }
+}
@@ -1,11 +1,15 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Postgres, QueryBuilder};
 
 use crate::{
     errors::repository::{RepositoryError, RepositoryResult},
-    models::admin_user::{AdminUserRow, NewAdminUser, UpdateAdminUser},
+    models::{
+        admin_user::{AdminUserRow, NewAdminUser, UpdateAdminUser},
+        temporary_token::{TemporaryTokenPurpose, TemporaryTokenRow},
+    },
 };
 
 #[async_trait]
@@ -16,6 +20,29 @@ pub trait AdminUserRepositoryTrait {
     async fn get_by_login(&self, login: &str) -> RepositoryResult<AdminUserRow>;
     async fn update(&self, id: i64, admin_user: UpdateAdminUser) -> RepositoryResult<AdminUserRow>;
     async fn delete(&self, id: i64) -> RepositoryResult<()>;
+    /// Stores an encrypted 2FA secret that only replaces the current one once activated,
+    /// overwriting any earlier pending secret of the user
+    async fn set_pending_two_fa_secret(
+        &self,
+        id: i64,
+        two_fa_secret: String,
+        expires_at: DateTime<Utc>,
+    ) -> RepositoryResult<()>;
+    async fn get_pending_two_fa_secret(&self, id: i64) -> RepositoryResult<String>;
+    /// Swaps in the pending secret, `OptimisticLockViolation` if it expired or was replaced
+    async fn activate_pending_two_fa_secret(
+        &self,
+        id: i64,
+        two_fa_secret: &str,
+    ) -> RepositoryResult<()>;
+    /// In one transaction replaces the 2FA secret, drops the pending secret and recovery
+    /// codes, revokes every session of the user and issues a 2FA enrollment token
+    async fn reset_two_fa(
+        &self,
+        id: i64,
+        two_fa_secret: String,
+        enrollment_expires_at: DateTime<Utc>,
+    ) -> RepositoryResult<TemporaryTokenRow>;
 }
 
 #[derive(Clone)]
@@ -142,6 +169,133 @@ impl AdminUserRepositoryTrait for AdminUserRepository {
         .await?;
         Ok(())
     }
+
+    async fn set_pending_two_fa_secret(
+        &self,
+        id: i64,
+        two_fa_secret: String,
+        expires_at: DateTime<Utc>,
+    ) -> RepositoryResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO admin_user_pending_two_fa_secrets (user_id, two_fa_secret, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET two_fa_secret = EXCLUDED.two_fa_secret,
+                expires_at = EXCLUDED.expires_at,
+                created_at = NOW()
+            "#,
+            id,
+            two_fa_secret,
+            expires_at
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_pending_two_fa_secret(&self, id: i64) -> RepositoryResult<String> {
+        let result = sqlx::query_scalar!(
+            "SELECT two_fa_secret FROM admin_user_pending_two_fa_secrets WHERE user_id = $1 AND expires_at > NOW()",
+            id
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn activate_pending_two_fa_secret(
+        &self,
+        id: i64,
+        two_fa_secret: &str,
+    ) -> RepositoryResult<()> {
+        let rows = sqlx::query!(
+            r#"
+            WITH pending AS (
+                DELETE FROM admin_user_pending_two_fa_secrets
+                WHERE user_id = $1 AND two_fa_secret = $2 AND expires_at > NOW()
+                RETURNING two_fa_secret
+            )
+            UPDATE admin_users
+            SET two_fa_secret = pending.two_fa_secret
+            FROM pending
+            WHERE admin_users.id = $1
+              AND admin_users.deleted_at IS NULL
+              AND admin_users.is_system = false
+            "#,
+            id,
+            two_fa_secret
+        )
+        .execute(&*self.pool)
+        .await?
+        .rows_affected();
+
+        if rows == 0 {
+            return Err(RepositoryError::OptimisticLockViolation);
+        }
+        Ok(())
+    }
+
+    async fn reset_two_fa(
+        &self,
+        id: i64,
+        two_fa_secret: String,
+        enrollment_expires_at: DateTime<Utc>,
+    ) -> RepositoryResult<TemporaryTokenRow> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query!(
+            r#"
+            UPDATE admin_users
+            SET two_fa_secret = $2
+            WHERE id = $1 AND deleted_at IS NULL AND is_system = false
+            "#,
+            id,
+            two_fa_secret
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if rows == 0 {
+            return Err(RepositoryError::NotFound(
+                "admin user: not found".to_string(),
+            ));
+        }
+
+        sqlx::query!(
+            "DELETE FROM admin_user_pending_two_fa_secrets WHERE user_id = $1",
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM admin_user_recovery_codes WHERE user_id = $1",
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "UPDATE active_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        let token = sqlx::query_as!(
+            TemporaryTokenRow,
+            r#"
+            INSERT INTO temporary_tokens (user_id, purpose, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING token, user_id, purpose as "purpose: _", expires_at, created_at, used_at
+            "#,
+            id,
+            TemporaryTokenPurpose::TwoFaEnrollment as TemporaryTokenPurpose,
+            enrollment_expires_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(token)
+    }
 }
 
 #[cfg(test)]
@@ -277,4 +431,127 @@ mod tests {
         // Assert that updated_at has changed
         assert!(updated_user.updated_at > initial_updated_at);
     }
+
+    #[sqlx::test]
+    async fn test_pending_two_fa_secret(pool: PgPool) {
+        let repo = AdminUserRepository::new(Arc::new(pool.clone()));
+        let user = create_test_user(&pool, "testuser_pending_two_fa").await;
+        let expires_at = Utc::now() + chrono::Duration::minutes(10);
+
+        assert!(repo.get_pending_two_fa_secret(user.id).await.is_err());
+
+        repo.set_pending_two_fa_secret(user.id, "first".to_string(), expires_at)
+            .await
+            .unwrap();
+        repo.set_pending_two_fa_secret(user.id, "second".to_string(), expires_at)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_pending_two_fa_secret(user.id).await.unwrap(),
+            "second"
+        );
+        // The current secret stays until the pending one is activated
+        assert_eq!(repo.get_by_id(user.id).await.unwrap().two_fa_secret, "");
+
+        assert!(matches!(
+            repo.activate_pending_two_fa_secret(user.id, "first").await,
+            Err(RepositoryError::OptimisticLockViolation)
+        ));
+        repo.activate_pending_two_fa_secret(user.id, "second")
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_by_id(user.id).await.unwrap().two_fa_secret,
+            "second"
+        );
+        assert!(repo.get_pending_two_fa_secret(user.id).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_expired_pending_two_fa_secret_is_not_activated(pool: PgPool) {
+        let repo = AdminUserRepository::new(Arc::new(pool.clone()));
+        let user = create_test_user(&pool, "testuser_expired_two_fa").await;
+
+        repo.set_pending_two_fa_secret(
+            user.id,
+            "expired".to_string(),
+            Utc::now() - chrono::Duration::minutes(1),
+        )
+        .await
+        .unwrap();
+
+        assert!(repo.get_pending_two_fa_secret(user.id).await.is_err());
+        assert!(
+            repo.activate_pending_two_fa_secret(user.id, "expired")
+                .await
+                .is_err()
+        );
+        assert_eq!(repo.get_by_id(user.id).await.unwrap().two_fa_secret, "");
+    }
+
+    #[sqlx::test]
+    async fn test_reset_two_fa(pool: PgPool) {
+        let repo = AdminUserRepository::new(Arc::new(pool.clone()));
+        let user = create_test_user(&pool, "testuser_reset_two_fa").await;
+        sqlx::query!(
+            "INSERT INTO active_tokens (user_id, token_type, expires_at) VALUES ($1, 'access', NOW() + INTERVAL '1 hour')",
+            user.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO admin_user_recovery_codes (user_id, code_hash) VALUES ($1, 'hash')",
+            user.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        repo.set_pending_two_fa_secret(
+            user.id,
+            "pending".to_string(),
+            Utc::now() + chrono::Duration::minutes(10),
+        )
+        .await
+        .unwrap();
+
+        let token = repo
+            .reset_two_fa(
+                user.id,
+                "placeholder".to_string(),
+                Utc::now() + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(token.user_id, user.id);
+        assert_eq!(token.purpose, TemporaryTokenPurpose::TwoFaEnrollment);
+        assert_eq!(
+            repo.get_by_id(user.id).await.unwrap().two_fa_secret,
+            "placeholder"
+        );
+        assert!(repo.get_pending_two_fa_secret(user.id).await.is_err());
+        let active_sessions = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM active_tokens WHERE user_id = $1 AND revoked_at IS NULL"#,
+            user.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(active_sessions, 0);
+        let recovery_codes = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM admin_user_recovery_codes WHERE user_id = $1"#,
+            user.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(recovery_codes, 0);
+
+        assert!(matches!(
+            repo.reset_two_fa(-1, "placeholder".to_string(), Utc::now())
+                .await,
+            Err(RepositoryError::NotFound(_))
+        ));
+    }
 }
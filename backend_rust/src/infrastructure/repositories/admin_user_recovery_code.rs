@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::{
+    errors::repository::RepositoryResult,
+    models::admin_user_recovery_code::AdminUserRecoveryCodeRow,
+};
+
+#[async_trait]
+pub trait AdminUserRecoveryCodeRepositoryTrait {
+    async fn get_unused(&self, user_id: i64) -> RepositoryResult<Vec<AdminUserRecoveryCodeRow>>;
+    async fn replace_for_user(
+        &self,
+        user_id: i64,
+        code_hashes: Vec<String>,
+    ) -> RepositoryResult<()>;
+    async fn consume(&self, user_id: i64, code_hash: &str) -> RepositoryResult<bool>;
+}
+
+#[derive(Clone)]
+pub struct AdminUserRecoveryCodeRepository {
+    pool: Arc<PgPool>,
+}
+
+impl AdminUserRecoveryCodeRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AdminUserRecoveryCodeRepositoryTrait for AdminUserRecoveryCodeRepository {
+    async fn get_unused(&self, user_id: i64) -> RepositoryResult<Vec<AdminUserRecoveryCodeRow>> {
+        let result = sqlx::query_as!(
+            AdminUserRecoveryCodeRow,
+            "SELECT * FROM admin_user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+            user_id
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn replace_for_user(
+        &self,
+        user_id: i64,
+        code_hashes: Vec<String>,
+    ) -> RepositoryResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "DELETE FROM admin_user_recovery_codes WHERE user_id = $1",
+            user_id
+        )
+        .execute(tx.as_mut())
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO admin_user_recovery_codes (user_id, code_hash)
+            SELECT $1, c.hash
+            FROM unnest($2::TEXT[]) AS c(hash)
+            ON CONFLICT (user_id, code_hash) DO NOTHING
+            "#,
+            user_id,
+            &code_hashes[..]
+        )
+        .execute(tx.as_mut())
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn consume(&self, user_id: i64, code_hash: &str) -> RepositoryResult<bool> {
+        let rows = sqlx::query!(
+            r#"
+            UPDATE admin_user_recovery_codes
+            SET used_at = NOW()
+            WHERE user_id = $1
+              AND code_hash = $2
+              AND used_at IS NULL
+            "#,
+            user_id,
+            code_hash
+        )
+        .execute(&*self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::admin_user::AdminUserRow;
+    use sqlx::PgPool;
+
+    async fn create_test_user(pool: &PgPool, login: &str) -> AdminUserRow {
+        sqlx::query_as!(
+            AdminUserRow,
+            r#"
+            INSERT INTO admin_users (login, hashed_password, two_fa_secret, created_by, is_system)
+            VALUES ($1, 'password', '', 1, false)
+            RETURNING *
+            "#,
+            login
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_replace_for_user(pool: PgPool) {
+        let repo = AdminUserRecoveryCodeRepository::new(Arc::new(pool.clone()));
+        let user = create_test_user(&pool, "user_for_recovery_codes").await;
+
+        repo.replace_for_user(user.id, vec!["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(repo.get_unused(user.id).await.unwrap().len(), 2);
+
+        repo.replace_for_user(user.id, vec!["c".to_string()])
+            .await
+            .unwrap();
+        let codes = repo.get_unused(user.id).await.unwrap();
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].code_hash, "c");
+    }
+
+    #[sqlx::test]
+    async fn test_consume_is_single_use(pool: PgPool) {
+        let repo = AdminUserRecoveryCodeRepository::new(Arc::new(pool.clone()));
+        let user = create_test_user(&pool, "user_for_recovery_consume").await;
+        let other = create_test_user(&pool, "other_for_recovery_consume").await;
+
+        repo.replace_for_user(user.id, vec!["hash".to_string()])
+            .await
+            .unwrap();
+
+        assert!(!repo.consume(other.id, "hash").await.unwrap());
+        assert!(repo.consume(user.id, "hash").await.unwrap());
+        assert!(!repo.consume(user.id, "hash").await.unwrap());
+        assert!(repo.get_unused(user.id).await.unwrap().is_empty());
+    }
+}
@@ -5,7 +5,7 @@ use sqlx::{PgPool, Postgres, QueryBuilder, types::ipnetwork::IpNetwork};
 
 use crate::{
     errors::repository::RepositoryResult,
-    infrastructure::lib::query::{apply_filters, apply_list_query},
+    infrastructure::lib::query::{apply_filters, apply_list_query, into_paginated_result},
     models::{
         audit_log::{AuditLogListQuery, AuditLogRow, NewAuditLog},
         common::PaginatedResult,
@@ -19,6 +19,12 @@ pub trait AuditLogRepositoryTrait {
         query: AuditLogListQuery,
     ) -> RepositoryResult<PaginatedResult<AuditLogRow>>;
     async fn create(&self, audit_log: NewAuditLog) -> RepositoryResult<AuditLogRow>;
+    /// Every log of one record, oldest first
+    async fn get_for_target(
+        &self,
+        target_table: &str,
+        target_id: &str,
+    ) -> RepositoryResult<Vec<AuditLogRow>>;
 }
 
 #[derive(Clone)]
@@ -54,9 +60,11 @@ impl AuditLogRepositoryTrait for AuditLogRepository {
         LEFT JOIN admin_users ON audit_logs.admin_user_id = admin_users.id"#,
         );
         apply_list_query(&mut query_builder, &query);
-        let query = query_builder.build_query_as::<AuditLogRow>();
-        let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        let items = query_builder
+            .build_query_as::<AuditLogRow>()
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(into_paginated_result(&query, items, total, |row| row.id))
     }
 
     async fn create(&self, audit_log: NewAuditLog) -> RepositoryResult<AuditLogRow> {
@@ -115,6 +123,44 @@ impl AuditLogRepositoryTrait for AuditLogRepository {
 
         Ok(row)
     }
+
+    async fn get_for_target(
+        &self,
+        target_table: &str,
+        target_id: &str,
+    ) -> RepositoryResult<Vec<AuditLogRow>> {
+        let rows = sqlx::query_as!(
+            AuditLogRow,
+            r#"
+            SELECT
+                al.id,
+                al.admin_user_id,
+                al.customer_id,
+                al.action as "action: _",
+                al.status as "status: _",
+                al.target_table,
+                al.target_id,
+                al.old_values,
+                al.new_values,
+                al.ip_address,
+                al.user_agent,
+                al.request_id,
+                al.error_message,
+                al.created_at,
+                au.login AS "admin_user_login?"
+            FROM audit_logs al
+            LEFT JOIN admin_users au ON al.admin_user_id = au.id
+            WHERE al.target_table = $1 AND al.target_id = $2
+            ORDER BY al.created_at, al.id
+            "#,
+            target_table,
+            target_id
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+
+        Ok(rows)
+    }
 }
 
 #[cfg(test)]
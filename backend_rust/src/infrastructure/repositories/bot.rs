@@ -67,7 +67,11 @@ impl BotRepositoryTrait for BotRepository {
         apply_list_query(&mut query_builder, &query);
         let query = query_builder.build_query_as::<BotRow>();
         let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
     }
 
     async fn create(&self, bot: NewBot) -> RepositoryResult<BotRow> {
@@ -1,15 +1,21 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use shared_dtos::broadcast::BroadcastStatus;
-use sqlx::{PgPool, QueryBuilder};
+use chrono::{DateTime, Utc};
+use shared_dtos::broadcast::{BroadcastRecipientStatus, BroadcastStatus};
+use sqlx::{PgPool, Postgres, QueryBuilder, types::Json};
 
 use crate::{
     errors::repository::{RepositoryError, RepositoryResult},
-    infrastructure::lib::query::{apply_filters, apply_list_query},
+    infrastructure::lib::query::{apply_filters, apply_list_query, into_paginated_result},
     models::{
-        broadcast::{BroadcastListQuery, BroadcastRow, NewBroadcast, UpdateBroadcast},
+        broadcast::{
+            BroadcastDeliveryRow, BroadcastListQuery, BroadcastRecipientListQuery,
+            BroadcastRecipientRow, BroadcastRecipientStats, BroadcastRow, BroadcastVariantRow,
+            BroadcastVariantStatsRow, NewBroadcast, UpdateBroadcast,
+        },
         common::PaginatedResult,
+        customer::{CustomerListQuery, customer_list_source},
     },
 };
 
@@ -20,9 +26,59 @@ pub trait BroadcastRepositoryTrait {
         query: BroadcastListQuery,
     ) -> RepositoryResult<PaginatedResult<BroadcastRow>>;
     async fn create(&self, broadcast: NewBroadcast) -> RepositoryResult<BroadcastRow>;
+    /// With `expected_status` set, a broadcast in another status is an optimistic lock violation
     async fn update(&self, id: i64, broadcast: UpdateBroadcast) -> RepositoryResult<BroadcastRow>;
     async fn get_by_id(&self, id: i64) -> RepositoryResult<BroadcastRow>;
     async fn get_ready_broadcasts(&self) -> RepositoryResult<Vec<BroadcastRow>>;
+    async fn get_in_progress_broadcasts(&self) -> RepositoryResult<Vec<BroadcastRow>>;
+    /// Moves a pending/scheduled broadcast to `in_progress` and materializes its recipients.
+    /// Returns `None` if the broadcast is no longer startable.
+    async fn start(
+        &self,
+        id: i64,
+        audience: &CustomerListQuery,
+    ) -> RepositoryResult<Option<(BroadcastRow, u64)>>;
+    async fn get_pending_recipients(
+        &self,
+        broadcast_id: i64,
+        limit: i64,
+    ) -> RepositoryResult<Vec<BroadcastDeliveryRow>>;
+    async fn update_recipient_status(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> RepositoryResult<()>;
+    /// Stores the bot's delivery result of a dispatched recipient
+    async fn report_recipient_delivery(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> RepositoryResult<()>;
+    /// Fails recipients dispatched before `dispatched_before` that the bot never reported on
+    async fn fail_undelivered_recipients(
+        &self,
+        broadcast_id: i64,
+        dispatched_before: DateTime<Utc>,
+        error_message: &str,
+    ) -> RepositoryResult<u64>;
+    async fn get_recipient_stats(
+        &self,
+        broadcast_id: i64,
+    ) -> RepositoryResult<BroadcastRecipientStats>;
+    async fn get_recipients(
+        &self,
+        broadcast_id: i64,
+        query: BroadcastRecipientListQuery,
+    ) -> RepositoryResult<PaginatedResult<BroadcastRecipientRow>>;
+    async fn get_variants(&self, broadcast_id: i64) -> RepositoryResult<Vec<BroadcastVariantRow>>;
+    /// Per-variant delivery numbers, a conversion is a paid order within `conversion_days` of delivery
+    async fn get_variant_stats(
+        &self,
+        broadcast_id: i64,
+        conversion_days: i32,
+    ) -> RepositoryResult<Vec<BroadcastVariantStatsRow>>;
 }
 
 #[derive(Clone)]
@@ -52,32 +108,86 @@ impl BroadcastRepositoryTrait for BroadcastRepository {
         apply_list_query(&mut query_builder, &query);
         let items_query = query_builder.build_query_as::<BroadcastRow>();
         let items = items_query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
     }
 
     async fn create(&self, broadcast: NewBroadcast) -> RepositoryResult<BroadcastRow> {
+        let mut tx = self.pool.begin().await?;
+
         let result = sqlx::query_as!(
             BroadcastRow,
             r#"
-            INSERT INTO broadcasts (status, content_text, content_image_id, filters, created_by, scheduled_for)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO broadcasts (
+                status, content_text, content_image_id, content_entities, content_media,
+                content_buttons, filters, segment_id, created_by, scheduled_for
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING 
-                id, status as "status: _", content_text, content_image_id, filters, statistics,
+                id, status as "status: _", content_text, content_image_id,
+                content_entities as "content_entities: _", content_media as "content_media: _",
+                content_buttons as "content_buttons: _", filters, segment_id, statistics,
                 created_by, scheduled_for, started_at, finished_at, created_at, updated_at
             "#,
             broadcast.status as BroadcastStatus,
             broadcast.content_text,
             broadcast.content_image_id,
+            Json(&broadcast.content_entities) as _,
+            Json(&broadcast.content_media) as _,
+            Json(&broadcast.content_buttons) as _,
             broadcast.filters,
+            broadcast.segment_id,
             broadcast.created_by,
             broadcast.scheduled_for
         )
-        .fetch_one(&*self.pool)
+        .fetch_one(tx.as_mut())
         .await?;
 
+        for variant in &broadcast.variants {
+            sqlx::query!(
+                r#"
+                INSERT INTO broadcast_variants (
+                    broadcast_id, name, content_text, content_image_id, content_entities,
+                    content_media, content_buttons
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                result.id,
+                variant.name,
+                variant.content_text,
+                variant.content_image_id,
+                Json(&variant.content_entities) as _,
+                Json(&variant.content_media) as _,
+                Json(&variant.content_buttons) as _
+            )
+            .execute(tx.as_mut())
+            .await?;
+        }
+
+        tx.commit().await?;
         Ok(result)
     }
 
+    async fn get_variants(&self, broadcast_id: i64) -> RepositoryResult<Vec<BroadcastVariantRow>> {
+        sqlx::query_as!(
+            BroadcastVariantRow,
+            r#"
+            SELECT
+                id, broadcast_id, name, content_text, content_image_id,
+                content_entities as "content_entities: _", content_media as "content_media: _",
+                content_buttons as "content_buttons: _", created_at
+            FROM broadcast_variants WHERE broadcast_id = $1
+            ORDER BY id"#,
+            broadcast_id
+        )
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(RepositoryError::from)
+    }
+
     async fn update(&self, id: i64, broadcast: UpdateBroadcast) -> RepositoryResult<BroadcastRow> {
         let mut query_builder = QueryBuilder::new("UPDATE broadcasts SET status = COALESCE(");
 
@@ -93,6 +203,21 @@ impl BroadcastRepositoryTrait for BroadcastRepository {
             }
         }
 
+        if let Some(content_entities) = broadcast.content_entities {
+            query_builder.push(", content_entities = ");
+            query_builder.push_bind(Json(content_entities));
+        }
+
+        if let Some(content_media) = broadcast.content_media {
+            query_builder.push(", content_media = ");
+            query_builder.push_bind(Json(content_media));
+        }
+
+        if let Some(content_buttons) = broadcast.content_buttons {
+            query_builder.push(", content_buttons = ");
+            query_builder.push_bind(Json(content_buttons));
+        }
+
         if let Some(filters) = broadcast.filters {
             query_builder.push(", filters = ");
             if let Some(filters) = filters {
@@ -149,10 +274,24 @@ impl BroadcastRepositoryTrait for BroadcastRepository {
 
         query_builder.push(" WHERE id = ");
         query_builder.push_bind(id);
+        if let Some(expected_status) = &broadcast.expected_status {
+            query_builder.push(" AND status IN (");
+            let mut separated = query_builder.separated(", ");
+            for status in expected_status {
+                separated.push_bind(*status);
+            }
+            separated.push_unseparated(")");
+        }
         query_builder.push(" RETURNING *");
 
         let query = query_builder.build_query_as::<BroadcastRow>();
 
+        if broadcast.expected_status.is_some() {
+            return query
+                .fetch_optional(&*self.pool)
+                .await?
+                .ok_or(RepositoryError::OptimisticLockViolation);
+        }
         query
             .fetch_one(&*self.pool)
             .await
@@ -164,7 +303,9 @@ impl BroadcastRepositoryTrait for BroadcastRepository {
             BroadcastRow,
             r#"
             SELECT  
-                id, status as "status: _", content_text, content_image_id, filters, statistics,
+                id, status as "status: _", content_text, content_image_id,
+                content_entities as "content_entities: _", content_media as "content_media: _",
+                content_buttons as "content_buttons: _", filters, segment_id, statistics,
                 created_by, scheduled_for, started_at, finished_at, created_at, updated_at
             FROM broadcasts WHERE id = $1"#,
             id
@@ -179,7 +320,9 @@ impl BroadcastRepositoryTrait for BroadcastRepository {
             BroadcastRow,
             r#"
             SELECT  
-                id, status as "status: _", content_text, content_image_id, filters, statistics,
+                id, status as "status: _", content_text, content_image_id,
+                content_entities as "content_entities: _", content_media as "content_media: _",
+                content_buttons as "content_buttons: _", filters, segment_id, statistics,
                 created_by, scheduled_for, started_at, finished_at, created_at, updated_at
             FROM broadcasts WHERE
                 status = $1
@@ -194,6 +337,273 @@ impl BroadcastRepositoryTrait for BroadcastRepository {
         .await
         .map_err(RepositoryError::from)
     }
+
+    async fn get_in_progress_broadcasts(&self) -> RepositoryResult<Vec<BroadcastRow>> {
+        sqlx::query_as!(
+            BroadcastRow,
+            r#"
+            SELECT
+                id, status as "status: _", content_text, content_image_id,
+                content_entities as "content_entities: _", content_media as "content_media: _",
+                content_buttons as "content_buttons: _", filters, segment_id, statistics,
+                created_by, scheduled_for, started_at, finished_at, created_at, updated_at
+            FROM broadcasts WHERE status = $1
+            ORDER BY id"#,
+            BroadcastStatus::InProgress as _
+        )
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(RepositoryError::from)
+    }
+
+    async fn start(
+        &self,
+        id: i64,
+        audience: &CustomerListQuery,
+    ) -> RepositoryResult<Option<(BroadcastRow, u64)>> {
+        let mut tx = self.pool.begin().await?;
+
+        let started = sqlx::query_as!(
+            BroadcastRow,
+            r#"
+            UPDATE broadcasts SET status = $2, started_at = NOW()
+            WHERE id = $1 AND status IN ($3, $4)
+            RETURNING
+                id, status as "status: _", content_text, content_image_id,
+                content_entities as "content_entities: _", content_media as "content_media: _",
+                content_buttons as "content_buttons: _", filters, segment_id, statistics,
+                created_by, scheduled_for, started_at, finished_at, created_at, updated_at
+            "#,
+            id,
+            BroadcastStatus::InProgress as _,
+            BroadcastStatus::Pending as _,
+            BroadcastStatus::Scheduled as _
+        )
+        .fetch_optional(tx.as_mut())
+        .await?;
+
+        let Some(started) = started else {
+            return Ok(None);
+        };
+
+        // Base content (NULL) and every variant get an equal share of randomly ordered customers
+        let variant_ids: Vec<Option<i64>> = std::iter::once(None)
+            .chain(
+                sqlx::query_scalar!(
+                    "SELECT id FROM broadcast_variants WHERE broadcast_id = $1 ORDER BY id",
+                    id
+                )
+                .fetch_all(tx.as_mut())
+                .await?
+                .into_iter()
+                .map(Some),
+            )
+            .collect();
+        let variants_count = variant_ids.len() as i64;
+
+        let mut qb = QueryBuilder::new(
+            "INSERT INTO broadcast_recipients (broadcast_id, customer_id, variant_id) SELECT ",
+        );
+        qb.push_bind(id);
+        qb.push(", id, (");
+        qb.push_bind(variant_ids);
+        qb.push("::BIGINT[])[1 + (ROW_NUMBER() OVER (ORDER BY RANDOM()) - 1) % ");
+        qb.push_bind(variants_count);
+        qb.push("] FROM ");
+        qb.push(customer_list_source(audience));
+        apply_filters(&mut qb, audience);
+        qb.push(" ON CONFLICT (broadcast_id, customer_id) DO NOTHING");
+        let inserted = qb.build().execute(tx.as_mut()).await?.rows_affected();
+
+        tx.commit().await?;
+        Ok(Some((started, inserted)))
+    }
+
+    async fn get_pending_recipients(
+        &self,
+        broadcast_id: i64,
+        limit: i64,
+    ) -> RepositoryResult<Vec<BroadcastDeliveryRow>> {
+        sqlx::query_as!(
+            BroadcastDeliveryRow,
+            r#"
+            SELECT
+                br.id, br.customer_id, c.telegram_id, c.last_seen_with_bot, br.variant_id
+            FROM broadcast_recipients br
+            JOIN customers c ON c.id = br.customer_id
+            WHERE br.broadcast_id = $1 AND br.status = $2
+            ORDER BY br.id
+            LIMIT $3"#,
+            broadcast_id,
+            BroadcastRecipientStatus::Pending as _,
+            limit
+        )
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(RepositoryError::from)
+    }
+
+    async fn update_recipient_status(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> RepositoryResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE broadcast_recipients
+            SET
+                status = $2,
+                error_message = $3,
+                sent_at = CASE WHEN $2 = 'sent' THEN NOW() ELSE sent_at END
+            WHERE id = $1"#,
+            id,
+            status as _,
+            error_message
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn report_recipient_delivery(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> RepositoryResult<()> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE broadcast_recipients
+            SET
+                status = $2,
+                error_message = $3,
+                sent_at = CASE WHEN $2 = 'sent' THEN NOW() ELSE sent_at END
+            WHERE id = $1 AND status = $4"#,
+            id,
+            status as _,
+            error_message,
+            BroadcastRecipientStatus::Dispatched as _
+        )
+        .execute(&*self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::OptimisticLockViolation);
+        }
+        Ok(())
+    }
+
+    async fn fail_undelivered_recipients(
+        &self,
+        broadcast_id: i64,
+        dispatched_before: DateTime<Utc>,
+        error_message: &str,
+    ) -> RepositoryResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE broadcast_recipients
+            SET status = $4, error_message = $5
+            WHERE broadcast_id = $1 AND status = $2 AND updated_at < $3"#,
+            broadcast_id,
+            BroadcastRecipientStatus::Dispatched as _,
+            dispatched_before,
+            BroadcastRecipientStatus::Failed as _,
+            error_message
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_recipient_stats(
+        &self,
+        broadcast_id: i64,
+    ) -> RepositoryResult<BroadcastRecipientStats> {
+        sqlx::query_as!(
+            BroadcastRecipientStats,
+            r#"
+            SELECT
+                COUNT(*) as "total!",
+                COUNT(*) FILTER (WHERE status = 'pending') as "pending!",
+                COUNT(*) FILTER (WHERE status = 'dispatched') as "dispatched!",
+                COUNT(*) FILTER (WHERE status = 'sent') as "sent!",
+                COUNT(*) FILTER (WHERE status = 'blocked') as "blocked!",
+                COUNT(*) FILTER (WHERE status = 'failed') as "failed!"
+            FROM broadcast_recipients WHERE broadcast_id = $1"#,
+            broadcast_id
+        )
+        .fetch_one(&*self.pool)
+        .await
+        .map_err(RepositoryError::from)
+    }
+
+    async fn get_variant_stats(
+        &self,
+        broadcast_id: i64,
+        conversion_days: i32,
+    ) -> RepositoryResult<Vec<BroadcastVariantStatsRow>> {
+        sqlx::query_as!(
+            BroadcastVariantStatsRow,
+            r#"
+            SELECT
+                br.variant_id,
+                COUNT(*) as "recipients!",
+                COUNT(*) FILTER (WHERE br.status = 'sent') as "sent!",
+                COUNT(*) FILTER (
+                    WHERE br.status = 'sent' AND EXISTS (
+                        SELECT 1 FROM orders o
+                        WHERE o.customer_id = br.customer_id
+                            AND o.status IN ('paid', 'fulfilled')
+                            AND o.created_at >= br.sent_at
+                            AND o.created_at < br.sent_at + make_interval(days => $2)
+                    )
+                ) as "conversions!"
+            FROM broadcast_recipients br
+            WHERE br.broadcast_id = $1
+            GROUP BY br.variant_id
+            ORDER BY br.variant_id NULLS FIRST"#,
+            broadcast_id,
+            conversion_days
+        )
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(RepositoryError::from)
+    }
+
+    async fn get_recipients(
+        &self,
+        broadcast_id: i64,
+        query: BroadcastRecipientListQuery,
+    ) -> RepositoryResult<PaginatedResult<BroadcastRecipientRow>> {
+        let mut count_qb = QueryBuilder::new("SELECT COUNT(*) FROM ");
+        push_recipients_source(&mut count_qb, broadcast_id);
+        apply_filters(&mut count_qb, &query);
+        let total: i64 = count_qb.build_query_scalar().fetch_one(&*self.pool).await?;
+
+        let mut query_builder = QueryBuilder::new("SELECT * FROM ");
+        push_recipients_source(&mut query_builder, broadcast_id);
+        apply_list_query(&mut query_builder, &query);
+        let items = query_builder
+            .build_query_as::<BroadcastRecipientRow>()
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(into_paginated_result(&query, items, total, |row| row.id))
+    }
+}
+
+// Wrapped in a subquery so list filters can use the unqualified recipient columns
+fn push_recipients_source(qb: &mut QueryBuilder<'_, Postgres>, broadcast_id: i64) {
+    qb.push(
+        r#"(
+            SELECT
+                br.id, br.broadcast_id, br.customer_id, c.telegram_id, br.variant_id, br.status,
+                br.error_message, br.sent_at, br.created_at, br.updated_at
+            FROM broadcast_recipients br
+            JOIN customers c ON c.id = br.customer_id
+            WHERE br.broadcast_id = "#,
+    );
+    qb.push_bind(broadcast_id);
+    qb.push(") AS recipients");
 }
 
 #[cfg(test)]
@@ -237,23 +647,37 @@ mod tests {
             status,
             content_text: Some("Test content".to_string()),
             content_image_id: None,
+            content_entities: vec![],
+            content_media: vec![],
+            content_buttons: vec![],
             filters: Some(json!({"filters": [{"field": "balance", "op": "gt", "value": 100}]})),
             created_by,
             scheduled_for,
+            segment_id: None,
+            variants: vec![],
         };
         sqlx::query_as!(
             BroadcastRow,
             r#"
-            INSERT INTO broadcasts (status, content_text, content_image_id, filters, created_by, scheduled_for)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO broadcasts (
+                status, content_text, content_image_id, content_entities, content_media,
+                content_buttons, filters, segment_id, created_by, scheduled_for
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING 
-                id, status as "status: _", content_text, content_image_id, filters, statistics,
+                id, status as "status: _", content_text, content_image_id,
+                content_entities as "content_entities: _", content_media as "content_media: _",
+                content_buttons as "content_buttons: _", filters, segment_id, statistics,
                 created_by, scheduled_for, started_at, finished_at, created_at, updated_at
             "#,
             new_broadcast.status as _,
             new_broadcast.content_text,
             new_broadcast.content_image_id,
+            Json(&new_broadcast.content_entities) as _,
+            Json(&new_broadcast.content_media) as _,
+            Json(&new_broadcast.content_buttons) as _,
             new_broadcast.filters,
+            new_broadcast.segment_id,
             new_broadcast.created_by,
             new_broadcast.scheduled_for
         )
@@ -271,9 +695,14 @@ mod tests {
             status: BroadcastStatus::Pending,
             content_text: Some("Initial content".to_string()),
             content_image_id: None,
+            content_entities: vec![],
+            content_media: vec![],
+            content_buttons: vec![],
             filters: None,
             created_by: admin_user.id,
             scheduled_for: None,
+            segment_id: None,
+            variants: vec![],
         };
 
         let created_broadcast = repo.create(new_broadcast).await.unwrap();
@@ -288,6 +717,78 @@ mod tests {
         );
     }
 
+    #[sqlx::test]
+    async fn test_rich_content_round_trip(pool: PgPool) {
+        use shared_dtos::broadcast::{
+            BroadcastButton, BroadcastButtonAction, BroadcastMedia, BroadcastMediaKind,
+            BroadcastTextEntity, BroadcastTextEntityKind,
+        };
+
+        let repo = BroadcastRepository::new(Arc::new(pool.clone()));
+        let admin_user = create_test_admin_user(&pool, "test_admin_7").await;
+        let entities = vec![BroadcastTextEntity {
+            kind: BroadcastTextEntityKind::Bold,
+            offset: 0,
+            length: 4,
+            url: None,
+            language: None,
+        }];
+        let media = vec![BroadcastMedia {
+            kind: BroadcastMediaKind::Video,
+            image_id: None,
+            url: Some("https://example.com/promo.mp4".to_string()),
+        }];
+        let buttons = vec![vec![BroadcastButton {
+            text: "Open".to_string(),
+            action: BroadcastButtonAction::Product { product_id: 42 },
+        }]];
+
+        let created = repo
+            .create(NewBroadcast {
+                status: BroadcastStatus::Pending,
+                content_text: None,
+                content_image_id: None,
+                content_entities: vec![],
+                content_media: media.clone(),
+                content_buttons: vec![],
+                filters: None,
+                created_by: admin_user.id,
+                scheduled_for: None,
+                segment_id: None,
+                variants: vec![],
+            })
+            .await
+            .unwrap();
+        assert_eq!(created.content_media.0, media);
+
+        let updated = repo
+            .update(
+                created.id,
+                UpdateBroadcast {
+                    status: None,
+                    content_text: Some(Some("Sale".to_string())),
+                    content_image_id: None,
+                    content_entities: Some(entities.clone()),
+                    content_media: None,
+                    content_buttons: Some(buttons.clone()),
+                    filters: None,
+                    scheduled_for: None,
+                    statistics: None,
+                    started_at: None,
+                    finished_at: None,
+                    expected_status: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.content_entities.0, entities);
+        assert_eq!(updated.content_buttons.0, buttons);
+
+        let fetched = repo.get_by_id(created.id).await.unwrap();
+        assert_eq!(fetched.content_media.0, media);
+        assert_eq!(fetched.content_buttons.0, buttons);
+    }
+
     #[sqlx::test]
     async fn test_update_broadcast(pool: PgPool) {
         let repo = BroadcastRepository::new(Arc::new(pool.clone()));
@@ -299,11 +800,15 @@ mod tests {
             status: Some(BroadcastStatus::Completed),
             content_text: Some(Some("Updated content".to_string())),
             content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
             filters: Some(Some(json!({"filters": "updated"}))),
             scheduled_for: None,
             finished_at: Some(Some(Utc::now())),
             started_at: Some(Some(Utc::now())),
             statistics: Some(Some(json!({"statistics": "updated"}))),
+            expected_status: None,
         };
 
         let updated_broadcast = repo.update(broadcast.id, update_payload).await.unwrap();
@@ -393,4 +898,317 @@ mod tests {
                         && b.scheduled_for.unwrap() < Utc::now()))
         );
     }
+
+    async fn create_test_customer(pool: &PgPool, telegram_id: i64) -> i64 {
+        sqlx::query_scalar!(
+            "INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot) VALUES ($1, 1, 1) RETURNING id",
+            telegram_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_start_materializes_recipients(pool: PgPool) {
+        let repo = BroadcastRepository::new(Arc::new(pool.clone()));
+        let admin_user = create_test_admin_user(&pool, "test_admin_5").await;
+        let broadcast =
+            create_test_broadcast(&pool, admin_user.id, BroadcastStatus::Pending, None).await;
+        create_test_customer(&pool, 5001).await;
+        create_test_customer(&pool, 5002).await;
+        create_test_customer(&pool, 5003).await;
+
+        let (started, recipients) = repo
+            .start(broadcast.id, &CustomerListQuery::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(started.status, BroadcastStatus::InProgress);
+        assert!(started.started_at.is_some());
+        assert_eq!(recipients, 3);
+
+        // Already started
+        let restarted = repo
+            .start(broadcast.id, &CustomerListQuery::default())
+            .await
+            .unwrap();
+        assert!(restarted.is_none());
+
+        let in_progress = repo.get_in_progress_broadcasts().await.unwrap();
+        assert_eq!(in_progress.len(), 1);
+        assert_eq!(in_progress[0].id, broadcast.id);
+    }
+
+    #[sqlx::test]
+    async fn test_recipient_delivery_tracking(pool: PgPool) {
+        let repo = BroadcastRepository::new(Arc::new(pool.clone()));
+        let admin_user = create_test_admin_user(&pool, "test_admin_6").await;
+        let broadcast =
+            create_test_broadcast(&pool, admin_user.id, BroadcastStatus::Pending, None).await;
+        for telegram_id in 6001..6004 {
+            create_test_customer(&pool, telegram_id).await;
+        }
+        repo.start(broadcast.id, &CustomerListQuery::default())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let batch = repo.get_pending_recipients(broadcast.id, 2).await.unwrap();
+        assert_eq!(batch.len(), 2);
+
+        repo.update_recipient_status(batch[0].id, BroadcastRecipientStatus::Sent, None)
+            .await
+            .unwrap();
+        repo.update_recipient_status(
+            batch[1].id,
+            BroadcastRecipientStatus::Failed,
+            Some("bot unavailable".to_string()),
+        )
+        .await
+        .unwrap();
+
+        // Handled recipients are never returned again
+        let remaining = repo.get_pending_recipients(broadcast.id, 10).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert!(remaining.iter().all(|r| batch.iter().all(|b| b.id != r.id)));
+
+        let stats = repo.get_recipient_stats(broadcast.id).await.unwrap();
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.pending, 1);
+        assert_eq!(stats.sent, 1);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.blocked, 0);
+
+        use crate::models::broadcast::BroadcastRecipientFilterFields;
+        let query = BroadcastRecipientListQuery {
+            filters: vec![Filter {
+                field: BroadcastRecipientFilterFields::Status,
+                op: Operator::Eq,
+                value: FilterValue::Scalar(ScalarValue::Text("failed".to_string())),
+            }],
+            ..Default::default()
+        };
+        let failed = repo.get_recipients(broadcast.id, query).await.unwrap();
+        assert_eq!(failed.total, 1);
+        assert_eq!(failed.items[0].id, batch[1].id);
+        assert_eq!(
+            failed.items[0].error_message.as_deref(),
+            Some("bot unavailable")
+        );
+
+        let sent = repo
+            .get_recipients(broadcast.id, BroadcastRecipientListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(sent.total, 3);
+        assert!(
+            sent.items
+                .iter()
+                .filter(|r| r.status == BroadcastRecipientStatus::Sent)
+                .all(|r| r.sent_at.is_some())
+        );
+    }
+
+    async fn create_test_bot(pool: &PgPool) -> i64 {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO bots (
+                owner_id, token, username, type, is_active, is_primary, referral_percentage, created_by
+            )
+            VALUES (NULL, 'broadcast_bot', 'broadcast_bot', 'main', true, false, 0.1, 1)
+            RETURNING id
+            "#
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn create_test_order(pool: &PgPool, customer_id: i64, bot_id: i64, status: &str) {
+        sqlx::query!(
+            "INSERT INTO orders (customer_id, amount, status, bot_id) VALUES ($1, 10, $2, $3)",
+            customer_id,
+            status,
+            bot_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_variants_split_audience_and_track_conversions(pool: PgPool) {
+        use crate::models::broadcast::NewBroadcastVariant;
+
+        let repo = BroadcastRepository::new(Arc::new(pool.clone()));
+        let admin_user = create_test_admin_user(&pool, "test_admin_8").await;
+        let broadcast = repo
+            .create(NewBroadcast {
+                status: BroadcastStatus::Pending,
+                content_text: Some("Variant A".to_string()),
+                content_image_id: None,
+                content_entities: vec![],
+                content_media: vec![],
+                content_buttons: vec![],
+                filters: None,
+                created_by: admin_user.id,
+                scheduled_for: None,
+                segment_id: None,
+                variants: vec![NewBroadcastVariant {
+                    name: "B".to_string(),
+                    content_text: Some("Variant B".to_string()),
+                    content_image_id: None,
+                    content_entities: vec![],
+                    content_media: vec![],
+                    content_buttons: vec![],
+                }],
+            })
+            .await
+            .unwrap();
+        let variants = repo.get_variants(broadcast.id).await.unwrap();
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].content().text.as_deref(), Some("Variant B"));
+
+        for telegram_id in 8001..8005 {
+            create_test_customer(&pool, telegram_id).await;
+        }
+        repo.start(broadcast.id, &CustomerListQuery::default())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Audience is split evenly between the base content and the variant
+        let recipients = repo.get_pending_recipients(broadcast.id, 10).await.unwrap();
+        assert_eq!(recipients.len(), 4);
+        let with_variant = recipients
+            .iter()
+            .filter(|r| r.variant_id == Some(variants[0].id))
+            .count();
+        assert_eq!(with_variant, 2);
+
+        for recipient in &recipients {
+            repo.update_recipient_status(recipient.id, BroadcastRecipientStatus::Sent, None)
+                .await
+                .unwrap();
+        }
+        let bot_id = create_test_bot(&pool).await;
+        let converted = recipients.iter().find(|r| r.variant_id.is_some()).unwrap();
+        create_test_order(&pool, converted.customer_id, bot_id, "paid").await;
+        let not_paid = recipients.iter().find(|r| r.variant_id.is_none()).unwrap();
+        create_test_order(&pool, not_paid.customer_id, bot_id, "created").await;
+
+        let stats = repo.get_variant_stats(broadcast.id, 7).await.unwrap();
+        assert_eq!(
+            stats,
+            vec![
+                BroadcastVariantStatsRow {
+                    variant_id: None,
+                    recipients: 2,
+                    sent: 2,
+                    conversions: 0,
+                },
+                BroadcastVariantStatsRow {
+                    variant_id: Some(variants[0].id),
+                    recipients: 2,
+                    sent: 2,
+                    conversions: 1,
+                },
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_delivery_reports_and_undelivered_recipients(pool: PgPool) {
+        let repo = BroadcastRepository::new(Arc::new(pool.clone()));
+        let admin_user = create_test_admin_user(&pool, "test_admin_reports").await;
+        let broadcast =
+            create_test_broadcast(&pool, admin_user.id, BroadcastStatus::Pending, None).await;
+        for telegram_id in 7001..7004 {
+            create_test_customer(&pool, telegram_id).await;
+        }
+        repo.start(broadcast.id, &CustomerListQuery::default())
+            .await
+            .unwrap()
+            .unwrap();
+        let batch = repo.get_pending_recipients(broadcast.id, 10).await.unwrap();
+        for recipient in &batch {
+            repo.update_recipient_status(recipient.id, BroadcastRecipientStatus::Dispatched, None)
+                .await
+                .unwrap();
+        }
+
+        repo.report_recipient_delivery(batch[0].id, BroadcastRecipientStatus::Sent, None)
+            .await
+            .unwrap();
+        repo.report_recipient_delivery(
+            batch[1].id,
+            BroadcastRecipientStatus::Blocked,
+            Some("Forbidden: bot was blocked by the user".to_string()),
+        )
+        .await
+        .unwrap();
+        // Only dispatched recipients take a report
+        let err = repo
+            .report_recipient_delivery(batch[0].id, BroadcastRecipientStatus::Failed, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::OptimisticLockViolation));
+
+        let not_yet = repo
+            .fail_undelivered_recipients(broadcast.id, Utc::now() - Duration::minutes(10), "late")
+            .await
+            .unwrap();
+        assert_eq!(not_yet, 0);
+        let failed = repo
+            .fail_undelivered_recipients(broadcast.id, Utc::now() + Duration::minutes(1), "late")
+            .await
+            .unwrap();
+        assert_eq!(failed, 1);
+
+        let stats = repo.get_recipient_stats(broadcast.id).await.unwrap();
+        assert_eq!(stats.sent, 1);
+        assert_eq!(stats.blocked, 1);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.dispatched, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_update_with_expected_status(pool: PgPool) {
+        let repo = BroadcastRepository::new(Arc::new(pool.clone()));
+        let admin_user = create_test_admin_user(&pool, "test_admin_expected").await;
+        let broadcast =
+            create_test_broadcast(&pool, admin_user.id, BroadcastStatus::Paused, None).await;
+        let complete = || UpdateBroadcast {
+            status: Some(BroadcastStatus::Completed),
+            content_text: None,
+            content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
+            filters: None,
+            scheduled_for: None,
+            statistics: None,
+            started_at: None,
+            finished_at: None,
+            expected_status: Some(vec![BroadcastStatus::InProgress]),
+        };
+
+        let err = repo.update(broadcast.id, complete()).await.unwrap_err();
+        assert!(matches!(err, RepositoryError::OptimisticLockViolation));
+        assert_eq!(
+            repo.get_by_id(broadcast.id).await.unwrap().status,
+            BroadcastStatus::Paused
+        );
+
+        sqlx::query!(
+            "UPDATE broadcasts SET status = 'in_progress' WHERE id = $1",
+            broadcast.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let updated = repo.update(broadcast.id, complete()).await.unwrap();
+        assert_eq!(updated.status, BroadcastStatus::Completed);
+    }
 }
@@ -8,7 +8,9 @@ use crate::{
     infrastructure::lib::query::{apply_filters, apply_list_query},
     models::{
         common::PaginatedResult,
-        customer::{CustomerListQuery, CustomerRow, NewCustomer, UpdateCustomer},
+        customer::{
+            CustomerListQuery, CustomerRow, NewCustomer, UpdateCustomer, customer_list_source,
+        },
     },
 };
 
@@ -42,19 +44,24 @@ impl CustomerRepositoryTrait for CustomerRepository {
         &self,
         query: CustomerListQuery,
     ) -> RepositoryResult<PaginatedResult<CustomerRow>> {
+        let source = customer_list_source(&query);
         let mut count_qb: QueryBuilder<Postgres> =
-            QueryBuilder::new("SELECT COUNT(*) FROM customers");
+            QueryBuilder::new(format!("SELECT COUNT(*) FROM {source}"));
         apply_filters(&mut count_qb, &query);
 
         let count_query = count_qb.build_query_scalar();
         let total: i64 = count_query.fetch_one(&*self.pool).await?;
 
         let mut query_builder: QueryBuilder<Postgres> =
-            QueryBuilder::new("SELECT * FROM customers");
+            QueryBuilder::new(format!("SELECT customers.* FROM {source}"));
         apply_list_query(&mut query_builder, &query);
         let items_query = query_builder.build_query_as::<CustomerRow>();
         let items = items_query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
     }
 
     async fn create(&self, customer: NewCustomer) -> RepositoryResult<CustomerRow> {
@@ -63,7 +70,10 @@ impl CustomerRepositoryTrait for CustomerRepository {
             r#"
             INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot)
             VALUES ($1, $2, $3)
-            RETURNING *
+            RETURNING
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
             "#,
             customer.telegram_id,
             customer.registered_with_bot,
@@ -76,9 +86,20 @@ impl CustomerRepositoryTrait for CustomerRepository {
     }
 
     async fn get_by_id(&self, id: i64) -> RepositoryResult<CustomerRow> {
-        let result = sqlx::query_as!(CustomerRow, "SELECT * FROM customers WHERE id = $1", id)
-            .fetch_one(&*self.pool)
-            .await?;
+        let result = sqlx::query_as!(
+            CustomerRow,
+            r#"
+            SELECT
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
+            FROM customers
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(&*self.pool)
+        .await?;
 
         Ok(result)
     }
@@ -86,7 +107,14 @@ impl CustomerRepositoryTrait for CustomerRepository {
     async fn get_by_telegram_id(&self, id: i64) -> RepositoryResult<CustomerRow> {
         let result = sqlx::query_as!(
             CustomerRow,
-            "SELECT * FROM customers WHERE telegram_id = $1",
+            r#"
+            SELECT
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
+            FROM customers
+            WHERE telegram_id = $1
+            "#,
             id
         )
         .fetch_one(&*self.pool)
@@ -122,6 +150,11 @@ impl CustomerRepositoryTrait for CustomerRepository {
             query_builder.push_bind(is_blocked);
         }
 
+        if let Some(display_currency) = customer.display_currency {
+            query_builder.push(", display_currency = ");
+            query_builder.push_bind(display_currency);
+        }
+
         if let Some(blocked_until) = customer.blocked_until {
             query_builder.push(", blocked_until = ");
             if let Some(blocked_until) = blocked_until {
@@ -146,7 +179,14 @@ impl CustomerRepositoryTrait for CustomerRepository {
     async fn get_list_by_ids(&self, ids: &[i64]) -> RepositoryResult<Vec<CustomerRow>> {
         let query = sqlx::query_as!(
             CustomerRow,
-            "SELECT * FROM customers WHERE id = ANY($1)",
+            r#"
+            SELECT
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
+            FROM customers
+            WHERE id = ANY($1)
+            "#,
             ids
         )
         .fetch_all(&*self.pool)
@@ -160,6 +200,7 @@ mod tests {
     use super::*;
     use crate::models::customer::CustomerListQuery;
     use chrono::Utc;
+    use shared_dtos::currency::Currency;
     use sqlx::PgPool;
 
     async fn create_test_customer(
@@ -172,7 +213,10 @@ mod tests {
             r#"
             INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot)
             VALUES ($1, $2, $3)
-            RETURNING *
+            RETURNING
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
             "#,
             telegram_id,
             registered_with_bot,
@@ -229,6 +273,7 @@ mod tests {
             last_seen_with_bot: Some(2),
             last_seen_at: Some(updated_at),
             blocked_until: None,
+            display_currency: Some(Currency::Usd),
         };
 
         let _updated_customer = repo.update(initial_customer.id, update_data).await.unwrap();
@@ -239,6 +284,7 @@ mod tests {
         assert_eq!(fetched_customer.id, initial_customer.id);
         assert!(fetched_customer.is_blocked);
         assert!(fetched_customer.bot_is_blocked_by_user);
+        assert_eq!(fetched_customer.display_currency, Some(Currency::Usd));
         assert!(fetched_customer.has_passed_captcha);
         assert_eq!(fetched_customer.last_seen_with_bot, 2);
         assert_eq!(
@@ -278,4 +324,64 @@ mod tests {
         assert!(customers.iter().any(|c| c.id == customer1.id));
         assert!(customers.iter().any(|c| c.id == customer2.id));
     }
+
+    #[sqlx::test]
+    async fn test_get_list_filters_by_purchase_aggregates(pool: PgPool) {
+        use crate::models::{
+            common::Filter,
+            customer::{CustomerFilterFields, CustomerOrderFields},
+        };
+        use shared_dtos::list_query::{FilterValue, Operator, OrderDir, ScalarValue};
+
+        let repo = CustomerRepository::new(Arc::new(pool.clone()));
+        let buyer = create_test_customer(&pool, 3001, 1).await;
+        let big_buyer = create_test_customer(&pool, 3002, 1).await;
+        let cancelled = create_test_customer(&pool, 3003, 1).await;
+        create_test_customer(&pool, 3004, 1).await;
+        let bot_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO bots (
+                owner_id, token, username, type, is_active, is_primary, referral_percentage, created_by
+            )
+            VALUES (NULL, 'audience_bot', 'audience_bot', 'main', true, false, 0.1, 1)
+            RETURNING id
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        for (customer_id, amount, status) in [
+            (buyer.id, 100, "paid"),
+            (big_buyer.id, 300, "fulfilled"),
+            (big_buyer.id, 200, "paid"),
+            (cancelled.id, 900, "cancelled"),
+        ] {
+            sqlx::query!(
+                "INSERT INTO orders (customer_id, amount, status, bot_id) VALUES ($1, $2, $3, $4)",
+                customer_id,
+                rust_decimal::Decimal::from(amount),
+                status,
+                bot_id
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let query = CustomerListQuery {
+            filters: vec![Filter {
+                field: CustomerFilterFields::OrdersCount,
+                op: Operator::Gt,
+                value: FilterValue::Scalar(ScalarValue::Int(0)),
+            }],
+            order_by: Some(CustomerOrderFields::OrdersTotal),
+            order_dir: OrderDir::Desc,
+            ..Default::default()
+        };
+        let customers = repo.get_list(query).await.unwrap();
+
+        assert_eq!(customers.total, 2);
+        let ids: Vec<i64> = customers.items.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![big_buyer.id, buyer.id]);
+    }
 }
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::{
+    errors::repository::{RepositoryError, RepositoryResult},
+    models::customer_segment::{CustomerSegmentRow, NewCustomerSegment, UpdateCustomerSegment},
+};
+
+#[async_trait]
+pub trait CustomerSegmentRepositoryTrait {
+    async fn get_list(&self) -> RepositoryResult<Vec<CustomerSegmentRow>>;
+    async fn create(&self, segment: NewCustomerSegment) -> RepositoryResult<CustomerSegmentRow>;
+    async fn get_by_id(&self, id: i64) -> RepositoryResult<CustomerSegmentRow>;
+    async fn update(
+        &self,
+        id: i64,
+        segment: UpdateCustomerSegment,
+    ) -> RepositoryResult<CustomerSegmentRow>;
+    async fn delete(&self, id: i64) -> RepositoryResult<()>;
+}
+
+#[derive(Clone)]
+pub struct CustomerSegmentRepository {
+    pool: Arc<PgPool>,
+}
+
+impl CustomerSegmentRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CustomerSegmentRepositoryTrait for CustomerSegmentRepository {
+    async fn get_list(&self) -> RepositoryResult<Vec<CustomerSegmentRow>> {
+        let result = sqlx::query_as!(
+            CustomerSegmentRow,
+            "SELECT * FROM customer_segments ORDER BY name ASC"
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn create(&self, segment: NewCustomerSegment) -> RepositoryResult<CustomerSegmentRow> {
+        let result = sqlx::query_as!(
+            CustomerSegmentRow,
+            r#"
+            INSERT INTO customer_segments (name, description, filters, created_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            segment.name,
+            segment.description,
+            segment.filters,
+            segment.created_by
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    async fn get_by_id(&self, id: i64) -> RepositoryResult<CustomerSegmentRow> {
+        let result = sqlx::query_as!(
+            CustomerSegmentRow,
+            "SELECT * FROM customer_segments WHERE id = $1",
+            id
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        segment: UpdateCustomerSegment,
+    ) -> RepositoryResult<CustomerSegmentRow> {
+        let mut query_builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("UPDATE customer_segments SET name = COALESCE(");
+
+        query_builder.push_bind(segment.name);
+        query_builder.push(", name)");
+
+        if let Some(description) = segment.description {
+            query_builder.push(", description = ");
+            query_builder.push_bind(description);
+        }
+
+        if let Some(filters) = segment.filters {
+            query_builder.push(", filters = ");
+            query_builder.push_bind(filters);
+        }
+
+        query_builder.push(" WHERE id = ");
+        query_builder.push_bind(id);
+        query_builder.push(" RETURNING *");
+
+        query_builder
+            .build_query_as::<CustomerSegmentRow>()
+            .fetch_one(&*self.pool)
+            .await
+            .map_err(RepositoryError::from)
+    }
+
+    async fn delete(&self, id: i64) -> RepositoryResult<()> {
+        sqlx::query!("DELETE FROM customer_segments WHERE id = $1", id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    fn new_segment(name: &str) -> NewCustomerSegment {
+        NewCustomerSegment {
+            name: name.to_string(),
+            description: Some("Customers with money to spend".to_string()),
+            filters: json!({"filters": [{"field": "balance", "op": "gt", "value": 0}]}),
+            created_by: 1,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_create_update_and_delete_segment(pool: PgPool) {
+        let repo = CustomerSegmentRepository::new(Arc::new(pool));
+
+        let created = repo.create(new_segment("Positive balance")).await.unwrap();
+        assert_eq!(created.name, "Positive balance");
+        assert_eq!(repo.get_list().await.unwrap().len(), 1);
+
+        let updated = repo
+            .update(
+                created.id,
+                UpdateCustomerSegment {
+                    name: None,
+                    description: Some(None),
+                    filters: Some(json!({"filters": []})),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.name, "Positive balance");
+        assert_eq!(updated.description, None);
+        assert_eq!(updated.filters, json!({"filters": []}));
+
+        repo.delete(created.id).await.unwrap();
+        assert!(matches!(
+            repo.get_by_id(created.id).await,
+            Err(RepositoryError::NotFound(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_segment_name_is_unique(pool: PgPool) {
+        let repo = CustomerSegmentRepository::new(Arc::new(pool));
+
+        repo.create(new_segment("Buyers")).await.unwrap();
+        let result = repo.create(new_segment("Buyers")).await;
+        assert!(matches!(result, Err(RepositoryError::UniqueViolation(_))));
+    }
+}
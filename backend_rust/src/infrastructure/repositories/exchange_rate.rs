@@ -0,0 +1,131 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use shared_dtos::currency::Currency;
+use sqlx::PgPool;
+
+use crate::{errors::repository::RepositoryResult, models::exchange_rate::ExchangeRateRow};
+
+#[async_trait]
+pub trait ExchangeRateRepositoryTrait {
+    async fn get_for_base(&self, base: Currency) -> RepositoryResult<Vec<ExchangeRateRow>>;
+    /// Replaces rates of the given currencies; rates missing from `rates` are kept
+    async fn upsert(
+        &self,
+        base: Currency,
+        rates: &HashMap<Currency, Decimal>,
+        source: &str,
+    ) -> RepositoryResult<()>;
+}
+
+#[derive(Clone)]
+pub struct ExchangeRateRepository {
+    pool: Arc<PgPool>,
+}
+
+impl ExchangeRateRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ExchangeRateRepositoryTrait for ExchangeRateRepository {
+    async fn get_for_base(&self, base: Currency) -> RepositoryResult<Vec<ExchangeRateRow>> {
+        let result = sqlx::query_as!(
+            ExchangeRateRow,
+            r#"
+            SELECT
+                base_currency as "base_currency: _",
+                currency as "currency: _",
+                rate,
+                source,
+                updated_at
+            FROM exchange_rates
+            WHERE base_currency = $1
+            ORDER BY currency
+            "#,
+            base as Currency
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn upsert(
+        &self,
+        base: Currency,
+        rates: &HashMap<Currency, Decimal>,
+        source: &str,
+    ) -> RepositoryResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for (currency, rate) in rates {
+            sqlx::query!(
+                r#"
+                INSERT INTO exchange_rates (base_currency, currency, rate, source, updated_at)
+                VALUES ($1, $2, $3, $4, NOW())
+                ON CONFLICT (base_currency, currency) DO UPDATE SET
+                    rate = EXCLUDED.rate,
+                    source = EXCLUDED.source,
+                    updated_at = EXCLUDED.updated_at
+                "#,
+                base as Currency,
+                *currency as Currency,
+                rate,
+                source
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_upsert_replaces_rates_per_base(pool: PgPool) {
+        let repo = ExchangeRateRepository::new(Arc::new(pool));
+
+        repo.upsert(
+            Currency::Rub,
+            &HashMap::from([(Currency::Usd, dec!(0.011)), (Currency::Eur, dec!(0.0095))]),
+            "static",
+        )
+        .await
+        .unwrap();
+        repo.upsert(
+            Currency::Rub,
+            &HashMap::from([(Currency::Usd, dec!(0.0125))]),
+            "cbr",
+        )
+        .await
+        .unwrap();
+        repo.upsert(
+            Currency::Usd,
+            &HashMap::from([(Currency::Rub, dec!(80))]),
+            "cbr",
+        )
+        .await
+        .unwrap();
+
+        let rates = repo.get_for_base(Currency::Rub).await.unwrap();
+        let rates: Vec<_> = rates
+            .iter()
+            .map(|r| (r.currency, r.rate, r.source.as_str()))
+            .collect();
+        assert_eq!(
+            rates,
+            vec![
+                (Currency::Eur, dec!(0.0095), "static"),
+                (Currency::Usd, dec!(0.0125), "cbr"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    errors::repository::RepositoryResult,
+    models::gateway_attempt::{GatewayAttemptStatsRow, NewGatewayAttempt},
+};
+
+#[async_trait]
+pub trait GatewayAttemptRepositoryTrait {
+    async fn create(&self, attempt: NewGatewayAttempt) -> RepositoryResult<()>;
+    /// Per-gateway stats of attempts made since `since`, gateways without attempts are omitted
+    async fn get_stats(
+        &self,
+        since: DateTime<Utc>,
+    ) -> RepositoryResult<Vec<GatewayAttemptStatsRow>>;
+    /// Deletes attempts made before `before`, returns how many were removed
+    async fn delete_older_than(&self, before: DateTime<Utc>) -> RepositoryResult<u64>;
+}
+
+#[derive(Clone)]
+pub struct GatewayAttemptRepository {
+    pool: Arc<PgPool>,
+}
+
+impl GatewayAttemptRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl GatewayAttemptRepositoryTrait for GatewayAttemptRepository {
+    async fn create(&self, attempt: NewGatewayAttempt) -> RepositoryResult<()> {
+        sqlx::query!(
+            "INSERT INTO gateway_attempts (gateway, outcome, latency_ms) VALUES ($1, $2, $3)",
+            attempt.gateway as _,
+            attempt.outcome as _,
+            attempt.latency_ms
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_stats(
+        &self,
+        since: DateTime<Utc>,
+    ) -> RepositoryResult<Vec<GatewayAttemptStatsRow>> {
+        let result = sqlx::query_as!(
+            GatewayAttemptStatsRow,
+            r#"
+            SELECT
+                gateway as "gateway: _",
+                COUNT(*) as "attempts!",
+                COUNT(*) FILTER (WHERE outcome = 'success') as "successes!",
+                COUNT(*) FILTER (WHERE outcome <> 'success') as "failures!",
+                COUNT(*) FILTER (WHERE outcome = 'no_requisites') as "no_requisites!",
+                AVG(latency_ms)::FLOAT8 as avg_latency_ms,
+                PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY latency_ms) as p95_latency_ms,
+                (ARRAY_AGG(outcome ORDER BY created_at DESC, id DESC))[1] as "last_outcome!: _",
+                MAX(created_at) as "last_attempt_at!",
+                MAX(created_at) FILTER (WHERE outcome = 'success') as last_success_at,
+                MAX(created_at) FILTER (WHERE outcome <> 'success') as last_failure_at
+            FROM gateway_attempts
+            WHERE created_at >= $1
+            GROUP BY gateway
+            ORDER BY gateway
+            "#,
+            since
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn delete_older_than(&self, before: DateTime<Utc>) -> RepositoryResult<u64> {
+        let result = sqlx::query!("DELETE FROM gateway_attempts WHERE created_at < $1", before)
+            .execute(&*self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use shared_dtos::invoice::PaymentSystem;
+
+    use super::*;
+    use crate::models::gateway_attempt::GatewayAttemptOutcome;
+
+    async fn attempt(
+        repo: &GatewayAttemptRepository,
+        gateway: PaymentSystem,
+        outcome: GatewayAttemptOutcome,
+        latency_ms: i32,
+    ) {
+        repo.create(NewGatewayAttempt {
+            gateway,
+            outcome,
+            latency_ms,
+        })
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_get_stats(pool: PgPool) {
+        let pool = Arc::new(pool);
+        let repo = GatewayAttemptRepository::new(pool.clone());
+
+        attempt(
+            &repo,
+            PaymentSystem::PlatformCard,
+            GatewayAttemptOutcome::Success,
+            100,
+        )
+        .await;
+        attempt(
+            &repo,
+            PaymentSystem::PlatformCard,
+            GatewayAttemptOutcome::NoRequisites,
+            200,
+        )
+        .await;
+        attempt(
+            &repo,
+            PaymentSystem::PlatformCard,
+            GatewayAttemptOutcome::Error,
+            300,
+        )
+        .await;
+        attempt(
+            &repo,
+            PaymentSystem::PlatformSBP,
+            GatewayAttemptOutcome::Success,
+            50,
+        )
+        .await;
+
+        // Attempts outside the window are ignored
+        sqlx::query!(
+            "INSERT INTO gateway_attempts (gateway, outcome, latency_ms, created_at) VALUES ('mock', 'error', 10, NOW() - INTERVAL '2 hours')"
+        )
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+        let stats = repo
+            .get_stats(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(stats.len(), 2);
+
+        let card = stats
+            .iter()
+            .find(|s| s.gateway == PaymentSystem::PlatformCard)
+            .unwrap();
+        assert_eq!(card.attempts, 3);
+        assert_eq!(card.successes, 1);
+        assert_eq!(card.failures, 2);
+        assert_eq!(card.no_requisites, 1);
+        assert_eq!(card.avg_latency_ms, Some(200.0));
+        assert_eq!(card.last_outcome, GatewayAttemptOutcome::Error);
+        assert!(card.last_success_at.is_some());
+        assert_eq!(card.last_failure_at, Some(card.last_attempt_at));
+
+        let sbp = stats
+            .iter()
+            .find(|s| s.gateway == PaymentSystem::PlatformSBP)
+            .unwrap();
+        assert_eq!(sbp.attempts, 1);
+        assert_eq!(sbp.last_outcome, GatewayAttemptOutcome::Success);
+        assert!(sbp.last_failure_at.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_delete_older_than(pool: PgPool) {
+        let pool = Arc::new(pool);
+        let repo = GatewayAttemptRepository::new(pool.clone());
+
+        attempt(
+            &repo,
+            PaymentSystem::PlatformCard,
+            GatewayAttemptOutcome::Success,
+            100,
+        )
+        .await;
+        sqlx::query!(
+            "INSERT INTO gateway_attempts (gateway, outcome, latency_ms, created_at) VALUES ('mock', 'error', 10, NOW() - INTERVAL '40 days')"
+        )
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+        let deleted = repo
+            .delete_older_than(Utc::now() - Duration::days(30))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+
+        let stats = repo
+            .get_stats(Utc::now() - Duration::days(60))
+            .await
+            .unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].gateway, PaymentSystem::PlatformCard);
+    }
+}
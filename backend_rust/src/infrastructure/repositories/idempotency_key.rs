@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    errors::repository::RepositoryResult,
+    models::idempotency_key::{IdempotencyKeyRow, NewIdempotencyKey, StoredResponse},
+};
+
+#[async_trait]
+pub trait IdempotencyKeyRepositoryTrait {
+    /// Claims the key for a new request. Returns `None` when the key is already taken by a
+    /// live entry; expired entries and requests in progress since before `stale_before` are
+    /// taken over.
+    async fn claim(
+        &self,
+        key: NewIdempotencyKey,
+        stale_before: DateTime<Utc>,
+    ) -> RepositoryResult<Option<IdempotencyKeyRow>>;
+    async fn get(&self, scope: &str, key: &str) -> RepositoryResult<Option<IdempotencyKeyRow>>;
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        response: StoredResponse,
+    ) -> RepositoryResult<()>;
+    /// Drops a claimed key that has no stored response yet, so the request can be retried
+    async fn release(&self, scope: &str, key: &str) -> RepositoryResult<()>;
+    async fn delete_expired(&self) -> RepositoryResult<u64>;
+}
+
+#[derive(Clone)]
+pub struct IdempotencyKeyRepository {
+    pool: Arc<PgPool>,
+}
+
+impl IdempotencyKeyRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdempotencyKeyRepositoryTrait for IdempotencyKeyRepository {
+    async fn claim(
+        &self,
+        key: NewIdempotencyKey,
+        stale_before: DateTime<Utc>,
+    ) -> RepositoryResult<Option<IdempotencyKeyRow>> {
+        let result = sqlx::query_as!(
+            IdempotencyKeyRow,
+            r#"
+            INSERT INTO idempotency_keys (scope, key, request_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (scope, key) DO UPDATE SET
+                request_hash = EXCLUDED.request_hash,
+                status_code = NULL,
+                response_body = NULL,
+                content_type = NULL,
+                created_at = NOW(),
+                expires_at = EXCLUDED.expires_at
+            WHERE idempotency_keys.expires_at <= NOW()
+                OR (idempotency_keys.status_code IS NULL AND idempotency_keys.created_at < $5)
+            RETURNING *
+            "#,
+            key.scope,
+            key.key,
+            key.request_hash,
+            key.expires_at,
+            stale_before
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn get(&self, scope: &str, key: &str) -> RepositoryResult<Option<IdempotencyKeyRow>> {
+        let result = sqlx::query_as!(
+            IdempotencyKeyRow,
+            "SELECT * FROM idempotency_keys WHERE scope = $1 AND key = $2",
+            scope,
+            key
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        response: StoredResponse,
+    ) -> RepositoryResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET status_code = $3, response_body = $4, content_type = $5
+            WHERE scope = $1 AND key = $2
+            "#,
+            scope,
+            key,
+            response.status_code,
+            response.body,
+            response.content_type
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> RepositoryResult<()> {
+        sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE scope = $1 AND key = $2 AND status_code IS NULL",
+            scope,
+            key
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> RepositoryResult<u64> {
+        let result = sqlx::query!("DELETE FROM idempotency_keys WHERE expires_at <= NOW()")
+            .execute(&*self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn new_key(key: &str, hash: &str, ttl: Duration) -> NewIdempotencyKey {
+        NewIdempotencyKey {
+            scope: "1".to_string(),
+            key: key.to_string(),
+            request_hash: hash.to_string(),
+            expires_at: Utc::now() + ttl,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_claim_complete_and_release(pool: PgPool) {
+        let repo = IdempotencyKeyRepository::new(Arc::new(pool));
+        let stale_before = Utc::now() - Duration::minutes(1);
+
+        let claimed = repo
+            .claim(new_key("k1", "h1", Duration::hours(1)), stale_before)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(claimed.status_code.is_none());
+
+        // A live key can't be claimed twice
+        assert!(
+            repo.claim(new_key("k1", "h1", Duration::hours(1)), stale_before)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        repo.complete(
+            "1",
+            "k1",
+            StoredResponse {
+                status_code: 201,
+                body: b"{\"id\":1}".to_vec(),
+                content_type: Some("application/json".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+        // Completed keys are never released
+        repo.release("1", "k1").await.unwrap();
+
+        let stored = repo.get("1", "k1").await.unwrap().unwrap();
+        assert_eq!(stored.status_code, Some(201));
+        assert_eq!(stored.response_body.as_deref(), Some(&b"{\"id\":1}"[..]));
+
+        // Same key in another scope is independent
+        let mut other = new_key("k1", "h1", Duration::hours(1));
+        other.scope = "2".to_string();
+        assert!(repo.claim(other, stale_before).await.unwrap().is_some());
+        repo.release("2", "k1").await.unwrap();
+        assert!(repo.get("2", "k1").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_claim_takes_over_expired_and_stale_keys(pool: PgPool) {
+        let repo = IdempotencyKeyRepository::new(Arc::new(pool));
+
+        repo.claim(new_key("expired", "h1", -Duration::hours(1)), Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        let reclaimed = repo
+            .claim(
+                new_key("expired", "h2", Duration::hours(1)),
+                Utc::now() - Duration::minutes(1),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reclaimed.request_hash, "h2");
+
+        // In progress for longer than the lock timeout
+        repo.claim(new_key("stuck", "h1", Duration::hours(1)), Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            repo.claim(
+                new_key("stuck", "h1", Duration::hours(1)),
+                Utc::now() + Duration::minutes(1)
+            )
+            .await
+            .unwrap()
+            .is_some()
+        );
+
+        repo.claim(new_key("old", "h1", -Duration::hours(1)), Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(repo.delete_expired().await.unwrap(), 1);
+        assert!(repo.get("1", "old").await.unwrap().is_none());
+    }
+}
@@ -50,7 +50,11 @@ impl ImageRepositoryTrait for ImageRepository {
         apply_list_query(&mut query_builder, query);
         let items_query = query_builder.build_query_as::<ImageRow>();
         let items = items_query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
     }
 
     async fn get_by_id(&self, id: Uuid) -> RepositoryResult<ImageRow> {
@@ -5,8 +5,8 @@ use shared_dtos::order::OrderStatus;
 use sqlx::{PgPool, Postgres, QueryBuilder};
 
 use crate::{
-    errors::repository::RepositoryResult,
-    infrastructure::lib::query::{apply_filters, apply_list_query},
+    errors::repository::{RepositoryError, RepositoryResult},
+    infrastructure::lib::query::{apply_filters, apply_list_query, into_paginated_result},
     models::{
         common::PaginatedResult,
         order::{NewOrder, OrderListQuery, OrderRow},
@@ -19,6 +19,14 @@ pub trait OrderRepositoryTrait {
     async fn get_for_customer(&self, customer_id: i64) -> RepositoryResult<Vec<OrderRow>>;
     async fn create(&self, order: NewOrder) -> RepositoryResult<OrderRow>;
     async fn get_by_id(&self, id: i64) -> RepositoryResult<OrderRow>;
+    /// Moves the order from `from` to `to`, stamping the matching timestamp. Fails with
+    /// an optimistic lock violation if the order is no longer in `from`.
+    async fn update_status(
+        &self,
+        id: i64,
+        from: OrderStatus,
+        to: OrderStatus,
+    ) -> RepositoryResult<OrderRow>;
 }
 
 #[derive(Clone)]
@@ -43,9 +51,9 @@ impl OrderRepositoryTrait for OrderRepository {
 
         let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM orders");
         apply_list_query(&mut query_builder, &query);
-        let query = query_builder.build_query_as::<OrderRow>();
-        let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        let items_query = query_builder.build_query_as::<OrderRow>();
+        let items = items_query.fetch_all(&*self.pool).await?;
+        Ok(into_paginated_result(&query, items, total, |row| row.id))
     }
 
     async fn get_for_customer(&self, customer_id: i64) -> RepositoryResult<Vec<OrderRow>> {
@@ -105,6 +113,36 @@ impl OrderRepositoryTrait for OrderRepository {
 
         Ok(result)
     }
+
+    async fn update_status(
+        &self,
+        id: i64,
+        from: OrderStatus,
+        to: OrderStatus,
+    ) -> RepositoryResult<OrderRow> {
+        let result = sqlx::query_as!(
+            OrderRow,
+            r#"
+            UPDATE orders
+            SET
+                status = $3,
+                paid_at = CASE WHEN $3 = 'paid' THEN NOW() ELSE paid_at END,
+                fulfilled_at = CASE WHEN $3 = 'fulfilled' THEN NOW() ELSE fulfilled_at END,
+                cancelled_at = CASE WHEN $3 = 'cancelled' THEN NOW() ELSE cancelled_at END
+            WHERE id = $1 AND status = $2
+            RETURNING
+                id, customer_id, amount, currency, status as "status: _", bot_id,
+                created_at, updated_at, paid_at, fulfilled_at, cancelled_at
+            "#,
+            id,
+            from as OrderStatus,
+            to as OrderStatus,
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        result.ok_or(RepositoryError::OptimisticLockViolation)
+    }
 }
 
 #[cfg(test)]
@@ -171,6 +209,41 @@ mod tests {
         assert_eq!(fetched_order.id, created_order.id);
     }
 
+    #[sqlx::test]
+    async fn test_update_status_is_conditional(pool: PgPool) {
+        let repo = OrderRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 54322).await;
+        let bot_id = create_test_bot(&pool, Some(customer_id), "order_bot_3", "order_bot_3").await;
+        let order = repo
+            .create(NewOrder {
+                customer_id,
+                amount: Decimal::from(100),
+                currency: "USD".to_string(),
+                status: OrderStatus::Created,
+                bot_id,
+                paid_at: None,
+                fulfilled_at: None,
+            })
+            .await
+            .unwrap();
+
+        let paid = repo
+            .update_status(order.id, OrderStatus::Created, OrderStatus::Paid)
+            .await
+            .unwrap();
+        assert_eq!(paid.status, OrderStatus::Paid);
+        assert!(paid.paid_at.is_some());
+        assert!(paid.cancelled_at.is_none());
+
+        let stale = repo
+            .update_status(order.id, OrderStatus::Created, OrderStatus::Cancelled)
+            .await;
+        assert!(matches!(
+            stale,
+            Err(RepositoryError::OptimisticLockViolation)
+        ));
+    }
+
     #[sqlx::test]
     async fn test_get_list_orders(pool: PgPool) {
         let repo = OrderRepository::new(Arc::new(pool.clone()));
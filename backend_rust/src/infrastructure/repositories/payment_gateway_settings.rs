@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared_dtos::invoice::PaymentSystem;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::{
+    errors::repository::{RepositoryError, RepositoryResult},
+    models::payment_gateway_settings::{PaymentGatewaySettingsRow, UpdatePaymentGatewaySettings},
+};
+
+#[async_trait]
+pub trait PaymentGatewaySettingsRepositoryTrait {
+    async fn get_all(&self) -> RepositoryResult<Vec<PaymentGatewaySettingsRow>>;
+    async fn get_by_gateway(
+        &self,
+        gateway: PaymentSystem,
+    ) -> RepositoryResult<PaymentGatewaySettingsRow>;
+    async fn update(
+        &self,
+        gateway: PaymentSystem,
+        update: UpdatePaymentGatewaySettings,
+    ) -> RepositoryResult<PaymentGatewaySettingsRow>;
+}
+
+#[derive(Clone)]
+pub struct PaymentGatewaySettingsRepository {
+    pool: Arc<PgPool>,
+}
+
+impl PaymentGatewaySettingsRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PaymentGatewaySettingsRepositoryTrait for PaymentGatewaySettingsRepository {
+    async fn get_all(&self) -> RepositoryResult<Vec<PaymentGatewaySettingsRow>> {
+        let result = sqlx::query_as!(
+            PaymentGatewaySettingsRow,
+            r#"
+            SELECT
+                gateway as "gateway: _", invoice_ttl_minutes, min_amount, max_amount, amount_step,
+                max_open_invoices, daily_deposit_limit, updated_at, currency as "currency: _"
+            FROM payment_gateway_settings
+            ORDER BY gateway
+            "#
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn get_by_gateway(
+        &self,
+        gateway: PaymentSystem,
+    ) -> RepositoryResult<PaymentGatewaySettingsRow> {
+        let result = sqlx::query_as!(
+            PaymentGatewaySettingsRow,
+            r#"
+            SELECT
+                gateway as "gateway: _", invoice_ttl_minutes, min_amount, max_amount, amount_step,
+                max_open_invoices, daily_deposit_limit, updated_at, currency as "currency: _"
+            FROM payment_gateway_settings
+            WHERE gateway = $1
+            "#,
+            gateway as _
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn update(
+        &self,
+        gateway: PaymentSystem,
+        update: UpdatePaymentGatewaySettings,
+    ) -> RepositoryResult<PaymentGatewaySettingsRow> {
+        let mut query_builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("UPDATE payment_gateway_settings SET updated_at = NOW()");
+
+        if let Some(invoice_ttl_minutes) = update.invoice_ttl_minutes {
+            query_builder.push(", invoice_ttl_minutes = ");
+            query_builder.push_bind(invoice_ttl_minutes);
+        }
+
+        if let Some(min_amount) = update.min_amount {
+            query_builder.push(", min_amount = ");
+            query_builder.push_bind(min_amount);
+        }
+
+        if let Some(max_amount) = update.max_amount {
+            query_builder.push(", max_amount = ");
+            query_builder.push_bind(max_amount);
+        }
+
+        if let Some(amount_step) = update.amount_step {
+            query_builder.push(", amount_step = ");
+            query_builder.push_bind(amount_step);
+        }
+
+        if let Some(max_open_invoices) = update.max_open_invoices {
+            query_builder.push(", max_open_invoices = ");
+            query_builder.push_bind(max_open_invoices);
+        }
+
+        if let Some(daily_deposit_limit) = update.daily_deposit_limit {
+            query_builder.push(", daily_deposit_limit = ");
+            query_builder.push_bind(daily_deposit_limit);
+        }
+
+        query_builder.push(" WHERE gateway = ");
+        query_builder.push_bind(gateway);
+        query_builder.push(" RETURNING *");
+
+        query_builder
+            .build_query_as::<PaymentGatewaySettingsRow>()
+            .fetch_one(&*self.pool)
+            .await
+            .map_err(RepositoryError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_defaults_and_update(pool: PgPool) {
+        let repo = PaymentGatewaySettingsRepository::new(Arc::new(pool));
+
+        // Every gateway gets a row with the old hard-coded behaviour
+        let all = repo.get_all().await.unwrap();
+        assert_eq!(all.len(), 3);
+        let card = repo
+            .get_by_gateway(PaymentSystem::PlatformCard)
+            .await
+            .unwrap();
+        assert_eq!(card.invoice_ttl_minutes, 1440);
+        assert_eq!(card.max_amount, dec!(1000000));
+        assert!(card.max_open_invoices.is_none());
+
+        let updated = repo
+            .update(
+                PaymentSystem::PlatformCard,
+                UpdatePaymentGatewaySettings {
+                    invoice_ttl_minutes: Some(30),
+                    min_amount: Some(dec!(100)),
+                    amount_step: Some(dec!(50)),
+                    max_open_invoices: Some(Some(2)),
+                    daily_deposit_limit: Some(Some(dec!(20000))),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.invoice_ttl_minutes, 30);
+        assert_eq!(updated.min_amount, dec!(100));
+        assert_eq!(updated.amount_step, dec!(50));
+        assert_eq!(updated.max_open_invoices, Some(2));
+        assert_eq!(updated.daily_deposit_limit, Some(dec!(20000)));
+        assert_eq!(updated.max_amount, dec!(1000000));
+
+        let cleared = repo
+            .update(
+                PaymentSystem::PlatformCard,
+                UpdatePaymentGatewaySettings {
+                    max_open_invoices: Some(None),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(cleared.max_open_invoices.is_none());
+        assert_eq!(cleared.daily_deposit_limit, Some(dec!(20000)));
+
+        // Other gateways are untouched
+        let sbp = repo
+            .get_by_gateway(PaymentSystem::PlatformSBP)
+            .await
+            .unwrap();
+        assert_eq!(sbp.invoice_ttl_minutes, 1440);
+    }
+}
@@ -2,18 +2,27 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use shared_dtos::invoice::InvoiceStatus;
-use sqlx::{PgPool, Postgres, QueryBuilder};
+use rust_decimal::Decimal;
+use shared_dtos::{
+    currency::Currency,
+    invoice::{InvoiceStatus, PaymentDetails, PaymentSystem},
+};
+use sqlx::{PgExecutor, PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
 use crate::{
     errors::repository::{RepositoryError, RepositoryResult},
-    infrastructure::lib::query::{apply_filters, apply_list_query},
+    infrastructure::{
+        lib::query::{apply_filters, apply_list_query, into_paginated_result},
+        repositories::transaction::insert_transaction,
+    },
     models::{
         common::PaginatedResult,
         payment_invoice::{
-            NewPaymentInvoice, PaymentInvoiceListQuery, PaymentInvoiceRow, UpdatePaymentInvoice,
+            CustomerGatewayUsage, InvoiceLimitExceeded, InvoiceLimits, NewPaymentInvoice,
+            PaymentInvoiceListQuery, PaymentInvoiceRow, UpdatePaymentInvoice,
         },
+        transaction::NewTransaction,
     },
 };
 
@@ -27,20 +36,56 @@ pub trait PaymentInvoiceRepositoryTrait {
         &self,
         payment_invoice: NewPaymentInvoice,
     ) -> RepositoryResult<PaymentInvoiceRow>;
+    /// Inserts the invoice if the customer's usage of its gateway still fits `limits`.
+    /// Concurrent creations for one customer are serialized, so they can't all pass the check.
+    async fn create_within_limits(
+        &self,
+        payment_invoice: NewPaymentInvoice,
+        limits: InvoiceLimits,
+    ) -> RepositoryResult<Result<PaymentInvoiceRow, InvoiceLimitExceeded>>;
+    /// Fills in the gateway order of an invoice reserved by `create_within_limits`
+    async fn attach_gateway_order(
+        &self,
+        id: i64,
+        gateway_invoice_id: String,
+        payment_details: PaymentDetails,
+        amount_in_usdt: Decimal,
+    ) -> RepositoryResult<PaymentInvoiceRow>;
     async fn update(
         &self,
         id: i64,
         payment_invoice: UpdatePaymentInvoice,
     ) -> RepositoryResult<PaymentInvoiceRow>;
+    /// Moves the invoice from `expected_status` to completed and records its deposit in
+    /// the same transaction, so a paid invoice is never left without its balance credit
+    async fn complete_with_deposit(
+        &self,
+        id: i64,
+        expected_status: InvoiceStatus,
+        finished_at: DateTime<Utc>,
+        deposit: NewTransaction,
+    ) -> RepositoryResult<PaymentInvoiceRow>;
     async fn get_by_id(&self, id: i64) -> RepositoryResult<PaymentInvoiceRow>;
     async fn get_by_order_id(&self, order_id: Uuid) -> RepositoryResult<PaymentInvoiceRow>;
+    async fn get_by_gateway_invoice_id(
+        &self,
+        gateway: PaymentSystem,
+        gateway_invoice_id: &str,
+    ) -> RepositoryResult<PaymentInvoiceRow>;
     async fn get_for_customer(&self, customer_id: i64) -> RepositoryResult<Vec<PaymentInvoiceRow>>;
-    async fn expire_old_invoices(&self) -> RepositoryResult<u64>;
+    /// Expires overdue pending invoices and returns them
+    async fn expire_old_invoices(&self) -> RepositoryResult<Vec<PaymentInvoiceRow>>;
     async fn get_pending_invoices(
         &self,
         older_than: DateTime<Utc>,
     ) -> RepositoryResult<Vec<PaymentInvoiceRow>>;
     async fn mark_invoices_notified(&self, ids: &[i64]) -> RepositoryResult<u64>;
+    async fn get_customer_usage(
+        &self,
+        customer_id: i64,
+        gateway: PaymentSystem,
+        since: DateTime<Utc>,
+    ) -> RepositoryResult<CustomerGatewayUsage>;
 }
 
 #[derive(Clone)]
@@ -70,44 +115,74 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
         let mut query_builder: QueryBuilder<Postgres> =
             QueryBuilder::new("SELECT * FROM payment_invoices");
         apply_list_query(&mut query_builder, &query);
-        let query = query_builder.build_query_as::<PaymentInvoiceRow>();
-        let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        let items_query = query_builder.build_query_as::<PaymentInvoiceRow>();
+        let items = items_query.fetch_all(&*self.pool).await?;
+        Ok(into_paginated_result(&query, items, total, |row| row.id))
     }
 
     async fn create(
         &self,
         payment_invoice: NewPaymentInvoice,
+    ) -> RepositoryResult<PaymentInvoiceRow> {
+        insert_invoice(&*self.pool, payment_invoice).await
+    }
+
+    async fn create_within_limits(
+        &self,
+        payment_invoice: NewPaymentInvoice,
+        limits: InvoiceLimits,
+    ) -> RepositoryResult<Result<PaymentInvoiceRow, InvoiceLimitExceeded>> {
+        let mut tx = self.pool.begin().await?;
+        // Held until commit; other invoices of the customer wait here and see this one
+        sqlx::query!(
+            "SELECT id FROM customers WHERE id = $1 FOR NO KEY UPDATE",
+            payment_invoice.customer_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let usage = customer_usage(
+            &mut *tx,
+            payment_invoice.customer_id,
+            payment_invoice.gateway,
+            limits.since,
+        )
+        .await?;
+        if let Err(exceeded) = limits.check(&usage) {
+            return Ok(Err(exceeded));
+        }
+
+        let result = insert_invoice(&mut *tx, payment_invoice).await?;
+        tx.commit().await?;
+        Ok(Ok(result))
+    }
+
+    async fn attach_gateway_order(
+        &self,
+        id: i64,
+        gateway_invoice_id: String,
+        payment_details: PaymentDetails,
+        amount_in_usdt: Decimal,
     ) -> RepositoryResult<PaymentInvoiceRow> {
         let result = sqlx::query_as!(
             PaymentInvoiceRow,
             r#"
-            INSERT INTO payment_invoices (
-                customer_id, original_amount, amount, amount_in_usdt, status, expires_at, gateway, gateway_invoice_id,
-                order_id, payment_details, bot_message_id
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            UPDATE payment_invoices
+            SET gateway_invoice_id = $2, payment_details = $3, amount_in_usdt = $4
+            WHERE id = $1
             RETURNING
                 id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
                 expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
                 bot_message_id, notification_sent_at, receipt_requested_at,
-                receipt_submitted_at, dispute_opened_at, finished_at
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
             "#,
-            payment_invoice.customer_id,
-            payment_invoice.original_amount,
-            payment_invoice.amount,
-            payment_invoice.amount_in_usdt,
-            payment_invoice.status as InvoiceStatus,
-            payment_invoice.expires_at,
-            payment_invoice.gateway as _,
-            payment_invoice.gateway_invoice_id,
-            payment_invoice.order_id,
-            payment_invoice.payment_details.map(|p| serde_json::to_value(p).unwrap_or_default()).unwrap_or_default(),
-            payment_invoice.bot_message_id
+            id,
+            gateway_invoice_id,
+            serde_json::to_value(payment_details).unwrap_or_default(),
+            amount_in_usdt
         )
         .fetch_one(&*self.pool)
         .await?;
-
         Ok(result)
     }
 
@@ -139,6 +214,10 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
             query_builder.push(", receipt_submitted_at = ");
             query_builder.push_bind(receipt_submitted_at);
         }
+        if let Some(receipt_url) = payment_invoice.receipt_url {
+            query_builder.push(", receipt_url = ");
+            query_builder.push_bind(receipt_url);
+        }
         if let Some(dispute_opened_at) = payment_invoice.dispute_opened_at {
             query_builder.push(", dispute_opened_at = ");
             query_builder.push_bind(dispute_opened_at);
@@ -150,14 +229,57 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
 
         query_builder.push(" WHERE id = ");
         query_builder.push_bind(id);
+        if let Some(expected_status) = payment_invoice.expected_status {
+            query_builder.push(" AND status = ");
+            query_builder.push_bind(expected_status);
+        }
         query_builder.push(" RETURNING *");
 
         let query = query_builder.build_query_as::<PaymentInvoiceRow>();
 
-        query
-            .fetch_one(&*self.pool)
-            .await
-            .map_err(RepositoryError::from)
+        match query.fetch_optional(&*self.pool).await? {
+            Some(row) => Ok(row),
+            None if payment_invoice.expected_status.is_some() => {
+                Err(RepositoryError::OptimisticLockViolation)
+            }
+            None => Err(RepositoryError::NotFound(
+                "payment invoice: not found".to_string(),
+            )),
+        }
+    }
+
+    async fn complete_with_deposit(
+        &self,
+        id: i64,
+        expected_status: InvoiceStatus,
+        finished_at: DateTime<Utc>,
+        deposit: NewTransaction,
+    ) -> RepositoryResult<PaymentInvoiceRow> {
+        let mut tx = self.pool.begin().await?;
+        let completed = sqlx::query_as!(
+            PaymentInvoiceRow,
+            r#"
+            UPDATE payment_invoices
+            SET status = 'completed', finished_at = $3
+            WHERE id = $1 AND status = $2
+            RETURNING
+                id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
+                expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
+                bot_message_id, notification_sent_at, receipt_requested_at,
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
+            "#,
+            id,
+            expected_status as InvoiceStatus,
+            finished_at
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(RepositoryError::OptimisticLockViolation)?;
+
+        insert_transaction(&mut *tx, deposit).await?;
+        tx.commit().await?;
+        Ok(completed)
     }
 
     async fn get_by_id(&self, id: i64) -> RepositoryResult<PaymentInvoiceRow> {
@@ -168,7 +290,8 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
                 id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
                 expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
                 bot_message_id, notification_sent_at, receipt_requested_at,
-                receipt_submitted_at, dispute_opened_at, finished_at
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
             FROM payment_invoices WHERE id = $1"#,
             id
         )
@@ -186,7 +309,8 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
                 id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
                 expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
                 bot_message_id, notification_sent_at, receipt_requested_at,
-                receipt_submitted_at, dispute_opened_at, finished_at
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
             FROM payment_invoices WHERE order_id = $1"#,
             order_id
         )
@@ -196,6 +320,30 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
         Ok(result)
     }
 
+    async fn get_by_gateway_invoice_id(
+        &self,
+        gateway: PaymentSystem,
+        gateway_invoice_id: &str,
+    ) -> RepositoryResult<PaymentInvoiceRow> {
+        let result = sqlx::query_as!(
+            PaymentInvoiceRow,
+            r#"
+            SELECT
+                id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
+                expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
+                bot_message_id, notification_sent_at, receipt_requested_at,
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
+            FROM payment_invoices WHERE gateway = $1 AND gateway_invoice_id = $2"#,
+            gateway as PaymentSystem,
+            gateway_invoice_id
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
     async fn get_for_customer(&self, customer_id: i64) -> RepositoryResult<Vec<PaymentInvoiceRow>> {
         let result = sqlx::query_as!(
             PaymentInvoiceRow,
@@ -204,7 +352,8 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
                 id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
                 expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
                 bot_message_id, notification_sent_at, receipt_requested_at,
-                receipt_submitted_at, dispute_opened_at, finished_at
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
             FROM payment_invoices WHERE customer_id = $1"#,
             customer_id
         )
@@ -214,20 +363,27 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
         Ok(result)
     }
 
-    async fn expire_old_invoices(&self) -> RepositoryResult<u64> {
-        let result = sqlx::query!(
+    async fn expire_old_invoices(&self) -> RepositoryResult<Vec<PaymentInvoiceRow>> {
+        let result = sqlx::query_as!(
+            PaymentInvoiceRow,
             r#"
         UPDATE payment_invoices
         SET status = 'expired'
         WHERE status = 'pending'
         AND expires_at < NOW()
         AND deleted_at IS NULL
+        RETURNING
+            id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
+            expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
+            bot_message_id, notification_sent_at, receipt_requested_at,
+            receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+            currency as "currency: _", checkout_order_id
         "#,
         )
-        .execute(&*self.pool)
+        .fetch_all(&*self.pool)
         .await?;
 
-        Ok(result.rows_affected())
+        Ok(result)
     }
 
     async fn get_pending_invoices(
@@ -241,12 +397,15 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
                 id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
                 expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
                 bot_message_id, notification_sent_at, receipt_requested_at,
-                receipt_submitted_at, dispute_opened_at, finished_at
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
             FROM payment_invoices
             WHERE
                 status IN ('pending', 'processing', 'awaiting_receipt', 'receipt_submitted', 'disputed') AND
                 created_at < $1 AND
-                deleted_at IS NULL
+                deleted_at IS NULL AND
+                -- A reserved invoice keeps its order id until the gateway answers
+                gateway_invoice_id != order_id::text
             "#,
             older_than
         )
@@ -265,6 +424,81 @@ impl PaymentInvoiceRepositoryTrait for PaymentInvoiceRepository {
         .await?;
         Ok(result.rows_affected())
     }
+
+    async fn get_customer_usage(
+        &self,
+        customer_id: i64,
+        gateway: PaymentSystem,
+        since: DateTime<Utc>,
+    ) -> RepositoryResult<CustomerGatewayUsage> {
+        customer_usage(&*self.pool, customer_id, gateway, since).await
+    }
+}
+
+async fn insert_invoice(
+    executor: impl PgExecutor<'_>,
+    payment_invoice: NewPaymentInvoice,
+) -> RepositoryResult<PaymentInvoiceRow> {
+    let result = sqlx::query_as!(
+        PaymentInvoiceRow,
+        r#"
+        INSERT INTO payment_invoices (
+            customer_id, original_amount, amount, amount_in_usdt, status, expires_at, gateway, gateway_invoice_id,
+            order_id, payment_details, bot_message_id, currency, checkout_order_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        RETURNING
+            id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
+            expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
+            bot_message_id, notification_sent_at, receipt_requested_at,
+            receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+            currency as "currency: _", checkout_order_id
+        "#,
+        payment_invoice.customer_id,
+        payment_invoice.original_amount,
+        payment_invoice.amount,
+        payment_invoice.amount_in_usdt,
+        payment_invoice.status as InvoiceStatus,
+        payment_invoice.expires_at,
+        payment_invoice.gateway as _,
+        payment_invoice.gateway_invoice_id,
+        payment_invoice.order_id,
+        payment_invoice.payment_details.map(|p| serde_json::to_value(p).unwrap_or_default()).unwrap_or_default(),
+        payment_invoice.bot_message_id,
+        payment_invoice.currency as Currency,
+        payment_invoice.checkout_order_id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(result)
+}
+
+async fn customer_usage(
+    executor: impl PgExecutor<'_>,
+    customer_id: i64,
+    gateway: PaymentSystem,
+    since: DateTime<Utc>,
+) -> RepositoryResult<CustomerGatewayUsage> {
+    let result = sqlx::query_as!(
+        CustomerGatewayUsage,
+        r#"
+        SELECT
+            COUNT(*) FILTER (
+                WHERE status IN ('pending', 'processing', 'awaiting_receipt', 'receipt_submitted', 'disputed')
+            ) as "open_invoices!",
+            COALESCE(SUM(original_amount) FILTER (
+                WHERE created_at >= $3 AND status NOT IN ('failed', 'expired', 'cancelled', 'refunded')
+            ), 0) as "deposited_since!"
+        FROM payment_invoices
+        WHERE customer_id = $1 AND gateway = $2 AND deleted_at IS NULL
+        "#,
+        customer_id,
+        gateway as _,
+        since
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -272,7 +506,7 @@ mod tests {
     use super::*;
     use chrono::{Duration, Utc};
     use rust_decimal::Decimal;
-    use shared_dtos::invoice::PaymentSystem;
+    use shared_dtos::{invoice::PaymentSystem, transaction::TransactionType};
     use sqlx::PgPool;
     use uuid::Uuid;
 
@@ -339,6 +573,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
 
         sqlx::query_as!(
@@ -346,14 +582,15 @@ mod tests {
             r#"
             INSERT INTO payment_invoices (
                 customer_id, original_amount, amount, amount_in_usdt, status, expires_at, gateway, gateway_invoice_id,
-                order_id, payment_details, bot_message_id, created_at
+                order_id, payment_details, bot_message_id, created_at, currency
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             RETURNING
                 id, customer_id, original_amount, amount, amount_in_usdt, status as "status: _", created_at, updated_at,
                 expires_at, deleted_at, gateway as "gateway: _", gateway_invoice_id, order_id, payment_details,
                 bot_message_id, notification_sent_at, receipt_requested_at,
-                receipt_submitted_at, dispute_opened_at, finished_at
+                receipt_submitted_at, dispute_opened_at, finished_at, receipt_url,
+                currency as "currency: _", checkout_order_id
             "#,
             new_invoice.customer_id,
             new_invoice.original_amount,
@@ -369,7 +606,8 @@ mod tests {
                 .map(|p| serde_json::to_value(p).unwrap_or_default())
                 .unwrap_or_default(),
             new_invoice.bot_message_id,
-            created_at
+            created_at,
+            new_invoice.currency as Currency
         )
         .fetch_one(pool)
         .await
@@ -396,6 +634,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
 
         // Create an invoice
@@ -418,6 +658,154 @@ mod tests {
         assert!(!invoices.items.is_empty());
     }
 
+    #[sqlx::test]
+    async fn test_update_with_stale_expected_status(pool: PgPool) {
+        let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 1005).await;
+        let invoice_id = create_test_invoice(
+            &pool,
+            customer_id,
+            InvoiceStatus::Pending,
+            "conditional_invoice",
+            Utc::now(),
+        )
+        .await
+        .id;
+
+        let updated = repo
+            .update(
+                invoice_id,
+                UpdatePaymentInvoice {
+                    status: Some(InvoiceStatus::Completed),
+                    expected_status: Some(InvoiceStatus::Pending),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.status, InvoiceStatus::Completed);
+
+        // A second writer that still saw the invoice as pending loses
+        let result = repo
+            .update(
+                invoice_id,
+                UpdatePaymentInvoice {
+                    status: Some(InvoiceStatus::Failed),
+                    expected_status: Some(InvoiceStatus::Pending),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(RepositoryError::OptimisticLockViolation)
+        ));
+        assert_eq!(
+            repo.get_by_id(invoice_id).await.unwrap().status,
+            InvoiceStatus::Completed
+        );
+    }
+
+    fn test_deposit(customer_id: i64, invoice_id: i64) -> NewTransaction {
+        NewTransaction {
+            customer_id: Some(customer_id),
+            order_id: None,
+            r#type: TransactionType::Deposit,
+            amount: Decimal::from(10),
+            store_balance_delta: Decimal::from(8),
+            platform_commission: Decimal::ZERO,
+            gateway_commission: Decimal::from(2),
+            description: None,
+            payment_gateway: Some(PaymentSystem::Mock),
+            details: None,
+            bot_id: None,
+            payment_invoice_id: Some(invoice_id),
+        }
+    }
+
+    async fn count_deposits(pool: &PgPool, invoice_id: i64) -> i64 {
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM transactions WHERE payment_invoice_id = $1"#,
+            invoice_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_complete_with_deposit_credits_once(pool: PgPool) {
+        let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 1006).await;
+        let invoice_id = create_test_invoice(
+            &pool,
+            customer_id,
+            InvoiceStatus::Pending,
+            "complete_with_deposit",
+            Utc::now(),
+        )
+        .await
+        .id;
+
+        let completed = repo
+            .complete_with_deposit(
+                invoice_id,
+                InvoiceStatus::Pending,
+                Utc::now(),
+                test_deposit(customer_id, invoice_id),
+            )
+            .await
+            .unwrap();
+        assert_eq!(completed.status, InvoiceStatus::Completed);
+        assert!(completed.finished_at.is_some());
+        assert_eq!(count_deposits(&pool, invoice_id).await, 1);
+
+        let result = repo
+            .complete_with_deposit(
+                invoice_id,
+                InvoiceStatus::Pending,
+                Utc::now(),
+                test_deposit(customer_id, invoice_id),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(RepositoryError::OptimisticLockViolation)
+        ));
+        assert_eq!(count_deposits(&pool, invoice_id).await, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_complete_with_deposit_rolls_back_on_failed_deposit(pool: PgPool) {
+        let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 1007).await;
+        let invoice_id = create_test_invoice(
+            &pool,
+            customer_id,
+            InvoiceStatus::Pending,
+            "complete_with_failed_deposit",
+            Utc::now(),
+        )
+        .await
+        .id;
+
+        // Unknown customer, the deposit insert violates its foreign key
+        let result = repo
+            .complete_with_deposit(
+                invoice_id,
+                InvoiceStatus::Pending,
+                Utc::now(),
+                test_deposit(customer_id + 1000, invoice_id),
+            )
+            .await;
+        assert!(result.is_err());
+
+        let invoice = repo.get_by_id(invoice_id).await.unwrap();
+        assert_eq!(invoice.status, InvoiceStatus::Pending);
+        assert!(invoice.finished_at.is_none());
+        assert_eq!(count_deposits(&pool, invoice_id).await, 0);
+    }
+
     #[sqlx::test]
     async fn test_get_by_id(pool: PgPool) {
         let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
@@ -436,6 +824,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         let created_invoice = repo.create(new_invoice).await.unwrap();
 
@@ -467,6 +857,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         let created_invoice = repo.create(new_invoice).await.unwrap();
 
@@ -476,6 +868,42 @@ mod tests {
         assert_eq!(fetched_invoice.order_id, order_id);
     }
 
+    #[sqlx::test]
+    async fn test_get_by_gateway_invoice_id(pool: PgPool) {
+        let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 790).await;
+
+        let created_invoice = repo
+            .create(NewPaymentInvoice {
+                customer_id,
+                order_id: Uuid::new_v4(),
+                original_amount: Decimal::from(150),
+                amount: Decimal::from(150),
+                status: InvoiceStatus::Pending,
+                expires_at: Utc::now() + Duration::days(1),
+                gateway: PaymentSystem::PlatformCard,
+                gateway_invoice_id: "gw-token".to_string(),
+                payment_details: None,
+                bot_message_id: None,
+                amount_in_usdt: Decimal::from(1),
+                currency: Currency::Rub,
+                checkout_order_id: None,
+            })
+            .await
+            .unwrap();
+
+        let fetched_invoice = repo
+            .get_by_gateway_invoice_id(PaymentSystem::PlatformCard, "gw-token")
+            .await
+            .unwrap();
+        assert_eq!(fetched_invoice.id, created_invoice.id);
+
+        let other_gateway = repo
+            .get_by_gateway_invoice_id(PaymentSystem::Mock, "gw-token")
+            .await;
+        assert!(matches!(other_gateway, Err(RepositoryError::NotFound(_))));
+    }
+
     #[sqlx::test]
     async fn test_get_for_customer(pool: PgPool) {
         let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
@@ -496,6 +924,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         repo.create(new_invoice_1_1).await.unwrap();
 
@@ -511,6 +941,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         repo.create(new_invoice_1_2).await.unwrap();
 
@@ -527,6 +959,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         repo.create(new_invoice_2_1).await.unwrap();
 
@@ -566,6 +1000,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         let created_expired_invoice = repo.create(new_expired_invoice).await.unwrap();
 
@@ -583,12 +1019,18 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         let created_non_expired_invoice = repo.create(new_non_expired_invoice).await.unwrap();
 
         // Call the method to expire old invoices
-        let rows_affected = repo.expire_old_invoices().await.unwrap();
-        assert_eq!(rows_affected, 1);
+        let expired = repo.expire_old_invoices().await.unwrap();
+        assert_eq!(
+            expired.iter().map(|i| i.id).collect::<Vec<_>>(),
+            vec![created_expired_invoice.id]
+        );
+        assert_eq!(expired[0].status, InvoiceStatus::Expired);
 
         // Verify the status of the expired invoice
         let updated_expired_invoice = repo.get_by_id(created_expired_invoice.id).await.unwrap();
@@ -673,6 +1115,62 @@ mod tests {
         );
     }
 
+    #[sqlx::test]
+    async fn test_attach_gateway_order_to_reserved_invoice(pool: PgPool) {
+        let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 1011).await;
+        let order_id = Uuid::new_v4();
+        let reserved = repo
+            .create(NewPaymentInvoice {
+                customer_id,
+                order_id,
+                original_amount: Decimal::from(100),
+                amount: Decimal::from(100),
+                status: InvoiceStatus::Pending,
+                expires_at: Utc::now() + Duration::hours(1),
+                gateway: PaymentSystem::Mock,
+                gateway_invoice_id: order_id.to_string(),
+                payment_details: None,
+                bot_message_id: None,
+                amount_in_usdt: Decimal::ZERO,
+                currency: Currency::Rub,
+                checkout_order_id: None,
+            })
+            .await
+            .unwrap();
+        let older_than = Utc::now() + Duration::minutes(1);
+
+        // Nothing to ask the gateway about until it has answered
+        assert!(
+            repo.get_pending_invoices(older_than)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let attached = repo
+            .attach_gateway_order(
+                reserved.id,
+                "gateway_order_1".to_string(),
+                PaymentDetails::Mock {
+                    pay_url: "https://pay.example/1".to_string(),
+                },
+                Decimal::from(2),
+            )
+            .await
+            .unwrap();
+        assert_eq!(attached.gateway_invoice_id, "gateway_order_1");
+        assert_eq!(attached.amount_in_usdt, Decimal::from(2));
+        assert_eq!(
+            attached.payment_details["Mock"]["pay_url"],
+            "https://pay.example/1"
+        );
+        assert_eq!(
+            repo.get_pending_invoices(older_than).await.unwrap().len(),
+            1
+        );
+    }
+
     #[sqlx::test]
     async fn test_mark_invoices_notified(pool: PgPool) {
         let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
@@ -692,6 +1190,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         let created_invoice_1 = repo.create(new_invoice_1).await.unwrap();
 
@@ -707,6 +1207,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         let created_invoice_2 = repo.create(new_invoice_2).await.unwrap();
 
@@ -722,6 +1224,8 @@ mod tests {
             payment_details: None,
             bot_message_id: None,
             amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
         };
         let created_invoice_3 = repo.create(new_invoice_3).await.unwrap();
 
@@ -742,4 +1246,134 @@ mod tests {
         let invoice_3_updated = repo.get_by_id(created_invoice_3.id).await.unwrap();
         assert!(invoice_3_updated.notification_sent_at.is_none());
     }
+
+    #[sqlx::test]
+    async fn test_get_list_search(pool: PgPool) {
+        let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 777000111).await;
+        let other_customer_id = create_test_customer(&pool, 777000222).await;
+        let invoice = create_test_invoice(
+            &pool,
+            customer_id,
+            InvoiceStatus::Pending,
+            "gw-abc-123",
+            Utc::now(),
+        )
+        .await;
+        create_test_invoice(
+            &pool,
+            other_customer_id,
+            InvoiceStatus::Pending,
+            "gw-xyz-456",
+            Utc::now(),
+        )
+        .await;
+
+        for term in ["abc-1", "777000111", &invoice.order_id.to_string()] {
+            let result = repo
+                .get_list(PaymentInvoiceListQuery {
+                    search: Some(term.to_string()),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            assert_eq!(result.total, 1, "search term {term}");
+            assert_eq!(result.items[0].id, invoice.id);
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_get_customer_usage(pool: PgPool) {
+        let repo = PaymentInvoiceRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_test_customer(&pool, 1010).await;
+        let now = Utc::now();
+
+        create_test_invoice(&pool, customer_id, InvoiceStatus::Pending, "usage_1", now).await;
+        create_test_invoice(&pool, customer_id, InvoiceStatus::Completed, "usage_2", now).await;
+        create_test_invoice(&pool, customer_id, InvoiceStatus::Expired, "usage_3", now).await;
+        create_test_invoice(
+            &pool,
+            customer_id,
+            InvoiceStatus::Pending,
+            "usage_4",
+            now - Duration::days(2),
+        )
+        .await;
+
+        let usage = repo
+            .get_customer_usage(customer_id, PaymentSystem::Mock, now - Duration::days(1))
+            .await
+            .unwrap();
+        // Both pending invoices are open, only today's live ones count towards the limit
+        assert_eq!(usage.open_invoices, 2);
+        assert_eq!(usage.deposited_since, Decimal::from(20));
+
+        let other_gateway = repo
+            .get_customer_usage(
+                customer_id,
+                PaymentSystem::PlatformCard,
+                now - Duration::days(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(other_gateway.open_invoices, 0);
+        assert_eq!(other_gateway.deposited_since, Decimal::ZERO);
+    }
+
+    #[sqlx::test]
+    async fn test_create_within_limits_is_serialized_per_customer(pool: PgPool) {
+        let repo = Arc::new(PaymentInvoiceRepository::new(Arc::new(pool.clone())));
+        let customer_id = create_test_customer(&pool, 1010).await;
+        let new_invoice = |gateway_invoice_id: &str| NewPaymentInvoice {
+            customer_id,
+            order_id: Uuid::new_v4(),
+            original_amount: Decimal::from(100),
+            amount: Decimal::from(100),
+            status: InvoiceStatus::Pending,
+            expires_at: Utc::now() + Duration::hours(1),
+            gateway: PaymentSystem::Mock,
+            gateway_invoice_id: gateway_invoice_id.to_string(),
+            payment_details: None,
+            bot_message_id: None,
+            amount_in_usdt: Decimal::from(1),
+            currency: Currency::Rub,
+            checkout_order_id: None,
+        };
+        let limits = InvoiceLimits {
+            max_open_invoices: Some(1),
+            daily_deposit_limit: None,
+            since: Utc::now() - Duration::days(1),
+            amount: Decimal::from(100),
+        };
+
+        // Another request is between its limit check and its insert
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query!(
+            "SELECT id FROM customers WHERE id = $1 FOR NO KEY UPDATE",
+            customer_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+
+        let concurrent = tokio::spawn({
+            let repo = repo.clone();
+            let invoice = new_invoice("concurrent_2");
+            async move { repo.create_within_limits(invoice, limits).await.unwrap() }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!concurrent.is_finished());
+
+        insert_invoice(&mut *tx, new_invoice("concurrent_1"))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // The waiting request sees the invoice created meanwhile
+        assert_eq!(
+            concurrent.await.unwrap().unwrap_err(),
+            InvoiceLimitExceeded::OpenInvoices
+        );
+        assert_eq!(repo.get_for_customer(customer_id).await.unwrap().len(), 1);
+    }
 }
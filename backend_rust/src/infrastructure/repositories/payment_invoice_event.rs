@@ -0,0 +1,259 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::{
+    errors::repository::RepositoryResult,
+    models::payment_invoice_event::{NewPaymentInvoiceEvent, PaymentInvoiceEventRow},
+};
+
+#[async_trait]
+pub trait PaymentInvoiceEventRepositoryTrait {
+    /// Appends an event. Returns `None` when an event with the same dedup key was
+    /// already recorded for the invoice.
+    async fn create(
+        &self,
+        event: NewPaymentInvoiceEvent,
+    ) -> RepositoryResult<Option<PaymentInvoiceEventRow>>;
+    /// Appends an event unless the last event of the same kind recorded for the invoice
+    /// carries the same payload. Returns `None` when nothing changed.
+    async fn create_if_changed(
+        &self,
+        event: NewPaymentInvoiceEvent,
+    ) -> RepositoryResult<Option<PaymentInvoiceEventRow>>;
+    async fn exists(&self, invoice_id: i64, dedup_key: &str) -> RepositoryResult<bool>;
+    /// Events of one invoice, oldest first
+    async fn get_for_invoice(
+        &self,
+        invoice_id: i64,
+    ) -> RepositoryResult<Vec<PaymentInvoiceEventRow>>;
+}
+
+#[derive(Clone)]
+pub struct PaymentInvoiceEventRepository {
+    pool: Arc<PgPool>,
+}
+
+impl PaymentInvoiceEventRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PaymentInvoiceEventRepositoryTrait for PaymentInvoiceEventRepository {
+    async fn create(
+        &self,
+        event: NewPaymentInvoiceEvent,
+    ) -> RepositoryResult<Option<PaymentInvoiceEventRow>> {
+        let result = sqlx::query_as!(
+            PaymentInvoiceEventRow,
+            r#"
+            INSERT INTO payment_invoice_events (invoice_id, kind, payload, dedup_key)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (invoice_id, dedup_key) WHERE dedup_key IS NOT NULL DO NOTHING
+            RETURNING
+                id, invoice_id, kind as "kind: _", from_status as "from_status: _",
+                to_status as "to_status: _", payload, dedup_key, created_at
+            "#,
+            event.invoice_id,
+            event.kind as _,
+            event.payload,
+            event.dedup_key
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn create_if_changed(
+        &self,
+        event: NewPaymentInvoiceEvent,
+    ) -> RepositoryResult<Option<PaymentInvoiceEventRow>> {
+        let result = sqlx::query_as!(
+            PaymentInvoiceEventRow,
+            r#"
+            INSERT INTO payment_invoice_events (invoice_id, kind, payload, dedup_key)
+            SELECT $1, $2, $3, $4
+            WHERE (
+                SELECT payload FROM payment_invoice_events
+                WHERE invoice_id = $1 AND kind = $2
+                ORDER BY id DESC
+                LIMIT 1
+            ) IS DISTINCT FROM $3
+            ON CONFLICT (invoice_id, dedup_key) WHERE dedup_key IS NOT NULL DO NOTHING
+            RETURNING
+                id, invoice_id, kind as "kind: _", from_status as "from_status: _",
+                to_status as "to_status: _", payload, dedup_key, created_at
+            "#,
+            event.invoice_id,
+            event.kind as _,
+            event.payload,
+            event.dedup_key
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn exists(&self, invoice_id: i64, dedup_key: &str) -> RepositoryResult<bool> {
+        let result = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM payment_invoice_events WHERE invoice_id = $1 AND dedup_key = $2
+            ) as "exists!"
+            "#,
+            invoice_id,
+            dedup_key
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn get_for_invoice(
+        &self,
+        invoice_id: i64,
+    ) -> RepositoryResult<Vec<PaymentInvoiceEventRow>> {
+        let result = sqlx::query_as!(
+            PaymentInvoiceEventRow,
+            r#"
+            SELECT
+                id, invoice_id, kind as "kind: _", from_status as "from_status: _",
+                to_status as "to_status: _", payload, dedup_key, created_at
+            FROM payment_invoice_events
+            WHERE invoice_id = $1
+            ORDER BY id
+            "#,
+            invoice_id
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use shared_dtos::invoice::{InvoiceStatus, PaymentInvoiceEventKind};
+
+    use super::*;
+
+    async fn create_test_invoice(pool: &PgPool) -> i64 {
+        let customer_id = sqlx::query_scalar!(
+            "INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot) VALUES (1, 1, 1) RETURNING id"
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO payment_invoices (
+                customer_id, original_amount, amount, amount_in_usdt, expires_at, gateway,
+                gateway_invoice_id, order_id, payment_details
+            )
+            VALUES ($1, 100, 100, 1, NOW() + INTERVAL '1 hour', 'platform_card', 'gw-1', gen_random_uuid(), '{}')
+            RETURNING id
+            "#,
+            customer_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_status_changes_are_logged(pool: PgPool) {
+        let invoice_id = create_test_invoice(&pool).await;
+        sqlx::query!(
+            "UPDATE payment_invoices SET status = 'awaiting_receipt' WHERE id = $1",
+            invoice_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // Touching other columns doesn't log anything
+        sqlx::query!(
+            "UPDATE payment_invoices SET notification_sent_at = NOW() WHERE id = $1",
+            invoice_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let repo = PaymentInvoiceEventRepository::new(Arc::new(pool));
+
+        let events = repo.get_for_invoice(invoice_id).await.unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.kind, e.from_status, e.to_status))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    PaymentInvoiceEventKind::StatusChanged,
+                    None,
+                    Some(InvoiceStatus::Pending)
+                ),
+                (
+                    PaymentInvoiceEventKind::StatusChanged,
+                    Some(InvoiceStatus::Pending),
+                    Some(InvoiceStatus::AwaitingReceipt)
+                ),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_create_deduplicates_and_is_append_only(pool: PgPool) {
+        let invoice_id = create_test_invoice(&pool).await;
+        let repo = PaymentInvoiceEventRepository::new(Arc::new(pool.clone()));
+        let event = NewPaymentInvoiceEvent::notification(
+            invoice_id,
+            "request_receipt".to_string(),
+            json!({}),
+        );
+
+        assert!(!repo.exists(invoice_id, "request_receipt").await.unwrap());
+        let created = repo.create(event.clone()).await.unwrap().unwrap();
+        assert_eq!(created.kind, PaymentInvoiceEventKind::NotificationSent);
+        assert!(repo.exists(invoice_id, "request_receipt").await.unwrap());
+        assert!(repo.create(event).await.unwrap().is_none());
+
+        let deleted = sqlx::query!(
+            "DELETE FROM payment_invoice_events WHERE id = $1",
+            created.id
+        )
+        .execute(&pool)
+        .await;
+        assert!(deleted.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_create_if_changed_skips_repeated_payloads(pool: PgPool) {
+        let invoice_id = create_test_invoice(&pool).await;
+        let repo = PaymentInvoiceEventRepository::new(Arc::new(pool));
+        let pending = json!({"status": "merch_process"});
+        let checking = json!({"status": "trader_check_query"});
+
+        for payload in [&pending, &pending, &checking, &checking, &pending] {
+            repo.create_if_changed(NewPaymentInvoiceEvent::gateway_response(
+                invoice_id, payload,
+            ))
+            .await
+            .unwrap();
+        }
+
+        let responses = repo
+            .get_for_invoice(invoice_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.kind == PaymentInvoiceEventKind::GatewayResponse)
+            .filter_map(|e| e.payload)
+            .collect::<Vec<_>>();
+        assert_eq!(responses, vec![pending.clone(), checking, pending]);
+    }
+}
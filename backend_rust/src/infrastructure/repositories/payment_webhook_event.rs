@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared_dtos::invoice::PaymentSystem;
+use sqlx::PgPool;
+
+use crate::{
+    errors::repository::RepositoryResult,
+    models::payment_webhook_event::{NewPaymentWebhookEvent, PaymentWebhookEventRow},
+};
+
+#[async_trait]
+pub trait PaymentWebhookEventRepositoryTrait {
+    /// Stores a received event. Returns `None` when the same gateway event was already
+    /// processed; an event whose processing failed is returned again so retries go through.
+    async fn register(
+        &self,
+        event: NewPaymentWebhookEvent,
+    ) -> RepositoryResult<Option<PaymentWebhookEventRow>>;
+    /// Marks the event as processed, or keeps it retryable with `error`
+    async fn mark_processed(&self, id: i64, error: Option<String>) -> RepositoryResult<()>;
+    /// Events received for one gateway invoice, oldest first
+    async fn get_for_invoice(
+        &self,
+        gateway: PaymentSystem,
+        gateway_invoice_id: &str,
+    ) -> RepositoryResult<Vec<PaymentWebhookEventRow>>;
+}
+
+#[derive(Clone)]
+pub struct PaymentWebhookEventRepository {
+    pool: Arc<PgPool>,
+}
+
+impl PaymentWebhookEventRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PaymentWebhookEventRepositoryTrait for PaymentWebhookEventRepository {
+    async fn register(
+        &self,
+        event: NewPaymentWebhookEvent,
+    ) -> RepositoryResult<Option<PaymentWebhookEventRow>> {
+        let result = sqlx::query_as!(
+            PaymentWebhookEventRow,
+            r#"
+            INSERT INTO payment_webhook_events (gateway, event_id, gateway_invoice_id, payload)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (gateway, event_id) DO UPDATE SET error = NULL
+            WHERE payment_webhook_events.processed_at IS NULL
+            RETURNING
+                id, gateway as "gateway: _", event_id, gateway_invoice_id, payload,
+                received_at, processed_at, error
+            "#,
+            event.gateway as PaymentSystem,
+            event.event_id,
+            event.gateway_invoice_id,
+            event.payload
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn mark_processed(&self, id: i64, error: Option<String>) -> RepositoryResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE payment_webhook_events
+            SET processed_at = CASE WHEN $2::TEXT IS NULL THEN NOW() END, error = $2
+            WHERE id = $1
+            "#,
+            id,
+            error
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_for_invoice(
+        &self,
+        gateway: PaymentSystem,
+        gateway_invoice_id: &str,
+    ) -> RepositoryResult<Vec<PaymentWebhookEventRow>> {
+        let result = sqlx::query_as!(
+            PaymentWebhookEventRow,
+            r#"
+            SELECT
+                id, gateway as "gateway: _", event_id, gateway_invoice_id, payload,
+                received_at, processed_at, error
+            FROM payment_webhook_events
+            WHERE gateway = $1 AND gateway_invoice_id = $2
+            ORDER BY received_at, id
+            "#,
+            gateway as PaymentSystem,
+            gateway_invoice_id
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(event_id: &str) -> NewPaymentWebhookEvent {
+        NewPaymentWebhookEvent {
+            gateway: PaymentSystem::PlatformCard,
+            event_id: event_id.to_string(),
+            gateway_invoice_id: "gw-1".to_string(),
+            payload: json!({"status": "merch_success"}),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_register_deduplicates_processed_events(pool: PgPool) {
+        let repo = PaymentWebhookEventRepository::new(Arc::new(pool));
+
+        let first = repo.register(event("ev-1")).await.unwrap().unwrap();
+        assert!(first.processed_at.is_none());
+
+        // Failed processing keeps the event retryable
+        repo.mark_processed(first.id, Some("boom".to_string()))
+            .await
+            .unwrap();
+        let retry = repo.register(event("ev-1")).await.unwrap().unwrap();
+        assert_eq!(retry.id, first.id);
+        assert!(retry.error.is_none());
+
+        repo.mark_processed(first.id, None).await.unwrap();
+        assert!(repo.register(event("ev-1")).await.unwrap().is_none());
+
+        // Other events of the same invoice are independent
+        assert!(repo.register(event("ev-2")).await.unwrap().is_some());
+
+        let events = repo
+            .get_for_invoice(PaymentSystem::PlatformCard, "gw-1")
+            .await
+            .unwrap();
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| e.event_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ev-1", "ev-2"]
+        );
+        assert!(
+            repo.get_for_invoice(PaymentSystem::PlatformSBP, "gw-1")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared_dtos::product_sync::{ProductSyncStatus, ProductSyncTrigger};
+use sqlx::{PgPool, QueryBuilder, types::Json};
+
+use crate::{
+    errors::repository::RepositoryResult,
+    infrastructure::lib::query::{apply_filters, apply_list_query},
+    models::{
+        common::PaginatedResult,
+        product_sync::{
+            NewProductSyncRun, ProductSyncCounts, ProductSyncRunListQuery, ProductSyncRunRow,
+            ProductSyncSnapshotRow,
+        },
+    },
+};
+
+#[async_trait]
+pub trait ProductSyncRunRepositoryTrait {
+    async fn get_list(
+        &self,
+        query: ProductSyncRunListQuery,
+    ) -> RepositoryResult<PaginatedResult<ProductSyncRunRow>>;
+    async fn create(&self, run: NewProductSyncRun) -> RepositoryResult<ProductSyncRunRow>;
+    /// Queues a run for the sync worker; a run already queued for the provider is returned instead
+    async fn queue(&self, run: NewProductSyncRun) -> RepositoryResult<ProductSyncRunRow>;
+    async fn get_next_queued(&self) -> RepositoryResult<Option<ProductSyncRunRow>>;
+    /// Moves a queued run to `running`, `None` when another instance already started it
+    async fn start_queued(&self, id: i64) -> RepositoryResult<Option<ProductSyncRunRow>>;
+    async fn finish(
+        &self,
+        id: i64,
+        status: ProductSyncStatus,
+        counts: ProductSyncCounts,
+    ) -> RepositoryResult<ProductSyncRunRow>;
+}
+
+#[derive(Clone)]
+pub struct ProductSyncRunRepository {
+    pool: Arc<PgPool>,
+}
+
+impl ProductSyncRunRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ProductSyncRunRepositoryTrait for ProductSyncRunRepository {
+    async fn get_list(
+        &self,
+        query: ProductSyncRunListQuery,
+    ) -> RepositoryResult<PaginatedResult<ProductSyncRunRow>> {
+        let mut count_qb = QueryBuilder::new("SELECT COUNT(*) FROM product_sync_runs");
+        apply_filters(&mut count_qb, &query);
+
+        let count_query = count_qb.build_query_scalar();
+        let total: i64 = count_query.fetch_one(&*self.pool).await?;
+
+        let mut query_builder = QueryBuilder::new("SELECT * FROM product_sync_runs");
+        apply_list_query(&mut query_builder, &query);
+        let items_query = query_builder.build_query_as::<ProductSyncRunRow>();
+        let items = items_query.fetch_all(&*self.pool).await?;
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
+    }
+
+    async fn create(&self, run: NewProductSyncRun) -> RepositoryResult<ProductSyncRunRow> {
+        let result = sqlx::query_as!(
+            ProductSyncRunRow,
+            r#"
+            INSERT INTO product_sync_runs (provider_name, trigger, triggered_by)
+            VALUES ($1, $2, $3)
+            RETURNING
+                id, provider_name, trigger as "trigger: _", status as "status: _",
+                added, updated, hidden, restored, errors as "errors: _",
+                triggered_by, started_at, finished_at
+            "#,
+            run.provider_name,
+            run.trigger as ProductSyncTrigger,
+            run.triggered_by
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn queue(&self, run: NewProductSyncRun) -> RepositoryResult<ProductSyncRunRow> {
+        let result = sqlx::query_as!(
+            ProductSyncRunRow,
+            r#"
+            INSERT INTO product_sync_runs (provider_name, trigger, triggered_by, status)
+            VALUES ($1, $2, $3, 'queued')
+            ON CONFLICT (provider_name) WHERE status = 'queued'
+                DO UPDATE SET provider_name = EXCLUDED.provider_name
+            RETURNING
+                id, provider_name, trigger as "trigger: _", status as "status: _",
+                added, updated, hidden, restored, errors as "errors: _",
+                triggered_by, started_at, finished_at
+            "#,
+            run.provider_name,
+            run.trigger as ProductSyncTrigger,
+            run.triggered_by
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn get_next_queued(&self) -> RepositoryResult<Option<ProductSyncRunRow>> {
+        let result = sqlx::query_as!(
+            ProductSyncRunRow,
+            r#"
+            SELECT
+                id, provider_name, trigger as "trigger: _", status as "status: _",
+                added, updated, hidden, restored, errors as "errors: _",
+                triggered_by, started_at, finished_at
+            FROM product_sync_runs
+            WHERE status = 'queued'
+            ORDER BY id
+            LIMIT 1
+            "#
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn start_queued(&self, id: i64) -> RepositoryResult<Option<ProductSyncRunRow>> {
+        let result = sqlx::query_as!(
+            ProductSyncRunRow,
+            r#"
+            UPDATE product_sync_runs
+            SET status = 'running', started_at = NOW()
+            WHERE id = $1 AND status = 'queued'
+            RETURNING
+                id, provider_name, trigger as "trigger: _", status as "status: _",
+                added, updated, hidden, restored, errors as "errors: _",
+                triggered_by, started_at, finished_at
+            "#,
+            id
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn finish(
+        &self,
+        id: i64,
+        status: ProductSyncStatus,
+        counts: ProductSyncCounts,
+    ) -> RepositoryResult<ProductSyncRunRow> {
+        let result = sqlx::query_as!(
+            ProductSyncRunRow,
+            r#"
+            UPDATE product_sync_runs
+            SET status = $2, added = $3, updated = $4, hidden = $5, restored = $6,
+                errors = $7, finished_at = NOW()
+            WHERE id = $1
+            RETURNING
+                id, provider_name, trigger as "trigger: _", status as "status: _",
+                added, updated, hidden, restored, errors as "errors: _",
+                triggered_by, started_at, finished_at
+            "#,
+            id,
+            status as ProductSyncStatus,
+            counts.added,
+            counts.updated,
+            counts.hidden,
+            counts.restored,
+            Json(counts.errors) as _
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+}
+
+#[async_trait]
+pub trait ProductSyncSnapshotRepositoryTrait {
+    async fn get_for_provider(
+        &self,
+        provider_name: &str,
+    ) -> RepositoryResult<Vec<ProductSyncSnapshotRow>>;
+    async fn upsert(&self, snapshot: ProductSyncSnapshotRow) -> RepositoryResult<()>;
+}
+
+#[derive(Clone)]
+pub struct ProductSyncSnapshotRepository {
+    pool: Arc<PgPool>,
+}
+
+impl ProductSyncSnapshotRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ProductSyncSnapshotRepositoryTrait for ProductSyncSnapshotRepository {
+    async fn get_for_provider(
+        &self,
+        provider_name: &str,
+    ) -> RepositoryResult<Vec<ProductSyncSnapshotRow>> {
+        let result = sqlx::query_as!(
+            ProductSyncSnapshotRow,
+            r#"
+            SELECT s.product_id, s.name, s.base_price, s.category_id, s.details as "details: _"
+            FROM product_sync_snapshots s
+            JOIN products p ON p.id = s.product_id
+            WHERE p.provider_name = $1
+            "#,
+            provider_name
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn upsert(&self, snapshot: ProductSyncSnapshotRow) -> RepositoryResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO product_sync_snapshots (product_id, name, base_price, category_id, details)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (product_id) DO UPDATE
+            SET name = EXCLUDED.name, base_price = EXCLUDED.base_price,
+                category_id = EXCLUDED.category_id, details = EXCLUDED.details,
+                updated_at = NOW()
+            "#,
+            snapshot.product_id,
+            snapshot.name,
+            snapshot.base_price,
+            snapshot.category_id,
+            snapshot.details as _
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_create_and_finish_run(pool: PgPool) {
+        let repo = ProductSyncRunRepository::new(Arc::new(pool));
+
+        let run = repo
+            .create(NewProductSyncRun {
+                provider_name: "contms".to_string(),
+                trigger: ProductSyncTrigger::Manual,
+                triggered_by: Some(1),
+            })
+            .await
+            .unwrap();
+        assert_eq!(run.status, ProductSyncStatus::Running);
+        assert!(run.finished_at.is_none());
+
+        let finished = repo
+            .finish(
+                run.id,
+                ProductSyncStatus::Partial,
+                ProductSyncCounts {
+                    added: 2,
+                    updated: 1,
+                    hidden: 3,
+                    restored: 0,
+                    errors: vec!["boom".to_string()],
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(finished.status, ProductSyncStatus::Partial);
+        assert_eq!(
+            (
+                finished.added,
+                finished.updated,
+                finished.hidden,
+                finished.restored
+            ),
+            (2, 1, 3, 0)
+        );
+        assert_eq!(finished.errors.0, vec!["boom".to_string()]);
+        assert!(finished.finished_at.is_some());
+
+        let list = repo
+            .get_list(ProductSyncRunListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(list.total, 1);
+        assert_eq!(list.items[0].id, run.id);
+    }
+
+    #[sqlx::test]
+    async fn test_queue_keeps_one_queued_run_per_provider(pool: PgPool) {
+        let repo = ProductSyncRunRepository::new(Arc::new(pool));
+        let new_run = || NewProductSyncRun {
+            provider_name: "contms".to_string(),
+            trigger: ProductSyncTrigger::Manual,
+            triggered_by: Some(1),
+        };
+
+        let queued = repo.queue(new_run()).await.unwrap();
+        assert_eq!(queued.status, ProductSyncStatus::Queued);
+        assert_eq!(repo.queue(new_run()).await.unwrap().id, queued.id);
+        assert_eq!(
+            repo.get_next_queued().await.unwrap().map(|run| run.id),
+            Some(queued.id)
+        );
+
+        let started = repo.start_queued(queued.id).await.unwrap().unwrap();
+        assert_eq!(started.status, ProductSyncStatus::Running);
+        // Already started by this instance
+        assert!(repo.start_queued(queued.id).await.unwrap().is_none());
+        assert!(repo.get_next_queued().await.unwrap().is_none());
+
+        // A running sync doesn't block the next request
+        assert_ne!(repo.queue(new_run()).await.unwrap().id, queued.id);
+    }
+}
@@ -64,13 +64,17 @@ impl ProductRepositoryTrait for ProductRepository {
                 id, name, base_price, category_id, image_id, type,
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             FROM products"#,
         );
         apply_list_query(&mut query_builder, &query);
         let query = query_builder.build_query_as::<ProductRow>();
         let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
     }
 
     async fn create(&self, product: NewProduct) -> RepositoryResult<ProductRow> {
@@ -85,7 +89,7 @@ impl ProductRepositoryTrait for ProductRepository {
             RETURNING id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             "#,
             product.name,
             product.base_price,
@@ -113,7 +117,7 @@ impl ProductRepositoryTrait for ProductRepository {
                 id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             FROM products WHERE id = $1 AND deleted_at IS NULL"#,
             id
         )
@@ -195,6 +199,15 @@ impl ProductRepositoryTrait for ProductRepository {
             query_builder.push_bind(subscription_period_days);
         }
 
+        // Keep the original hide time when an already hidden product is hidden again
+        if let Some(hidden) = product.hidden {
+            if hidden {
+                query_builder.push(", hidden_at = COALESCE(hidden_at, NOW())");
+            } else {
+                query_builder.push(", hidden_at = NULL");
+            }
+        }
+
         query_builder.push(" WHERE id = ");
         query_builder.push_bind(id);
         query_builder.push(" AND deleted_at IS NULL RETURNING *");
@@ -228,7 +241,7 @@ impl ProductRepositoryTrait for ProductRepository {
                 id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             FROM products WHERE provider_name = $1 AND external_id = $2 AND deleted_at IS NULL"#,
             provider_name,
             external_id
@@ -248,7 +261,7 @@ impl ProductRepositoryTrait for ProductRepository {
                 id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             FROM products WHERE provider_name = $1 AND deleted_at IS NULL"#,
             provider_name
         )
@@ -264,7 +277,7 @@ impl ProductRepositoryTrait for ProductRepository {
                 id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             FROM products WHERE name = $1 AND deleted_at IS NULL"#,
             name
         )
@@ -299,7 +312,7 @@ mod tests {
             RETURNING id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             "#,
             name,
             Decimal::try_from(base_price).unwrap(),
@@ -382,6 +395,7 @@ mod tests {
             fulfillment_text: Some(Some("Digital code via email".to_string())),
             fulfillment_image_id: Some(Some(new_fulfillment_image_id)),
             external_id: Some(Some("EXT123".to_string())),
+            hidden: None,
         };
 
         let _updated_product = repo.update(initial_product.id, update_data).await.unwrap();
@@ -426,7 +440,7 @@ mod tests {
             RETURNING id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, fulfillment_text, fulfillment_image_id,
                 provider_name, external_id, created_by, created_at, updated_at, deleted_at,
-                stock
+                hidden_at, stock
             "#,
             "Old Book",
             Decimal::try_from(25.00).unwrap(),
@@ -456,6 +470,7 @@ mod tests {
             fulfillment_text: Some(Some("Download link".to_string())), // Update
             fulfillment_image_id: None,           // Keep original
             external_id: None,                    // Keep original
+            hidden: None,
         };
 
         let _updated_product = repo.update(initial_product.id, update_data).await.unwrap();
@@ -499,6 +514,7 @@ mod tests {
             fulfillment_text: None,
             fulfillment_image_id: None,
             external_id: None,
+            hidden: None,
         };
 
         let _updated_product = repo.update(initial_product.id, update_data).await.unwrap();
@@ -604,6 +620,37 @@ mod tests {
         assert!(result.total >= 3); // Account for other tests creating products
     }
 
+    #[sqlx::test]
+    async fn test_get_list_search(pool: PgPool) {
+        let repo = ProductRepository::new(Arc::new(pool.clone()));
+        let category_id = create_test_category(&pool, "SearchCategory").await;
+
+        let spotify = create_test_product(&pool, "Spotify", 10.0, category_id).await;
+        create_test_product(&pool, "Netflix Premium", 20.0, category_id).await;
+
+        // Substring, typo-tolerant and id matches
+        for term in ["SPOT", "spotfy", &spotify.id.to_string()] {
+            let result = repo
+                .get_list(ProductListQuery {
+                    search: Some(term.to_string()),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            assert_eq!(result.total, 1, "search term {term}");
+            assert_eq!(result.items[0].id, spotify.id);
+        }
+
+        let result = repo
+            .get_list(ProductListQuery {
+                search: Some("hulu".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.total, 0);
+    }
+
     #[sqlx::test]
     async fn test_get_for_external_provider(pool: PgPool) {
         let repo = ProductRepository::new(Arc::new(pool.clone()));
@@ -0,0 +1,433 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use shared_dtos::reconciliation::ReconciliationDiscrepancyKind;
+use sqlx::{PgPool, QueryBuilder, types::Json};
+
+use crate::{
+    errors::repository::RepositoryResult,
+    infrastructure::lib::query::{apply_filters, apply_list_query},
+    models::{
+        common::PaginatedResult,
+        reconciliation::{
+            NewReconciliationReport, ReconciliationCounts, ReconciliationDiscrepancy,
+            ReconciliationReportListQuery, ReconciliationReportRow,
+        },
+    },
+};
+
+#[async_trait]
+pub trait ReconciliationRepositoryTrait {
+    async fn get_list(
+        &self,
+        query: ReconciliationReportListQuery,
+    ) -> RepositoryResult<PaginatedResult<ReconciliationReportRow>>;
+    async fn get_by_day(&self, day: NaiveDate)
+    -> RepositoryResult<Option<ReconciliationReportRow>>;
+    /// Checks transactions created and invoices completed within `[from, to)`
+    async fn find_discrepancies(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ReconciliationDiscrepancy>>;
+    async fn count_checked(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> RepositoryResult<ReconciliationCounts>;
+    /// Stores the report, replacing an earlier report of the same day
+    async fn save(
+        &self,
+        report: NewReconciliationReport,
+    ) -> RepositoryResult<ReconciliationReportRow>;
+}
+
+#[derive(Clone)]
+pub struct ReconciliationRepository {
+    pool: Arc<PgPool>,
+}
+
+impl ReconciliationRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ReconciliationRepositoryTrait for ReconciliationRepository {
+    async fn get_list(
+        &self,
+        query: ReconciliationReportListQuery,
+    ) -> RepositoryResult<PaginatedResult<ReconciliationReportRow>> {
+        let mut count_qb = QueryBuilder::new("SELECT COUNT(*) FROM reconciliation_reports");
+        apply_filters(&mut count_qb, &query);
+
+        let count_query = count_qb.build_query_scalar();
+        let total: i64 = count_query.fetch_one(&*self.pool).await?;
+
+        let mut query_builder = QueryBuilder::new("SELECT * FROM reconciliation_reports");
+        apply_list_query(&mut query_builder, &query);
+        let items_query = query_builder.build_query_as::<ReconciliationReportRow>();
+        let items = items_query.fetch_all(&*self.pool).await?;
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
+    }
+
+    async fn get_by_day(
+        &self,
+        day: NaiveDate,
+    ) -> RepositoryResult<Option<ReconciliationReportRow>> {
+        let result = sqlx::query_as!(
+            ReconciliationReportRow,
+            r#"
+            SELECT
+                id, day, transactions_checked, invoices_checked, customers_checked,
+                discrepancies as "discrepancies: _", triggered_by, created_at
+            FROM reconciliation_reports
+            WHERE day = $1
+            "#,
+            day
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn find_discrepancies(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> RepositoryResult<Vec<ReconciliationDiscrepancy>> {
+        let result = sqlx::query_as!(
+            ReconciliationDiscrepancy,
+            r#"
+            WITH day_transactions AS (
+                SELECT id, customer_id, amount, user_balance_after, store_balance_delta, store_balance_after
+                FROM transactions
+                WHERE created_at >= $1 AND created_at < $2
+            ),
+            day_invoices AS (
+                SELECT id
+                FROM payment_invoices
+                WHERE status = 'completed'
+                  AND COALESCE(finished_at, updated_at) >= $1
+                  AND COALESCE(finished_at, updated_at) < $2
+            )
+            SELECT
+                'customer_balance_mismatch' AS "kind!: ReconciliationDiscrepancyKind",
+                c.id AS "entity_id!",
+                COALESCE(SUM(t.amount), 0)::NUMERIC AS "expected!",
+                c.balance::NUMERIC AS "actual!"
+            FROM customers c
+            LEFT JOIN transactions t ON t.customer_id = c.id
+            WHERE c.id IN (SELECT customer_id FROM day_transactions)
+            GROUP BY c.id
+            HAVING c.balance <> COALESCE(SUM(t.amount), 0)
+
+            UNION ALL
+
+            SELECT 'user_balance_chain_broken', t.id, prev.balance + t.amount, t.user_balance_after
+            FROM day_transactions t
+            CROSS JOIN LATERAL (
+                SELECT COALESCE((
+                    SELECT p.user_balance_after
+                    FROM transactions p
+                    WHERE p.customer_id = t.customer_id AND p.id < t.id
+                    ORDER BY p.id DESC
+                    LIMIT 1
+                ), 0) AS balance
+            ) prev
+            WHERE t.customer_id IS NOT NULL AND t.user_balance_after <> prev.balance + t.amount
+
+            UNION ALL
+
+            SELECT 'store_balance_chain_broken', t.id, prev.balance + t.store_balance_delta, t.store_balance_after
+            FROM day_transactions t
+            CROSS JOIN LATERAL (
+                SELECT COALESCE((
+                    SELECT p.store_balance_after
+                    FROM transactions p
+                    WHERE p.id < t.id
+                    ORDER BY p.id DESC
+                    LIMIT 1
+                ), 0) AS balance
+            ) prev
+            WHERE t.store_balance_after <> prev.balance + t.store_balance_delta
+
+            UNION ALL
+
+            SELECT
+                CASE WHEN COUNT(t.id) = 0 THEN 'invoice_missing_deposit' ELSE 'invoice_duplicate_deposits' END,
+                i.id,
+                1,
+                COUNT(t.id)
+            FROM day_invoices i
+            LEFT JOIN transactions t ON t.payment_invoice_id = i.id AND t.type = 'deposit'
+            GROUP BY i.id
+            HAVING COUNT(t.id) <> 1
+
+            ORDER BY 1, 2
+            "#,
+            from,
+            to
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn count_checked(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> RepositoryResult<ReconciliationCounts> {
+        let result = sqlx::query_as!(
+            ReconciliationCounts,
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM transactions WHERE created_at >= $1 AND created_at < $2)::INT
+                    AS "transactions_checked!",
+                (
+                    SELECT COUNT(*) FROM payment_invoices
+                    WHERE status = 'completed'
+                      AND COALESCE(finished_at, updated_at) >= $1
+                      AND COALESCE(finished_at, updated_at) < $2
+                )::INT AS "invoices_checked!",
+                (
+                    SELECT COUNT(DISTINCT customer_id) FROM transactions
+                    WHERE created_at >= $1 AND created_at < $2
+                )::INT AS "customers_checked!"
+            "#,
+            from,
+            to
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn save(
+        &self,
+        report: NewReconciliationReport,
+    ) -> RepositoryResult<ReconciliationReportRow> {
+        let result = sqlx::query_as!(
+            ReconciliationReportRow,
+            r#"
+            INSERT INTO reconciliation_reports (
+                day, transactions_checked, invoices_checked, customers_checked,
+                discrepancies, triggered_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (day) DO UPDATE SET
+                transactions_checked = EXCLUDED.transactions_checked,
+                invoices_checked = EXCLUDED.invoices_checked,
+                customers_checked = EXCLUDED.customers_checked,
+                discrepancies = EXCLUDED.discrepancies,
+                triggered_by = EXCLUDED.triggered_by,
+                created_at = NOW()
+            RETURNING
+                id, day, transactions_checked, invoices_checked, customers_checked,
+                discrepancies as "discrepancies: _", triggered_by, created_at
+            "#,
+            report.day,
+            report.counts.transactions_checked,
+            report.counts.invoices_checked,
+            report.counts.customers_checked,
+            Json(report.discrepancies) as _,
+            report.triggered_by
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    async fn create_customer(pool: &PgPool, telegram_id: i64) -> i64 {
+        sqlx::query_scalar!(
+            "INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot) VALUES ($1, 1, 1) RETURNING id",
+            telegram_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn create_completed_invoice(pool: &PgPool, customer_id: i64) -> i64 {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO payment_invoices (
+                customer_id, original_amount, amount, amount_in_usdt, status, expires_at,
+                gateway, gateway_invoice_id, finished_at
+            )
+            VALUES ($1, 100, 100, 1, 'completed', NOW() + INTERVAL '1 hour', 'mock', gen_random_uuid()::TEXT, NOW())
+            RETURNING id
+            "#,
+            customer_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn create_deposit(pool: &PgPool, customer_id: i64, invoice_id: Option<i64>) -> i64 {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO transactions (customer_id, type, amount, store_balance_delta, payment_invoice_id)
+            VALUES ($1, 'deposit', 100, 80, $2)
+            RETURNING id
+            "#,
+            customer_id,
+            invoice_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn today() -> (DateTime<Utc>, DateTime<Utc>) {
+        let from = Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        (from, from + Duration::days(1))
+    }
+
+    #[sqlx::test]
+    async fn test_consistent_ledger_has_no_discrepancies(pool: PgPool) {
+        let repo = ReconciliationRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_customer(&pool, 1001).await;
+        let invoice_id = create_completed_invoice(&pool, customer_id).await;
+        create_deposit(&pool, customer_id, Some(invoice_id)).await;
+
+        let (from, to) = today();
+        assert!(repo.find_discrepancies(from, to).await.unwrap().is_empty());
+
+        let counts = repo.count_checked(from, to).await.unwrap();
+        assert_eq!(
+            counts,
+            ReconciliationCounts {
+                transactions_checked: 1,
+                invoices_checked: 1,
+                customers_checked: 1,
+            }
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_finds_every_discrepancy_kind(pool: PgPool) {
+        let repo = ReconciliationRepository::new(Arc::new(pool.clone()));
+        let customer_id = create_customer(&pool, 1002).await;
+        let missing_invoice = create_completed_invoice(&pool, customer_id).await;
+        let duplicate_invoice = create_completed_invoice(&pool, customer_id).await;
+        create_deposit(&pool, customer_id, Some(duplicate_invoice)).await;
+        let last_tx = create_deposit(&pool, customer_id, Some(duplicate_invoice)).await;
+
+        sqlx::query!(
+            "UPDATE transactions SET user_balance_after = 150, store_balance_after = 1 WHERE id = $1",
+            last_tx
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "UPDATE customers SET balance = 150 WHERE id = $1",
+            customer_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (from, to) = today();
+        let discrepancies = repo.find_discrepancies(from, to).await.unwrap();
+        assert_eq!(
+            discrepancies,
+            vec![
+                ReconciliationDiscrepancy {
+                    kind: ReconciliationDiscrepancyKind::CustomerBalanceMismatch,
+                    entity_id: customer_id,
+                    expected: dec!(200),
+                    actual: dec!(150),
+                },
+                ReconciliationDiscrepancy {
+                    kind: ReconciliationDiscrepancyKind::InvoiceDuplicateDeposits,
+                    entity_id: duplicate_invoice,
+                    expected: dec!(1),
+                    actual: dec!(2),
+                },
+                ReconciliationDiscrepancy {
+                    kind: ReconciliationDiscrepancyKind::InvoiceMissingDeposit,
+                    entity_id: missing_invoice,
+                    expected: dec!(1),
+                    actual: dec!(0),
+                },
+                ReconciliationDiscrepancy {
+                    kind: ReconciliationDiscrepancyKind::StoreBalanceChainBroken,
+                    entity_id: last_tx,
+                    expected: dec!(160),
+                    actual: dec!(1),
+                },
+                ReconciliationDiscrepancy {
+                    kind: ReconciliationDiscrepancyKind::UserBalanceChainBroken,
+                    entity_id: last_tx,
+                    expected: dec!(200),
+                    actual: dec!(150),
+                },
+            ]
+        );
+
+        // Other days are not affected
+        assert!(
+            repo.find_discrepancies(from - Duration::days(1), from)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_save_replaces_report_of_the_same_day(pool: PgPool) {
+        let repo = ReconciliationRepository::new(Arc::new(pool));
+        let day = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+
+        let first = repo
+            .save(NewReconciliationReport {
+                day,
+                counts: ReconciliationCounts::default(),
+                discrepancies: vec![ReconciliationDiscrepancy {
+                    kind: ReconciliationDiscrepancyKind::InvoiceMissingDeposit,
+                    entity_id: 1,
+                    expected: dec!(1),
+                    actual: dec!(0),
+                }],
+                triggered_by: None,
+            })
+            .await
+            .unwrap();
+        let second = repo
+            .save(NewReconciliationReport {
+                day,
+                counts: ReconciliationCounts::default(),
+                discrepancies: vec![],
+                triggered_by: Some(1),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(first.id, second.id);
+        let stored = repo.get_by_day(day).await.unwrap().unwrap();
+        assert!(stored.discrepancies.is_empty());
+        assert_eq!(stored.triggered_by, Some(1));
+    }
+}
@@ -5,7 +5,7 @@ use sqlx::{PgPool, QueryBuilder};
 
 use crate::{
     errors::repository::RepositoryResult,
-    infrastructure::lib::query::{apply_filters, apply_list_query},
+    infrastructure::lib::query::{apply_filters, apply_list_query, into_paginated_result},
     models::{
         common::PaginatedResult,
         stock_movement::{NewStockMovement, StockMovementListQuery, StockMovementRow},
@@ -45,28 +45,33 @@ impl StockMovementRepositoryTrait for StockMovementRepository {
         let count_query = count_qb.build_query_scalar();
         let total: i64 = count_query.fetch_one(&*self.pool).await?;
 
+        // Wrapped so filter columns don't clash with `products.id`, `type`, `created_at`...
         let mut query_builder = QueryBuilder::new(
             r#"
-        SELECT
-            sm.id,
-            sm.order_id,
-            sm.product_id,
-            sm.type,
-            sm.quantity,
-            sm.created_by,
-            sm.description,
-            sm.reference_id,
-            sm.balance_after,
-            sm.created_at,
-            p.name AS product_name
-        FROM stock_movements sm
-        LEFT JOIN products p ON sm.product_id = p.id
+        SELECT * FROM (
+            SELECT
+                sm.id,
+                sm.order_id,
+                sm.product_id,
+                sm.type,
+                sm.quantity,
+                sm.created_by,
+                sm.description,
+                sm.reference_id,
+                sm.balance_after,
+                sm.created_at,
+                p.name AS product_name
+            FROM stock_movements sm
+            LEFT JOIN products p ON sm.product_id = p.id
+        ) sm
         "#,
         );
         apply_list_query(&mut query_builder, &query);
-        let query = query_builder.build_query_as::<StockMovementRow>();
-        let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        let items = query_builder
+            .build_query_as::<StockMovementRow>()
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(into_paginated_result(&query, items, total, |row| row.id))
     }
 
     async fn create(&self, stock_movement: NewStockMovement) -> RepositoryResult<StockMovementRow> {
@@ -148,13 +153,18 @@ impl StockMovementRepositoryTrait for StockMovementRepository {
 #[cfg(test)]
 mod tests {
     use rust_decimal::Decimal;
-    use shared_dtos::stock_movement::StockMovementType;
+    use shared_dtos::{
+        list_query::{FilterValue, Operator, ScalarValue},
+        stock_movement::StockMovementType,
+    };
 
     use crate::models::admin_user::{AdminUserRow, NewAdminUser};
     use crate::models::bot::BotRow;
+    use crate::models::common::Filter;
     use crate::models::customer::CustomerRow;
     use crate::models::order::OrderRow;
     use crate::models::product::ProductRow;
+    use crate::models::stock_movement::StockMovementFilterFields;
 
     use super::*;
 
@@ -220,7 +230,7 @@ mod tests {
                 id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, deleted_at, fulfillment_text, 
                 fulfillment_image_id, provider_name, external_id, created_at, 
-                updated_at, created_by, stock
+                updated_at, created_by, stock, hidden_at
             FROM products WHERE id = $1"#,
             product_id
         )
@@ -266,7 +276,10 @@ mod tests {
             r#"
             INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot)
             VALUES ($1, $2, $3)
-            RETURNING *
+            RETURNING
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
             "#,
             telegram_id,
             bot_id,
@@ -508,6 +521,58 @@ mod tests {
         assert!(repo.create(valid_sale).await.is_ok());
     }
 
+    #[sqlx::test]
+    async fn test_get_list_filters_by_columns_shared_with_products(pool: PgPool) {
+        let admin_user = create_test_user(&pool, "stock_admin_list").await;
+        let product = create_test_product(&pool, "product_list", admin_user.id).await;
+
+        let repo = StockMovementRepository::new(Arc::new(pool.clone()));
+        let initial = repo
+            .create(NewStockMovement {
+                order_id: None,
+                product_id: product.id,
+                r#type: StockMovementType::Initial,
+                quantity: 10,
+                created_by: admin_user.id,
+                description: None,
+                reference_id: None,
+            })
+            .await
+            .unwrap();
+        repo.create(NewStockMovement {
+            order_id: None,
+            product_id: product.id,
+            r#type: StockMovementType::Adjustment,
+            quantity: -2,
+            created_by: admin_user.id,
+            description: None,
+            reference_id: None,
+        })
+        .await
+        .unwrap();
+
+        let query = StockMovementListQuery {
+            filters: vec![
+                Filter {
+                    field: StockMovementFilterFields::Type,
+                    op: Operator::Eq,
+                    value: FilterValue::Scalar(ScalarValue::Text("initial".to_string())),
+                },
+                Filter {
+                    field: StockMovementFilterFields::CreatedAt,
+                    op: Operator::Ge,
+                    value: FilterValue::Scalar(ScalarValue::DateTime(initial.created_at)),
+                },
+            ],
+            ..Default::default()
+        };
+        let result = repo.get_list(query).await.unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].id, initial.id);
+        assert_eq!(result.items[0].product_name, "product_list");
+    }
+
     #[sqlx::test]
     async fn test_get_last_by_product_id(pool: PgPool) {
         let admin_user = create_test_user(&pool, "stock_admin_get_last").await;
@@ -63,7 +63,11 @@ impl StoreBalanceRequestRepositoryTrait for StoreBalanceRequestRepository {
         apply_list_query(&mut query_builder, &query);
         let query = query_builder.build_query_as::<StoreBalanceRequestRow>();
         let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
     }
 
     async fn get_by_id(&self, id: i64) -> RepositoryResult<StoreBalanceRequestRow> {
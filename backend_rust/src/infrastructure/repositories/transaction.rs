@@ -1,11 +1,11 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use sqlx::{PgPool, QueryBuilder};
+use sqlx::{PgExecutor, PgPool, QueryBuilder};
 
 use crate::{
     errors::repository::RepositoryResult,
-    infrastructure::lib::query::{apply_filters, apply_list_query},
+    infrastructure::lib::query::{apply_filters, apply_list_query, into_paginated_result},
     models::{
         common::PaginatedResult,
         transaction::{NewTransaction, TransactionListQuery, TransactionRow},
@@ -50,46 +50,19 @@ impl TransactionRepositoryTrait for TransactionRepository {
         SELECT
             id, customer_id, order_id, type, amount, store_balance_delta,
             platform_commission, gateway_commission, description, payment_gateway,
-            details, created_at, store_balance_after, user_balance_after, bot_id
+            details, created_at, store_balance_after, user_balance_after, bot_id, payment_invoice_id
         FROM transactions"#,
         );
         apply_list_query(&mut query_builder, &query);
-        let query = query_builder.build_query_as::<TransactionRow>();
-        let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        let items = query_builder
+            .build_query_as::<TransactionRow>()
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(into_paginated_result(&query, items, total, |row| row.id))
     }
 
     async fn create(&self, transaction: NewTransaction) -> RepositoryResult<TransactionRow> {
-        let result = sqlx::query_as!(
-            TransactionRow,
-            r#"
-            INSERT INTO transactions (
-                customer_id, order_id, type, amount, store_balance_delta,
-                platform_commission, gateway_commission,
-                description, payment_gateway, details, bot_id
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-            RETURNING
-                id, customer_id, order_id, type as "type: _", amount, store_balance_delta,
-                platform_commission, gateway_commission, description, payment_gateway as "payment_gateway: _",
-                details, created_at, store_balance_after, user_balance_after, bot_id
-            "#,
-            transaction.customer_id,
-            transaction.order_id,
-            transaction.r#type as _,
-            transaction.amount,
-            transaction.store_balance_delta,
-            transaction.platform_commission,
-            transaction.gateway_commission,
-            transaction.description,
-            transaction.payment_gateway as _,
-            transaction.details,
-            transaction.bot_id
-        )
-        .fetch_one(&*self.pool)
-        .await?;
-
-        Ok(result)
+        insert_transaction(&*self.pool, transaction).await
     }
 
     async fn get_last(&self) -> RepositoryResult<TransactionRow> {
@@ -99,7 +72,7 @@ impl TransactionRepositoryTrait for TransactionRepository {
             SELECT
                 id, customer_id, order_id, type as "type: _", amount, store_balance_delta,
                 platform_commission, gateway_commission, description, payment_gateway as "payment_gateway: _",
-                details, created_at, store_balance_after, user_balance_after, bot_id
+                details, created_at, store_balance_after, user_balance_after, bot_id, payment_invoice_id
             FROM transactions
             ORDER BY id DESC
             LIMIT 1
@@ -111,6 +84,43 @@ impl TransactionRepositoryTrait for TransactionRepository {
     }
 }
 
+pub async fn insert_transaction(
+    executor: impl PgExecutor<'_>,
+    transaction: NewTransaction,
+) -> RepositoryResult<TransactionRow> {
+    let result = sqlx::query_as!(
+        TransactionRow,
+        r#"
+        INSERT INTO transactions (
+            customer_id, order_id, type, amount, store_balance_delta,
+            platform_commission, gateway_commission,
+            description, payment_gateway, details, bot_id, payment_invoice_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING
+            id, customer_id, order_id, type as "type: _", amount, store_balance_delta,
+            platform_commission, gateway_commission, description, payment_gateway as "payment_gateway: _",
+            details, created_at, store_balance_after, user_balance_after, bot_id, payment_invoice_id
+        "#,
+        transaction.customer_id,
+        transaction.order_id,
+        transaction.r#type as _,
+        transaction.amount,
+        transaction.store_balance_delta,
+        transaction.platform_commission,
+        transaction.gateway_commission,
+        transaction.description,
+        transaction.payment_gateway as _,
+        transaction.details,
+        transaction.bot_id,
+        transaction.payment_invoice_id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::models::common::Filter;
@@ -171,6 +181,7 @@ mod tests {
             payment_gateway: None,
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         };
         repo.create(tx1).await.unwrap();
 
@@ -186,6 +197,7 @@ mod tests {
             payment_gateway: None,
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         };
         let last_tx_created = repo.create(tx2).await.unwrap();
 
@@ -214,6 +226,7 @@ mod tests {
             payment_gateway: None,
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         })
         .await
         .unwrap();
@@ -229,6 +242,7 @@ mod tests {
             payment_gateway: None,
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         })
         .await
         .unwrap();
@@ -246,6 +260,7 @@ mod tests {
             payment_gateway: None,
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         })
         .await
         .unwrap();
@@ -314,6 +329,7 @@ mod tests {
             payment_gateway: Some(PaymentSystem::Mock),
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         };
 
         let result1 = repo.create(deposit_tx).await.unwrap();
@@ -324,7 +340,14 @@ mod tests {
         // Verify customer balance after deposit
         let customer = sqlx::query_as!(
             crate::models::customer::CustomerRow, // Full path for CustomerRow
-            "SELECT * FROM customers WHERE id = $1",
+            r#"
+            SELECT
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
+            FROM customers
+            WHERE id = $1
+            "#,
             customer_id
         )
         .fetch_one(&pool)
@@ -345,6 +368,7 @@ mod tests {
             payment_gateway: None,
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         };
 
         let result2 = repo.create(purchase_tx).await.unwrap();
@@ -355,7 +379,14 @@ mod tests {
         // Verify customer balance after purchase
         let customer = sqlx::query_as!(
             crate::models::customer::CustomerRow, // Full path for CustomerRow
-            "SELECT * FROM customers WHERE id = $1",
+            r#"
+            SELECT
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
+            FROM customers
+            WHERE id = $1
+            "#,
             customer_id
         )
         .fetch_one(&pool)
@@ -381,6 +412,7 @@ mod tests {
             payment_gateway: Some(PaymentSystem::Mock),
             details: None,
             bot_id: Some(bot_id),
+            payment_invoice_id: None,
         };
 
         let created = repo.create(tx).await.unwrap();
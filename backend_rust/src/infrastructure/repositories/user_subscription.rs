@@ -66,7 +66,11 @@ impl UserSubscriptionRepositoryTrait for UserSubscriptionRepository {
         apply_list_query(&mut query_builder, &query);
         let query = query_builder.build_query_as::<UserSubscriptionRow>();
         let items = query.fetch_all(&*self.pool).await?;
-        Ok(PaginatedResult { items, total })
+        Ok(PaginatedResult {
+            items,
+            total,
+            next_cursor: None,
+        })
     }
 
     async fn create(
@@ -207,7 +211,10 @@ mod tests {
             r#"
             INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot)
             VALUES ($1, $2, $3)
-            RETURNING *
+            RETURNING
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
             "#,
             telegram_id,
             bot_id,
@@ -281,7 +288,7 @@ mod tests {
                 id, name, base_price, category_id, image_id, type as "type: _",
                 subscription_period_days, details, deleted_at, fulfillment_text,
                 fulfillment_image_id, provider_name, external_id, created_at,
-                updated_at, created_by, stock
+                updated_at, created_by, stock, hidden_at
             FROM products WHERE id = $1"#,
             product_id
         )
@@ -374,12 +381,15 @@ mod tests {
 
         let list_query = UserSubscriptionListQuery {
             filters: vec![],
+            groups: vec![],
             pagination: Pagination {
                 page: 1,
                 page_size: 10,
             },
             order_by: None,
             order_dir: OrderDir::Desc,
+            cursor: None,
+            search: None,
         };
         let result = repo.get_list(list_query).await.unwrap();
         assert_eq!(result.total, 1);
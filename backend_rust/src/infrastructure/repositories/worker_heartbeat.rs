@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::{errors::repository::RepositoryResult, models::worker_heartbeat::WorkerHeartbeatRow};
+
+#[async_trait]
+pub trait WorkerHeartbeatRepositoryTrait {
+    /// Round trip to the database, used by readiness checks
+    async fn ping(&self) -> RepositoryResult<()>;
+    /// Records a finished worker run; `last_success_at` only moves on runs without errors
+    async fn record_run(&self, worker: &str, errors: i32) -> RepositoryResult<()>;
+    async fn get_all(&self) -> RepositoryResult<Vec<WorkerHeartbeatRow>>;
+}
+
+#[derive(Clone)]
+pub struct WorkerHeartbeatRepository {
+    pool: Arc<PgPool>,
+}
+
+impl WorkerHeartbeatRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WorkerHeartbeatRepositoryTrait for WorkerHeartbeatRepository {
+    async fn ping(&self) -> RepositoryResult<()> {
+        sqlx::query!("SELECT 1 AS one")
+            .fetch_one(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn record_run(&self, worker: &str, errors: i32) -> RepositoryResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO worker_heartbeats (worker, last_run_at, last_success_at, last_run_errors)
+            VALUES ($1, NOW(), CASE WHEN $2 = 0 THEN NOW() END, $2)
+            ON CONFLICT (worker) DO UPDATE SET
+                last_run_at = EXCLUDED.last_run_at,
+                last_success_at = COALESCE(EXCLUDED.last_success_at, worker_heartbeats.last_success_at),
+                last_run_errors = EXCLUDED.last_run_errors
+            "#,
+            worker,
+            errors
+        )
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_all(&self) -> RepositoryResult<Vec<WorkerHeartbeatRow>> {
+        let result = sqlx::query_as!(
+            WorkerHeartbeatRow,
+            "SELECT * FROM worker_heartbeats ORDER BY worker"
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_record_run_keeps_last_success(pool: PgPool) {
+        let repo = WorkerHeartbeatRepository::new(Arc::new(pool));
+        repo.ping().await.unwrap();
+
+        repo.record_run("broadcasts", 0).await.unwrap();
+        let first = repo.get_all().await.unwrap().remove(0);
+        assert_eq!(first.worker, "broadcasts");
+        assert_eq!(first.last_success_at, Some(first.last_run_at));
+
+        // A failed run moves `last_run_at` but keeps the last success
+        repo.record_run("broadcasts", 2).await.unwrap();
+        let failed = repo.get_all().await.unwrap().remove(0);
+        assert_eq!(failed.last_run_errors, 2);
+        assert_eq!(failed.last_success_at, first.last_success_at);
+        assert!(failed.last_run_at >= first.last_run_at);
+
+        repo.record_run("reconciliation", 1).await.unwrap();
+        let all = repo.get_all().await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all[1].last_success_at.is_none());
+    }
+}
@@ -8,11 +8,16 @@ pub mod models;
 pub mod presentation;
 pub mod services;
 pub mod state;
+pub mod telemetry;
 pub mod workers;
 
 use std::sync::Arc;
 
-use axum::{Router, http, routing::get};
+use axum::{Json, Router, extract::State, http, http::StatusCode, routing::get};
+use shared_dtos::{
+    health::{ReadinessResponse, ReadinessStatus},
+    list_query::{FilterValue, Operator, ScalarValue},
+};
 use tower_http::{
     cors::CorsLayer,
     trace::{DefaultMakeSpan, DefaultOnFailure, TraceLayer},
@@ -26,12 +31,68 @@ use tracing_subscriber::{
     util::SubscriberInitExt,
 };
 
-use crate::state::AppState;
+use crate::{
+    models::{
+        common::Filter,
+        payment_invoice::{PaymentInvoiceFilterFields, PaymentInvoiceListQuery},
+    },
+    services::{
+        admin_api_key::API_KEY_HEADER, health::HealthServiceTrait,
+        payment_invoice::PaymentInvoiceServiceTrait,
+    },
+    state::AppState,
+};
 
 pub async fn healthz() -> &'static str {
     "healthy"
 }
 
+/// Per-dependency readiness; 503 only when a critical dependency (Postgres) is down, a
+/// degraded instance keeps serving with the affected features switched off
+pub async fn readyz(
+    State(app_state): State<Arc<AppState>>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let report = app_state.health_service.readiness().await;
+    let status = match report.status {
+        ReadinessStatus::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        ReadinessStatus::Ok | ReadinessStatus::Degraded => StatusCode::OK,
+    };
+    (status, Json(report))
+}
+
+/// Prometheus scrape endpoint. Business gauges are refreshed here rather than by a worker,
+/// so they are current as of the scrape.
+pub async fn metrics(State(app_state): State<Arc<AppState>>) -> String {
+    let mut query = PaymentInvoiceListQuery::default();
+    query.filters.push(Filter {
+        field: PaymentInvoiceFilterFields::Status,
+        op: Operator::Eq,
+        value: FilterValue::Scalar(ScalarValue::Text("pending".to_string())),
+    });
+    query.pagination.page_size = 1;
+    match app_state.payment_invoice_service.get_list(query).await {
+        Ok(pending) => telemetry::set_pending_invoices(pending.total),
+        Err(e) => tracing::error!("Failed to count pending invoices for metrics: {e}"),
+    }
+
+    telemetry::render()
+}
+
+/// Router served in worker-only mode, so workers stay probeable
+pub fn create_worker_app(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(app_state)
+}
+
+/// Router of the internal metrics listener on `metrics_port`
+pub fn create_metrics_app(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(app_state)
+}
+
 pub fn create_app(app_state: Arc<AppState>) -> Router {
     use axum::http::Method;
     use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE};
@@ -52,15 +113,30 @@ pub fn create_app(app_state: Arc<AppState>) -> Router {
             Method::PATCH,
             Method::OPTIONS,
         ])
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE])
+        .allow_headers([
+            AUTHORIZATION,
+            ACCEPT,
+            CONTENT_TYPE,
+            http::HeaderName::from_bytes(API_KEY_HEADER.as_bytes()).unwrap(),
+        ])
         .allow_credentials(true);
 
-    Router::new()
+    let client_ip_source = app_state.config.client_ip_source.clone();
+
+    let router = Router::new()
         .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
         .nest("/api/admin", presentation::admin::router::router())
-        .nest("/api/bot", presentation::bot::router::router())
+        .nest(
+            "/api/bot",
+            presentation::bot::router::router().layer(axum::middleware::from_fn_with_state(
+                app_state.clone(),
+                middlewares::idempotency::idempotency,
+            )),
+        )
         .nest("/api/webhook", presentation::webhook::router::router())
         .nest("/api", presentation::images::router::router())
+        .route_layer(axum::middleware::from_fn(telemetry::track_http))
         .layer(cors)
         .layer(
             TraceLayer::new_for_http()
@@ -78,8 +154,13 @@ pub fn create_app(app_state: Arc<AppState>) -> Router {
                     }
                 })
                 .on_failure(DefaultOnFailure::new().level(Level::ERROR)),
-        )
-        .with_state(app_state)
+        );
+
+    match client_ip_source {
+        Some(source) => router.layer(source.into_extension()),
+        None => router,
+    }
+    .with_state(app_state)
 }
 
 pub fn init_tracing() {
@@ -118,3 +199,71 @@ pub async fn run_migrations(pool: &sqlx::PgPool) -> Result<(), sqlx::migrate::Mi
     tracing::info!("Running database migrations");
     sqlx::migrate!("./migrations").run(pool).await
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{config::Config, db::Database};
+
+    fn test_config() -> Config {
+        let vars = [
+            ("BACKEND_PORT", "3000"),
+            ("DATABASE_HOST", "localhost"),
+            ("DATABASE_PORT", "5432"),
+            ("DATABASE_USER", "postgres"),
+            ("DATABASE_PASSWORD", "postgres"),
+            ("DATABASE_NAME", "autosales"),
+            ("CORS_ORIGINS", "http://localhost"),
+            ("JWT_SECRET", "secret"),
+            (
+                "TOTP_ENCODE_SECRET",
+                "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+            ),
+            ("TWO_FA_TOKEN_TTL_MINUTES", "5"),
+            ("ACCESS_TOKEN_TTL_MINUTES", "15"),
+            ("REFRESH_TOKEN_TTL_MINUTES", "60"),
+            ("IMAGE_UPLOAD_PATH", "uploads"),
+            ("SERVICE_API_KEY", "service"),
+            ("BOT_DISPATCHER_WEBHOOK_URL", "http://localhost"),
+            ("BOT_ADMIN_DISPATCHER_WEBHOOK_URL", "http://localhost"),
+            ("CAPTCHA_API_URL", "http://localhost"),
+            ("CONTMS_API_URL", "http://localhost"),
+            ("MOCK_PAYMENTS_PROVIDER_URL", "http://localhost"),
+            ("PAYMENT_NOTIFICATION_MINUTES", "10"),
+            ("SUBSCRIPTION_EXPIRY_NOTIFICATION_WINDOW_HOURS", "24"),
+            (
+                "SUBSCRIPTION_EXPIRY_NOTIFICATION_POLL_INTERVAL_SECONDS",
+                "60",
+            ),
+            ("PLATFORM_PAYMENT_SYSTEM_BASE_URL", "http://localhost"),
+            ("PLATFORM_PAYMENT_SYSTEM_LOGIN", "login"),
+            ("PLATFORM_PAYMENT_SYSTEM_PASSWORD", "password"),
+            ("PLATFORM_PAYMENT_SYSTEM_2FA_KEY", "JBSWY3DPEHPK3PXP"),
+            ("FILES_FM_UPLOAD_TOKEN", "token"),
+            ("FILES_FM_FOLDER_HASH", "hash"),
+        ];
+        envy::from_iter(vars.map(|(key, value)| (key.to_string(), value.to_string()))).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_metrics_are_only_served_on_the_metrics_router(pool: PgPool) {
+        let app_state = Arc::new(AppState::new(Database { pool }, test_config()));
+        let request = || Request::get("/metrics").body(Body::empty()).unwrap();
+
+        let public = create_app(app_state.clone())
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert_eq!(public.status(), StatusCode::NOT_FOUND);
+
+        let internal = create_metrics_app(app_state)
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert_eq!(internal.status(), StatusCode::OK);
+    }
+}
@@ -1,24 +1,43 @@
 use shared_dtos::{
+    accounting_export::{
+        AccountingExportEntity, AccountingExportFormat, AccountingExportResponse,
+        AccountingExportStatus,
+    },
     admin_user::{
         AdminUserWithRolesAdminResponse, NewAdminUserAdminRequest, UpdateAdminUserAdminRequest,
     },
     analytics::BotAnalyticsBotResponse,
+    api_key::{ApiKeyAdminResponse, CreatedApiKeyAdminResponse, NewApiKeyAdminRequest},
     audit_log::AuditLogAdminResponse,
     auth::{
-        LoginStep1AdminRequest, LoginStep1AdminResponse, LoginStep2AdminRequest,
-        LoginStep2AdminResponse,
+        ChangePasswordAdminRequest, CompleteTwoFaEnrollmentAdminRequest,
+        ConfirmTwoFaRotationAdminRequest, LoginRecoveryAdminRequest, LoginStep1AdminRequest,
+        LoginStep1AdminResponse, LoginStep2AdminRequest, LoginStep2AdminResponse,
+        ReauthenticateAdminRequest, RecoveryCodesAdminResponse, TwoFaEnrollmentAdminResponse,
+        TwoFaResetAdminResponse, TwoFaRotationAdminResponse,
     },
     bot::{
         BotAdminResponse, BotBotResponse, NewBotAdminRequest, NewBotBotRequest,
         UpdateBotAdminRequest, UpdateBotBotRequest,
     },
-    broadcast::{BroadcastResponse, NewBroadcastRequest},
+    broadcast::{
+        BroadcastAudiencePreviewRequest, BroadcastAudiencePreviewResponse, BroadcastButton,
+        BroadcastButtonAction, BroadcastDeliveryReportBotRequest, BroadcastMedia,
+        BroadcastMediaKind, BroadcastRecipientResponse, BroadcastRecipientStatus,
+        BroadcastResponse, BroadcastStatus, BroadcastTextEntity, BroadcastTextEntityKind,
+        BroadcastVariantResponse, BroadcastVariantStatsResponse, NewBroadcastRequest,
+        NewBroadcastVariantRequest, TestBroadcastRequest,
+    },
     can_operate::CanOperateBotResponse,
     captcha::CaptchaBotResponse,
     category::{CategoryAdminResponse, NewCategoryAdminRequest, UpdateCategoryAdminRequest},
+    currency::{Currency, ExchangeRateResponse, ExchangeRatesResponse},
     customer::{
         CustomerAdminResponse, CustomerBotResponse, NewCustomerBotRequest, UpdateCustomerBotRequest,
     },
+    customer_segment::{
+        CustomerSegmentResponse, NewCustomerSegmentRequest, UpdateCustomerSegmentRequest,
+    },
     dashboard::{
         CategorySalesResponse, DashboardOverviewResponse, SalesOverTimeResponse,
         StatWithTrendResponse, TimeSeriesDashboardDataResponse, TimeSeriesPointResponse,
@@ -27,37 +46,47 @@ use shared_dtos::{
     error::ApiErrorResponse,
     image::ImageAdminResponse,
     invoice::{
-        GatewayBotResponse, NewPaymentInvoiceBotRequest, PaymentInvoiceAdminResponse,
-        PaymentInvoiceBotResponse, UpdatePaymentInvoiceBotRequest,
+        GatewayBotResponse, GatewayCircuitState, GatewayHealthAdminResponse,
+        NewPaymentInvoiceBotRequest, PaymentInvoiceAdminResponse, PaymentInvoiceBotResponse,
+        PaymentInvoiceEventAdminResponse, PaymentInvoiceEventKind,
+        PaymentInvoiceHistoryAdminResponse, PaymentWebhookEventAdminResponse,
+        ResolvePaymentInvoiceAdminRequest, UpdatePaymentInvoiceBotRequest,
     },
     list_response::ListResponse,
     order::{
-        EnrichedOrderBotResponse, OrderAdminResponse, OrderItemBotResponse, PurchaseBotResponse,
+        CheckoutBotRequest, EnrichedOrderBotResponse, OrderAdminResponse, OrderItemBotResponse,
+        PurchaseBotResponse,
     },
     permission::PermissionAdminResponse,
     product::{
         NewProductAdminRequest, ProductAdminResponse, ProductType, UpdateProductAdminRequest,
     },
+    product_sync::{
+        ProductSyncRunResponse, ProductSyncStatus, ProductSyncTrigger, RunProductSyncRequest,
+    },
+    reconciliation::{
+        ReconciliationDiscrepancyKind, ReconciliationDiscrepancyResponse, ReconciliationEntity,
+        ReconciliationReportResponse, RunReconciliationRequest,
+    },
     role::{NewRoleAdminRequest, RoleAdminResponse, UpdateRoleAdminRequest},
     settings::{
-        BotSettingsAdminResponse, PricingSettingsAdminResponse, SettingsBotResponse,
-        UpdateBotSettingsAdminRequest, UpdatePricingSettingsAdminRequest,
+        BotSettingsAdminResponse, PaymentGatewaySettingsAdminResponse,
+        PricingSettingsAdminResponse, SettingsBotResponse, UpdateBotSettingsAdminRequest,
+        UpdatePaymentGatewaySettingsAdminRequest, UpdatePricingSettingsAdminRequest,
     },
     stock_movement::StockMovementAdminResponse,
     store_balance::StoreBalanceAdminResponse,
     transaction::TransactionAdminResponse,
 };
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 use tokio::signal;
 use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
-#[cfg(feature = "contms-provider")]
-use backend_rust::workers::contms_products_sync::contms_products_sync_task;
 use backend_rust::{
     config::Config,
-    create_app,
+    create_app, create_metrics_app, create_worker_app,
     db::Database,
     init_tracing,
     presentation::{
@@ -66,8 +95,12 @@ use backend_rust::{
     },
     run_migrations,
     state::AppState,
+    telemetry,
     workers::{
-        broadcasts::broadcasts_task, pending_payments::pending_payments_task,
+        accounting_exports::accounting_exports_task, broadcasts::broadcasts_task,
+        exchange_rates::exchange_rates_task, external_products_sync::external_products_sync_task,
+        idempotency_keys_cleanup::idempotency_keys_cleanup_task, leader::run_as_leader,
+        pending_payments::pending_payments_task, reconciliation::reconciliation_task,
         subscription_expiry_notifications::subscription_expiry_notifications_task,
     },
 };
@@ -77,11 +110,14 @@ use backend_rust::{
     modifiers(&SecurityAddon),
     security(
         ("admin_bearer" = []),
+        ("admin_api_key" = []),
         ("service_api_key" = [], "bot_id" = [])
     ),
     paths(
         admin_handlers::auth::login_step1,
         admin_handlers::auth::login_step2,
+        admin_handlers::auth::login_recovery,
+        admin_handlers::auth::complete_two_fa_enrollment,
         admin_handlers::auth::logout,
         admin_handlers::category::create_category,
         admin_handlers::category::delete_category,
@@ -94,6 +130,8 @@ use backend_rust::{
         admin_handlers::product::list_products,
         admin_handlers::product::update_product,
         admin_handlers::product::upload_products,
+        admin_handlers::product_sync::run_sync,
+        admin_handlers::product_sync::list_runs,
         admin_handlers::image::create_image,
         admin_handlers::image::delete_image,
         admin_handlers::image::list_images,
@@ -107,6 +145,7 @@ use backend_rust::{
         admin_handlers::admin_user::delete_admin_user,
         admin_handlers::admin_user::get_admin_user_permissions,
         admin_handlers::admin_user::update_admin_user_permissions,
+        admin_handlers::admin_user::reset_admin_user_two_fa,
         admin_handlers::role::list_roles,
         admin_handlers::role::create_role,
         admin_handlers::role::update_role,
@@ -118,11 +157,31 @@ use backend_rust::{
         admin_handlers::settings::get_pricing_settings,
         admin_handlers::settings::update_bot_settings,
         admin_handlers::settings::update_pricing_settings,
+        admin_handlers::settings::list_gateway_settings,
+        admin_handlers::settings::update_gateway_settings,
+        admin_handlers::settings::get_exchange_rates,
         admin_handlers::me::get_me,
         admin_handlers::me::get_me_permissions,
+        admin_handlers::me::change_my_password,
+        admin_handlers::me::rotate_my_two_fa,
+        admin_handlers::me::confirm_my_two_fa_rotation,
+        admin_handlers::me::regenerate_my_recovery_codes,
         admin_handlers::transaction::list_transactions,
+        admin_handlers::reconciliation::run_reconciliation,
+        admin_handlers::reconciliation::list_reports,
+        admin_handlers::reconciliation::get_report,
+        admin_handlers::accounting_export::export_transactions,
+        admin_handlers::accounting_export::export_orders,
+        admin_handlers::accounting_export::export_payment_invoices,
+        admin_handlers::accounting_export::export_stock_movements,
+        admin_handlers::accounting_export::list_exports,
+        admin_handlers::accounting_export::get_export,
+        admin_handlers::accounting_export::download_export,
         admin_handlers::store_balance::create_store_balance_request,
         admin_handlers::audit_log::list_audit_logs,
+        admin_handlers::api_key::create_api_key,
+        admin_handlers::api_key::list_api_keys,
+        admin_handlers::api_key::revoke_api_key,
         admin_handlers::stock_movement::list_stock_movement,
         admin_handlers::bot::create_bot,
         admin_handlers::bot::list_bots,
@@ -130,11 +189,31 @@ use backend_rust::{
         admin_handlers::order::list_orders,
         admin_handlers::store_balance::get_store_balance,
         admin_handlers::broadcast::create_broadcast,
+        admin_handlers::broadcast::list_broadcasts,
+        admin_handlers::broadcast::get_broadcast,
+        admin_handlers::broadcast::list_broadcast_recipients,
+        admin_handlers::broadcast::pause_broadcast,
+        admin_handlers::broadcast::resume_broadcast,
+        admin_handlers::broadcast::cancel_broadcast,
+        admin_handlers::broadcast::send_test_broadcast,
+        admin_handlers::broadcast::preview_broadcast_audience,
+        admin_handlers::broadcast::list_broadcast_variants,
+        admin_handlers::broadcast::get_broadcast_variant_stats,
+        admin_handlers::customer_segment::create_segment,
+        admin_handlers::customer_segment::list_segments,
+        admin_handlers::customer_segment::get_segment,
+        admin_handlers::customer_segment::update_segment,
+        admin_handlers::customer_segment::delete_segment,
         admin_handlers::dashboard::get_dashboard_stats,
         admin_handlers::dashboard::get_time_series,
         admin_handlers::dashboard::get_top_products,
         admin_handlers::dashboard::get_sales_by_category,
         admin_handlers::payment_invoice::list_payment_invoices,
+        admin_handlers::payment_invoice::get_payment_invoice_history,
+        admin_handlers::payment_invoice::complete_payment_invoice,
+        admin_handlers::payment_invoice::fail_payment_invoice,
+        admin_handlers::payment_invoice::resend_payment_invoice_receipt,
+        admin_handlers::gateway::get_gateways_health,
         bot_handlers::bot::create_bot,
         bot_handlers::bot::get_bot,
         bot_handlers::bot::list_bots,
@@ -160,14 +239,19 @@ use backend_rust::{
         bot_handlers::invoice::confirm_invoice,
         bot_handlers::invoice::cancel_invoice,
         bot_handlers::invoice::send_invoice_receipt,
+        bot_handlers::broadcast::report_delivery,
         bot_handlers::order::purchase,
+        bot_handlers::order::checkout,
         bot_handlers::order::get_order,
         bot_handlers::product::list_products,
         bot_handlers::product::get_product,
         bot_handlers::settings::get_settings,
+        bot_handlers::settings::get_exchange_rates,
         bot_handlers::store_balance::complete_store_balance_request,
         bot_handlers::store_balance::reject_store_balance_request,
         images_handlers::image::get_image,
+        webhook_handlers::payment::platform_card_webhook,
+        webhook_handlers::payment::platform_sbp_webhook,
         #[cfg(feature = "mock-payments-provider")]
         webhook_handlers::payment::mock_payments_provider_webhook,
     ),
@@ -179,19 +263,28 @@ use backend_rust::{
         NewProductAdminRequest,
         UpdateProductAdminRequest,
         ProductType,
+        ProductSyncRunResponse,
+        ProductSyncStatus,
+        ProductSyncTrigger,
+        RunProductSyncRequest,
         ImageAdminResponse,
         ListResponse<CategoryAdminResponse>,
         ListResponse<ProductAdminResponse>,
+        ListResponse<ProductSyncRunResponse>,
         ListResponse<CustomerAdminResponse>,
         ListResponse<BotAdminResponse>,
         ListResponse<AdminUserWithRolesAdminResponse>,
         ListResponse<RoleAdminResponse>,
+        ListResponse<ApiKeyAdminResponse>,
         ListResponse<PermissionAdminResponse>,
         ListResponse<OrderAdminResponse>,
         ListResponse<TransactionAdminResponse>,
+        ListResponse<ReconciliationReportResponse>,
         ListResponse<StockMovementAdminResponse>,
         ListResponse<AuditLogAdminResponse>,
         ListResponse<BroadcastResponse>,
+        ListResponse<BroadcastRecipientResponse>,
+        ListResponse<CustomerSegmentResponse>,
         ListResponse<CustomerBotResponse>,
         ListResponse<BotBotResponse>,
         ListResponse<GatewayBotResponse>,
@@ -209,13 +302,43 @@ use backend_rust::{
         NewRoleAdminRequest,
         UpdateRoleAdminRequest,
         RoleAdminResponse,
+        ApiKeyAdminResponse,
+        NewApiKeyAdminRequest,
+        CreatedApiKeyAdminResponse,
         PermissionAdminResponse,
         OrderAdminResponse,
         TransactionAdminResponse,
+        ReconciliationReportResponse,
+        ReconciliationDiscrepancyResponse,
+        ReconciliationDiscrepancyKind,
+        ReconciliationEntity,
+        RunReconciliationRequest,
+        AccountingExportResponse,
+        AccountingExportEntity,
+        AccountingExportFormat,
+        AccountingExportStatus,
         AuditLogAdminResponse,
         StoreBalanceAdminResponse,
         BroadcastResponse,
+        BroadcastStatus,
+        BroadcastRecipientResponse,
+        BroadcastRecipientStatus,
+        BroadcastTextEntity,
+        BroadcastTextEntityKind,
+        BroadcastMedia,
+        BroadcastMediaKind,
+        BroadcastButton,
+        BroadcastButtonAction,
         NewBroadcastRequest,
+        NewBroadcastVariantRequest,
+        TestBroadcastRequest,
+        BroadcastVariantResponse,
+        BroadcastVariantStatsResponse,
+        BroadcastAudiencePreviewRequest,
+        BroadcastAudiencePreviewResponse,
+        CustomerSegmentResponse,
+        NewCustomerSegmentRequest,
+        UpdateCustomerSegmentRequest,
         DashboardOverviewResponse,
         StatWithTrendResponse,
         TimeSeriesPointResponse,
@@ -227,10 +350,24 @@ use backend_rust::{
         BotSettingsAdminResponse,
         UpdatePricingSettingsAdminRequest,
         UpdateBotSettingsAdminRequest,
+        PaymentGatewaySettingsAdminResponse,
+        Currency,
+        ExchangeRateResponse,
+        ExchangeRatesResponse,
+        UpdatePaymentGatewaySettingsAdminRequest,
         LoginStep1AdminRequest,
         LoginStep1AdminResponse,
         LoginStep2AdminRequest,
         LoginStep2AdminResponse,
+        LoginRecoveryAdminRequest,
+        ChangePasswordAdminRequest,
+        ReauthenticateAdminRequest,
+        TwoFaEnrollmentAdminResponse,
+        TwoFaRotationAdminResponse,
+        ConfirmTwoFaRotationAdminRequest,
+        RecoveryCodesAdminResponse,
+        TwoFaResetAdminResponse,
+        CompleteTwoFaEnrollmentAdminRequest,
         StockMovementAdminResponse,
         BotBotResponse,
         NewBotBotRequest,
@@ -245,10 +382,19 @@ use backend_rust::{
         NewPaymentInvoiceBotRequest,
         UpdatePaymentInvoiceBotRequest,
         PaymentInvoiceAdminResponse,
+        PaymentInvoiceHistoryAdminResponse,
+        PaymentInvoiceEventAdminResponse,
+        PaymentInvoiceEventKind,
+        PaymentWebhookEventAdminResponse,
+        ResolvePaymentInvoiceAdminRequest,
+        GatewayHealthAdminResponse,
+        GatewayCircuitState,
         BotAnalyticsBotResponse,
         EnrichedOrderBotResponse,
         OrderItemBotResponse,
         PurchaseBotResponse,
+        CheckoutBotRequest,
+        BroadcastDeliveryReportBotRequest,
         SettingsBotResponse,
         ApiErrorResponse,
     ))
@@ -269,6 +415,10 @@ impl Modify for SecurityAddon {
                     .build(),
             ),
         );
+        components.add_security_scheme(
+            "admin_api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-ADMIN-API-KEY"))),
+        );
         components.add_security_scheme(
             "service_api_key",
             SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-KEY"))),
@@ -283,6 +433,7 @@ impl Modify for SecurityAddon {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_tracing();
+    telemetry::install();
     let config = Config::from_env();
     let pool = Database::new(&format!(
         "postgres://{}:{}@{}:{}/{}",
@@ -298,12 +449,70 @@ async fn main() -> anyhow::Result<()> {
     }
     let app_state = Arc::new(AppState::new(pool, config.clone()));
 
-    #[cfg(feature = "contms-provider")]
-    tokio::spawn(contms_products_sync_task(app_state.clone()));
+    let metrics_port = config.metrics_port;
+    let metrics_app = create_metrics_app(app_state.clone());
+    tokio::spawn(async move {
+        if let Err(e) = telemetry::serve_metrics(metrics_port, metrics_app).await {
+            tracing::error!(error = %e, "metrics server error");
+        }
+    });
+
+    // Each worker runs on a single replica at a time, see `workers::leader`
+    if config.run_mode.runs_workers() {
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "external_products_sync",
+            external_products_sync_task,
+        ));
 
-    tokio::spawn(broadcasts_task(app_state.clone()));
-    tokio::spawn(pending_payments_task(app_state.clone()));
-    tokio::spawn(subscription_expiry_notifications_task(app_state.clone()));
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "broadcasts",
+            broadcasts_task,
+        ));
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "pending_payments",
+            pending_payments_task,
+        ));
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "subscription_expiry_notifications",
+            subscription_expiry_notifications_task,
+        ));
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "idempotency_keys_cleanup",
+            idempotency_keys_cleanup_task,
+        ));
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "reconciliation",
+            reconciliation_task,
+        ));
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "accounting_exports",
+            accounting_exports_task,
+        ));
+        tokio::spawn(run_as_leader(
+            app_state.clone(),
+            "exchange_rates",
+            exchange_rates_task,
+        ));
+    }
+
+    if !config.run_mode.runs_api() {
+        tracing::info!("running in worker-only mode, serving only health checks");
+        let listener_address = format!("0.0.0.0:{}", config.backend_port);
+        let listener = tokio::net::TcpListener::bind(listener_address).await?;
+        let server = axum::serve(listener, create_worker_app(app_state.clone()))
+            .with_graceful_shutdown(shutdown_signal(app_state));
+        if let Err(e) = server.await {
+            tracing::error!(error = %e, "server error");
+        }
+        return Ok(());
+    }
 
     let app = create_app(app_state.clone())
         .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi()));
@@ -313,7 +522,11 @@ async fn main() -> anyhow::Result<()> {
 
     let listener = tokio::net::TcpListener::bind(listener_address).await?;
 
-    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown_signal(app_state));
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(app_state));
 
     if let Err(e) = server.await {
         tracing::error!(error = %e, "server error");
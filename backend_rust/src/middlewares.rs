@@ -1,6 +1,7 @@
 pub mod auth;
 pub mod bot_auth;
 pub mod context;
+pub mod idempotency;
 pub mod query;
 pub mod require_permission;
 pub mod validator;
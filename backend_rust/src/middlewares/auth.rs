@@ -1,11 +1,18 @@
 use std::{str::FromStr, sync::Arc};
 
-use axum::{extract::FromRequestParts, http::request::Parts};
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderMap, header, request::Parts},
+};
+use axum_client_ip::ClientIp;
 use uuid::Uuid;
 
 use crate::{
     errors::api::ApiError,
-    services::auth::{AuthServiceTrait, AuthUser},
+    services::{
+        admin_api_key::{API_KEY_HEADER, AdminApiKeyServiceTrait, AuthApiKey},
+        auth::{AdminActor, AuthServiceTrait, AuthUser},
+    },
     state::AppState,
 };
 
@@ -18,7 +25,7 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
     ) -> Result<Self, Self::Rejection> {
         let auth_header = parts
             .headers
-            .get(axum::http::header::AUTHORIZATION)
+            .get(header::AUTHORIZATION)
             .and_then(|v| v.to_str().ok())
             .ok_or(ApiError::AuthenticationError(
                 "Missing auth header".to_string(),
@@ -36,3 +43,85 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
             .map_err(|e| ApiError::AuthenticationError(e.to_string()))
     }
 }
+
+impl FromRequestParts<Arc<AppState>> for AuthApiKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+            .ok_or(ApiError::AuthenticationError(
+                "Missing API key header".to_string(),
+            ))?;
+
+        let ip = ClientIp::from_request_parts(parts, state)
+            .await
+            .ok()
+            .map(|ip| ip.0);
+
+        state.admin_api_key_service.authenticate(&key, ip).await
+    }
+}
+
+impl FromRequestParts<Arc<AppState>> for AdminActor {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if uses_api_key(&parts.headers)? {
+            Ok(AdminActor::ApiKey(
+                AuthApiKey::from_request_parts(parts, state).await?,
+            ))
+        } else {
+            Ok(AdminActor::User(
+                AuthUser::from_request_parts(parts, state).await?,
+            ))
+        }
+    }
+}
+
+// A session with a key attached would act as the user with the key's scope
+fn uses_api_key(headers: &HeaderMap) -> Result<bool, ApiError> {
+    match (
+        headers.contains_key(API_KEY_HEADER),
+        headers.contains_key(header::AUTHORIZATION),
+    ) {
+        (true, true) => Err(ApiError::BadRequest(
+            "Send either a session token or an API key, not both".to_string(),
+        )),
+        (has_key, _) => Ok(has_key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_uses_api_key_rejects_both_credentials() {
+        let mut headers = HeaderMap::new();
+        assert!(!uses_api_key(&headers).unwrap());
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer x"));
+        assert!(!uses_api_key(&headers).unwrap());
+
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("key"));
+        assert!(matches!(
+            uses_api_key(&headers),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        headers.remove(header::AUTHORIZATION);
+        assert!(uses_api_key(&headers).unwrap());
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    errors::api::ApiError,
+    middlewares::verified_service::VerifiedService,
+    models::idempotency_key::StoredResponse,
+    services::idempotency::{IdempotencyOutcome, IdempotencyServiceTrait},
+    state::AppState,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Makes mutating requests carrying an `Idempotency-Key` header safe to retry: the first
+/// response is stored per bot and replayed for duplicates until the key expires. Server
+/// errors are not stored, so the request can be retried with the same key.
+pub async fn idempotency(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return next.run(request).await;
+    }
+    let Some(key) = request
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|v| v.to_str().map(str::to_owned))
+    else {
+        return next.run(request).await;
+    };
+
+    let (mut parts, body) = request.into_parts();
+    // Unauthenticated requests are rejected by the handlers, don't store anything for them
+    if VerifiedService::from_request_parts(&mut parts, &state)
+        .await
+        .is_err()
+    {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    let key = match key {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+        _ => {
+            return ApiError::BadRequest(format!(
+                "{IDEMPOTENCY_KEY_HEADER} must be 1-{MAX_KEY_LENGTH} visible ASCII characters"
+            ))
+            .into_response();
+        }
+    };
+    let scope = parts
+        .headers
+        .get("X-BOT-ID")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return ApiError::BadRequest("Request body is too large".to_string()).into_response();
+        }
+    };
+    let request_hash = request_hash(&parts.method, parts.uri.path(), &body);
+
+    let service = &state.idempotency_service;
+    match service.begin(&scope, &key, &request_hash).await {
+        Ok(IdempotencyOutcome::Started) => {}
+        Ok(IdempotencyOutcome::Replay(stored)) => return replay(stored),
+        Err(e) => return e.into_response(),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    if response.status().is_server_error() {
+        if let Err(e) = service.release(&scope, &key).await {
+            tracing::error!("Failed to release idempotency key {key}: {e}");
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for idempotency key {key}: {e}");
+            if let Err(e) = service.release(&scope, &key).await {
+                tracing::error!("Failed to release idempotency key {key}: {e}");
+            }
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let stored = StoredResponse {
+        status_code: parts.status.as_u16() as i16,
+        body: body.to_vec(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_owned),
+    };
+    if let Err(e) = service.complete(&scope, &key, stored).await {
+        tracing::error!("Failed to store response for idempotency key {key}: {e}");
+        if let Err(e) = service.release(&scope, &key).await {
+            tracing::error!("Failed to release idempotency key {key}: {e}");
+        }
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn request_hash(method: &Method, path: &str, body: &[u8]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher.finalize().to_string()
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status_code as u16).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|v| HeaderValue::from_str(&v).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_hash_covers_method_path_and_body() {
+        let base = request_hash(&Method::POST, "/orders", b"{\"product_id\":1}");
+        assert_eq!(
+            base,
+            request_hash(&Method::POST, "/orders", b"{\"product_id\":1}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::POST, "/orders", b"{\"product_id\":2}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::POST, "/invoices", b"{\"product_id\":1}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::PATCH, "/orders", b"{\"product_id\":1}")
+        );
+    }
+
+    #[test]
+    fn test_replay_restores_stored_response() {
+        let response = replay(StoredResponse {
+            status_code: 201,
+            body: b"{}".to_vec(),
+            content_type: Some("application/json".to_string()),
+        });
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            HeaderValue::from_static("application/json")
+        );
+        assert_eq!(
+            response.headers()[IDEMPOTENT_REPLAYED_HEADER],
+            HeaderValue::from_static("true")
+        );
+    }
+}
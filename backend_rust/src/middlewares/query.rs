@@ -1,24 +1,42 @@
 use std::convert::TryFrom;
 
 use axum::extract::{FromRequest, Request};
-use shared_dtos::list_query::RawListQuery;
+use shared_dtos::list_query::{
+    FilterValue, Operator, RawFilter, RawFilterGroup, RawFilterNode, RawListQuery,
+};
 use urlencoding::decode;
 
 use crate::{
     errors::api::{ApiError, ApiResult},
-    models::common::{AllowedField, Filter, ListQuery},
+    models::common::{
+        AllowedField, Cursor, CursorField, Filter, FilterExpr, ListQuery, SearchField,
+    },
 };
 
+const MAX_SEARCH_LEN: usize = 100;
+const MAX_FILTER_DEPTH: usize = 4;
+const MAX_FILTER_NODES: usize = 50;
+
 impl<S, F, O> FromRequest<S> for ListQuery<F, O>
 where
     S: Send + Sync,
-    F: AllowedField + TryFrom<String, Error = String> + Send,
-    O: AllowedField + TryFrom<String, Error = String> + Send,
+    F: AllowedField + SearchField + TryFrom<String, Error = String> + Send,
+    O: AllowedField + CursorField + TryFrom<String, Error = String> + Send,
 {
     type Rejection = ApiError;
 
     async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
-        let raw_query_str = req.uri().query().unwrap_or("");
+        ListQuery::from_query_str(req.uri().query().unwrap_or(""))
+    }
+}
+
+impl<F, O> ListQuery<F, O>
+where
+    F: AllowedField + SearchField + TryFrom<String, Error = String> + Send,
+    O: AllowedField + CursorField + TryFrom<String, Error = String> + Send,
+{
+    /// Parses a raw (still URL-encoded) query string, e.g. one stored for later use
+    pub fn from_query_str(raw_query_str: &str) -> ApiResult<Self> {
         let decoded = decode(raw_query_str)
             .map_err(|e| ApiError::BadRequest(format!("Invalid URL encoding: {}", e)))?;
         let raw_query: RawListQuery =
@@ -26,24 +44,10 @@ where
 
         ListQuery::try_from_raw(raw_query)
     }
-}
 
-impl<F, O> ListQuery<F, O>
-where
-    F: AllowedField + TryFrom<String, Error = String> + Send,
-    O: AllowedField + TryFrom<String, Error = String> + Send,
-{
     pub fn try_from_raw(raw_query: RawListQuery) -> ApiResult<Self> {
-        let mut filters = Vec::new();
-        for raw_filter in raw_query.filters {
-            let field =
-                F::try_from(raw_filter.field).map_err(|e| ApiError::BadRequest(e.to_string()))?;
-            filters.push(Filter {
-                field,
-                op: raw_filter.op,
-                value: raw_filter.value,
-            });
-        }
+        let (filters, groups) =
+            filters_from_raw(raw_query.filters, raw_query.groups).map_err(ApiError::BadRequest)?;
 
         let order_by = match raw_query.order_by {
             Some(field_str) => {
@@ -64,15 +68,166 @@ where
             ));
         }
 
+        let cursor = match raw_query.cursor {
+            Some(raw) => {
+                let (cursor_field, _) = O::cursor_field().ok_or_else(|| {
+                    ApiError::BadRequest("Cursor pagination is not supported here".to_string())
+                })?;
+                if order_by.as_ref().is_some_and(|o| *o != cursor_field) {
+                    return Err(ApiError::BadRequest(format!(
+                        "Cursor pagination only supports ordering by {}",
+                        cursor_field.as_ref()
+                    )));
+                }
+                Some(Cursor::decode(&raw).map_err(ApiError::BadRequest)?)
+            }
+            None => None,
+        };
+
+        let search = raw_query
+            .search
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        if let Some(search) = &search {
+            if F::search_fields().is_empty() {
+                return Err(ApiError::BadRequest(
+                    "Search is not supported here".to_string(),
+                ));
+            }
+            if search.chars().count() > MAX_SEARCH_LEN {
+                return Err(ApiError::BadRequest(format!(
+                    "Search query cannot exceed {MAX_SEARCH_LEN} characters"
+                )));
+            }
+        }
+
         Ok(ListQuery {
             filters,
+            groups,
             pagination: raw_query.pagination,
             order_by,
             order_dir: raw_query.order_dir,
+            cursor,
+            search,
         })
     }
 }
 
+pub type ValidatedFilters<F> = (Vec<Filter<F>>, Vec<FilterExpr<F>>);
+
+// Validates flat filters and nested groups against the filter field whitelist
+pub fn filters_from_raw<F, T>(
+    raw_filters: Vec<T>,
+    raw_groups: Vec<RawFilterGroup<T>>,
+) -> Result<ValidatedFilters<F>, String>
+where
+    F: AllowedField + TryFrom<String, Error = String>,
+    T: Into<RawFilter>,
+{
+    let mut budget = MAX_FILTER_NODES;
+    let filters = raw_filters
+        .into_iter()
+        .map(|raw| filter_from_raw(raw.into(), &mut budget))
+        .collect::<Result<Vec<_>, _>>()?;
+    let groups = raw_groups
+        .into_iter()
+        .map(|group| filter_group_from_raw(group, 1, &mut budget))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((filters, groups))
+}
+
+fn filter_from_raw<F>(raw: RawFilter, budget: &mut usize) -> Result<Filter<F>, String>
+where
+    F: AllowedField + TryFrom<String, Error = String>,
+{
+    take_filter_node(budget)?;
+    let field = F::try_from(raw.field)?;
+    match (&raw.op, &raw.value) {
+        (Operator::In, FilterValue::Array(values)) if values.is_empty() => {
+            return Err(format!(
+                "Filter {} expects at least one value",
+                field.as_ref()
+            ));
+        }
+        (Operator::Between, FilterValue::Array(values)) if values.len() != 2 => {
+            return Err(format!(
+                "Filter {} expects exactly two values",
+                field.as_ref()
+            ));
+        }
+        (Operator::In | Operator::Between, FilterValue::Scalar(_)) => {
+            return Err(format!("Filter {} expects an array value", field.as_ref()));
+        }
+        (Operator::IsNull | Operator::NotNull | Operator::In | Operator::Between, _) => {}
+        (_, FilterValue::Array(_)) => {
+            return Err(format!("Filter {} expects a single value", field.as_ref()));
+        }
+        (_, FilterValue::Scalar(_)) => {}
+    }
+    Ok(Filter {
+        field,
+        op: raw.op,
+        value: raw.value,
+    })
+}
+
+fn filter_group_from_raw<F, T>(
+    group: RawFilterGroup<T>,
+    depth: usize,
+    budget: &mut usize,
+) -> Result<FilterExpr<F>, String>
+where
+    F: AllowedField + TryFrom<String, Error = String>,
+    T: Into<RawFilter>,
+{
+    if depth > MAX_FILTER_DEPTH {
+        return Err(format!(
+            "Filter groups cannot be nested deeper than {MAX_FILTER_DEPTH} levels"
+        ));
+    }
+    take_filter_node(budget)?;
+
+    let mut children = |nodes: Vec<RawFilterNode<T>>| {
+        if nodes.is_empty() {
+            return Err("Filter groups cannot be empty".to_string());
+        }
+        nodes
+            .into_iter()
+            .map(|node| filter_node_from_raw(node, depth, budget))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    Ok(match group {
+        RawFilterGroup::And(nodes) => FilterExpr::And(children(nodes)?),
+        RawFilterGroup::Or(nodes) => FilterExpr::Or(children(nodes)?),
+        RawFilterGroup::Not(node) => {
+            FilterExpr::Not(Box::new(filter_node_from_raw(*node, depth, budget)?))
+        }
+    })
+}
+
+fn filter_node_from_raw<F, T>(
+    node: RawFilterNode<T>,
+    depth: usize,
+    budget: &mut usize,
+) -> Result<FilterExpr<F>, String>
+where
+    F: AllowedField + TryFrom<String, Error = String>,
+    T: Into<RawFilter>,
+{
+    match node {
+        RawFilterNode::Filter(raw) => Ok(FilterExpr::Filter(filter_from_raw(raw.into(), budget)?)),
+        RawFilterNode::Group(group) => filter_group_from_raw(group, depth + 1, budget),
+    }
+}
+
+fn take_filter_node(budget: &mut usize) -> Result<(), String> {
+    *budget = budget
+        .checked_sub(1)
+        .ok_or_else(|| format!("Query cannot contain more than {MAX_FILTER_NODES} filters"))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -94,10 +249,13 @@ mod tests {
         order_fields: {
             TestOrderFields,
             [
+                Id => "id",
                 Name => "name",
                 CreatedAt => "created_at",
             ]
-        }
+        },
+        search_fields: ["name" => Fuzzy],
+        cursor_field: Id => "id"
     }
 
     async fn execute_from_request(uri: &str) -> Result<TestListQuery, ApiError> {
@@ -192,12 +350,99 @@ mod tests {
         assert!(matches!(result, Err(ApiError::BadRequest(_))));
     }
 
+    #[tokio::test]
+    async fn test_filter_groups() {
+        let uri = "http://localhost?groups[0][or][0][field]=age&groups[0][or][0][op]=between&groups[0][or][0][value][]=18&groups[0][or][0][value][]=30&groups[0][or][1][not][field]=name&groups[0][or][1][not][op]=is_null";
+        let query = execute_from_request(uri).await.unwrap();
+
+        assert!(query.filters.is_empty());
+        assert_eq!(query.groups.len(), 1);
+        let FilterExpr::Or(children) = &query.groups[0] else {
+            panic!("Expected OR group");
+        };
+        assert_eq!(children.len(), 2);
+        assert!(matches!(
+            &children[0],
+            FilterExpr::Filter(Filter { field: TestFilterFields::Age, op: Operator::Between, value: FilterValue::Array(values) }) if values.len() == 2
+        ));
+        assert!(matches!(
+            &children[1],
+            FilterExpr::Not(inner) if matches!(
+                inner.as_ref(),
+                FilterExpr::Filter(Filter { field: TestFilterFields::Name, op: Operator::IsNull, .. })
+            )
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_filter_groups() {
+        let result = execute_from_request(
+            "http://localhost?groups[0][or][0][field]=invalid&groups[0][or][0][op]=eq&groups[0][or][0][value]=1",
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let result = execute_from_request(
+            "http://localhost?filters[0][field]=age&filters[0][op]=between&filters[0][value][]=1",
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let deep = "[not]".repeat(MAX_FILTER_DEPTH + 1);
+        let uri = format!(
+            "http://localhost?groups[0]{deep}[field]=age&groups[0]{deep}[op]=eq&groups[0]{deep}[value]=1"
+        );
+        let result = execute_from_request(&uri).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
     #[tokio::test]
     async fn test_invalid_order_field() {
         let result = execute_from_request("http://localhost?order_by=invalid").await;
         assert!(matches!(result, Err(ApiError::BadRequest(_))));
     }
 
+    #[tokio::test]
+    async fn test_cursor_query() {
+        let query = execute_from_request("http://localhost?cursor=&page_size=20")
+            .await
+            .unwrap();
+        assert_eq!(query.cursor, Some(Cursor::default()));
+
+        let uri = format!("http://localhost?cursor={}", Cursor::encode(7));
+        let query = execute_from_request(&uri).await.unwrap();
+        assert_eq!(query.cursor, Some(Cursor { after: Some(7) }));
+
+        let query = execute_from_request("http://localhost").await.unwrap();
+        assert!(query.cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_cursor_query() {
+        let result = execute_from_request("http://localhost?cursor=garbage").await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let result = execute_from_request("http://localhost?cursor=&order_by=name").await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_search_query() {
+        let query = execute_from_request("http://localhost?search=%20iphone%2015%20")
+            .await
+            .unwrap();
+        assert_eq!(query.search.as_deref(), Some("iphone 15"));
+
+        let query = execute_from_request("http://localhost?search=")
+            .await
+            .unwrap();
+        assert!(query.search.is_none());
+
+        let uri = format!("http://localhost?search={}", "a".repeat(101));
+        let result = execute_from_request(&uri).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
     #[tokio::test]
     async fn test_pagination_limits() {
         let result = execute_from_request("http://localhost?page=0").await;
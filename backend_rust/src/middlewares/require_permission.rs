@@ -5,7 +5,10 @@ use axum::{extract::FromRequestParts, http::request::Parts};
 use crate::{
     errors::{api::ApiError, auth::AuthError},
     models::permission::Permission,
-    services::auth::{AuthServiceTrait, AuthUser},
+    services::{
+        admin_api_key::AdminApiKeyServiceTrait,
+        auth::{AdminActor, AuthServiceTrait},
+    },
     state::AppState,
 };
 
@@ -19,13 +22,19 @@ where
         parts: &mut Parts,
         state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
-        let user = AuthUser::from_request_parts(parts, state).await?;
-
-        let has_permission = state
-            .auth_service
-            .has_permission(user.id, P::PERMISSION)
-            .await
-            .map_err(|e: AuthError| ApiError::AuthenticationError(e.to_string()))?;
+        let has_permission = match AdminActor::from_request_parts(parts, state).await? {
+            AdminActor::ApiKey(key) => {
+                state
+                    .admin_api_key_service
+                    .has_permission(&key, P::PERMISSION)
+                    .await?
+            }
+            AdminActor::User(user) => state
+                .auth_service
+                .has_permission(user.id, P::PERMISSION)
+                .await
+                .map_err(|e: AuthError| ApiError::AuthenticationError(e.to_string()))?,
+        };
 
         if !has_permission {
             return Err(ApiError::AuthorizationError(
@@ -73,10 +82,11 @@ define_permission_markers! {
     ImagesCreate, ImagesRead, ImagesUpdate, ImagesDelete,
     TransactionsRead,
     StoreBalanceRead, StoreBalanceDeposit, StoreBalanceWithdraw,
-    InvoicesRead,
+    InvoicesRead, InvoicesResolve,
     BotsCreate, BotsRead, BotsUpdate, BotsDelete,
     SettingsRead, SettingsEdit,
     PricingRead, PricingEdit,
     BroadcastCreate, BroadcastRead,
     AuditLogRead,
+    ApiKeysManage,
 }
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared_dtos::accounting_export::{
+    AccountingExportEntity, AccountingExportFormat, AccountingExportStatus,
+};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::define_list_query;
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct AccountingExportRow {
+    pub id: i64,
+    pub entity: AccountingExportEntity,
+    pub format: AccountingExportFormat,
+    pub query: String,
+    pub date_from: DateTime<Utc>,
+    pub date_to: DateTime<Utc>,
+    pub status: AccountingExportStatus,
+    pub rows_count: Option<i64>,
+    pub file_name: Option<String>,
+    pub download_token: Option<Uuid>,
+    pub error: Option<String>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct NewAccountingExport {
+    pub entity: AccountingExportEntity,
+    pub format: AccountingExportFormat,
+    /// Raw list query string, validated before the export is queued
+    pub query: String,
+    pub date_from: DateTime<Utc>,
+    pub date_to: DateTime<Utc>,
+    pub created_by: i64,
+}
+
+#[derive(Debug)]
+pub struct CompletedAccountingExport {
+    pub rows_count: i64,
+    pub file_name: String,
+    pub download_token: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+define_list_query! {
+    query_name: AccountingExportListQuery,
+    filter_fields: {
+        AccountingExportFilterFields,
+        [
+            Id => "id",
+            Entity => "entity",
+            Format => "format",
+            Status => "status",
+            CreatedBy => "created_by",
+            CreatedAt => "created_at",
+        ]
+    },
+    order_fields: {
+        AccountingExportOrderFields,
+        [
+            Id => "id",
+            CreatedAt => "created_at",
+        ]
+    }
+}
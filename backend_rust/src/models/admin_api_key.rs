@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{prelude::FromRow, types::ipnetwork::IpNetwork};
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct AdminApiKeyRow {
+    pub id: i64,
+    pub name: String,
+    pub key_prefix: String,
+    #[serde(skip)]
+    pub key_hash: String,
+    pub permissions: Vec<String>,
+    pub allowed_ips: Vec<IpNetwork>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<IpNetwork>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct NewAdminApiKey {
+    pub name: String,
+    pub key_prefix: String,
+    pub key_hash: String,
+    pub permissions: Vec<String>,
+    pub allowed_ips: Vec<IpNetwork>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: i64,
+}
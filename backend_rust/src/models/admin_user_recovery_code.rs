@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+#[derive(FromRow, Debug)]
+pub struct AdminUserRecoveryCodeRow {
+    pub id: i64,
+    pub user_id: i64,
+    pub code_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
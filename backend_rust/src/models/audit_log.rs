@@ -78,5 +78,6 @@ define_list_query! {
             ErrorMessage => "error_message",
             CreatedAt => "created_at",
         ]
-    }
+    },
+    cursor_field: Id => "audit_logs.id"
 }
@@ -1,7 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use shared_dtos::broadcast::BroadcastStatus;
-use sqlx::prelude::FromRow;
+use shared_dtos::broadcast::{
+    BroadcastButton, BroadcastContent, BroadcastMedia, BroadcastMediaKind,
+    BroadcastRecipientStatus, BroadcastStatus, BroadcastTextEntity,
+};
+use sqlx::{prelude::FromRow, types::Json};
 use uuid::Uuid;
 
 use crate::define_list_query;
@@ -12,7 +15,11 @@ pub struct BroadcastRow {
     pub status: BroadcastStatus,
     pub content_text: Option<String>,
     pub content_image_id: Option<Uuid>,
+    pub content_entities: Json<Vec<BroadcastTextEntity>>,
+    pub content_media: Json<Vec<BroadcastMedia>>,
+    pub content_buttons: Json<Vec<Vec<BroadcastButton>>>,
     pub filters: Option<serde_json::Value>,
+    pub segment_id: Option<i64>,
     pub statistics: Option<serde_json::Value>,
     pub created_by: i64,
     pub scheduled_for: Option<DateTime<Utc>>,
@@ -27,9 +34,24 @@ pub struct NewBroadcast {
     pub status: BroadcastStatus,
     pub content_text: Option<String>,
     pub content_image_id: Option<Uuid>,
+    pub content_entities: Vec<BroadcastTextEntity>,
+    pub content_media: Vec<BroadcastMedia>,
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
     pub filters: Option<serde_json::Value>,
+    pub segment_id: Option<i64>,
     pub created_by: i64,
     pub scheduled_for: Option<DateTime<Utc>>,
+    pub variants: Vec<NewBroadcastVariant>,
+}
+
+#[derive(Debug)]
+pub struct NewBroadcastVariant {
+    pub name: String,
+    pub content_text: Option<String>,
+    pub content_image_id: Option<Uuid>,
+    pub content_entities: Vec<BroadcastTextEntity>,
+    pub content_media: Vec<BroadcastMedia>,
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
 }
 
 #[derive(Debug)]
@@ -37,11 +59,80 @@ pub struct UpdateBroadcast {
     pub status: Option<BroadcastStatus>,
     pub content_text: Option<Option<String>>,
     pub content_image_id: Option<Option<Uuid>>,
+    pub content_entities: Option<Vec<BroadcastTextEntity>>,
+    pub content_media: Option<Vec<BroadcastMedia>>,
+    pub content_buttons: Option<Vec<Vec<BroadcastButton>>>,
     pub filters: Option<Option<serde_json::Value>>,
     pub scheduled_for: Option<Option<DateTime<Utc>>>,
     pub statistics: Option<Option<serde_json::Value>>,
     pub started_at: Option<Option<DateTime<Utc>>>,
     pub finished_at: Option<Option<DateTime<Utc>>>,
+    /// Only applies the update while the broadcast is in one of these statuses
+    pub expected_status: Option<Vec<BroadcastStatus>>,
+}
+
+impl BroadcastRow {
+    pub fn content(&self) -> BroadcastContent {
+        build_broadcast_content(
+            self.content_text.clone(),
+            self.content_image_id,
+            self.content_entities.0.clone(),
+            self.content_media.0.clone(),
+            self.content_buttons.0.clone(),
+        )
+    }
+}
+
+/// Alternative content of a broadcast, recipients without a variant get the broadcast's own content
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct BroadcastVariantRow {
+    pub id: i64,
+    pub broadcast_id: i64,
+    pub name: String,
+    pub content_text: Option<String>,
+    pub content_image_id: Option<Uuid>,
+    pub content_entities: Json<Vec<BroadcastTextEntity>>,
+    pub content_media: Json<Vec<BroadcastMedia>>,
+    pub content_buttons: Json<Vec<Vec<BroadcastButton>>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BroadcastVariantRow {
+    pub fn content(&self) -> BroadcastContent {
+        build_broadcast_content(
+            self.content_text.clone(),
+            self.content_image_id,
+            self.content_entities.0.clone(),
+            self.content_media.0.clone(),
+            self.content_buttons.0.clone(),
+        )
+    }
+}
+
+/// The legacy single `content_image_id` is sent as the first photo
+pub fn build_broadcast_content(
+    text: Option<String>,
+    image_id: Option<Uuid>,
+    entities: Vec<BroadcastTextEntity>,
+    media: Vec<BroadcastMedia>,
+    buttons: Vec<Vec<BroadcastButton>>,
+) -> BroadcastContent {
+    let media = image_id
+        .map(|image_id| BroadcastMedia {
+            kind: BroadcastMediaKind::Photo,
+            image_id: Some(image_id),
+            url: None,
+        })
+        .into_iter()
+        .chain(media)
+        .collect();
+
+    BroadcastContent {
+        text,
+        entities,
+        media,
+        buttons,
+    }
 }
 
 define_list_query! {
@@ -67,3 +158,69 @@ define_list_query! {
         ]
     }
 }
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct BroadcastRecipientRow {
+    pub id: i64,
+    pub broadcast_id: i64,
+    pub customer_id: i64,
+    pub telegram_id: i64,
+    pub variant_id: Option<i64>,
+    pub status: BroadcastRecipientStatus,
+    pub error_message: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A pending recipient joined with the customer fields needed to deliver the message
+#[derive(FromRow, Debug, Clone)]
+pub struct BroadcastDeliveryRow {
+    pub id: i64,
+    pub customer_id: i64,
+    pub telegram_id: i64,
+    pub last_seen_with_bot: i64,
+    pub variant_id: Option<i64>,
+}
+
+#[derive(FromRow, Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BroadcastRecipientStats {
+    pub total: i64,
+    pub pending: i64,
+    pub dispatched: i64,
+    pub sent: i64,
+    pub blocked: i64,
+    pub failed: i64,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastVariantStatsRow {
+    pub variant_id: Option<i64>,
+    pub recipients: i64,
+    pub sent: i64,
+    pub conversions: i64,
+}
+
+define_list_query! {
+    query_name: BroadcastRecipientListQuery,
+    filter_fields: {
+        BroadcastRecipientFilterFields,
+        [
+            Id => "id",
+            CustomerId => "customer_id",
+            TelegramId => "telegram_id",
+            VariantId => "variant_id",
+            Status => "status",
+            SentAt => "sent_at",
+        ]
+    },
+    order_fields: {
+        BroadcastRecipientOrderFields,
+        [
+            Id => "id",
+            Status => "status",
+            SentAt => "sent_at",
+        ]
+    },
+    cursor_field: Id => "id"
+}
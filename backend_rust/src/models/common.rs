@@ -1,3 +1,4 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use serde::{Deserialize, Serialize};
 use shared_dtos::list_query::{FilterValue, Operator, OrderDir, Pagination};
 use std::fmt::Debug;
@@ -20,10 +21,54 @@ impl<T> AllowedField for T where
 {
 }
 
+// Implemented by order field enums; lists with a unique ordered key support keyset pagination
+pub trait CursorField: Sized {
+    fn cursor_field() -> Option<(Self, &'static str)>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    // Exact match when the term parses as the column type
+    Id,
+    Uuid,
+    // Substring match, backed by trigram indexes
+    Contains,
+    // Substring, trigram similarity or full-text match
+    Fuzzy,
+}
+
+// Implemented by filter field enums; lists declaring search columns accept a free-text `search`
+pub trait SearchField {
+    fn search_fields() -> &'static [(&'static str, SearchMode)];
+}
+
 #[derive(Debug, Serialize)]
 pub struct PaginatedResult<T> {
     pub items: Vec<T>,
     pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub after: Option<i64>,
+}
+
+impl Cursor {
+    pub fn encode(after: i64) -> String {
+        let json = serde_json::to_vec(&Cursor { after: Some(after) }).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(raw: &str) -> Result<Self, String> {
+        if raw.is_empty() {
+            return Ok(Cursor::default());
+        }
+        let bytes = URL_SAFE_NO_PAD
+            .decode(raw)
+            .map_err(|_| "Invalid cursor".to_string())?;
+        serde_json::from_slice(&bytes).map_err(|_| "Invalid cursor".to_string())
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -33,31 +78,68 @@ pub struct Filter<F: AllowedField> {
     pub value: FilterValue,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterExpr<F: AllowedField> {
+    Filter(Filter<F>),
+    And(Vec<FilterExpr<F>>),
+    Or(Vec<FilterExpr<F>>),
+    Not(Box<FilterExpr<F>>),
+}
+
+impl<F: AllowedField> FilterExpr<F> {
+    pub fn any_field(&self, pred: &impl Fn(&F) -> bool) -> bool {
+        match self {
+            FilterExpr::Filter(filter) => pred(&filter.field),
+            FilterExpr::And(exprs) | FilterExpr::Or(exprs) => {
+                exprs.iter().any(|expr| expr.any_field(pred))
+            }
+            FilterExpr::Not(expr) => expr.any_field(pred),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ListQuery<F: AllowedField, O: AllowedField> {
     pub filters: Vec<Filter<F>>,
+    pub groups: Vec<FilterExpr<F>>,
     pub pagination: Pagination,
     pub order_by: Option<O>,
     pub order_dir: OrderDir,
+    pub cursor: Option<Cursor>,
+    pub search: Option<String>,
 }
 
 impl<F: AllowedField, O: AllowedField> Default for ListQuery<F, O> {
     fn default() -> Self {
         Self {
             filters: Vec::new(),
+            groups: Vec::new(),
             pagination: Pagination::default(),
             order_by: None,
             order_dir: OrderDir::default(),
+            cursor: None,
+            search: None,
         }
     }
 }
 
+impl<F: AllowedField, O: AllowedField> ListQuery<F, O> {
+    /// Whether any flat or grouped filter uses a field matching `pred`
+    pub fn filters_any(&self, pred: impl Fn(&F) -> bool) -> bool {
+        self.filters.iter().any(|filter| pred(&filter.field))
+            || self.groups.iter().any(|expr| expr.any_field(&pred))
+    }
+}
+
 #[macro_export]
 macro_rules! define_list_query {
     (
         query_name: $query_name:ident,
         filter_fields: { $filter_enum:ident, [$($filter_variant:ident => $filter_str:literal),* $(,)?] },
         order_fields: { $order_enum:ident, [$($order_variant:ident => $order_str:literal),* $(,)?] }
+        $(, search_fields: [$($search_column:literal => $search_mode:ident),* $(,)?])?
+        $(, cursor_field: $cursor_variant:ident => $cursor_column:literal)? $(,)?
     ) => {
         #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
         #[serde(rename_all = "snake_case")]
@@ -83,6 +165,12 @@ macro_rules! define_list_query {
             }
         }
 
+        impl $crate::models::common::SearchField for $filter_enum {
+            fn search_fields() -> &'static [(&'static str, $crate::models::common::SearchMode)] {
+                &[$($(($search_column, $crate::models::common::SearchMode::$search_mode)),*)?]
+            }
+        }
+
         #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
         #[serde(rename_all = "snake_case")]
         pub enum $order_enum {
@@ -107,8 +195,16 @@ macro_rules! define_list_query {
             }
         }
 
+        impl $crate::models::common::CursorField for $order_enum {
+            fn cursor_field() -> Option<(Self, &'static str)> {
+                $crate::define_list_query!(@cursor $(Self::$cursor_variant => $cursor_column)?)
+            }
+        }
+
         pub type $query_name = $crate::models::common::ListQuery<$filter_enum, $order_enum>;
     };
+    (@cursor) => { None };
+    (@cursor $variant:path => $column:literal) => { Some(($variant, $column)) };
 }
 
 #[cfg(test)]
@@ -122,6 +218,14 @@ mod tests {
         assert_eq!(pagination.page_size, 10);
     }
 
+    #[test]
+    fn test_cursor_roundtrip() {
+        let encoded = Cursor::encode(42);
+        assert_eq!(Cursor::decode(&encoded).unwrap().after, Some(42));
+        assert_eq!(Cursor::decode("").unwrap(), Cursor::default());
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+
     #[test]
     fn test_pagination_deserialize_with_defaults() {
         let json = r#"{}"#;
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use shared_dtos::currency::Currency;
 use sqlx::prelude::FromRow;
 
 use crate::define_list_query;
@@ -19,6 +20,8 @@ pub struct CustomerRow {
     pub last_seen_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// `None` while amounts are shown in the store base currency
+    pub display_currency: Option<Currency>,
 }
 
 #[derive(Debug)]
@@ -35,6 +38,7 @@ pub struct UpdateCustomer {
     pub last_seen_with_bot: Option<i64>,
     pub last_seen_at: Option<DateTime<Utc>>,
     pub blocked_until: Option<Option<DateTime<Utc>>>,
+    pub display_currency: Option<Currency>,
 }
 
 define_list_query! {
@@ -53,6 +57,15 @@ define_list_query! {
             LastSeenAt => "last_seen_at",
             CreatedAt => "created_at",
             UpdatedAt => "updated_at",
+            OrdersCount => "orders_count",
+            OrdersTotal => "orders_total",
+            LastOrderAt => "last_order_at",
+            DaysSinceLastOrder => "days_since_last_order",
+            DepositsCount => "deposits_count",
+            DepositsTotal => "deposits_total",
+            LastDepositAt => "last_deposit_at",
+            DaysSinceLastDeposit => "days_since_last_deposit",
+            DaysSinceRegistration => "days_since_registration",
         ]
     },
     order_fields: {
@@ -69,6 +82,63 @@ define_list_query! {
             LastSeenAt => "last_seen_at",
             CreatedAt => "created_at",
             UpdatedAt => "updated_at",
+            OrdersCount => "orders_count",
+            OrdersTotal => "orders_total",
+            LastOrderAt => "last_order_at",
+            DepositsCount => "deposits_count",
+            DepositsTotal => "deposits_total",
+            LastDepositAt => "last_deposit_at",
         ]
+    },
+    search_fields: [
+        "telegram_id" => Id,
+        "id" => Id,
+    ]
+}
+
+impl CustomerFilterFields {
+    /// Purchase/deposit aggregates, only available on the `customer_audience` view
+    pub fn is_aggregate(&self) -> bool {
+        matches!(
+            self,
+            Self::OrdersCount
+                | Self::OrdersTotal
+                | Self::LastOrderAt
+                | Self::DaysSinceLastOrder
+                | Self::DepositsCount
+                | Self::DepositsTotal
+                | Self::LastDepositAt
+                | Self::DaysSinceLastDeposit
+                | Self::DaysSinceRegistration
+        )
+    }
+}
+
+impl CustomerOrderFields {
+    pub fn is_aggregate(&self) -> bool {
+        matches!(
+            self,
+            Self::OrdersCount
+                | Self::OrdersTotal
+                | Self::LastOrderAt
+                | Self::DepositsCount
+                | Self::DepositsTotal
+                | Self::LastDepositAt
+        )
+    }
+}
+
+/// Table to select customers from, aggregates are only computed when the query needs them
+pub fn customer_list_source(query: &CustomerListQuery) -> &'static str {
+    let needs_aggregates = query.filters_any(CustomerFilterFields::is_aggregate)
+        || query
+            .order_by
+            .as_ref()
+            .is_some_and(CustomerOrderFields::is_aggregate);
+
+    if needs_aggregates {
+        "customer_audience AS customers"
+    } else {
+        "customers"
     }
 }
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct CustomerSegmentRow {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub filters: serde_json::Value,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct NewCustomerSegment {
+    pub name: String,
+    pub description: Option<String>,
+    pub filters: serde_json::Value,
+    pub created_by: i64,
+}
+
+#[derive(Debug)]
+pub struct UpdateCustomerSegment {
+    pub name: Option<String>,
+    pub description: Option<Option<String>>,
+    pub filters: Option<serde_json::Value>,
+}
@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use shared_dtos::currency::Currency;
+use sqlx::prelude::FromRow;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct ExchangeRateRow {
+    pub base_currency: Currency,
+    pub currency: Currency,
+    /// Units of `currency` for one unit of `base_currency`
+    pub rate: Decimal,
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+}
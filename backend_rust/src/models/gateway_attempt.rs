@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared_dtos::invoice::PaymentSystem;
+use sqlx::prelude::FromRow;
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayAttemptOutcome {
+    Success,
+    /// Gateway answered but had no requisites for the amount
+    NoRequisites,
+    Error,
+}
+
+#[derive(Debug)]
+pub struct NewGatewayAttempt {
+    pub gateway: PaymentSystem,
+    pub outcome: GatewayAttemptOutcome,
+    pub latency_ms: i32,
+}
+
+/// Aggregated `init_order` attempts of one gateway over a time window
+#[derive(FromRow, Debug, Clone)]
+pub struct GatewayAttemptStatsRow {
+    pub gateway: PaymentSystem,
+    pub attempts: i64,
+    pub successes: i64,
+    pub failures: i64,
+    pub no_requisites: i64,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub last_outcome: GatewayAttemptOutcome,
+    pub last_attempt_at: DateTime<Utc>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+}
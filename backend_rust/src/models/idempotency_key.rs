@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct IdempotencyKeyRow {
+    pub scope: String,
+    pub key: String,
+    pub request_hash: String,
+    pub status_code: Option<i16>,
+    pub response_body: Option<Vec<u8>>,
+    pub content_type: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct NewIdempotencyKey {
+    pub scope: String,
+    pub key: String,
+    pub request_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct StoredResponse {
+    pub status_code: i16,
+    pub body: Vec<u8>,
+    pub content_type: Option<String>,
+}
@@ -53,5 +53,10 @@ define_list_query! {
             BotId => "bot_id",
             CreatedAt => "created_at",
         ]
-    }
+    },
+    search_fields: [
+        "id" => Id,
+        "(SELECT telegram_id FROM customers WHERE customers.id = orders.customer_id)" => Id,
+    ],
+    cursor_field: Id => "id"
 }
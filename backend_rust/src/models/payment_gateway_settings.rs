@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use shared_dtos::{currency::Currency, invoice::PaymentSystem};
+use sqlx::prelude::FromRow;
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct PaymentGatewaySettingsRow {
+    pub gateway: PaymentSystem,
+    pub invoice_ttl_minutes: i32,
+    pub min_amount: Decimal,
+    pub max_amount: Decimal,
+    /// Deposits must be a multiple of it
+    pub amount_step: Decimal,
+    /// Pending, processing or disputed invoices a customer may have at once, unlimited when `None`
+    pub max_open_invoices: Option<i32>,
+    /// Sum of a customer's deposits per UTC day, failed and expired invoices don't count
+    pub daily_deposit_limit: Option<Decimal>,
+    pub updated_at: DateTime<Utc>,
+    /// Currency the gateway charges in, amounts above are in it too
+    pub currency: Currency,
+}
+
+#[derive(Debug, Default)]
+pub struct UpdatePaymentGatewaySettings {
+    pub invoice_ttl_minutes: Option<i32>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub amount_step: Option<Decimal>,
+    pub max_open_invoices: Option<Option<i32>>,
+    pub daily_deposit_limit: Option<Option<Decimal>>,
+}
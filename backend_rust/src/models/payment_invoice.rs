@@ -1,7 +1,10 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use shared_dtos::invoice::{InvoiceStatus, PaymentDetails, PaymentSystem};
+use shared_dtos::{
+    currency::Currency,
+    invoice::{InvoiceStatus, PaymentDetails, PaymentSystem},
+};
 use sqlx::prelude::FromRow;
 
 use crate::define_list_query;
@@ -28,6 +31,11 @@ pub struct PaymentInvoiceRow {
     pub receipt_submitted_at: Option<DateTime<Utc>>,
     pub dispute_opened_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
+    pub receipt_url: Option<String>,
+    /// Currency of `amount`; `original_amount` is in the store base currency
+    pub currency: Currency,
+    /// Order bought with this invoice once it is paid (pay-for-product checkout)
+    pub checkout_order_id: Option<i64>,
 }
 
 #[derive(Debug)]
@@ -43,14 +51,19 @@ pub struct NewPaymentInvoice {
     pub order_id: uuid::Uuid,
     pub payment_details: Option<PaymentDetails>,
     pub bot_message_id: Option<i64>,
+    pub currency: Currency,
+    pub checkout_order_id: Option<i64>,
 }
 
 #[derive(Debug, Default)]
 pub struct UpdatePaymentInvoice {
     pub status: Option<InvoiceStatus>,
+    /// Only update the invoice while it is still in this status
+    pub expected_status: Option<InvoiceStatus>,
     pub notification_sent_at: Option<Option<DateTime<Utc>>>,
     pub receipt_requested_at: Option<DateTime<Utc>>,
     pub receipt_submitted_at: Option<DateTime<Utc>>,
+    pub receipt_url: Option<String>,
     pub dispute_opened_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
 }
@@ -84,5 +97,57 @@ define_list_query! {
             UpdatedAt => "updated_at",
             ExpiresAt => "expires_at",
         ]
+    },
+    search_fields: [
+        "id" => Id,
+        "order_id" => Uuid,
+        "gateway_invoice_id" => Contains,
+        "(SELECT telegram_id FROM customers WHERE customers.id = payment_invoices.customer_id)" => Id,
+    ],
+    cursor_field: Id => "id"
+}
+
+/// What a customer already has going on with one gateway, checked against its limits
+#[derive(Debug, Clone, Default)]
+pub struct CustomerGatewayUsage {
+    pub open_invoices: i64,
+    /// Original amounts of invoices created since the given time that may still be paid
+    pub deposited_since: Decimal,
+}
+
+/// Gateway limits a new invoice must fit into
+#[derive(Debug, Clone, Copy)]
+pub struct InvoiceLimits {
+    pub max_open_invoices: Option<i32>,
+    pub daily_deposit_limit: Option<Decimal>,
+    /// Start of the day the deposit limit is counted from
+    pub since: DateTime<Utc>,
+    /// Amount of the new invoice
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvoiceLimitExceeded {
+    OpenInvoices,
+    DailyDeposit,
+}
+
+impl InvoiceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_open_invoices.is_none() && self.daily_deposit_limit.is_none()
+    }
+
+    pub fn check(&self, usage: &CustomerGatewayUsage) -> Result<(), InvoiceLimitExceeded> {
+        if let Some(max_open_invoices) = self.max_open_invoices
+            && usage.open_invoices >= max_open_invoices as i64
+        {
+            return Err(InvoiceLimitExceeded::OpenInvoices);
+        }
+        if let Some(daily_deposit_limit) = self.daily_deposit_limit
+            && usage.deposited_since + self.amount > daily_deposit_limit
+        {
+            return Err(InvoiceLimitExceeded::DailyDeposit);
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use shared_dtos::invoice::{InvoiceStatus, PaymentInvoiceEventKind};
+use sqlx::prelude::FromRow;
+
+/// Payload keys holding the trader's requisites or the payer's personal data
+const REDACTED_KEYS: &[&str] = &["value", "data_people", "token_link", "user_token"];
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct PaymentInvoiceEventRow {
+    pub id: i64,
+    pub invoice_id: i64,
+    pub kind: PaymentInvoiceEventKind,
+    pub from_status: Option<InvoiceStatus>,
+    pub to_status: Option<InvoiceStatus>,
+    pub payload: Option<Value>,
+    pub dedup_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewPaymentInvoiceEvent {
+    pub invoice_id: i64,
+    pub kind: PaymentInvoiceEventKind,
+    pub payload: Option<Value>,
+    pub dedup_key: Option<String>,
+}
+
+impl NewPaymentInvoiceEvent {
+    /// Raw gateway answer with requisites redacted
+    pub fn gateway_response(invoice_id: i64, response: &impl Serialize) -> Self {
+        Self {
+            invoice_id,
+            kind: PaymentInvoiceEventKind::GatewayResponse,
+            payload: Some(redact(serde_json::to_value(response).unwrap_or_default())),
+            dedup_key: None,
+        }
+    }
+
+    pub fn webhook(invoice_id: i64, event_id: &str, payload: Value) -> Self {
+        Self {
+            invoice_id,
+            kind: PaymentInvoiceEventKind::Webhook,
+            payload: Some(redact(payload)),
+            dedup_key: Some(format!("webhook:{event_id}")),
+        }
+    }
+
+    /// A notification identified by `dedup_key`, which must differ for every
+    /// notification meant to be sent
+    pub fn notification(invoice_id: i64, dedup_key: String, payload: Value) -> Self {
+        Self {
+            invoice_id,
+            kind: PaymentInvoiceEventKind::NotificationSent,
+            payload: Some(payload),
+            dedup_key: Some(dedup_key),
+        }
+    }
+}
+
+/// Masks requisites and personal data anywhere in a gateway payload, keeping the
+/// last 4 characters of requisite numbers so support can match them with the customer
+pub fn redact(mut payload: Value) -> Value {
+    redact_in_place(&mut payload);
+    payload
+}
+
+fn redact_in_place(payload: &mut Value) {
+    match payload {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if REDACTED_KEYS.contains(&key.as_str()) {
+                    *value = mask(value);
+                } else {
+                    redact_in_place(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_in_place),
+        _ => {}
+    }
+}
+
+fn mask(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        Value::String(s) if s.chars().count() > 8 => {
+            let tail = s.chars().skip(s.chars().count() - 4).collect::<String>();
+            Value::String(format!("***{tail}"))
+        }
+        _ => Value::String("***".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_redact_masks_requisites_and_people() {
+        let payload = json!({
+            "object_token": "token-1",
+            "value": "4111111111111111",
+            "data_people": {"surname": "Doe", "name": "Jane", "patronymic": "Q"},
+            "requisite_data": {"bank_name": "TestBank", "value": "+7900"},
+            "token_link": null,
+        });
+
+        assert_eq!(
+            redact(payload),
+            json!({
+                "object_token": "token-1",
+                "value": "***1111",
+                "data_people": "***",
+                "requisite_data": {"bank_name": "TestBank", "value": "***"},
+                "token_link": null,
+            })
+        );
+    }
+}
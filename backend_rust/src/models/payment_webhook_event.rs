@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use shared_dtos::invoice::PaymentSystem;
+use sqlx::prelude::FromRow;
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct PaymentWebhookEventRow {
+    pub id: i64,
+    pub gateway: PaymentSystem,
+    pub event_id: String,
+    pub gateway_invoice_id: String,
+    pub payload: serde_json::Value,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct NewPaymentWebhookEvent {
+    pub gateway: PaymentSystem,
+    pub event_id: String,
+    pub gateway_invoice_id: String,
+    pub payload: serde_json::Value,
+}
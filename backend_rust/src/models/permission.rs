@@ -63,6 +63,7 @@ pub enum Permission {
 
     // 📋 Invoices
     InvoicesRead,
+    InvoicesResolve,
 
     // 🤖 Bots
     BotsCreate,
@@ -82,6 +83,9 @@ pub enum Permission {
 
     // 📝 Audit
     AuditLogRead,
+
+    // 🔑 API keys
+    ApiKeysManage,
 }
 
 impl fmt::Display for Permission {
@@ -136,6 +140,7 @@ impl fmt::Display for Permission {
 
             // 📋 Инвойсы
             Self::InvoicesRead => "invoices:read",
+            Self::InvoicesResolve => "invoices:resolve",
 
             // 🤖 Боты
             Self::BotsCreate => "bots:create",
@@ -155,6 +160,9 @@ impl fmt::Display for Permission {
 
             // 📝 Аудит
             Self::AuditLogRead => "audit_log:read",
+
+            // 🔑 API-ключи
+            Self::ApiKeysManage => "api_keys:manage",
         };
         f.write_str(s)
     }
@@ -23,6 +23,7 @@ pub struct ProductRow {
     pub fulfillment_image_id: Option<Uuid>,
     pub provider_name: String,
     pub external_id: Option<String>,
+    pub hidden_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: i64,
@@ -56,6 +57,7 @@ pub struct UpdateProduct {
     pub fulfillment_text: Option<Option<String>>,
     pub fulfillment_image_id: Option<Option<Uuid>>,
     pub external_id: Option<Option<String>>,
+    pub hidden: Option<bool>,
 }
 
 define_list_query! {
@@ -69,6 +71,7 @@ define_list_query! {
             ExternalId => "external_id",
             BasePrice => "base_price",
             Type => "type",
+            HiddenAt => "hidden_at",
         ]
     },
     order_fields: {
@@ -82,5 +85,10 @@ define_list_query! {
             CreatedAt => "created_at",
             Type => "type",
         ]
-    }
+    },
+    search_fields: [
+        "name" => Fuzzy,
+        "external_id" => Contains,
+        "id" => Id,
+    ]
 }
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared_dtos::{
+    product::ProductDetails,
+    product_sync::{ProductSyncStatus, ProductSyncTrigger},
+};
+use sqlx::{prelude::FromRow, types::Json};
+
+use crate::define_list_query;
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct ProductSyncRunRow {
+    pub id: i64,
+    pub provider_name: String,
+    pub trigger: ProductSyncTrigger,
+    pub status: ProductSyncStatus,
+    pub added: i32,
+    pub updated: i32,
+    pub hidden: i32,
+    pub restored: i32,
+    pub errors: Json<Vec<String>>,
+    pub triggered_by: Option<i64>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct NewProductSyncRun {
+    pub provider_name: String,
+    pub trigger: ProductSyncTrigger,
+    pub triggered_by: Option<i64>,
+}
+
+/// Catalog values the sync last wrote to a product
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ProductSyncSnapshotRow {
+    pub product_id: i64,
+    pub name: String,
+    /// `None` while the supplier doesn't publish a price
+    pub base_price: Option<Decimal>,
+    pub category_id: i64,
+    pub details: Option<Json<ProductDetails>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProductSyncCounts {
+    pub added: i32,
+    pub updated: i32,
+    pub hidden: i32,
+    pub restored: i32,
+    pub errors: Vec<String>,
+}
+
+define_list_query! {
+    query_name: ProductSyncRunListQuery,
+    filter_fields: {
+        ProductSyncRunFilterFields,
+        [
+            Id => "id",
+            ProviderName => "provider_name",
+            Trigger => "trigger",
+            Status => "status",
+            StartedAt => "started_at",
+        ]
+    },
+    order_fields: {
+        ProductSyncRunOrderFields,
+        [
+            Id => "id",
+            StartedAt => "started_at",
+        ]
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared_dtos::reconciliation::ReconciliationDiscrepancyKind;
+use sqlx::{prelude::FromRow, types::Json};
+
+use crate::define_list_query;
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct ReconciliationReportRow {
+    pub id: i64,
+    pub day: NaiveDate,
+    pub transactions_checked: i32,
+    pub invoices_checked: i32,
+    pub customers_checked: i32,
+    pub discrepancies: Json<Vec<ReconciliationDiscrepancy>>,
+    pub triggered_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationDiscrepancy {
+    pub kind: ReconciliationDiscrepancyKind,
+    /// Customer, transaction or payment invoice id, depending on `kind`
+    pub entity_id: i64,
+    pub expected: Decimal,
+    pub actual: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReconciliationCounts {
+    pub transactions_checked: i32,
+    pub invoices_checked: i32,
+    pub customers_checked: i32,
+}
+
+#[derive(Debug)]
+pub struct NewReconciliationReport {
+    pub day: NaiveDate,
+    pub counts: ReconciliationCounts,
+    pub discrepancies: Vec<ReconciliationDiscrepancy>,
+    pub triggered_by: Option<i64>,
+}
+
+define_list_query! {
+    query_name: ReconciliationReportListQuery,
+    filter_fields: {
+        ReconciliationReportFilterFields,
+        [
+            Id => "id",
+            Day => "day",
+            CreatedAt => "created_at",
+        ]
+    },
+    order_fields: {
+        ReconciliationReportOrderFields,
+        [
+            Id => "id",
+            Day => "day",
+        ]
+    }
+}
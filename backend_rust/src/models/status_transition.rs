@@ -0,0 +1,115 @@
+use std::fmt::Debug;
+
+use shared_dtos::{invoice::InvoiceStatus, order::OrderStatus};
+
+use crate::models::{order::OrderRow, payment_invoice::PaymentInvoiceRow};
+
+/// Status with a fixed table of allowed moves. Staying in the same status is not a transition.
+pub trait StatusMachine: Copy + PartialEq + Debug + Send + Sync + 'static {
+    /// Statuses reachable from `self` in one step
+    fn next_statuses(self) -> &'static [Self];
+
+    fn can_transition_to(self, to: Self) -> bool {
+        self.next_statuses().contains(&to)
+    }
+}
+
+impl StatusMachine for InvoiceStatus {
+    fn next_statuses(self) -> &'static [Self] {
+        use InvoiceStatus::*;
+        match self {
+            Pending => &[
+                Processing,
+                AwaitingReceipt,
+                ReceiptSubmitted,
+                Disputed,
+                Completed,
+                Failed,
+                Expired,
+                Cancelled,
+            ],
+            Processing => &[
+                AwaitingReceipt,
+                ReceiptSubmitted,
+                Disputed,
+                Completed,
+                Failed,
+                Expired,
+                Cancelled,
+            ],
+            AwaitingReceipt => &[
+                ReceiptSubmitted,
+                Disputed,
+                Completed,
+                Failed,
+                Expired,
+                Cancelled,
+            ],
+            ReceiptSubmitted => &[
+                AwaitingReceipt,
+                Disputed,
+                Completed,
+                Failed,
+                Expired,
+                Cancelled,
+            ],
+            Disputed => &[
+                AwaitingReceipt,
+                ReceiptSubmitted,
+                Completed,
+                Failed,
+                Expired,
+                Cancelled,
+            ],
+            // A payment found after the invoice was closed is still credited
+            Failed | Expired | Cancelled => &[Completed],
+            Completed => &[Refunded],
+            Refunded => &[],
+        }
+    }
+}
+
+impl StatusMachine for OrderStatus {
+    fn next_statuses(self) -> &'static [Self] {
+        use OrderStatus::*;
+        match self {
+            Created => &[Paid, Fulfilled, Cancelled],
+            Paid => &[Fulfilled, Cancelled, Refunded],
+            Fulfilled => &[Refunded],
+            Cancelled | Refunded => &[],
+        }
+    }
+}
+
+/// A status change that was applied, `row` is the updated record
+#[derive(Debug, Clone)]
+pub struct StatusTransition<S, R> {
+    pub from: S,
+    pub to: S,
+    pub row: R,
+}
+
+pub type InvoiceTransition = StatusTransition<InvoiceStatus, PaymentInvoiceRow>;
+pub type OrderTransition = StatusTransition<OrderStatus, OrderRow>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finished_invoices_do_not_reopen() {
+        assert!(InvoiceStatus::Pending.can_transition_to(InvoiceStatus::AwaitingReceipt));
+        assert!(InvoiceStatus::Expired.can_transition_to(InvoiceStatus::Completed));
+        assert!(!InvoiceStatus::Completed.can_transition_to(InvoiceStatus::Pending));
+        assert!(!InvoiceStatus::Failed.can_transition_to(InvoiceStatus::AwaitingReceipt));
+        assert!(!InvoiceStatus::Pending.can_transition_to(InvoiceStatus::Pending));
+    }
+
+    #[test]
+    fn test_order_transitions() {
+        assert!(OrderStatus::Created.can_transition_to(OrderStatus::Paid));
+        assert!(OrderStatus::Paid.can_transition_to(OrderStatus::Fulfilled));
+        assert!(!OrderStatus::Fulfilled.can_transition_to(OrderStatus::Created));
+        assert!(!OrderStatus::Cancelled.can_transition_to(OrderStatus::Paid));
+    }
+}
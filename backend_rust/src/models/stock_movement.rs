@@ -56,5 +56,6 @@ define_list_query! {
             CreatedAt => "created_at",
             CreatedBy => "created_by"
         ]
-    }
+    },
+    cursor_field: Id => "sm.id"
 }
@@ -9,6 +9,7 @@ use uuid::Uuid;
 pub enum TemporaryTokenPurpose {
     TwoFa,
     PasswordReset,
+    TwoFaEnrollment,
 }
 
 #[derive(Debug, FromRow)]
@@ -23,6 +23,7 @@ pub struct TransactionRow {
     pub payment_gateway: Option<PaymentSystem>,
     pub details: Option<serde_json::Value>,
     pub bot_id: Option<i64>,
+    pub payment_invoice_id: Option<i64>,
 }
 
 #[derive(Debug)]
@@ -38,6 +39,7 @@ pub struct NewTransaction {
     pub payment_gateway: Option<PaymentSystem>,
     pub details: Option<serde_json::Value>,
     pub bot_id: Option<i64>,
+    pub payment_invoice_id: Option<i64>,
 }
 
 define_list_query! {
@@ -48,6 +50,7 @@ define_list_query! {
             Id => "id",
             CustomerId => "customer_id",
             OrderId => "order_id",
+            PaymentInvoiceId => "payment_invoice_id",
             Type => "type",
             Amount => "amount",
             StoreBalanceDelta => "store_balance_delta",
@@ -69,5 +72,6 @@ define_list_query! {
             GatewayCommission => "gateway_commission",
             CreatedAt => "created_at",
         ]
-    }
+    },
+    cursor_field: Id => "id"
 }
@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use sqlx::prelude::FromRow;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct WorkerHeartbeatRow {
+    pub worker: String,
+    pub last_run_at: DateTime<Utc>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// Errors logged during the last run, zero for a successful one
+    pub last_run_errors: i32,
+}
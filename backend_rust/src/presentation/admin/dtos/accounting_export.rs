@@ -0,0 +1,25 @@
+use shared_dtos::accounting_export::AccountingExportResponse;
+
+use crate::models::accounting_export::AccountingExportRow;
+
+impl From<AccountingExportRow> for AccountingExportResponse {
+    fn from(r: AccountingExportRow) -> Self {
+        AccountingExportResponse {
+            id: r.id,
+            entity: r.entity,
+            format: r.format,
+            status: r.status,
+            date_from: r.date_from,
+            date_to: r.date_to,
+            rows_count: r.rows_count,
+            error: r.error,
+            download_url: r
+                .download_token
+                .map(|token| format!("/api/admin/exports/download/{token}")),
+            expires_at: r.expires_at,
+            created_by: r.created_by,
+            created_at: r.created_at,
+            finished_at: r.finished_at,
+        }
+    }
+}
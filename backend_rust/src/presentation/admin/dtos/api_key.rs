@@ -0,0 +1,90 @@
+use shared_dtos::api_key::{ApiKeyAdminResponse, CreatedApiKeyAdminResponse};
+
+use crate::{models::admin_api_key::AdminApiKeyRow, services::admin_api_key::CreatedApiKey};
+
+impl From<AdminApiKeyRow> for ApiKeyAdminResponse {
+    fn from(r: AdminApiKeyRow) -> Self {
+        ApiKeyAdminResponse {
+            id: r.id,
+            name: r.name,
+            key_prefix: r.key_prefix,
+            permissions: r.permissions,
+            allowed_ips: r.allowed_ips.iter().map(ToString::to_string).collect(),
+            expires_at: r.expires_at,
+            last_used_at: r.last_used_at,
+            last_used_ip: r.last_used_ip.map(|ip| ip.ip().to_string()),
+            created_by: r.created_by,
+            created_at: r.created_at,
+            revoked_at: r.revoked_at,
+        }
+    }
+}
+
+impl From<CreatedApiKey> for CreatedApiKeyAdminResponse {
+    fn from(c: CreatedApiKey) -> Self {
+        CreatedApiKeyAdminResponse {
+            key: c.key,
+            api_key: c.api_key.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use shared_dtos::api_key::NewApiKeyAdminRequest;
+    use validator::Validate;
+
+    #[test]
+    fn test_api_key_response_from_row() {
+        let now = Utc::now();
+        let row = AdminApiKeyRow {
+            id: 1,
+            name: "accounting".to_string(),
+            key_prefix: "ak_abcdef".to_string(),
+            key_hash: "hash".to_string(),
+            permissions: vec!["orders:read".to_string()],
+            allowed_ips: vec!["10.0.0.0/8".parse().unwrap()],
+            expires_at: None,
+            last_used_at: Some(now),
+            last_used_ip: Some("10.0.0.1".parse().unwrap()),
+            created_by: 1,
+            created_at: now,
+            revoked_at: None,
+        };
+
+        let response: ApiKeyAdminResponse = row.into();
+
+        assert_eq!(response.key_prefix, "ak_abcdef");
+        assert_eq!(response.allowed_ips, vec!["10.0.0.0/8".to_string()]);
+        assert_eq!(response.last_used_ip, Some("10.0.0.1".to_string()));
+    }
+
+    #[test]
+    fn test_new_api_key_request_validation() {
+        let req = NewApiKeyAdminRequest {
+            name: "accounting".to_string(),
+            permissions: vec!["orders:read".to_string()],
+            allowed_ips: vec![],
+            expires_at: None,
+        };
+        assert!(req.validate().is_ok());
+
+        let req = NewApiKeyAdminRequest {
+            name: "ab".to_string(),
+            permissions: vec!["orders:read".to_string()],
+            allowed_ips: vec![],
+            expires_at: None,
+        };
+        assert!(req.validate().is_err());
+
+        let req = NewApiKeyAdminRequest {
+            name: "accounting".to_string(),
+            permissions: vec![],
+            allowed_ips: vec![],
+            expires_at: None,
+        };
+        assert!(req.validate().is_err());
+    }
+}
@@ -1,6 +1,32 @@
+use shared_dtos::auth::{TwoFaEnrollmentAdminResponse, TwoFaRotationAdminResponse};
+
+use crate::services::admin_user::{TwoFaEnrollment, TwoFaRotation};
+
+impl From<TwoFaEnrollment> for TwoFaEnrollmentAdminResponse {
+    fn from(r: TwoFaEnrollment) -> Self {
+        TwoFaEnrollmentAdminResponse {
+            two_fa_secret: r.two_fa_secret,
+            two_fa_qr_code: r.two_fa_qr_code,
+            recovery_codes: r.recovery_codes,
+        }
+    }
+}
+
+impl From<TwoFaRotation> for TwoFaRotationAdminResponse {
+    fn from(r: TwoFaRotation) -> Self {
+        TwoFaRotationAdminResponse {
+            two_fa_secret: r.two_fa_secret,
+            two_fa_qr_code: r.two_fa_qr_code,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use shared_dtos::auth::{LoginStep1AdminRequest, LoginStep2AdminRequest};
+    use shared_dtos::auth::{
+        ChangePasswordAdminRequest, LoginRecoveryAdminRequest, LoginStep1AdminRequest,
+        LoginStep2AdminRequest,
+    };
     use uuid::Uuid;
     use validator::Validate;
 
@@ -65,4 +91,34 @@ mod tests {
         };
         assert!(req.validate().is_err());
     }
+
+    #[test]
+    fn test_login_recovery_request_validation() {
+        let req = LoginRecoveryAdminRequest {
+            temp_token: Uuid::new_v4(),
+            recovery_code: "abcde-fghjk".to_string(),
+        };
+        assert!(req.validate().is_ok());
+
+        let req = LoginRecoveryAdminRequest {
+            temp_token: Uuid::new_v4(),
+            recovery_code: "abc".to_string(),
+        };
+        assert!(req.validate().is_err());
+    }
+
+    #[test]
+    fn test_change_password_request_validation() {
+        let req = ChangePasswordAdminRequest {
+            current_password: "old".to_string(),
+            new_password: "goodpassword".to_string(),
+        };
+        assert!(req.validate().is_ok());
+
+        let req = ChangePasswordAdminRequest {
+            current_password: "old".to_string(),
+            new_password: "short".to_string(),
+        };
+        assert!(req.validate().is_err());
+    }
 }
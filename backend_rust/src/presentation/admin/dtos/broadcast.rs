@@ -1,29 +1,32 @@
-use crate::models::common::Filter;
-use shared_dtos::broadcast::{BroadcastResponse, JsonRawListQuery};
+use reqwest::Url;
+use shared_dtos::broadcast::{
+    BroadcastButton, BroadcastButtonAction, BroadcastMedia, BroadcastMediaKind,
+    BroadcastRecipientResponse, BroadcastResponse, BroadcastTextEntity, BroadcastTextEntityKind,
+    BroadcastVariantResponse, BroadcastVariantStatsResponse, JsonRawListQuery,
+};
+use uuid::Uuid;
 
-use crate::models::{
-    broadcast::BroadcastRow,
-    customer::{CustomerFilterFields, CustomerListQuery},
+use crate::{
+    middlewares::query::filters_from_raw,
+    models::{
+        broadcast::{
+            BroadcastRecipientRow, BroadcastRow, BroadcastVariantRow, BroadcastVariantStatsRow,
+        },
+        customer::CustomerListQuery,
+    },
 };
 
 impl CustomerListQuery {
     pub fn try_from_json(json_val: JsonRawListQuery) -> Result<Self, String> {
-        let mut filters = Vec::new();
-        for raw_filter in json_val.filters {
-            let field =
-                CustomerFilterFields::try_from(raw_filter.field).map_err(|e| e.to_string())?;
-
-            filters.push(Filter {
-                field,
-                op: raw_filter.op,
-                value: raw_filter.value.into(),
-            });
-        }
+        let (filters, groups) = filters_from_raw(json_val.filters, json_val.groups)?;
         Ok(CustomerListQuery {
             filters,
+            groups,
             pagination: Default::default(),
             order_by: None,
             order_dir: Default::default(),
+            cursor: None,
+            search: None,
         })
     }
 }
@@ -35,7 +38,11 @@ impl From<BroadcastRow> for BroadcastResponse {
             status: r.status,
             content_text: r.content_text,
             content_image_id: r.content_image_id,
+            content_entities: r.content_entities.0,
+            content_media: r.content_media.0,
+            content_buttons: r.content_buttons.0,
             filters: r.filters,
+            segment_id: r.segment_id,
             statistics: r.statistics,
             created_by: r.created_by,
             scheduled_for: r.scheduled_for,
@@ -47,9 +54,177 @@ impl From<BroadcastRow> for BroadcastResponse {
     }
 }
 
+// Telegram Bot API limits
+const MAX_MEDIA_GROUP_SIZE: usize = 10;
+const MAX_BUTTONS_PER_ROW: usize = 8;
+const MAX_BUTTONS: usize = 100;
+const MAX_BUTTON_TEXT_LEN: usize = 64;
+
+/// Checks rich broadcast content against what Telegram is able to deliver
+pub fn validate_broadcast_content(
+    text: Option<&str>,
+    image_id: Option<Uuid>,
+    entities: &[BroadcastTextEntity],
+    media: &[BroadcastMedia],
+    buttons: &[Vec<BroadcastButton>],
+) -> Result<(), String> {
+    let text = text.filter(|text| !text.is_empty());
+    let media_count = media.len() + usize::from(image_id.is_some());
+
+    if text.is_none() && media_count == 0 {
+        return Err("Broadcast must have text or media".to_string());
+    }
+    if media_count > MAX_MEDIA_GROUP_SIZE {
+        return Err(format!(
+            "Broadcast can have at most {MAX_MEDIA_GROUP_SIZE} media attachments"
+        ));
+    }
+
+    for item in media {
+        match (&item.image_id, &item.url) {
+            (Some(_), None) if item.kind == BroadcastMediaKind::Photo => {}
+            (Some(_), None) => {
+                return Err("Only photos can reference uploaded images".to_string());
+            }
+            (None, Some(url)) => validate_url(url, &["http", "https"])?,
+            _ => return Err("Media must have either image_id or url".to_string()),
+        }
+    }
+    if media_count > 1 {
+        let documents = media
+            .iter()
+            .filter(|item| item.kind == BroadcastMediaKind::Document)
+            .count();
+        if documents > 0 && documents != media_count {
+            return Err("Documents can't be grouped with photos or videos".to_string());
+        }
+        if text.is_none() && !buttons.is_empty() {
+            return Err("Media groups with buttons require text".to_string());
+        }
+    }
+
+    if !entities.is_empty() {
+        let Some(text) = text else {
+            return Err("Text entities require text".to_string());
+        };
+        let text_len = text.encode_utf16().count() as u64;
+        for entity in entities {
+            if entity.length == 0 || u64::from(entity.offset) + u64::from(entity.length) > text_len
+            {
+                return Err("Text entity is out of text bounds".to_string());
+            }
+            if entity.kind == BroadcastTextEntityKind::TextLink {
+                let url = entity
+                    .url
+                    .as_deref()
+                    .ok_or("Text link entity requires url")?;
+                validate_url(url, &["http", "https", "tg"])?;
+            }
+        }
+    }
+
+    if buttons.iter().map(Vec::len).sum::<usize>() > MAX_BUTTONS {
+        return Err(format!("Broadcast can have at most {MAX_BUTTONS} buttons"));
+    }
+    for row in buttons {
+        if row.is_empty() || row.len() > MAX_BUTTONS_PER_ROW {
+            return Err(format!(
+                "Button row must have from 1 to {MAX_BUTTONS_PER_ROW} buttons"
+            ));
+        }
+        for button in row {
+            let len = button.text.trim().chars().count();
+            if len == 0 || len > MAX_BUTTON_TEXT_LEN {
+                return Err(format!(
+                    "Button text must be from 1 to {MAX_BUTTON_TEXT_LEN} characters"
+                ));
+            }
+            if let BroadcastButtonAction::Url { url } = &button.action {
+                validate_url(url, &["http", "https", "tg"])?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_url(url: &str, schemes: &[&str]) -> Result<(), String> {
+    match Url::parse(url) {
+        Ok(parsed) if schemes.contains(&parsed.scheme()) => Ok(()),
+        _ => Err(format!("Invalid url: {url}")),
+    }
+}
+
+impl From<BroadcastRecipientRow> for BroadcastRecipientResponse {
+    fn from(r: BroadcastRecipientRow) -> Self {
+        BroadcastRecipientResponse {
+            id: r.id,
+            broadcast_id: r.broadcast_id,
+            customer_id: r.customer_id,
+            telegram_id: r.telegram_id,
+            variant_id: r.variant_id,
+            status: r.status,
+            error_message: r.error_message,
+            sent_at: r.sent_at,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
+
+impl From<BroadcastVariantRow> for BroadcastVariantResponse {
+    fn from(r: BroadcastVariantRow) -> Self {
+        BroadcastVariantResponse {
+            id: r.id,
+            broadcast_id: r.broadcast_id,
+            name: r.name,
+            content_text: r.content_text,
+            content_image_id: r.content_image_id,
+            content_entities: r.content_entities.0,
+            content_media: r.content_media.0,
+            content_buttons: r.content_buttons.0,
+            created_at: r.created_at,
+        }
+    }
+}
+
+/// Name of the broadcast's own content in variant stats
+pub const BASE_VARIANT_NAME: &str = "A";
+
+/// Labels per-variant stats with variant names, the base content comes first
+pub fn variant_stats_response(
+    stats: Vec<BroadcastVariantStatsRow>,
+    variants: &[BroadcastVariantRow],
+) -> Vec<BroadcastVariantStatsResponse> {
+    stats
+        .into_iter()
+        .map(|row| {
+            let name = row
+                .variant_id
+                .and_then(|id| variants.iter().find(|variant| variant.id == id))
+                .map_or(BASE_VARIANT_NAME, |variant| variant.name.as_str())
+                .to_string();
+            let conversion_rate = if row.sent > 0 {
+                row.conversions as f64 / row.sent as f64
+            } else {
+                0.0
+            };
+            BroadcastVariantStatsResponse {
+                variant_id: row.variant_id,
+                name,
+                recipients: row.recipients,
+                sent: row.sent,
+                conversions: row.conversions,
+                conversion_rate,
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::common::FilterExpr;
     use chrono::{DateTime, Timelike, Utc};
     use serde_json::json;
     use shared_dtos::{
@@ -59,6 +234,7 @@ mod tests {
         },
         list_query::{FilterValue, Operator, ScalarValue},
     };
+    use sqlx::types::Json;
     use uuid::Uuid;
     use validator::Validate;
 
@@ -214,6 +390,7 @@ mod tests {
                     value: JsonFilterValue::Scalar(JsonScalarValue::DateTime(Utc::now())),
                 },
             ],
+            groups: vec![],
         };
 
         let customer_list_query = CustomerListQuery::try_from_json(json_raw_query).unwrap();
@@ -241,6 +418,7 @@ mod tests {
                 op: Operator::Gt,
                 value: JsonFilterValue::Scalar(JsonScalarValue::Int(100)),
             }],
+            groups: vec![],
         };
 
         let result = CustomerListQuery::try_from_json(json_raw_query);
@@ -252,6 +430,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_try_from_json_conversion_groups() {
+        let json_str = r#"{
+            "filters": [],
+            "groups": [
+                {
+                    "or": [
+                        {"field": "balance", "op": "between", "value": [100, 500]},
+                        {"not": {"field": "last_seen_at", "op": "not_null"}}
+                    ]
+                }
+            ]
+        }"#;
+        let query: JsonRawListQuery = serde_json::from_str(json_str).unwrap();
+        let customer_list_query = CustomerListQuery::try_from_json(query).unwrap();
+        assert!(customer_list_query.filters.is_empty());
+
+        let [FilterExpr::Or(children)] = customer_list_query.groups.as_slice() else {
+            panic!("Expected a single OR group");
+        };
+        match &children[0] {
+            FilterExpr::Filter(filter) => {
+                assert_eq!(filter.op, Operator::Between);
+                assert!(matches!(&filter.value, FilterValue::Array(values) if values.len() == 2));
+            }
+            _ => panic!("Expected a filter"),
+        }
+        match &children[1] {
+            FilterExpr::Not(inner) => {
+                assert!(
+                    matches!(inner.as_ref(), FilterExpr::Filter(f) if f.op == Operator::NotNull)
+                )
+            }
+            _ => panic!("Expected a NOT group"),
+        }
+
+        let json_str = r#"{"filters": [], "groups": [{"and": [{"field": "invalid_field", "op": "eq", "value": 1}]}]}"#;
+        let query: JsonRawListQuery = serde_json::from_str(json_str).unwrap();
+        assert!(CustomerListQuery::try_from_json(query).is_err());
+    }
+
     #[test]
     fn test_json_raw_list_query_serialization_scalar_int() {
         let json_raw_query = JsonRawListQuery {
@@ -260,6 +479,7 @@ mod tests {
                 op: Operator::Gt,
                 value: JsonFilterValue::Scalar(JsonScalarValue::Int(500)),
             }],
+            groups: vec![],
         };
         let serialized = serde_json::to_string(&json_raw_query).unwrap();
         let expected = r#"{"filters":[{"field":"balance","op":"gt","value":500}]}"#;
@@ -275,6 +495,7 @@ mod tests {
                 op: Operator::Gt,
                 value: JsonFilterValue::Scalar(JsonScalarValue::DateTime(dt)),
             }],
+            groups: vec![],
         };
         let serialized = serde_json::to_string(&json_raw_query).unwrap();
         let expected_json = serde_json::json!({
@@ -293,7 +514,12 @@ mod tests {
         let req = NewBroadcastRequest {
             content_text: Some("short text".to_string()),
             content_image_id: None,
+            content_entities: vec![],
+            content_media: vec![],
+            content_buttons: vec![],
             filters: None,
+            segment_id: None,
+            variants: vec![],
             scheduled_for: None,
         };
         assert!(req.validate().is_ok());
@@ -302,7 +528,12 @@ mod tests {
         let req = NewBroadcastRequest {
             content_text: None,
             content_image_id: Some(Uuid::new_v4()),
+            content_entities: vec![],
+            content_media: vec![],
+            content_buttons: vec![],
             filters: None,
+            segment_id: None,
+            variants: vec![],
             scheduled_for: None,
         };
         assert!(req.validate().is_ok());
@@ -311,7 +542,12 @@ mod tests {
         let req = NewBroadcastRequest {
             content_text: Some("short text".to_string()),
             content_image_id: Some(Uuid::new_v4()),
+            content_entities: vec![],
+            content_media: vec![],
+            content_buttons: vec![],
             filters: None,
+            segment_id: None,
+            variants: vec![],
             scheduled_for: None,
         };
         assert!(req.validate().is_ok());
@@ -320,7 +556,12 @@ mod tests {
         let req = NewBroadcastRequest {
             content_text: Some("a".repeat(1025)),
             content_image_id: None,
+            content_entities: vec![],
+            content_media: vec![],
+            content_buttons: vec![],
             filters: None,
+            segment_id: None,
+            variants: vec![],
             scheduled_for: None,
         };
         assert!(req.validate().is_err());
@@ -329,7 +570,15 @@ mod tests {
         let req = NewBroadcastRequest {
             content_text: Some("short text".to_string()),
             content_image_id: None,
-            filters: Some(JsonRawListQuery { filters: vec![] }),
+            content_entities: vec![],
+            content_media: vec![],
+            content_buttons: vec![],
+            filters: Some(JsonRawListQuery {
+                filters: vec![],
+                groups: vec![],
+            }),
+            segment_id: None,
+            variants: vec![],
             scheduled_for: None,
         };
         assert!(req.validate().is_ok());
@@ -341,6 +590,9 @@ mod tests {
         let req = UpdateBroadcastRequest {
             content_text: Some(Some("short text".to_string())),
             content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
             filters: None,
             scheduled_for: None,
         };
@@ -350,6 +602,9 @@ mod tests {
         let req = UpdateBroadcastRequest {
             content_text: Some(None),
             content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
             filters: None,
             scheduled_for: None,
         };
@@ -359,6 +614,9 @@ mod tests {
         let req = UpdateBroadcastRequest {
             content_text: None,
             content_image_id: Some(Some(Uuid::new_v4())),
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
             filters: None,
             scheduled_for: None,
         };
@@ -368,6 +626,9 @@ mod tests {
         let req = UpdateBroadcastRequest {
             content_text: None,
             content_image_id: Some(None),
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
             filters: None,
             scheduled_for: None,
         };
@@ -377,6 +638,9 @@ mod tests {
         let req = UpdateBroadcastRequest {
             content_text: Some(Some("a".repeat(1025))),
             content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
             filters: None,
             scheduled_for: None,
         };
@@ -386,7 +650,13 @@ mod tests {
         let req = UpdateBroadcastRequest {
             content_text: Some(Some("short text".to_string())),
             content_image_id: None,
-            filters: Some(JsonRawListQuery { filters: vec![] }),
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
+            filters: Some(JsonRawListQuery {
+                filters: vec![],
+                groups: vec![],
+            }),
             scheduled_for: None,
         };
         assert!(req.validate().is_ok());
@@ -400,7 +670,11 @@ mod tests {
             status: BroadcastStatus::Scheduled,
             content_text: Some("test broadcast content".to_string()),
             content_image_id: Some(Uuid::new_v4()),
+            content_entities: Json(vec![]),
+            content_media: Json(vec![]),
+            content_buttons: Json(vec![]),
             filters: Some(json!({"filter_key": "filter_value"})),
+            segment_id: None,
             statistics: Some(json!({"stats_key": "stats_value"})),
             created_by: 101,
             scheduled_for: Some(now + chrono::Duration::hours(1)),
@@ -443,7 +717,11 @@ mod tests {
             status: BroadcastStatus::Pending,
             content_text: None,
             content_image_id: None,
+            content_entities: Json(vec![]),
+            content_media: Json(vec![]),
+            content_buttons: Json(vec![]),
             filters: None,
+            segment_id: None,
             statistics: None,
             created_by: 102,
             scheduled_for: None,
@@ -468,4 +746,159 @@ mod tests {
         assert_eq!(response.created_at, now);
         assert_eq!(response.updated_at, now);
     }
+
+    fn button(text: &str, action: BroadcastButtonAction) -> BroadcastButton {
+        BroadcastButton {
+            text: text.to_string(),
+            action,
+        }
+    }
+
+    fn media(kind: BroadcastMediaKind, url: &str) -> BroadcastMedia {
+        BroadcastMedia {
+            kind,
+            image_id: None,
+            url: Some(url.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_validate_broadcast_content() {
+        let bold = BroadcastTextEntity {
+            kind: BroadcastTextEntityKind::Bold,
+            offset: 0,
+            length: 5,
+            url: None,
+            language: None,
+        };
+        let buttons = vec![vec![
+            button("Open", BroadcastButtonAction::Product { product_id: 1 }),
+            button(
+                "Site",
+                BroadcastButtonAction::Url {
+                    url: "https://example.com".to_string(),
+                },
+            ),
+        ]];
+        let album = vec![
+            media(BroadcastMediaKind::Photo, "https://example.com/a.jpg"),
+            media(BroadcastMediaKind::Video, "https://example.com/b.mp4"),
+        ];
+
+        assert!(
+            validate_broadcast_content(
+                Some("Hello"),
+                Some(Uuid::new_v4()),
+                std::slice::from_ref(&bold),
+                &album,
+                &buttons
+            )
+            .is_ok()
+        );
+        // Entity offsets are counted in UTF-16 code units
+        assert!(
+            validate_broadcast_content(Some("🎉🎉 !"), None, std::slice::from_ref(&bold), &[], &[])
+                .is_ok()
+        );
+
+        // No content at all
+        assert!(validate_broadcast_content(None, None, &[], &[], &buttons).is_err());
+        // Entity out of bounds
+        assert!(
+            validate_broadcast_content(Some("Hi"), None, std::slice::from_ref(&bold), &[], &[])
+                .is_err()
+        );
+        // Text link without url
+        let link = BroadcastTextEntity {
+            kind: BroadcastTextEntityKind::TextLink,
+            ..bold
+        };
+        assert!(validate_broadcast_content(Some("Hello"), None, &[link], &[], &[]).is_err());
+        // Documents mixed with photos
+        let mixed = vec![
+            media(BroadcastMediaKind::Photo, "https://example.com/a.jpg"),
+            media(BroadcastMediaKind::Document, "https://example.com/b.pdf"),
+        ];
+        assert!(validate_broadcast_content(Some("Hello"), None, &[], &mixed, &[]).is_err());
+        // Album with buttons but no text to attach the keyboard to
+        assert!(validate_broadcast_content(None, None, &[], &album, &buttons).is_err());
+        // Uploaded images can only be photos
+        let video = BroadcastMedia {
+            kind: BroadcastMediaKind::Video,
+            image_id: Some(Uuid::new_v4()),
+            url: None,
+        };
+        assert!(validate_broadcast_content(None, None, &[], &[video], &[]).is_err());
+        // Too many media
+        let too_many = vec![media(BroadcastMediaKind::Photo, "https://example.com/a.jpg"); 11];
+        assert!(validate_broadcast_content(None, None, &[], &too_many, &[]).is_err());
+        // Unsupported url scheme and empty rows
+        let bad_url = vec![vec![button(
+            "Open",
+            BroadcastButtonAction::Url {
+                url: "javascript:alert(1)".to_string(),
+            },
+        )]];
+        assert!(validate_broadcast_content(Some("Hello"), None, &[], &[], &bad_url).is_err());
+        assert!(validate_broadcast_content(Some("Hello"), None, &[], &[], &[vec![]]).is_err());
+    }
+
+    #[test]
+    fn test_variant_stats_response_names_and_rates() {
+        let now = Utc::now();
+        let variant = BroadcastVariantRow {
+            id: 7,
+            broadcast_id: 1,
+            name: "Short text".to_string(),
+            content_text: Some("Hi".to_string()),
+            content_image_id: None,
+            content_entities: sqlx::types::Json(vec![]),
+            content_media: sqlx::types::Json(vec![]),
+            content_buttons: sqlx::types::Json(vec![]),
+            created_at: now,
+        };
+        let stats = vec![
+            BroadcastVariantStatsRow {
+                variant_id: None,
+                recipients: 10,
+                sent: 8,
+                conversions: 2,
+            },
+            BroadcastVariantStatsRow {
+                variant_id: Some(7),
+                recipients: 10,
+                sent: 0,
+                conversions: 0,
+            },
+        ];
+
+        let response = variant_stats_response(stats, &[variant]);
+
+        assert_eq!(response[0].name, BASE_VARIANT_NAME);
+        assert_eq!(response[0].conversion_rate, 0.25);
+        assert_eq!(response[1].name, "Short text");
+        assert_eq!(response[1].conversion_rate, 0.0);
+    }
+
+    #[test]
+    fn test_segment_filters_are_combined() {
+        let segment: JsonRawListQuery = serde_json::from_value(json!({
+            "filters": [{"field": "orders_count", "op": "gt", "value": 0}],
+            "groups": [{"or": [
+                {"field": "registered_with_bot", "op": "eq", "value": 1},
+                {"field": "registered_with_bot", "op": "eq", "value": 2}
+            ]}]
+        }))
+        .unwrap();
+        let explicit: JsonRawListQuery = serde_json::from_value(json!({
+            "filters": [{"field": "balance", "op": "gt", "value": 0}]
+        }))
+        .unwrap();
+
+        let query = CustomerListQuery::try_from_json(segment.and(explicit)).unwrap();
+
+        assert_eq!(query.filters.len(), 2);
+        assert_eq!(query.groups.len(), 1);
+        assert!(query.filters_any(|field| field.is_aggregate()));
+    }
 }
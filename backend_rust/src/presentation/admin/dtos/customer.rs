@@ -17,6 +17,7 @@ impl From<CustomerRow> for CustomerAdminResponse {
             last_seen_at: r.last_seen_at,
             created_at: r.created_at,
             updated_at: r.updated_at,
+            display_currency: r.display_currency,
         }
     }
 }
@@ -45,6 +46,7 @@ mod tests {
             created_at: now,
             updated_at: now,
             blocked_until: None,
+            display_currency: None,
         };
 
         let customer_response: CustomerAdminResponse = customer_row.into();
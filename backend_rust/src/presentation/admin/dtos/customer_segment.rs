@@ -0,0 +1,17 @@
+use shared_dtos::customer_segment::CustomerSegmentResponse;
+
+use crate::models::customer_segment::CustomerSegmentRow;
+
+impl From<CustomerSegmentRow> for CustomerSegmentResponse {
+    fn from(r: CustomerSegmentRow) -> Self {
+        CustomerSegmentResponse {
+            id: r.id,
+            name: r.name,
+            description: r.description,
+            filters: r.filters,
+            created_by: r.created_by,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
@@ -1,7 +1,19 @@
 use rust_decimal::prelude::ToPrimitive;
-use shared_dtos::invoice::PaymentInvoiceAdminResponse;
+use shared_dtos::{
+    audit_log::AuditLogAdminResponse,
+    invoice::{
+        PaymentInvoiceAdminResponse, PaymentInvoiceEventAdminResponse,
+        PaymentInvoiceHistoryAdminResponse, PaymentWebhookEventAdminResponse,
+    },
+};
 
-use crate::models::payment_invoice::PaymentInvoiceRow;
+use crate::{
+    models::{
+        payment_invoice::PaymentInvoiceRow, payment_invoice_event::PaymentInvoiceEventRow,
+        payment_webhook_event::PaymentWebhookEventRow,
+    },
+    services::payment_invoice_resolution::PaymentInvoiceHistory,
+};
 
 impl From<PaymentInvoiceRow> for PaymentInvoiceAdminResponse {
     fn from(r: PaymentInvoiceRow) -> Self {
@@ -16,6 +28,58 @@ impl From<PaymentInvoiceRow> for PaymentInvoiceAdminResponse {
             expires_at: r.expires_at,
             gateway: r.gateway,
             gateway_invoice_id: r.gateway_invoice_id,
+            currency: r.currency,
+            checkout_order_id: r.checkout_order_id,
+        }
+    }
+}
+
+impl From<PaymentInvoiceEventRow> for PaymentInvoiceEventAdminResponse {
+    fn from(r: PaymentInvoiceEventRow) -> Self {
+        PaymentInvoiceEventAdminResponse {
+            id: r.id,
+            kind: r.kind,
+            from_status: r.from_status,
+            to_status: r.to_status,
+            payload: r.payload,
+            created_at: r.created_at,
+        }
+    }
+}
+
+impl From<PaymentWebhookEventRow> for PaymentWebhookEventAdminResponse {
+    fn from(r: PaymentWebhookEventRow) -> Self {
+        PaymentWebhookEventAdminResponse {
+            id: r.id,
+            event_id: r.event_id,
+            payload: r.payload,
+            received_at: r.received_at,
+            processed_at: r.processed_at,
+            error: r.error,
+        }
+    }
+}
+
+impl From<PaymentInvoiceHistory> for PaymentInvoiceHistoryAdminResponse {
+    fn from(r: PaymentInvoiceHistory) -> Self {
+        let invoice = r.invoice;
+        PaymentInvoiceHistoryAdminResponse {
+            order_id: invoice.order_id,
+            payment_details: invoice.payment_details.clone(),
+            notification_sent_at: invoice.notification_sent_at,
+            receipt_requested_at: invoice.receipt_requested_at,
+            receipt_submitted_at: invoice.receipt_submitted_at,
+            receipt_url: invoice.receipt_url.clone(),
+            dispute_opened_at: invoice.dispute_opened_at,
+            finished_at: invoice.finished_at,
+            invoice: invoice.into(),
+            events: r.events.into_iter().map(Into::into).collect(),
+            webhook_events: r.webhook_events.into_iter().map(Into::into).collect(),
+            actions: r
+                .actions
+                .into_iter()
+                .map(AuditLogAdminResponse::from)
+                .collect(),
         }
     }
 }
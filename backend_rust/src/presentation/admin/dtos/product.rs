@@ -21,6 +21,7 @@ impl From<Product> for ProductAdminResponse {
             fulfillment_image_id: r.fulfillment_image_id,
             provider_name: r.provider_name,
             external_id: r.external_id,
+            hidden_at: r.hidden_at,
             created_at: r.created_at,
             updated_at: r.updated_at,
             created_by: r.created_by,
@@ -58,6 +59,7 @@ mod tests {
             fulfillment_image_id: Some(Uuid::new_v4()),
             provider_name: "SomeProvider".to_string(),
             external_id: Some("ext123".to_string()),
+            hidden_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
             created_by: 1,
@@ -204,6 +206,7 @@ mod tests {
             fulfillment_image_id: None,
             external_id: None,
             stock: None,
+            hidden: None,
         };
         assert!(req.validate().is_ok());
 
@@ -220,6 +223,7 @@ mod tests {
             fulfillment_image_id: Some(Some(Uuid::new_v4())),
             external_id: Some(Some("ext456".to_string())),
             stock: Some(50),
+            hidden: None,
         };
         assert!(req.validate().is_ok());
 
@@ -236,6 +240,7 @@ mod tests {
             fulfillment_image_id: Some(None),
             external_id: Some(None),
             stock: None,
+            hidden: None,
         };
         assert!(req.validate().is_ok());
 
@@ -252,6 +257,7 @@ mod tests {
             fulfillment_image_id: None,
             external_id: None,
             stock: None,
+            hidden: None,
         };
         assert!(req.validate().is_err());
 
@@ -268,6 +274,7 @@ mod tests {
             fulfillment_image_id: None,
             external_id: None,
             stock: None,
+            hidden: None,
         };
         assert!(req.validate().is_err());
 
@@ -284,6 +291,7 @@ mod tests {
             fulfillment_image_id: None,
             external_id: None,
             stock: None,
+            hidden: None,
         };
         assert!(req.validate().is_err());
 
@@ -300,6 +308,7 @@ mod tests {
             fulfillment_image_id: None,
             external_id: None,
             stock: None,
+            hidden: None,
         };
         assert!(req.validate().is_err());
     }
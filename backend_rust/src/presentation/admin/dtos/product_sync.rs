@@ -0,0 +1,22 @@
+use shared_dtos::product_sync::ProductSyncRunResponse;
+
+use crate::models::product_sync::ProductSyncRunRow;
+
+impl From<ProductSyncRunRow> for ProductSyncRunResponse {
+    fn from(r: ProductSyncRunRow) -> Self {
+        ProductSyncRunResponse {
+            id: r.id,
+            provider_name: r.provider_name,
+            trigger: r.trigger,
+            status: r.status,
+            added: r.added,
+            updated: r.updated,
+            hidden: r.hidden,
+            restored: r.restored,
+            errors: r.errors.0,
+            triggered_by: r.triggered_by,
+            started_at: r.started_at,
+            finished_at: r.finished_at,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use rust_decimal::prelude::ToPrimitive;
+use shared_dtos::reconciliation::{
+    ReconciliationDiscrepancyKind, ReconciliationDiscrepancyResponse, ReconciliationEntity,
+    ReconciliationReportResponse,
+};
+
+use crate::models::reconciliation::{ReconciliationDiscrepancy, ReconciliationReportRow};
+
+impl From<ReconciliationDiscrepancy> for ReconciliationDiscrepancyResponse {
+    fn from(d: ReconciliationDiscrepancy) -> Self {
+        let entity = match d.kind {
+            ReconciliationDiscrepancyKind::CustomerBalanceMismatch => {
+                ReconciliationEntity::Customer
+            }
+            ReconciliationDiscrepancyKind::UserBalanceChainBroken
+            | ReconciliationDiscrepancyKind::StoreBalanceChainBroken => {
+                ReconciliationEntity::Transaction
+            }
+            ReconciliationDiscrepancyKind::InvoiceMissingDeposit
+            | ReconciliationDiscrepancyKind::InvoiceDuplicateDeposits => {
+                ReconciliationEntity::PaymentInvoice
+            }
+        };
+        let id = d.entity_id;
+        let link = match entity {
+            ReconciliationEntity::Customer => format!("/api/admin/customers/{id}"),
+            ReconciliationEntity::Transaction => format!(
+                "/api/admin/transactions?filters[0][field]=id&filters[0][op]=eq&filters[0][value]={id}"
+            ),
+            ReconciliationEntity::PaymentInvoice => format!(
+                "/api/admin/payment-invoices?filters[0][field]=id&filters[0][op]=eq&filters[0][value]={id}"
+            ),
+        };
+
+        ReconciliationDiscrepancyResponse {
+            kind: d.kind,
+            entity,
+            entity_id: id,
+            expected: d.expected.to_f64().unwrap_or_default(),
+            actual: d.actual.to_f64().unwrap_or_default(),
+            link,
+        }
+    }
+}
+
+impl From<ReconciliationReportRow> for ReconciliationReportResponse {
+    fn from(r: ReconciliationReportRow) -> Self {
+        ReconciliationReportResponse {
+            id: r.id,
+            day: r.day,
+            transactions_checked: r.transactions_checked,
+            invoices_checked: r.invoices_checked,
+            customers_checked: r.customers_checked,
+            discrepancies: r.discrepancies.0.into_iter().map(Into::into).collect(),
+            triggered_by: r.triggered_by,
+            created_at: r.created_at,
+        }
+    }
+}
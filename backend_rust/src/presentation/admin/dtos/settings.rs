@@ -1,10 +1,17 @@
 use rust_decimal::prelude::{Decimal, FromPrimitive, ToPrimitive};
 use shared_dtos::settings::{
-    BotSettingsAdminResponse, PricingSettingsAdminResponse, UpdateBotSettingsAdminRequest,
+    BotSettingsAdminResponse, PaymentGatewaySettingsAdminResponse, PricingSettingsAdminResponse,
+    UpdateBotSettingsAdminRequest, UpdatePaymentGatewaySettingsAdminRequest,
     UpdatePricingSettingsAdminRequest,
 };
 
-use crate::{models::settings::Settings, services::settings::UpdateSettingsCommand};
+use crate::{
+    models::{payment_gateway_settings::PaymentGatewaySettingsRow, settings::Settings},
+    services::{
+        payment_gateway_settings::UpdatePaymentGatewaySettingsCommand,
+        settings::UpdateSettingsCommand,
+    },
+};
 
 impl From<Settings> for PricingSettingsAdminResponse {
     fn from(r: Settings) -> Self {
@@ -89,6 +96,38 @@ impl From<UpdateBotSettingsAdminRequest> for UpdateSettingsCommand {
     }
 }
 
+impl From<PaymentGatewaySettingsRow> for PaymentGatewaySettingsAdminResponse {
+    fn from(r: PaymentGatewaySettingsRow) -> Self {
+        PaymentGatewaySettingsAdminResponse {
+            gateway: r.gateway,
+            invoice_ttl_minutes: r.invoice_ttl_minutes,
+            min_amount: r.min_amount.to_f64().unwrap_or_default(),
+            max_amount: r.max_amount.to_f64().unwrap_or_default(),
+            amount_step: r.amount_step.to_f64().unwrap_or_default(),
+            max_open_invoices: r.max_open_invoices,
+            daily_deposit_limit: r.daily_deposit_limit.and_then(|limit| limit.to_f64()),
+            updated_at: r.updated_at,
+            currency: r.currency,
+        }
+    }
+}
+
+impl From<UpdatePaymentGatewaySettingsAdminRequest> for UpdatePaymentGatewaySettingsCommand {
+    fn from(r: UpdatePaymentGatewaySettingsAdminRequest) -> Self {
+        let f64_opt_to_bd =
+            |opt: Option<f64>| opt.map(|f| Decimal::from_f64(f).unwrap_or_default());
+        UpdatePaymentGatewaySettingsCommand {
+            invoice_ttl_minutes: r.invoice_ttl_minutes,
+            min_amount: f64_opt_to_bd(r.min_amount),
+            max_amount: f64_opt_to_bd(r.max_amount),
+            amount_step: f64_opt_to_bd(r.amount_step),
+            max_open_invoices: r.max_open_invoices,
+            daily_deposit_limit: r.daily_deposit_limit.map(f64_opt_to_bd),
+            ..UpdatePaymentGatewaySettingsCommand::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
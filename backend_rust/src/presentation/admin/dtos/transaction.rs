@@ -9,6 +9,7 @@ impl From<TransactionRow> for TransactionAdminResponse {
             id: row.id,
             customer_id: row.customer_id,
             order_id: row.order_id,
+            payment_invoice_id: row.payment_invoice_id,
             r#type: row.r#type,
             amount: row.amount.to_f64().unwrap_or_default(),
             store_balance_delta: row.store_balance_delta.to_f64().unwrap_or_default(),
@@ -51,6 +52,7 @@ mod tests {
             store_balance_after: dec!(0),
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         };
 
         let transaction_response: TransactionAdminResponse = transaction_row.into();
@@ -93,6 +95,7 @@ mod tests {
             store_balance_after: dec!(0),
             details: None,
             bot_id: None,
+            payment_invoice_id: None,
         };
 
         let transaction_response: TransactionAdminResponse = transaction_row.into();
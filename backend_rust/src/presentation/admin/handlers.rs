@@ -1,17 +1,23 @@
+pub mod accounting_export;
 pub mod admin_user;
+pub mod api_key;
 pub mod audit_log;
 pub mod auth;
 pub mod bot;
 pub mod broadcast;
 pub mod category;
 pub mod customer;
+pub mod customer_segment;
 pub mod dashboard;
+pub mod gateway;
 pub mod image;
 pub mod me;
 pub mod order;
 pub mod payment_invoice;
 pub mod permission;
 pub mod product;
+pub mod product_sync;
+pub mod reconciliation;
 pub mod role;
 pub mod settings;
 pub mod stock_movement;
@@ -0,0 +1,365 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Path, Query, RawQuery, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use shared_dtos::{
+    accounting_export::{
+        AccountingExportEntity, AccountingExportFormat, AccountingExportParams,
+        AccountingExportResponse,
+    },
+    error::ApiErrorResponse,
+    list_query::{FilterValue, Operator, ScalarValue},
+    list_response::ListResponse,
+};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    middlewares::require_permission::{
+        InvoicesRead, OrdersRead, RequirePermission, StockRead, TransactionsRead,
+    },
+    models::{
+        accounting_export::{AccountingExportFilterFields, AccountingExportListQuery},
+        common::Filter,
+    },
+    services::{
+        accounting_export::{
+            AccountingExportServiceTrait, AccountingExportStart, CreateAccountingExportCommand,
+            CsvSink, ExportSink, XlsxSink, content_type, export_file_name,
+        },
+        auth::AdminActor,
+    },
+    state::AppState,
+};
+
+/// Buffer between the CSV writer task and the response body
+const CSV_STREAM_BUFFER_BYTES: usize = 64 * 1024;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_exports))
+        .route("/{id}", get(get_export))
+        .route("/download/{token}", get(download_export))
+        .route("/transactions", get(export_transactions))
+        .route("/orders", get(export_orders))
+        .route("/payment-invoices", get(export_payment_invoices))
+        .route("/stock-movements", get(export_stock_movements))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports/transactions",
+    tag = "Exports",
+    params(
+        ("format" = Option<AccountingExportFormat>, Query, description = "csv (default) or xlsx"),
+        ("from" = String, Query, description = "Inclusive start of the created_at range, RFC 3339"),
+        ("to" = String, Query, description = "Exclusive end of the created_at range, RFC 3339"),
+    ),
+    responses(
+        (status = 200, description = "Export file, accepts the transactions list filters"),
+        (status = 202, description = "Export queued", body = AccountingExportResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn export_transactions(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<TransactionsRead>,
+    Query(params): Query<AccountingExportParams>,
+    RawQuery(query): RawQuery,
+) -> ApiResult<Response> {
+    start_export(
+        state,
+        actor,
+        AccountingExportEntity::Transactions,
+        params,
+        query,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports/orders",
+    tag = "Exports",
+    params(
+        ("format" = Option<AccountingExportFormat>, Query, description = "csv (default) or xlsx"),
+        ("from" = String, Query, description = "Inclusive start of the created_at range, RFC 3339"),
+        ("to" = String, Query, description = "Exclusive end of the created_at range, RFC 3339"),
+    ),
+    responses(
+        (status = 200, description = "Export file with one row per order item, accepts the orders list filters"),
+        (status = 202, description = "Export queued", body = AccountingExportResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn export_orders(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<OrdersRead>,
+    Query(params): Query<AccountingExportParams>,
+    RawQuery(query): RawQuery,
+) -> ApiResult<Response> {
+    start_export(state, actor, AccountingExportEntity::Orders, params, query).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports/payment-invoices",
+    tag = "Exports",
+    params(
+        ("format" = Option<AccountingExportFormat>, Query, description = "csv (default) or xlsx"),
+        ("from" = String, Query, description = "Inclusive start of the created_at range, RFC 3339"),
+        ("to" = String, Query, description = "Exclusive end of the created_at range, RFC 3339"),
+    ),
+    responses(
+        (status = 200, description = "Export file, accepts the payment invoices list filters"),
+        (status = 202, description = "Export queued", body = AccountingExportResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn export_payment_invoices(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<InvoicesRead>,
+    Query(params): Query<AccountingExportParams>,
+    RawQuery(query): RawQuery,
+) -> ApiResult<Response> {
+    start_export(
+        state,
+        actor,
+        AccountingExportEntity::PaymentInvoices,
+        params,
+        query,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports/stock-movements",
+    tag = "Exports",
+    params(
+        ("format" = Option<AccountingExportFormat>, Query, description = "csv (default) or xlsx"),
+        ("from" = String, Query, description = "Inclusive start of the created_at range, RFC 3339"),
+        ("to" = String, Query, description = "Exclusive end of the created_at range, RFC 3339"),
+    ),
+    responses(
+        (status = 200, description = "Export file, accepts the stock movements list filters"),
+        (status = 202, description = "Export queued", body = AccountingExportResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn export_stock_movements(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<StockRead>,
+    Query(params): Query<AccountingExportParams>,
+    RawQuery(query): RawQuery,
+) -> ApiResult<Response> {
+    start_export(
+        state,
+        actor,
+        AccountingExportEntity::StockMovements,
+        params,
+        query,
+    )
+    .await
+}
+
+async fn start_export(
+    state: Arc<AppState>,
+    actor: AdminActor,
+    entity: AccountingExportEntity,
+    params: AccountingExportParams,
+    query: Option<String>,
+) -> ApiResult<Response> {
+    let start = state
+        .accounting_export_service
+        .start(CreateAccountingExportCommand {
+            entity,
+            format: params.format,
+            query: query.unwrap_or_default(),
+            date_from: params.from,
+            date_to: params.to,
+            created_by: actor.id(),
+        })
+        .await?;
+
+    let query = match start {
+        AccountingExportStart::Queued(export) => {
+            return Ok((
+                StatusCode::ACCEPTED,
+                Json(AccountingExportResponse::from(export)),
+            )
+                .into_response());
+        }
+        AccountingExportStart::Stream(query) => query,
+    };
+
+    let body = match params.format {
+        AccountingExportFormat::Csv => {
+            let (writer, reader) = tokio::io::duplex(CSV_STREAM_BUFFER_BYTES);
+            tokio::spawn(async move {
+                let mut sink = CsvSink::new(writer);
+                let result = state
+                    .accounting_export_service
+                    .write(query, &mut sink as &mut dyn ExportSink)
+                    .await;
+                // On error the response is cut short, the client sees an incomplete download
+                match result {
+                    Ok(_) => {
+                        if let Err(e) = sink.finish().await {
+                            tracing::error!("Failed to finish {entity:?} export: {e}");
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to stream {entity:?} export: {e}"),
+                }
+            });
+            Body::from_stream(ReaderStream::new(reader))
+        }
+        AccountingExportFormat::Xlsx => {
+            let mut sink = XlsxSink::default();
+            state
+                .accounting_export_service
+                .write(query, &mut sink)
+                .await?;
+            Body::from(sink.into_bytes()?)
+        }
+    };
+
+    file_response(
+        body,
+        params.format,
+        &export_file_name(entity, params.format, params.from, params.to),
+    )
+}
+
+fn file_response(
+    body: Body,
+    format: AccountingExportFormat,
+    file_name: &str,
+) -> ApiResult<Response> {
+    let disposition = HeaderValue::from_str(&format!("attachment; filename=\"{file_name}\""))
+        .map_err(|_| {
+            ApiError::InternalServerError("Failed to set content disposition".to_string())
+        })?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(content_type(format)),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports",
+    tag = "Exports",
+    responses(
+        (status = 200, description = "Background exports of the current user", body = ListResponse<AccountingExportResponse>),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_exports(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    mut query: AccountingExportListQuery,
+) -> ApiResult<Json<ListResponse<AccountingExportResponse>>> {
+    query.filters.push(Filter {
+        field: AccountingExportFilterFields::CreatedBy,
+        op: Operator::Eq,
+        value: FilterValue::Scalar(ScalarValue::Int(actor.id())),
+    });
+    let exports = state.accounting_export_service.get_list(query).await?;
+
+    Ok(Json(ListResponse {
+        total: exports.total,
+        items: exports
+            .items
+            .into_iter()
+            .map(AccountingExportResponse::from)
+            .collect(),
+        next_cursor: exports.next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports/{id}",
+    tag = "Exports",
+    params(("id" = i64, Path, description = "Export ID")),
+    responses(
+        (status = 200, description = "Background export", body = AccountingExportResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_export(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    Path(id): Path<i64>,
+) -> ApiResult<Json<AccountingExportResponse>> {
+    let export = state.accounting_export_service.get_by_id(id).await?;
+    // Exports may contain data of tables the other admins can't read
+    if export.created_by != actor.id() {
+        return Err(ApiError::NotFound("Not found".to_string()));
+    }
+    Ok(Json(export.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports/download/{token}",
+    tag = "Exports",
+    params(("token" = Uuid, Path, description = "Download token of a completed export")),
+    responses(
+        (status = 200, description = "Export file"),
+        (status = 404, description = "Unknown or expired token", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn download_export(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<Uuid>,
+) -> ApiResult<Response> {
+    let (export, file) = state.accounting_export_service.get_download(token).await?;
+
+    file_response(
+        Body::from_stream(ReaderStream::new(file)),
+        export.format,
+        &export_file_name(
+            export.entity,
+            export.format,
+            export.date_from,
+            export.date_to,
+        ),
+    )
+}
@@ -4,6 +4,7 @@ use shared_dtos::{
         AdminUserAdminResponse, AdminUserWithRolesAdminResponse, NewAdminUserAdminRequest,
         NewAdminUserAdminResponse, UpdateAdminUserAdminRequest,
     },
+    auth::TwoFaResetAdminResponse,
     error::ApiErrorResponse,
     list_response::ListResponse,
     permission::PermissionAdminResponse,
@@ -30,9 +31,10 @@ use crate::{
     models::user_permission::{UpdateUserPermissions, UpsertUserPermission},
     services::{
         admin_user::{
-            AdminUserServiceTrait, CreateAdminUser, DeleteAdminUserCommand, UpdateAdminUserCommand,
+            AdminUserServiceTrait, CreateAdminUser, DeleteAdminUserCommand,
+            RequestTwoFaResetCommand, UpdateAdminUserCommand,
         },
-        auth::AuthUser,
+        auth::AdminActor,
         permission::PermissionServiceTrait,
     },
     state::AppState,
@@ -51,6 +53,7 @@ pub fn router() -> Router<Arc<AppState>> {
             "/{id}/permissions",
             get(get_admin_user_permissions).patch(update_admin_user_permissions),
         )
+        .route("/{id}/2fa/reset", post(reset_admin_user_two_fa))
 }
 
 #[utoipa::path(
@@ -68,7 +71,7 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn create_admin_user(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<AdminUsersCreate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<NewAdminUserAdminRequest>,
@@ -79,7 +82,7 @@ async fn create_admin_user(
             CreateAdminUser {
                 login: payload.login,
                 password: payload.password,
-                created_by: user.id,
+                created_by: actor.id(),
                 roles: payload.roles,
             },
             ctx,
@@ -113,7 +116,6 @@ async fn create_admin_user(
 )]
 async fn list_admin_users(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<AdminUsersRead>,
 ) -> ApiResult<Json<ListResponse<AdminUserWithRolesAdminResponse>>> {
     let admin_users = state.admin_user_service.get_all_users_with_roles().await?;
@@ -124,6 +126,7 @@ async fn list_admin_users(
             .into_iter()
             .map(AdminUserWithRolesAdminResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
 
@@ -142,7 +145,6 @@ async fn list_admin_users(
 async fn get_admin_user(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<AdminUsersRead>,
 ) -> ApiResult<Json<AdminUserAdminResponse>> {
     let admin_user = state.admin_user_service.get_by_id(id).await?;
@@ -166,7 +168,7 @@ async fn get_admin_user(
 async fn update_admin_user(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<AdminUsersUpdate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<UpdateAdminUserAdminRequest>,
@@ -180,7 +182,7 @@ async fn update_admin_user(
                 password: payload.password,
                 telegram_id: payload.telegram_id,
                 roles: payload.roles,
-                updated_by: user.id,
+                updated_by: actor.id(),
             },
             ctx,
         )
@@ -204,7 +206,7 @@ async fn update_admin_user(
 async fn delete_admin_user(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<AdminUsersDelete>,
     ctx: RequestContext,
 ) -> ApiResult<StatusCode> {
@@ -213,7 +215,7 @@ async fn delete_admin_user(
         .delete(
             DeleteAdminUserCommand {
                 id,
-                deleted_by: user.id,
+                deleted_by: actor.id(),
             },
             ctx,
         )
@@ -237,7 +239,6 @@ async fn delete_admin_user(
 async fn get_admin_user_permissions(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<RbacManage>,
 ) -> ApiResult<Json<ListResponse<UserPermissionAdminResponse>>> {
     let permissions = state.permission_service.get_for_admin_user(id).await?;
@@ -251,6 +252,7 @@ async fn get_admin_user_permissions(
                 id: p.permission_id,
             })
             .collect(),
+        next_cursor: None,
     }))
 }
 
@@ -270,7 +272,7 @@ async fn get_admin_user_permissions(
 async fn update_admin_user_permissions(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<RbacManage>,
     ValidatedJson(payload): ValidatedJson<UpdateUserPermissionsRequest>,
 ) -> ApiResult<Json<ListResponse<UserPermissionAdminResponse>>> {
@@ -278,7 +280,7 @@ async fn update_admin_user_permissions(
         .permission_service
         .update_admin_user_permissions(UpdateUserPermissions {
             user_id: id,
-            created_by: user.id,
+            created_by: actor.id(),
             removed: payload.removed,
             upserted: payload
                 .upserted
@@ -302,5 +304,42 @@ async fn update_admin_user_permissions(
                 id: p.permission_id,
             })
             .collect(),
+        next_cursor: None,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/admin-users/{id}/2fa/reset",
+    tag = "Admin Users",
+    responses(
+        (status = 200, description = "2FA reset, one-time enrollment token issued", body = TwoFaResetAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn reset_admin_user_two_fa(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<AdminUsersUpdate>,
+    ctx: RequestContext,
+) -> ApiResult<Json<TwoFaResetAdminResponse>> {
+    let token = state
+        .admin_user_service
+        .request_two_fa_reset(
+            RequestTwoFaResetCommand {
+                id,
+                requested_by: actor.id(),
+            },
+            ctx,
+        )
+        .await?;
+
+    Ok(Json(TwoFaResetAdminResponse {
+        enrollment_token: token.token,
+        expires_at: token.expires_at,
     }))
 }
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, post},
+};
+use shared_dtos::{
+    api_key::{ApiKeyAdminResponse, CreatedApiKeyAdminResponse, NewApiKeyAdminRequest},
+    error::ApiErrorResponse,
+    list_response::ListResponse,
+};
+use sqlx::types::ipnetwork::IpNetwork;
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    middlewares::{
+        context::RequestContext,
+        require_permission::{ApiKeysManage, RequirePermission},
+        validator::ValidatedJson,
+    },
+    services::{
+        admin_api_key::{AdminApiKeyServiceTrait, CreateApiKeyCommand, RevokeApiKeyCommand},
+        auth::AdminActor,
+    },
+    state::AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(create_api_key).get(list_api_keys))
+        .route("/{id}", delete(revoke_api_key))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/api-keys",
+    tag = "API keys",
+    request_body = NewApiKeyAdminRequest,
+    responses(
+        (status = 200, description = "API key created, the key is shown only once", body = CreatedApiKeyAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<ApiKeysManage>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<NewApiKeyAdminRequest>,
+) -> ApiResult<Json<CreatedApiKeyAdminResponse>> {
+    let allowed_ips = payload
+        .allowed_ips
+        .iter()
+        .map(|ip| {
+            ip.parse::<IpNetwork>()
+                .map_err(|_| ApiError::BadRequest(format!("Invalid IP or network: {ip}")))
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    let created = state
+        .admin_api_key_service
+        .create(CreateApiKeyCommand {
+            name: payload.name,
+            permissions: payload.permissions,
+            allowed_ips,
+            expires_at: payload.expires_at,
+            created_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(created.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/api-keys",
+    tag = "API keys",
+    responses(
+        (status = 200, description = "API key list", body = ListResponse<ApiKeyAdminResponse>),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<ApiKeysManage>,
+) -> ApiResult<Json<ListResponse<ApiKeyAdminResponse>>> {
+    let keys = state.admin_api_key_service.get_list().await?;
+
+    Ok(Json(ListResponse {
+        total: keys.len() as i64,
+        items: keys.into_iter().map(ApiKeyAdminResponse::from).collect(),
+        next_cursor: None,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/api-keys/{id}",
+    tag = "API keys",
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<ApiKeysManage>,
+    ctx: RequestContext,
+) -> ApiResult<StatusCode> {
+    state
+        .admin_api_key_service
+        .revoke(RevokeApiKeyCommand {
+            id,
+            revoked_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
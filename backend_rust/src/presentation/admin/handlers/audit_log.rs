@@ -9,7 +9,7 @@ use crate::{
     errors::api::ApiResult,
     middlewares::require_permission::{AuditLogRead, RequirePermission},
     models::audit_log::AuditLogListQuery,
-    services::{audit_log::AuditLogServiceTrait, auth::AuthUser},
+    services::audit_log::AuditLogServiceTrait,
     state::AppState,
 };
 
@@ -31,7 +31,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn list_audit_logs(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<AuditLogRead>,
     query: AuditLogListQuery,
 ) -> ApiResult<Json<ListResponse<AuditLogAdminResponse>>> {
@@ -44,5 +43,6 @@ async fn list_audit_logs(
             .into_iter()
             .map(AuditLogAdminResponse::from)
             .collect(),
+        next_cursor: audit_logs.next_cursor,
     }))
 }
@@ -4,8 +4,9 @@ use axum::{Json, Router, debug_handler, extract::State, routing::post};
 use shared_dtos::{
     audit_log::{AuditAction, AuditStatus},
     auth::{
-        LoginStep1AdminRequest, LoginStep1AdminResponse, LoginStep2AdminRequest,
-        LoginStep2AdminResponse,
+        CompleteTwoFaEnrollmentAdminRequest, LoginRecoveryAdminRequest, LoginStep1AdminRequest,
+        LoginStep1AdminResponse, LoginStep2AdminRequest, LoginStep2AdminResponse,
+        TwoFaEnrollmentAdminResponse,
     },
     error::ApiErrorResponse,
 };
@@ -15,6 +16,7 @@ use crate::{
     middlewares::{context::RequestContext, validator::ValidatedJson},
     models::audit_log::NewAuditLog,
     services::{
+        admin_user::{AdminUserServiceTrait, CompleteTwoFaEnrollmentCommand},
         audit_log::AuditLogServiceTrait,
         auth::{AuthServiceTrait, AuthUser},
     },
@@ -25,6 +27,8 @@ pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/login", post(login_step1))
         .route("/login/2fa", post(login_step2))
+        .route("/login/recovery", post(login_recovery))
+        .route("/2fa/enroll", post(complete_two_fa_enrollment))
         .route("/logout", post(logout))
 }
 
@@ -129,6 +133,67 @@ async fn login_step2(
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/admin/auth/login/recovery",
+    tag = "Auth",
+    security(()),
+    request_body = LoginRecoveryAdminRequest,
+    responses(
+        (status = 200, description = "Login successful", body = LoginStep2AdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn login_recovery(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<LoginRecoveryAdminRequest>,
+) -> ApiResult<Json<LoginStep2AdminResponse>> {
+    let access_token = state
+        .auth_service
+        .login_step2_recovery(&payload.temp_token, &payload.recovery_code)
+        .await?;
+
+    Ok(Json(LoginStep2AdminResponse {
+        token: access_token.jti,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/auth/2fa/enroll",
+    tag = "Auth",
+    security(()),
+    request_body = CompleteTwoFaEnrollmentAdminRequest,
+    responses(
+        (status = 200, description = "2FA enrolled", body = TwoFaEnrollmentAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn complete_two_fa_enrollment(
+    State(state): State<Arc<AppState>>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<CompleteTwoFaEnrollmentAdminRequest>,
+) -> ApiResult<Json<TwoFaEnrollmentAdminResponse>> {
+    let enrollment = state
+        .admin_user_service
+        .complete_two_fa_enrollment(
+            CompleteTwoFaEnrollmentCommand {
+                token: payload.enrollment_token,
+                password: payload.password,
+            },
+            ctx,
+        )
+        .await?;
+
+    Ok(Json(TwoFaEnrollmentAdminResponse::from(enrollment)))
+}
+
 #[utoipa::path(
     post,
     path = "/api/admin/auth/logout",
@@ -22,7 +22,7 @@ use crate::{
     },
     models::bot::BotListQuery,
     services::{
-        auth::AuthUser,
+        auth::AdminActor,
         bot::{BotServiceTrait, CreateBotCommand, DeleteBotCommand, UpdateBotCommand},
     },
     state::AppState,
@@ -49,7 +49,7 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn create_bot(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<BotsCreate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<NewBotAdminRequest>,
@@ -57,7 +57,7 @@ async fn create_bot(
     let bot = state
         .bot_service
         .create(CreateBotCommand {
-            created_by: Some(user.id),
+            created_by: Some(actor.id()),
             is_active: true,
             is_primary: false,
             owner_id: None,
@@ -84,7 +84,6 @@ async fn create_bot(
 )]
 async fn list_bots(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<BotsRead>,
     query: BotListQuery,
 ) -> ApiResult<Json<ListResponse<BotAdminResponse>>> {
@@ -93,6 +92,7 @@ async fn list_bots(
     Ok(Json(ListResponse {
         total: bots.total,
         items: bots.items.into_iter().map(BotAdminResponse::from).collect(),
+        next_cursor: bots.next_cursor,
     }))
 }
 
@@ -112,7 +112,7 @@ async fn list_bots(
 async fn update_bot(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<BotsUpdate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<UpdateBotAdminRequest>,
@@ -130,7 +130,7 @@ async fn update_bot(
                         .ok_or_else(|| ApiError::BadRequest("Invalid referral percentage".into()))
                 })
                 .transpose()?,
-            updated_by: Some(user.id),
+            updated_by: Some(actor.id()),
             username: None,
             ctx: Some(ctx),
         })
@@ -154,7 +154,7 @@ async fn update_bot(
 async fn delete_bot(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<BotsDelete>,
     ctx: RequestContext,
 ) -> ApiResult<StatusCode> {
@@ -162,7 +162,7 @@ async fn delete_bot(
         .bot_service
         .delete(DeleteBotCommand {
             id,
-            deleted_by: Some(user.id),
+            deleted_by: Some(actor.id()),
             ctx: Some(ctx),
         })
         .await?;
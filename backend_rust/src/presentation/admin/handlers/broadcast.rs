@@ -1,27 +1,69 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
-use axum::{Json, Router, extract::State, routing::post};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{get, post},
+};
+use serde::Deserialize;
 use shared_dtos::{
-    broadcast::{BroadcastResponse, NewBroadcastRequest},
+    broadcast::{
+        BroadcastAudiencePreviewRequest, BroadcastAudiencePreviewResponse,
+        BroadcastRecipientResponse, BroadcastResponse, BroadcastVariantResponse,
+        BroadcastVariantStatsResponse, JsonRawListQuery, NewBroadcastRequest,
+        NewBroadcastVariantRequest, TestBroadcastRequest,
+    },
+    customer::CustomerAdminResponse,
     error::ApiErrorResponse,
+    list_response::ListResponse,
+    notification::{DispatchMessage, DispatchMessagePayload},
 };
 
 use crate::{
-    errors::api::ApiResult,
+    errors::api::{ApiError, ApiResult},
     middlewares::{
         context::RequestContext,
-        require_permission::{BroadcastCreate, RequirePermission},
+        require_permission::{BroadcastCreate, BroadcastRead, RequirePermission},
         validator::ValidatedJson,
     },
+    models::{
+        broadcast::{
+            BroadcastListQuery, BroadcastRecipientListQuery, NewBroadcastVariant,
+            build_broadcast_content,
+        },
+        customer::CustomerListQuery,
+    },
+    presentation::admin::dtos::broadcast::{
+        BASE_VARIANT_NAME, validate_broadcast_content, variant_stats_response,
+    },
     services::{
-        auth::AuthUser,
-        broadcast::{BroadcastServiceTrait, CreateBroadcastCommand},
+        auth::AdminActor,
+        broadcast::{BroadcastActionCommand, BroadcastServiceTrait, CreateBroadcastCommand},
+        customer::CustomerServiceTrait,
+        customer_segment::CustomerSegmentServiceTrait,
+        notification_service::NotificationServiceTrait,
     },
     state::AppState,
 };
+use validator::Validate;
+
+const MAX_BROADCAST_VARIANTS: usize = 3;
+const DEFAULT_AUDIENCE_SAMPLE_SIZE: i64 = 10;
+const DEFAULT_CONVERSION_DAYS: i32 = 7;
+const MAX_CONVERSION_DAYS: i32 = 90;
 
 pub fn router() -> Router<Arc<AppState>> {
-    Router::new().route("/", post(create_broadcast))
+    Router::new()
+        .route("/", post(create_broadcast).get(list_broadcasts))
+        .route("/test", post(send_test_broadcast))
+        .route("/audience-preview", post(preview_broadcast_audience))
+        .route("/{id}", get(get_broadcast))
+        .route("/{id}/recipients", get(list_broadcast_recipients))
+        .route("/{id}/variants", get(list_broadcast_variants))
+        .route("/{id}/variant-stats", get(get_broadcast_variant_stats))
+        .route("/{id}/pause", post(pause_broadcast))
+        .route("/{id}/resume", post(resume_broadcast))
+        .route("/{id}/cancel", post(cancel_broadcast))
 }
 
 #[utoipa::path(
@@ -38,22 +80,457 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn create_broadcast(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<BroadcastCreate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<NewBroadcastRequest>,
 ) -> ApiResult<Json<BroadcastResponse>> {
+    let filters = audience_filters(&state, payload.filters, payload.segment_id).await?;
+    if let Some(filters) = &filters {
+        CustomerListQuery::try_from_json(filters.clone()).map_err(ApiError::BadRequest)?;
+    }
+    validate_broadcast_content(
+        payload.content_text.as_deref(),
+        payload.content_image_id,
+        &payload.content_entities,
+        &payload.content_media,
+        &payload.content_buttons,
+    )
+    .map_err(ApiError::BadRequest)?;
+    let variants = new_broadcast_variants(payload.variants)?;
+
     let broadcast = state
         .broadcast_service
         .create(CreateBroadcastCommand {
             content_image_id: payload.content_image_id,
+            content_entities: payload.content_entities,
+            content_media: payload.content_media,
+            content_buttons: payload.content_buttons,
             content_text: payload.content_text,
-            created_by: user.id,
+            created_by: actor.id(),
             ctx: Some(ctx),
-            filters: payload.filters,
+            filters,
+            segment_id: payload.segment_id,
+            variants,
             scheduled_for: payload.scheduled_for,
         })
         .await?;
 
     Ok(Json(BroadcastResponse::from(broadcast)))
 }
+
+// Segment filters are combined with the explicit ones, a customer has to match both
+async fn audience_filters(
+    state: &AppState,
+    filters: Option<JsonRawListQuery>,
+    segment_id: Option<i64>,
+) -> ApiResult<Option<JsonRawListQuery>> {
+    let Some(segment_id) = segment_id else {
+        return Ok(filters);
+    };
+
+    let segment_filters = state
+        .customer_segment_service
+        .get_filters(segment_id)
+        .await
+        .map_err(|e| match e {
+            ApiError::NotFound(_) => ApiError::BadRequest("Segment not found".to_string()),
+            e => e,
+        })?;
+
+    Ok(Some(match filters {
+        Some(filters) => segment_filters.and(filters),
+        None => segment_filters,
+    }))
+}
+
+fn new_broadcast_variants(
+    variants: Vec<NewBroadcastVariantRequest>,
+) -> ApiResult<Vec<NewBroadcastVariant>> {
+    if variants.len() > MAX_BROADCAST_VARIANTS {
+        return Err(ApiError::BadRequest(format!(
+            "Broadcast can have at most {MAX_BROADCAST_VARIANTS} extra variants"
+        )));
+    }
+
+    let mut names = HashSet::from([BASE_VARIANT_NAME.to_string()]);
+    variants
+        .into_iter()
+        .map(|variant| {
+            variant.validate()?;
+            if !names.insert(variant.name.clone()) {
+                return Err(ApiError::BadRequest(format!(
+                    "Variant names must be unique, \"{BASE_VARIANT_NAME}\" is the broadcast itself"
+                )));
+            }
+            validate_broadcast_content(
+                variant.content_text.as_deref(),
+                variant.content_image_id,
+                &variant.content_entities,
+                &variant.content_media,
+                &variant.content_buttons,
+            )
+            .map_err(ApiError::BadRequest)?;
+
+            Ok(NewBroadcastVariant {
+                name: variant.name,
+                content_text: variant.content_text,
+                content_image_id: variant.content_image_id,
+                content_entities: variant.content_entities,
+                content_media: variant.content_media,
+                content_buttons: variant.content_buttons,
+            })
+        })
+        .collect()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/broadcasts/audience-preview",
+    tag = "Broadcast",
+    request_body = BroadcastAudiencePreviewRequest,
+    responses(
+        (status = 200, description = "Audience size and a sample of customers", body = BroadcastAudiencePreviewResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn preview_broadcast_audience(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<BroadcastRead>,
+    ValidatedJson(payload): ValidatedJson<BroadcastAudiencePreviewRequest>,
+) -> ApiResult<Json<BroadcastAudiencePreviewResponse>> {
+    let filters = audience_filters(&state, payload.filters, payload.segment_id).await?;
+    let mut query = match filters {
+        Some(filters) => CustomerListQuery::try_from_json(filters).map_err(ApiError::BadRequest)?,
+        None => CustomerListQuery::default(),
+    };
+    query.pagination.page_size = payload
+        .sample_size
+        .unwrap_or(DEFAULT_AUDIENCE_SAMPLE_SIZE)
+        .clamp(1, 50) as u32;
+
+    let customers = state.customer_service.get_list(query).await?;
+
+    Ok(Json(BroadcastAudiencePreviewResponse {
+        total: customers.total,
+        sample: customers
+            .items
+            .into_iter()
+            .map(CustomerAdminResponse::from)
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/broadcasts",
+    tag = "Broadcast",
+    responses(
+        (status = 200, description = "List of broadcasts", body = ListResponse<BroadcastResponse>),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_broadcasts(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<BroadcastRead>,
+    query: BroadcastListQuery,
+) -> ApiResult<Json<ListResponse<BroadcastResponse>>> {
+    let broadcasts = state.broadcast_service.get_list(query).await?;
+
+    Ok(Json(ListResponse {
+        total: broadcasts.total,
+        items: broadcasts
+            .items
+            .into_iter()
+            .map(BroadcastResponse::from)
+            .collect(),
+        next_cursor: broadcasts.next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/broadcasts/{id}",
+    tag = "Broadcast",
+    responses(
+        (status = 200, description = "Broadcast", body = BroadcastResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_broadcast(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    _perm: RequirePermission<BroadcastRead>,
+) -> ApiResult<Json<BroadcastResponse>> {
+    let broadcast = state.broadcast_service.get_by_id(id).await?;
+
+    Ok(Json(BroadcastResponse::from(broadcast)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/broadcasts/{id}/recipients",
+    tag = "Broadcast",
+    responses(
+        (status = 200, description = "Broadcast recipients with delivery status", body = ListResponse<BroadcastRecipientResponse>),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_broadcast_recipients(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    _perm: RequirePermission<BroadcastRead>,
+    query: BroadcastRecipientListQuery,
+) -> ApiResult<Json<ListResponse<BroadcastRecipientResponse>>> {
+    let recipients = state.broadcast_service.get_recipients(id, query).await?;
+
+    Ok(Json(ListResponse {
+        total: recipients.total,
+        items: recipients
+            .items
+            .into_iter()
+            .map(BroadcastRecipientResponse::from)
+            .collect(),
+        next_cursor: recipients.next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/broadcasts/{id}/variants",
+    tag = "Broadcast",
+    responses(
+        (status = 200, description = "Alternative contents of the broadcast", body = Vec<BroadcastVariantResponse>),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_broadcast_variants(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    _perm: RequirePermission<BroadcastRead>,
+) -> ApiResult<Json<Vec<BroadcastVariantResponse>>> {
+    state.broadcast_service.get_by_id(id).await?;
+    let variants = state.broadcast_service.get_variants(id).await?;
+
+    Ok(Json(
+        variants
+            .into_iter()
+            .map(BroadcastVariantResponse::from)
+            .collect(),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct VariantStatsQuery {
+    conversion_days: Option<i32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/broadcasts/{id}/variant-stats",
+    tag = "Broadcast",
+    params(
+        ("conversion_days" = Option<i32>, Query, description = "Days after delivery an order counts as a conversion, defaults to 7")
+    ),
+    responses(
+        (status = 200, description = "Delivery and conversion stats per variant", body = Vec<BroadcastVariantStatsResponse>),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_broadcast_variant_stats(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    _perm: RequirePermission<BroadcastRead>,
+    Query(query): Query<VariantStatsQuery>,
+) -> ApiResult<Json<Vec<BroadcastVariantStatsResponse>>> {
+    let conversion_days = query.conversion_days.unwrap_or(DEFAULT_CONVERSION_DAYS);
+    if !(1..=MAX_CONVERSION_DAYS).contains(&conversion_days) {
+        return Err(ApiError::BadRequest(format!(
+            "conversion_days must be between 1 and {MAX_CONVERSION_DAYS}"
+        )));
+    }
+
+    let stats = state
+        .broadcast_service
+        .get_variant_stats(id, conversion_days)
+        .await?;
+    let variants = state.broadcast_service.get_variants(id).await?;
+
+    Ok(Json(variant_stats_response(stats, &variants)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/broadcasts/{id}/pause",
+    tag = "Broadcast",
+    responses(
+        (status = 200, description = "Broadcast paused", body = BroadcastResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 409, description = "Broadcast cannot be paused", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn pause_broadcast(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<BroadcastCreate>,
+    ctx: RequestContext,
+) -> ApiResult<Json<BroadcastResponse>> {
+    let broadcast = state
+        .broadcast_service
+        .pause(BroadcastActionCommand {
+            id,
+            updated_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(BroadcastResponse::from(broadcast)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/broadcasts/{id}/resume",
+    tag = "Broadcast",
+    responses(
+        (status = 200, description = "Broadcast resumed", body = BroadcastResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 409, description = "Broadcast is not paused", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn resume_broadcast(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<BroadcastCreate>,
+    ctx: RequestContext,
+) -> ApiResult<Json<BroadcastResponse>> {
+    let broadcast = state
+        .broadcast_service
+        .resume(BroadcastActionCommand {
+            id,
+            updated_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(BroadcastResponse::from(broadcast)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/broadcasts/{id}/cancel",
+    tag = "Broadcast",
+    responses(
+        (status = 200, description = "Broadcast cancelled", body = BroadcastResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 409, description = "Broadcast is already finished", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn cancel_broadcast(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<BroadcastCreate>,
+    ctx: RequestContext,
+) -> ApiResult<Json<BroadcastResponse>> {
+    let broadcast = state
+        .broadcast_service
+        .cancel(BroadcastActionCommand {
+            id,
+            updated_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(BroadcastResponse::from(broadcast)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/broadcasts/test",
+    tag = "Broadcast",
+    request_body = TestBroadcastRequest,
+    responses(
+        (status = 200, description = "Test message dispatched"),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn send_test_broadcast(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<BroadcastCreate>,
+    ValidatedJson(payload): ValidatedJson<TestBroadcastRequest>,
+) -> ApiResult<()> {
+    validate_broadcast_content(
+        payload.content_text.as_deref(),
+        payload.content_image_id,
+        &payload.content_entities,
+        &payload.content_media,
+        &payload.content_buttons,
+    )
+    .map_err(ApiError::BadRequest)?;
+
+    let bot_id = match payload.bot_id {
+        Some(bot_id) => bot_id,
+        None => {
+            state
+                .customer_service
+                .get_by_telegram_id(payload.telegram_id)
+                .await
+                .map_err(|e| match e {
+                    ApiError::NotFound(_) => ApiError::BadRequest(
+                        "Recipient has never used the bot, specify bot_id explicitly".to_string(),
+                    ),
+                    e => e,
+                })?
+                .last_seen_with_bot
+        }
+    };
+
+    state
+        .notification_service
+        .dispatch_message(DispatchMessagePayload {
+            bot_id,
+            telegram_id: payload.telegram_id,
+            message: DispatchMessage::BroadcastMessage(build_broadcast_content(
+                payload.content_text,
+                payload.content_image_id,
+                payload.content_entities,
+                payload.content_media,
+                payload.content_buttons,
+            )),
+        })
+        .await
+}
@@ -22,7 +22,7 @@ use crate::{
         validator::ValidatedJson,
     },
     services::{
-        auth::AuthUser,
+        auth::AdminActor,
         category::{
             CategoryServiceTrait, CreateCategoryCommand, DeleteCategoryCommand,
             UpdateCategoryCommand,
@@ -57,7 +57,7 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn create_category(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<CategoriesCreate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<NewCategoryAdminRequest>,
@@ -65,7 +65,7 @@ async fn create_category(
     let category = state
         .category_service
         .create(CreateCategoryCommand {
-            created_by: user.id,
+            created_by: actor.id(),
             image_id: payload.image_id,
             name: payload.name,
             parent_id: payload.parent_id,
@@ -90,7 +90,6 @@ async fn create_category(
 )]
 async fn list_categories(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<CategoriesRead>,
 ) -> ApiResult<Json<ListResponse<CategoryAdminResponse>>> {
     let categories = state.category_service.get_list().await?;
@@ -101,6 +100,7 @@ async fn list_categories(
             .into_iter()
             .map(CategoryAdminResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
 
@@ -119,7 +119,6 @@ async fn list_categories(
 async fn get_category(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<CategoriesRead>,
 ) -> ApiResult<Json<CategoryAdminResponse>> {
     let category = state.category_service.get_by_id(id).await?;
@@ -143,7 +142,7 @@ async fn get_category(
 async fn update_category(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<CategoriesUpdate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<UpdateCategoryAdminRequest>,
@@ -157,7 +156,7 @@ async fn update_category(
                 name: payload.name,
                 parent_id: payload.parent_id,
                 position: payload.position,
-                updated_by: user.id,
+                updated_by: actor.id(),
             },
             ctx,
         )
@@ -181,7 +180,7 @@ async fn update_category(
 async fn delete_category(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     ctx: RequestContext,
     _perm: RequirePermission<CategoriesDelete>,
 ) -> ApiResult<StatusCode> {
@@ -190,7 +189,7 @@ async fn delete_category(
         .delete(
             DeleteCategoryCommand {
                 id,
-                deleted_by: user.id,
+                deleted_by: actor.id(),
             },
             ctx,
         )
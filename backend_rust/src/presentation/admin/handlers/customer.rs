@@ -20,7 +20,7 @@ use crate::{
     },
     models::customer::CustomerListQuery,
     services::{
-        auth::AuthUser,
+        auth::AdminActor,
         customer::{CustomerServiceTrait, UpdateCustomerCommand},
     },
     state::AppState,
@@ -46,7 +46,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn list_customers(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<CustomersRead>,
     query: CustomerListQuery,
 ) -> ApiResult<Json<ListResponse<CustomerAdminResponse>>> {
@@ -59,6 +58,7 @@ async fn list_customers(
             .into_iter()
             .map(CustomerAdminResponse::from)
             .collect(),
+        next_cursor: customers.next_cursor,
     }))
 }
 
@@ -78,7 +78,7 @@ async fn list_customers(
 async fn update_customer(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<CustomersUpdate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<UpdateCustomerAdminRequest>,
@@ -87,7 +87,7 @@ async fn update_customer(
         .customer_service
         .update(UpdateCustomerCommand {
             id,
-            updated_by: Some(user.id),
+            updated_by: Some(actor.id()),
             is_blocked: payload.is_blocked,
             bot_is_blocked_by_user: None,
             has_passed_captcha: None,
@@ -95,6 +95,7 @@ async fn update_customer(
             last_seen_with_bot: None,
             ctx: Some(ctx),
             blocked_until: None,
+            display_currency: None,
         })
         .await?;
 
@@ -117,7 +118,6 @@ async fn update_customer(
 async fn get_customer(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<CustomersRead>,
 ) -> ApiResult<Json<CustomerAdminResponse>> {
     let customer = state.customer_service.get_by_id(id).await?;
@@ -0,0 +1,196 @@
+use axum::http::StatusCode;
+use shared_dtos::{
+    customer_segment::{
+        CustomerSegmentResponse, NewCustomerSegmentRequest, UpdateCustomerSegmentRequest,
+    },
+    error::ApiErrorResponse,
+    list_response::ListResponse,
+};
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, post},
+};
+
+use crate::{
+    errors::api::ApiResult,
+    middlewares::{
+        context::RequestContext,
+        require_permission::{BroadcastCreate, BroadcastRead, RequirePermission},
+        validator::ValidatedJson,
+    },
+    services::{
+        auth::AdminActor,
+        customer_segment::{
+            CreateCustomerSegmentCommand, CustomerSegmentServiceTrait,
+            DeleteCustomerSegmentCommand, UpdateCustomerSegmentCommand,
+        },
+    },
+    state::AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(create_segment).get(list_segments))
+        .route(
+            "/{id}",
+            get(get_segment)
+                .patch(update_segment)
+                .delete(delete_segment),
+        )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/customer-segments",
+    tag = "Customer segments",
+    request_body = NewCustomerSegmentRequest,
+    responses(
+        (status = 200, description = "Segment created", body = CustomerSegmentResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 409, description = "Segment name is taken", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn create_segment(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<BroadcastCreate>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<NewCustomerSegmentRequest>,
+) -> ApiResult<Json<CustomerSegmentResponse>> {
+    let segment = state
+        .customer_segment_service
+        .create(CreateCustomerSegmentCommand {
+            name: payload.name,
+            description: payload.description,
+            filters: payload.filters,
+            created_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(segment.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/customer-segments",
+    tag = "Customer segments",
+    responses(
+        (status = 200, description = "List of segments", body = ListResponse<CustomerSegmentResponse>),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_segments(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<BroadcastRead>,
+) -> ApiResult<Json<ListResponse<CustomerSegmentResponse>>> {
+    let segments = state.customer_segment_service.get_list().await?;
+
+    Ok(Json(ListResponse {
+        total: segments.len() as i64,
+        items: segments
+            .into_iter()
+            .map(CustomerSegmentResponse::from)
+            .collect(),
+        next_cursor: None,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/customer-segments/{id}",
+    tag = "Customer segments",
+    responses(
+        (status = 200, description = "Segment details", body = CustomerSegmentResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_segment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    _perm: RequirePermission<BroadcastRead>,
+) -> ApiResult<Json<CustomerSegmentResponse>> {
+    let segment = state.customer_segment_service.get_by_id(id).await?;
+
+    Ok(Json(segment.into()))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/admin/customer-segments/{id}",
+    tag = "Customer segments",
+    request_body = UpdateCustomerSegmentRequest,
+    responses(
+        (status = 200, description = "Segment updated", body = CustomerSegmentResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 409, description = "Segment name is taken", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn update_segment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<BroadcastCreate>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<UpdateCustomerSegmentRequest>,
+) -> ApiResult<Json<CustomerSegmentResponse>> {
+    let segment = state
+        .customer_segment_service
+        .update(UpdateCustomerSegmentCommand {
+            id,
+            name: payload.name,
+            description: payload.description,
+            filters: payload.filters,
+            updated_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(segment.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/customer-segments/{id}",
+    tag = "Customer segments",
+    responses(
+        (status = 204, description = "Segment deleted"),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn delete_segment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<BroadcastCreate>,
+    ctx: RequestContext,
+) -> ApiResult<StatusCode> {
+    state
+        .customer_segment_service
+        .delete(DeleteCustomerSegmentCommand {
+            id,
+            deleted_by: actor.id(),
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
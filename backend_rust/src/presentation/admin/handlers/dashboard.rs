@@ -18,7 +18,7 @@ use shared_dtos::{
 use crate::{
     errors::api::{ApiError, ApiResult},
     middlewares::require_permission::{DashboardRead, RequirePermission},
-    services::{auth::AuthUser, dashboard::DashboardServiceTrait},
+    services::dashboard::DashboardServiceTrait,
     state::AppState,
 };
 
@@ -50,7 +50,6 @@ struct TimeSeriesQuery {
 )]
 async fn get_dashboard_stats(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<DashboardRead>,
 ) -> ApiResult<Json<DashboardOverviewResponse>> {
     let stats = state.dashboard_service.get_dashboard_stats().await?;
@@ -75,7 +74,6 @@ async fn get_dashboard_stats(
 )]
 async fn get_time_series(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<DashboardRead>,
     Query(query): Query<TimeSeriesQuery>,
 ) -> ApiResult<Json<TimeSeriesDashboardDataResponse>> {
@@ -107,7 +105,6 @@ async fn get_time_series(
 )]
 async fn get_top_products(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<DashboardRead>,
 ) -> ApiResult<Json<Vec<TopProductResponse>>> {
     let products = state.dashboard_service.get_top_products(5).await?;
@@ -128,7 +125,6 @@ async fn get_top_products(
 )]
 async fn get_sales_by_category(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<DashboardRead>,
 ) -> ApiResult<Json<Vec<CategorySalesResponse>>> {
     let categories = state.dashboard_service.get_sales_by_category().await?;
@@ -0,0 +1,64 @@
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use shared_dtos::{error::ApiErrorResponse, invoice::GatewayHealthAdminResponse};
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    middlewares::require_permission::{InvoicesRead, RequirePermission},
+    services::gateway_health::GatewayHealthServiceTrait,
+    state::AppState,
+};
+
+const DEFAULT_WINDOW_MINUTES: i64 = 60;
+const MAX_WINDOW_MINUTES: i64 = 60 * 24 * 7;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/health", get(get_gateways_health))
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewayHealthQuery {
+    window_minutes: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/gateways/health",
+    tag = "Payment Invoices",
+    params(
+        ("window_minutes" = Option<i64>, Query, description = "Stats window, defaults to 60 minutes; the circuit state always uses the breaker window")
+    ),
+    responses(
+        (status = 200, description = "Success rates, latencies and circuit state per gateway", body = Vec<GatewayHealthAdminResponse>),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_gateways_health(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<InvoicesRead>,
+    Query(query): Query<GatewayHealthQuery>,
+) -> ApiResult<Json<Vec<GatewayHealthAdminResponse>>> {
+    let window_minutes = query.window_minutes.unwrap_or(DEFAULT_WINDOW_MINUTES);
+    if !(1..=MAX_WINDOW_MINUTES).contains(&window_minutes) {
+        return Err(ApiError::BadRequest(format!(
+            "window_minutes must be between 1 and {MAX_WINDOW_MINUTES}"
+        )));
+    }
+
+    let health = state
+        .gateway_health_service
+        .get_health(Utc::now() - Duration::minutes(window_minutes))
+        .await?;
+
+    Ok(Json(health))
+}
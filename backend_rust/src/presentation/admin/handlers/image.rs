@@ -18,7 +18,7 @@ use crate::{
     middlewares::require_permission::{ImagesCreate, ImagesDelete, ImagesRead, RequirePermission},
     models::image::ImageListQuery,
     services::{
-        auth::AuthUser,
+        auth::AdminActor,
         image::{CreateImage, ImageServiceTrait},
     },
     state::AppState,
@@ -53,14 +53,14 @@ struct CreateImageMultipartRequest {
 )]
 async fn create_image(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<ImagesCreate>,
     mut multipart: Multipart,
 ) -> ApiResult<Json<ImageAdminResponse>> {
     let image = state
         .image_service
         .create(
-            parse_image_form(&mut multipart, user.id)
+            parse_image_form(&mut multipart, actor.id())
                 .await
                 .map_err(|e| ApiError::BadRequest(e.to_string()))?,
         )
@@ -83,7 +83,6 @@ async fn create_image(
 )]
 async fn list_images(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<ImagesRead>,
     query: ImageListQuery,
 ) -> ApiResult<Json<ListResponse<ImageAdminResponse>>> {
@@ -96,6 +95,7 @@ async fn list_images(
             .into_iter()
             .map(ImageAdminResponse::from)
             .collect(),
+        next_cursor: categories.next_cursor,
     }))
 }
 
@@ -114,7 +114,6 @@ async fn list_images(
 async fn delete_image(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
-    _user: AuthUser,
     _perm: RequirePermission<ImagesDelete>,
 ) -> ApiResult<StatusCode> {
     state.image_service.delete(id).await?;
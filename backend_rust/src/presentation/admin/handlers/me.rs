@@ -1,14 +1,26 @@
 use std::sync::Arc;
 
-use axum::{Json, Router, debug_handler, extract::State, routing::get};
+use axum::{
+    Json, Router, debug_handler,
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+};
 use shared_dtos::{
-    admin_user::AdminUserAdminResponse, error::ApiErrorResponse, list_response::ListResponse,
+    admin_user::AdminUserAdminResponse,
+    auth::{
+        ChangePasswordAdminRequest, ConfirmTwoFaRotationAdminRequest, ReauthenticateAdminRequest,
+        RecoveryCodesAdminResponse, TwoFaRotationAdminResponse,
+    },
+    error::ApiErrorResponse,
+    list_response::ListResponse,
 };
 
 use crate::{
-    errors::api::ApiResult,
+    errors::{api::ApiResult, auth::AuthError},
+    middlewares::{context::RequestContext, validator::ValidatedJson},
     services::{
-        admin_user::AdminUserServiceTrait,
+        admin_user::{AdminUserServiceTrait, ChangePasswordCommand},
         auth::{AuthServiceTrait, AuthUser},
     },
     state::AppState,
@@ -18,6 +30,10 @@ pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(get_me))
         .route("/permissions", get(get_me_permissions))
+        .route("/password", post(change_my_password))
+        .route("/2fa/rotate", post(rotate_my_two_fa))
+        .route("/2fa/rotate/confirm", post(confirm_my_two_fa_rotation))
+        .route("/2fa/recovery-codes", post(regenerate_my_recovery_codes))
 }
 
 #[utoipa::path(
@@ -61,5 +77,143 @@ async fn get_me_permissions(
     Ok(Json(ListResponse {
         total: user_permissions.len() as i64,
         items: user_permissions,
+        next_cursor: None,
     }))
 }
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/me/password",
+    tag = "Me",
+    request_body = ChangePasswordAdminRequest,
+    responses(
+        (status = 204, description = "Password changed"),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn change_my_password(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<ChangePasswordAdminRequest>,
+) -> ApiResult<StatusCode> {
+    state
+        .auth_service
+        .verify_credentials(user.id, &payload.current_password, None)
+        .await?;
+    state
+        .admin_user_service
+        .change_password(
+            ChangePasswordCommand {
+                user_id: user.id,
+                new_password: payload.new_password,
+            },
+            ctx,
+        )
+        .await?;
+    state
+        .auth_service
+        .revoke_sessions(user.id, Some(user.token))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/me/2fa/rotate",
+    tag = "Me",
+    request_body = ReauthenticateAdminRequest,
+    responses(
+        (status = 200, description = "New 2FA secret, active once confirmed with a code from it", body = TwoFaRotationAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn rotate_my_two_fa(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    ValidatedJson(payload): ValidatedJson<ReauthenticateAdminRequest>,
+) -> ApiResult<Json<TwoFaRotationAdminResponse>> {
+    state
+        .auth_service
+        .verify_credentials(user.id, &payload.password, Some(&payload.code))
+        .await?;
+    let rotation = state.admin_user_service.rotate_two_fa(user.id).await?;
+
+    Ok(Json(TwoFaRotationAdminResponse::from(rotation)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/me/2fa/rotate/confirm",
+    tag = "Me",
+    request_body = ConfirmTwoFaRotationAdminRequest,
+    responses(
+        (status = 200, description = "New secret activated, new recovery codes", body = RecoveryCodesAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 409, description = "Pending secret expired or replaced", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn confirm_my_two_fa_rotation(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<ConfirmTwoFaRotationAdminRequest>,
+) -> ApiResult<Json<RecoveryCodesAdminResponse>> {
+    let pending = state
+        .admin_user_service
+        .get_pending_two_fa_secret(user.id)
+        .await?;
+    if !state
+        .auth_service
+        .verify_totp_code(&pending.secret, &payload.code)?
+    {
+        return Err(AuthError::Invalid2FACode.into());
+    }
+    let recovery_codes = state
+        .admin_user_service
+        .confirm_two_fa_rotation(user.id, pending, ctx)
+        .await?;
+
+    Ok(Json(RecoveryCodesAdminResponse { recovery_codes }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/me/2fa/recovery-codes",
+    tag = "Me",
+    request_body = ReauthenticateAdminRequest,
+    responses(
+        (status = 200, description = "New recovery codes", body = RecoveryCodesAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn regenerate_my_recovery_codes(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<ReauthenticateAdminRequest>,
+) -> ApiResult<Json<RecoveryCodesAdminResponse>> {
+    state
+        .auth_service
+        .verify_credentials(user.id, &payload.password, Some(&payload.code))
+        .await?;
+    let recovery_codes = state
+        .admin_user_service
+        .regenerate_recovery_codes(user.id, ctx)
+        .await?;
+
+    Ok(Json(RecoveryCodesAdminResponse { recovery_codes }))
+}
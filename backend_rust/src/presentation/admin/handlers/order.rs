@@ -9,7 +9,7 @@ use crate::{
     errors::api::ApiResult,
     middlewares::require_permission::{OrdersRead, RequirePermission},
     models::order::OrderListQuery,
-    services::{auth::AuthUser, order::OrderServiceTrait},
+    services::order::OrderServiceTrait,
     state::AppState,
 };
 
@@ -31,7 +31,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn list_orders(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<OrdersRead>,
     query: OrderListQuery,
 ) -> ApiResult<Json<ListResponse<OrderAdminResponse>>> {
@@ -44,5 +43,6 @@ async fn list_orders(
             .into_iter()
             .map(OrderAdminResponse::from)
             .collect(),
+        next_cursor: orders.next_cursor,
     }))
 }
@@ -1,20 +1,44 @@
 use shared_dtos::{
-    error::ApiErrorResponse, invoice::PaymentInvoiceAdminResponse, list_response::ListResponse,
+    error::ApiErrorResponse,
+    invoice::{
+        PaymentInvoiceAdminResponse, PaymentInvoiceHistoryAdminResponse,
+        ResolvePaymentInvoiceAdminRequest,
+    },
+    list_response::ListResponse,
 };
 use std::sync::Arc;
 
-use axum::{Json, Router, extract::State, routing::get};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, post},
+};
 
 use crate::{
     errors::api::ApiResult,
-    middlewares::require_permission::{InvoicesRead, RequirePermission},
+    middlewares::{
+        context::RequestContext,
+        require_permission::{InvoicesRead, InvoicesResolve, RequirePermission},
+        validator::ValidatedJson,
+    },
     models::payment_invoice::PaymentInvoiceListQuery,
-    services::{auth::AuthUser, payment_invoice::PaymentInvoiceServiceTrait},
+    services::{
+        auth::AdminActor,
+        payment_invoice::PaymentInvoiceServiceTrait,
+        payment_invoice_resolution::{
+            PaymentInvoiceResolutionServiceTrait, ResolvePaymentInvoiceCommand,
+        },
+    },
     state::AppState,
 };
 
 pub fn router() -> Router<Arc<AppState>> {
-    Router::new().route("/", get(list_payment_invoices))
+    Router::new()
+        .route("/", get(list_payment_invoices))
+        .route("/{id}/history", get(get_payment_invoice_history))
+        .route("/{id}/complete", post(complete_payment_invoice))
+        .route("/{id}/fail", post(fail_payment_invoice))
+        .route("/{id}/resend-receipt", post(resend_payment_invoice_receipt))
 }
 
 #[utoipa::path(
@@ -31,7 +55,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn list_payment_invoices(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<InvoicesRead>,
     query: PaymentInvoiceListQuery,
 ) -> ApiResult<Json<ListResponse<PaymentInvoiceAdminResponse>>> {
@@ -44,5 +67,148 @@ async fn list_payment_invoices(
             .into_iter()
             .map(PaymentInvoiceAdminResponse::from)
             .collect(),
+        next_cursor: invoices.next_cursor,
     }))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/payment-invoices/{id}/history",
+    tag = "Payment Invoices",
+    params(
+        ("id" = i64, Path, description = "Payment invoice ID")
+    ),
+    responses(
+        (status = 200, description = "Invoice timestamps, gateway webhook events and audit log", body = PaymentInvoiceHistoryAdminResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_payment_invoice_history(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    _perm: RequirePermission<InvoicesRead>,
+) -> ApiResult<Json<PaymentInvoiceHistoryAdminResponse>> {
+    let history = state
+        .payment_invoice_resolution_service
+        .get_history(id)
+        .await?;
+
+    Ok(Json(history.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/payment-invoices/{id}/complete",
+    tag = "Payment Invoices",
+    params(
+        ("id" = i64, Path, description = "Payment invoice ID")
+    ),
+    request_body = ResolvePaymentInvoiceAdminRequest,
+    responses(
+        (status = 200, description = "Invoice completed and the customer credited", body = PaymentInvoiceAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn complete_payment_invoice(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<InvoicesResolve>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<ResolvePaymentInvoiceAdminRequest>,
+) -> ApiResult<Json<PaymentInvoiceAdminResponse>> {
+    let invoice = state
+        .payment_invoice_resolution_service
+        .complete(ResolvePaymentInvoiceCommand {
+            id,
+            resolved_by: actor.id(),
+            reason: payload.reason,
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(invoice.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/payment-invoices/{id}/fail",
+    tag = "Payment Invoices",
+    params(
+        ("id" = i64, Path, description = "Payment invoice ID")
+    ),
+    request_body = ResolvePaymentInvoiceAdminRequest,
+    responses(
+        (status = 200, description = "Invoice marked failed", body = PaymentInvoiceAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn fail_payment_invoice(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<InvoicesResolve>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<ResolvePaymentInvoiceAdminRequest>,
+) -> ApiResult<Json<PaymentInvoiceAdminResponse>> {
+    let invoice = state
+        .payment_invoice_resolution_service
+        .fail(ResolvePaymentInvoiceCommand {
+            id,
+            resolved_by: actor.id(),
+            reason: payload.reason,
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(invoice.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/payment-invoices/{id}/resend-receipt",
+    tag = "Payment Invoices",
+    params(
+        ("id" = i64, Path, description = "Payment invoice ID")
+    ),
+    request_body = ResolvePaymentInvoiceAdminRequest,
+    responses(
+        (status = 200, description = "Receipt submitted to the gateway again", body = PaymentInvoiceAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn resend_payment_invoice_receipt(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    actor: AdminActor,
+    _perm: RequirePermission<InvoicesResolve>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<ResolvePaymentInvoiceAdminRequest>,
+) -> ApiResult<Json<PaymentInvoiceAdminResponse>> {
+    let invoice = state
+        .payment_invoice_resolution_service
+        .resend_receipt(ResolvePaymentInvoiceCommand {
+            id,
+            resolved_by: actor.id(),
+            reason: payload.reason,
+            ctx: Some(ctx),
+        })
+        .await?;
+
+    Ok(Json(invoice.into()))
+}
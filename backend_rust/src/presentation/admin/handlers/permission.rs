@@ -8,7 +8,7 @@ use shared_dtos::{
 use crate::{
     errors::api::ApiResult,
     middlewares::require_permission::{RbacManage, RequirePermission},
-    services::{auth::AuthUser, permission::PermissionServiceTrait},
+    services::permission::PermissionServiceTrait,
     state::AppState,
 };
 
@@ -30,7 +30,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn list_permissions(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<RbacManage>,
 ) -> ApiResult<Json<ListResponse<PermissionAdminResponse>>> {
     let permissions = state.permission_service.get_list().await?;
@@ -41,5 +40,6 @@ async fn list_permissions(
             .into_iter()
             .map(PermissionAdminResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
@@ -30,7 +30,7 @@ use crate::{
     },
     models::product::ProductListQuery,
     services::{
-        auth::AuthUser,
+        auth::AdminActor,
         product::{
             CreateProductCommand, DeleteProductCommand, ProductServiceTrait, UpdateProductCommand,
             UploadProductsCommand,
@@ -73,7 +73,7 @@ struct UploadProductsMultipartRequest {
 )]
 async fn create_product(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<ProductsCreate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<NewProductAdminRequest>,
@@ -82,7 +82,7 @@ async fn create_product(
         .product_service
         .create(CreateProductCommand {
             category_id: payload.category_id,
-            created_by: user.id,
+            created_by: actor.id(),
             details: None,
             external_id: None,
             fulfillment_image_id: payload.fulfillment_image_id,
@@ -117,7 +117,7 @@ async fn create_product(
 )]
 async fn upload_products(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<ProductsCreate>,
     ctx: RequestContext,
     mut multipart: Multipart,
@@ -131,7 +131,7 @@ async fn upload_products(
         .product_service
         .upload_products(UploadProductsCommand {
             products: products_to_create,
-            created_by: user.id,
+            created_by: actor.id(),
             ctx: Some(ctx),
         })
         .await?;
@@ -162,7 +162,6 @@ async fn upload_products(
 )]
 async fn list_products(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<ProductsRead>,
     query: ProductListQuery,
 ) -> ApiResult<Json<ListResponse<ProductAdminResponse>>> {
@@ -175,6 +174,7 @@ async fn list_products(
             .into_iter()
             .map(ProductAdminResponse::from)
             .collect(),
+        next_cursor: products.next_cursor,
     }))
 }
 
@@ -192,7 +192,6 @@ async fn list_products(
 async fn get_product(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<ProductsRead>,
 ) -> ApiResult<Json<ProductAdminResponse>> {
     let product = state.product_service.get_by_id(id).await?;
@@ -216,7 +215,7 @@ async fn get_product(
 async fn update_product(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<ProductsUpdate>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<UpdateProductAdminRequest>,
@@ -243,7 +242,8 @@ async fn update_product(
                 subscription_period_days: payload.subscription_period_days,
                 r#type: payload.r#type,
                 stock: payload.stock,
-                updated_by: user.id,
+                hidden: payload.hidden,
+                updated_by: actor.id(),
             },
             ctx,
         )
@@ -266,7 +266,7 @@ async fn update_product(
 async fn delete_product(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     ctx: RequestContext,
     _perm: RequirePermission<ProductsDelete>,
 ) -> ApiResult<StatusCode> {
@@ -274,7 +274,7 @@ async fn delete_product(
         .product_service
         .delete(DeleteProductCommand {
             id,
-            deleted_by: user.id,
+            deleted_by: actor.id(),
             ctx: Some(ctx),
         })
         .await?;
@@ -0,0 +1,98 @@
+use shared_dtos::{
+    error::ApiErrorResponse,
+    list_response::ListResponse,
+    product_sync::{ProductSyncRunResponse, ProductSyncTrigger, RunProductSyncRequest},
+};
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+};
+
+use crate::{
+    errors::api::ApiResult,
+    middlewares::require_permission::{ProductsRead, ProductsUpdate, RequirePermission},
+    models::product_sync::ProductSyncRunListQuery,
+    services::{
+        auth::AdminActor,
+        product_sync::{ProductSyncServiceTrait, RunProductSyncCommand},
+    },
+    state::AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(run_sync))
+        .route("/runs", get(list_runs))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/product-sync",
+    tag = "Products",
+    request_body = RunProductSyncRequest,
+    responses(
+        (status = 202, description = "Sync runs queued for the sync worker", body = ListResponse<ProductSyncRunResponse>),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Provider is not enabled", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn run_sync(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<ProductsUpdate>,
+    Json(payload): Json<RunProductSyncRequest>,
+) -> ApiResult<(StatusCode, Json<ListResponse<ProductSyncRunResponse>>)> {
+    // Catalog syncs take a while, the worker picks the runs up within seconds
+    let runs = state
+        .product_sync_service
+        .queue(RunProductSyncCommand {
+            provider_name: payload.provider_name,
+            trigger: ProductSyncTrigger::Manual,
+            triggered_by: Some(actor.id()),
+        })
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ListResponse {
+            total: runs.len() as i64,
+            items: runs.into_iter().map(ProductSyncRunResponse::from).collect(),
+            next_cursor: None,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/product-sync/runs",
+    tag = "Products",
+    responses(
+        (status = 200, description = "Sync runs list", body = ListResponse<ProductSyncRunResponse>),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_runs(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<ProductsRead>,
+    query: ProductSyncRunListQuery,
+) -> ApiResult<Json<ListResponse<ProductSyncRunResponse>>> {
+    let runs = state.product_sync_service.get_runs(query).await?;
+
+    Ok(Json(ListResponse {
+        total: runs.total,
+        items: runs
+            .items
+            .into_iter()
+            .map(ProductSyncRunResponse::from)
+            .collect(),
+        next_cursor: runs.next_cursor,
+    }))
+}
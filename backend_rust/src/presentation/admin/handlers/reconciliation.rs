@@ -0,0 +1,114 @@
+use chrono::{Duration, NaiveDate, Utc};
+use shared_dtos::{
+    error::ApiErrorResponse,
+    list_response::ListResponse,
+    reconciliation::{ReconciliationReportResponse, RunReconciliationRequest},
+};
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::get,
+};
+
+use crate::{
+    errors::api::ApiResult,
+    middlewares::require_permission::{RequirePermission, TransactionsRead},
+    models::reconciliation::ReconciliationReportListQuery,
+    services::{
+        auth::AdminActor,
+        reconciliation::{ReconciliationServiceTrait, RunReconciliationCommand},
+    },
+    state::AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_reports).post(run_reconciliation))
+        .route("/{day}", get(get_report))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/reconciliation",
+    tag = "Transactions",
+    request_body = RunReconciliationRequest,
+    responses(
+        (status = 200, description = "Reconciliation report", body = ReconciliationReportResponse),
+        (status = 400, description = "Day is in the future", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn run_reconciliation(
+    State(state): State<Arc<AppState>>,
+    actor: AdminActor,
+    _perm: RequirePermission<TransactionsRead>,
+    Json(payload): Json<RunReconciliationRequest>,
+) -> ApiResult<Json<ReconciliationReportResponse>> {
+    let report = state
+        .reconciliation_service
+        .run(RunReconciliationCommand {
+            day: payload
+                .day
+                .unwrap_or_else(|| Utc::now().date_naive() - Duration::days(1)),
+            triggered_by: Some(actor.id()),
+            notify: payload.notify,
+        })
+        .await?;
+
+    Ok(Json(report.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/reconciliation",
+    tag = "Transactions",
+    responses(
+        (status = 200, description = "Reconciliation reports list", body = ListResponse<ReconciliationReportResponse>),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_reports(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<TransactionsRead>,
+    query: ReconciliationReportListQuery,
+) -> ApiResult<Json<ListResponse<ReconciliationReportResponse>>> {
+    let reports = state.reconciliation_service.get_list(query).await?;
+
+    Ok(Json(ListResponse {
+        total: reports.total,
+        items: reports
+            .items
+            .into_iter()
+            .map(ReconciliationReportResponse::from)
+            .collect(),
+        next_cursor: reports.next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/reconciliation/{day}",
+    tag = "Transactions",
+    params(("day" = NaiveDate, Path, description = "UTC day, e.g. 2026-10-18")),
+    responses(
+        (status = 200, description = "Reconciliation report", body = ReconciliationReportResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "No report for this day", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_report(
+    State(state): State<Arc<AppState>>,
+    Path(day): Path<NaiveDate>,
+    _perm: RequirePermission<TransactionsRead>,
+) -> ApiResult<Json<ReconciliationReportResponse>> {
+    let report = state.reconciliation_service.get_by_day(day).await?;
+    Ok(Json(report.into()))
+}
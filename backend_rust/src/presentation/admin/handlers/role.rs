@@ -28,7 +28,7 @@ use crate::{
         role_permission::UpdateRolePermissions,
     },
     services::{
-        auth::AuthUser, permission::PermissionServiceTrait, role::RoleServiceTrait,
+        auth::AdminActor, permission::PermissionServiceTrait, role::RoleServiceTrait,
         role_permission::RolePermissionServiceTrait,
     },
     state::AppState,
@@ -58,7 +58,7 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn create_role(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<RbacManage>,
     ValidatedJson(payload): ValidatedJson<NewRoleAdminRequest>,
 ) -> ApiResult<Json<RoleAdminResponse>> {
@@ -67,7 +67,7 @@ async fn create_role(
         .create(NewRole {
             description: payload.description,
             name: payload.name,
-            created_by: user.id,
+            created_by: actor.id(),
         })
         .await?;
 
@@ -88,7 +88,6 @@ async fn create_role(
 )]
 async fn list_roles(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<RbacManage>,
 ) -> ApiResult<Json<ListResponse<RoleAdminResponse>>> {
     let roles = state.role_service.get_list().await?;
@@ -96,6 +95,7 @@ async fn list_roles(
     Ok(Json(ListResponse {
         total: roles.len() as i64,
         items: roles.into_iter().map(RoleAdminResponse::from).collect(),
+        next_cursor: None,
     }))
 }
 
@@ -114,7 +114,6 @@ async fn list_roles(
 async fn update_role(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<RbacManage>,
     ValidatedJson(payload): ValidatedJson<UpdateRoleAdminRequest>,
 ) -> ApiResult<Json<RoleAdminResponse>> {
@@ -147,7 +146,6 @@ async fn update_role(
 async fn delete_role(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<RbacManage>,
 ) -> ApiResult<StatusCode> {
     state.role_service.delete(id).await?;
@@ -170,7 +168,6 @@ async fn delete_role(
 async fn get_role_permissions(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    _user: AuthUser,
     _perm: RequirePermission<RbacManage>,
 ) -> ApiResult<Json<ListResponse<PermissionAdminResponse>>> {
     let permissions = state.permission_service.get_for_role(id).await?;
@@ -181,6 +178,7 @@ async fn get_role_permissions(
             .into_iter()
             .map(PermissionAdminResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
 
@@ -199,7 +197,7 @@ async fn get_role_permissions(
 async fn update_role_permissions(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<RbacManage>,
     ValidatedJson(payload): ValidatedJson<UpdateRolePermissionsAdminRequest>,
 ) -> ApiResult<Json<ListResponse<PermissionAdminResponse>>> {
@@ -208,7 +206,7 @@ async fn update_role_permissions(
         .update_role_permissions(UpdateRolePermissions {
             added: payload.added,
             removed: payload.removed,
-            created_by: user.id,
+            created_by: actor.id(),
             role_id: id,
         })
         .await?;
@@ -221,5 +219,6 @@ async fn update_role_permissions(
             .into_iter()
             .map(PermissionAdminResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
@@ -1,11 +1,18 @@
 use std::sync::Arc;
 
-use axum::{Json, Router, extract::State, routing::get};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, patch},
+};
 use shared_dtos::{
+    currency::ExchangeRatesResponse,
     error::ApiErrorResponse,
+    invoice::PaymentSystem,
     settings::{
-        BotSettingsAdminResponse, PricingSettingsAdminResponse, UpdateBotSettingsAdminRequest,
-        UpdatePricingSettingsAdminRequest,
+        BotSettingsAdminResponse, PaymentGatewaySettingsAdminResponse,
+        PricingSettingsAdminResponse, UpdateBotSettingsAdminRequest,
+        UpdatePaymentGatewaySettingsAdminRequest, UpdatePricingSettingsAdminRequest,
     },
 };
 
@@ -19,7 +26,11 @@ use crate::{
         validator::ValidatedJson,
     },
     services::{
-        auth::AuthUser,
+        auth::AdminActor,
+        currency::CurrencyServiceTrait,
+        payment_gateway_settings::{
+            PaymentGatewaySettingsServiceTrait, UpdatePaymentGatewaySettingsCommand,
+        },
         settings::{SettingsServiceTrait, UpdateSettingsCommand},
     },
     state::AppState,
@@ -32,6 +43,9 @@ pub fn router() -> Router<Arc<AppState>> {
             get(get_pricing_settings).patch(update_pricing_settings),
         )
         .route("/bot", get(get_bot_settings).patch(update_bot_settings))
+        .route("/gateways", get(list_gateway_settings))
+        .route("/gateways/{gateway}", patch(update_gateway_settings))
+        .route("/exchange-rates", get(get_exchange_rates))
 }
 
 #[utoipa::path(
@@ -48,7 +62,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn get_pricing_settings(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<PricingRead>,
 ) -> ApiResult<Json<PricingSettingsAdminResponse>> {
     let settings = state.settings_service.load_settings().await?;
@@ -70,13 +83,13 @@ async fn get_pricing_settings(
 )]
 async fn update_pricing_settings(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<PricingEdit>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<UpdatePricingSettingsAdminRequest>,
 ) -> ApiResult<Json<PricingSettingsAdminResponse>> {
     let mut command = UpdateSettingsCommand::from(payload);
-    command.updated_by = user.id;
+    command.updated_by = actor.id();
     command.ctx = Some(ctx);
     let category = state.settings_service.update(command).await?;
 
@@ -97,7 +110,6 @@ async fn update_pricing_settings(
 )]
 async fn get_bot_settings(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<SettingsRead>,
 ) -> ApiResult<Json<BotSettingsAdminResponse>> {
     let settings = state.settings_service.load_settings().await?;
@@ -119,15 +131,89 @@ async fn get_bot_settings(
 )]
 async fn update_bot_settings(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     _perm: RequirePermission<SettingsEdit>,
     ctx: RequestContext,
     ValidatedJson(payload): ValidatedJson<UpdateBotSettingsAdminRequest>,
 ) -> ApiResult<Json<BotSettingsAdminResponse>> {
     let mut command = UpdateSettingsCommand::from(payload);
-    command.updated_by = user.id;
+    command.updated_by = actor.id();
     command.ctx = Some(ctx);
     let category = state.settings_service.update(command).await?;
 
     Ok(Json(category.into()))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/settings/gateways",
+    tag = "Settings",
+    responses(
+        (status = 200, description = "Invoice lifetime and deposit limits per gateway", body = Vec<PaymentGatewaySettingsAdminResponse>),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn list_gateway_settings(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<SettingsRead>,
+) -> ApiResult<Json<Vec<PaymentGatewaySettingsAdminResponse>>> {
+    let settings = state.payment_gateway_settings_service.get_all().await?;
+
+    Ok(Json(settings.into_iter().map(Into::into).collect()))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/admin/settings/gateways/{gateway}",
+    tag = "Settings",
+    params(
+        ("gateway" = PaymentSystem, Path, description = "Gateway to configure")
+    ),
+    request_body = UpdatePaymentGatewaySettingsAdminRequest,
+    responses(
+        (status = 200, description = "Gateway settings updated", body = PaymentGatewaySettingsAdminResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn update_gateway_settings(
+    State(state): State<Arc<AppState>>,
+    Path(gateway): Path<PaymentSystem>,
+    actor: AdminActor,
+    _perm: RequirePermission<SettingsEdit>,
+    ctx: RequestContext,
+    ValidatedJson(payload): ValidatedJson<UpdatePaymentGatewaySettingsAdminRequest>,
+) -> ApiResult<Json<PaymentGatewaySettingsAdminResponse>> {
+    let mut command = UpdatePaymentGatewaySettingsCommand::from(payload);
+    command.updated_by = actor.id();
+    command.ctx = Some(ctx);
+    let settings = state
+        .payment_gateway_settings_service
+        .update(gateway, command)
+        .await?;
+
+    Ok(Json(settings.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/settings/exchange-rates",
+    tag = "Settings",
+    responses(
+        (status = 200, description = "Store base currency and the rates in use", body = ExchangeRatesResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 403, description = "Forbidden", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_exchange_rates(
+    State(state): State<Arc<AppState>>,
+    _perm: RequirePermission<SettingsRead>,
+) -> ApiResult<Json<ExchangeRatesResponse>> {
+    Ok(Json(state.currency_service.get_rates().await?))
+}
@@ -10,7 +10,7 @@ use crate::{
     errors::api::ApiResult,
     middlewares::require_permission::{RequirePermission, StockRead},
     models::stock_movement::StockMovementListQuery,
-    services::{auth::AuthUser, stock_movement::StockMovementServiceTrait},
+    services::stock_movement::StockMovementServiceTrait,
     state::AppState,
 };
 
@@ -32,7 +32,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn list_stock_movement(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<StockRead>,
     query: StockMovementListQuery,
 ) -> ApiResult<Json<ListResponse<StockMovementAdminResponse>>> {
@@ -45,5 +44,6 @@ async fn list_stock_movement(
             .into_iter()
             .map(StockMovementAdminResponse::from)
             .collect(),
+        next_cursor: stock_movements.next_cursor,
     }))
 }
@@ -18,7 +18,7 @@ use crate::{
         validator::ValidatedJson,
     },
     services::{
-        auth::AuthUser,
+        auth::AdminActor,
         store_balance_request::{
             CreateStoreBalanceRequestCommand, StoreBalanceRequestServiceTrait,
         },
@@ -48,7 +48,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn get_store_balance(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<StoreBalanceRead>,
 ) -> ApiResult<Json<StoreBalanceAdminResponse>> {
     let last_transaction = state.transaction_service.get_last().await;
@@ -83,7 +82,7 @@ async fn get_store_balance(
 )]
 async fn create_store_balance_request(
     State(state): State<Arc<AppState>>,
-    user: AuthUser,
+    actor: AdminActor,
     // TODO make one permission to manage store balance
     _perm: RequirePermission<StoreBalanceWithdraw>,
     ctx: RequestContext,
@@ -92,7 +91,7 @@ async fn create_store_balance_request(
     let _ = state
         .store_balance_request_service
         .create(CreateStoreBalanceRequestCommand {
-            admin_user_id: user.id,
+            admin_user_id: actor.id(),
             amount: Decimal::from_f64(payload.amount)
                 .ok_or_else(|| ApiError::BadRequest("Invalid amount".into()))?,
             request_type: payload.request_type,
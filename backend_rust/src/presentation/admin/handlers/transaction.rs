@@ -9,7 +9,7 @@ use crate::{
     errors::api::ApiResult,
     middlewares::require_permission::{RequirePermission, TransactionsRead},
     models::transaction::TransactionListQuery,
-    services::{auth::AuthUser, transaction::TransactionServiceTrait},
+    services::transaction::TransactionServiceTrait,
     state::AppState,
 };
 
@@ -31,7 +31,6 @@ pub fn router() -> Router<Arc<AppState>> {
 )]
 async fn list_transactions(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser,
     _perm: RequirePermission<TransactionsRead>,
     query: TransactionListQuery,
 ) -> ApiResult<Json<ListResponse<TransactionAdminResponse>>> {
@@ -44,5 +43,6 @@ async fn list_transactions(
             .into_iter()
             .map(TransactionAdminResponse::from)
             .collect(),
+        next_cursor: transactions.next_cursor,
     }))
 }
@@ -4,9 +4,10 @@ use axum::Router;
 
 use crate::{
     presentation::admin::handlers::{
-        admin_user, audit_log, auth, bot, broadcast, category, customer, dashboard, image, me,
-        order, payment_invoice, permission, product, role, settings, stock_movement, store_balance,
-        transaction,
+        accounting_export, admin_user, api_key, audit_log, auth, bot, broadcast, category,
+        customer, customer_segment, dashboard, gateway, image, me, order, payment_invoice,
+        permission, product, product_sync, reconciliation, role, settings, stock_movement,
+        store_balance, transaction,
     },
     state::AppState,
 };
@@ -18,9 +19,13 @@ pub fn router() -> Router<Arc<AppState>> {
         .nest("/me", me::router())
         .nest("/admin-users", admin_user::router())
         .nest("/roles", role::router())
+        .nest("/api-keys", api_key::router())
         .nest("/permissions", permission::router())
         .nest("/transactions", transaction::router())
+        .nest("/reconciliation", reconciliation::router())
+        .nest("/exports", accounting_export::router())
         .nest("/products", product::router())
+        .nest("/product-sync", product_sync::router())
         .nest("/images", image::router())
         .nest("/stock-movements", stock_movement::router())
         .nest("/customers", customer::router())
@@ -30,6 +35,8 @@ pub fn router() -> Router<Arc<AppState>> {
         .nest("/orders", order::router())
         .nest("/store-balance", store_balance::router())
         .nest("/broadcasts", broadcast::router())
+        .nest("/customer-segments", customer_segment::router())
         .nest("/dashboard", dashboard::router())
         .nest("/payment-invoices", payment_invoice::router())
+        .nest("/gateways", gateway::router())
 }
@@ -1,18 +1,23 @@
-use rust_decimal::prelude::ToPrimitive;
-use shared_dtos::customer::CustomerBotResponse;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use shared_dtos::{currency::Currency, customer::CustomerBotResponse};
 
 use crate::models::customer::CustomerRow;
 
-impl From<CustomerRow> for CustomerBotResponse {
-    fn from(r: CustomerRow) -> Self {
-        CustomerBotResponse {
-            id: r.id,
-            telegram_id: r.telegram_id,
-            balance: r.balance.to_f64().unwrap_or_default(),
-            is_blocked: r.is_blocked,
-            bot_is_blocked_by_user: r.bot_is_blocked_by_user,
-            has_passed_captcha: r.has_passed_captcha,
-            blocked_until: r.blocked_until,
-        }
+/// Balance stays in the base currency, the bot multiplies it by `display_rate`
+pub fn customer_bot_response(
+    r: CustomerRow,
+    display_currency: Currency,
+    display_rate: Decimal,
+) -> CustomerBotResponse {
+    CustomerBotResponse {
+        id: r.id,
+        telegram_id: r.telegram_id,
+        balance: r.balance.to_f64().unwrap_or_default(),
+        is_blocked: r.is_blocked,
+        bot_is_blocked_by_user: r.bot_is_blocked_by_user,
+        has_passed_captcha: r.has_passed_captcha,
+        blocked_until: r.blocked_until,
+        display_currency,
+        display_rate: display_rate.to_f64().unwrap_or(1.0),
     }
 }
@@ -16,6 +16,8 @@ impl From<PaymentInvoiceRow> for PaymentInvoiceBotResponse {
             created_at: r.created_at,
             gateway: r.gateway,
             gateway_invoice_id: r.gateway_invoice_id,
+            currency: r.currency,
+            checkout_order_id: r.checkout_order_id,
         }
     }
 }
@@ -1,7 +1,20 @@
 use rust_decimal::prelude::ToPrimitive;
-use shared_dtos::order::{EnrichedOrderBotResponse, OrderItemBotResponse};
+use shared_dtos::order::{EnrichedOrderBotResponse, OrderItemBotResponse, PurchaseBotResponse};
 
-use crate::services::order::EnrichedOrder;
+use crate::services::{order::EnrichedOrder, purchase::PurchaseResult};
+
+impl From<PurchaseResult> for PurchaseBotResponse {
+    fn from(value: PurchaseResult) -> Self {
+        Self {
+            product_name: value.product_name,
+            balance: value.balance,
+            details: value.details,
+            fulfilled_text: value.fulfilled_text,
+            fulfilled_image_id: value.fulfilled_image_id,
+            price: value.price,
+        }
+    }
+}
 
 impl From<EnrichedOrder> for EnrichedOrderBotResponse {
     fn from(value: EnrichedOrder) -> Self {
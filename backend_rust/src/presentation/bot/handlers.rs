@@ -1,4 +1,5 @@
 pub mod bot;
+pub mod broadcast;
 pub mod can_operate;
 pub mod captcha;
 pub mod category;
@@ -107,6 +107,7 @@ async fn list_bots(
     Ok(Json(ListResponse {
         total: bots.total,
         items: bots.items.into_iter().map(BotBotResponse::from).collect(),
+        next_cursor: bots.next_cursor,
     }))
 }
 
@@ -164,6 +165,7 @@ async fn get_primary_bots(
     Ok(Json(ListResponse {
         total: bots.len() as i64,
         items: bots.into_iter().map(BotBotResponse::from).collect(),
+        next_cursor: None,
     }))
 }
 
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::post,
+};
+use shared_dtos::{broadcast::BroadcastDeliveryReportBotRequest, error::ApiErrorResponse};
+
+use crate::{
+    errors::api::ApiResult,
+    middlewares::{bot_auth::AuthBot, validator::ValidatedJson},
+    services::broadcast::BroadcastServiceTrait,
+    state::AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/recipients/{id}/delivery", post(report_delivery))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/bot/broadcasts/recipients/{id}/delivery",
+    tag = "Broadcasts",
+    request_body = BroadcastDeliveryReportBotRequest,
+    responses(
+        (status = 200, description = "Delivery result stored"),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 409, description = "Recipient is not awaiting a delivery report", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn report_delivery(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    _bot: AuthBot,
+    ValidatedJson(payload): ValidatedJson<BroadcastDeliveryReportBotRequest>,
+) -> ApiResult<Json<()>> {
+    state
+        .broadcast_service
+        .report_delivery(id, payload.status, payload.error_message)
+        .await?;
+
+    Ok(Json(()))
+}
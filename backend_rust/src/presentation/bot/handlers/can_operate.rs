@@ -4,8 +4,10 @@ use axum::{Json, Router, extract::State, routing::get};
 use shared_dtos::{can_operate::CanOperateBotResponse, error::ApiErrorResponse};
 
 use crate::{
-    errors::api::ApiResult, middlewares::verified_service::VerifiedService,
-    services::bot::BotServiceTrait, state::AppState,
+    errors::api::ApiResult,
+    middlewares::verified_service::VerifiedService,
+    services::{bot::BotServiceTrait, health::HealthServiceTrait},
+    state::AppState,
 };
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -28,6 +30,10 @@ async fn can_operate(
     _service: VerifiedService,
 ) -> ApiResult<Json<CanOperateBotResponse>> {
     let can_operate = state.bot_service.can_operate().await?;
+    let payments_available = state.health_service.payments_available().await;
 
-    Ok(Json(CanOperateBotResponse { can_operate }))
+    Ok(Json(CanOperateBotResponse {
+        can_operate,
+        payments_available,
+    }))
 }
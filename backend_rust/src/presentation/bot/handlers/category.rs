@@ -37,5 +37,6 @@ async fn list_categories(
             .into_iter()
             .map(CategoryAdminResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
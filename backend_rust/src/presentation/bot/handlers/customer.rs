@@ -5,6 +5,7 @@ use axum::{
     extract::{Path, State},
     routing::{get, post},
 };
+use rust_decimal::Decimal;
 use shared_dtos::{
     analytics::BotAnalyticsBotResponse,
     customer::{CustomerBotResponse, NewCustomerBotRequest, UpdateCustomerBotRequest},
@@ -16,11 +17,13 @@ use shared_dtos::{
 };
 
 use crate::{
-    errors::api::ApiResult,
+    errors::api::{ApiError, ApiResult},
     middlewares::{bot_auth::AuthBot, validator::ValidatedJson},
-    models::customer::NewCustomer,
+    models::customer::{CustomerRow, NewCustomer},
+    presentation::bot::dtos::customer::customer_bot_response,
     services::{
         analytics::AnalyticsServiceTrait,
+        currency::CurrencyServiceTrait,
         customer::{CustomerServiceTrait, UpdateCustomerCommand},
         order::OrderServiceTrait,
         payment_invoice::PaymentInvoiceServiceTrait,
@@ -49,6 +52,23 @@ pub fn router() -> Router<Arc<AppState>> {
         )
 }
 
+/// Falls back to the base currency when the chosen one has no rate anymore
+async fn to_response(state: &AppState, customer: CustomerRow) -> ApiResult<CustomerBotResponse> {
+    let base_currency = state.currency_service.base_currency();
+    let display_currency = customer.display_currency.unwrap_or(base_currency);
+    let (display_currency, display_rate) = match state.currency_service.rate(display_currency).await
+    {
+        Ok(rate) => (display_currency, rate),
+        Err(ApiError::Conflict(_)) => (base_currency, Decimal::ONE),
+        Err(e) => return Err(e),
+    };
+    Ok(customer_bot_response(
+        customer,
+        display_currency,
+        display_rate,
+    ))
+}
+
 #[utoipa::path(
     get,
     path = "/api/bot/customers/{telegram_id}",
@@ -68,7 +88,7 @@ async fn get_customer(
         .customer_service
         .get_by_telegram_id(telegram_id)
         .await?;
-    Ok(Json(CustomerBotResponse::from(customer)))
+    Ok(Json(to_response(&state, customer).await?))
 }
 
 #[utoipa::path(
@@ -94,7 +114,7 @@ async fn create_customer(
             registered_with_bot: bot.bot_id,
         })
         .await?;
-    Ok(Json(CustomerBotResponse::from(customer)))
+    Ok(Json(to_response(&state, customer).await?))
 }
 
 #[utoipa::path(
@@ -105,6 +125,7 @@ async fn create_customer(
         (status = 200, description = "Update customer", body = CustomerBotResponse),
         (status = 400, description = "Bad request", body = ApiErrorResponse),
         (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 409, description = "No exchange rate for the display currency", body = ApiErrorResponse),
         (status = 500, description = "Internal server error", body = ApiErrorResponse),
     )
 )]
@@ -118,6 +139,9 @@ async fn update_customer(
         .customer_service
         .get_by_telegram_id(telegram_id)
         .await?;
+    if let Some(display_currency) = payload.display_currency {
+        state.currency_service.rate(display_currency).await?;
+    }
     let customer = state
         .customer_service
         .update(UpdateCustomerCommand {
@@ -130,9 +154,10 @@ async fn update_customer(
             last_seen_with_bot: None,
             ctx: None,
             blocked_until: None,
+            display_currency: payload.display_currency,
         })
         .await?;
-    Ok(Json(CustomerBotResponse::from(customer)))
+    Ok(Json(to_response(&state, customer).await?))
 }
 
 #[utoipa::path(
@@ -165,6 +190,7 @@ async fn get_customer_invoices(
             .into_iter()
             .map(PaymentInvoiceBotResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
 
@@ -195,6 +221,7 @@ async fn get_customer_orders(
             .into_iter()
             .map(EnrichedOrderBotResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
 
@@ -255,6 +282,7 @@ async fn get_customer_subscriptions(
             .into_iter()
             .map(UserSubscriptionBotResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
 
@@ -287,5 +315,6 @@ async fn get_customer_referral_analytics(
             .into_iter()
             .map(BotAnalyticsBotResponse::from)
             .collect(),
+        next_cursor: None,
     }))
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use axum::{Json, Router, extract::State, routing::get};
+use rust_decimal::prelude::ToPrimitive;
 use shared_dtos::{
     error::ApiErrorResponse,
     invoice::{GatewayBotResponse, PaymentSystem},
@@ -8,7 +9,13 @@ use shared_dtos::{
 };
 
 use crate::{
-    errors::api::ApiResult, middlewares::verified_service::VerifiedService, state::AppState,
+    errors::api::ApiResult,
+    middlewares::verified_service::VerifiedService,
+    services::{
+        gateway_health::GatewayHealthServiceTrait,
+        payment_gateway_settings::PaymentGatewaySettingsServiceTrait,
+    },
+    state::AppState,
 };
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -27,28 +34,41 @@ pub fn router() -> Router<Arc<AppState>> {
     )
 )]
 async fn get_gateways(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     _service: VerifiedService,
 ) -> ApiResult<Json<ListResponse<GatewayBotResponse>>> {
-    let items = vec![
-        GatewayBotResponse {
-            name: PaymentSystem::PlatformCard,
-            display_name: "Платформа (Карта)".to_string(),
-        },
-        // GatewayResponse {
-        //     name: PaymentSystem::PlatformSBP,
-        //     display_name: "Платформа (СБП)".to_string(),
-        // },
+    #[allow(unused_mut)]
+    let mut gateways = vec![
+        (PaymentSystem::PlatformCard, "Платформа (Карта)"),
+        // (PaymentSystem::PlatformSBP, "Платформа (СБП)"),
     ];
 
     // #[cfg(feature = "mock-payments-provider")]
-    // items.push(GatewayResponse {
-    //     name: PaymentSystem::Mock,
-    //     display_name: "Криптоплатежи (мок-провайдер)".to_string(),
-    // });
+    // gateways.push((PaymentSystem::Mock, "Криптоплатежи (мок-провайдер)"));
+
+    // Gateways with an open circuit are hidden until their cooldown passes
+    let unavailable = state.gateway_health_service.get_unavailable().await?;
+    gateways.retain(|(name, _)| !unavailable.contains(name));
+
+    let settings = state.payment_gateway_settings_service.get_all().await?;
+    let items: Vec<GatewayBotResponse> = gateways
+        .into_iter()
+        .filter_map(|(name, display_name)| {
+            let limits = settings.iter().find(|s| s.gateway == name)?;
+            Some(GatewayBotResponse {
+                name,
+                display_name: display_name.to_string(),
+                min_amount: limits.min_amount.to_f64().unwrap_or_default(),
+                max_amount: limits.max_amount.to_f64().unwrap_or_default(),
+                amount_step: limits.amount_step.to_f64().unwrap_or_default(),
+                currency: limits.currency,
+            })
+        })
+        .collect();
 
     Ok(Json(ListResponse {
         total: items.len() as i64,
         items,
+        next_cursor: None,
     }))
 }
@@ -74,6 +74,7 @@ async fn list_invoices(
             .into_iter()
             .map(PaymentInvoiceBotResponse::from)
             .collect(),
+        next_cursor: payment_invoices.next_cursor,
     }))
 }
 
@@ -107,6 +108,7 @@ async fn get_invoice(
         (status = 200, description = "Invoice created", body = PaymentInvoiceBotResponse),
         (status = 400, description = "Bad request", body = ApiErrorResponse),
         (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 409, description = "Limit reached or no exchange rate for the currency", body = ApiErrorResponse),
         (status = 500, description = "Internal server error", body = ApiErrorResponse),
     )
 )]
@@ -127,6 +129,8 @@ async fn create_invoice(
                 .ok_or(ApiError::BadRequest("Failed to parse amount".to_string()))?,
             customer_id,
             gateway: payload.gateway,
+            currency: payload.currency,
+            checkout_order_id: None,
         })
         .await?;
     Ok(Json(PaymentInvoiceBotResponse::from(payment_invoice)))
@@ -142,6 +146,7 @@ async fn create_invoice(
         (status = 400, description = "Bad request", body = ApiErrorResponse),
         (status = 401, description = "Unauthorized", body = ApiErrorResponse),
         (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 409, description = "Status transition not allowed or lost to a concurrent update", body = ApiErrorResponse),
         (status = 500, description = "Internal server error", body = ApiErrorResponse),
     )
 )]
@@ -7,7 +7,10 @@ use axum::{
 };
 use shared_dtos::{
     error::ApiErrorResponse,
-    order::{EnrichedOrderBotResponse, PurchaseBotRequest, PurchaseBotResponse},
+    invoice::PaymentInvoiceBotResponse,
+    order::{
+        CheckoutBotRequest, EnrichedOrderBotResponse, PurchaseBotRequest, PurchaseBotResponse,
+    },
 };
 
 use crate::{
@@ -15,7 +18,8 @@ use crate::{
     middlewares::{bot_auth::AuthBot, validator::ValidatedJson},
     services::{
         order::OrderServiceTrait,
-        purchase::{PurchaseProductCommand, PurchaseServiceTrait},
+        payment_invoice::{CreatePaymentInvoiceCommand, PaymentInvoiceServiceTrait},
+        purchase::{CreateCheckoutOrderCommand, PurchaseProductCommand, PurchaseServiceTrait},
     },
     state::AppState,
 };
@@ -23,6 +27,7 @@ use crate::{
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", post(purchase))
+        .route("/checkout", post(checkout))
         .route("/{id}", get(get_order))
 }
 
@@ -52,14 +57,60 @@ async fn purchase(
         })
         .await?;
 
-    Ok(Json(PurchaseBotResponse {
-        product_name: result.product_name,
-        balance: result.balance,
-        details: result.details,
-        fulfilled_text: result.fulfilled_text,
-        fulfilled_image_id: result.fulfilled_image_id,
-        price: result.price,
-    }))
+    Ok(Json(PurchaseBotResponse::from(result)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/bot/orders/checkout",
+    tag = "Orders",
+    request_body = CheckoutBotRequest,
+    responses(
+        (status = 200, description = "Invoice for the order created", body = PaymentInvoiceBotResponse),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 409, description = "Limit reached or no exchange rate for the currency", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn checkout(
+    State(state): State<Arc<AppState>>,
+    bot: AuthBot,
+    ValidatedJson(payload): ValidatedJson<CheckoutBotRequest>,
+) -> ApiResult<Json<PaymentInvoiceBotResponse>> {
+    let checkout = state
+        .purchase_service
+        .create_checkout_order(CreateCheckoutOrderCommand {
+            product_id: payload.product_id,
+            telegram_id: payload.telegram_id,
+            bot_id: bot.bot_id,
+            pay_full_price: payload.pay_full_price,
+        })
+        .await?;
+
+    let payment_invoice = match state
+        .payment_invoice_service
+        .create(CreatePaymentInvoiceCommand {
+            customer_id: checkout.order.customer_id,
+            amount: checkout.amount_to_pay,
+            gateway: payload.gateway,
+            currency: Some(state.config.store_base_currency),
+            checkout_order_id: Some(checkout.order.id),
+        })
+        .await
+    {
+        Ok(invoice) => invoice,
+        Err(err) => {
+            // Without an invoice nobody can pay for the order, release its stock
+            state
+                .purchase_service
+                .cancel_checkout_order(checkout.order.id)
+                .await?;
+            return Err(err);
+        }
+    };
+
+    Ok(Json(PaymentInvoiceBotResponse::from(payment_invoice)))
 }
 
 #[utoipa::path(
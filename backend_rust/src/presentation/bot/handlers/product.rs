@@ -6,12 +6,21 @@ use axum::{
     routing::get,
 };
 use shared_dtos::{
-    error::ApiErrorResponse, list_response::ListResponse, product::ProductAdminResponse,
+    error::ApiErrorResponse,
+    list_query::{FilterValue, Operator},
+    list_response::ListResponse,
+    product::ProductAdminResponse,
 };
 
 use crate::{
-    errors::api::ApiResult, middlewares::bot_auth::AuthBot, models::product::ProductListQuery,
-    services::product::ProductServiceTrait, state::AppState,
+    errors::api::{ApiError, ApiResult},
+    middlewares::bot_auth::AuthBot,
+    models::{
+        common::Filter,
+        product::{ProductFilterFields, ProductListQuery},
+    },
+    services::product::ProductServiceTrait,
+    state::AppState,
 };
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -33,8 +42,14 @@ pub fn router() -> Router<Arc<AppState>> {
 async fn list_products(
     State(state): State<Arc<AppState>>,
     _bot: AuthBot,
-    query: ProductListQuery,
+    mut query: ProductListQuery,
 ) -> ApiResult<Json<ListResponse<ProductAdminResponse>>> {
+    // Products missing from their supplier catalog are not sold
+    query.filters.push(Filter {
+        field: ProductFilterFields::HiddenAt,
+        op: Operator::IsNull,
+        value: FilterValue::default(),
+    });
     let products = state.product_service.get_list(query).await?;
 
     Ok(Json(ListResponse {
@@ -44,6 +59,7 @@ async fn list_products(
             .into_iter()
             .map(ProductAdminResponse::from)
             .collect(),
+        next_cursor: products.next_cursor,
     }))
 }
 
@@ -54,6 +70,7 @@ async fn list_products(
     responses(
         (status = 200, description = "Product details", body = ProductAdminResponse),
         (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 404, description = "Product not found", body = ApiErrorResponse),
         (status = 500, description = "Internal server error", body = ApiErrorResponse),
     )
 )]
@@ -63,6 +80,9 @@ async fn get_product(
     _bot: AuthBot,
 ) -> ApiResult<Json<ProductAdminResponse>> {
     let product = state.product_service.get_by_id(id).await?;
+    if product.hidden_at.is_some() {
+        return Err(ApiError::NotFound("Product not found".to_string()));
+    }
 
     Ok(Json(ProductAdminResponse::from(product)))
 }
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use axum::{Json, Router, extract::State, routing::get};
 use shared_dtos::{
+    currency::ExchangeRatesResponse,
     error::ApiErrorResponse,
     settings::{SettingsBotResponse, UpdateBotManagedSettingsBotRequest},
 };
@@ -9,12 +10,17 @@ use shared_dtos::{
 use crate::{
     errors::api::ApiResult,
     middlewares::{validator::ValidatedJson, verified_service::VerifiedService},
-    services::settings::{SettingsServiceTrait, UpdateSettingsCommand},
+    services::{
+        currency::CurrencyServiceTrait,
+        settings::{SettingsServiceTrait, UpdateSettingsCommand},
+    },
     state::AppState,
 };
 
 pub fn router() -> Router<Arc<AppState>> {
-    Router::new().route("/", get(get_settings).patch(update_bot_managed_settings))
+    Router::new()
+        .route("/", get(get_settings).patch(update_bot_managed_settings))
+        .route("/exchange-rates", get(get_exchange_rates))
 }
 
 #[utoipa::path(
@@ -60,3 +66,20 @@ async fn update_bot_managed_settings(
 
     Ok(Json(SettingsBotResponse::from(settings)))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/bot/settings/exchange-rates",
+    tag = "Bot",
+    responses(
+        (status = 200, description = "Currencies customers can pick, with their rates", body = ExchangeRatesResponse),
+        (status = 401, description = "Unauthorized", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn get_exchange_rates(
+    State(state): State<Arc<AppState>>,
+    _service: VerifiedService,
+) -> ApiResult<Json<ExchangeRatesResponse>> {
+    Ok(Json(state.currency_service.get_rates().await?))
+}
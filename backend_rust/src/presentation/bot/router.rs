@@ -4,8 +4,8 @@ use axum::Router;
 
 use crate::{
     presentation::bot::handlers::{
-        bot, can_operate, captcha, category, customer, gateway, invoice, order, product, settings,
-        store_balance,
+        bot, broadcast, can_operate, captcha, category, customer, gateway, invoice, order, product,
+        settings, store_balance,
     },
     state::AppState,
 };
@@ -16,6 +16,7 @@ pub fn router() -> Router<Arc<AppState>> {
         .nest("/categories", category::router())
         .nest("/products", product::router())
         .nest("/bots", bot::router())
+        .nest("/broadcasts", broadcast::router())
         .nest("/can-operate", can_operate::router())
         .nest("/captcha", captcha::router())
         .nest("/customers", customer::router())
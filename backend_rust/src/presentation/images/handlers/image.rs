@@ -1,6 +1,6 @@
 use axum::{
     body::Body,
-    http::{HeaderMap, Response, header},
+    http::{HeaderMap, Response, StatusCode, header},
     response::IntoResponse,
 };
 use shared_dtos::error::ApiErrorResponse;
@@ -32,6 +32,7 @@ pub fn router() -> Router<Arc<AppState>> {
     security(()),
     responses(
         (status = 200, description = "Image file"),
+        (status = 304, description = "Image matches the `If-None-Match` ETag"),
         (status = 404, description = "Image not found", body = ApiErrorResponse),
         (status = 500, description = "Internal server error", body = ApiErrorResponse),
     )
@@ -39,8 +40,10 @@ pub fn router() -> Router<Arc<AppState>> {
 async fn get_image(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
+    request_headers: HeaderMap,
 ) -> ApiResult<Response<Body>> {
     let image = state.image_service.get_by_id(id).await?;
+    let etag = format!("\"{}\"", image.hash);
     let path = get_image_path(
         &image.hash,
         std::path::Path::new(&state.config.image_upload_path),
@@ -50,13 +53,28 @@ async fn get_image(
         return Err(ApiError::NotFound("Image not found".to_string()));
     }
 
+    let mut headers = HeaderMap::new();
+
+    headers.insert(
+        header::ETAG,
+        etag.parse()
+            .map_err(|_| ApiError::InternalServerError("Failed to set etag".to_string()))?,
+    );
+
+    // Clients that already have this content (e.g. the bot's Telegram file_id cache) skip the body
+    let not_modified = request_headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
     let file = File::open(&path).await.map_err(|e| {
         tracing::error!("Failed to open image file {:?}: {}", path, e);
         ApiError::InternalServerError("Failed to read image".into())
     })?;
 
-    let mut headers = HeaderMap::new();
-
     headers.insert(
         header::CONTENT_TYPE,
         image
@@ -65,13 +83,6 @@ async fn get_image(
             .map_err(|_| ApiError::InternalServerError("Failed to set content type".to_string()))?,
     );
 
-    headers.insert(
-        header::ETAG,
-        format!("\"{}\"", image.hash)
-            .parse()
-            .map_err(|_| ApiError::InternalServerError("Failed to set etag".to_string()))?,
-    );
-
     headers.insert(
         header::CACHE_CONTROL,
         "public, max-age=86400".parse().map_err(|_| {
@@ -1,6 +1,9 @@
-use axum::Json;
-#[cfg(feature = "mock-payments-provider")]
-use shared_dtos::error::ApiErrorResponse;
+use axum::{
+    Json,
+    http::{HeaderMap, StatusCode},
+};
+use bytes::Bytes;
+use shared_dtos::{error::ApiErrorResponse, invoice::PaymentSystem};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -13,17 +16,106 @@ use crate::infrastructure::external::payment::mock::{
 
 use crate::{
     errors::api::{ApiError, ApiResult},
+    infrastructure::external::payment::autosales_platform::{
+        AutosalesPlatformPaymentsProviderTrait,
+        dto::{AutosalesPlatformWebhookPayload, is_fake_appeal},
+    },
+    services::payment_processing_service::{
+        GatewayStatusUpdate, HandleWebhookEventCommand, PaymentProcessingServiceTrait,
+    },
     state::AppState,
 };
 
+const SIGNATURE_HEADER: &str = "X-Signature";
+
 pub fn router() -> Router<Arc<AppState>> {
-    let router = Router::new();
+    let router = Router::new()
+        .route("/platform-card", post(platform_card_webhook))
+        .route("/platform-sbp", post(platform_sbp_webhook));
     #[cfg(feature = "mock-payments-provider")]
     let router = router.route("/mock-provider", post(mock_payments_provider_webhook));
 
     router
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/webhook/payment/platform-card",
+    tag = "Webhook",
+    security(()),
+    params(("X-Signature" = String, Header, description = "Hex HMAC-SHA256 of the body")),
+    request_body = AutosalesPlatformWebhookPayload,
+    responses(
+        (status = 200, description = "Webhook accepted"),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Invalid signature", body = ApiErrorResponse),
+        (status = 404, description = "Invoice not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn platform_card_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<StatusCode> {
+    handle_platform_webhook(&state, PaymentSystem::PlatformCard, &headers, &body).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhook/payment/platform-sbp",
+    tag = "Webhook",
+    security(()),
+    params(("X-Signature" = String, Header, description = "Hex HMAC-SHA256 of the body")),
+    request_body = AutosalesPlatformWebhookPayload,
+    responses(
+        (status = 200, description = "Webhook accepted"),
+        (status = 400, description = "Bad request", body = ApiErrorResponse),
+        (status = 401, description = "Invalid signature", body = ApiErrorResponse),
+        (status = 404, description = "Invoice not found", body = ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = ApiErrorResponse),
+    )
+)]
+async fn platform_sbp_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<StatusCode> {
+    handle_platform_webhook(&state, PaymentSystem::PlatformSBP, &headers, &body).await
+}
+
+async fn handle_platform_webhook(
+    state: &AppState,
+    gateway: PaymentSystem,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> ApiResult<StatusCode> {
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| ApiError::AuthenticationError("Missing signature".to_string()))?;
+    let payload = state
+        .platform_payments_provider
+        .verify_webhook(body, signature)
+        .map_err(ApiError::AuthenticationError)?;
+
+    state
+        .payment_processing_service
+        .handle_webhook_event(HandleWebhookEventCommand {
+            gateway,
+            event_id: payload.event_id.clone(),
+            gateway_invoice_id: payload.object_token.clone(),
+            update: GatewayStatusUpdate {
+                status: payload.status.into(),
+                is_fraud: is_fake_appeal(payload.appeal_fake_status.as_deref()),
+            },
+            payload: serde_json::to_value(&payload)
+                .map_err(|e| ApiError::InternalServerError(e.to_string()))?,
+        })
+        .await?;
+    Ok(StatusCode::OK)
+}
+
 #[cfg(feature = "mock-payments-provider")]
 #[utoipa::path(
     post,
@@ -41,8 +133,6 @@ async fn mock_payments_provider_webhook(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<MockProviderInvoiceWebhookPayload>,
 ) -> ApiResult<Json<Uuid>> {
-    use crate::services::payment_processing_service::PaymentProcessingServiceTrait;
-
     let order_id = state
         .mock_payments_provider
         .handle_webhook(payload)
@@ -1,3 +1,5 @@
+pub mod accounting_export;
+pub mod admin_api_key;
 pub mod admin_user;
 pub mod analytics;
 pub mod audit_log;
@@ -6,20 +8,30 @@ pub mod bot;
 pub mod broadcast;
 pub mod captcha;
 pub mod category;
+pub mod currency;
 pub mod customer;
+pub mod customer_segment;
 pub mod dashboard;
+pub mod gateway_health;
+pub mod health;
+pub mod idempotency;
 pub mod image;
 pub mod notification_service;
 pub mod order;
 pub mod order_item;
+pub mod payment_gateway_settings;
 pub mod payment_invoice;
+pub mod payment_invoice_resolution;
 pub mod payment_processing_service;
 pub mod permission;
 pub mod product;
+pub mod product_sync;
 pub mod purchase;
+pub mod reconciliation;
 pub mod role;
 pub mod role_permission;
 pub mod settings;
+pub mod status_transition;
 pub mod stock_movement;
 pub mod store_balance_request;
 pub mod topt_encryptor;
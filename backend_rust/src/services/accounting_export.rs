@@ -0,0 +1,1192 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Serialize;
+use shared_dtos::{
+    accounting_export::{AccountingExportEntity, AccountingExportFormat},
+    list_query::{FilterValue, Operator, OrderDir, ScalarValue},
+};
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use uuid::Uuid;
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::{
+        accounting_export::AccountingExportRepositoryTrait, order::OrderRepositoryTrait,
+        order_item::OrderItemRepositoryTrait, payment_invoice::PaymentInvoiceRepositoryTrait,
+        stock_movement::StockMovementRepositoryTrait, transaction::TransactionRepositoryTrait,
+    },
+    models::{
+        accounting_export::{
+            AccountingExportListQuery, AccountingExportRow, CompletedAccountingExport,
+            NewAccountingExport,
+        },
+        common::{AllowedField, Cursor, CursorField, Filter, ListQuery, PaginatedResult},
+        order::{OrderFilterFields, OrderListQuery},
+        order_item::OrderItemRow,
+        payment_invoice::{PaymentInvoiceFilterFields, PaymentInvoiceListQuery},
+        stock_movement::{StockMovementFilterFields, StockMovementListQuery},
+        transaction::{TransactionFilterFields, TransactionListQuery},
+    },
+};
+
+/// Rows fetched from the database per query while an export is written
+const EXPORT_BATCH_SIZE: u32 = 1000;
+/// Exports stuck in `processing` for this long are picked up again
+const STALE_EXPORT_MINUTES: i64 = 30;
+/// Inline XLSX is built in memory inside the request, larger workbooks go to the worker
+const XLSX_SYNC_ROW_LIMIT: i64 = 2_000;
+
+const TRANSACTION_COLUMNS: &[&str] = &[
+    "id",
+    "created_at",
+    "type",
+    "customer_id",
+    "order_id",
+    "payment_invoice_id",
+    "bot_id",
+    "amount",
+    "store_balance_delta",
+    "platform_commission",
+    "gateway_commission",
+    "user_balance_after",
+    "store_balance_after",
+    "payment_gateway",
+    "description",
+];
+
+const ORDER_COLUMNS: &[&str] = &[
+    "order_id",
+    "created_at",
+    "status",
+    "customer_id",
+    "bot_id",
+    "order_amount",
+    "currency",
+    "paid_at",
+    "fulfilled_at",
+    "cancelled_at",
+    "item_id",
+    "product_id",
+    "product_name",
+    "price",
+    "quantity",
+    "item_total",
+];
+
+const PAYMENT_INVOICE_COLUMNS: &[&str] = &[
+    "id",
+    "created_at",
+    "status",
+    "customer_id",
+    "gateway",
+    "gateway_invoice_id",
+    "order_id",
+    "original_amount",
+    "amount",
+    "amount_in_usdt",
+    "expires_at",
+    "finished_at",
+];
+
+const STOCK_MOVEMENT_COLUMNS: &[&str] = &[
+    "id",
+    "created_at",
+    "type",
+    "product_id",
+    "product_name",
+    "quantity",
+    "balance_after",
+    "order_id",
+    "created_by",
+    "reference_id",
+    "description",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportCell {
+    Empty,
+    Int(i64),
+    Number(Decimal),
+    Text(String),
+    DateTime(DateTime<Utc>),
+}
+
+impl ExportCell {
+    fn text(&self) -> String {
+        match self {
+            ExportCell::Empty => String::new(),
+            ExportCell::Int(value) => value.to_string(),
+            ExportCell::Number(value) => value.to_string(),
+            ExportCell::Text(value) => value.clone(),
+            ExportCell::DateTime(value) => value.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+impl From<i64> for ExportCell {
+    fn from(value: i64) -> Self {
+        ExportCell::Int(value)
+    }
+}
+
+impl From<Decimal> for ExportCell {
+    fn from(value: Decimal) -> Self {
+        ExportCell::Number(value)
+    }
+}
+
+impl From<String> for ExportCell {
+    fn from(value: String) -> Self {
+        ExportCell::Text(value)
+    }
+}
+
+impl From<DateTime<Utc>> for ExportCell {
+    fn from(value: DateTime<Utc>) -> Self {
+        ExportCell::DateTime(value)
+    }
+}
+
+impl<T: Into<ExportCell>> From<Option<T>> for ExportCell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(ExportCell::Empty, Into::into)
+    }
+}
+
+// Enums are exported with the same snake_case names the API uses
+fn enum_cell<T: Serialize>(value: &T) -> ExportCell {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => ExportCell::Text(text),
+        _ => ExportCell::Empty,
+    }
+}
+
+/// Destination of export rows, written batch by batch
+#[async_trait]
+pub trait ExportSink: Send {
+    async fn write_header(&mut self, columns: &[&str]) -> ApiResult<()>;
+    async fn write_rows(&mut self, rows: Vec<Vec<ExportCell>>) -> ApiResult<()>;
+}
+
+pub struct CsvSink<W> {
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin + Send> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    async fn write_records<I>(&mut self, records: I) -> ApiResult<()>
+    where
+        I: IntoIterator<Item = Vec<String>>,
+    {
+        let mut csv = csv::Writer::from_writer(Vec::new());
+        for record in records {
+            csv.write_record(&record)
+                .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        }
+        let bytes = csv
+            .into_inner()
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        self.writer
+            .write_all(&bytes)
+            .await
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))
+    }
+
+    /// Flushes and closes the writer, handing it back
+    pub async fn finish(mut self) -> ApiResult<W> {
+        self.writer
+            .shutdown()
+            .await
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        Ok(self.writer)
+    }
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> ExportSink for CsvSink<W> {
+    async fn write_header(&mut self, columns: &[&str]) -> ApiResult<()> {
+        self.write_records([columns.iter().map(|c| c.to_string()).collect()])
+            .await
+    }
+
+    async fn write_rows(&mut self, rows: Vec<Vec<ExportCell>>) -> ApiResult<()> {
+        self.write_records(
+            rows.into_iter()
+                .map(|row| row.iter().map(ExportCell::text).collect()),
+        )
+        .await
+    }
+}
+
+/// XLSX files can't be streamed. The default workbook is built in memory, a
+/// `constant_memory` one keeps only the current row and is saved to disk.
+pub struct XlsxSink {
+    workbook: Workbook,
+    next_row: u32,
+}
+
+impl Default for XlsxSink {
+    fn default() -> Self {
+        let mut workbook = Workbook::new();
+        workbook.add_worksheet();
+        Self {
+            workbook,
+            next_row: 0,
+        }
+    }
+}
+
+impl XlsxSink {
+    pub fn constant_memory() -> Self {
+        let mut workbook = Workbook::new();
+        workbook.add_worksheet_with_constant_memory();
+        Self {
+            workbook,
+            next_row: 0,
+        }
+    }
+
+    pub fn into_bytes(mut self) -> ApiResult<Vec<u8>> {
+        self.workbook
+            .save_to_buffer()
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))
+    }
+
+    pub fn save(mut self, path: &Path) -> ApiResult<()> {
+        self.workbook
+            .save(path)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl ExportSink for XlsxSink {
+    async fn write_header(&mut self, columns: &[&str]) -> ApiResult<()> {
+        let bold = Format::new().set_bold();
+        let sheet = self
+            .workbook
+            .worksheet_from_index(0)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        for (col, name) in columns.iter().enumerate() {
+            sheet
+                .write_string_with_format(self.next_row, col as u16, *name, &bold)
+                .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        }
+        self.next_row += 1;
+        Ok(())
+    }
+
+    async fn write_rows(&mut self, rows: Vec<Vec<ExportCell>>) -> ApiResult<()> {
+        let sheet = self
+            .workbook
+            .worksheet_from_index(0)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        for row in rows {
+            for (col, cell) in row.into_iter().enumerate() {
+                let col = col as u16;
+                let result = match cell {
+                    ExportCell::Empty => continue,
+                    ExportCell::Int(value) => sheet.write_number(self.next_row, col, value as f64),
+                    ExportCell::Number(value) => {
+                        sheet.write_number(self.next_row, col, value.to_f64().unwrap_or_default())
+                    }
+                    cell @ (ExportCell::Text(_) | ExportCell::DateTime(_)) => {
+                        sheet.write_string(self.next_row, col, cell.text())
+                    }
+                };
+                result.map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+            }
+            self.next_row += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Validated list query of one of the exportable tables, walked by `id` in batches
+#[derive(Debug, Clone)]
+pub enum ExportQuery {
+    Transactions(TransactionListQuery),
+    Orders(OrderListQuery),
+    PaymentInvoices(PaymentInvoiceListQuery),
+    StockMovements(StockMovementListQuery),
+}
+
+impl ExportQuery {
+    /// Parses the list filters from `raw_query` and limits them to `[from, to)`
+    pub fn parse(
+        entity: AccountingExportEntity,
+        raw_query: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> ApiResult<Self> {
+        if from >= to {
+            return Err(ApiError::BadRequest(
+                "Export range start must be before its end".to_string(),
+            ));
+        }
+
+        Ok(match entity {
+            AccountingExportEntity::Transactions => Self::Transactions(batched(
+                ListQuery::from_query_str(raw_query)?,
+                TransactionFilterFields::CreatedAt,
+                from,
+                to,
+            )),
+            AccountingExportEntity::Orders => Self::Orders(batched(
+                ListQuery::from_query_str(raw_query)?,
+                OrderFilterFields::CreatedAt,
+                from,
+                to,
+            )),
+            AccountingExportEntity::PaymentInvoices => Self::PaymentInvoices(batched(
+                ListQuery::from_query_str(raw_query)?,
+                PaymentInvoiceFilterFields::CreatedAt,
+                from,
+                to,
+            )),
+            AccountingExportEntity::StockMovements => Self::StockMovements(batched(
+                ListQuery::from_query_str(raw_query)?,
+                StockMovementFilterFields::CreatedAt,
+                from,
+                to,
+            )),
+        })
+    }
+
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            ExportQuery::Transactions(_) => TRANSACTION_COLUMNS,
+            ExportQuery::Orders(_) => ORDER_COLUMNS,
+            ExportQuery::PaymentInvoices(_) => PAYMENT_INVOICE_COLUMNS,
+            ExportQuery::StockMovements(_) => STOCK_MOVEMENT_COLUMNS,
+        }
+    }
+}
+
+fn batched<F: AllowedField, O: AllowedField + CursorField>(
+    mut query: ListQuery<F, O>,
+    created_at: F,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> ListQuery<F, O> {
+    query.filters.push(Filter {
+        field: created_at.clone(),
+        op: Operator::Ge,
+        value: FilterValue::Scalar(ScalarValue::DateTime(from)),
+    });
+    query.filters.push(Filter {
+        field: created_at,
+        op: Operator::Lt,
+        value: FilterValue::Scalar(ScalarValue::DateTime(to)),
+    });
+    // Exports are always ordered by id, so keyset pagination can walk the whole range
+    query.order_by = None;
+    query.order_dir = OrderDir::Asc;
+    query.cursor = Some(Cursor::default());
+    query.pagination.page = 1;
+    query.pagination.page_size = EXPORT_BATCH_SIZE;
+    query
+}
+
+fn next_page<F: AllowedField, O: AllowedField>(
+    query: &mut ListQuery<F, O>,
+    next_cursor: Option<String>,
+    last_id: Option<i64>,
+) -> bool {
+    match (next_cursor, last_id) {
+        (Some(_), Some(id)) => {
+            query.cursor = Some(Cursor { after: Some(id) });
+            true
+        }
+        _ => false,
+    }
+}
+
+pub fn export_file_name(
+    entity: AccountingExportEntity,
+    format: AccountingExportFormat,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> String {
+    format!(
+        "{}_{}_{}.{}",
+        enum_cell(&entity).text(),
+        from.format("%Y%m%d"),
+        to.format("%Y%m%d"),
+        file_extension(format)
+    )
+}
+
+pub fn file_extension(format: AccountingExportFormat) -> &'static str {
+    match format {
+        AccountingExportFormat::Csv => "csv",
+        AccountingExportFormat::Xlsx => "xlsx",
+    }
+}
+
+pub fn content_type(format: AccountingExportFormat) -> &'static str {
+    match format {
+        AccountingExportFormat::Csv => "text/csv; charset=utf-8",
+        AccountingExportFormat::Xlsx => {
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateAccountingExportCommand {
+    pub entity: AccountingExportEntity,
+    pub format: AccountingExportFormat,
+    /// Raw query string with the list filters
+    pub query: String,
+    pub date_from: DateTime<Utc>,
+    pub date_to: DateTime<Utc>,
+    pub created_by: i64,
+}
+
+#[derive(Debug)]
+pub enum AccountingExportStart {
+    /// Small enough to be written straight into the response
+    Stream(ExportQuery),
+    /// Queued for the background worker
+    Queued(AccountingExportRow),
+}
+
+#[async_trait]
+pub trait AccountingExportServiceTrait: Send + Sync {
+    async fn get_list(
+        &self,
+        query: AccountingExportListQuery,
+    ) -> ApiResult<PaginatedResult<AccountingExportRow>>;
+    async fn get_by_id(&self, id: i64) -> ApiResult<AccountingExportRow>;
+    async fn start(
+        &self,
+        command: CreateAccountingExportCommand,
+    ) -> ApiResult<AccountingExportStart>;
+    /// Writes the header and every row matching `query`, returns the number of rows
+    async fn write(&self, query: ExportQuery, sink: &mut dyn ExportSink) -> ApiResult<i64>;
+    /// Builds the oldest queued export, returns `None` when the queue is empty
+    async fn process_next(&self) -> ApiResult<Option<AccountingExportRow>>;
+    /// Completed export and its opened file for a download token
+    async fn get_download(&self, token: Uuid) -> ApiResult<(AccountingExportRow, tokio::fs::File)>;
+    async fn delete_expired(&self) -> ApiResult<u64>;
+}
+
+pub struct AccountingExportService<R, T, O, OI, P, S> {
+    repo: Arc<R>,
+    transaction_repo: Arc<T>,
+    order_repo: Arc<O>,
+    order_item_repo: Arc<OI>,
+    payment_invoice_repo: Arc<P>,
+    stock_movement_repo: Arc<S>,
+    ttl: Duration,
+    sync_row_limit: i64,
+    /// Where background export files are written, see `ACCOUNTING_EXPORT_PATH`
+    export_dir: PathBuf,
+}
+
+impl<R, T, O, OI, P, S> AccountingExportService<R, T, O, OI, P, S>
+where
+    R: AccountingExportRepositoryTrait + Send + Sync,
+    T: TransactionRepositoryTrait + Send + Sync,
+    O: OrderRepositoryTrait + Send + Sync,
+    OI: OrderItemRepositoryTrait + Send + Sync,
+    P: PaymentInvoiceRepositoryTrait + Send + Sync,
+    S: StockMovementRepositoryTrait + Send + Sync,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        repo: Arc<R>,
+        transaction_repo: Arc<T>,
+        order_repo: Arc<O>,
+        order_item_repo: Arc<OI>,
+        payment_invoice_repo: Arc<P>,
+        stock_movement_repo: Arc<S>,
+        ttl: Duration,
+        sync_row_limit: i64,
+        export_dir: PathBuf,
+    ) -> Self {
+        Self {
+            repo,
+            transaction_repo,
+            order_repo,
+            order_item_repo,
+            payment_invoice_repo,
+            stock_movement_repo,
+            ttl,
+            sync_row_limit,
+            export_dir,
+        }
+    }
+
+    async fn count(&self, query: &ExportQuery) -> ApiResult<i64> {
+        // A single-row page is enough to get the total
+        Ok(match query {
+            ExportQuery::Transactions(query) => {
+                let mut query = query.clone();
+                query.pagination.page_size = 1;
+                self.transaction_repo.get_list(query).await?.total
+            }
+            ExportQuery::Orders(query) => {
+                let mut query = query.clone();
+                query.pagination.page_size = 1;
+                self.order_repo.get_list(query).await?.total
+            }
+            ExportQuery::PaymentInvoices(query) => {
+                let mut query = query.clone();
+                query.pagination.page_size = 1;
+                self.payment_invoice_repo.get_list(query).await?.total
+            }
+            ExportQuery::StockMovements(query) => {
+                let mut query = query.clone();
+                query.pagination.page_size = 1;
+                self.stock_movement_repo.get_list(query).await?.total
+            }
+        })
+    }
+
+    /// Writes the export file under `export_dir`, returns its file name and rows count.
+    /// A partly written file is removed when the export fails.
+    async fn build_file(&self, export: &AccountingExportRow) -> ApiResult<(String, i64)> {
+        let query = ExportQuery::parse(
+            export.entity,
+            &export.query,
+            export.date_from,
+            export.date_to,
+        )?;
+        let file_name = format!("{}.{}", export.id, file_extension(export.format));
+        tokio::fs::create_dir_all(&self.export_dir)
+            .await
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        let path = self.export_dir.join(&file_name);
+
+        let result = self.write_file(query, export.format, &path).await;
+        if result.is_err() {
+            self.remove_file(&file_name).await;
+        }
+        Ok((file_name, result?))
+    }
+
+    async fn write_file(
+        &self,
+        query: ExportQuery,
+        format: AccountingExportFormat,
+        path: &Path,
+    ) -> ApiResult<i64> {
+        match format {
+            AccountingExportFormat::Csv => {
+                let file = tokio::fs::File::create(path)
+                    .await
+                    .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+                let mut sink = CsvSink::new(BufWriter::new(file));
+                let rows_count = self.write(query, &mut sink).await?;
+                sink.finish().await?;
+                Ok(rows_count)
+            }
+            AccountingExportFormat::Xlsx => {
+                let mut sink = XlsxSink::constant_memory();
+                let rows_count = self.write(query, &mut sink).await?;
+                sink.save(path)?;
+                Ok(rows_count)
+            }
+        }
+    }
+
+    async fn remove_file(&self, file_name: &str) {
+        match tokio::fs::remove_file(self.export_dir.join(file_name)).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::error!("Failed to remove export file {file_name}: {e}"),
+        }
+    }
+}
+
+#[async_trait]
+impl<R, T, O, OI, P, S> AccountingExportServiceTrait for AccountingExportService<R, T, O, OI, P, S>
+where
+    R: AccountingExportRepositoryTrait + Send + Sync,
+    T: TransactionRepositoryTrait + Send + Sync,
+    O: OrderRepositoryTrait + Send + Sync,
+    OI: OrderItemRepositoryTrait + Send + Sync,
+    P: PaymentInvoiceRepositoryTrait + Send + Sync,
+    S: StockMovementRepositoryTrait + Send + Sync,
+{
+    async fn get_list(
+        &self,
+        query: AccountingExportListQuery,
+    ) -> ApiResult<PaginatedResult<AccountingExportRow>> {
+        Ok(self.repo.get_list(query).await?)
+    }
+
+    async fn get_by_id(&self, id: i64) -> ApiResult<AccountingExportRow> {
+        Ok(self.repo.get_by_id(id).await?)
+    }
+
+    async fn start(
+        &self,
+        command: CreateAccountingExportCommand,
+    ) -> ApiResult<AccountingExportStart> {
+        let query = ExportQuery::parse(
+            command.entity,
+            &command.query,
+            command.date_from,
+            command.date_to,
+        )?;
+        let sync_row_limit = match command.format {
+            AccountingExportFormat::Csv => self.sync_row_limit,
+            AccountingExportFormat::Xlsx => self.sync_row_limit.min(XLSX_SYNC_ROW_LIMIT),
+        };
+        if self.count(&query).await? <= sync_row_limit {
+            return Ok(AccountingExportStart::Stream(query));
+        }
+
+        let export = self
+            .repo
+            .create(NewAccountingExport {
+                entity: command.entity,
+                format: command.format,
+                query: command.query,
+                date_from: command.date_from,
+                date_to: command.date_to,
+                created_by: command.created_by,
+            })
+            .await?;
+        Ok(AccountingExportStart::Queued(export))
+    }
+
+    async fn write(&self, query: ExportQuery, sink: &mut dyn ExportSink) -> ApiResult<i64> {
+        sink.write_header(query.columns()).await?;
+        let mut rows_count = 0;
+
+        match query {
+            ExportQuery::Transactions(mut query) => loop {
+                let page = self.transaction_repo.get_list(query.clone()).await?;
+                rows_count += page.items.len() as i64;
+                let last_id = page.items.last().map(|row| row.id);
+                let rows = page
+                    .items
+                    .into_iter()
+                    .map(|row| {
+                        vec![
+                            row.id.into(),
+                            row.created_at.into(),
+                            enum_cell(&row.r#type),
+                            row.customer_id.into(),
+                            row.order_id.into(),
+                            row.payment_invoice_id.into(),
+                            row.bot_id.into(),
+                            row.amount.into(),
+                            row.store_balance_delta.into(),
+                            row.platform_commission.into(),
+                            row.gateway_commission.into(),
+                            row.user_balance_after.into(),
+                            row.store_balance_after.into(),
+                            row.payment_gateway
+                                .as_ref()
+                                .map_or(ExportCell::Empty, enum_cell),
+                            row.description.into(),
+                        ]
+                    })
+                    .collect();
+                sink.write_rows(rows).await?;
+                if !next_page(&mut query, page.next_cursor, last_id) {
+                    break;
+                }
+            },
+            ExportQuery::Orders(mut query) => loop {
+                let page = self.order_repo.get_list(query.clone()).await?;
+                let last_id = page.items.last().map(|order| order.id);
+                let mut items_by_order_id: HashMap<i64, Vec<OrderItemRow>> = HashMap::new();
+                for item in self
+                    .order_item_repo
+                    .get_for_orders(page.items.iter().map(|order| order.id).collect())
+                    .await?
+                {
+                    items_by_order_id
+                        .entry(item.order_id)
+                        .or_default()
+                        .push(item);
+                }
+                let mut rows = Vec::new();
+                for order in page.items {
+                    let order_items = items_by_order_id.remove(&order.id).unwrap_or_default();
+                    let order_cells: Vec<ExportCell> = vec![
+                        order.id.into(),
+                        order.created_at.into(),
+                        enum_cell(&order.status),
+                        order.customer_id.into(),
+                        order.bot_id.into(),
+                        order.amount.into(),
+                        order.currency.into(),
+                        order.paid_at.into(),
+                        order.fulfilled_at.into(),
+                        order.cancelled_at.into(),
+                    ];
+                    if order_items.is_empty() {
+                        let mut row = order_cells;
+                        row.resize(ORDER_COLUMNS.len(), ExportCell::Empty);
+                        rows.push(row);
+                        continue;
+                    }
+                    for item in order_items {
+                        let mut row = order_cells.clone();
+                        row.extend([
+                            item.id.into(),
+                            item.product_id.into(),
+                            item.name_at_purchase.into(),
+                            item.price_at_purchase.into(),
+                            i64::from(item.quantity).into(),
+                            (item.price_at_purchase * Decimal::from(item.quantity)).into(),
+                        ]);
+                        rows.push(row);
+                    }
+                }
+                rows_count += rows.len() as i64;
+                sink.write_rows(rows).await?;
+                if !next_page(&mut query, page.next_cursor, last_id) {
+                    break;
+                }
+            },
+            ExportQuery::PaymentInvoices(mut query) => loop {
+                let page = self.payment_invoice_repo.get_list(query.clone()).await?;
+                rows_count += page.items.len() as i64;
+                let last_id = page.items.last().map(|row| row.id);
+                let rows = page
+                    .items
+                    .into_iter()
+                    .map(|row| {
+                        vec![
+                            row.id.into(),
+                            row.created_at.into(),
+                            enum_cell(&row.status),
+                            row.customer_id.into(),
+                            enum_cell(&row.gateway),
+                            row.gateway_invoice_id.into(),
+                            row.order_id.to_string().into(),
+                            row.original_amount.into(),
+                            row.amount.into(),
+                            row.amount_in_usdt.into(),
+                            row.expires_at.into(),
+                            row.finished_at.into(),
+                        ]
+                    })
+                    .collect();
+                sink.write_rows(rows).await?;
+                if !next_page(&mut query, page.next_cursor, last_id) {
+                    break;
+                }
+            },
+            ExportQuery::StockMovements(mut query) => loop {
+                let page = self.stock_movement_repo.get_list(query.clone()).await?;
+                rows_count += page.items.len() as i64;
+                let last_id = page.items.last().map(|row| row.id);
+                let rows = page
+                    .items
+                    .into_iter()
+                    .map(|row| {
+                        vec![
+                            row.id.into(),
+                            row.created_at.into(),
+                            enum_cell(&row.r#type),
+                            row.product_id.into(),
+                            row.product_name.into(),
+                            row.quantity.into(),
+                            row.balance_after.into(),
+                            row.order_id.into(),
+                            row.created_by.into(),
+                            row.reference_id.into(),
+                            row.description.into(),
+                        ]
+                    })
+                    .collect();
+                sink.write_rows(rows).await?;
+                if !next_page(&mut query, page.next_cursor, last_id) {
+                    break;
+                }
+            },
+        }
+
+        Ok(rows_count)
+    }
+
+    async fn process_next(&self) -> ApiResult<Option<AccountingExportRow>> {
+        let stale_before = Utc::now() - Duration::minutes(STALE_EXPORT_MINUTES);
+        let Some(export) = self.repo.claim_next(stale_before).await? else {
+            return Ok(None);
+        };
+
+        let expires_at = Utc::now() + self.ttl;
+        let export = match self.build_file(&export).await {
+            Ok((file_name, rows_count)) => {
+                self.repo
+                    .complete(
+                        export.id,
+                        CompletedAccountingExport {
+                            rows_count,
+                            file_name,
+                            download_token: Uuid::new_v4(),
+                            expires_at,
+                        },
+                    )
+                    .await?
+            }
+            Err(e) => {
+                tracing::error!("Accounting export {} failed: {e}", export.id);
+                self.repo
+                    .fail(export.id, &e.to_string(), expires_at)
+                    .await?
+            }
+        };
+        Ok(Some(export))
+    }
+
+    async fn get_download(&self, token: Uuid) -> ApiResult<(AccountingExportRow, tokio::fs::File)> {
+        let export = self
+            .repo
+            .get_by_download_token(token)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Export not found or expired".to_string()))?;
+        let file_name = export.file_name.as_deref().ok_or_else(|| {
+            ApiError::InternalServerError("Completed export has no file".to_string())
+        })?;
+        let file = tokio::fs::File::open(self.export_dir.join(file_name))
+            .await
+            .map_err(|e| {
+                ApiError::InternalServerError(format!("Failed to open export file: {e}"))
+            })?;
+        Ok((export, file))
+    }
+
+    async fn delete_expired(&self) -> ApiResult<u64> {
+        let deleted = self.repo.delete_expired().await?;
+        for file_name in deleted
+            .iter()
+            .filter_map(|export| export.file_name.as_deref())
+        {
+            self.remove_file(file_name).await;
+        }
+        Ok(deleted.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::infrastructure::repositories::{
+        accounting_export::AccountingExportRepository, order::OrderRepository,
+        order_item::OrderItemRepository, payment_invoice::PaymentInvoiceRepository,
+        stock_movement::StockMovementRepository, transaction::TransactionRepository,
+    };
+
+    type TestService = AccountingExportService<
+        AccountingExportRepository,
+        TransactionRepository,
+        OrderRepository,
+        OrderItemRepository,
+        PaymentInvoiceRepository,
+        StockMovementRepository,
+    >;
+
+    fn build_service(pool: &PgPool, sync_row_limit: i64) -> TestService {
+        let export_dir =
+            std::env::temp_dir().join(format!("accounting_exports_{}", Uuid::new_v4()));
+        let pool = Arc::new(pool.clone());
+        AccountingExportService::new(
+            Arc::new(AccountingExportRepository::new(pool.clone())),
+            Arc::new(TransactionRepository::new(pool.clone())),
+            Arc::new(OrderRepository::new(pool.clone())),
+            Arc::new(OrderItemRepository::new(pool.clone())),
+            Arc::new(PaymentInvoiceRepository::new(pool.clone())),
+            Arc::new(StockMovementRepository::new(pool)),
+            Duration::hours(1),
+            sync_row_limit,
+            export_dir,
+        )
+    }
+
+    async fn create_customer(pool: &PgPool, telegram_id: i64) -> i64 {
+        sqlx::query_scalar!(
+            "INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot) VALUES ($1, 1, 1) RETURNING id",
+            telegram_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn create_transaction(pool: &PgPool, customer_id: i64, r#type: &str, amount: i64) {
+        sqlx::query!(
+            r#"
+            INSERT INTO transactions (
+                customer_id, type, amount, store_balance_delta, platform_commission,
+                gateway_commission
+            )
+            VALUES ($1, $2, $3, $3, 0, 0)
+            "#,
+            customer_id,
+            r#type,
+            Decimal::from(amount)
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn command(
+        entity: AccountingExportEntity,
+        query: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> CreateAccountingExportCommand {
+        CreateAccountingExportCommand {
+            entity,
+            format: AccountingExportFormat::Csv,
+            query: query.to_string(),
+            date_from: from,
+            date_to: to,
+            created_by: 1,
+        }
+    }
+
+    async fn write_csv(service: &TestService, query: ExportQuery) -> (i64, Vec<Vec<String>>) {
+        let mut buffer = Vec::new();
+        let mut sink = CsvSink::new(&mut buffer);
+        let rows_count = service.write(query, &mut sink).await.unwrap();
+        sink.finish().await.unwrap();
+        let records = csv::Reader::from_reader(buffer.as_slice())
+            .records()
+            .map(|record| record.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        (rows_count, records)
+    }
+
+    #[sqlx::test]
+    async fn test_small_export_is_streamed_with_filters_and_range(pool: PgPool) {
+        let service = build_service(&pool, 100);
+        let customer_id = create_customer(&pool, 9101).await;
+        create_transaction(&pool, customer_id, "deposit", 100).await;
+        create_transaction(&pool, customer_id, "deposit", 50).await;
+        create_transaction(&pool, customer_id, "purchase", -30).await;
+        sqlx::query!(
+            "UPDATE transactions SET created_at = NOW() - INTERVAL '10 days' WHERE amount = 50"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let start = service
+            .start(command(
+                AccountingExportEntity::Transactions,
+                "format=csv&filters[0][field]=type&filters[0][op]=eq&filters[0][value]=deposit",
+                Utc::now() - Duration::days(1),
+                Utc::now() + Duration::minutes(1),
+            ))
+            .await
+            .unwrap();
+        let AccountingExportStart::Stream(query) = start else {
+            panic!("small export must be streamed");
+        };
+
+        let (rows_count, records) = write_csv(&service, query).await;
+        assert_eq!(rows_count, 1);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0][2], "deposit");
+        assert_eq!(records[0][7], "100.00");
+    }
+
+    #[sqlx::test]
+    async fn test_orders_export_has_row_per_item(pool: PgPool) {
+        let service = build_service(&pool, 100);
+        let customer_id = create_customer(&pool, 9102).await;
+        let product_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO products (name, base_price, type, created_by, provider_name)
+            VALUES ('export_product', 10.0, 'item', 1, 'test')
+            RETURNING id
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let bot_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO bots (owner_id, token, username, type, is_active, is_primary, referral_percentage)
+            VALUES ($1, 'export_token', 'export_bot', 'main', true, false, 0.0)
+            RETURNING id
+            "#,
+            customer_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let order_ids = sqlx::query_scalar!(
+            r#"
+            INSERT INTO orders (customer_id, amount, currency, status, bot_id)
+            VALUES ($1, 30.00, 'RUB', 'fulfilled', $2), ($1, 0.00, 'RUB', 'created', $2)
+            RETURNING id
+            "#,
+            customer_id,
+            bot_id
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO order_items (order_id, product_id, name_at_purchase, price_at_purchase, quantity)
+            VALUES ($1, $2, 'first', 10.00, 1), ($1, $2, 'second', 10.00, 2)
+            "#,
+            order_ids[0],
+            product_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let query = ExportQuery::parse(
+            AccountingExportEntity::Orders,
+            "",
+            Utc::now() - Duration::days(1),
+            Utc::now() + Duration::minutes(1),
+        )
+        .unwrap();
+        let (rows_count, records) = write_csv(&service, query).await;
+
+        assert_eq!(rows_count, 3);
+        let names: Vec<&str> = records.iter().map(|record| record[12].as_str()).collect();
+        assert_eq!(names, vec!["first", "second", ""]);
+        assert_eq!(records[1][15], "20.00");
+    }
+
+    #[sqlx::test]
+    async fn test_large_export_is_queued_and_built_by_worker(pool: PgPool) {
+        let service = build_service(&pool, 0);
+        let customer_id = create_customer(&pool, 9103).await;
+        create_transaction(&pool, customer_id, "deposit", 100).await;
+
+        let start = service
+            .start(command(
+                AccountingExportEntity::Transactions,
+                "",
+                Utc::now() - Duration::days(1),
+                Utc::now() + Duration::minutes(1),
+            ))
+            .await
+            .unwrap();
+        let AccountingExportStart::Queued(export) = start else {
+            panic!("export above the sync limit must be queued");
+        };
+
+        let processed = service.process_next().await.unwrap().unwrap();
+        assert_eq!(processed.id, export.id);
+        assert_eq!(
+            processed.status,
+            shared_dtos::accounting_export::AccountingExportStatus::Completed
+        );
+        assert_eq!(processed.rows_count, Some(1));
+        assert!(service.process_next().await.unwrap().is_none());
+
+        let (_, mut file) = service
+            .get_download(processed.download_token.unwrap())
+            .await
+            .unwrap();
+        let mut contents = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut file, &mut contents)
+            .await
+            .unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.starts_with("id,created_at,type"));
+
+        // Expired exports take their files with them
+        sqlx::query!(
+            "UPDATE accounting_exports SET expires_at = NOW() WHERE id = $1",
+            export.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let path = service
+            .export_dir
+            .join(processed.file_name.as_deref().unwrap());
+        assert!(path.exists());
+        assert_eq!(service.delete_expired().await.unwrap(), 1);
+        assert!(!path.exists());
+    }
+
+    #[sqlx::test]
+    async fn test_large_xlsx_export_is_queued_below_sync_limit(pool: PgPool) {
+        let service = build_service(&pool, 10_000);
+        let customer_id = create_customer(&pool, 9104).await;
+        sqlx::query!(
+            r#"
+            INSERT INTO transactions (
+                customer_id, type, amount, store_balance_delta, platform_commission,
+                gateway_commission
+            )
+            SELECT $1, 'deposit', 1, 1, 0, 0 FROM generate_series(1, $2::INT)
+            "#,
+            customer_id,
+            XLSX_SYNC_ROW_LIMIT as i32 + 1
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let range = (
+            Utc::now() - Duration::days(1),
+            Utc::now() + Duration::minutes(1),
+        );
+
+        let csv = service
+            .start(command(
+                AccountingExportEntity::Transactions,
+                "",
+                range.0,
+                range.1,
+            ))
+            .await
+            .unwrap();
+        assert!(matches!(csv, AccountingExportStart::Stream(_)));
+
+        let xlsx = service
+            .start(CreateAccountingExportCommand {
+                format: AccountingExportFormat::Xlsx,
+                ..command(AccountingExportEntity::Transactions, "", range.0, range.1)
+            })
+            .await
+            .unwrap();
+        let AccountingExportStart::Queued(export) = xlsx else {
+            panic!("large XLSX export must be queued");
+        };
+
+        let processed = service.process_next().await.unwrap().unwrap();
+        assert_eq!(processed.id, export.id);
+        assert_eq!(processed.rows_count, Some(XLSX_SYNC_ROW_LIMIT + 1));
+        let content = std::fs::read(service.export_dir.join(processed.file_name.unwrap())).unwrap();
+        assert!(content.starts_with(b"PK"));
+    }
+
+    #[sqlx::test]
+    async fn test_invalid_range_is_rejected(pool: PgPool) {
+        let service = build_service(&pool, 100);
+        let now = Utc::now();
+
+        let result = service
+            .start(command(AccountingExportEntity::Orders, "", now, now))
+            .await;
+
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+}
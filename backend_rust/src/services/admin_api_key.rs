@@ -0,0 +1,356 @@
+use std::{net::IpAddr, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::distr::{Alphanumeric, SampleString};
+use shared_dtos::audit_log::{AuditAction, AuditStatus};
+use sqlx::types::ipnetwork::IpNetwork;
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::{
+        admin_api_key::{AdminApiKeyRepository, AdminApiKeyRepositoryTrait},
+        audit_log::AuditLogRepository,
+        effective_permission::{EffectivePermissionRepository, EffectivePermissionRepositoryTrait},
+    },
+    middlewares::context::RequestContext,
+    models::{
+        admin_api_key::{AdminApiKeyRow, NewAdminApiKey},
+        audit_log::NewAuditLog,
+        permission::Permission,
+    },
+    services::audit_log::{AuditLogService, AuditLogServiceTrait},
+};
+
+pub const API_KEY_HEADER: &str = "X-ADMIN-API-KEY";
+const API_KEY_PREFIX: &str = "ak_";
+const API_KEY_RANDOM_LEN: usize = 40;
+
+#[derive(Debug, Clone)]
+pub struct AuthApiKey {
+    pub id: i64,
+    pub created_by: i64,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CreateApiKeyCommand {
+    pub name: String,
+    pub permissions: Vec<String>,
+    pub allowed_ips: Vec<IpNetwork>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: i64,
+    pub ctx: Option<RequestContext>,
+}
+
+#[derive(Debug)]
+pub struct RevokeApiKeyCommand {
+    pub id: i64,
+    pub revoked_by: i64,
+    pub ctx: Option<RequestContext>,
+}
+
+#[derive(Debug)]
+pub struct CreatedApiKey {
+    pub key: String,
+    pub api_key: AdminApiKeyRow,
+}
+
+#[async_trait]
+pub trait AdminApiKeyServiceTrait: Send + Sync {
+    async fn get_list(&self) -> ApiResult<Vec<AdminApiKeyRow>>;
+    async fn create(&self, command: CreateApiKeyCommand) -> ApiResult<CreatedApiKey>;
+    async fn revoke(&self, command: RevokeApiKeyCommand) -> ApiResult<AdminApiKeyRow>;
+    async fn authenticate(&self, key: &str, ip: Option<IpAddr>) -> ApiResult<AuthApiKey>;
+    async fn has_permission(&self, key: &AuthApiKey, permission: Permission) -> ApiResult<bool>;
+}
+
+pub struct AdminApiKeyService<R, E, A> {
+    repo: Arc<R>,
+    effective_permission_repo: Arc<E>,
+    audit_log_service: Arc<A>,
+}
+
+impl<R, E, A> AdminApiKeyService<R, E, A>
+where
+    R: AdminApiKeyRepositoryTrait + Send + Sync,
+    E: EffectivePermissionRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    pub fn new(repo: Arc<R>, effective_permission_repo: Arc<E>, audit_log_service: Arc<A>) -> Self {
+        Self {
+            repo,
+            effective_permission_repo,
+            audit_log_service,
+        }
+    }
+}
+
+#[async_trait]
+impl AdminApiKeyServiceTrait
+    for AdminApiKeyService<
+        AdminApiKeyRepository,
+        EffectivePermissionRepository,
+        AuditLogService<AuditLogRepository>,
+    >
+{
+    async fn get_list(&self) -> ApiResult<Vec<AdminApiKeyRow>> {
+        self.repo.get_list().await.map_err(ApiError::from)
+    }
+
+    async fn create(&self, command: CreateApiKeyCommand) -> ApiResult<CreatedApiKey> {
+        if command.expires_at.is_some_and(|at| at <= Utc::now()) {
+            return Err(ApiError::BadRequest(
+                "Expiration must be in the future".to_string(),
+            ));
+        }
+
+        // A key can never grant more than its creator currently has
+        let granted = self
+            .effective_permission_repo
+            .get_for_user(command.created_by)
+            .await?;
+        let mut permissions = command.permissions;
+        permissions.sort();
+        permissions.dedup();
+        if let Some(missing) = permissions.iter().find(|p| !granted.contains(p)) {
+            return Err(ApiError::BadRequest(format!(
+                "Permission {missing} is not granted to you"
+            )));
+        }
+
+        let key = generate_api_key();
+        let created = self
+            .repo
+            .create(NewAdminApiKey {
+                name: command.name,
+                key_prefix: key[..API_KEY_PREFIX.len() + 6].to_string(),
+                key_hash: hash_api_key(&key),
+                permissions,
+                allowed_ips: command.allowed_ips,
+                expires_at: command.expires_at,
+                created_by: command.created_by,
+            })
+            .await?;
+
+        self.audit_log_service
+            .create(NewAuditLog {
+                action: AuditAction::ApiKeyCreate,
+                status: AuditStatus::Success,
+                admin_user_id: Some(command.created_by),
+                customer_id: None,
+                error_message: None,
+                new_values: serde_json::to_value(created.clone()).ok(),
+                old_values: None,
+                target_id: created.id.to_string(),
+                target_table: "admin_api_keys".to_string(),
+                ip_address: command.ctx.clone().and_then(|ctx| ctx.ip_address),
+                request_id: command.ctx.clone().map(|ctx| ctx.request_id),
+                user_agent: command.ctx.and_then(|ctx| ctx.user_agent),
+            })
+            .await?;
+
+        Ok(CreatedApiKey {
+            key,
+            api_key: created,
+        })
+    }
+
+    async fn revoke(&self, command: RevokeApiKeyCommand) -> ApiResult<AdminApiKeyRow> {
+        let prev = self.repo.get_by_id(command.id).await?;
+        let revoked = self.repo.revoke(command.id).await?;
+
+        self.audit_log_service
+            .create(NewAuditLog {
+                action: AuditAction::ApiKeyRevoke,
+                status: AuditStatus::Success,
+                admin_user_id: Some(command.revoked_by),
+                customer_id: None,
+                error_message: None,
+                new_values: serde_json::to_value(revoked.clone()).ok(),
+                old_values: serde_json::to_value(prev).ok(),
+                target_id: command.id.to_string(),
+                target_table: "admin_api_keys".to_string(),
+                ip_address: command.ctx.clone().and_then(|ctx| ctx.ip_address),
+                request_id: command.ctx.clone().map(|ctx| ctx.request_id),
+                user_agent: command.ctx.and_then(|ctx| ctx.user_agent),
+            })
+            .await?;
+
+        Ok(revoked)
+    }
+
+    async fn authenticate(&self, key: &str, ip: Option<IpAddr>) -> ApiResult<AuthApiKey> {
+        let row = self
+            .repo
+            .get_active_by_hash(&hash_api_key(key))
+            .await
+            .map_err(|_| ApiError::AuthenticationError("Invalid API key".to_string()))?;
+
+        if !is_ip_allowed(&row.allowed_ips, ip) {
+            return Err(ApiError::AuthorizationError(
+                "API key is not allowed from this address".to_string(),
+            ));
+        }
+
+        self.repo.touch(row.id, ip.map(IpNetwork::from)).await?;
+
+        Ok(AuthApiKey {
+            id: row.id,
+            created_by: row.created_by,
+            permissions: row.permissions,
+        })
+    }
+
+    async fn has_permission(&self, key: &AuthApiKey, permission: Permission) -> ApiResult<bool> {
+        if !key.permissions.contains(&permission.to_string()) {
+            return Ok(false);
+        }
+        Ok(self
+            .effective_permission_repo
+            .has_permission(key.created_by, permission)
+            .await?)
+    }
+}
+
+pub fn generate_api_key() -> String {
+    format!(
+        "{API_KEY_PREFIX}{}",
+        Alphanumeric.sample_string(&mut rand::rng(), API_KEY_RANDOM_LEN)
+    )
+}
+
+pub fn hash_api_key(key: &str) -> String {
+    blake3::hash(key.trim().as_bytes()).to_string()
+}
+
+fn is_ip_allowed(allowed: &[IpNetwork], ip: Option<IpAddr>) -> bool {
+    if allowed.is_empty() {
+        return true;
+    }
+    ip.is_some_and(|ip| allowed.iter().any(|net| net.contains(ip)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    fn build_service(
+        pool: &PgPool,
+    ) -> AdminApiKeyService<
+        AdminApiKeyRepository,
+        EffectivePermissionRepository,
+        AuditLogService<AuditLogRepository>,
+    > {
+        let pool = Arc::new(pool.clone());
+        AdminApiKeyService::new(
+            Arc::new(AdminApiKeyRepository::new(pool.clone())),
+            Arc::new(EffectivePermissionRepository::new(pool.clone())),
+            Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+                pool,
+            )))),
+        )
+    }
+
+    async fn create_user_with_permission(pool: &PgPool, login: &str, permission: &str) -> i64 {
+        let user_id: i64 = sqlx::query_scalar!(
+            r#"
+            INSERT INTO admin_users (login, hashed_password, two_fa_secret, created_by, is_system)
+            VALUES ($1, 'password', '', 1, false)
+            RETURNING id
+            "#,
+            login
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO user_permissions (user_id, permission_id, effect, created_by)
+            SELECT $1, id, 'allow', 1 FROM permissions WHERE name = $2
+            "#,
+            user_id,
+            permission
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        user_id
+    }
+
+    #[test]
+    fn test_is_ip_allowed() {
+        let allowed: Vec<IpNetwork> = vec!["10.0.0.0/8".parse().unwrap()];
+        assert!(is_ip_allowed(&[], None));
+        assert!(is_ip_allowed(&allowed, Some("10.2.3.4".parse().unwrap())));
+        assert!(!is_ip_allowed(
+            &allowed,
+            Some("192.168.0.1".parse().unwrap())
+        ));
+        assert!(!is_ip_allowed(&allowed, None));
+    }
+
+    #[sqlx::test]
+    async fn test_create_and_authenticate(pool: PgPool) {
+        let service = build_service(&pool);
+        let user_id = create_user_with_permission(&pool, "api_key_owner", "orders:read").await;
+
+        let created = service
+            .create(CreateApiKeyCommand {
+                name: "accounting".to_string(),
+                permissions: vec!["orders:read".to_string()],
+                allowed_ips: vec![],
+                expires_at: None,
+                created_by: user_id,
+                ctx: None,
+            })
+            .await
+            .unwrap();
+        assert!(created.key.starts_with(&created.api_key.key_prefix));
+        assert_ne!(created.api_key.key_hash, created.key);
+
+        let auth = service.authenticate(&created.key, None).await.unwrap();
+        assert_eq!(auth.created_by, user_id);
+        assert!(
+            service
+                .has_permission(&auth, Permission::OrdersRead)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !service
+                .has_permission(&auth, Permission::TransactionsRead)
+                .await
+                .unwrap()
+        );
+
+        service
+            .revoke(RevokeApiKeyCommand {
+                id: created.api_key.id,
+                revoked_by: user_id,
+                ctx: None,
+            })
+            .await
+            .unwrap();
+        assert!(service.authenticate(&created.key, None).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_create_rejects_permissions_not_held_by_creator(pool: PgPool) {
+        let service = build_service(&pool);
+        let user_id = create_user_with_permission(&pool, "api_key_limited", "orders:read").await;
+
+        let result = service
+            .create(CreateApiKeyCommand {
+                name: "too much".to_string(),
+                permissions: vec!["transactions:read".to_string()],
+                allowed_ips: vec![],
+                expires_at: None,
+                created_by: user_id,
+                ctx: None,
+            })
+            .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+}
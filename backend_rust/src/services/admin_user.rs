@@ -1,16 +1,24 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use shared_dtos::audit_log::{AuditAction, AuditStatus};
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{
-    errors::api::ApiResult,
+    errors::{
+        api::{ApiError, ApiResult},
+        repository::RepositoryError,
+    },
     infrastructure::repositories::{
         admin_user::{AdminUserRepository, AdminUserRepositoryTrait},
+        admin_user_recovery_code::{
+            AdminUserRecoveryCodeRepository, AdminUserRecoveryCodeRepositoryTrait,
+        },
         admin_user_with_roles::get_admin_user_with_roles_list,
         audit_log::AuditLogRepository,
+        temporary_token::{TemporaryTokenRepository, TemporaryTokenRepositoryTrait},
         user_role::{UserRoleRepository, UserRoleRepositoryTrait},
     },
     middlewares::context::RequestContext,
@@ -18,14 +26,19 @@ use crate::{
         admin_user::{AdminUserRow, NewAdminUser, UpdateAdminUser},
         admin_user_with_roles::AdminUserWithRolesRow,
         audit_log::NewAuditLog,
+        temporary_token::{TemporaryTokenPurpose, TemporaryTokenRow},
         user_role::AssignUserRoles,
     },
     services::{
         audit_log::{AuditLogService, AuditLogServiceTrait},
+        auth::{generate_recovery_codes, hash_recovery_code},
         topt_encryptor::TotpEncryptor,
     },
 };
 
+const TWO_FA_ENROLLMENT_TTL_HOURS: i64 = 24;
+const TWO_FA_ROTATION_TTL_MINUTES: i64 = 15;
+
 #[derive(Debug)]
 pub struct CreateAdminUser {
     pub login: String,
@@ -64,6 +77,43 @@ pub struct UpdateAdminUserCommand {
     pub updated_by: i64,
 }
 
+#[derive(Debug)]
+pub struct ChangePasswordCommand {
+    pub user_id: i64,
+    pub new_password: String,
+}
+
+#[derive(Debug)]
+pub struct RequestTwoFaResetCommand {
+    pub id: i64,
+    pub requested_by: i64,
+}
+
+#[derive(Debug)]
+pub struct CompleteTwoFaEnrollmentCommand {
+    pub token: Uuid,
+    pub password: String,
+}
+
+#[derive(Debug)]
+pub struct TwoFaEnrollment {
+    pub two_fa_secret: String,
+    pub two_fa_qr_code: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct TwoFaRotation {
+    pub two_fa_secret: String,
+    pub two_fa_qr_code: String,
+}
+
+#[derive(Debug)]
+pub struct PendingTwoFaSecret {
+    pub secret: String,
+    encrypted_secret: String,
+}
+
 #[async_trait]
 pub trait AdminUserServiceTrait: Send + Sync {
     async fn get_list(&self) -> ApiResult<Vec<AdminUserRow>>;
@@ -82,26 +132,62 @@ pub trait AdminUserServiceTrait: Send + Sync {
         ctx: RequestContext,
     ) -> ApiResult<AdminUserRow>;
     async fn delete(&self, command: DeleteAdminUserCommand, ctx: RequestContext) -> ApiResult<()>;
+    async fn change_password(
+        &self,
+        command: ChangePasswordCommand,
+        ctx: RequestContext,
+    ) -> ApiResult<()>;
+    /// Issues a new secret that only replaces the current one once a code from it is confirmed
+    async fn rotate_two_fa(&self, user_id: i64) -> ApiResult<TwoFaRotation>;
+    async fn get_pending_two_fa_secret(&self, user_id: i64) -> ApiResult<PendingTwoFaSecret>;
+    /// Activates a pending secret whose code has been verified, returns new recovery codes
+    async fn confirm_two_fa_rotation(
+        &self,
+        user_id: i64,
+        pending: PendingTwoFaSecret,
+        ctx: RequestContext,
+    ) -> ApiResult<Vec<String>>;
+    async fn regenerate_recovery_codes(
+        &self,
+        user_id: i64,
+        ctx: RequestContext,
+    ) -> ApiResult<Vec<String>>;
+    async fn request_two_fa_reset(
+        &self,
+        command: RequestTwoFaResetCommand,
+        ctx: RequestContext,
+    ) -> ApiResult<TemporaryTokenRow>;
+    async fn complete_two_fa_enrollment(
+        &self,
+        command: CompleteTwoFaEnrollmentCommand,
+        ctx: RequestContext,
+    ) -> ApiResult<TwoFaEnrollment>;
 }
 
-pub struct AdminUserService<R, S, A> {
+pub struct AdminUserService<R, S, A, T, C> {
     pool: Arc<PgPool>,
     repo: Arc<R>,
     user_role_repo: Arc<S>,
+    temp_token_repo: Arc<T>,
+    recovery_code_repo: Arc<C>,
     totp_encryptor: Arc<TotpEncryptor>,
     audit_log_service: Arc<A>,
 }
 
-impl<R, S, A> AdminUserService<R, S, A>
+impl<R, S, A, T, C> AdminUserService<R, S, A, T, C>
 where
     R: AdminUserRepositoryTrait + Send + Sync,
     S: UserRoleRepositoryTrait + Send + Sync,
     A: AuditLogServiceTrait + Send + Sync,
+    T: TemporaryTokenRepositoryTrait + Send + Sync,
+    C: AdminUserRecoveryCodeRepositoryTrait + Send + Sync,
 {
     pub fn new(
         pool: Arc<PgPool>,
         repo: Arc<R>,
         user_role_repo: Arc<S>,
+        temp_token_repo: Arc<T>,
+        recovery_code_repo: Arc<C>,
         totp_encryptor: Arc<TotpEncryptor>,
         audit_log_service: Arc<A>,
     ) -> Self {
@@ -110,9 +196,70 @@ where
             repo,
             totp_encryptor,
             user_role_repo,
+            temp_token_repo,
+            recovery_code_repo,
             audit_log_service,
         }
     }
+
+    async fn issue_two_fa_secret(&self, user: &AdminUserRow) -> ApiResult<TwoFaEnrollment> {
+        let secret = totp_rs::Secret::generate_secret().to_encoded().to_string();
+        self.repo
+            .update(
+                user.id,
+                UpdateAdminUser {
+                    login: None,
+                    hashed_password: None,
+                    two_fa_secret: Some(self.totp_encryptor.encrypt(&secret)?),
+                    telegram_id: None,
+                },
+            )
+            .await?;
+        let recovery_codes = self.issue_recovery_codes(user.id).await?;
+
+        Ok(TwoFaEnrollment {
+            two_fa_qr_code: self.totp_encryptor.generate_qr_code(&user.login, &secret)?,
+            two_fa_secret: secret,
+            recovery_codes,
+        })
+    }
+
+    async fn issue_recovery_codes(&self, user_id: i64) -> ApiResult<Vec<String>> {
+        let codes = generate_recovery_codes();
+        self.recovery_code_repo
+            .replace_for_user(
+                user_id,
+                codes.iter().map(|c| hash_recovery_code(c)).collect(),
+            )
+            .await?;
+        Ok(codes)
+    }
+
+    async fn log_credentials_change(
+        &self,
+        action: AuditAction,
+        admin_user_id: i64,
+        target_id: i64,
+        ctx: RequestContext,
+    ) -> ApiResult<()> {
+        self.audit_log_service
+            .create(NewAuditLog {
+                action,
+                status: AuditStatus::Success,
+                admin_user_id: Some(admin_user_id),
+                customer_id: None,
+                error_message: None,
+                ip_address: ctx.ip_address,
+                new_values: None,
+                old_values: None,
+                request_id: Some(ctx.request_id),
+                target_id: target_id.to_string(),
+                target_table: "admin_users".to_string(),
+                user_agent: ctx.user_agent,
+            })
+            .await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -121,6 +268,8 @@ impl AdminUserServiceTrait
         AdminUserRepository,
         UserRoleRepository,
         AuditLogService<AuditLogRepository>,
+        TemporaryTokenRepository,
+        AdminUserRecoveryCodeRepository,
     >
 {
     async fn get_list(&self) -> ApiResult<Vec<AdminUserRow>> {
@@ -286,13 +435,163 @@ impl AdminUserServiceTrait
             .await?;
         Ok(())
     }
+
+    async fn change_password(
+        &self,
+        command: ChangePasswordCommand,
+        ctx: RequestContext,
+    ) -> ApiResult<()> {
+        self.repo
+            .update(
+                command.user_id,
+                UpdateAdminUser {
+                    login: None,
+                    hashed_password: Some(bcrypt::hash(
+                        &command.new_password,
+                        bcrypt::DEFAULT_COST,
+                    )?),
+                    two_fa_secret: None,
+                    telegram_id: None,
+                },
+            )
+            .await?;
+        self.log_credentials_change(
+            AuditAction::UserPasswordChange,
+            command.user_id,
+            command.user_id,
+            ctx,
+        )
+        .await
+    }
+
+    async fn rotate_two_fa(&self, user_id: i64) -> ApiResult<TwoFaRotation> {
+        let user = self.repo.get_by_id(user_id).await?;
+        let secret = totp_rs::Secret::generate_secret().to_encoded().to_string();
+        self.repo
+            .set_pending_two_fa_secret(
+                user.id,
+                self.totp_encryptor.encrypt(&secret)?,
+                Utc::now() + Duration::minutes(TWO_FA_ROTATION_TTL_MINUTES),
+            )
+            .await?;
+
+        Ok(TwoFaRotation {
+            two_fa_qr_code: self.totp_encryptor.generate_qr_code(&user.login, &secret)?,
+            two_fa_secret: secret,
+        })
+    }
+
+    async fn get_pending_two_fa_secret(&self, user_id: i64) -> ApiResult<PendingTwoFaSecret> {
+        let encrypted_secret = self
+            .repo
+            .get_pending_two_fa_secret(user_id)
+            .await
+            .map_err(|e| match e {
+                RepositoryError::NotFound(_) => {
+                    ApiError::BadRequest("No pending 2FA rotation".to_string())
+                }
+                e => e.into(),
+            })?;
+
+        Ok(PendingTwoFaSecret {
+            secret: self.totp_encryptor.decrypt(&encrypted_secret)?,
+            encrypted_secret,
+        })
+    }
+
+    async fn confirm_two_fa_rotation(
+        &self,
+        user_id: i64,
+        pending: PendingTwoFaSecret,
+        ctx: RequestContext,
+    ) -> ApiResult<Vec<String>> {
+        self.repo
+            .activate_pending_two_fa_secret(user_id, &pending.encrypted_secret)
+            .await?;
+        let codes = self.issue_recovery_codes(user_id).await?;
+        self.log_credentials_change(AuditAction::UserTwoFaRotate, user_id, user_id, ctx)
+            .await?;
+        Ok(codes)
+    }
+
+    async fn regenerate_recovery_codes(
+        &self,
+        user_id: i64,
+        ctx: RequestContext,
+    ) -> ApiResult<Vec<String>> {
+        let codes = self.issue_recovery_codes(user_id).await?;
+        self.log_credentials_change(
+            AuditAction::UserRecoveryCodesRegenerate,
+            user_id,
+            user_id,
+            ctx,
+        )
+        .await?;
+        Ok(codes)
+    }
+
+    async fn request_two_fa_reset(
+        &self,
+        command: RequestTwoFaResetCommand,
+        ctx: RequestContext,
+    ) -> ApiResult<TemporaryTokenRow> {
+        let user = self.repo.get_by_id(command.id).await?;
+        // The lost authenticator must stop working right away, so the old secret
+        // is replaced with one nobody has seen until enrollment is completed
+        let placeholder = totp_rs::Secret::generate_secret().to_encoded().to_string();
+        let token = self
+            .repo
+            .reset_two_fa(
+                user.id,
+                self.totp_encryptor.encrypt(&placeholder)?,
+                Utc::now() + Duration::hours(TWO_FA_ENROLLMENT_TTL_HOURS),
+            )
+            .await?;
+        self.log_credentials_change(
+            AuditAction::UserTwoFaReset,
+            command.requested_by,
+            user.id,
+            ctx,
+        )
+        .await?;
+        Ok(token)
+    }
+
+    async fn complete_two_fa_enrollment(
+        &self,
+        command: CompleteTwoFaEnrollmentCommand,
+        ctx: RequestContext,
+    ) -> ApiResult<TwoFaEnrollment> {
+        let token = self
+            .temp_token_repo
+            .find_unused_by_token_and_purpose(
+                &command.token,
+                TemporaryTokenPurpose::TwoFaEnrollment,
+            )
+            .await
+            .map_err(|_| ApiError::AuthenticationError("Invalid token".to_string()))?;
+        let user = self.repo.get_by_id(token.user_id).await?;
+        if !bcrypt::verify(&command.password, &user.hashed_password)? {
+            return Err(ApiError::AuthenticationError(
+                "Invalid credentials".to_string(),
+            ));
+        }
+        if !self.temp_token_repo.mark_as_used(&token.token).await? {
+            return Err(ApiError::AuthenticationError("Invalid token".to_string()));
+        }
+        let enrollment = self.issue_two_fa_secret(&user).await?;
+        self.log_credentials_change(AuditAction::UserTwoFaRotate, user.id, user.id, ctx)
+            .await?;
+        Ok(enrollment)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::infrastructure::repositories::{
-        admin_user::AdminUserRepository, audit_log::AuditLogRepository,
+        admin_user::AdminUserRepository, admin_user_recovery_code::AdminUserRecoveryCodeRepository,
+        audit_log::AuditLogRepository, temporary_token::TemporaryTokenRepository,
         user_role::UserRoleRepository,
     };
     use crate::services::audit_log::AuditLogService;
@@ -316,6 +615,8 @@ mod tests {
         AdminUserRepository,
         UserRoleRepository,
         AuditLogService<AuditLogRepository>,
+        TemporaryTokenRepository,
+        AdminUserRecoveryCodeRepository,
     > {
         let pool = Arc::new(pool.clone());
         let audit_log_service = Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
@@ -329,6 +630,8 @@ mod tests {
             pool.clone(),
             Arc::new(AdminUserRepository::new(pool.clone())),
             Arc::new(UserRoleRepository::new(pool.clone())),
+            Arc::new(TemporaryTokenRepository::new(pool.clone())),
+            Arc::new(AdminUserRecoveryCodeRepository::new(pool.clone())),
             totp_encryptor,
             audit_log_service,
         )
@@ -396,4 +699,143 @@ mod tests {
             .await
             .unwrap();
     }
+
+    #[sqlx::test]
+    async fn test_change_password(pool: PgPool) {
+        let service = build_service(&pool);
+        let role_id = create_role(&pool, "password_role").await;
+        let created = service
+            .create(
+                CreateAdminUser {
+                    login: "password_user".to_string(),
+                    password: "password".to_string(),
+                    created_by: 1,
+                    roles: vec![role_id],
+                },
+                build_context(),
+            )
+            .await
+            .unwrap();
+
+        service
+            .change_password(
+                ChangePasswordCommand {
+                    user_id: created.id,
+                    new_password: "new_password".to_string(),
+                },
+                build_context(),
+            )
+            .await
+            .unwrap();
+
+        let user = service.get_by_id(created.id).await.unwrap();
+        assert!(bcrypt::verify("new_password", &user.hashed_password).unwrap());
+        assert!(!bcrypt::verify("password", &user.hashed_password).unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_two_fa_reset_and_enrollment(pool: PgPool) {
+        let service = build_service(&pool);
+        let role_id = create_role(&pool, "reset_role").await;
+        let created = service
+            .create(
+                CreateAdminUser {
+                    login: "reset_user".to_string(),
+                    password: "password".to_string(),
+                    created_by: 1,
+                    roles: vec![role_id],
+                },
+                build_context(),
+            )
+            .await
+            .unwrap();
+        let before = service.get_by_id(created.id).await.unwrap();
+
+        let token = service
+            .request_two_fa_reset(
+                RequestTwoFaResetCommand {
+                    id: created.id,
+                    requested_by: 1,
+                },
+                build_context(),
+            )
+            .await
+            .unwrap();
+        let after_reset = service.get_by_id(created.id).await.unwrap();
+        assert_ne!(before.two_fa_secret, after_reset.two_fa_secret);
+
+        let wrong_password = service
+            .complete_two_fa_enrollment(
+                CompleteTwoFaEnrollmentCommand {
+                    token: token.token,
+                    password: "wrong_password".to_string(),
+                },
+                build_context(),
+            )
+            .await;
+        assert!(wrong_password.is_err());
+
+        let enrollment = service
+            .complete_two_fa_enrollment(
+                CompleteTwoFaEnrollmentCommand {
+                    token: token.token,
+                    password: "password".to_string(),
+                },
+                build_context(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(enrollment.recovery_codes.len(), 10);
+        assert!(!enrollment.two_fa_qr_code.is_empty());
+
+        let reused = service
+            .complete_two_fa_enrollment(
+                CompleteTwoFaEnrollmentCommand {
+                    token: token.token,
+                    password: "password".to_string(),
+                },
+                build_context(),
+            )
+            .await;
+        assert!(reused.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_two_fa_rotation_keeps_secret_until_confirmed(pool: PgPool) {
+        let service = build_service(&pool);
+        let role_id = create_role(&pool, "rotate_role").await;
+        let created = service
+            .create(
+                CreateAdminUser {
+                    login: "rotate_user".to_string(),
+                    password: "password".to_string(),
+                    created_by: 1,
+                    roles: vec![role_id],
+                },
+                build_context(),
+            )
+            .await
+            .unwrap();
+        let before = service.get_by_id(created.id).await.unwrap();
+
+        let not_started = service.get_pending_two_fa_secret(created.id).await;
+        assert!(matches!(not_started, Err(ApiError::BadRequest(_))));
+
+        let rotation = service.rotate_two_fa(created.id).await.unwrap();
+        assert!(!rotation.two_fa_qr_code.is_empty());
+        let unchanged = service.get_by_id(created.id).await.unwrap();
+        assert_eq!(before.two_fa_secret, unchanged.two_fa_secret);
+
+        let pending = service.get_pending_two_fa_secret(created.id).await.unwrap();
+        assert_eq!(pending.secret, rotation.two_fa_secret);
+        let recovery_codes = service
+            .confirm_two_fa_rotation(created.id, pending, build_context())
+            .await
+            .unwrap();
+        assert_eq!(recovery_codes.len(), 10);
+
+        let after = service.get_by_id(created.id).await.unwrap();
+        assert_ne!(before.two_fa_secret, after.two_fa_secret);
+        assert!(service.get_pending_two_fa_secret(created.id).await.is_err());
+    }
 }
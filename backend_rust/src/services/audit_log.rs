@@ -15,6 +15,11 @@ use crate::{
 pub trait AuditLogServiceTrait: Send + Sync {
     async fn get_list(&self, query: AuditLogListQuery) -> ApiResult<PaginatedResult<AuditLogRow>>;
     async fn create(&self, audit_log: NewAuditLog) -> ApiResult<AuditLogRow>;
+    async fn get_for_target(
+        &self,
+        target_table: &str,
+        target_id: &str,
+    ) -> ApiResult<Vec<AuditLogRow>>;
 }
 
 pub struct AuditLogService<R> {
@@ -44,6 +49,18 @@ impl AuditLogServiceTrait for AuditLogService<AuditLogRepository> {
 
         Ok(created)
     }
+
+    async fn get_for_target(
+        &self,
+        target_table: &str,
+        target_id: &str,
+    ) -> ApiResult<Vec<AuditLogRow>> {
+        let res = self
+            .audit_log_repo
+            .get_for_target(target_table, target_id)
+            .await?;
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
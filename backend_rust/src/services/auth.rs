@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use chrono::Duration;
+use rand::RngExt;
 use serde::Deserialize;
 use uuid::Uuid;
 
@@ -9,6 +10,7 @@ use crate::{
     errors::auth::{AuthError, AuthResult},
     infrastructure::repositories::{
         active_token::ActiveTokenRepositoryTrait, admin_user::AdminUserRepositoryTrait,
+        admin_user_recovery_code::AdminUserRecoveryCodeRepositoryTrait,
         effective_permission::EffectivePermissionRepositoryTrait,
         temporary_token::TemporaryTokenRepositoryTrait,
     },
@@ -17,14 +19,33 @@ use crate::{
         permission::Permission,
         temporary_token::{TemporaryTokenPurpose, TemporaryTokenRow},
     },
-    services::topt_encryptor::TotpEncryptor,
+    services::{admin_api_key::AuthApiKey, topt_encryptor::TotpEncryptor},
 };
 
+const RECOVERY_CODES_COUNT: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
 pub struct AuthUser {
     pub id: i64,
     pub token: Uuid,
 }
 
+/// Caller of a permission-checked admin endpoint: a session user or a scoped API key
+pub enum AdminActor {
+    User(AuthUser),
+    ApiKey(AuthApiKey),
+}
+
+impl AdminActor {
+    /// Admin user recorded as `created_by` and in audit logs, the key creator for API keys
+    pub fn id(&self) -> i64 {
+        match self {
+            AdminActor::User(user) => user.id,
+            AdminActor::ApiKey(key) => key.created_by,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Claims {
     pub sub: i64,
@@ -38,6 +59,11 @@ pub trait AuthServiceTrait: Send + Sync {
     async fn login_step1(&self, login: &str, password: &str) -> AuthResult<TemporaryTokenRow>;
     async fn login_step2(&self, temp_token: &Uuid, code: &str)
     -> Result<ActiveTokenRow, AuthError>;
+    async fn login_step2_recovery(
+        &self,
+        temp_token: &Uuid,
+        recovery_code: &str,
+    ) -> AuthResult<ActiveTokenRow>;
     async fn logout(&self, token: Uuid) -> Result<(), AuthError>;
     async fn authenticate(&self, token: Uuid) -> AuthResult<AuthUser>;
     async fn has_permission(
@@ -46,15 +72,23 @@ pub trait AuthServiceTrait: Send + Sync {
         permission: Permission,
     ) -> Result<bool, AuthError>;
     async fn get_user_permissions(&self, admin_user_id: i64) -> Result<Vec<String>, AuthError>;
+    async fn verify_credentials(
+        &self,
+        admin_user_id: i64,
+        password: &str,
+        code: Option<&str>,
+    ) -> AuthResult<()>;
+    async fn revoke_sessions(&self, admin_user_id: i64, keep: Option<Uuid>) -> AuthResult<u64>;
     fn verify_password(&self, plain: &str, hash: &str) -> AuthResult<bool>;
     fn verify_totp_code(&self, secret: &str, code: &str) -> AuthResult<bool>;
 }
 
-pub struct AuthService<S, T, R, E> {
+pub struct AuthService<S, T, R, E, C> {
     tokens: Arc<S>,
     temp_tokens: Arc<T>,
     admin_user_repo: Arc<R>,
     effective_permission_repo: Arc<E>,
+    recovery_code_repo: Arc<C>,
     totp_encryptor: Arc<TotpEncryptor>,
     config: AuthServiceConfig,
 }
@@ -71,18 +105,20 @@ pub struct AuthServiceConfig {
     pub two_fa_token_ttl: Duration,
 }
 
-impl<S, T, R, E> AuthService<S, T, R, E>
+impl<S, T, R, E, C> AuthService<S, T, R, E, C>
 where
     S: ActiveTokenRepositoryTrait + Send + Sync,
     T: TemporaryTokenRepositoryTrait + Send + Sync,
     R: AdminUserRepositoryTrait + Send + Sync,
     E: EffectivePermissionRepositoryTrait + Send + Sync,
+    C: AdminUserRecoveryCodeRepositoryTrait + Send + Sync,
 {
     pub fn new(
         tokens: Arc<S>,
         temp_tokens: Arc<T>,
         admin_user_repo: Arc<R>,
         effective_permission_repo: Arc<E>,
+        recovery_code_repo: Arc<C>,
         totp_encryptor: Arc<TotpEncryptor>,
         config: AuthServiceConfig,
     ) -> Self {
@@ -91,19 +127,32 @@ where
             temp_tokens,
             admin_user_repo,
             effective_permission_repo,
+            recovery_code_repo,
             totp_encryptor,
             config,
         }
     }
+
+    async fn issue_access_token(&self, user_id: i64) -> AuthResult<ActiveTokenRow> {
+        self.tokens
+            .insert_token(NewToken {
+                token_type: TokenType::Access,
+                user_id,
+                ttl: self.config.access_token_ttl,
+            })
+            .await
+            .map_err(AuthError::from)
+    }
 }
 
 #[async_trait]
-impl<S, T, R, E> AuthServiceTrait for AuthService<S, T, R, E>
+impl<S, T, R, E, C> AuthServiceTrait for AuthService<S, T, R, E, C>
 where
     S: ActiveTokenRepositoryTrait + Send + Sync,
     T: TemporaryTokenRepositoryTrait + Send + Sync,
     R: AdminUserRepositoryTrait + Send + Sync,
     E: EffectivePermissionRepositoryTrait + Send + Sync,
+    C: AdminUserRecoveryCodeRepositoryTrait + Send + Sync,
 {
     async fn authenticate(&self, token: Uuid) -> AuthResult<AuthUser> {
         let token = self
@@ -191,12 +240,61 @@ where
 
         self.temp_tokens.mark_as_used(&temp_token.token).await?;
 
+        self.issue_access_token(temp_token.user_id).await
+    }
+
+    async fn login_step2_recovery(
+        &self,
+        temp_token: &Uuid,
+        recovery_code: &str,
+    ) -> AuthResult<ActiveTokenRow> {
+        let temp_token = self
+            .temp_tokens
+            .find_unused_by_token_and_purpose(temp_token, TemporaryTokenPurpose::TwoFa)
+            .await
+            .map_err(|_e| AuthError::InvalidCredentials)?;
+
+        if !self
+            .recovery_code_repo
+            .consume(temp_token.user_id, &hash_recovery_code(recovery_code))
+            .await?
+        {
+            return Err(AuthError::Invalid2FACode);
+        }
+
+        self.temp_tokens.mark_as_used(&temp_token.token).await?;
+
+        self.issue_access_token(temp_token.user_id).await
+    }
+
+    async fn verify_credentials(
+        &self,
+        admin_user_id: i64,
+        password: &str,
+        code: Option<&str>,
+    ) -> AuthResult<()> {
+        let user = self.admin_user_repo.get_by_id(admin_user_id).await?;
+
+        if !self.verify_password(password, &user.hashed_password)? {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        if let Some(code) = code {
+            let secret = self
+                .totp_encryptor
+                .decrypt(&user.two_fa_secret)
+                .map_err(|e| AuthError::InternalServerError(e.to_string()))?;
+            if !self.verify_totp_code(&secret, code)? {
+                return Err(AuthError::Invalid2FACode);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn revoke_sessions(&self, admin_user_id: i64, keep: Option<Uuid>) -> AuthResult<u64> {
         self.tokens
-            .insert_token(NewToken {
-                token_type: TokenType::Access,
-                user_id: temp_token.user_id,
-                ttl: self.config.access_token_ttl,
-            })
+            .revoke_all_for_user(admin_user_id, keep)
             .await
             .map_err(AuthError::from)
     }
@@ -226,3 +324,57 @@ where
             .map_err(AuthError::from)
     }
 }
+
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::rng();
+    (0..RECOVERY_CODES_COUNT)
+        .map(|_| {
+            let raw: String = (0..10)
+                .map(|_| {
+                    RECOVERY_CODE_ALPHABET[rng.random_range(0..RECOVERY_CODE_ALPHABET.len())]
+                        as char
+                })
+                .collect();
+            format!("{}-{}", &raw[..5], &raw[5..])
+        })
+        .collect()
+}
+
+// Codes are random, so a fast hash is enough (unlike passwords)
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    blake3::hash(normalized.as_bytes()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODES_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), 11);
+            assert_eq!(&code[5..6], "-");
+        }
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+    }
+
+    #[test]
+    fn test_hash_recovery_code_is_normalized() {
+        assert_eq!(
+            hash_recovery_code("abcde-fghjk"),
+            hash_recovery_code(" ABCDE fghjk ")
+        );
+        assert_ne!(
+            hash_recovery_code("abcde-fghjk"),
+            hash_recovery_code("abcde-fghjm")
+        );
+    }
+}
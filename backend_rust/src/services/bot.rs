@@ -510,6 +510,7 @@ mod tests {
                 payment_gateway: None,
                 details: None,
                 bot_id: None,
+                payment_invoice_id: None,
             })
             .await
             .unwrap();
@@ -4,12 +4,18 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use shared_dtos::{
     audit_log::{AuditAction, AuditStatus},
-    broadcast::{BroadcastStatus, JsonRawListQuery},
+    broadcast::{
+        BroadcastButton, BroadcastMedia, BroadcastRecipientStatus, BroadcastStatus,
+        BroadcastTextEntity, JsonRawListQuery,
+    },
 };
 use uuid::Uuid;
 
 use crate::{
-    errors::api::{ApiError, ApiResult},
+    errors::{
+        api::{ApiError, ApiResult},
+        repository::RepositoryError,
+    },
     infrastructure::repositories::{
         audit_log::AuditLogRepository,
         broadcast::{BroadcastRepository, BroadcastRepositoryTrait},
@@ -17,8 +23,13 @@ use crate::{
     middlewares::context::RequestContext,
     models::{
         audit_log::NewAuditLog,
-        broadcast::{BroadcastListQuery, BroadcastRow, NewBroadcast, UpdateBroadcast},
+        broadcast::{
+            BroadcastDeliveryRow, BroadcastListQuery, BroadcastRecipientListQuery,
+            BroadcastRecipientRow, BroadcastRecipientStats, BroadcastRow, BroadcastVariantRow,
+            BroadcastVariantStatsRow, NewBroadcast, NewBroadcastVariant, UpdateBroadcast,
+        },
         common::PaginatedResult,
+        customer::CustomerListQuery,
     },
     services::audit_log::{AuditLogService, AuditLogServiceTrait},
 };
@@ -27,7 +38,12 @@ use crate::{
 pub struct CreateBroadcastCommand {
     pub content_text: Option<String>,
     pub content_image_id: Option<Uuid>,
+    pub content_entities: Vec<BroadcastTextEntity>,
+    pub content_media: Vec<BroadcastMedia>,
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
     pub filters: Option<JsonRawListQuery>,
+    pub segment_id: Option<i64>,
+    pub variants: Vec<NewBroadcastVariant>,
     pub scheduled_for: Option<DateTime<Utc>>,
     pub created_by: i64,
     pub ctx: Option<RequestContext>,
@@ -39,22 +55,101 @@ pub struct UpdateBroadcastCommand {
     pub status: Option<BroadcastStatus>,
     pub content_text: Option<Option<String>>,
     pub content_image_id: Option<Option<Uuid>>,
+    pub content_entities: Option<Vec<BroadcastTextEntity>>,
+    pub content_media: Option<Vec<BroadcastMedia>>,
+    pub content_buttons: Option<Vec<Vec<BroadcastButton>>>,
     pub filters: Option<JsonRawListQuery>,
     pub scheduled_for: Option<Option<DateTime<Utc>>>,
     pub updated_by: Option<i64>,
     pub statistics: Option<Option<serde_json::Value>>,
     pub started_at: Option<Option<DateTime<Utc>>>,
     pub finished_at: Option<Option<DateTime<Utc>>>,
+    /// Refuses the update with `409` unless the broadcast is in one of these statuses
+    pub expected_status: Option<Vec<BroadcastStatus>>,
+    pub ctx: Option<RequestContext>,
+}
+
+#[derive(Debug)]
+pub struct BroadcastActionCommand {
+    pub id: i64,
+    pub updated_by: i64,
     pub ctx: Option<RequestContext>,
 }
 
+impl From<BroadcastActionCommand> for UpdateBroadcastCommand {
+    fn from(command: BroadcastActionCommand) -> Self {
+        UpdateBroadcastCommand {
+            id: command.id,
+            status: None,
+            content_text: None,
+            content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
+            filters: None,
+            scheduled_for: None,
+            updated_by: Some(command.updated_by),
+            statistics: None,
+            started_at: None,
+            finished_at: None,
+            expected_status: None,
+            ctx: command.ctx,
+        }
+    }
+}
+
 #[async_trait]
 pub trait BroadcastServiceTrait: Send + Sync {
     async fn get_list(&self, query: BroadcastListQuery)
     -> ApiResult<PaginatedResult<BroadcastRow>>;
+    async fn get_by_id(&self, id: i64) -> ApiResult<BroadcastRow>;
     async fn create(&self, command: CreateBroadcastCommand) -> ApiResult<BroadcastRow>;
     async fn update(&self, command: UpdateBroadcastCommand) -> ApiResult<BroadcastRow>;
     async fn get_ready_broadcasts(&self) -> ApiResult<Vec<BroadcastRow>>;
+    async fn get_in_progress_broadcasts(&self) -> ApiResult<Vec<BroadcastRow>>;
+    async fn start(
+        &self,
+        id: i64,
+        audience: &CustomerListQuery,
+    ) -> ApiResult<Option<(BroadcastRow, u64)>>;
+    async fn pause(&self, command: BroadcastActionCommand) -> ApiResult<BroadcastRow>;
+    async fn resume(&self, command: BroadcastActionCommand) -> ApiResult<BroadcastRow>;
+    async fn cancel(&self, command: BroadcastActionCommand) -> ApiResult<BroadcastRow>;
+    async fn get_pending_recipients(
+        &self,
+        broadcast_id: i64,
+        limit: i64,
+    ) -> ApiResult<Vec<BroadcastDeliveryRow>>;
+    async fn update_recipient_status(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> ApiResult<()>;
+    /// Stores what Telegram answered the bot for a dispatched recipient
+    async fn report_delivery(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> ApiResult<()>;
+    async fn fail_undelivered_recipients(
+        &self,
+        broadcast_id: i64,
+        dispatched_before: DateTime<Utc>,
+    ) -> ApiResult<u64>;
+    async fn get_recipient_stats(&self, broadcast_id: i64) -> ApiResult<BroadcastRecipientStats>;
+    async fn get_recipients(
+        &self,
+        broadcast_id: i64,
+        query: BroadcastRecipientListQuery,
+    ) -> ApiResult<PaginatedResult<BroadcastRecipientRow>>;
+    async fn get_variants(&self, broadcast_id: i64) -> ApiResult<Vec<BroadcastVariantRow>>;
+    async fn get_variant_stats(
+        &self,
+        broadcast_id: i64,
+        conversion_days: i32,
+    ) -> ApiResult<Vec<BroadcastVariantStatsRow>>;
 }
 
 pub struct BroadcastService<R, A> {
@@ -86,6 +181,10 @@ impl BroadcastServiceTrait
         self.repo.get_list(query).await.map_err(ApiError::from)
     }
 
+    async fn get_by_id(&self, id: i64) -> ApiResult<BroadcastRow> {
+        self.repo.get_by_id(id).await.map_err(ApiError::from)
+    }
+
     async fn create(&self, command: CreateBroadcastCommand) -> ApiResult<BroadcastRow> {
         let created = self
             .repo
@@ -97,11 +196,16 @@ impl BroadcastServiceTrait
                 },
                 content_text: command.content_text,
                 content_image_id: command.content_image_id,
+                content_entities: command.content_entities,
+                content_media: command.content_media,
+                content_buttons: command.content_buttons,
                 filters: command
                     .filters
                     .map(|f| serde_json::to_value(f).unwrap_or_default()),
+                segment_id: command.segment_id,
                 created_by: command.created_by,
                 scheduled_for: command.scheduled_for,
+                variants: command.variants,
             })
             .await?;
 
@@ -133,6 +237,9 @@ impl BroadcastServiceTrait
                 command.id,
                 UpdateBroadcast {
                     content_image_id: command.content_image_id,
+                    content_entities: command.content_entities,
+                    content_media: command.content_media,
+                    content_buttons: command.content_buttons,
                     content_text: command.content_text,
                     filters: command
                         .filters
@@ -142,6 +249,7 @@ impl BroadcastServiceTrait
                     started_at: command.started_at,
                     finished_at: command.finished_at,
                     statistics: command.statistics,
+                    expected_status: command.expected_status,
                 },
             )
             .await?;
@@ -172,6 +280,192 @@ impl BroadcastServiceTrait
             .await
             .map_err(ApiError::from)
     }
+
+    async fn get_in_progress_broadcasts(&self) -> ApiResult<Vec<BroadcastRow>> {
+        self.repo
+            .get_in_progress_broadcasts()
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn start(
+        &self,
+        id: i64,
+        audience: &CustomerListQuery,
+    ) -> ApiResult<Option<(BroadcastRow, u64)>> {
+        self.repo.start(id, audience).await.map_err(ApiError::from)
+    }
+
+    async fn pause(&self, command: BroadcastActionCommand) -> ApiResult<BroadcastRow> {
+        let broadcast = self.repo.get_by_id(command.id).await?;
+        if !matches!(
+            broadcast.status,
+            BroadcastStatus::Pending | BroadcastStatus::Scheduled | BroadcastStatus::InProgress
+        ) {
+            return Err(ApiError::Conflict(
+                "Only pending, scheduled or running broadcasts can be paused".to_string(),
+            ));
+        }
+
+        let statistics = self.get_recipient_stats(command.id).await?;
+        self.update(UpdateBroadcastCommand {
+            status: Some(BroadcastStatus::Paused),
+            statistics: Some(serde_json::to_value(statistics).ok()),
+            expected_status: Some(vec![broadcast.status]),
+            ..command.into()
+        })
+        .await
+    }
+
+    async fn resume(&self, command: BroadcastActionCommand) -> ApiResult<BroadcastRow> {
+        let broadcast = self.repo.get_by_id(command.id).await?;
+        if broadcast.status != BroadcastStatus::Paused {
+            return Err(ApiError::Conflict(
+                "Only paused broadcasts can be resumed".to_string(),
+            ));
+        }
+
+        // Recipients are materialized on start, so a started broadcast picks up where it stopped
+        let status = if broadcast.started_at.is_some() {
+            BroadcastStatus::InProgress
+        } else if broadcast.scheduled_for.is_some() {
+            BroadcastStatus::Scheduled
+        } else {
+            BroadcastStatus::Pending
+        };
+
+        self.update(UpdateBroadcastCommand {
+            status: Some(status),
+            expected_status: Some(vec![BroadcastStatus::Paused]),
+            ..command.into()
+        })
+        .await
+    }
+
+    async fn cancel(&self, command: BroadcastActionCommand) -> ApiResult<BroadcastRow> {
+        let broadcast = self.repo.get_by_id(command.id).await?;
+        if matches!(
+            broadcast.status,
+            BroadcastStatus::Completed | BroadcastStatus::Cancelled | BroadcastStatus::Failed
+        ) {
+            return Err(ApiError::Conflict(
+                "Broadcast is already finished".to_string(),
+            ));
+        }
+
+        let statistics = self.get_recipient_stats(command.id).await?;
+        self.update(UpdateBroadcastCommand {
+            status: Some(BroadcastStatus::Cancelled),
+            statistics: Some(serde_json::to_value(statistics).ok()),
+            finished_at: Some(Some(Utc::now())),
+            expected_status: Some(vec![broadcast.status]),
+            ..command.into()
+        })
+        .await
+    }
+
+    async fn get_pending_recipients(
+        &self,
+        broadcast_id: i64,
+        limit: i64,
+    ) -> ApiResult<Vec<BroadcastDeliveryRow>> {
+        self.repo
+            .get_pending_recipients(broadcast_id, limit)
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn update_recipient_status(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> ApiResult<()> {
+        self.repo
+            .update_recipient_status(id, status, error_message)
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn report_delivery(
+        &self,
+        id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> ApiResult<()> {
+        if !matches!(
+            status,
+            BroadcastRecipientStatus::Sent
+                | BroadcastRecipientStatus::Blocked
+                | BroadcastRecipientStatus::Failed
+        ) {
+            return Err(ApiError::BadRequest(
+                "Delivery status must be sent, blocked or failed".to_string(),
+            ));
+        }
+        self.repo
+            .report_recipient_delivery(id, status, error_message)
+            .await
+            .map_err(|e| match e {
+                RepositoryError::OptimisticLockViolation => ApiError::Conflict(format!(
+                    "Broadcast recipient {id} is not awaiting a delivery report"
+                )),
+                e => ApiError::from(e),
+            })
+    }
+
+    async fn fail_undelivered_recipients(
+        &self,
+        broadcast_id: i64,
+        dispatched_before: DateTime<Utc>,
+    ) -> ApiResult<u64> {
+        self.repo
+            .fail_undelivered_recipients(
+                broadcast_id,
+                dispatched_before,
+                "No delivery report from the bot",
+            )
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn get_recipient_stats(&self, broadcast_id: i64) -> ApiResult<BroadcastRecipientStats> {
+        self.repo
+            .get_recipient_stats(broadcast_id)
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn get_recipients(
+        &self,
+        broadcast_id: i64,
+        query: BroadcastRecipientListQuery,
+    ) -> ApiResult<PaginatedResult<BroadcastRecipientRow>> {
+        self.repo.get_by_id(broadcast_id).await?;
+        self.repo
+            .get_recipients(broadcast_id, query)
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn get_variants(&self, broadcast_id: i64) -> ApiResult<Vec<BroadcastVariantRow>> {
+        self.repo
+            .get_variants(broadcast_id)
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn get_variant_stats(
+        &self,
+        broadcast_id: i64,
+        conversion_days: i32,
+    ) -> ApiResult<Vec<BroadcastVariantStatsRow>> {
+        self.repo.get_by_id(broadcast_id).await?;
+        self.repo
+            .get_variant_stats(broadcast_id, conversion_days)
+            .await
+            .map_err(ApiError::from)
+    }
 }
 
 #[cfg(test)]
@@ -228,10 +522,15 @@ mod tests {
             .create(CreateBroadcastCommand {
                 content_text: Some("Hello".to_string()),
                 content_image_id: None,
+                content_entities: vec![],
+                content_media: vec![],
+                content_buttons: vec![],
                 filters: None,
                 scheduled_for: None,
                 created_by: admin_id,
                 ctx: Some(build_context()),
+                segment_id: None,
+                variants: vec![],
             })
             .await
             .unwrap();
@@ -244,12 +543,16 @@ mod tests {
                 status: Some(BroadcastStatus::Completed),
                 content_text: Some(Some("Updated".to_string())),
                 content_image_id: None,
+                content_entities: None,
+                content_media: None,
+                content_buttons: None,
                 filters: None,
                 scheduled_for: None,
                 updated_by: Some(admin_id),
                 statistics: None,
                 started_at: None,
                 finished_at: None,
+                expected_status: None,
                 ctx: Some(build_context()),
             })
             .await
@@ -269,10 +572,15 @@ mod tests {
             .create(CreateBroadcastCommand {
                 content_text: Some("Now".to_string()),
                 content_image_id: None,
+                content_entities: vec![],
+                content_media: vec![],
+                content_buttons: vec![],
                 filters: None,
                 scheduled_for: None,
                 created_by: admin_id,
                 ctx: Some(build_context()),
+                segment_id: None,
+                variants: vec![],
             })
             .await
             .unwrap();
@@ -281,10 +589,15 @@ mod tests {
             .create(CreateBroadcastCommand {
                 content_text: Some("Later".to_string()),
                 content_image_id: None,
+                content_entities: vec![],
+                content_media: vec![],
+                content_buttons: vec![],
                 filters: None,
                 scheduled_for: Some(Utc::now() + Duration::minutes(10)),
                 created_by: admin_id,
                 ctx: Some(build_context()),
+                segment_id: None,
+                variants: vec![],
             })
             .await
             .unwrap();
@@ -301,4 +614,59 @@ mod tests {
                 .any(|b| b.content_text.as_deref() == Some("Later"))
         );
     }
+
+    #[sqlx::test]
+    async fn test_pause_resume_and_cancel(pool: PgPool) {
+        let service = build_service(&pool);
+        let admin_id = create_admin_user(&pool, "broadcast_admin_3").await;
+
+        let created = service
+            .create(CreateBroadcastCommand {
+                content_text: Some("Later".to_string()),
+                content_image_id: None,
+                content_entities: vec![],
+                content_media: vec![],
+                content_buttons: vec![],
+                filters: None,
+                scheduled_for: Some(Utc::now() + Duration::minutes(10)),
+                created_by: admin_id,
+                ctx: Some(build_context()),
+                segment_id: None,
+                variants: vec![],
+            })
+            .await
+            .unwrap();
+        let action = || BroadcastActionCommand {
+            id: created.id,
+            updated_by: admin_id,
+            ctx: Some(build_context()),
+        };
+
+        let paused = service.pause(action()).await.unwrap();
+        assert_eq!(paused.status, BroadcastStatus::Paused);
+        assert!(matches!(
+            service.pause(action()).await,
+            Err(ApiError::Conflict(_))
+        ));
+
+        // Not started yet, so it goes back to its schedule
+        let resumed = service.resume(action()).await.unwrap();
+        assert_eq!(resumed.status, BroadcastStatus::Scheduled);
+        assert!(matches!(
+            service.resume(action()).await,
+            Err(ApiError::Conflict(_))
+        ));
+
+        let cancelled = service.cancel(action()).await.unwrap();
+        assert_eq!(cancelled.status, BroadcastStatus::Cancelled);
+        assert!(cancelled.finished_at.is_some());
+        assert!(matches!(
+            service.cancel(action()).await,
+            Err(ApiError::Conflict(_))
+        ));
+        assert!(matches!(
+            service.resume(action()).await,
+            Err(ApiError::Conflict(_))
+        ));
+    }
 }
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use shared_dtos::currency::{Currency, ExchangeRateResponse, ExchangeRatesResponse};
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::{
+        external::exchange_rates::{
+            ExchangeRateSource, STATIC_SOURCE_NAME, StaticExchangeRateSource,
+        },
+        repositories::exchange_rate::ExchangeRateRepositoryTrait,
+    },
+};
+
+pub const BASE_SOURCE_NAME: &str = "base";
+
+#[async_trait]
+pub trait CurrencyServiceTrait: Send + Sync {
+    /// Currency balances, prices and orders are kept in
+    fn base_currency(&self) -> Currency;
+    /// Every currency with a known rate, the base one included
+    async fn get_rates(&self) -> ApiResult<ExchangeRatesResponse>;
+    /// Units of `currency` for one base unit
+    async fn rate(&self, currency: Currency) -> ApiResult<Decimal>;
+    async fn convert(&self, amount: Decimal, from: Currency, to: Currency) -> ApiResult<Decimal>;
+    /// Fetches rates from the configured source, returns how many were stored
+    async fn refresh_rates(&self) -> ApiResult<usize>;
+}
+
+/// Rates against the store base currency. Fetched rates are stored and win over
+/// `EXCHANGE_RATES_STATIC`, which only covers the time before the first refresh.
+pub struct CurrencyService<R> {
+    repo: Arc<R>,
+    base_currency: Currency,
+    fallback: StaticExchangeRateSource,
+    source: Arc<dyn ExchangeRateSource>,
+}
+
+impl<R> CurrencyService<R>
+where
+    R: ExchangeRateRepositoryTrait + Send + Sync,
+{
+    pub fn new(
+        repo: Arc<R>,
+        base_currency: Currency,
+        fallback: StaticExchangeRateSource,
+        source: Arc<dyn ExchangeRateSource>,
+    ) -> Self {
+        Self {
+            repo,
+            base_currency,
+            fallback,
+            source,
+        }
+    }
+
+    async fn known_rates(&self) -> ApiResult<Vec<(Currency, Decimal, ExchangeRateResponse)>> {
+        let stored = self.repo.get_for_base(self.base_currency).await?;
+        Ok(Currency::ALL
+            .into_iter()
+            .filter_map(|currency| {
+                let (rate, source, updated_at) = if currency == self.base_currency {
+                    (Decimal::ONE, BASE_SOURCE_NAME.to_string(), None)
+                } else if let Some(row) = stored.iter().find(|r| r.currency == currency) {
+                    (row.rate, row.source.clone(), Some(row.updated_at))
+                } else {
+                    let rate = *self.fallback.rates().get(&currency)?;
+                    (rate, STATIC_SOURCE_NAME.to_string(), None)
+                };
+                Some((
+                    currency,
+                    rate,
+                    ExchangeRateResponse {
+                        currency,
+                        rate: rate.to_f64().unwrap_or_default(),
+                        source,
+                        updated_at,
+                    },
+                ))
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<R> CurrencyServiceTrait for CurrencyService<R>
+where
+    R: ExchangeRateRepositoryTrait + Send + Sync,
+{
+    fn base_currency(&self) -> Currency {
+        self.base_currency
+    }
+
+    async fn get_rates(&self) -> ApiResult<ExchangeRatesResponse> {
+        Ok(ExchangeRatesResponse {
+            base_currency: self.base_currency,
+            rates: self
+                .known_rates()
+                .await?
+                .into_iter()
+                .map(|(_, _, response)| response)
+                .collect(),
+        })
+    }
+
+    async fn rate(&self, currency: Currency) -> ApiResult<Decimal> {
+        if currency == self.base_currency {
+            return Ok(Decimal::ONE);
+        }
+        self.known_rates()
+            .await?
+            .into_iter()
+            .find(|(c, _, _)| *c == currency)
+            .map(|(_, rate, _)| rate)
+            .ok_or_else(|| {
+                ApiError::Conflict(format!("Exchange rate for {currency} is not available"))
+            })
+    }
+
+    async fn convert(&self, amount: Decimal, from: Currency, to: Currency) -> ApiResult<Decimal> {
+        if from == to {
+            return Ok(amount);
+        }
+        let from_rate = self.rate(from).await?;
+        let to_rate = self.rate(to).await?;
+        Ok(amount / from_rate * to_rate)
+    }
+
+    async fn refresh_rates(&self) -> ApiResult<usize> {
+        let rates = self
+            .source
+            .fetch_rates(self.base_currency)
+            .await
+            .map_err(ApiError::InternalServerError)?;
+        self.repo
+            .upsert(self.base_currency, &rates, self.source.name())
+            .await?;
+        Ok(rates.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rust_decimal_macros::dec;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::infrastructure::repositories::exchange_rate::ExchangeRateRepository;
+
+    struct FakeSource;
+
+    #[async_trait]
+    impl ExchangeRateSource for FakeSource {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        async fn fetch_rates(&self, _base: Currency) -> Result<HashMap<Currency, Decimal>, String> {
+            Ok(HashMap::from([(Currency::Usd, dec!(0.0125))]))
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_stored_rates_override_static_fallback(pool: PgPool) {
+        let repo = Arc::new(ExchangeRateRepository::new(Arc::new(pool)));
+        let service = CurrencyService::new(
+            repo,
+            Currency::Rub,
+            StaticExchangeRateSource::parse("USD=0.01,EUR=0.01"),
+            Arc::new(FakeSource),
+        );
+
+        assert_eq!(
+            service
+                .convert(dec!(200), Currency::Rub, Currency::Usd)
+                .await
+                .unwrap(),
+            dec!(2)
+        );
+        assert!(matches!(
+            service.rate(Currency::Usdt).await,
+            Err(ApiError::Conflict(_))
+        ));
+
+        assert_eq!(service.refresh_rates().await.unwrap(), 1);
+        assert_eq!(
+            service
+                .convert(dec!(2.5), Currency::Usd, Currency::Eur)
+                .await
+                .unwrap(),
+            dec!(2)
+        );
+        let rates = service.get_rates().await.unwrap();
+        let sources: Vec<_> = rates
+            .rates
+            .iter()
+            .map(|r| (r.currency, r.source.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                (Currency::Rub, "base"),
+                (Currency::Usd, "fake"),
+                (Currency::Eur, "static"),
+            ]
+        );
+    }
+}
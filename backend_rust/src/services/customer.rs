@@ -2,7 +2,10 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use shared_dtos::audit_log::{AuditAction, AuditStatus};
+use shared_dtos::{
+    audit_log::{AuditAction, AuditStatus},
+    currency::Currency,
+};
 
 use crate::{
     errors::api::{ApiError, ApiResult},
@@ -29,6 +32,7 @@ pub struct UpdateCustomerCommand {
     pub last_seen_at: Option<DateTime<Utc>>,
     pub updated_by: Option<i64>,
     pub blocked_until: Option<Option<DateTime<Utc>>>,
+    pub display_currency: Option<Currency>,
     pub ctx: Option<RequestContext>,
 }
 
@@ -101,6 +105,7 @@ impl CustomerServiceTrait
                     last_seen_at: command.last_seen_at,
                     last_seen_with_bot: command.last_seen_with_bot,
                     blocked_until: command.blocked_until,
+                    display_currency: command.display_currency,
                 },
             )
             .await?;
@@ -137,6 +142,7 @@ impl CustomerServiceTrait
                     last_seen_at: Some(Utc::now()),
                     last_seen_with_bot: Some(bot_id),
                     blocked_until: None,
+                    display_currency: None,
                 },
             )
             .await?;
@@ -181,7 +187,10 @@ mod tests {
                 telegram_id, registered_with_bot, last_seen_with_bot, balance
             )
             VALUES ($1, 1, 1, $2)
-            RETURNING *
+            RETURNING
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
             "#,
             telegram_id,
             Decimal::ZERO
@@ -207,6 +216,7 @@ mod tests {
                 updated_by: None,
                 ctx: None,
                 blocked_until: None,
+                display_currency: None,
             })
             .await
             .unwrap();
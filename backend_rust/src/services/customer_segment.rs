@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shared_dtos::{
+    audit_log::{AuditAction, AuditStatus},
+    broadcast::JsonRawListQuery,
+};
+
+use crate::{
+    errors::{
+        api::{ApiError, ApiResult},
+        repository::RepositoryError,
+    },
+    infrastructure::repositories::customer_segment::CustomerSegmentRepositoryTrait,
+    middlewares::context::RequestContext,
+    models::{
+        audit_log::NewAuditLog,
+        customer::CustomerListQuery,
+        customer_segment::{CustomerSegmentRow, NewCustomerSegment, UpdateCustomerSegment},
+    },
+    services::audit_log::AuditLogServiceTrait,
+};
+
+#[derive(Debug)]
+pub struct CreateCustomerSegmentCommand {
+    pub name: String,
+    pub description: Option<String>,
+    pub filters: JsonRawListQuery,
+    pub created_by: i64,
+    pub ctx: Option<RequestContext>,
+}
+
+#[derive(Debug)]
+pub struct UpdateCustomerSegmentCommand {
+    pub id: i64,
+    pub name: Option<String>,
+    pub description: Option<Option<String>>,
+    pub filters: Option<JsonRawListQuery>,
+    pub updated_by: i64,
+    pub ctx: Option<RequestContext>,
+}
+
+#[derive(Debug)]
+pub struct DeleteCustomerSegmentCommand {
+    pub id: i64,
+    pub deleted_by: i64,
+    pub ctx: Option<RequestContext>,
+}
+
+#[async_trait]
+pub trait CustomerSegmentServiceTrait: Send + Sync {
+    async fn get_list(&self) -> ApiResult<Vec<CustomerSegmentRow>>;
+    async fn get_by_id(&self, id: i64) -> ApiResult<CustomerSegmentRow>;
+    /// Filters of a segment, ready to be combined with other broadcast filters
+    async fn get_filters(&self, id: i64) -> ApiResult<JsonRawListQuery>;
+    async fn create(&self, command: CreateCustomerSegmentCommand) -> ApiResult<CustomerSegmentRow>;
+    async fn update(&self, command: UpdateCustomerSegmentCommand) -> ApiResult<CustomerSegmentRow>;
+    async fn delete(&self, command: DeleteCustomerSegmentCommand) -> ApiResult<()>;
+}
+
+pub struct CustomerSegmentService<R, A> {
+    repo: Arc<R>,
+    audit_log_service: Arc<A>,
+}
+
+impl<R, A> CustomerSegmentService<R, A>
+where
+    R: CustomerSegmentRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    pub fn new(repo: Arc<R>, audit_log_service: Arc<A>) -> Self {
+        Self {
+            repo,
+            audit_log_service,
+        }
+    }
+}
+
+fn filters_to_value(filters: JsonRawListQuery) -> ApiResult<serde_json::Value> {
+    CustomerListQuery::try_from_json(filters.clone()).map_err(ApiError::BadRequest)?;
+    serde_json::to_value(filters).map_err(|e| ApiError::InternalServerError(e.to_string()))
+}
+
+fn map_name_conflict(err: RepositoryError) -> ApiError {
+    match err {
+        RepositoryError::UniqueViolation(_) => {
+            ApiError::Conflict("Segment with this name already exists".to_string())
+        }
+        err => ApiError::from(err),
+    }
+}
+
+#[async_trait]
+impl<R, A> CustomerSegmentServiceTrait for CustomerSegmentService<R, A>
+where
+    R: CustomerSegmentRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    async fn get_list(&self) -> ApiResult<Vec<CustomerSegmentRow>> {
+        self.repo.get_list().await.map_err(ApiError::from)
+    }
+
+    async fn get_by_id(&self, id: i64) -> ApiResult<CustomerSegmentRow> {
+        self.repo.get_by_id(id).await.map_err(ApiError::from)
+    }
+
+    async fn get_filters(&self, id: i64) -> ApiResult<JsonRawListQuery> {
+        let segment = self.get_by_id(id).await?;
+        serde_json::from_value(segment.filters)
+            .map_err(|e| ApiError::InternalServerError(format!("Invalid segment filters: {e}")))
+    }
+
+    async fn create(&self, command: CreateCustomerSegmentCommand) -> ApiResult<CustomerSegmentRow> {
+        let created = self
+            .repo
+            .create(NewCustomerSegment {
+                name: command.name,
+                description: command.description,
+                filters: filters_to_value(command.filters)?,
+                created_by: command.created_by,
+            })
+            .await
+            .map_err(map_name_conflict)?;
+
+        self.audit_log_service
+            .create(NewAuditLog {
+                action: AuditAction::CustomerSegmentCreate,
+                status: AuditStatus::Success,
+                admin_user_id: Some(command.created_by),
+                customer_id: None,
+                error_message: None,
+                new_values: serde_json::to_value(created.clone()).ok(),
+                old_values: None,
+                target_id: created.id.to_string(),
+                target_table: "customer_segments".to_string(),
+                ip_address: command.ctx.clone().and_then(|ctx| ctx.ip_address),
+                request_id: command.ctx.clone().map(|ctx| ctx.request_id),
+                user_agent: command.ctx.and_then(|ctx| ctx.user_agent),
+            })
+            .await?;
+
+        Ok(created)
+    }
+
+    async fn update(&self, command: UpdateCustomerSegmentCommand) -> ApiResult<CustomerSegmentRow> {
+        let prev = self.get_by_id(command.id).await?;
+        let filters = command.filters.map(filters_to_value).transpose()?;
+        let updated = self
+            .repo
+            .update(
+                command.id,
+                UpdateCustomerSegment {
+                    name: command.name,
+                    description: command.description,
+                    filters,
+                },
+            )
+            .await
+            .map_err(map_name_conflict)?;
+
+        self.audit_log_service
+            .create(NewAuditLog {
+                action: AuditAction::CustomerSegmentUpdate,
+                status: AuditStatus::Success,
+                admin_user_id: Some(command.updated_by),
+                customer_id: None,
+                error_message: None,
+                new_values: serde_json::to_value(updated.clone()).ok(),
+                old_values: serde_json::to_value(prev.clone()).ok(),
+                target_id: prev.id.to_string(),
+                target_table: "customer_segments".to_string(),
+                ip_address: command.ctx.clone().and_then(|ctx| ctx.ip_address),
+                request_id: command.ctx.clone().map(|ctx| ctx.request_id),
+                user_agent: command.ctx.and_then(|ctx| ctx.user_agent),
+            })
+            .await?;
+
+        Ok(updated)
+    }
+
+    async fn delete(&self, command: DeleteCustomerSegmentCommand) -> ApiResult<()> {
+        let prev = self.get_by_id(command.id).await?;
+        self.repo.delete(command.id).await?;
+
+        self.audit_log_service
+            .create(NewAuditLog {
+                action: AuditAction::CustomerSegmentDelete,
+                status: AuditStatus::Success,
+                admin_user_id: Some(command.deleted_by),
+                customer_id: None,
+                error_message: None,
+                new_values: None,
+                old_values: serde_json::to_value(prev.clone()).ok(),
+                target_id: prev.id.to_string(),
+                target_table: "customer_segments".to_string(),
+                ip_address: command.ctx.clone().and_then(|ctx| ctx.ip_address),
+                request_id: command.ctx.clone().map(|ctx| ctx.request_id),
+                user_agent: command.ctx.and_then(|ctx| ctx.user_agent),
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::repositories::{
+        audit_log::AuditLogRepository, customer_segment::CustomerSegmentRepository,
+    };
+    use crate::services::audit_log::AuditLogService;
+    use sqlx::PgPool;
+
+    fn build_service(
+        pool: &PgPool,
+    ) -> CustomerSegmentService<CustomerSegmentRepository, AuditLogService<AuditLogRepository>>
+    {
+        let pool = Arc::new(pool.clone());
+        let audit_log_service = Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+            pool.clone(),
+        ))));
+        CustomerSegmentService::new(
+            Arc::new(CustomerSegmentRepository::new(pool)),
+            audit_log_service,
+        )
+    }
+
+    fn raw_filters(json: serde_json::Value) -> JsonRawListQuery {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn create_command(name: &str, filters: JsonRawListQuery) -> CreateCustomerSegmentCommand {
+        CreateCustomerSegmentCommand {
+            name: name.to_string(),
+            description: None,
+            filters,
+            created_by: 1,
+            ctx: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_create_segment_with_aggregate_filters(pool: PgPool) {
+        let service = build_service(&pool);
+
+        let created = service
+            .create(create_command(
+                "Bought in last 30 days",
+                raw_filters(serde_json::json!({
+                    "filters": [{"field": "days_since_last_order", "op": "le", "value": 30}]
+                })),
+            ))
+            .await
+            .unwrap();
+
+        let filters = service.get_filters(created.id).await.unwrap();
+        assert_eq!(filters.filters.len(), 1);
+        assert_eq!(filters.filters[0].field, "days_since_last_order");
+    }
+
+    #[sqlx::test]
+    async fn test_create_segment_rejects_unknown_fields(pool: PgPool) {
+        let service = build_service(&pool);
+
+        let result = service
+            .create(create_command(
+                "Broken",
+                raw_filters(serde_json::json!({
+                    "filters": [{"field": "password", "op": "eq", "value": "x"}]
+                })),
+            ))
+            .await;
+
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_duplicate_segment_name_conflicts(pool: PgPool) {
+        let service = build_service(&pool);
+        let filters = || raw_filters(serde_json::json!({"filters": []}));
+
+        service
+            .create(create_command("Everyone", filters()))
+            .await
+            .unwrap();
+        let result = service.create(create_command("Everyone", filters())).await;
+
+        assert!(matches!(result, Err(ApiError::Conflict(_))));
+    }
+}
@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use shared_dtos::invoice::{GatewayCircuitState, GatewayHealthAdminResponse, PaymentSystem};
+
+use crate::{
+    errors::api::ApiResult,
+    infrastructure::repositories::gateway_attempt::GatewayAttemptRepositoryTrait,
+    models::gateway_attempt::{GatewayAttemptOutcome, GatewayAttemptStatsRow, NewGatewayAttempt},
+};
+
+#[async_trait]
+pub trait GatewayHealthServiceTrait: Send + Sync {
+    async fn record_attempt(
+        &self,
+        gateway: PaymentSystem,
+        outcome: GatewayAttemptOutcome,
+        latency: std::time::Duration,
+    ) -> ApiResult<()>;
+    /// Stats of attempts made since `since`, with the circuit state of each gateway
+    async fn get_health(&self, since: DateTime<Utc>) -> ApiResult<Vec<GatewayHealthAdminResponse>>;
+    /// Gateways whose circuit is open, they aren't offered to customers
+    async fn get_unavailable(&self) -> ApiResult<Vec<PaymentSystem>>;
+    /// Deletes attempts older than the retention period, returns how many were removed
+    async fn delete_expired(&self) -> ApiResult<u64>;
+}
+
+/// Circuit breaker over recent `init_order` attempts. A gateway trips when at least
+/// `min_attempts` in `window` failed at `failure_rate_percent` or more and the last one
+/// failed too; it stays hidden for `cooldown` after the last failure and is offered again
+/// afterwards, the next attempt closing or re-opening the circuit.
+pub struct GatewayHealthService<R> {
+    repo: Arc<R>,
+    window: Duration,
+    min_attempts: i64,
+    failure_rate_percent: i64,
+    cooldown: Duration,
+    retention: Duration,
+}
+
+impl<R> GatewayHealthService<R>
+where
+    R: GatewayAttemptRepositoryTrait + Send + Sync,
+{
+    pub fn new(
+        repo: Arc<R>,
+        window: Duration,
+        min_attempts: i64,
+        failure_rate_percent: i64,
+        cooldown: Duration,
+        retention: Duration,
+    ) -> Self {
+        Self {
+            repo,
+            window,
+            min_attempts,
+            failure_rate_percent,
+            cooldown,
+            retention,
+        }
+    }
+
+    fn circuit(
+        &self,
+        stats: &GatewayAttemptStatsRow,
+        now: DateTime<Utc>,
+    ) -> (GatewayCircuitState, Option<DateTime<Utc>>) {
+        let tripped = stats.attempts >= self.min_attempts
+            && stats.failures * 100 >= self.failure_rate_percent * stats.attempts
+            && stats.last_outcome != GatewayAttemptOutcome::Success;
+        if !tripped {
+            return (GatewayCircuitState::Closed, None);
+        }
+        let open_until = stats.last_attempt_at + self.cooldown;
+        if now < open_until {
+            (GatewayCircuitState::Open, Some(open_until))
+        } else {
+            (GatewayCircuitState::HalfOpen, None)
+        }
+    }
+}
+
+#[async_trait]
+impl<R> GatewayHealthServiceTrait for GatewayHealthService<R>
+where
+    R: GatewayAttemptRepositoryTrait + Send + Sync,
+{
+    async fn record_attempt(
+        &self,
+        gateway: PaymentSystem,
+        outcome: GatewayAttemptOutcome,
+        latency: std::time::Duration,
+    ) -> ApiResult<()> {
+        self.repo
+            .create(NewGatewayAttempt {
+                gateway,
+                outcome,
+                latency_ms: latency.as_millis().min(i32::MAX as u128) as i32,
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn get_health(&self, since: DateTime<Utc>) -> ApiResult<Vec<GatewayHealthAdminResponse>> {
+        let now = Utc::now();
+        let stats = self.repo.get_stats(since).await?;
+        let recent = self.repo.get_stats(now - self.window).await?;
+
+        let mut gateways: Vec<PaymentSystem> = stats.iter().map(|s| s.gateway).collect();
+        for row in &recent {
+            if !gateways.contains(&row.gateway) {
+                gateways.push(row.gateway);
+            }
+        }
+
+        Ok(gateways
+            .into_iter()
+            .map(|gateway| {
+                let (circuit, open_until) = recent
+                    .iter()
+                    .find(|r| r.gateway == gateway)
+                    .map(|r| self.circuit(r, now))
+                    .unwrap_or((GatewayCircuitState::Closed, None));
+                let row = stats.iter().find(|s| s.gateway == gateway);
+                GatewayHealthAdminResponse {
+                    gateway,
+                    attempts: row.map_or(0, |r| r.attempts),
+                    successes: row.map_or(0, |r| r.successes),
+                    failures: row.map_or(0, |r| r.failures),
+                    no_requisites: row.map_or(0, |r| r.no_requisites),
+                    success_rate: row.map(|r| r.successes as f64 / r.attempts as f64),
+                    avg_latency_ms: row.and_then(|r| r.avg_latency_ms),
+                    p95_latency_ms: row.and_then(|r| r.p95_latency_ms),
+                    last_success_at: row.and_then(|r| r.last_success_at),
+                    last_failure_at: row.and_then(|r| r.last_failure_at),
+                    circuit,
+                    open_until,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_unavailable(&self) -> ApiResult<Vec<PaymentSystem>> {
+        let now = Utc::now();
+        let recent = self.repo.get_stats(now - self.window).await?;
+        Ok(recent
+            .iter()
+            .filter(|r| self.circuit(r, now).0 == GatewayCircuitState::Open)
+            .map(|r| r.gateway)
+            .collect())
+    }
+
+    async fn delete_expired(&self) -> ApiResult<u64> {
+        Ok(self
+            .repo
+            .delete_older_than(Utc::now() - self.retention)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::infrastructure::repositories::gateway_attempt::GatewayAttemptRepository;
+
+    fn service(pool: PgPool, cooldown: Duration) -> GatewayHealthService<GatewayAttemptRepository> {
+        GatewayHealthService::new(
+            Arc::new(GatewayAttemptRepository::new(Arc::new(pool))),
+            Duration::minutes(15),
+            3,
+            50,
+            cooldown,
+            Duration::days(30),
+        )
+    }
+
+    async fn record(
+        service: &GatewayHealthService<GatewayAttemptRepository>,
+        outcome: GatewayAttemptOutcome,
+    ) {
+        service
+            .record_attempt(
+                PaymentSystem::PlatformCard,
+                outcome,
+                std::time::Duration::from_millis(120),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_circuit_opens_after_failures(pool: PgPool) {
+        let service = service(pool, Duration::minutes(5));
+
+        record(&service, GatewayAttemptOutcome::Success).await;
+        record(&service, GatewayAttemptOutcome::Error).await;
+        // Too few attempts to judge
+        assert!(service.get_unavailable().await.unwrap().is_empty());
+
+        record(&service, GatewayAttemptOutcome::NoRequisites).await;
+        assert_eq!(
+            service.get_unavailable().await.unwrap(),
+            vec![PaymentSystem::PlatformCard]
+        );
+
+        let health = service
+            .get_health(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(health.len(), 1);
+        assert_eq!(health[0].attempts, 3);
+        assert_eq!(health[0].failures, 2);
+        assert_eq!(health[0].no_requisites, 1);
+        assert_eq!(health[0].circuit, GatewayCircuitState::Open);
+        assert!(health[0].open_until.unwrap() > Utc::now());
+        assert!((health[0].success_rate.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+
+        // A success after the circuit was probed closes it again
+        record(&service, GatewayAttemptOutcome::Success).await;
+        assert!(service.get_unavailable().await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_circuit_half_opens_after_cooldown(pool: PgPool) {
+        let service = service(pool, Duration::zero());
+        for _ in 0..3 {
+            record(&service, GatewayAttemptOutcome::Error).await;
+        }
+
+        assert!(service.get_unavailable().await.unwrap().is_empty());
+        let health = service
+            .get_health(Utc::now() - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(health[0].circuit, GatewayCircuitState::HalfOpen);
+        assert_eq!(health[0].success_rate, Some(0.0));
+        assert!(health[0].open_until.is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use std::{future::Future, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use deadpool_redis::{Pool, redis};
+use shared_dtos::health::{
+    ComponentHealthResponse, ComponentStatus, ReadinessResponse, ReadinessStatus,
+    WorkerHealthResponse,
+};
+use tokio::{
+    sync::RwLock,
+    time::{Duration, Instant, timeout},
+};
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::worker_heartbeat::WorkerHeartbeatRepositoryTrait,
+};
+
+pub const PAYMENT_PLATFORM_COMPONENT: &str = "payment_platform";
+
+#[async_trait]
+pub trait HealthServiceTrait: Send + Sync {
+    /// Checks every dependency, results are reused for `cache_ttl`
+    async fn readiness(&self) -> ReadinessResponse;
+    /// Degraded mode: the bot stops offering payments while the platform is unreachable
+    async fn payments_available(&self) -> bool;
+    async fn record_worker_run(&self, worker: &str, errors: i32) -> ApiResult<()>;
+}
+
+pub struct HealthService<R> {
+    repo: Arc<R>,
+    client: Arc<reqwest::Client>,
+    redis_pool: Option<Pool>,
+    captcha_url: String,
+    payment_platform_url: String,
+    check_timeout: Duration,
+    cache_ttl: Duration,
+    cache: RwLock<Option<(Instant, ReadinessResponse)>>,
+}
+
+impl<R> HealthService<R>
+where
+    R: WorkerHeartbeatRepositoryTrait + Send + Sync,
+{
+    pub fn new(
+        repo: Arc<R>,
+        client: Arc<reqwest::Client>,
+        redis_pool: Option<Pool>,
+        captcha_url: String,
+        payment_platform_url: String,
+        check_timeout: Duration,
+        cache_ttl: Duration,
+    ) -> Self {
+        Self {
+            repo,
+            client,
+            redis_pool,
+            captcha_url,
+            payment_platform_url,
+            check_timeout,
+            cache_ttl,
+            cache: RwLock::new(None),
+        }
+    }
+
+    async fn check_redis(&self, pool: &Pool) -> Result<(), String> {
+        let mut conn = pool.get().await.map_err(|e| e.to_string())?;
+        redis::cmd("PING")
+            .query_async::<String>(&mut *conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Any response short of a server error means the service is reachable
+    async fn check_http(&self, url: &str) -> Result<(), String> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_server_error() {
+            return Err(format!("Responded with {}", response.status()));
+        }
+        Ok(())
+    }
+
+    async fn check(
+        &self,
+        name: &str,
+        critical: bool,
+        check: impl Future<Output = Result<(), String>>,
+    ) -> ComponentHealthResponse {
+        let started_at = Instant::now();
+        let result = match timeout(self.check_timeout, check).await {
+            Ok(result) => result,
+            Err(_) => Err(format!(
+                "Timed out after {}ms",
+                self.check_timeout.as_millis()
+            )),
+        };
+        if let Err(e) = &result {
+            tracing::warn!(component = name, error = %e, "Readiness check failed");
+        }
+        ComponentHealthResponse {
+            name: name.to_string(),
+            status: match result {
+                Ok(()) => ComponentStatus::Up,
+                Err(_) => ComponentStatus::Down,
+            },
+            critical,
+            latency_ms: started_at.elapsed().as_millis() as i64,
+            error: result.err(),
+        }
+    }
+
+    async fn run_checks(&self) -> ReadinessResponse {
+        let postgres = self.check("postgres", true, async {
+            self.repo.ping().await.map_err(|e| e.to_string())
+        });
+        let redis = async {
+            match &self.redis_pool {
+                Some(pool) => Some(self.check("redis", false, self.check_redis(pool)).await),
+                None => None,
+            }
+        };
+        let captcha = self.check("captcha", false, self.check_http(&self.captcha_url));
+        let payment_platform = self.check(
+            PAYMENT_PLATFORM_COMPONENT,
+            false,
+            self.check_http(&self.payment_platform_url),
+        );
+        let (postgres, redis, captcha, payment_platform) =
+            tokio::join!(postgres, redis, captcha, payment_platform);
+
+        let workers = match self.repo.get_all().await {
+            Ok(rows) => rows
+                .into_iter()
+                .map(|row| WorkerHealthResponse {
+                    worker: row.worker,
+                    last_run_at: row.last_run_at,
+                    last_success_at: row.last_success_at,
+                    last_run_errors: row.last_run_errors,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load worker heartbeats");
+                Vec::new()
+            }
+        };
+
+        let components = [Some(postgres), redis, Some(captcha), Some(payment_platform)]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let down = |critical: bool| {
+            components
+                .iter()
+                .any(|c| c.critical == critical && c.status == ComponentStatus::Down)
+        };
+        let status = if down(true) {
+            ReadinessStatus::Unavailable
+        } else if down(false) {
+            ReadinessStatus::Degraded
+        } else {
+            ReadinessStatus::Ok
+        };
+        let payments_available = components
+            .iter()
+            .any(|c| c.name == PAYMENT_PLATFORM_COMPONENT && c.status == ComponentStatus::Up);
+
+        ReadinessResponse {
+            status,
+            payments_available,
+            components,
+            workers,
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+#[async_trait]
+impl<R> HealthServiceTrait for HealthService<R>
+where
+    R: WorkerHeartbeatRepositoryTrait + Send + Sync,
+{
+    async fn readiness(&self) -> ReadinessResponse {
+        if let Some((checked_at, report)) = self.cache.read().await.as_ref()
+            && checked_at.elapsed() < self.cache_ttl
+        {
+            return report.clone();
+        }
+
+        let report = self.run_checks().await;
+        *self.cache.write().await = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    async fn payments_available(&self) -> bool {
+        self.readiness().await.payments_available
+    }
+
+    async fn record_worker_run(&self, worker: &str, errors: i32) -> ApiResult<()> {
+        self.repo
+            .record_run(worker, errors)
+            .await
+            .map_err(ApiError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::infrastructure::repositories::worker_heartbeat::WorkerHeartbeatRepository;
+
+    /// Accepts connections and never answers, so checks against it time out
+    async fn hanging_url() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        (listener, url)
+    }
+
+    /// Nothing listens there once the listener is dropped
+    async fn closed_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    fn service(
+        pool: PgPool,
+        captcha_url: String,
+        payment_platform_url: String,
+        cache_ttl: Duration,
+    ) -> HealthService<WorkerHeartbeatRepository> {
+        HealthService::new(
+            Arc::new(WorkerHeartbeatRepository::new(Arc::new(pool))),
+            Arc::new(reqwest::Client::new()),
+            None,
+            captcha_url,
+            payment_platform_url,
+            Duration::from_millis(300),
+            cache_ttl,
+        )
+    }
+
+    #[sqlx::test]
+    async fn test_unreachable_platform_degrades_and_disables_payments(pool: PgPool) {
+        let (_listener, hanging) = hanging_url().await;
+        let service = service(pool, hanging, closed_url().await, Duration::ZERO);
+        service.record_worker_run("broadcasts", 0).await.unwrap();
+
+        let report = service.readiness().await;
+        assert_eq!(report.status, ReadinessStatus::Degraded);
+        assert!(!report.payments_available);
+        assert!(!service.payments_available().await);
+
+        let status = |name: &str| {
+            report
+                .components
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(status("postgres").status, ComponentStatus::Up);
+        let captcha = status("captcha");
+        assert_eq!(captcha.status, ComponentStatus::Down);
+        assert!(captcha.error.unwrap().starts_with("Timed out"));
+        assert_eq!(
+            status(PAYMENT_PLATFORM_COMPONENT).status,
+            ComponentStatus::Down
+        );
+        // Redis isn't configured, so it isn't reported
+        assert!(report.components.iter().all(|c| c.name != "redis"));
+
+        assert_eq!(report.workers.len(), 1);
+        assert_eq!(report.workers[0].worker, "broadcasts");
+        assert!(report.workers[0].last_success_at.is_some());
+    }
+
+    #[sqlx::test]
+    async fn test_readiness_is_cached(pool: PgPool) {
+        let service = service(
+            pool,
+            closed_url().await,
+            closed_url().await,
+            Duration::from_secs(60),
+        );
+
+        let first = service.readiness().await;
+        let second = service.readiness().await;
+        assert_eq!(first.checked_at, second.checked_at);
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::idempotency_key::IdempotencyKeyRepositoryTrait,
+    models::idempotency_key::{NewIdempotencyKey, StoredResponse},
+};
+
+#[derive(Debug)]
+pub enum IdempotencyOutcome {
+    /// The key is claimed by the caller, which must `complete` or `release` it
+    Started,
+    /// A previous request with the same key and payload already finished
+    Replay(StoredResponse),
+}
+
+#[async_trait]
+pub trait IdempotencyServiceTrait: Send + Sync {
+    async fn begin(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> ApiResult<IdempotencyOutcome>;
+    async fn complete(&self, scope: &str, key: &str, response: StoredResponse) -> ApiResult<()>;
+    async fn release(&self, scope: &str, key: &str) -> ApiResult<()>;
+    async fn delete_expired(&self) -> ApiResult<u64>;
+}
+
+pub struct IdempotencyService<R> {
+    repo: Arc<R>,
+    ttl: Duration,
+    lock_timeout: Duration,
+}
+
+impl<R> IdempotencyService<R>
+where
+    R: IdempotencyKeyRepositoryTrait + Send + Sync,
+{
+    pub fn new(repo: Arc<R>, ttl: Duration, lock_timeout: Duration) -> Self {
+        Self {
+            repo,
+            ttl,
+            lock_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl<R> IdempotencyServiceTrait for IdempotencyService<R>
+where
+    R: IdempotencyKeyRepositoryTrait + Send + Sync,
+{
+    async fn begin(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> ApiResult<IdempotencyOutcome> {
+        let now = Utc::now();
+        let claimed = self
+            .repo
+            .claim(
+                NewIdempotencyKey {
+                    scope: scope.to_string(),
+                    key: key.to_string(),
+                    request_hash: request_hash.to_string(),
+                    expires_at: now + self.ttl,
+                },
+                now - self.lock_timeout,
+            )
+            .await?;
+        if claimed.is_some() {
+            return Ok(IdempotencyOutcome::Started);
+        }
+
+        let existing = self.repo.get(scope, key).await?.ok_or_else(|| {
+            // Released or expired between the two queries
+            ApiError::Conflict("Request with this Idempotency-Key is being processed".to_string())
+        })?;
+
+        if existing.request_hash != request_hash {
+            return Err(ApiError::BadRequest(
+                "Idempotency-Key was already used for a different request".to_string(),
+            ));
+        }
+
+        match (existing.status_code, existing.response_body) {
+            (Some(status_code), Some(body)) => Ok(IdempotencyOutcome::Replay(StoredResponse {
+                status_code,
+                body,
+                content_type: existing.content_type,
+            })),
+            _ => Err(ApiError::Conflict(
+                "Request with this Idempotency-Key is being processed".to_string(),
+            )),
+        }
+    }
+
+    async fn complete(&self, scope: &str, key: &str, response: StoredResponse) -> ApiResult<()> {
+        self.repo.complete(scope, key, response).await?;
+        Ok(())
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> ApiResult<()> {
+        self.repo.release(scope, key).await?;
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> ApiResult<u64> {
+        Ok(self.repo.delete_expired().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::infrastructure::repositories::idempotency_key::IdempotencyKeyRepository;
+
+    fn build_service(pool: PgPool) -> IdempotencyService<IdempotencyKeyRepository> {
+        IdempotencyService::new(
+            Arc::new(IdempotencyKeyRepository::new(Arc::new(pool))),
+            Duration::hours(24),
+            Duration::seconds(60),
+        )
+    }
+
+    #[sqlx::test]
+    async fn test_duplicate_request_is_replayed(pool: PgPool) {
+        let service = build_service(pool);
+
+        let first = service.begin("7", "cb:1", "hash").await.unwrap();
+        assert!(matches!(first, IdempotencyOutcome::Started));
+
+        // Still running
+        let err = service.begin("7", "cb:1", "hash").await.unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(_)));
+
+        service
+            .complete(
+                "7",
+                "cb:1",
+                StoredResponse {
+                    status_code: 200,
+                    body: b"{\"ok\":true}".to_vec(),
+                    content_type: Some("application/json".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+
+        match service.begin("7", "cb:1", "hash").await.unwrap() {
+            IdempotencyOutcome::Replay(stored) => {
+                assert_eq!(stored.status_code, 200);
+                assert_eq!(stored.body, b"{\"ok\":true}");
+            }
+            other => panic!("expected replay, got {other:?}"),
+        }
+
+        // Reusing the key for another payload is rejected
+        let err = service.begin("7", "cb:1", "other").await.unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+    }
+
+    #[sqlx::test]
+    async fn test_released_key_can_be_retried(pool: PgPool) {
+        let service = build_service(pool);
+
+        service.begin("7", "cb:2", "hash").await.unwrap();
+        service.release("7", "cb:2").await.unwrap();
+
+        let retry = service.begin("7", "cb:2", "hash").await.unwrap();
+        assert!(matches!(retry, IdempotencyOutcome::Started));
+    }
+}
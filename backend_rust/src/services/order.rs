@@ -16,7 +16,9 @@ use crate::{
         common::PaginatedResult,
         order::{NewOrder, OrderListQuery, OrderRow},
         order_item::OrderItemRow,
+        status_transition::{OrderTransition, StatusMachine, StatusTransition},
     },
+    services::status_transition::TransitionPublisher,
 };
 
 #[derive(Debug, Clone, Serialize)]
@@ -41,11 +43,14 @@ pub trait OrderServiceTrait: Send + Sync {
     async fn get_for_customer(&self, customer_id: i64) -> ApiResult<Vec<EnrichedOrder>>;
     async fn get_by_id(&self, id: i64) -> ApiResult<EnrichedOrder>;
     async fn create(&self, order: NewOrder) -> ApiResult<OrderRow>;
+    /// Moves the order to `to` if the transition is allowed and nobody moved it first
+    async fn transition(&self, id: i64, to: OrderStatus) -> ApiResult<OrderRow>;
 }
 
 pub struct OrderService<R, OI> {
     order_repo: Arc<R>,
     order_item_repo: Arc<OI>,
+    transitions: Arc<TransitionPublisher<OrderTransition>>,
 }
 
 impl<R, OI> OrderService<R, OI>
@@ -53,10 +58,15 @@ where
     R: OrderRepositoryTrait + Send + Sync,
     OI: OrderItemRepositoryTrait + Send + Sync,
 {
-    pub fn new(order_repo: Arc<R>, order_item_repo: Arc<OI>) -> Self {
+    pub fn new(
+        order_repo: Arc<R>,
+        order_item_repo: Arc<OI>,
+        transitions: Arc<TransitionPublisher<OrderTransition>>,
+    ) -> Self {
         Self {
             order_repo,
             order_item_repo,
+            transitions,
         }
     }
 }
@@ -86,7 +96,8 @@ impl OrderServiceTrait for OrderService<OrderRepository, OrderItemRepository> {
                 });
 
         Ok(PaginatedResult {
-            total: orders.items.len() as i64,
+            total: orders.total,
+            next_cursor: orders.next_cursor,
             items: orders
                 .items
                 .iter()
@@ -181,16 +192,39 @@ impl OrderServiceTrait for OrderService<OrderRepository, OrderItemRepository> {
         let res = self.order_repo.create(order).await?;
         Ok(res)
     }
+
+    async fn transition(&self, id: i64, to: OrderStatus) -> ApiResult<OrderRow> {
+        let from = self.order_repo.get_by_id(id).await?.status;
+        if !from.can_transition_to(to) {
+            return Err(ApiError::Conflict(format!(
+                "Order can't move from {from:?} to {to:?}"
+            )));
+        }
+        let res = self.order_repo.update_status(id, from, to).await?;
+        self.transitions
+            .publish(&StatusTransition {
+                from,
+                to,
+                row: res.clone(),
+            })
+            .await;
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::infrastructure::repositories::{
-        order::OrderRepository, order_item::OrderItemRepository,
+        audit_log::AuditLogRepository, order::OrderRepository, order_item::OrderItemRepository,
     };
     use crate::models::order_item::NewOrderItem;
+    use crate::services::{
+        audit_log::{AuditLogService, AuditLogServiceTrait},
+        status_transition::TransitionAuditSubscriber,
+    };
     use rust_decimal::Decimal;
+    use shared_dtos::audit_log::AuditAction;
     use sqlx::PgPool;
     use std::collections::HashMap;
     use std::sync::Arc;
@@ -242,6 +276,7 @@ mod tests {
         OrderService::new(
             Arc::new(OrderRepository::new(pool.clone())),
             Arc::new(OrderItemRepository::new(pool.clone())),
+            Arc::new(TransitionPublisher::default()),
         )
     }
 
@@ -461,4 +496,57 @@ mod tests {
         assert_eq!(created.status, OrderStatus::Created);
         assert_eq!(created.bot_id, bot_id);
     }
+
+    #[sqlx::test]
+    async fn test_transition_validates_and_audits(pool: PgPool) {
+        let audit_log_service = Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+            Arc::new(pool.clone()),
+        ))));
+        let service = OrderService::new(
+            Arc::new(OrderRepository::new(Arc::new(pool.clone()))),
+            Arc::new(OrderItemRepository::new(Arc::new(pool.clone()))),
+            Arc::new(TransitionPublisher::default().subscribe(Arc::new(
+                TransitionAuditSubscriber::new(audit_log_service.clone()),
+            ))),
+        );
+        let customer_id = create_customer(&pool, 50505).await;
+        let bot_id = create_bot(
+            &pool,
+            Some(customer_id),
+            "order_svc_bot_4",
+            "order_svc_bot_4",
+        )
+        .await;
+        let order = service
+            .create(NewOrder {
+                customer_id,
+                amount: Decimal::from(250),
+                currency: "USD".to_string(),
+                status: OrderStatus::Created,
+                bot_id,
+                paid_at: None,
+                fulfilled_at: None,
+            })
+            .await
+            .unwrap();
+
+        let paid = service
+            .transition(order.id, OrderStatus::Paid)
+            .await
+            .unwrap();
+        assert_eq!(paid.status, OrderStatus::Paid);
+        let err = service
+            .transition(order.id, OrderStatus::Created)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(_)));
+
+        let logs = audit_log_service
+            .get_for_target("orders", &order.id.to_string())
+            .await
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].action, AuditAction::OrderStatusChange);
+        assert_eq!(logs[0].customer_id, Some(customer_id));
+    }
 }
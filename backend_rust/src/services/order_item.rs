@@ -19,6 +19,8 @@ use crate::{
 pub trait OrderItemServiceTrait: Send + Sync {
     async fn get_for_order(&self, order_id: i64) -> ApiResult<Vec<OrderItemRow>>;
     async fn create(&self, order: NewOrderItem) -> ApiResult<OrderItemRow>;
+    /// Puts the items of a cancelled order back in stock
+    async fn return_stock(&self, order_id: i64) -> ApiResult<()>;
 }
 
 pub struct OrderItemService<R, S> {
@@ -61,6 +63,25 @@ impl OrderItemServiceTrait for OrderItemService<OrderItemRepository, StockMoveme
             .await?;
         Ok(res)
     }
+
+    async fn return_stock(&self, order_id: i64) -> ApiResult<()> {
+        let items = self.order_repo.get_for_order(order_id).await?;
+        for item in items {
+            self.stock_movement_repo
+                .create(NewStockMovement {
+                    description: None,
+                    order_id: Some(order_id),
+                    product_id: item.product_id,
+                    // Undoes the sale movement written by `create`
+                    quantity: -(item.quantity as i64),
+                    reference_id: None,
+                    r#type: StockMovementType::Return,
+                    created_by: 1, // System
+                })
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -223,4 +244,55 @@ mod tests {
         assert_eq!(items.len(), 1);
         assert_eq!(items[0].order_id, order_id);
     }
+
+    #[sqlx::test]
+    async fn test_return_stock_undoes_sale(pool: PgPool) {
+        let service = build_service(&pool);
+        let customer_id = create_customer(&pool, 70707).await;
+        let bot_id = create_bot(
+            &pool,
+            Some(customer_id),
+            "order_item_bot_3",
+            "order_item_bot_3",
+        )
+        .await;
+        let product_id = create_product(&pool, "order_item_product_3").await;
+        let order_id = create_order(&pool, customer_id, bot_id).await;
+        let stock_before =
+            sqlx::query_scalar!("SELECT stock FROM products WHERE id = $1", product_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        service
+            .create(NewOrderItem {
+                order_id,
+                product_id,
+                name_at_purchase: "Test Product".to_string(),
+                price_at_purchase: Decimal::from(10),
+                quantity: 3,
+                fulfillment_type: "text".to_string(),
+                fulfillment_content: None,
+                fulfillment_image_id: None,
+                details: None,
+            })
+            .await
+            .unwrap();
+        service.return_stock(order_id).await.unwrap();
+
+        let stock_after =
+            sqlx::query_scalar!("SELECT stock FROM products WHERE id = $1", product_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(stock_after, stock_before);
+        let returned = sqlx::query_scalar!(
+            "SELECT quantity FROM stock_movements WHERE order_id = $1 AND type = 'return'",
+            order_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(returned, -3);
+    }
 }
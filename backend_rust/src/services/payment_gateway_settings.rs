@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use shared_dtos::{
+    audit_log::{AuditAction, AuditStatus},
+    invoice::PaymentSystem,
+};
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::payment_gateway_settings::PaymentGatewaySettingsRepositoryTrait,
+    middlewares::context::RequestContext,
+    models::{
+        audit_log::NewAuditLog,
+        payment_gateway_settings::{PaymentGatewaySettingsRow, UpdatePaymentGatewaySettings},
+    },
+    services::audit_log::AuditLogServiceTrait,
+};
+
+#[derive(Debug, Default)]
+pub struct UpdatePaymentGatewaySettingsCommand {
+    pub updated_by: i64,
+    pub invoice_ttl_minutes: Option<i32>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub amount_step: Option<Decimal>,
+    pub max_open_invoices: Option<Option<i32>>,
+    pub daily_deposit_limit: Option<Option<Decimal>>,
+    pub ctx: Option<RequestContext>,
+}
+
+#[async_trait]
+pub trait PaymentGatewaySettingsServiceTrait: Send + Sync {
+    async fn get_all(&self) -> ApiResult<Vec<PaymentGatewaySettingsRow>>;
+    async fn update(
+        &self,
+        gateway: PaymentSystem,
+        command: UpdatePaymentGatewaySettingsCommand,
+    ) -> ApiResult<PaymentGatewaySettingsRow>;
+}
+
+pub struct PaymentGatewaySettingsService<R, A> {
+    repo: Arc<R>,
+    audit_log_service: Arc<A>,
+}
+
+impl<R, A> PaymentGatewaySettingsService<R, A>
+where
+    R: PaymentGatewaySettingsRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    pub fn new(repo: Arc<R>, audit_log_service: Arc<A>) -> Self {
+        Self {
+            repo,
+            audit_log_service,
+        }
+    }
+}
+
+#[async_trait]
+impl<R, A> PaymentGatewaySettingsServiceTrait for PaymentGatewaySettingsService<R, A>
+where
+    R: PaymentGatewaySettingsRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    async fn get_all(&self) -> ApiResult<Vec<PaymentGatewaySettingsRow>> {
+        let res = self.repo.get_all().await?;
+        Ok(res)
+    }
+
+    async fn update(
+        &self,
+        gateway: PaymentSystem,
+        command: UpdatePaymentGatewaySettingsCommand,
+    ) -> ApiResult<PaymentGatewaySettingsRow> {
+        let prev = self.repo.get_by_gateway(gateway).await?;
+
+        let min_amount = command.min_amount.unwrap_or(prev.min_amount);
+        let max_amount = command.max_amount.unwrap_or(prev.max_amount);
+        if min_amount > max_amount {
+            return Err(ApiError::BadRequest(
+                "min_amount must not exceed max_amount".to_string(),
+            ));
+        }
+
+        let updated = self
+            .repo
+            .update(
+                gateway,
+                UpdatePaymentGatewaySettings {
+                    invoice_ttl_minutes: command.invoice_ttl_minutes,
+                    min_amount: command.min_amount,
+                    max_amount: command.max_amount,
+                    amount_step: command.amount_step,
+                    max_open_invoices: command.max_open_invoices,
+                    daily_deposit_limit: command.daily_deposit_limit,
+                },
+            )
+            .await?;
+
+        self.audit_log_service
+            .create(NewAuditLog {
+                action: AuditAction::SystemSettingsUpdate,
+                status: AuditStatus::Success,
+                admin_user_id: Some(command.updated_by),
+                customer_id: None,
+                error_message: None,
+                new_values: serde_json::to_value(&updated).ok(),
+                old_values: serde_json::to_value(&prev).ok(),
+                target_id: serde_json::to_value(gateway)
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                target_table: "payment_gateway_settings".to_string(),
+                ip_address: command.ctx.clone().and_then(|ctx| ctx.ip_address),
+                request_id: command.ctx.clone().map(|ctx| ctx.request_id),
+                user_agent: command.ctx.and_then(|ctx| ctx.user_agent),
+            })
+            .await?;
+
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        infrastructure::repositories::{
+            audit_log::AuditLogRepository,
+            payment_gateway_settings::PaymentGatewaySettingsRepository,
+        },
+        services::audit_log::AuditLogService,
+    };
+
+    #[sqlx::test]
+    async fn test_update_validates_range_and_audits(pool: PgPool) {
+        let pool = Arc::new(pool);
+        let service = PaymentGatewaySettingsService::new(
+            Arc::new(PaymentGatewaySettingsRepository::new(pool.clone())),
+            Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+                pool.clone(),
+            )))),
+        );
+
+        // The stored maximum is 1,000,000
+        let err = service
+            .update(
+                PaymentSystem::PlatformCard,
+                UpdatePaymentGatewaySettingsCommand {
+                    updated_by: 1,
+                    min_amount: Some(dec!(2000000)),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+
+        let updated = service
+            .update(
+                PaymentSystem::PlatformCard,
+                UpdatePaymentGatewaySettingsCommand {
+                    updated_by: 1,
+                    max_amount: Some(dec!(15000)),
+                    daily_deposit_limit: Some(Some(dec!(30000))),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.max_amount, dec!(15000));
+        assert_eq!(updated.daily_deposit_limit, Some(dec!(30000)));
+
+        let audited = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM audit_logs WHERE target_table = 'payment_gateway_settings' AND target_id = 'platform_card'"
+        )
+        .fetch_one(&*pool)
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(audited, 1);
+    }
+}
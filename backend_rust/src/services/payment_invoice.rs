@@ -1,11 +1,14 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::{Decimal, prelude::FromPrimitive};
 use rust_decimal_macros::dec;
-use shared_dtos::invoice::{InvoiceStatus, PaymentDetails, PaymentSystem};
+use shared_dtos::{
+    currency::Currency,
+    invoice::{InvoiceStatus, PaymentDetails, PaymentSystem},
+};
 use uuid::Uuid;
 
 use crate::{
@@ -15,25 +18,37 @@ use crate::{
             autosales_platform::{
                 AutosalesPlatformPaymentsProviderTrait,
                 dto::{
-                    AutosalesPlatformInitializeOrderRequest, AutosalesPlatformPaymentMethod,
-                    AutosalesPlatformSendReceiptRequest,
+                    AutosalesPlatformError, AutosalesPlatformInitializeOrderRequest,
+                    AutosalesPlatformPaymentMethod, AutosalesPlatformSendReceiptRequest,
                 },
             },
             mock::{MockPaymentsProviderTrait, dto::MockProviderCreateInvoiceRequest},
         },
         repositories::{
-            customer::CustomerRepositoryTrait, payment_invoice::PaymentInvoiceRepositoryTrait,
+            customer::CustomerRepositoryTrait,
+            payment_gateway_settings::PaymentGatewaySettingsRepositoryTrait,
+            payment_invoice::PaymentInvoiceRepositoryTrait,
+            payment_invoice_event::PaymentInvoiceEventRepositoryTrait,
             settings::SettingsRepositoryTrait,
         },
     },
     models::{
         common::PaginatedResult,
         customer::UpdateCustomer,
+        gateway_attempt::GatewayAttemptOutcome,
+        payment_gateway_settings::PaymentGatewaySettingsRow,
         payment_invoice::{
-            NewPaymentInvoice, PaymentInvoiceListQuery, PaymentInvoiceRow, UpdatePaymentInvoice,
+            InvoiceLimits, NewPaymentInvoice, PaymentInvoiceListQuery, PaymentInvoiceRow,
+            UpdatePaymentInvoice,
         },
+        payment_invoice_event::{NewPaymentInvoiceEvent, PaymentInvoiceEventRow},
+        status_transition::{InvoiceTransition, StatusMachine, StatusTransition},
+        transaction::NewTransaction,
+    },
+    services::{
+        audit_log::AuditLogServiceTrait, currency::CurrencyServiceTrait,
+        gateway_health::GatewayHealthServiceTrait, status_transition::TransitionPublisher,
     },
-    services::audit_log::AuditLogServiceTrait,
 };
 
 #[derive(Debug)]
@@ -41,15 +56,22 @@ pub struct CreatePaymentInvoiceCommand {
     pub customer_id: i64,
     pub amount: Decimal,
     pub gateway: PaymentSystem,
+    /// Currency of `amount`, the gateway currency when not given
+    pub currency: Option<Currency>,
+    /// Pending order the invoice pays for; the amount is rounded up to fit the gateway
+    pub checkout_order_id: Option<i64>,
 }
 
 #[derive(Debug, Default)]
 pub struct UpdatePaymentInvoiceCommand {
     pub id: i64,
     pub status: Option<InvoiceStatus>,
+    /// Status the caller saw. The update is rejected if the invoice has moved on since.
+    pub expected_status: Option<InvoiceStatus>,
     pub notification_sent_at: Option<Option<DateTime<Utc>>>,
     pub receipt_requested_at: Option<DateTime<Utc>>,
     pub receipt_submitted_at: Option<DateTime<Utc>>,
+    pub receipt_url: Option<String>,
     pub dispute_opened_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
 }
@@ -68,7 +90,21 @@ pub trait PaymentInvoiceServiceTrait: Send + Sync {
     async fn create(&self, command: CreatePaymentInvoiceCommand) -> ApiResult<PaymentInvoiceRow>;
     async fn get_by_id(&self, id: i64) -> ApiResult<PaymentInvoiceRow>;
     async fn get_by_order_id(&self, order_id: Uuid) -> ApiResult<PaymentInvoiceRow>;
+    async fn get_by_gateway_invoice_id(
+        &self,
+        gateway: PaymentSystem,
+        gateway_invoice_id: &str,
+    ) -> ApiResult<PaymentInvoiceRow>;
+    /// Applies the update, moving the invoice only along the allowed status transitions
     async fn update(&self, command: UpdatePaymentInvoiceCommand) -> ApiResult<PaymentInvoiceRow>;
+    /// Completes the invoice and records its deposit together. The transition is published
+    /// only after both are committed.
+    async fn complete_with_deposit(
+        &self,
+        id: i64,
+        expected_status: InvoiceStatus,
+        deposit: NewTransaction,
+    ) -> ApiResult<PaymentInvoiceRow>;
     async fn get_for_customer(&self, customer_id: i64) -> ApiResult<Vec<PaymentInvoiceRow>>;
     async fn expire_old_invoices(&self) -> ApiResult<u64>;
     async fn get_pending_invoices(
@@ -82,9 +118,17 @@ pub trait PaymentInvoiceServiceTrait: Send + Sync {
         &self,
         command: SendInvoiceReceiptCommand,
     ) -> ApiResult<PaymentInvoiceRow>;
+    /// Appends to the invoice history. Returns `false` when an event with the same dedup
+    /// key was already recorded.
+    async fn record_event(&self, event: NewPaymentInvoiceEvent) -> ApiResult<bool>;
+    /// Appends to the invoice history unless the last event of the same kind carries the
+    /// same payload. Returns `false` when nothing changed.
+    async fn record_event_if_changed(&self, event: NewPaymentInvoiceEvent) -> ApiResult<bool>;
+    async fn has_event(&self, invoice_id: i64, dedup_key: &str) -> ApiResult<bool>;
+    async fn get_events(&self, invoice_id: i64) -> ApiResult<Vec<PaymentInvoiceEventRow>>;
 }
 
-pub struct PaymentInvoiceService<R, A, M, S, P, C> {
+pub struct PaymentInvoiceService<R, A, M, S, P, C, G, L, E, X> {
     repo: Arc<R>,
     settings_repo: Arc<S>,
     customers_repo: Arc<C>,
@@ -92,9 +136,14 @@ pub struct PaymentInvoiceService<R, A, M, S, P, C> {
     platform_payments_provider: Arc<P>,
     #[allow(dead_code)]
     audit_log_service: Arc<A>,
+    gateway_health_service: Arc<G>,
+    gateway_settings_repo: Arc<L>,
+    events_repo: Arc<E>,
+    transitions: Arc<TransitionPublisher<InvoiceTransition>>,
+    currency_service: Arc<X>,
 }
 
-impl<R, A, M, S, P, C> PaymentInvoiceService<R, A, M, S, P, C>
+impl<R, A, M, S, P, C, G, L, E, X> PaymentInvoiceService<R, A, M, S, P, C, G, L, E, X>
 where
     R: PaymentInvoiceRepositoryTrait + Send + Sync,
     A: AuditLogServiceTrait + Send + Sync,
@@ -102,7 +151,12 @@ where
     S: SettingsRepositoryTrait + Send + Sync,
     P: AutosalesPlatformPaymentsProviderTrait + Send + Sync,
     C: CustomerRepositoryTrait + Send + Sync,
+    G: GatewayHealthServiceTrait + Send + Sync,
+    L: PaymentGatewaySettingsRepositoryTrait + Send + Sync,
+    E: PaymentInvoiceEventRepositoryTrait + Send + Sync,
+    X: CurrencyServiceTrait + Send + Sync,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         repo: Arc<R>,
         settings_repo: Arc<S>,
@@ -110,6 +164,11 @@ where
         audit_log_service: Arc<A>,
         platform_payments_provider: Arc<P>,
         customers_repo: Arc<C>,
+        gateway_health_service: Arc<G>,
+        gateway_settings_repo: Arc<L>,
+        events_repo: Arc<E>,
+        transitions: Arc<TransitionPublisher<InvoiceTransition>>,
+        currency_service: Arc<X>,
     ) -> Self {
         Self {
             repo,
@@ -118,69 +177,165 @@ where
             audit_log_service,
             platform_payments_provider,
             customers_repo,
+            gateway_health_service,
+            gateway_settings_repo,
+            events_repo,
+            transitions,
+            currency_service,
         }
     }
-}
 
-#[async_trait]
-impl<R, A, M, S, P, C> PaymentInvoiceServiceTrait for PaymentInvoiceService<R, A, M, S, P, C>
-where
-    R: PaymentInvoiceRepositoryTrait + Send + Sync,
-    A: AuditLogServiceTrait + Send + Sync,
-    M: MockPaymentsProviderTrait + Send + Sync,
-    S: SettingsRepositoryTrait + Send + Sync,
-    P: AutosalesPlatformPaymentsProviderTrait + Send + Sync,
-    C: CustomerRepositoryTrait + Send + Sync,
-{
-    async fn get_list(
+    /// Enforces the gateway's amount range and step, open invoice cap and daily limit.
+    /// The caps are checked again when the invoice is inserted, this check only spares
+    /// the gateway call. `amount` is in the gateway currency.
+    async fn check_limits(
         &self,
-        query: PaymentInvoiceListQuery,
-    ) -> ApiResult<PaginatedResult<PaymentInvoiceRow>> {
-        self.repo.get_list(query).await.map_err(ApiError::from)
-    }
+        gateway_settings: &PaymentGatewaySettingsRow,
+        command: &CreatePaymentInvoiceCommand,
+        amount: Decimal,
+    ) -> ApiResult<InvoiceLimits> {
+        let (min_amount, max_amount) = (gateway_settings.min_amount, gateway_settings.max_amount);
+        if amount < min_amount || amount > max_amount {
+            return Err(ApiError::BadRequest(format!(
+                "Amount must be between {} and {}",
+                min_amount.normalize(),
+                max_amount.normalize()
+            )));
+        }
+        if !(amount % gateway_settings.amount_step).is_zero() {
+            return Err(ApiError::BadRequest(format!(
+                "Amount must be a multiple of {}",
+                gateway_settings.amount_step.normalize()
+            )));
+        }
 
-    async fn create(&self, command: CreatePaymentInvoiceCommand) -> ApiResult<PaymentInvoiceRow> {
-        let settings = self.settings_repo.load_settings().await?;
-        let order_id = Uuid::new_v4();
-        let discount = match command.gateway {
-            PaymentSystem::Mock => settings.pricing_gateway_bonus_mock_provider,
-            PaymentSystem::PlatformCard => settings.pricing_gateway_bonus_platform_card,
-            PaymentSystem::PlatformSBP => settings.pricing_gateway_bonus_platform_sbp,
+        let limits = InvoiceLimits {
+            max_open_invoices: gateway_settings.max_open_invoices,
+            daily_deposit_limit: gateway_settings.daily_deposit_limit,
+            since: Utc::now()
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc(),
+            amount,
         };
-        let amount = command.amount * (dec!(1) - discount / dec!(100));
-        let amount_parsed = amount.to_f64().ok_or(ApiError::InternalServerError(
-            "Failed to convert decimal".to_string(),
-        ))?;
-        let (gateway_invoice_id, payment_details, amount_in_usdt) = match command.gateway {
-            PaymentSystem::Mock => self
-                .mock_payments_provider
-                .create_invoide(MockProviderCreateInvoiceRequest {
-                    amount: amount_parsed,
-                    order_id,
-                    user_id: command.customer_id,
-                })
-                .await
-                .map(|r| {
-                    (
-                        r.invoice_id.to_string(),
-                        PaymentDetails::Mock { pay_url: r.pay_url },
-                        dec!(0), // Just for mock
-                    )
-                })
-                .map_err(ApiError::InternalServerError)?,
+        if limits.is_unlimited() {
+            return Ok(limits);
+        }
+        let usage = self
+            .repo
+            .get_customer_usage(command.customer_id, command.gateway, limits.since)
+            .await?;
+        limits.check(&usage)?;
+
+        Ok(limits)
+    }
+
+    /// Requested amount in the gateway currency. Converted amounts are rounded up to the
+    /// gateway step so the customer never gets less than asked for. Checkout amounts are
+    /// also raised to the gateway minimum, the surplus stays on the balance.
+    async fn gateway_amount(
+        &self,
+        gateway_settings: &PaymentGatewaySettingsRow,
+        command: &CreatePaymentInvoiceCommand,
+    ) -> ApiResult<Decimal> {
+        let currency = command.currency.unwrap_or(gateway_settings.currency);
+        let is_checkout = command.checkout_order_id.is_some();
+        if currency == gateway_settings.currency && !is_checkout {
+            return Ok(command.amount);
+        }
+        let mut converted = self
+            .currency_service
+            .convert(command.amount, currency, gateway_settings.currency)
+            .await?;
+        if is_checkout {
+            converted = converted.max(gateway_settings.min_amount);
+        }
+        let step = gateway_settings.amount_step;
+        Ok((converted / step).ceil() * step)
+    }
+
+    /// Feeds the gateway circuit breaker; a failure to record must not fail the invoice
+    async fn record_gateway_attempt(
+        &self,
+        gateway: PaymentSystem,
+        outcome: GatewayAttemptOutcome,
+        started_at: Instant,
+    ) {
+        if let Err(e) = self
+            .gateway_health_service
+            .record_attempt(gateway, outcome, started_at.elapsed())
+            .await
+        {
+            tracing::error!("Failed to record {gateway:?} gateway attempt: {e}");
+        }
+    }
+
+    /// Opens the order at the gateway. Returns the gateway invoice id, the details shown to
+    /// the customer, the amount in USDT and the raw gateway response.
+    async fn init_gateway_order(
+        &self,
+        command: &CreatePaymentInvoiceCommand,
+        amount: f64,
+        order_id: Uuid,
+    ) -> ApiResult<(String, PaymentDetails, Decimal, serde_json::Value)> {
+        let started_at = Instant::now();
+        let order = match command.gateway {
+            PaymentSystem::Mock => {
+                let result = self
+                    .mock_payments_provider
+                    .create_invoide(MockProviderCreateInvoiceRequest {
+                        amount,
+                        order_id,
+                        user_id: command.customer_id,
+                    })
+                    .await;
+                let outcome = match &result {
+                    Ok(_) => GatewayAttemptOutcome::Success,
+                    Err(_) => GatewayAttemptOutcome::Error,
+                };
+                self.record_gateway_attempt(command.gateway, outcome, started_at)
+                    .await;
+                result
+                    .map(|r| {
+                        let gateway_response = serde_json::to_value(&r).unwrap_or_default();
+                        (
+                            r.invoice_id.to_string(),
+                            PaymentDetails::Mock { pay_url: r.pay_url },
+                            dec!(0), // Just for mock
+                            gateway_response,
+                        )
+                    })
+                    .map_err(ApiError::InternalServerError)?
+            }
             PaymentSystem::PlatformCard | PaymentSystem::PlatformSBP => {
                 let id_pay_method = match command.gateway {
                     PaymentSystem::PlatformCard => AutosalesPlatformPaymentMethod::Card,
                     PaymentSystem::PlatformSBP => AutosalesPlatformPaymentMethod::SBP,
                     _ => unreachable!(),
                 };
-                let invoice = self
+                let result = self
                     .platform_payments_provider
                     .init_order(AutosalesPlatformInitializeOrderRequest {
-                        amount: amount_parsed as i64,
+                        amount: amount as i64,
                         id_pay_method,
                     })
-                    .await?;
+                    .await;
+                // Asking for a round amount is about the customer's input, not gateway health
+                let outcome = match &result {
+                    Ok(_) => Some(GatewayAttemptOutcome::Success),
+                    Err(AutosalesPlatformError::NoSuitableRequisites) => {
+                        Some(GatewayAttemptOutcome::NoRequisites)
+                    }
+                    Err(AutosalesPlatformError::IncreaseAmountBy10) => None,
+                    Err(AutosalesPlatformError::Unknown(_)) => Some(GatewayAttemptOutcome::Error),
+                };
+                if let Some(outcome) = outcome {
+                    self.record_gateway_attempt(command.gateway, outcome, started_at)
+                        .await;
+                }
+                let invoice = result?;
+                let gateway_response = serde_json::to_value(&invoice).unwrap_or_default();
 
                 let payment_details = match command.gateway {
                     PaymentSystem::PlatformCard => PaymentDetails::PlatformCard {
@@ -215,25 +370,151 @@ where
                             "Failed to convert amount_transfer to Decimal".to_string(),
                         ),
                     )?,
+                    gateway_response,
                 )
             }
         };
+        Ok(order)
+    }
+
+    /// Frees the slot of an invoice the gateway refused, so it doesn't count as open
+    async fn release_reservation(&self, id: i64) {
+        if let Err(e) = self
+            .repo
+            .update(
+                id,
+                UpdatePaymentInvoice {
+                    status: Some(InvoiceStatus::Failed),
+                    expected_status: Some(InvoiceStatus::Pending),
+                    finished_at: Some(Utc::now()),
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            tracing::error!("Failed to release reserved invoice {id}: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl<R, A, M, S, P, C, G, L, E, X> PaymentInvoiceServiceTrait
+    for PaymentInvoiceService<R, A, M, S, P, C, G, L, E, X>
+where
+    R: PaymentInvoiceRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+    M: MockPaymentsProviderTrait + Send + Sync,
+    S: SettingsRepositoryTrait + Send + Sync,
+    P: AutosalesPlatformPaymentsProviderTrait + Send + Sync,
+    C: CustomerRepositoryTrait + Send + Sync,
+    G: GatewayHealthServiceTrait + Send + Sync,
+    L: PaymentGatewaySettingsRepositoryTrait + Send + Sync,
+    E: PaymentInvoiceEventRepositoryTrait + Send + Sync,
+    X: CurrencyServiceTrait + Send + Sync,
+{
+    async fn get_list(
+        &self,
+        query: PaymentInvoiceListQuery,
+    ) -> ApiResult<PaginatedResult<PaymentInvoiceRow>> {
+        self.repo.get_list(query).await.map_err(ApiError::from)
+    }
+
+    async fn create(&self, command: CreatePaymentInvoiceCommand) -> ApiResult<PaymentInvoiceRow> {
+        let gateway_settings = self
+            .gateway_settings_repo
+            .get_by_gateway(command.gateway)
+            .await?;
+        let gateway_amount = self.gateway_amount(&gateway_settings, &command).await?;
+        let limits = self
+            .check_limits(&gateway_settings, &command, gateway_amount)
+            .await?;
+        // The balance is credited in the base currency
+        let original_amount = self
+            .currency_service
+            .convert(
+                gateway_amount,
+                gateway_settings.currency,
+                self.currency_service.base_currency(),
+            )
+            .await?
+            .round_dp(2);
+
+        let settings = self.settings_repo.load_settings().await?;
+        let order_id = Uuid::new_v4();
+        let discount = match command.gateway {
+            PaymentSystem::Mock => settings.pricing_gateway_bonus_mock_provider,
+            PaymentSystem::PlatformCard => settings.pricing_gateway_bonus_platform_card,
+            PaymentSystem::PlatformSBP => settings.pricing_gateway_bonus_platform_sbp,
+        };
+        let amount = gateway_amount * (dec!(1) - discount / dec!(100));
+        let amount_parsed = amount.to_f64().ok_or(ApiError::InternalServerError(
+            "Failed to convert decimal".to_string(),
+        ))?;
+        let unavailable = self.gateway_health_service.get_unavailable().await?;
+        if unavailable.contains(&command.gateway) {
+            return Err(ApiError::Conflict(
+                "Gateway temporarily unavailable".to_string(),
+            ));
+        }
+
+        // The slot is taken under the customer lock before the gateway is asked, so a
+        // concurrent request can't leave a gateway order behind without a local invoice.
+        // Until the gateway answers the invoice carries its order id as the gateway id.
+        let reserved = self
+            .repo
+            .create_within_limits(
+                NewPaymentInvoice {
+                    amount,
+                    original_amount,
+                    customer_id: command.customer_id,
+                    bot_message_id: None,
+                    expires_at: Utc::now()
+                        + Duration::minutes(gateway_settings.invoice_ttl_minutes as i64),
+                    gateway: command.gateway,
+                    gateway_invoice_id: order_id.to_string(),
+                    order_id,
+                    payment_details: None,
+                    status: InvoiceStatus::Pending,
+                    amount_in_usdt: dec!(0),
+                    currency: gateway_settings.currency,
+                    checkout_order_id: command.checkout_order_id,
+                },
+                limits,
+            )
+            .await??;
+        let (gateway_invoice_id, payment_details, amount_in_usdt, gateway_response) = match self
+            .init_gateway_order(&command, amount_parsed, order_id)
+            .await
+        {
+            Ok(order) => order,
+            Err(e) => {
+                self.release_reservation(reserved.id).await;
+                return Err(e);
+            }
+        };
         let created = self
             .repo
-            .create(NewPaymentInvoice {
-                amount,
-                original_amount: command.amount,
-                customer_id: command.customer_id,
-                bot_message_id: None,
-                expires_at: Utc::now() + chrono::Duration::days(1), // TODO
-                gateway: command.gateway,
+            .attach_gateway_order(
+                reserved.id,
                 gateway_invoice_id,
-                order_id,
-                payment_details: Some(payment_details),
-                status: InvoiceStatus::Pending,
+                payment_details,
                 amount_in_usdt,
-            })
+            )
             .await?;
+        // The invoice is already created at the gateway, losing its history entry is not fatal
+        if let Err(e) = self
+            .events_repo
+            .create(NewPaymentInvoiceEvent::gateway_response(
+                created.id,
+                &gateway_response,
+            ))
+            .await
+        {
+            tracing::error!(
+                "Failed to record invoice {} gateway response: {e}",
+                created.id
+            );
+        }
 
         Ok(created)
     }
@@ -248,7 +529,38 @@ where
         Ok(res)
     }
 
+    async fn get_by_gateway_invoice_id(
+        &self,
+        gateway: PaymentSystem,
+        gateway_invoice_id: &str,
+    ) -> ApiResult<PaymentInvoiceRow> {
+        let res = self
+            .repo
+            .get_by_gateway_invoice_id(gateway, gateway_invoice_id)
+            .await?;
+        Ok(res)
+    }
+
     async fn update(&self, command: UpdatePaymentInvoiceCommand) -> ApiResult<PaymentInvoiceRow> {
+        let mut expected_status = command.expected_status;
+        let mut transition = None;
+        if let Some(to) = command.status {
+            let from = match expected_status {
+                Some(from) => from,
+                None => self.repo.get_by_id(command.id).await?.status,
+            };
+            if from != to {
+                if !from.can_transition_to(to) {
+                    return Err(ApiError::Conflict(format!(
+                        "Invoice can't move from {from:?} to {to:?}"
+                    )));
+                }
+                transition = Some((from, to));
+            }
+            // Whoever changed the status in between wins, this update is rejected
+            expected_status = Some(from);
+        }
+
         let updated = self
             .repo
             .update(
@@ -256,25 +568,72 @@ where
                 UpdatePaymentInvoice {
                     notification_sent_at: command.notification_sent_at,
                     status: command.status,
+                    expected_status,
                     dispute_opened_at: command.dispute_opened_at,
                     finished_at: command.finished_at,
                     receipt_requested_at: command.receipt_requested_at,
                     receipt_submitted_at: command.receipt_submitted_at,
+                    receipt_url: command.receipt_url,
                 },
             )
             .await?;
 
+        if let Some((from, to)) = transition {
+            self.transitions
+                .publish(&StatusTransition {
+                    from,
+                    to,
+                    row: updated.clone(),
+                })
+                .await;
+        }
+
         Ok(updated)
     }
 
+    async fn complete_with_deposit(
+        &self,
+        id: i64,
+        expected_status: InvoiceStatus,
+        deposit: NewTransaction,
+    ) -> ApiResult<PaymentInvoiceRow> {
+        if !expected_status.can_transition_to(InvoiceStatus::Completed) {
+            return Err(ApiError::Conflict(format!(
+                "Invoice can't move from {expected_status:?} to {:?}",
+                InvoiceStatus::Completed
+            )));
+        }
+        let completed = self
+            .repo
+            .complete_with_deposit(id, expected_status, Utc::now(), deposit)
+            .await?;
+        self.transitions
+            .publish(&StatusTransition {
+                from: expected_status,
+                to: InvoiceStatus::Completed,
+                row: completed.clone(),
+            })
+            .await;
+        Ok(completed)
+    }
+
     async fn get_for_customer(&self, customer_id: i64) -> ApiResult<Vec<PaymentInvoiceRow>> {
         let res = self.repo.get_for_customer(customer_id).await?;
         Ok(res)
     }
 
     async fn expire_old_invoices(&self) -> ApiResult<u64> {
-        let res = self.repo.expire_old_invoices().await?;
-        Ok(res)
+        let expired = self.repo.expire_old_invoices().await?;
+        for row in &expired {
+            self.transitions
+                .publish(&StatusTransition {
+                    from: InvoiceStatus::Pending,
+                    to: InvoiceStatus::Expired,
+                    row: row.clone(),
+                })
+                .await;
+        }
+        Ok(expired.len() as u64)
     }
 
     async fn get_pending_invoices(
@@ -303,28 +662,24 @@ where
                     .process_order(invoice.gateway_invoice_id)
                     .await?;
                 let res = self
-                    .repo
-                    .update(
+                    .update(UpdatePaymentInvoiceCommand {
                         id,
-                        UpdatePaymentInvoice {
-                            status: Some(InvoiceStatus::Processing),
-                            ..Default::default()
-                        },
-                    )
+                        status: Some(InvoiceStatus::Processing),
+                        expected_status: Some(InvoiceStatus::Pending),
+                        ..Default::default()
+                    })
                     .await?;
 
                 Ok(res)
             }
             PaymentSystem::Mock => {
                 let res = self
-                    .repo
-                    .update(
+                    .update(UpdatePaymentInvoiceCommand {
                         id,
-                        UpdatePaymentInvoice {
-                            status: Some(InvoiceStatus::Completed),
-                            ..Default::default()
-                        },
-                    )
+                        status: Some(InvoiceStatus::Completed),
+                        expected_status: Some(InvoiceStatus::Pending),
+                        ..Default::default()
+                    })
                     .await?;
 
                 Ok(res)
@@ -345,15 +700,13 @@ where
                     .cancel_order(invoice.gateway_invoice_id)
                     .await?;
                 let res = self
-                    .repo
-                    .update(
+                    .update(UpdatePaymentInvoiceCommand {
                         id,
-                        UpdatePaymentInvoice {
-                            status: Some(InvoiceStatus::Cancelled),
-                            finished_at: Some(Utc::now()),
-                            ..Default::default()
-                        },
-                    )
+                        status: Some(InvoiceStatus::Cancelled),
+                        expected_status: Some(InvoiceStatus::Pending),
+                        finished_at: Some(Utc::now()),
+                        ..Default::default()
+                    })
                     .await?;
 
                 let cancelled_payments = self
@@ -383,15 +736,13 @@ where
             }
             PaymentSystem::Mock => {
                 let res = self
-                    .repo
-                    .update(
+                    .update(UpdatePaymentInvoiceCommand {
                         id,
-                        UpdatePaymentInvoice {
-                            status: Some(InvoiceStatus::Cancelled),
-                            finished_at: Some(Utc::now()),
-                            ..Default::default()
-                        },
-                    )
+                        status: Some(InvoiceStatus::Cancelled),
+                        expected_status: Some(InvoiceStatus::Pending),
+                        finished_at: Some(Utc::now()),
+                        ..Default::default()
+                    })
                     .await?;
 
                 Ok(res)
@@ -410,25 +761,45 @@ where
                 self.platform_payments_provider
                     .send_receipt(AutosalesPlatformSendReceiptRequest {
                         object_token: invoice.gateway_invoice_id,
-                        url_file: command.receipt_url,
+                        url_file: command.receipt_url.clone(),
                     })
                     .await?;
 
                 let res = self
-                    .repo
-                    .update(
-                        command.id,
-                        UpdatePaymentInvoice {
-                            status: Some(InvoiceStatus::ReceiptSubmitted),
-                            ..Default::default()
-                        },
-                    )
+                    .update(UpdatePaymentInvoiceCommand {
+                        id: command.id,
+                        status: Some(InvoiceStatus::ReceiptSubmitted),
+                        expected_status: Some(invoice.status),
+                        receipt_submitted_at: Some(Utc::now()),
+                        receipt_url: Some(command.receipt_url),
+                        ..Default::default()
+                    })
                     .await?;
 
                 Ok(res)
             }
         }
     }
+
+    async fn record_event(&self, event: NewPaymentInvoiceEvent) -> ApiResult<bool> {
+        let res = self.events_repo.create(event).await?;
+        Ok(res.is_some())
+    }
+
+    async fn record_event_if_changed(&self, event: NewPaymentInvoiceEvent) -> ApiResult<bool> {
+        let res = self.events_repo.create_if_changed(event).await?;
+        Ok(res.is_some())
+    }
+
+    async fn has_event(&self, invoice_id: i64, dedup_key: &str) -> ApiResult<bool> {
+        let res = self.events_repo.exists(invoice_id, dedup_key).await?;
+        Ok(res)
+    }
+
+    async fn get_events(&self, invoice_id: i64) -> ApiResult<Vec<PaymentInvoiceEventRow>> {
+        let res = self.events_repo.get_for_invoice(invoice_id).await?;
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
@@ -439,7 +810,8 @@ mod tests {
     use chrono::Utc;
     use rust_decimal_macros::dec;
     use serde_json::json;
-    use std::sync::Mutex;
+    use shared_dtos::{invoice::PaymentInvoiceEventKind, transaction::TransactionType};
+    use std::{collections::HashMap, sync::Mutex};
     use uuid::Uuid;
 
     use crate::{
@@ -459,8 +831,10 @@ mod tests {
             repositories::settings::SettingsRepositoryTrait,
         },
         models::customer::{CustomerListQuery, CustomerRow, NewCustomer, UpdateCustomer},
+        models::payment_invoice::{CustomerGatewayUsage, InvoiceLimitExceeded},
         models::{common::PaginatedResult, settings::Settings},
         services::audit_log::AuditLogServiceTrait,
+        services::status_transition::TransitionSubscriber,
     };
 
     #[derive(Clone)]
@@ -502,6 +876,14 @@ mod tests {
         {
             Err(ApiError::InternalServerError("not used".to_string()))
         }
+
+        async fn get_for_target(
+            &self,
+            _target_table: &str,
+            _target_id: &str,
+        ) -> crate::errors::api::ApiResult<Vec<crate::models::audit_log::AuditLogRow>> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
     }
 
     #[derive(Clone, Debug)]
@@ -519,8 +901,14 @@ mod tests {
         bot_message_id: Option<i64>,
     }
 
+    #[derive(Default)]
     struct FakeRepo {
         last_created: Mutex<Option<PaymentInvoiceSnapshot>>,
+        last_row: Mutex<Option<PaymentInvoiceRow>>,
+        released: Mutex<Vec<i64>>,
+        usage: CustomerGatewayUsage,
+        /// Usage seen under the customer lock, when another request got there first
+        locked_usage: Option<CustomerGatewayUsage>,
     }
 
     #[async_trait]
@@ -557,7 +945,7 @@ mod tests {
                 payment_details: payment_invoice.payment_details.clone(),
                 bot_message_id: payment_invoice.bot_message_id,
             });
-            Ok(PaymentInvoiceRow {
+            let row = PaymentInvoiceRow {
                 id: 1,
                 customer_id: payment_invoice.customer_id,
                 original_amount: payment_invoice.original_amount,
@@ -577,14 +965,80 @@ mod tests {
                 finished_at: None,
                 receipt_requested_at: None,
                 receipt_submitted_at: None,
+                receipt_url: None,
                 amount_in_usdt: payment_invoice.amount_in_usdt,
-            })
+                currency: payment_invoice.currency,
+                checkout_order_id: payment_invoice.checkout_order_id,
+            };
+            *self.last_row.lock().unwrap() = Some(row.clone());
+            Ok(row)
+        }
+
+        async fn create_within_limits(
+            &self,
+            payment_invoice: NewPaymentInvoice,
+            limits: InvoiceLimits,
+        ) -> Result<
+            Result<PaymentInvoiceRow, InvoiceLimitExceeded>,
+            crate::errors::repository::RepositoryError,
+        > {
+            let usage = self.locked_usage.as_ref().unwrap_or(&self.usage);
+            if let Err(exceeded) = limits.check(usage) {
+                return Ok(Err(exceeded));
+            }
+            self.create(payment_invoice).await.map(Ok)
+        }
+
+        async fn attach_gateway_order(
+            &self,
+            _id: i64,
+            gateway_invoice_id: String,
+            payment_details: PaymentDetails,
+            amount_in_usdt: Decimal,
+        ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
+            if let Some(created) = self.last_created.lock().unwrap().as_mut() {
+                created.gateway_invoice_id = gateway_invoice_id.clone();
+                created.payment_details = Some(payment_details.clone());
+            }
+            let mut row = self
+                .last_row
+                .lock()
+                .unwrap()
+                .clone()
+                .expect("invoice reserved");
+            row.gateway_invoice_id = gateway_invoice_id;
+            row.payment_details = serde_json::to_value(payment_details).unwrap_or_default();
+            row.amount_in_usdt = amount_in_usdt;
+            Ok(row)
         }
 
         async fn update(
+            &self,
+            id: i64,
+            payment_invoice: UpdatePaymentInvoice,
+        ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
+            assert_eq!(payment_invoice.status, Some(InvoiceStatus::Failed));
+            assert_eq!(
+                payment_invoice.expected_status,
+                Some(InvoiceStatus::Pending)
+            );
+            self.released.lock().unwrap().push(id);
+            let mut row = self
+                .last_row
+                .lock()
+                .unwrap()
+                .clone()
+                .expect("invoice reserved");
+            row.status = InvoiceStatus::Failed;
+            Ok(row)
+        }
+
+        async fn complete_with_deposit(
             &self,
             _id: i64,
-            _payment_invoice: UpdatePaymentInvoice,
+            _expected_status: InvoiceStatus,
+            _finished_at: DateTime<Utc>,
+            _deposit: NewTransaction,
         ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
             Err(crate::errors::repository::RepositoryError::QueryFailed(
                 "not used".to_string(),
@@ -609,6 +1063,16 @@ mod tests {
             ))
         }
 
+        async fn get_by_gateway_invoice_id(
+            &self,
+            _gateway: PaymentSystem,
+            _gateway_invoice_id: &str,
+        ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
+            Err(crate::errors::repository::RepositoryError::NotFound(
+                "not used".to_string(),
+            ))
+        }
+
         async fn get_for_customer(
             &self,
             _customer_id: i64,
@@ -620,7 +1084,7 @@ mod tests {
 
         async fn expire_old_invoices(
             &self,
-        ) -> Result<u64, crate::errors::repository::RepositoryError> {
+        ) -> Result<Vec<PaymentInvoiceRow>, crate::errors::repository::RepositoryError> {
             Err(crate::errors::repository::RepositoryError::QueryFailed(
                 "not used".to_string(),
             ))
@@ -643,6 +1107,15 @@ mod tests {
                 "not used".to_string(),
             ))
         }
+
+        async fn get_customer_usage(
+            &self,
+            _customer_id: i64,
+            _gateway: PaymentSystem,
+            _since: DateTime<Utc>,
+        ) -> Result<CustomerGatewayUsage, crate::errors::repository::RepositoryError> {
+            Ok(self.usage.clone())
+        }
     }
 
     #[derive(Clone)]
@@ -748,53 +1221,230 @@ mod tests {
         }
     }
 
-    fn base_settings() -> Settings {
-        Settings {
-            bot_messages_support: "support".to_string(),
-            bot_messages_new_user_welcome: "welcome".to_string(),
-            bot_messages_returning_user_welcome: "welcome back".to_string(),
-            ..Default::default()
-        }
+    #[derive(Default)]
+    struct FakeGatewayHealthService {
+        unavailable: Vec<PaymentSystem>,
+        attempts: Mutex<Vec<(PaymentSystem, GatewayAttemptOutcome)>>,
     }
 
-    #[cfg(feature = "mock-payments-provider")]
-    #[tokio::test]
-    async fn test_create_invoice_with_mock_discount() {
-        let mut settings = base_settings();
-        settings.pricing_gateway_bonus_mock_provider = dec!(10);
-        let repo = Arc::new(FakeRepo {
-            last_created: Mutex::new(None),
-        });
-        let provider = Arc::new(FakeMockProvider {
-            last_request: Mutex::new(None),
-        });
-        let platform = Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new());
-        let service = PaymentInvoiceService::new(
-            repo.clone(),
-            Arc::new(FakeSettingsRepo {
-                settings: settings.clone(),
-            }),
-            provider.clone(),
-            Arc::new(FakeAuditLogService),
-            platform,
-            Arc::new(FakeCustomerRepo),
-        );
+    #[async_trait]
+    impl GatewayHealthServiceTrait for FakeGatewayHealthService {
+        async fn record_attempt(
+            &self,
+            gateway: PaymentSystem,
+            outcome: GatewayAttemptOutcome,
+            _latency: std::time::Duration,
+        ) -> ApiResult<()> {
+            self.attempts.lock().unwrap().push((gateway, outcome));
+            Ok(())
+        }
 
-        let res = service
-            .create(CreatePaymentInvoiceCommand {
-                customer_id: 10,
-                amount: dec!(100),
-                gateway: PaymentSystem::Mock,
-            })
-            .await
-            .unwrap();
+        async fn get_health(
+            &self,
+            _since: DateTime<Utc>,
+        ) -> ApiResult<Vec<shared_dtos::invoice::GatewayHealthAdminResponse>> {
+            Ok(vec![])
+        }
 
-        let created = repo
-            .last_created
-            .lock()
-            .unwrap()
-            .take()
-            .expect("invoice created");
+        async fn get_unavailable(&self) -> ApiResult<Vec<PaymentSystem>> {
+            Ok(self.unavailable.clone())
+        }
+
+        async fn delete_expired(&self) -> ApiResult<u64> {
+            Ok(0)
+        }
+    }
+
+    struct FakeGatewaySettingsRepo {
+        settings: PaymentGatewaySettingsRow,
+    }
+
+    impl Default for FakeGatewaySettingsRepo {
+        fn default() -> Self {
+            Self {
+                settings: PaymentGatewaySettingsRow {
+                    gateway: PaymentSystem::PlatformCard,
+                    invoice_ttl_minutes: 1440,
+                    min_amount: dec!(1),
+                    max_amount: dec!(1000000),
+                    amount_step: dec!(1),
+                    max_open_invoices: None,
+                    daily_deposit_limit: None,
+                    updated_at: Utc::now(),
+                    currency: Currency::Rub,
+                },
+            }
+        }
+    }
+
+    #[async_trait]
+    impl PaymentGatewaySettingsRepositoryTrait for FakeGatewaySettingsRepo {
+        async fn get_all(&self) -> Result<Vec<PaymentGatewaySettingsRow>, RepositoryError> {
+            Ok(vec![self.settings.clone()])
+        }
+
+        async fn get_by_gateway(
+            &self,
+            gateway: PaymentSystem,
+        ) -> Result<PaymentGatewaySettingsRow, RepositoryError> {
+            Ok(PaymentGatewaySettingsRow {
+                gateway,
+                ..self.settings.clone()
+            })
+        }
+
+        async fn update(
+            &self,
+            _gateway: PaymentSystem,
+            _update: crate::models::payment_gateway_settings::UpdatePaymentGatewaySettings,
+        ) -> Result<PaymentGatewaySettingsRow, RepositoryError> {
+            Err(RepositoryError::QueryFailed("not used".to_string()))
+        }
+    }
+
+    /// RUB store with 80 RUB to the dollar
+    struct FakeCurrencyService {
+        rates: HashMap<Currency, Decimal>,
+    }
+
+    impl Default for FakeCurrencyService {
+        fn default() -> Self {
+            Self {
+                rates: HashMap::from([(Currency::Rub, dec!(1)), (Currency::Usd, dec!(0.0125))]),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CurrencyServiceTrait for FakeCurrencyService {
+        fn base_currency(&self) -> Currency {
+            Currency::Rub
+        }
+
+        async fn get_rates(&self) -> ApiResult<shared_dtos::currency::ExchangeRatesResponse> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn rate(&self, currency: Currency) -> ApiResult<Decimal> {
+            self.rates
+                .get(&currency)
+                .copied()
+                .ok_or_else(|| ApiError::Conflict("no rate".to_string()))
+        }
+
+        async fn convert(
+            &self,
+            amount: Decimal,
+            from: Currency,
+            to: Currency,
+        ) -> ApiResult<Decimal> {
+            Ok(amount / self.rate(from).await? * self.rate(to).await?)
+        }
+
+        async fn refresh_rates(&self) -> ApiResult<usize> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeEventsRepo {
+        created: Mutex<Vec<NewPaymentInvoiceEvent>>,
+    }
+
+    #[async_trait]
+    impl PaymentInvoiceEventRepositoryTrait for FakeEventsRepo {
+        async fn create(
+            &self,
+            event: NewPaymentInvoiceEvent,
+        ) -> Result<Option<PaymentInvoiceEventRow>, RepositoryError> {
+            self.created.lock().unwrap().push(event.clone());
+            Ok(Some(PaymentInvoiceEventRow {
+                id: 1,
+                invoice_id: event.invoice_id,
+                kind: event.kind,
+                from_status: None,
+                to_status: None,
+                payload: event.payload,
+                dedup_key: event.dedup_key,
+                created_at: Utc::now(),
+            }))
+        }
+
+        async fn create_if_changed(
+            &self,
+            _event: NewPaymentInvoiceEvent,
+        ) -> Result<Option<PaymentInvoiceEventRow>, RepositoryError> {
+            Err(RepositoryError::QueryFailed("not used".to_string()))
+        }
+
+        async fn exists(
+            &self,
+            _invoice_id: i64,
+            _dedup_key: &str,
+        ) -> Result<bool, RepositoryError> {
+            Err(RepositoryError::QueryFailed("not used".to_string()))
+        }
+
+        async fn get_for_invoice(
+            &self,
+            _invoice_id: i64,
+        ) -> Result<Vec<PaymentInvoiceEventRow>, RepositoryError> {
+            Err(RepositoryError::QueryFailed("not used".to_string()))
+        }
+    }
+
+    fn base_settings() -> Settings {
+        Settings {
+            bot_messages_support: "support".to_string(),
+            bot_messages_new_user_welcome: "welcome".to_string(),
+            bot_messages_returning_user_welcome: "welcome back".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[cfg(feature = "mock-payments-provider")]
+    #[tokio::test]
+    async fn test_create_invoice_with_mock_discount() {
+        let mut settings = base_settings();
+        settings.pricing_gateway_bonus_mock_provider = dec!(10);
+        let repo = Arc::new(FakeRepo::default());
+        let provider = Arc::new(FakeMockProvider {
+            last_request: Mutex::new(None),
+        });
+        let platform = Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new());
+        let service = PaymentInvoiceService::new(
+            repo.clone(),
+            Arc::new(FakeSettingsRepo {
+                settings: settings.clone(),
+            }),
+            provider.clone(),
+            Arc::new(FakeAuditLogService),
+            platform,
+            Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
+        );
+
+        let res = service
+            .create(CreatePaymentInvoiceCommand {
+                customer_id: 10,
+                amount: dec!(100),
+                gateway: PaymentSystem::Mock,
+                currency: None,
+                checkout_order_id: None,
+            })
+            .await
+            .unwrap();
+
+        let created = repo
+            .last_created
+            .lock()
+            .unwrap()
+            .take()
+            .expect("invoice created");
         assert_eq!(created.original_amount, dec!(100));
         assert_eq!(created.amount, dec!(90));
         assert_eq!(created.gateway, PaymentSystem::Mock);
@@ -807,13 +1457,67 @@ mod tests {
         assert_eq!(res.amount, dec!(90));
     }
 
+    #[cfg(feature = "mock-payments-provider")]
+    #[tokio::test]
+    async fn test_create_invoice_converts_requested_currency() {
+        let repo = Arc::new(FakeRepo::default());
+        let provider = Arc::new(FakeMockProvider {
+            last_request: Mutex::new(None),
+        });
+        let service = PaymentInvoiceService::new(
+            repo.clone(),
+            Arc::new(FakeSettingsRepo {
+                settings: base_settings(),
+            }),
+            provider.clone(),
+            Arc::new(FakeAuditLogService),
+            Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
+            Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo {
+                settings: PaymentGatewaySettingsRow {
+                    amount_step: dec!(50),
+                    ..FakeGatewaySettingsRepo::default().settings
+                },
+            }),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
+        );
+
+        // 3 USD is 240 RUB, rounded up to the gateway step
+        let res = service
+            .create(CreatePaymentInvoiceCommand {
+                customer_id: 10,
+                amount: dec!(3),
+                gateway: PaymentSystem::Mock,
+                currency: Some(Currency::Usd),
+                checkout_order_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.amount, dec!(250));
+        assert_eq!(res.original_amount, dec!(250));
+        assert_eq!(res.currency, Currency::Rub);
+
+        let err = service
+            .create(CreatePaymentInvoiceCommand {
+                customer_id: 10,
+                amount: dec!(3),
+                gateway: PaymentSystem::Mock,
+                currency: Some(Currency::Eur),
+                checkout_order_id: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(_)));
+    }
+
     #[tokio::test]
     async fn test_create_invoice_platform_card_details() {
         let mut settings = base_settings();
         settings.pricing_gateway_bonus_platform_card = dec!(0);
-        let repo = Arc::new(FakeRepo {
-            last_created: Mutex::new(None),
-        });
+        let repo = Arc::new(FakeRepo::default());
         let mut platform = MockAutosalesPlatformPaymentsProviderTrait::new();
         platform
             .expect_init_order()
@@ -842,6 +1546,8 @@ mod tests {
                 })
             });
         let platform = Arc::new(platform);
+        let gateway_health = Arc::new(FakeGatewayHealthService::default());
+        let events_repo = Arc::new(FakeEventsRepo::default());
 
         let service = PaymentInvoiceService::new(
             repo.clone(),
@@ -852,6 +1558,16 @@ mod tests {
             Arc::new(FakeAuditLogService),
             platform.clone(),
             Arc::new(FakeCustomerRepo),
+            gateway_health.clone(),
+            Arc::new(FakeGatewaySettingsRepo {
+                settings: PaymentGatewaySettingsRow {
+                    invoice_ttl_minutes: 30,
+                    ..FakeGatewaySettingsRepo::default().settings
+                },
+            }),
+            events_repo.clone(),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
         );
 
         let res = service
@@ -859,6 +1575,8 @@ mod tests {
                 customer_id: 10,
                 amount: dec!(100),
                 gateway: PaymentSystem::PlatformCard,
+                currency: None,
+                checkout_order_id: None,
             })
             .await
             .unwrap();
@@ -883,8 +1601,238 @@ mod tests {
             }
             _ => panic!("expected platform card details"),
         }
+        let ttl = created.expires_at - Utc::now();
+        assert!(ttl <= Duration::minutes(30) && ttl > Duration::minutes(29));
 
         assert_eq!(res.gateway, PaymentSystem::PlatformCard);
+        assert_eq!(
+            *gateway_health.attempts.lock().unwrap(),
+            vec![(PaymentSystem::PlatformCard, GatewayAttemptOutcome::Success)]
+        );
+        // The requisites are kept in payment details but not in the history
+        let events = events_repo.created.lock().unwrap();
+        let payload = events[0].payload.as_ref().unwrap();
+        assert_eq!(events[0].kind, PaymentInvoiceEventKind::GatewayResponse);
+        assert_eq!(payload["object_token"], "token-123");
+        assert_eq!(payload["value"], "***1111");
+        assert_eq!(payload["data_people"], "***");
+    }
+
+    #[tokio::test]
+    async fn test_create_invoice_records_failed_gateway_attempts() {
+        let mut platform = MockAutosalesPlatformPaymentsProviderTrait::new();
+        let mut errors = vec![
+            AutosalesPlatformError::IncreaseAmountBy10,
+            AutosalesPlatformError::Unknown("boom".to_string()),
+            AutosalesPlatformError::NoSuitableRequisites,
+        ];
+        platform
+            .expect_init_order()
+            .times(3)
+            .returning(move |_req| Err(errors.pop().unwrap()));
+        let gateway_health = Arc::new(FakeGatewayHealthService::default());
+        let repo = Arc::new(FakeRepo::default());
+        let service = PaymentInvoiceService::new(
+            repo.clone(),
+            Arc::new(FakeSettingsRepo {
+                settings: base_settings(),
+            }),
+            Arc::new(DummyMockProvider),
+            Arc::new(FakeAuditLogService),
+            Arc::new(platform),
+            Arc::new(FakeCustomerRepo),
+            gateway_health.clone(),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
+        );
+
+        for _ in 0..3 {
+            let err = service
+                .create(CreatePaymentInvoiceCommand {
+                    customer_id: 10,
+                    amount: dec!(100),
+                    gateway: PaymentSystem::PlatformSBP,
+                    currency: None,
+                    checkout_order_id: None,
+                })
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                ApiError::Conflict(_) | ApiError::InternalServerError(_)
+            ));
+        }
+
+        // Every refused order frees the invoice slot it reserved
+        assert_eq!(*repo.released.lock().unwrap(), vec![1, 1, 1]);
+        // Asking for a different amount isn't held against the gateway
+        assert_eq!(
+            *gateway_health.attempts.lock().unwrap(),
+            vec![
+                (
+                    PaymentSystem::PlatformSBP,
+                    GatewayAttemptOutcome::NoRequisites
+                ),
+                (PaymentSystem::PlatformSBP, GatewayAttemptOutcome::Error),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_invoice_enforces_gateway_limits() {
+        let limited = PaymentGatewaySettingsRow {
+            min_amount: dec!(100),
+            max_amount: dec!(5000),
+            amount_step: dec!(50),
+            max_open_invoices: Some(2),
+            daily_deposit_limit: Some(dec!(3000)),
+            ..FakeGatewaySettingsRepo::default().settings
+        };
+        let service = |usage: CustomerGatewayUsage| {
+            PaymentInvoiceService::new(
+                Arc::new(FakeRepo {
+                    usage,
+                    ..Default::default()
+                }),
+                Arc::new(FakeSettingsRepo {
+                    settings: base_settings(),
+                }),
+                Arc::new(DummyMockProvider),
+                Arc::new(FakeAuditLogService),
+                // No expectations: every request is rejected before reaching the gateway
+                Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
+                Arc::new(FakeCustomerRepo),
+                Arc::new(FakeGatewayHealthService::default()),
+                Arc::new(FakeGatewaySettingsRepo {
+                    settings: limited.clone(),
+                }),
+                Arc::new(FakeEventsRepo::default()),
+                Arc::new(TransitionPublisher::default()),
+                Arc::new(FakeCurrencyService::default()),
+            )
+        };
+        let create = |amount| CreatePaymentInvoiceCommand {
+            customer_id: 10,
+            amount,
+            gateway: PaymentSystem::PlatformCard,
+            currency: None,
+            checkout_order_id: None,
+        };
+        let usage = CustomerGatewayUsage {
+            open_invoices: 1,
+            deposited_since: dec!(2500),
+        };
+
+        let err = service(usage.clone())
+            .create(create(dec!(50)))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ApiError::BadRequest(msg) if msg == "Amount must be between 100 and 5000")
+        );
+
+        let err = service(usage.clone())
+            .create(create(dec!(125)))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ApiError::BadRequest(msg) if msg == "Amount must be a multiple of 50")
+        );
+
+        let err = service(usage.clone())
+            .create(create(dec!(600)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(msg) if msg == "Daily deposit limit exceeded"));
+
+        let err = service(CustomerGatewayUsage {
+            open_invoices: 2,
+            ..usage
+        })
+        .create(create(dec!(100)))
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(msg) if msg == "Too many open invoices"));
+    }
+
+    #[tokio::test]
+    async fn test_create_invoice_reserves_slot_before_calling_gateway() {
+        let limited = PaymentGatewaySettingsRow {
+            max_open_invoices: Some(1),
+            ..FakeGatewaySettingsRepo::default().settings
+        };
+        let service = PaymentInvoiceService::new(
+            Arc::new(FakeRepo {
+                locked_usage: Some(CustomerGatewayUsage {
+                    open_invoices: 1,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            Arc::new(FakeSettingsRepo {
+                settings: base_settings(),
+            }),
+            Arc::new(DummyMockProvider),
+            Arc::new(FakeAuditLogService),
+            // No expectations: a concurrent request took the last slot, no order is opened
+            Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
+            Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo { settings: limited }),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
+        );
+
+        let err = service
+            .create(CreatePaymentInvoiceCommand {
+                customer_id: 10,
+                amount: dec!(100),
+                gateway: PaymentSystem::PlatformCard,
+                currency: None,
+                checkout_order_id: None,
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ApiError::Conflict(msg) if msg == "Too many open invoices"));
+    }
+
+    #[tokio::test]
+    async fn test_create_invoice_rejects_gateway_with_open_circuit() {
+        let service = PaymentInvoiceService::new(
+            Arc::new(FakeRepo::default()),
+            Arc::new(FakeSettingsRepo {
+                settings: base_settings(),
+            }),
+            Arc::new(DummyMockProvider),
+            Arc::new(FakeAuditLogService),
+            // No expectations: the gateway must not be called
+            Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
+            Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService {
+                unavailable: vec![PaymentSystem::PlatformCard],
+                ..Default::default()
+            }),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
+        );
+
+        let err = service
+            .create(CreatePaymentInvoiceCommand {
+                customer_id: 10,
+                amount: dec!(100),
+                gateway: PaymentSystem::PlatformCard,
+                currency: None,
+                checkout_order_id: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(msg) if msg == "Gateway temporarily unavailable"));
     }
 
     struct StatusGuardRepo {
@@ -913,6 +1861,31 @@ mod tests {
             ))
         }
 
+        async fn create_within_limits(
+            &self,
+            _payment_invoice: NewPaymentInvoice,
+            _limits: InvoiceLimits,
+        ) -> Result<
+            Result<PaymentInvoiceRow, InvoiceLimitExceeded>,
+            crate::errors::repository::RepositoryError,
+        > {
+            Err(crate::errors::repository::RepositoryError::QueryFailed(
+                "not used".to_string(),
+            ))
+        }
+
+        async fn attach_gateway_order(
+            &self,
+            _id: i64,
+            _gateway_invoice_id: String,
+            _payment_details: PaymentDetails,
+            _amount_in_usdt: Decimal,
+        ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
+            Err(crate::errors::repository::RepositoryError::QueryFailed(
+                "not used".to_string(),
+            ))
+        }
+
         async fn update(
             &self,
             _id: i64,
@@ -920,9 +1893,11 @@ mod tests {
         ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
             *self.last_update.lock().unwrap() = Some(UpdatePaymentInvoice {
                 status: payment_invoice.status,
+                expected_status: payment_invoice.expected_status,
                 notification_sent_at: payment_invoice.notification_sent_at,
                 receipt_requested_at: payment_invoice.receipt_requested_at,
                 receipt_submitted_at: payment_invoice.receipt_submitted_at,
+                receipt_url: payment_invoice.receipt_url,
                 dispute_opened_at: payment_invoice.dispute_opened_at,
                 finished_at: payment_invoice.finished_at,
             });
@@ -935,6 +1910,29 @@ mod tests {
             Ok(invoice)
         }
 
+        async fn complete_with_deposit(
+            &self,
+            _id: i64,
+            expected_status: InvoiceStatus,
+            finished_at: DateTime<Utc>,
+            _deposit: NewTransaction,
+        ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
+            if self.invoice.status != expected_status {
+                return Err(crate::errors::repository::RepositoryError::OptimisticLockViolation);
+            }
+            *self.last_update.lock().unwrap() = Some(UpdatePaymentInvoice {
+                status: Some(InvoiceStatus::Completed),
+                expected_status: Some(expected_status),
+                finished_at: Some(finished_at),
+                ..Default::default()
+            });
+
+            let mut invoice = self.invoice.clone();
+            invoice.status = InvoiceStatus::Completed;
+            invoice.finished_at = Some(finished_at);
+            Ok(invoice)
+        }
+
         async fn get_by_id(
             &self,
             _id: i64,
@@ -951,6 +1949,16 @@ mod tests {
             ))
         }
 
+        async fn get_by_gateway_invoice_id(
+            &self,
+            _gateway: PaymentSystem,
+            _gateway_invoice_id: &str,
+        ) -> Result<PaymentInvoiceRow, crate::errors::repository::RepositoryError> {
+            Err(crate::errors::repository::RepositoryError::NotFound(
+                "not used".to_string(),
+            ))
+        }
+
         async fn get_for_customer(
             &self,
             _customer_id: i64,
@@ -960,7 +1968,7 @@ mod tests {
 
         async fn expire_old_invoices(
             &self,
-        ) -> Result<u64, crate::errors::repository::RepositoryError> {
+        ) -> Result<Vec<PaymentInvoiceRow>, crate::errors::repository::RepositoryError> {
             Err(crate::errors::repository::RepositoryError::QueryFailed(
                 "not used".to_string(),
             ))
@@ -983,6 +1991,17 @@ mod tests {
                 "not used".to_string(),
             ))
         }
+
+        async fn get_customer_usage(
+            &self,
+            _customer_id: i64,
+            _gateway: PaymentSystem,
+            _since: DateTime<Utc>,
+        ) -> Result<CustomerGatewayUsage, crate::errors::repository::RepositoryError> {
+            Err(crate::errors::repository::RepositoryError::QueryFailed(
+                "not used".to_string(),
+            ))
+        }
     }
 
     fn test_invoice_row(status: InvoiceStatus) -> PaymentInvoiceRow {
@@ -1006,8 +2025,11 @@ mod tests {
             notification_sent_at: None,
             receipt_requested_at: None,
             receipt_submitted_at: None,
+            receipt_url: None,
             dispute_opened_at: None,
             finished_at: None,
+            currency: Currency::Rub,
+            checkout_order_id: None,
         }
     }
 
@@ -1026,6 +2048,11 @@ mod tests {
             Arc::new(FakeAuditLogService),
             Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
             Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
         );
 
         let err = service.confirm_invoice(1).await.unwrap_err();
@@ -1048,6 +2075,11 @@ mod tests {
             Arc::new(FakeAuditLogService),
             Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
             Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
         );
 
         let updated = service.confirm_invoice(1).await.unwrap();
@@ -1069,6 +2101,11 @@ mod tests {
             Arc::new(FakeAuditLogService),
             Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
             Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
         );
 
         let err = service.cancel_invoice(1).await.unwrap_err();
@@ -1091,9 +2128,167 @@ mod tests {
             Arc::new(FakeAuditLogService),
             Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
             Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default()),
+            Arc::new(FakeCurrencyService::default()),
         );
 
         let updated = service.cancel_invoice(1).await.unwrap();
         assert_eq!(updated.status, InvoiceStatus::Cancelled);
     }
+    struct RecordingSubscriber {
+        seen: Mutex<Vec<(InvoiceStatus, InvoiceStatus)>>,
+    }
+
+    #[async_trait]
+    impl TransitionSubscriber<InvoiceTransition> for RecordingSubscriber {
+        async fn on_transition(&self, event: &InvoiceTransition) -> ApiResult<()> {
+            self.seen.lock().unwrap().push((event.from, event.to));
+            Ok(())
+        }
+    }
+
+    fn status_guard_service(
+        repo: Arc<StatusGuardRepo>,
+        subscriber: Arc<RecordingSubscriber>,
+    ) -> impl PaymentInvoiceServiceTrait {
+        PaymentInvoiceService::new(
+            repo,
+            Arc::new(FakeSettingsRepo {
+                settings: base_settings(),
+            }),
+            Arc::new(DummyMockProvider),
+            Arc::new(FakeAuditLogService),
+            Arc::new(MockAutosalesPlatformPaymentsProviderTrait::new()),
+            Arc::new(FakeCustomerRepo),
+            Arc::new(FakeGatewayHealthService::default()),
+            Arc::new(FakeGatewaySettingsRepo::default()),
+            Arc::new(FakeEventsRepo::default()),
+            Arc::new(TransitionPublisher::default().subscribe(subscriber)),
+            Arc::new(FakeCurrencyService::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_update_rejects_illegal_transition() {
+        let repo = Arc::new(StatusGuardRepo {
+            invoice: test_invoice_row(InvoiceStatus::Completed),
+            last_update: Mutex::new(None),
+        });
+        let subscriber = Arc::new(RecordingSubscriber {
+            seen: Mutex::new(vec![]),
+        });
+        let service = status_guard_service(repo.clone(), subscriber.clone());
+
+        let err = service
+            .update(UpdatePaymentInvoiceCommand {
+                id: 1,
+                status: Some(InvoiceStatus::Pending),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ApiError::Conflict(_)));
+        assert!(repo.last_update.lock().unwrap().is_none());
+        assert!(subscriber.seen.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_is_conditional_and_publishes_transition() {
+        let repo = Arc::new(StatusGuardRepo {
+            invoice: test_invoice_row(InvoiceStatus::Pending),
+            last_update: Mutex::new(None),
+        });
+        let subscriber = Arc::new(RecordingSubscriber {
+            seen: Mutex::new(vec![]),
+        });
+        let service = status_guard_service(repo.clone(), subscriber.clone());
+
+        service
+            .update(UpdatePaymentInvoiceCommand {
+                id: 1,
+                status: Some(InvoiceStatus::AwaitingReceipt),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repo.last_update
+                .lock()
+                .unwrap()
+                .as_ref()
+                .and_then(|u| u.expected_status),
+            Some(InvoiceStatus::Pending)
+        );
+        assert_eq!(
+            *subscriber.seen.lock().unwrap(),
+            vec![(InvoiceStatus::Pending, InvoiceStatus::AwaitingReceipt)]
+        );
+    }
+
+    fn test_deposit() -> NewTransaction {
+        NewTransaction {
+            customer_id: Some(10),
+            order_id: None,
+            r#type: TransactionType::Deposit,
+            amount: dec!(100),
+            store_balance_delta: dec!(80),
+            platform_commission: dec!(0),
+            gateway_commission: dec!(20),
+            description: None,
+            payment_gateway: Some(PaymentSystem::PlatformCard),
+            details: None,
+            bot_id: None,
+            payment_invoice_id: Some(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_deposit_publishes_transition() {
+        let repo = Arc::new(StatusGuardRepo {
+            invoice: test_invoice_row(InvoiceStatus::Processing),
+            last_update: Mutex::new(None),
+        });
+        let subscriber = Arc::new(RecordingSubscriber {
+            seen: Mutex::new(vec![]),
+        });
+        let service = status_guard_service(repo.clone(), subscriber.clone());
+
+        let completed = service
+            .complete_with_deposit(1, InvoiceStatus::Processing, test_deposit())
+            .await
+            .unwrap();
+
+        assert_eq!(completed.status, InvoiceStatus::Completed);
+        assert!(completed.finished_at.is_some());
+        assert_eq!(
+            *subscriber.seen.lock().unwrap(),
+            vec![(InvoiceStatus::Processing, InvoiceStatus::Completed)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_deposit_does_not_publish_when_rolled_back() {
+        let repo = Arc::new(StatusGuardRepo {
+            invoice: test_invoice_row(InvoiceStatus::Completed),
+            last_update: Mutex::new(None),
+        });
+        let subscriber = Arc::new(RecordingSubscriber {
+            seen: Mutex::new(vec![]),
+        });
+        let service = status_guard_service(repo.clone(), subscriber.clone());
+
+        let err = service
+            .complete_with_deposit(1, InvoiceStatus::Pending, test_deposit())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ApiError::Conflict(_)));
+        assert!(repo.last_update.lock().unwrap().is_none());
+        assert!(subscriber.seen.lock().unwrap().is_empty());
+    }
 }
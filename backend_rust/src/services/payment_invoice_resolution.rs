@@ -0,0 +1,592 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use shared_dtos::{
+    audit_log::{AuditAction, AuditStatus},
+    invoice::InvoiceStatus,
+};
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::payment_webhook_event::PaymentWebhookEventRepositoryTrait,
+    middlewares::context::RequestContext,
+    models::{
+        audit_log::{AuditLogRow, NewAuditLog},
+        payment_invoice::PaymentInvoiceRow,
+        payment_invoice_event::PaymentInvoiceEventRow,
+        payment_webhook_event::PaymentWebhookEventRow,
+    },
+    services::{
+        audit_log::AuditLogServiceTrait,
+        payment_invoice::{
+            PaymentInvoiceServiceTrait, SendInvoiceReceiptCommand, UpdatePaymentInvoiceCommand,
+        },
+        payment_processing_service::{PaymentProcessingServiceTrait, is_open},
+    },
+};
+
+const AUDIT_TARGET_TABLE: &str = "payment_invoices";
+
+#[derive(Debug, Default)]
+pub struct ResolvePaymentInvoiceCommand {
+    pub id: i64,
+    pub resolved_by: i64,
+    pub reason: Option<String>,
+    pub ctx: Option<RequestContext>,
+}
+
+/// Everything recorded about one invoice, for operators looking into a complaint
+#[derive(Debug)]
+pub struct PaymentInvoiceHistory {
+    pub invoice: PaymentInvoiceRow,
+    pub events: Vec<PaymentInvoiceEventRow>,
+    pub webhook_events: Vec<PaymentWebhookEventRow>,
+    pub actions: Vec<AuditLogRow>,
+}
+
+#[async_trait]
+pub trait PaymentInvoiceResolutionServiceTrait: Send + Sync {
+    async fn get_history(&self, id: i64) -> ApiResult<PaymentInvoiceHistory>;
+    /// Credits the customer as if the gateway reported the payment
+    async fn complete(&self, command: ResolvePaymentInvoiceCommand)
+    -> ApiResult<PaymentInvoiceRow>;
+    /// Closes an open invoice without crediting anything, `reason` is required
+    async fn fail(&self, command: ResolvePaymentInvoiceCommand) -> ApiResult<PaymentInvoiceRow>;
+    /// Submits the receipt the customer uploaded to the gateway again
+    async fn resend_receipt(
+        &self,
+        command: ResolvePaymentInvoiceCommand,
+    ) -> ApiResult<PaymentInvoiceRow>;
+}
+
+pub struct PaymentInvoiceResolutionService<I, P, W, A> {
+    payment_invoice_service: Arc<I>,
+    payment_processing_service: Arc<P>,
+    webhook_event_repo: Arc<W>,
+    audit_log_service: Arc<A>,
+}
+
+impl<I, P, W, A> PaymentInvoiceResolutionService<I, P, W, A>
+where
+    I: PaymentInvoiceServiceTrait + Send + Sync,
+    P: PaymentProcessingServiceTrait + Send + Sync,
+    W: PaymentWebhookEventRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    pub fn new(
+        payment_invoice_service: Arc<I>,
+        payment_processing_service: Arc<P>,
+        webhook_event_repo: Arc<W>,
+        audit_log_service: Arc<A>,
+    ) -> Self {
+        Self {
+            payment_invoice_service,
+            payment_processing_service,
+            webhook_event_repo,
+            audit_log_service,
+        }
+    }
+
+    /// Logs the outcome of an operator action, failed attempts included
+    async fn audit(
+        &self,
+        action: AuditAction,
+        command: ResolvePaymentInvoiceCommand,
+        prev: &PaymentInvoiceRow,
+        result: &ApiResult<PaymentInvoiceRow>,
+    ) -> ApiResult<()> {
+        let mut new_values = result
+            .as_ref()
+            .ok()
+            .and_then(|updated| serde_json::to_value(updated).ok());
+        if let (Some(serde_json::Value::Object(values)), Some(reason)) =
+            (new_values.as_mut(), command.reason)
+        {
+            values.insert("resolution_reason".to_string(), reason.into());
+        }
+
+        self.audit_log_service
+            .create(NewAuditLog {
+                action,
+                status: if result.is_ok() {
+                    AuditStatus::Success
+                } else {
+                    AuditStatus::Failed
+                },
+                admin_user_id: Some(command.resolved_by),
+                customer_id: None,
+                error_message: result.as_ref().err().map(ToString::to_string),
+                new_values,
+                old_values: serde_json::to_value(prev).ok(),
+                target_id: prev.id.to_string(),
+                target_table: AUDIT_TARGET_TABLE.to_string(),
+                ip_address: command.ctx.clone().and_then(|ctx| ctx.ip_address),
+                request_id: command.ctx.clone().map(|ctx| ctx.request_id),
+                user_agent: command.ctx.and_then(|ctx| ctx.user_agent),
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn do_complete(&self, invoice: &PaymentInvoiceRow) -> ApiResult<PaymentInvoiceRow> {
+        if matches!(
+            invoice.status,
+            InvoiceStatus::Completed | InvoiceStatus::Refunded
+        ) {
+            return Err(ApiError::BadRequest(
+                "Invoice is already completed".to_string(),
+            ));
+        }
+        self.payment_processing_service
+            .handle_payment_success(invoice.order_id)
+            .await?;
+        self.payment_invoice_service.get_by_id(invoice.id).await
+    }
+
+    async fn do_fail(
+        &self,
+        invoice: &PaymentInvoiceRow,
+        reason: Option<&str>,
+    ) -> ApiResult<PaymentInvoiceRow> {
+        if reason.is_none_or(|reason| reason.trim().is_empty()) {
+            return Err(ApiError::BadRequest("Reason is required".to_string()));
+        }
+        if !is_open(invoice.status) {
+            return Err(ApiError::BadRequest(
+                "Invoice is already finished".to_string(),
+            ));
+        }
+        self.payment_invoice_service
+            .update(UpdatePaymentInvoiceCommand {
+                id: invoice.id,
+                status: Some(InvoiceStatus::Failed),
+                finished_at: Some(Utc::now()),
+                ..Default::default()
+            })
+            .await
+    }
+
+    async fn do_resend_receipt(&self, invoice: &PaymentInvoiceRow) -> ApiResult<PaymentInvoiceRow> {
+        let Some(receipt_url) = invoice.receipt_url.clone() else {
+            return Err(ApiError::BadRequest("Invoice has no receipt".to_string()));
+        };
+        if !matches!(
+            invoice.status,
+            InvoiceStatus::AwaitingReceipt
+                | InvoiceStatus::ReceiptSubmitted
+                | InvoiceStatus::Disputed
+        ) {
+            return Err(ApiError::BadRequest(
+                "Invoice is not waiting for a receipt".to_string(),
+            ));
+        }
+        self.payment_invoice_service
+            .send_invoice_receipt(SendInvoiceReceiptCommand {
+                id: invoice.id,
+                receipt_url,
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<I, P, W, A> PaymentInvoiceResolutionServiceTrait
+    for PaymentInvoiceResolutionService<I, P, W, A>
+where
+    I: PaymentInvoiceServiceTrait + Send + Sync,
+    P: PaymentProcessingServiceTrait + Send + Sync,
+    W: PaymentWebhookEventRepositoryTrait + Send + Sync,
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    async fn get_history(&self, id: i64) -> ApiResult<PaymentInvoiceHistory> {
+        let invoice = self.payment_invoice_service.get_by_id(id).await?;
+        let events = self.payment_invoice_service.get_events(id).await?;
+        let webhook_events = self
+            .webhook_event_repo
+            .get_for_invoice(invoice.gateway, &invoice.gateway_invoice_id)
+            .await?;
+        let actions = self
+            .audit_log_service
+            .get_for_target(AUDIT_TARGET_TABLE, &id.to_string())
+            .await?;
+
+        Ok(PaymentInvoiceHistory {
+            invoice,
+            events,
+            webhook_events,
+            actions,
+        })
+    }
+
+    async fn complete(
+        &self,
+        command: ResolvePaymentInvoiceCommand,
+    ) -> ApiResult<PaymentInvoiceRow> {
+        let invoice = self.payment_invoice_service.get_by_id(command.id).await?;
+        let result = self.do_complete(&invoice).await;
+        self.audit(
+            AuditAction::InvoiceManualComplete,
+            command,
+            &invoice,
+            &result,
+        )
+        .await?;
+        result
+    }
+
+    async fn fail(&self, command: ResolvePaymentInvoiceCommand) -> ApiResult<PaymentInvoiceRow> {
+        let invoice = self.payment_invoice_service.get_by_id(command.id).await?;
+        let result = self.do_fail(&invoice, command.reason.as_deref()).await;
+        self.audit(AuditAction::InvoiceManualFail, command, &invoice, &result)
+            .await?;
+        result
+    }
+
+    async fn resend_receipt(
+        &self,
+        command: ResolvePaymentInvoiceCommand,
+    ) -> ApiResult<PaymentInvoiceRow> {
+        let invoice = self.payment_invoice_service.get_by_id(command.id).await?;
+        let result = self.do_resend_receipt(&invoice).await;
+        self.audit(
+            AuditAction::InvoiceReceiptResend,
+            command,
+            &invoice,
+            &result,
+        )
+        .await?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::{DateTime, Duration};
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+    use shared_dtos::{currency::Currency, invoice::PaymentSystem};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        infrastructure::repositories::{
+            audit_log::AuditLogRepository, payment_webhook_event::PaymentWebhookEventRepository,
+        },
+        models::{
+            common::PaginatedResult, payment_invoice::PaymentInvoiceListQuery,
+            payment_webhook_event::NewPaymentWebhookEvent, transaction::NewTransaction,
+        },
+        services::{
+            audit_log::AuditLogService,
+            payment_invoice::CreatePaymentInvoiceCommand,
+            payment_processing_service::{GatewayStatusUpdate, HandleWebhookEventCommand},
+        },
+    };
+
+    /// Keeps one invoice in memory, shared with the fake processing service
+    struct FakePaymentInvoiceService {
+        invoice: Arc<Mutex<PaymentInvoiceRow>>,
+        receipts_sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl PaymentInvoiceServiceTrait for FakePaymentInvoiceService {
+        async fn get_list(
+            &self,
+            _query: PaymentInvoiceListQuery,
+        ) -> ApiResult<PaginatedResult<PaymentInvoiceRow>> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn create(
+            &self,
+            _command: CreatePaymentInvoiceCommand,
+        ) -> ApiResult<PaymentInvoiceRow> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn get_by_id(&self, id: i64) -> ApiResult<PaymentInvoiceRow> {
+            let invoice = self.invoice.lock().unwrap().clone();
+            if invoice.id != id {
+                return Err(ApiError::NotFound("Invoice not found".to_string()));
+            }
+            Ok(invoice)
+        }
+
+        async fn get_by_order_id(&self, _order_id: Uuid) -> ApiResult<PaymentInvoiceRow> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn get_by_gateway_invoice_id(
+            &self,
+            _gateway: PaymentSystem,
+            _gateway_invoice_id: &str,
+        ) -> ApiResult<PaymentInvoiceRow> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn update(
+            &self,
+            command: UpdatePaymentInvoiceCommand,
+        ) -> ApiResult<PaymentInvoiceRow> {
+            let mut invoice = self.invoice.lock().unwrap();
+            if let Some(status) = command.status {
+                invoice.status = status;
+            }
+            if let Some(finished_at) = command.finished_at {
+                invoice.finished_at = Some(finished_at);
+            }
+            Ok(invoice.clone())
+        }
+
+        async fn complete_with_deposit(
+            &self,
+            _id: i64,
+            _expected_status: InvoiceStatus,
+            _deposit: NewTransaction,
+        ) -> ApiResult<PaymentInvoiceRow> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn get_for_customer(&self, _customer_id: i64) -> ApiResult<Vec<PaymentInvoiceRow>> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn expire_old_invoices(&self) -> ApiResult<u64> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn get_pending_invoices(
+            &self,
+            _older_than: DateTime<Utc>,
+        ) -> ApiResult<Vec<PaymentInvoiceRow>> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn mark_invoices_notified(&self, _ids: &[i64]) -> ApiResult<u64> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn confirm_invoice(&self, _id: i64) -> ApiResult<PaymentInvoiceRow> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn cancel_invoice(&self, _id: i64) -> ApiResult<PaymentInvoiceRow> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn send_invoice_receipt(
+            &self,
+            command: SendInvoiceReceiptCommand,
+        ) -> ApiResult<PaymentInvoiceRow> {
+            self.receipts_sent.lock().unwrap().push(command.receipt_url);
+            let mut invoice = self.invoice.lock().unwrap();
+            invoice.status = InvoiceStatus::ReceiptSubmitted;
+            Ok(invoice.clone())
+        }
+
+        async fn record_event(
+            &self,
+            _event: crate::models::payment_invoice_event::NewPaymentInvoiceEvent,
+        ) -> ApiResult<bool> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn record_event_if_changed(
+            &self,
+            _event: crate::models::payment_invoice_event::NewPaymentInvoiceEvent,
+        ) -> ApiResult<bool> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn has_event(&self, _invoice_id: i64, _dedup_key: &str) -> ApiResult<bool> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn get_events(&self, _invoice_id: i64) -> ApiResult<Vec<PaymentInvoiceEventRow>> {
+            Ok(vec![])
+        }
+    }
+
+    struct FakePaymentProcessingService {
+        invoice: Arc<Mutex<PaymentInvoiceRow>>,
+    }
+
+    #[async_trait]
+    impl PaymentProcessingServiceTrait for FakePaymentProcessingService {
+        async fn handle_payment_success(&self, order_id: Uuid) -> ApiResult<()> {
+            let mut invoice = self.invoice.lock().unwrap();
+            assert_eq!(invoice.order_id, order_id);
+            invoice.status = InvoiceStatus::Completed;
+            invoice.finished_at = Some(Utc::now());
+            Ok(())
+        }
+
+        async fn handle_gateway_status(
+            &self,
+            _invoice: &PaymentInvoiceRow,
+            _customer: &crate::models::customer::CustomerRow,
+            _update: GatewayStatusUpdate,
+        ) -> ApiResult<()> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn handle_webhook_event(
+            &self,
+            _command: HandleWebhookEventCommand,
+        ) -> ApiResult<bool> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+    }
+
+    type TestService = PaymentInvoiceResolutionService<
+        FakePaymentInvoiceService,
+        FakePaymentProcessingService,
+        PaymentWebhookEventRepository,
+        AuditLogService<AuditLogRepository>,
+    >;
+
+    fn test_invoice(status: InvoiceStatus, receipt_url: Option<&str>) -> PaymentInvoiceRow {
+        let now = Utc::now();
+        PaymentInvoiceRow {
+            id: 7,
+            customer_id: 10,
+            original_amount: dec!(1000),
+            amount: dec!(1000),
+            amount_in_usdt: dec!(10),
+            status,
+            created_at: now,
+            updated_at: now,
+            expires_at: now + Duration::days(1),
+            deleted_at: None,
+            gateway: PaymentSystem::PlatformCard,
+            gateway_invoice_id: "gw-7".to_string(),
+            order_id: Uuid::new_v4(),
+            payment_details: json!({}),
+            bot_message_id: None,
+            notification_sent_at: None,
+            receipt_requested_at: None,
+            receipt_submitted_at: None,
+            receipt_url: receipt_url.map(str::to_string),
+            dispute_opened_at: None,
+            finished_at: None,
+            currency: Currency::Rub,
+            checkout_order_id: None,
+        }
+    }
+
+    fn service(
+        pool: &Arc<PgPool>,
+        invoice: PaymentInvoiceRow,
+    ) -> (TestService, Arc<FakePaymentInvoiceService>) {
+        let invoice = Arc::new(Mutex::new(invoice));
+        let invoice_service = Arc::new(FakePaymentInvoiceService {
+            invoice: invoice.clone(),
+            receipts_sent: Mutex::new(vec![]),
+        });
+        let service = PaymentInvoiceResolutionService::new(
+            invoice_service.clone(),
+            Arc::new(FakePaymentProcessingService { invoice }),
+            Arc::new(PaymentWebhookEventRepository::new(pool.clone())),
+            Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+                pool.clone(),
+            )))),
+        );
+        (service, invoice_service)
+    }
+
+    fn command(reason: Option<&str>) -> ResolvePaymentInvoiceCommand {
+        ResolvePaymentInvoiceCommand {
+            id: 7,
+            resolved_by: 1,
+            reason: reason.map(str::to_string),
+            ctx: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_complete_credits_and_shows_in_history(pool: PgPool) {
+        let pool = Arc::new(pool);
+        let (service, _) = service(&pool, test_invoice(InvoiceStatus::Disputed, None));
+        PaymentWebhookEventRepository::new(pool.clone())
+            .register(NewPaymentWebhookEvent {
+                gateway: PaymentSystem::PlatformCard,
+                event_id: "ev-1".to_string(),
+                gateway_invoice_id: "gw-7".to_string(),
+                payload: json!({"status": "appeal"}),
+            })
+            .await
+            .unwrap();
+
+        let completed = service
+            .complete(command(Some("Payment found in the bank statement")))
+            .await
+            .unwrap();
+        assert_eq!(completed.status, InvoiceStatus::Completed);
+
+        // Already credited, a second completion is refused and logged as failed
+        let err = service.complete(command(None)).await.unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+
+        let history = service.get_history(7).await.unwrap();
+        assert_eq!(history.invoice.status, InvoiceStatus::Completed);
+        assert_eq!(history.webhook_events.len(), 1);
+        assert_eq!(history.webhook_events[0].event_id, "ev-1");
+        assert_eq!(history.actions.len(), 2);
+        assert_eq!(
+            history.actions[0].action,
+            AuditAction::InvoiceManualComplete
+        );
+        assert_eq!(history.actions[0].status, AuditStatus::Success);
+        assert_eq!(
+            history.actions[0].new_values.as_ref().unwrap()["resolution_reason"],
+            "Payment found in the bank statement"
+        );
+        assert_eq!(history.actions[1].status, AuditStatus::Failed);
+    }
+
+    #[sqlx::test]
+    async fn test_fail_requires_reason_and_open_invoice(pool: PgPool) {
+        let pool = Arc::new(pool);
+        let (service, _) = service(&pool, test_invoice(InvoiceStatus::ReceiptSubmitted, None));
+
+        let err = service.fail(command(Some("  "))).await.unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+
+        let failed = service.fail(command(Some("Fake receipt"))).await.unwrap();
+        assert_eq!(failed.status, InvoiceStatus::Failed);
+        assert!(failed.finished_at.is_some());
+
+        let err = service
+            .fail(command(Some("Fake receipt")))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+    }
+
+    #[sqlx::test]
+    async fn test_resend_receipt_uses_stored_url(pool: PgPool) {
+        let pool = Arc::new(pool);
+        let (without_receipt, _) = service(&pool, test_invoice(InvoiceStatus::Disputed, None));
+        let err = without_receipt
+            .resend_receipt(command(None))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+
+        let (with_receipt, invoice_service) = service(
+            &pool,
+            test_invoice(InvoiceStatus::Disputed, Some("https://files.fm/f/abc")),
+        );
+        let resent = with_receipt.resend_receipt(command(None)).await.unwrap();
+        assert_eq!(resent.status, InvoiceStatus::ReceiptSubmitted);
+        assert_eq!(
+            *invoice_service.receipts_sent.lock().unwrap(),
+            vec!["https://files.fm/f/abc".to_string()]
+        );
+    }
+}
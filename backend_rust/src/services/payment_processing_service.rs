@@ -1,67 +1,382 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
 use shared_dtos::{
-    invoice::InvoiceStatus,
+    currency::Currency,
+    invoice::{InvoiceStatus, PaymentSystem},
     notification::{DispatchMessage, DispatchMessagePayload},
+    order::PurchaseBotResponse,
     transaction::TransactionType,
 };
 use uuid::Uuid;
 
 use crate::{
-    errors::api::ApiResult,
-    models::transaction::NewTransaction,
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::payment_webhook_event::PaymentWebhookEventRepositoryTrait,
+    models::{
+        customer::CustomerRow, payment_invoice::PaymentInvoiceRow,
+        payment_invoice_event::NewPaymentInvoiceEvent,
+        payment_webhook_event::NewPaymentWebhookEvent, status_transition::StatusMachine,
+        transaction::NewTransaction,
+    },
     services::{
-        customer::CustomerServiceTrait,
+        customer::{CustomerServiceTrait, UpdateCustomerCommand},
         notification_service::NotificationServiceTrait,
         payment_invoice::{PaymentInvoiceServiceTrait, UpdatePaymentInvoiceCommand},
-        transaction::TransactionServiceTrait,
+        purchase::PurchaseServiceTrait,
     },
 };
 use rust_decimal_macros::dec;
 
+// TODO Should be configurable
+const REMINDER_INTERVAL_MINUTES: i64 = 5;
+pub const PAYMENT_TIMEOUT_MINUTES: i64 = 30;
+
+/// Invoice status reported by the payment gateway, polled or pushed by a webhook
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GatewayStatusUpdate {
+    pub status: InvoiceStatus,
+    /// The gateway flagged the payer as a fraudster (fake receipt appeal)
+    pub is_fraud: bool,
+}
+
+#[derive(Debug)]
+pub struct HandleWebhookEventCommand {
+    pub gateway: PaymentSystem,
+    pub event_id: String,
+    pub gateway_invoice_id: String,
+    pub update: GatewayStatusUpdate,
+    pub payload: serde_json::Value,
+}
+
 #[async_trait]
 pub trait PaymentProcessingServiceTrait: Send + Sync {
     async fn handle_payment_success(&self, order_id: Uuid) -> ApiResult<()>;
+    /// Moves an open invoice towards the gateway status: credits completed payments,
+    /// requests receipts, sends reminders and dispute notices, blocks fraudsters
+    async fn handle_gateway_status(
+        &self,
+        invoice: &PaymentInvoiceRow,
+        customer: &CustomerRow,
+        update: GatewayStatusUpdate,
+    ) -> ApiResult<()>;
+    /// Applies a webhook status event once per gateway event id. Returns `false` for
+    /// an event that was already processed.
+    async fn handle_webhook_event(&self, command: HandleWebhookEventCommand) -> ApiResult<bool>;
 }
 
-pub struct PaymentProcessingService<T, P, N, C> {
-    pub transactions_service: Arc<T>,
+pub struct PaymentProcessingService<P, N, C, W, U> {
     pub payment_invoice_service: Arc<P>,
     pub notification_service: Arc<N>,
     pub customer_service: Arc<C>,
+    pub webhook_event_repo: Arc<W>,
+    pub purchase_service: Arc<U>,
+    /// Currency invoices credit balances in
+    pub base_currency: Currency,
 }
 
-impl<T, P, N, C> PaymentProcessingService<T, P, N, C>
+impl<P, N, C, W, U> PaymentProcessingService<P, N, C, W, U>
 where
-    T: TransactionServiceTrait + Send + Sync,
     P: PaymentInvoiceServiceTrait + Send + Sync,
     N: NotificationServiceTrait + Send + Sync,
     C: CustomerServiceTrait + Send + Sync,
+    W: PaymentWebhookEventRepositoryTrait + Send + Sync,
+    U: PurchaseServiceTrait + Send + Sync,
 {
     pub fn new(
-        transactions_service: Arc<T>,
         payment_invoice_service: Arc<P>,
         notification_service: Arc<N>,
         customer_service: Arc<C>,
+        webhook_event_repo: Arc<W>,
+        purchase_service: Arc<U>,
+        base_currency: Currency,
     ) -> Self {
         Self {
-            transactions_service,
             payment_invoice_service,
             notification_service,
             customer_service,
+            webhook_event_repo,
+            purchase_service,
+            base_currency,
+        }
+    }
+
+    async fn notify(&self, customer: &CustomerRow, message: DispatchMessage) -> ApiResult<()> {
+        self.notification_service
+            .dispatch_message(DispatchMessagePayload {
+                message,
+                telegram_id: customer.telegram_id,
+                bot_id: customer.last_seen_with_bot,
+            })
+            .await
+    }
+
+    /// Sends a notification unless it was already sent for this state of the invoice, e.g.
+    /// by a webhook racing the pending payments worker or before a failed invoice update
+    async fn notify_once(
+        &self,
+        invoice: &PaymentInvoiceRow,
+        customer: &CustomerRow,
+        name: &str,
+        message: DispatchMessage,
+    ) -> ApiResult<()> {
+        let dedup_key = format!("{name}:{}", invoice.updated_at.timestamp_micros());
+        if self
+            .payment_invoice_service
+            .has_event(invoice.id, &dedup_key)
+            .await?
+        {
+            tracing::info!(
+                "Skipping already sent {dedup_key} notification for invoice {}",
+                invoice.id
+            );
+            return Ok(());
+        }
+
+        let payload = serde_json::to_value(&message).unwrap_or_default();
+        self.notify(customer, message).await?;
+        self.payment_invoice_service
+            .record_event(NewPaymentInvoiceEvent::notification(
+                invoice.id, dedup_key, payload,
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn update_invoice(&self, command: UpdatePaymentInvoiceCommand) -> ApiResult<()> {
+        self.payment_invoice_service.update(command).await?;
+        Ok(())
+    }
+
+    /// Moves the invoice out of the status it had when the gateway status was read. Losing
+    /// the race to a webhook or the poller is expected and not an error.
+    async fn transition_invoice(
+        &self,
+        invoice: &PaymentInvoiceRow,
+        command: UpdatePaymentInvoiceCommand,
+    ) -> ApiResult<()> {
+        let to = command.status;
+        match self
+            .payment_invoice_service
+            .update(UpdatePaymentInvoiceCommand {
+                expected_status: Some(invoice.status),
+                ..command
+            })
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(ApiError::Conflict(reason)) => {
+                tracing::info!(
+                    "Skipping {:?} -> {to:?} for invoice {}: {reason}",
+                    invoice.status,
+                    invoice.id
+                );
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn apply_status_transition(
+        &self,
+        invoice: &PaymentInvoiceRow,
+        customer: &CustomerRow,
+        status: InvoiceStatus,
+    ) -> ApiResult<()> {
+        let now = Utc::now();
+        let reminder_interval = Duration::minutes(REMINDER_INTERVAL_MINUTES);
+        let payment_timeout = Duration::minutes(PAYMENT_TIMEOUT_MINUTES);
+
+        match (invoice.status, status) {
+            (_, InvoiceStatus::Completed) => self.handle_payment_success(invoice.order_id).await,
+            (InvoiceStatus::Pending, InvoiceStatus::Pending) => {
+                let last_notification_sent_at =
+                    invoice.notification_sent_at.unwrap_or(invoice.created_at);
+                let is_notification_required = last_notification_sent_at + reminder_interval < now;
+                let is_pending_expired = invoice.created_at + payment_timeout < now;
+                if !is_notification_required || is_pending_expired {
+                    return Ok(());
+                }
+
+                self.notify_once(
+                    invoice,
+                    customer,
+                    "invoice_troubles",
+                    DispatchMessage::InvoiceTroublesNotification {
+                        invoice_id: invoice.id,
+                        amount: invoice.original_amount.to_f64().unwrap_or_default(),
+                        currency: self.base_currency,
+                        expired_at: invoice.created_at + payment_timeout,
+                    },
+                )
+                .await?;
+                self.payment_invoice_service
+                    .mark_invoices_notified(&[invoice.id])
+                    .await?;
+                Ok(())
+            }
+            (InvoiceStatus::AwaitingReceipt, InvoiceStatus::AwaitingReceipt) => {
+                let (Some(notification_sent_at), Some(receipt_requested_at)) =
+                    (invoice.notification_sent_at, invoice.receipt_requested_at)
+                else {
+                    return Ok(());
+                };
+                let is_request_not_expired = receipt_requested_at + payment_timeout > now;
+                let is_time_to_notify = notification_sent_at + reminder_interval < now;
+                if !is_request_not_expired || !is_time_to_notify {
+                    return Ok(());
+                }
+
+                self.notify_once(
+                    invoice,
+                    customer,
+                    "request_receipt_reminder",
+                    DispatchMessage::RequestReceiptNotification {
+                        invoice_id: invoice.id,
+                        is_first_time: false,
+                        expired_at: receipt_requested_at + payment_timeout,
+                    },
+                )
+                .await?;
+                self.update_invoice(UpdatePaymentInvoiceCommand {
+                    id: invoice.id,
+                    notification_sent_at: Some(Some(now)),
+                    ..Default::default()
+                })
+                .await
+            }
+            // The customer is notified about these by the invoice transition subscribers
+            (
+                InvoiceStatus::Pending | InvoiceStatus::Processing | InvoiceStatus::Disputed,
+                InvoiceStatus::AwaitingReceipt,
+            ) => {
+                self.transition_invoice(
+                    invoice,
+                    UpdatePaymentInvoiceCommand {
+                        id: invoice.id,
+                        status: Some(InvoiceStatus::AwaitingReceipt),
+                        receipt_requested_at: Some(now),
+                        notification_sent_at: Some(Some(now)),
+                        ..Default::default()
+                    },
+                )
+                .await
+            }
+            (InvoiceStatus::ReceiptSubmitted, InvoiceStatus::Disputed) => {
+                self.transition_invoice(
+                    invoice,
+                    UpdatePaymentInvoiceCommand {
+                        id: invoice.id,
+                        status: Some(InvoiceStatus::Disputed),
+                        dispute_opened_at: Some(now),
+                        ..Default::default()
+                    },
+                )
+                .await
+            }
+            (
+                from,
+                to @ (InvoiceStatus::Failed | InvoiceStatus::Expired | InvoiceStatus::Cancelled),
+            ) if from != to => {
+                self.transition_invoice(
+                    invoice,
+                    UpdatePaymentInvoiceCommand {
+                        id: invoice.id,
+                        status: Some(to),
+                        finished_at: Some(now),
+                        ..Default::default()
+                    },
+                )
+                .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn block_fraud(&self, invoice: &PaymentInvoiceRow) -> ApiResult<()> {
+        tracing::info!(
+            "Fraud user detected: invoice_id: {}, customer_id: {}",
+            invoice.id,
+            invoice.customer_id
+        );
+        // The gateway status may have already closed the invoice
+        let invoice = self.payment_invoice_service.get_by_id(invoice.id).await?;
+        if is_open(invoice.status) {
+            self.transition_invoice(
+                &invoice,
+                UpdatePaymentInvoiceCommand {
+                    id: invoice.id,
+                    status: Some(InvoiceStatus::Failed),
+                    finished_at: Some(Utc::now()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+        self.customer_service
+            .update(UpdateCustomerCommand {
+                id: invoice.customer_id,
+                is_blocked: Some(true),
+                ..Default::default()
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn apply_webhook_event(&self, command: &HandleWebhookEventCommand) -> ApiResult<()> {
+        let invoice = self
+            .payment_invoice_service
+            .get_by_gateway_invoice_id(command.gateway, &command.gateway_invoice_id)
+            .await?;
+        self.payment_invoice_service
+            .record_event(NewPaymentInvoiceEvent::webhook(
+                invoice.id,
+                &command.event_id,
+                command.payload.clone(),
+            ))
+            .await?;
+        // A payment the gateway confirms after we closed the invoice is still credited
+        let late_payment = command.update.status == InvoiceStatus::Completed
+            && invoice.status.can_transition_to(InvoiceStatus::Completed);
+        if !is_open(invoice.status) && !late_payment {
+            tracing::info!(
+                "Ignoring {:?} webhook event {} for {:?} invoice {}",
+                command.gateway,
+                command.event_id,
+                invoice.status,
+                invoice.id
+            );
+            return Ok(());
         }
+
+        let customer = self.customer_service.get_by_id(invoice.customer_id).await?;
+        self.handle_gateway_status(&invoice, &customer, command.update)
+            .await
     }
 }
 
+/// Statuses the gateway can still move, same set the pending payments worker polls
+pub fn is_open(status: InvoiceStatus) -> bool {
+    matches!(
+        status,
+        InvoiceStatus::Pending
+            | InvoiceStatus::Processing
+            | InvoiceStatus::AwaitingReceipt
+            | InvoiceStatus::ReceiptSubmitted
+            | InvoiceStatus::Disputed
+    )
+}
+
 #[async_trait]
-impl<T, P, N, C> PaymentProcessingServiceTrait for PaymentProcessingService<T, P, N, C>
+impl<P, N, C, W, U> PaymentProcessingServiceTrait for PaymentProcessingService<P, N, C, W, U>
 where
-    T: TransactionServiceTrait + Send + Sync,
     P: PaymentInvoiceServiceTrait + Send + Sync,
     N: NotificationServiceTrait + Send + Sync,
     C: CustomerServiceTrait + Send + Sync,
+    W: PaymentWebhookEventRepositoryTrait + Send + Sync,
+    U: PurchaseServiceTrait + Send + Sync,
 {
     async fn handle_payment_success(&self, order_id: Uuid) -> ApiResult<()> {
         let payment_invoice = self
@@ -73,6 +388,14 @@ where
             .get_by_id(payment_invoice.customer_id)
             .await?;
 
+        if !payment_invoice
+            .status
+            .can_transition_to(InvoiceStatus::Completed)
+        {
+            return Err(ApiError::Conflict(
+                "Invoice is already completed".to_string(),
+            ));
+        }
         let gateway_commission_percent = dec!(0.2); // TODO get from settings
         let platform_commission_percent = dec!(0.00); // TODO get from settings TEMPORARY DISABLED
         let platform_commission = payment_invoice.amount_in_usdt * platform_commission_percent;
@@ -80,43 +403,107 @@ where
         let store_balance_delta = payment_invoice.amount_in_usdt
             * (dec!(1) - platform_commission_percent - gateway_commission_percent);
 
-        self.transactions_service
-            .create(NewTransaction {
-                amount: payment_invoice.original_amount,
-                customer_id: Some(payment_invoice.customer_id),
-                r#type: TransactionType::Deposit,
-                store_balance_delta,
-                platform_commission,
-                gateway_commission,
-                description: None,
-                payment_gateway: Some(payment_invoice.gateway),
-                details: Some(payment_invoice.payment_details),
-                order_id: None, // Not invoice order id
-                bot_id: None,
-            })
-            .await?;
+        // Conditional on the status seen above, so a webhook and the poller can't both credit it
         self.payment_invoice_service
-            .update(UpdatePaymentInvoiceCommand {
-                id: payment_invoice.id,
-                status: Some(InvoiceStatus::Completed),
-                finished_at: Some(Utc::now()),
-                ..Default::default()
-            })
-            .await?;
-        self.notification_service
-            .dispatch_message(DispatchMessagePayload {
-                bot_id: customer.last_seen_with_bot,
-                message: DispatchMessage::GenericMessage {
-                    image_id: None,
-                    message: format!(
-                        "✅ Баланс пополнен на {} RUB",
-                        payment_invoice.original_amount.trunc_with_scale(2)
-                    ),
+            .complete_with_deposit(
+                payment_invoice.id,
+                payment_invoice.status,
+                NewTransaction {
+                    amount: payment_invoice.original_amount,
+                    customer_id: Some(payment_invoice.customer_id),
+                    r#type: TransactionType::Deposit,
+                    store_balance_delta,
+                    platform_commission,
+                    gateway_commission,
+                    description: None,
+                    payment_gateway: Some(payment_invoice.gateway),
+                    details: Some(payment_invoice.payment_details),
+                    order_id: None, // Not invoice order id
+                    bot_id: None,
+                    payment_invoice_id: Some(payment_invoice.id),
                 },
-                telegram_id: customer.telegram_id,
+            )
+            .await?;
+        let deposited = format!(
+            "✅ Баланс пополнен на {} {}",
+            payment_invoice.original_amount.trunc_with_scale(2),
+            self.base_currency
+        );
+        let message = match payment_invoice.checkout_order_id {
+            None => DispatchMessage::GenericMessage {
+                image_id: None,
+                message: deposited,
+            },
+            Some(checkout_order_id) => {
+                match self
+                    .purchase_service
+                    .complete_checkout_order(checkout_order_id)
+                    .await
+                {
+                    Ok(result) => DispatchMessage::CheckoutOrderFulfilledNotification {
+                        order_id: checkout_order_id,
+                        purchase: PurchaseBotResponse::from(result),
+                    },
+                    // The deposit is already credited, the customer can buy from the balance
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to complete checkout order {checkout_order_id} of invoice {}: {e}",
+                            payment_invoice.id
+                        );
+                        DispatchMessage::GenericMessage {
+                            image_id: None,
+                            message: format!(
+                                "{deposited}\n\nНе удалось оформить заказ #{checkout_order_id}, \
+                                 средства остались на балансе."
+                            ),
+                        }
+                    }
+                }
+            }
+        };
+        self.notify(&customer, message).await
+    }
+
+    async fn handle_gateway_status(
+        &self,
+        invoice: &PaymentInvoiceRow,
+        customer: &CustomerRow,
+        update: GatewayStatusUpdate,
+    ) -> ApiResult<()> {
+        let result = self
+            .apply_status_transition(invoice, customer, update.status)
+            .await;
+        // Fraud is blocked even if the transition failed, e.g. the notification wasn't delivered
+        if update.is_fraud {
+            self.block_fraud(invoice).await?;
+        }
+        result
+    }
+
+    async fn handle_webhook_event(&self, command: HandleWebhookEventCommand) -> ApiResult<bool> {
+        let Some(event) = self
+            .webhook_event_repo
+            .register(NewPaymentWebhookEvent {
+                gateway: command.gateway,
+                event_id: command.event_id.clone(),
+                gateway_invoice_id: command.gateway_invoice_id.clone(),
+                payload: command.payload.clone(),
             })
+            .await?
+        else {
+            tracing::info!(
+                "Skipping already processed {:?} webhook event {}",
+                command.gateway,
+                command.event_id
+            );
+            return Ok(false);
+        };
+
+        let result = self.apply_webhook_event(&command).await;
+        self.webhook_event_repo
+            .mark_processed(event.id, result.as_ref().err().map(ToString::to_string))
             .await?;
-        Ok(())
+        result.map(|_| true)
     }
 }
 
@@ -128,54 +515,50 @@ mod tests {
     use chrono::Utc;
     use rust_decimal_macros::dec;
     use serde_json::json;
-    use shared_dtos::{invoice::PaymentSystem, notification::DispatchAdminMessage};
+    use shared_dtos::{invoice::PaymentInvoiceEventKind, notification::DispatchAdminMessage};
     use std::sync::Mutex;
 
     use crate::{
-        errors::api::{ApiError, ApiResult},
+        errors::{
+            api::{ApiError, ApiResult},
+            repository::RepositoryResult,
+        },
         models::{
-            customer::CustomerRow, payment_invoice::PaymentInvoiceRow, transaction::TransactionRow,
+            payment_invoice_event::PaymentInvoiceEventRow,
+            payment_webhook_event::PaymentWebhookEventRow,
         },
         services::{
-            customer::UpdateCustomerCommand,
             payment_invoice::{CreatePaymentInvoiceCommand, SendInvoiceReceiptCommand},
+            purchase::PurchaseResult,
         },
     };
 
-    struct FakeTransactionService {
-        last_created: Mutex<Option<NewTransaction>>,
-        created_row: Mutex<Option<TransactionRow>>,
+    struct FakePaymentInvoiceService {
+        invoice: PaymentInvoiceRow,
+        updated: Mutex<Vec<UpdatePaymentInvoiceCommand>>,
+        deposits: Mutex<Vec<NewTransaction>>,
+        notified: Mutex<Vec<i64>>,
+        events: Mutex<Vec<NewPaymentInvoiceEvent>>,
     }
 
-    #[async_trait]
-    impl TransactionServiceTrait for FakeTransactionService {
-        async fn get_list(
-            &self,
-            _query: crate::models::transaction::TransactionListQuery,
-        ) -> ApiResult<crate::models::common::PaginatedResult<TransactionRow>> {
-            Err(ApiError::InternalServerError("not used".to_string()))
-        }
-
-        async fn create(&self, transaction: NewTransaction) -> ApiResult<TransactionRow> {
-            *self.last_created.lock().unwrap() = Some(transaction);
-            Ok(self
-                .created_row
+    impl FakePaymentInvoiceService {
+        /// The invoice with all status updates applied so far
+        fn current(&self) -> PaymentInvoiceRow {
+            let mut invoice = self.invoice.clone();
+            if let Some(status) = self
+                .updated
                 .lock()
                 .unwrap()
-                .take()
-                .expect("transaction row"))
-        }
-
-        async fn get_last(&self) -> ApiResult<TransactionRow> {
-            Err(ApiError::InternalServerError("not used".to_string()))
+                .iter()
+                .rev()
+                .find_map(|c| c.status)
+            {
+                invoice.status = status;
+            }
+            invoice
         }
     }
 
-    struct FakePaymentInvoiceService {
-        invoice: PaymentInvoiceRow,
-        updated: Mutex<Option<UpdatePaymentInvoiceCommand>>,
-    }
-
     #[async_trait]
     impl PaymentInvoiceServiceTrait for FakePaymentInvoiceService {
         async fn get_list(
@@ -192,8 +575,9 @@ mod tests {
             Err(ApiError::InternalServerError("not used".to_string()))
         }
 
-        async fn get_by_id(&self, _id: i64) -> ApiResult<PaymentInvoiceRow> {
-            Err(ApiError::InternalServerError("not used".to_string()))
+        async fn get_by_id(&self, id: i64) -> ApiResult<PaymentInvoiceRow> {
+            assert_eq!(id, self.invoice.id);
+            Ok(self.current())
         }
 
         async fn get_by_order_id(&self, order_id: Uuid) -> ApiResult<PaymentInvoiceRow> {
@@ -201,23 +585,56 @@ mod tests {
             Ok(self.invoice.clone())
         }
 
+        async fn get_by_gateway_invoice_id(
+            &self,
+            gateway: PaymentSystem,
+            gateway_invoice_id: &str,
+        ) -> ApiResult<PaymentInvoiceRow> {
+            assert_eq!(gateway, self.invoice.gateway);
+            assert_eq!(gateway_invoice_id, self.invoice.gateway_invoice_id);
+            Ok(self.invoice.clone())
+        }
+
         async fn update(
             &self,
             command: UpdatePaymentInvoiceCommand,
         ) -> ApiResult<PaymentInvoiceRow> {
-            *self.updated.lock().unwrap() = Some(command);
-            let mut updated = self.invoice.clone();
-            if let Some(cmd) = self.updated.lock().unwrap().as_ref() {
-                if let Some(status) = cmd.status {
-                    updated.status = status;
-                }
-                if let Some(notification_sent_at) = cmd.notification_sent_at {
-                    updated.notification_sent_at = notification_sent_at;
-                }
+            let mut updated = self.current();
+            if command
+                .expected_status
+                .is_some_and(|expected| expected != updated.status)
+            {
+                return Err(ApiError::Conflict("Invoice status changed".to_string()));
+            }
+            if let Some(status) = command.status {
+                updated.status = status;
             }
+            if let Some(notification_sent_at) = command.notification_sent_at {
+                updated.notification_sent_at = notification_sent_at;
+            }
+            self.updated.lock().unwrap().push(command);
             Ok(updated)
         }
 
+        async fn complete_with_deposit(
+            &self,
+            id: i64,
+            expected_status: InvoiceStatus,
+            deposit: NewTransaction,
+        ) -> ApiResult<PaymentInvoiceRow> {
+            let completed = self
+                .update(UpdatePaymentInvoiceCommand {
+                    id,
+                    status: Some(InvoiceStatus::Completed),
+                    expected_status: Some(expected_status),
+                    finished_at: Some(Utc::now()),
+                    ..Default::default()
+                })
+                .await?;
+            self.deposits.lock().unwrap().push(deposit);
+            Ok(completed)
+        }
+
         async fn get_for_customer(&self, _customer_id: i64) -> ApiResult<Vec<PaymentInvoiceRow>> {
             Err(ApiError::InternalServerError("not used".to_string()))
         }
@@ -233,8 +650,9 @@ mod tests {
             Err(ApiError::InternalServerError("not used".to_string()))
         }
 
-        async fn mark_invoices_notified(&self, _ids: &[i64]) -> ApiResult<u64> {
-            Err(ApiError::InternalServerError("not used".to_string()))
+        async fn mark_invoices_notified(&self, ids: &[i64]) -> ApiResult<u64> {
+            self.notified.lock().unwrap().extend_from_slice(ids);
+            Ok(ids.len() as u64)
         }
 
         async fn confirm_invoice(&self, _id: i64) -> ApiResult<PaymentInvoiceRow> {
@@ -251,6 +669,35 @@ mod tests {
         ) -> ApiResult<PaymentInvoiceRow> {
             Err(ApiError::InternalServerError("not used".to_string()))
         }
+
+        async fn record_event(&self, event: NewPaymentInvoiceEvent) -> ApiResult<bool> {
+            let mut events = self.events.lock().unwrap();
+            if events
+                .iter()
+                .any(|e| e.dedup_key.is_some() && e.dedup_key == event.dedup_key)
+            {
+                return Ok(false);
+            }
+            events.push(event);
+            Ok(true)
+        }
+
+        async fn record_event_if_changed(&self, _event: NewPaymentInvoiceEvent) -> ApiResult<bool> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+
+        async fn has_event(&self, _invoice_id: i64, dedup_key: &str) -> ApiResult<bool> {
+            Ok(self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|e| e.dedup_key.as_deref() == Some(dedup_key)))
+        }
+
+        async fn get_events(&self, _invoice_id: i64) -> ApiResult<Vec<PaymentInvoiceEventRow>> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
     }
 
     struct FakeNotificationService {
@@ -270,6 +717,7 @@ mod tests {
 
     struct FakeCustomerService {
         customer: CustomerRow,
+        updated: Mutex<Option<UpdateCustomerCommand>>,
     }
 
     #[async_trait]
@@ -297,8 +745,9 @@ mod tests {
             Err(ApiError::InternalServerError("not used".to_string()))
         }
 
-        async fn update(&self, _command: UpdateCustomerCommand) -> ApiResult<CustomerRow> {
-            Err(ApiError::InternalServerError("not used".to_string()))
+        async fn update(&self, command: UpdateCustomerCommand) -> ApiResult<CustomerRow> {
+            *self.updated.lock().unwrap() = Some(command);
+            Ok(self.customer.clone())
         }
 
         async fn update_last_seen(&self, _id: i64, _bot_id: i64) -> ApiResult<CustomerRow> {
@@ -310,23 +759,110 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn test_handle_payment_success_creates_transaction_updates_invoice_and_notifies() {
-        let order_id = Uuid::new_v4();
+    /// Accepts every event once, like the real table with a unique event id
+    struct FakeWebhookEventRepo {
+        seen: Mutex<Vec<String>>,
+        processed: Mutex<Vec<(i64, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl PaymentWebhookEventRepositoryTrait for FakeWebhookEventRepo {
+        async fn register(
+            &self,
+            event: NewPaymentWebhookEvent,
+        ) -> RepositoryResult<Option<PaymentWebhookEventRow>> {
+            let mut seen = self.seen.lock().unwrap();
+            if seen.contains(&event.event_id) {
+                return Ok(None);
+            }
+            seen.push(event.event_id.clone());
+            Ok(Some(PaymentWebhookEventRow {
+                id: seen.len() as i64,
+                gateway: event.gateway,
+                event_id: event.event_id,
+                gateway_invoice_id: event.gateway_invoice_id,
+                payload: event.payload,
+                received_at: Utc::now(),
+                processed_at: None,
+                error: None,
+            }))
+        }
+
+        async fn mark_processed(&self, id: i64, error: Option<String>) -> RepositoryResult<()> {
+            self.processed.lock().unwrap().push((id, error));
+            Ok(())
+        }
+
+        async fn get_for_invoice(
+            &self,
+            _gateway: PaymentSystem,
+            _gateway_invoice_id: &str,
+        ) -> RepositoryResult<Vec<PaymentWebhookEventRow>> {
+            Ok(vec![])
+        }
+    }
+
+    struct FakePurchaseService {
+        completed: Mutex<Vec<i64>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl PurchaseServiceTrait for FakePurchaseService {
+        async fn purchase_product(
+            &self,
+            _command: crate::services::purchase::PurchaseProductCommand,
+        ) -> ApiResult<PurchaseResult> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+        async fn create_checkout_order(
+            &self,
+            _command: crate::services::purchase::CreateCheckoutOrderCommand,
+        ) -> ApiResult<crate::services::purchase::CheckoutOrder> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+        async fn complete_checkout_order(&self, order_id: i64) -> ApiResult<PurchaseResult> {
+            if self.fail {
+                return Err(ApiError::BadRequest("Not enough stock".to_string()));
+            }
+            self.completed.lock().unwrap().push(order_id);
+            Ok(PurchaseResult {
+                product_name: "Proxy".to_string(),
+                balance: 0.0,
+                details: None,
+                fulfilled_text: Some("secret".to_string()),
+                fulfilled_image_id: None,
+                price: 100.0,
+            })
+        }
+        async fn cancel_checkout_order(&self, _order_id: i64) -> ApiResult<()> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+    }
+
+    type TestService = PaymentProcessingService<
+        FakePaymentInvoiceService,
+        FakeNotificationService,
+        FakeCustomerService,
+        FakeWebhookEventRepo,
+        FakePurchaseService,
+    >;
+
+    fn test_invoice(status: InvoiceStatus) -> PaymentInvoiceRow {
         let now = Utc::now();
-        let invoice = PaymentInvoiceRow {
+        PaymentInvoiceRow {
             id: 1,
             customer_id: 10,
             original_amount: dec!(100),
             amount: dec!(100),
-            status: InvoiceStatus::Pending,
+            status,
             created_at: now,
             updated_at: now,
             expires_at: now,
             deleted_at: None,
             gateway: PaymentSystem::PlatformCard,
             gateway_invoice_id: "gw-1".to_string(),
-            order_id,
+            order_id: Uuid::new_v4(),
             payment_details: json!({"ref": "abc"}),
             bot_message_id: None,
             notification_sent_at: None,
@@ -334,9 +870,16 @@ mod tests {
             finished_at: None,
             receipt_requested_at: None,
             receipt_submitted_at: None,
+            receipt_url: None,
             amount_in_usdt: dec!(1),
-        };
-        let customer = CustomerRow {
+            currency: Currency::Rub,
+            checkout_order_id: None,
+        }
+    }
+
+    fn test_customer() -> CustomerRow {
+        let now = Utc::now();
+        CustomerRow {
             id: 10,
             telegram_id: 555,
             balance: dec!(0),
@@ -349,52 +892,58 @@ mod tests {
             created_at: now,
             updated_at: now,
             blocked_until: None,
-        };
+            display_currency: None,
+        }
+    }
 
-        let transaction_row = TransactionRow {
-            id: 99,
-            customer_id: Some(10),
-            order_id: None,
-            r#type: TransactionType::Deposit,
-            amount: dec!(100),
-            store_balance_delta: dec!(79),
-            user_balance_after: Some(dec!(200)),
-            store_balance_after: dec!(1000),
-            platform_commission: dec!(1),
-            gateway_commission: dec!(20),
-            created_at: now,
-            description: None,
-            payment_gateway: Some(PaymentSystem::PlatformCard),
-            details: Some(json!({"ref": "abc"})),
-            bot_id: None,
-        };
+    fn build_service(invoice: PaymentInvoiceRow) -> TestService {
+        build_service_with_purchase(invoice, false)
+    }
 
-        let service = PaymentProcessingService::new(
-            Arc::new(FakeTransactionService {
-                last_created: Mutex::new(None),
-                created_row: Mutex::new(Some(transaction_row)),
-            }),
+    fn build_service_with_purchase(invoice: PaymentInvoiceRow, fail_purchase: bool) -> TestService {
+        PaymentProcessingService::new(
             Arc::new(FakePaymentInvoiceService {
-                invoice: invoice.clone(),
-                updated: Mutex::new(None),
+                invoice,
+                updated: Mutex::new(Vec::new()),
+                deposits: Mutex::new(Vec::new()),
+                notified: Mutex::new(Vec::new()),
+                events: Mutex::new(Vec::new()),
             }),
             Arc::new(FakeNotificationService {
                 last: Mutex::new(None),
             }),
             Arc::new(FakeCustomerService {
-                customer: customer.clone(),
+                customer: test_customer(),
+                updated: Mutex::new(None),
             }),
-        );
+            Arc::new(FakeWebhookEventRepo {
+                seen: Mutex::new(Vec::new()),
+                processed: Mutex::new(Vec::new()),
+            }),
+            Arc::new(FakePurchaseService {
+                completed: Mutex::new(Vec::new()),
+                fail: fail_purchase,
+            }),
+            Currency::Rub,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_handle_payment_success_creates_transaction_updates_invoice_and_notifies() {
+        let invoice = test_invoice(InvoiceStatus::Pending);
+        let order_id = invoice.order_id;
+        let customer = test_customer();
+
+        let service = build_service(invoice.clone());
 
         service.handle_payment_success(order_id).await.unwrap();
 
         let tx = service
-            .transactions_service
-            .as_ref()
-            .last_created
+            .payment_invoice_service
+            .deposits
             .lock()
             .unwrap()
-            .take()
+            .pop()
             .expect("transaction created");
         assert_eq!(tx.amount, invoice.original_amount);
         assert_eq!(tx.r#type, TransactionType::Deposit);
@@ -411,9 +960,10 @@ mod tests {
             .updated
             .lock()
             .unwrap();
-        let updated = updated_guard.as_ref().expect("invoice updated");
+        let updated = updated_guard.last().expect("invoice updated");
         assert_eq!(updated.id, invoice.id);
         assert_eq!(updated.status, Some(InvoiceStatus::Completed));
+        assert_eq!(updated.expected_status, Some(InvoiceStatus::Pending));
 
         let notify = service
             .notification_service
@@ -433,4 +983,360 @@ mod tests {
             _ => panic!("unexpected notification type"),
         }
     }
+
+    #[tokio::test]
+    async fn test_handle_payment_success_completes_checkout_order() {
+        let invoice = PaymentInvoiceRow {
+            checkout_order_id: Some(7),
+            ..test_invoice(InvoiceStatus::Pending)
+        };
+        let service = build_service_with_purchase(invoice.clone(), false);
+
+        service
+            .handle_payment_success(invoice.order_id)
+            .await
+            .unwrap();
+
+        let tx = service
+            .payment_invoice_service
+            .deposits
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("deposit created before the purchase");
+        assert_eq!(tx.r#type, TransactionType::Deposit);
+        assert_eq!(*service.purchase_service.completed.lock().unwrap(), vec![7]);
+        let notify = service
+            .notification_service
+            .last
+            .lock()
+            .unwrap()
+            .take()
+            .expect("notification sent");
+        match notify.message {
+            DispatchMessage::CheckoutOrderFulfilledNotification { order_id, purchase } => {
+                assert_eq!(order_id, 7);
+                assert_eq!(purchase.fulfilled_text.as_deref(), Some("secret"));
+            }
+            _ => panic!("unexpected notification type"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_payment_success_keeps_deposit_when_checkout_fails() {
+        let invoice = PaymentInvoiceRow {
+            checkout_order_id: Some(7),
+            ..test_invoice(InvoiceStatus::Pending)
+        };
+        let service = build_service_with_purchase(invoice.clone(), true);
+
+        service
+            .handle_payment_success(invoice.order_id)
+            .await
+            .unwrap();
+
+        assert!(
+            !service
+                .payment_invoice_service
+                .deposits
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+        let notify = service
+            .notification_service
+            .last
+            .lock()
+            .unwrap()
+            .take()
+            .expect("notification sent");
+        match notify.message {
+            DispatchMessage::GenericMessage { message, .. } => {
+                assert!(message.contains("Баланс пополнен"));
+                assert!(message.contains("#7"));
+            }
+            _ => panic!("unexpected notification type"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_payment_success_credits_invoice_once() {
+        let invoice = test_invoice(InvoiceStatus::Completed);
+        let service = build_service(invoice.clone());
+
+        let err = service
+            .handle_payment_success(invoice.order_id)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ApiError::Conflict(_)));
+        assert!(
+            service
+                .payment_invoice_service
+                .deposits
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_gateway_status_requests_receipt() {
+        let invoice = test_invoice(InvoiceStatus::Pending);
+        let service = build_service(invoice.clone());
+
+        service
+            .handle_gateway_status(
+                &invoice,
+                &test_customer(),
+                GatewayStatusUpdate {
+                    status: InvoiceStatus::AwaitingReceipt,
+                    is_fraud: false,
+                },
+            )
+            .await
+            .unwrap();
+
+        // The receipt request itself is sent by the transition subscriber
+        assert!(service.notification_service.last.lock().unwrap().is_none());
+        let updated = service.payment_invoice_service.updated.lock().unwrap();
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].status, Some(InvoiceStatus::AwaitingReceipt));
+        assert_eq!(updated[0].expected_status, Some(InvoiceStatus::Pending));
+        assert!(updated[0].receipt_requested_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_handle_gateway_status_skips_transition_lost_to_another_writer() {
+        let invoice = test_invoice(InvoiceStatus::Pending);
+        let service = build_service(invoice.clone());
+        let update = GatewayStatusUpdate {
+            status: InvoiceStatus::AwaitingReceipt,
+            is_fraud: false,
+        };
+
+        // Same invoice state seen twice, e.g. by a webhook and the pending payments worker
+        for _ in 0..2 {
+            service
+                .handle_gateway_status(&invoice, &test_customer(), update)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            service
+                .payment_invoice_service
+                .updated
+                .lock()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_gateway_status_does_not_repeat_sent_reminder() {
+        let mut invoice = test_invoice(InvoiceStatus::AwaitingReceipt);
+        invoice.receipt_requested_at = Some(Utc::now() - Duration::minutes(10));
+        invoice.notification_sent_at = invoice.receipt_requested_at;
+        let service = build_service(invoice.clone());
+        let update = GatewayStatusUpdate {
+            status: InvoiceStatus::AwaitingReceipt,
+            is_fraud: false,
+        };
+
+        // Same invoice state seen twice, e.g. by a webhook and the pending payments worker
+        for _ in 0..2 {
+            service
+                .handle_gateway_status(&invoice, &test_customer(), update)
+                .await
+                .unwrap();
+        }
+
+        let events = service.payment_invoice_service.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, PaymentInvoiceEventKind::NotificationSent);
+        // The invoice update is still retried
+        assert_eq!(
+            service
+                .payment_invoice_service
+                .updated
+                .lock()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_gateway_status_reminds_about_pending_payment() {
+        let mut invoice = test_invoice(InvoiceStatus::Pending);
+        invoice.created_at = Utc::now() - Duration::minutes(10);
+        let service = build_service(invoice.clone());
+        let pending = GatewayStatusUpdate {
+            status: InvoiceStatus::Pending,
+            is_fraud: false,
+        };
+
+        service
+            .handle_gateway_status(&invoice, &test_customer(), pending)
+            .await
+            .unwrap();
+        assert_eq!(
+            *service.payment_invoice_service.notified.lock().unwrap(),
+            vec![invoice.id]
+        );
+
+        // Reminded recently, nothing to do
+        invoice.notification_sent_at = Some(Utc::now());
+        service.notification_service.last.lock().unwrap().take();
+        service
+            .handle_gateway_status(&invoice, &test_customer(), pending)
+            .await
+            .unwrap();
+        assert!(service.notification_service.last.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_gateway_status_blocks_fraud_and_applies_final_status() {
+        let invoice = test_invoice(InvoiceStatus::Disputed);
+        let service = build_service(invoice.clone());
+
+        service
+            .handle_gateway_status(
+                &invoice,
+                &test_customer(),
+                GatewayStatusUpdate {
+                    status: InvoiceStatus::Failed,
+                    is_fraud: true,
+                },
+            )
+            .await
+            .unwrap();
+
+        let blocked = service.customer_service.updated.lock().unwrap().take();
+        assert_eq!(blocked.and_then(|c| c.is_blocked), Some(true));
+        let updated = service.payment_invoice_service.updated.lock().unwrap();
+        assert_eq!(
+            updated.iter().map(|u| u.status).collect::<Vec<_>>(),
+            vec![Some(InvoiceStatus::Failed)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_webhook_event_is_idempotent() {
+        let invoice = test_invoice(InvoiceStatus::Pending);
+        let service = build_service(invoice.clone());
+        let command = || HandleWebhookEventCommand {
+            gateway: invoice.gateway,
+            event_id: "ev-1".to_string(),
+            gateway_invoice_id: invoice.gateway_invoice_id.clone(),
+            update: GatewayStatusUpdate {
+                status: InvoiceStatus::Cancelled,
+                is_fraud: false,
+            },
+            payload: json!({}),
+        };
+
+        assert!(service.handle_webhook_event(command()).await.unwrap());
+        assert!(!service.handle_webhook_event(command()).await.unwrap());
+
+        let updated = service.payment_invoice_service.updated.lock().unwrap();
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].status, Some(InvoiceStatus::Cancelled));
+        assert_eq!(
+            *service.webhook_event_repo.processed.lock().unwrap(),
+            vec![(1, None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_webhook_event_ignores_finished_invoices() {
+        let invoice = test_invoice(InvoiceStatus::Completed);
+        let service = build_service(invoice.clone());
+
+        let processed = service
+            .handle_webhook_event(HandleWebhookEventCommand {
+                gateway: invoice.gateway,
+                event_id: "ev-1".to_string(),
+                gateway_invoice_id: invoice.gateway_invoice_id.clone(),
+                update: GatewayStatusUpdate {
+                    status: InvoiceStatus::Completed,
+                    is_fraud: false,
+                },
+                payload: json!({}),
+            })
+            .await
+            .unwrap();
+
+        assert!(processed);
+        assert!(
+            service
+                .payment_invoice_service
+                .deposits
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_webhook_event_credits_payment_after_expiry() {
+        let invoice = test_invoice(InvoiceStatus::Expired);
+        let service = build_service(invoice.clone());
+
+        let processed = service
+            .handle_webhook_event(HandleWebhookEventCommand {
+                gateway: invoice.gateway,
+                event_id: "ev-1".to_string(),
+                gateway_invoice_id: invoice.gateway_invoice_id.clone(),
+                update: GatewayStatusUpdate {
+                    status: InvoiceStatus::Completed,
+                    is_fraud: false,
+                },
+                payload: json!({}),
+            })
+            .await
+            .unwrap();
+
+        assert!(processed);
+        assert_eq!(
+            service
+                .payment_invoice_service
+                .deposits
+                .lock()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_webhook_event_ignores_non_completion_after_expiry() {
+        let invoice = test_invoice(InvoiceStatus::Expired);
+        let service = build_service(invoice.clone());
+
+        service
+            .handle_webhook_event(HandleWebhookEventCommand {
+                gateway: invoice.gateway,
+                event_id: "ev-1".to_string(),
+                gateway_invoice_id: invoice.gateway_invoice_id.clone(),
+                update: GatewayStatusUpdate {
+                    status: InvoiceStatus::Failed,
+                    is_fraud: false,
+                },
+                payload: json!({}),
+            })
+            .await
+            .unwrap();
+
+        assert!(
+            service
+                .payment_invoice_service
+                .deposits
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
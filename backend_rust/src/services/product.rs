@@ -52,6 +52,7 @@ pub struct Product {
     pub fulfillment_image_id: Option<Uuid>,
     pub provider_name: String,
     pub external_id: Option<String>,
+    pub hidden_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: i64,
@@ -89,6 +90,7 @@ pub struct UpdateProductCommand {
     pub fulfillment_image_id: Option<Option<Uuid>>,
     pub external_id: Option<Option<String>>,
     pub stock: Option<i64>,
+    pub hidden: Option<bool>,
     pub updated_by: i64,
 }
 
@@ -184,6 +186,7 @@ impl ProductServiceTrait
                     .map(|row| from_product_row(row.clone(), &settings.clone()))
                     .collect(),
                 total: res.total,
+                next_cursor: res.next_cursor,
             })
     }
 
@@ -300,6 +303,7 @@ impl ProductServiceTrait
                     base_price: command.base_price,
                     subscription_period_days: command.subscription_period_days,
                     r#type: command.r#type,
+                    hidden: command.hidden,
                 },
             )
             .await?;
@@ -497,6 +501,7 @@ fn from_product_row(res: ProductRow, settings: &Settings) -> Product {
         base_price: res.base_price,
         provider_name: res.provider_name,
         external_id: res.external_id,
+        hidden_at: res.hidden_at,
         created_at: res.created_at,
         created_by: res.created_by,
         updated_at: res.updated_at,
@@ -0,0 +1,1049 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use shared_dtos::product_sync::{ProductSyncStatus, ProductSyncTrigger};
+use sqlx::{PgPool, types::Json};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    errors::{
+        api::{ApiError, ApiResult},
+        repository::RepositoryError,
+    },
+    infrastructure::{
+        external::products::{
+            ExternalCatalogItem, ExternalProductProvider, ExternalProductProviderRegistry,
+        },
+        lib::advisory_lock::AdvisoryLock,
+        repositories::product_sync::{
+            ProductSyncRunRepositoryTrait, ProductSyncSnapshotRepositoryTrait,
+        },
+    },
+    middlewares::context::RequestContext,
+    models::{
+        common::PaginatedResult,
+        product_sync::{
+            NewProductSyncRun, ProductSyncCounts, ProductSyncRunListQuery, ProductSyncRunRow,
+            ProductSyncSnapshotRow,
+        },
+    },
+    services::{
+        category::{CategoryServiceTrait, CreateCategorySequenceCommand},
+        product::{CreateProductCommand, Product, ProductServiceTrait, UpdateProductCommand},
+    },
+};
+
+#[derive(Debug)]
+pub struct RunProductSyncCommand {
+    /// Sync a single provider; every enabled provider when `None`
+    pub provider_name: Option<String>,
+    pub trigger: ProductSyncTrigger,
+    pub triggered_by: Option<i64>,
+}
+
+#[async_trait]
+pub trait ProductSyncServiceTrait: Send + Sync {
+    async fn get_runs(
+        &self,
+        query: ProductSyncRunListQuery,
+    ) -> ApiResult<PaginatedResult<ProductSyncRunRow>>;
+    /// Syncs provider catalogs into `products`, recording a run per provider. A provider
+    /// that is being synced by another instance is skipped, or rejected when requested by name.
+    async fn run(&self, command: RunProductSyncCommand) -> ApiResult<Vec<ProductSyncRunRow>>;
+    /// Queues a run per provider for the sync worker, reusing runs that are already queued
+    async fn queue(&self, command: RunProductSyncCommand) -> ApiResult<Vec<ProductSyncRunRow>>;
+    /// Runs the oldest queued sync; `None` when the queue is empty or its provider is busy
+    async fn process_queued(&self) -> ApiResult<Option<ProductSyncRunRow>>;
+}
+
+/// Config-driven mapping of supplier catalogs onto our products
+#[derive(Debug, Default)]
+pub struct ProductSyncRules {
+    category_templates: HashMap<String, String>,
+    price_markups: HashMap<String, Decimal>,
+    default_prices: HashMap<String, Decimal>,
+}
+
+impl ProductSyncRules {
+    pub fn from_config(config: &Config) -> Self {
+        Self::parse(
+            config
+                .external_products_category_map
+                .as_deref()
+                .unwrap_or_default(),
+            config
+                .external_products_price_markup
+                .as_deref()
+                .unwrap_or_default(),
+            config
+                .external_products_default_price
+                .as_deref()
+                .unwrap_or_default(),
+        )
+    }
+
+    fn parse(category_map: &str, price_markup: &str, default_price: &str) -> Self {
+        Self {
+            category_templates: parse_rules(category_map),
+            price_markups: parse_decimal_rules(price_markup, "price markup"),
+            default_prices: parse_decimal_rules(default_price, "default price"),
+        }
+    }
+
+    /// Local category path; the most specific template wins: `provider/category`,
+    /// then `provider`, then the provider's own default
+    fn category_path(
+        &self,
+        provider: &dyn ExternalProductProvider,
+        item: &ExternalCatalogItem,
+    ) -> String {
+        let template = self
+            .category_templates
+            .get(&format!("{}/{}", provider.name(), item.category))
+            .or_else(|| self.category_templates.get(provider.name()))
+            .map(String::as_str)
+            .unwrap_or(provider.category_template());
+        render_category_path(template, item)
+    }
+
+    fn base_price(&self, provider_name: &str, supplier_price: Decimal) -> Decimal {
+        let markup = self
+            .price_markups
+            .get(provider_name)
+            .copied()
+            .unwrap_or_default();
+        (supplier_price * (dec!(1) + markup / dec!(100))).round_dp(2)
+    }
+
+    /// Price of new products whose supplier doesn't publish one
+    fn default_price(&self, provider_name: &str) -> Option<Decimal> {
+        self.default_prices.get(provider_name).copied()
+    }
+}
+
+fn parse_decimal_rules(raw: &str, what: &str) -> HashMap<String, Decimal> {
+    parse_rules(raw)
+        .into_iter()
+        .filter_map(|(provider, value)| match Decimal::from_str(&value) {
+            Ok(value) => Some((provider, value)),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid {what} {value} for {provider}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_rules(raw: &str) -> HashMap<String, String> {
+    raw.split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+        .collect()
+}
+
+/// Substitutes `{category}` and `{attribute}` placeholders; a path segment whose
+/// attribute is missing is dropped, so `Proxy/{country}` falls back to `Proxy`
+fn render_category_path(template: &str, item: &ExternalCatalogItem) -> String {
+    template
+        .split('/')
+        .filter_map(|segment| render_segment(segment, item))
+        .map(|segment| segment.trim().to_string())
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn render_segment(segment: &str, item: &ExternalCatalogItem) -> Option<String> {
+    let mut rendered = String::new();
+    let mut rest = segment;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let key = &rest[start + 1..start + len];
+        let value = match key {
+            "category" => Some(&item.category),
+            key => item.attributes.get(key),
+        }?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(value);
+        rest = &rest[start + len + 1..];
+    }
+    rendered.push_str(rest);
+    Some(rendered)
+}
+
+fn blank_update(id: i64) -> UpdateProductCommand {
+    UpdateProductCommand {
+        id,
+        name: None,
+        base_price: None,
+        category_id: None,
+        image_id: None,
+        r#type: None,
+        subscription_period_days: None,
+        details: None,
+        fulfillment_text: None,
+        fulfillment_image_id: None,
+        external_id: None,
+        stock: None,
+        hidden: None,
+        updated_by: 1, // System
+    }
+}
+
+/// Fields of `product` that differ from the catalog; `None` when it's up to date.
+/// Fields an admin changed since the last sync no longer match `snapshot` and are kept.
+fn product_update(
+    product: &Product,
+    item: &ExternalCatalogItem,
+    base_price: Option<Decimal>,
+    category_id: i64,
+    snapshot: Option<&ProductSyncSnapshotRow>,
+) -> Option<UpdateProductCommand> {
+    let mut update = blank_update(product.id);
+    if product.name != item.name && snapshot.is_none_or(|s| s.name == product.name) {
+        update.name = Some(item.name.clone());
+    }
+    if let Some(base_price) = base_price
+        && product.base_price != base_price
+        && snapshot.is_none_or(|s| s.base_price == Some(product.base_price))
+    {
+        update.base_price = Some(base_price);
+    }
+    if product.category_id != Some(category_id)
+        && snapshot.is_none_or(|s| product.category_id == Some(s.category_id))
+    {
+        update.category_id = Some(category_id);
+    }
+    if product.details != item.details
+        && snapshot.is_none_or(|s| s.details.as_ref().map(|d| &d.0) == product.details.as_ref())
+    {
+        update.details = Some(item.details.clone());
+    }
+    if let Some(days) = item.subscription_period_days
+        && product.subscription_period_days != days
+    {
+        update.subscription_period_days = Some(days);
+    }
+    if product.hidden_at.is_some() {
+        update.hidden = Some(false);
+    }
+
+    let changed = update.name.is_some()
+        || update.base_price.is_some()
+        || update.category_id.is_some()
+        || update.details.is_some()
+        || update.subscription_period_days.is_some()
+        || update.hidden.is_some();
+    changed.then_some(update)
+}
+
+/// Catalog values written by this sync, keeping the last published price while the
+/// supplier omits it
+fn sync_snapshot(
+    product_id: i64,
+    item: &ExternalCatalogItem,
+    base_price: Option<Decimal>,
+    category_id: i64,
+    prev: Option<&ProductSyncSnapshotRow>,
+) -> ProductSyncSnapshotRow {
+    ProductSyncSnapshotRow {
+        product_id,
+        name: item.name.clone(),
+        base_price: base_price.or_else(|| prev.and_then(|s| s.base_price)),
+        category_id,
+        details: item.details.clone().map(Json),
+    }
+}
+
+fn system_context() -> RequestContext {
+    RequestContext {
+        ip_address: None,
+        user_agent: None,
+        request_id: Uuid::new_v4(),
+    }
+}
+
+pub struct ProductSyncService<R, S, P, C> {
+    repo: Arc<R>,
+    snapshot_repo: Arc<S>,
+    product_service: Arc<P>,
+    category_service: Arc<C>,
+    providers: Arc<ExternalProductProviderRegistry>,
+    pool: Arc<PgPool>,
+    rules: ProductSyncRules,
+}
+
+impl<R, S, P, C> ProductSyncService<R, S, P, C>
+where
+    R: ProductSyncRunRepositoryTrait + Send + Sync,
+    S: ProductSyncSnapshotRepositoryTrait + Send + Sync,
+    P: ProductServiceTrait,
+    C: CategoryServiceTrait,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        repo: Arc<R>,
+        snapshot_repo: Arc<S>,
+        product_service: Arc<P>,
+        category_service: Arc<C>,
+        providers: Arc<ExternalProductProviderRegistry>,
+        pool: Arc<PgPool>,
+        rules: ProductSyncRules,
+    ) -> Self {
+        Self {
+            repo,
+            snapshot_repo,
+            product_service,
+            category_service,
+            providers,
+            pool,
+            rules,
+        }
+    }
+
+    fn command_providers(
+        &self,
+        command: &RunProductSyncCommand,
+    ) -> ApiResult<Vec<Arc<dyn ExternalProductProvider>>> {
+        Ok(match &command.provider_name {
+            Some(name) => vec![self.providers.get(name).ok_or_else(|| {
+                ApiError::NotFound(format!("Product provider {name} is not enabled"))
+            })?],
+            None => self.providers.providers().cloned().collect(),
+        })
+    }
+
+    async fn try_lock(&self, provider_name: &str) -> ApiResult<Option<AdvisoryLock>> {
+        Ok(
+            AdvisoryLock::try_acquire(&self.pool, &format!("product_sync:{provider_name}"))
+                .await
+                .map_err(RepositoryError::from)?,
+        )
+    }
+
+    async fn release_lock(lock: AdvisoryLock, provider_name: &str) {
+        if let Err(e) = lock.release().await {
+            tracing::error!("Failed to release product_sync:{provider_name} lock: {e}");
+        }
+    }
+
+    async fn sync_run(
+        &self,
+        provider: &dyn ExternalProductProvider,
+        run: ProductSyncRunRow,
+    ) -> ApiResult<ProductSyncRunRow> {
+        let (status, counts) = match self.apply_catalog(provider).await {
+            Ok(counts) if counts.errors.is_empty() => (ProductSyncStatus::Succeeded, counts),
+            Ok(counts) => (ProductSyncStatus::Partial, counts),
+            Err(e) => (
+                ProductSyncStatus::Failed,
+                ProductSyncCounts {
+                    errors: vec![e],
+                    ..Default::default()
+                },
+            ),
+        };
+
+        Ok(self.repo.finish(run.id, status, counts).await?)
+    }
+
+    /// Adds new catalog items, updates changed ones, hides products missing from the
+    /// catalog and restores hidden ones that came back. Per-product failures are collected.
+    async fn apply_catalog(
+        &self,
+        provider: &dyn ExternalProductProvider,
+    ) -> Result<ProductSyncCounts, String> {
+        let provider_name = provider.name();
+        let catalog = provider.list_catalog().await?;
+        let existing = self
+            .product_service
+            .get_all_external_provider(provider_name)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let snapshots = self
+            .snapshot_repo
+            .get_for_provider(provider_name)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|snapshot| (snapshot.product_id, snapshot))
+            .collect::<HashMap<_, _>>();
+
+        let existing_by_id = existing
+            .iter()
+            .filter_map(|p| p.external_id.as_deref().map(|id| (id, p)))
+            .collect::<HashMap<_, _>>();
+        let catalog_ids = catalog
+            .iter()
+            .map(|item| item.external_id.as_str())
+            .collect::<HashSet<_>>();
+
+        let mut counts = ProductSyncCounts::default();
+        let mut category_ids = HashMap::new();
+        for item in &catalog {
+            let result = match existing_by_id.get(item.external_id.as_str()) {
+                Some(product) => {
+                    self.update_product(
+                        provider,
+                        product,
+                        snapshots.get(&product.id),
+                        item,
+                        &mut category_ids,
+                        &mut counts,
+                    )
+                    .await
+                }
+                None => {
+                    self.add_product(provider, item, &mut category_ids, &mut counts)
+                        .await
+                }
+            };
+            if let Err(e) = result {
+                counts.errors.push(format!("{}: {e}", item.external_id));
+            }
+        }
+
+        for product in &existing {
+            let Some(external_id) = product.external_id.as_deref() else {
+                continue;
+            };
+            if catalog_ids.contains(external_id) || product.hidden_at.is_some() {
+                continue;
+            }
+            let mut update = blank_update(product.id);
+            update.hidden = Some(true);
+            match self.product_service.update(update, system_context()).await {
+                Ok(_) => counts.hidden += 1,
+                Err(e) => counts.errors.push(format!("{external_id}: {e}")),
+            }
+        }
+
+        Ok(counts)
+    }
+
+    async fn add_product(
+        &self,
+        provider: &dyn ExternalProductProvider,
+        item: &ExternalCatalogItem,
+        category_ids: &mut HashMap<String, i64>,
+        counts: &mut ProductSyncCounts,
+    ) -> ApiResult<()> {
+        let base_price = item
+            .base_price
+            .map(|price| self.rules.base_price(provider.name(), price));
+        let price = base_price
+            .or_else(|| self.rules.default_price(provider.name()))
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "No supplier price and no default price for {}",
+                    provider.name()
+                ))
+            })?;
+        let category_id = self.category_id(provider, item, category_ids).await?;
+        let product = self
+            .product_service
+            .create(CreateProductCommand {
+                base_price: price,
+                category_id,
+                fulfillment_text: None,
+                external_id: Some(item.external_id.clone()),
+                name: item.name.clone(),
+                r#type: item.r#type,
+                initial_stock: None,
+                provider_name: provider.name().to_string(),
+                subscription_period_days: item.subscription_period_days,
+                fulfillment_image_id: None,
+                details: item.details.clone(),
+                image_id: None,
+                created_by: 1, // System
+                ctx: None,
+            })
+            .await?;
+        self.snapshot_repo
+            .upsert(sync_snapshot(
+                product.id,
+                item,
+                base_price,
+                category_id,
+                None,
+            ))
+            .await?;
+        counts.added += 1;
+        Ok(())
+    }
+
+    async fn update_product(
+        &self,
+        provider: &dyn ExternalProductProvider,
+        product: &Product,
+        snapshot: Option<&ProductSyncSnapshotRow>,
+        item: &ExternalCatalogItem,
+        category_ids: &mut HashMap<String, i64>,
+        counts: &mut ProductSyncCounts,
+    ) -> ApiResult<()> {
+        let category_id = self.category_id(provider, item, category_ids).await?;
+        let base_price = item
+            .base_price
+            .map(|price| self.rules.base_price(provider.name(), price));
+        let new_snapshot = sync_snapshot(product.id, item, base_price, category_id, snapshot);
+        let update = product_update(product, item, base_price, category_id, snapshot);
+        if snapshot != Some(&new_snapshot) {
+            self.snapshot_repo.upsert(new_snapshot).await?;
+        }
+        let Some(update) = update else {
+            return Ok(());
+        };
+
+        let restored = update.hidden.is_some();
+        let changed = update.name.is_some()
+            || update.base_price.is_some()
+            || update.category_id.is_some()
+            || update.details.is_some()
+            || update.subscription_period_days.is_some();
+        self.product_service
+            .update(update, system_context())
+            .await?;
+        if restored {
+            counts.restored += 1;
+        }
+        if changed {
+            counts.updated += 1;
+        }
+        Ok(())
+    }
+
+    async fn category_id(
+        &self,
+        provider: &dyn ExternalProductProvider,
+        item: &ExternalCatalogItem,
+        category_ids: &mut HashMap<String, i64>,
+    ) -> ApiResult<i64> {
+        let path = self.rules.category_path(provider, item);
+        if let Some(id) = category_ids.get(&path) {
+            return Ok(*id);
+        }
+
+        let category = self
+            .category_service
+            .create_category_sequence(CreateCategorySequenceCommand {
+                name: path.clone(),
+                created_by: 1, // System
+                ctx: None,
+            })
+            .await?
+            .ok_or_else(|| ApiError::BadRequest("Category path is empty".to_string()))?;
+        category_ids.insert(path, category.id);
+        Ok(category.id)
+    }
+}
+
+#[async_trait]
+impl<R, S, P, C> ProductSyncServiceTrait for ProductSyncService<R, S, P, C>
+where
+    R: ProductSyncRunRepositoryTrait + Send + Sync,
+    S: ProductSyncSnapshotRepositoryTrait + Send + Sync,
+    P: ProductServiceTrait,
+    C: CategoryServiceTrait,
+{
+    async fn get_runs(
+        &self,
+        query: ProductSyncRunListQuery,
+    ) -> ApiResult<PaginatedResult<ProductSyncRunRow>> {
+        self.repo.get_list(query).await.map_err(ApiError::from)
+    }
+
+    async fn run(&self, command: RunProductSyncCommand) -> ApiResult<Vec<ProductSyncRunRow>> {
+        let providers = self.command_providers(&command)?;
+
+        let mut runs = Vec::with_capacity(providers.len());
+        for provider in providers {
+            let Some(lock) = self.try_lock(provider.name()).await? else {
+                if command.provider_name.is_some() {
+                    return Err(ApiError::Conflict(format!(
+                        "{} sync is already running",
+                        provider.name()
+                    )));
+                }
+                tracing::info!("Skipping {} sync, it is already running", provider.name());
+                continue;
+            };
+
+            let run = match self
+                .repo
+                .create(NewProductSyncRun {
+                    provider_name: provider.name().to_string(),
+                    trigger: command.trigger,
+                    triggered_by: command.triggered_by,
+                })
+                .await
+            {
+                Ok(run) => self.sync_run(provider.as_ref(), run).await,
+                Err(e) => Err(e.into()),
+            };
+            Self::release_lock(lock, provider.name()).await;
+            runs.push(run?);
+        }
+
+        Ok(runs)
+    }
+
+    async fn queue(&self, command: RunProductSyncCommand) -> ApiResult<Vec<ProductSyncRunRow>> {
+        let providers = self.command_providers(&command)?;
+
+        let mut runs = Vec::with_capacity(providers.len());
+        for provider in providers {
+            let run = self
+                .repo
+                .queue(NewProductSyncRun {
+                    provider_name: provider.name().to_string(),
+                    trigger: command.trigger,
+                    triggered_by: command.triggered_by,
+                })
+                .await?;
+            runs.push(run);
+        }
+        Ok(runs)
+    }
+
+    async fn process_queued(&self) -> ApiResult<Option<ProductSyncRunRow>> {
+        let Some(run) = self.repo.get_next_queued().await? else {
+            return Ok(None);
+        };
+        let Some(provider) = self.providers.get(&run.provider_name) else {
+            // The provider was disabled after the run was queued
+            let counts = ProductSyncCounts {
+                errors: vec![format!(
+                    "Product provider {} is not enabled",
+                    run.provider_name
+                )],
+                ..Default::default()
+            };
+            let run = self
+                .repo
+                .finish(run.id, ProductSyncStatus::Failed, counts)
+                .await?;
+            return Ok(Some(run));
+        };
+        let Some(lock) = self.try_lock(provider.name()).await? else {
+            return Ok(None);
+        };
+
+        let run = match self.repo.start_queued(run.id).await {
+            Ok(Some(run)) => self.sync_run(provider.as_ref(), run).await.map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(e.into()),
+        };
+        Self::release_lock(lock, provider.name()).await;
+        run
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Mutex};
+
+    use chrono::{DateTime, Duration, Utc};
+    use shared_dtos::{
+        product::{ProductDetails, ProductType},
+        user_subscription::UserSubscriptionDetails,
+    };
+
+    use super::*;
+    use crate::{
+        infrastructure::{
+            external::products::{ExternalSubscription, ExternalSubscriptionStatus},
+            repositories::{
+                audit_log::AuditLogRepository,
+                category::CategoryRepository,
+                product_sync::{ProductSyncRunRepository, ProductSyncSnapshotRepository},
+                products::ProductRepository,
+                settings::SettingsRepository,
+                stock_movement::StockMovementRepository,
+            },
+        },
+        services::{
+            audit_log::AuditLogService, category::CategoryService, product::ProductService,
+        },
+    };
+
+    struct FakeProvider {
+        catalog: Mutex<Vec<ExternalCatalogItem>>,
+    }
+
+    #[async_trait]
+    impl ExternalProductProvider for FakeProvider {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn category_template(&self) -> &'static str {
+            "{category}/{country}"
+        }
+
+        async fn list_catalog(&self) -> Result<Vec<ExternalCatalogItem>, String> {
+            Ok(self.catalog.lock().unwrap().clone())
+        }
+
+        async fn fulfill_purchase(
+            &self,
+            _external_id: &str,
+            _details: Option<&ProductDetails>,
+            _period: Duration,
+        ) -> Result<ExternalSubscription, String> {
+            Err("not used".to_string())
+        }
+
+        async fn renew(
+            &self,
+            _subscription: &UserSubscriptionDetails,
+            _period: Duration,
+        ) -> Result<DateTime<Utc>, String> {
+            Err("not used".to_string())
+        }
+
+        async fn revoke(&self, _subscription: &UserSubscriptionDetails) -> Result<(), String> {
+            Err("not used".to_string())
+        }
+
+        async fn check_status(
+            &self,
+            _subscription: &UserSubscriptionDetails,
+        ) -> Result<ExternalSubscriptionStatus, String> {
+            Err("not used".to_string())
+        }
+    }
+
+    fn catalog_item(
+        external_id: &str,
+        price: Decimal,
+        country: Option<&str>,
+    ) -> ExternalCatalogItem {
+        ExternalCatalogItem {
+            external_id: external_id.to_string(),
+            name: format!("Proxy {external_id}"),
+            category: "Proxy".to_string(),
+            attributes: country
+                .map(|c| BTreeMap::from([("country".to_string(), c.to_string())]))
+                .unwrap_or_default(),
+            r#type: ProductType::Subscription,
+            base_price: Some(price),
+            subscription_period_days: Some(30),
+            details: None,
+        }
+    }
+
+    type ProductSyncServiceShort = ProductSyncService<
+        ProductSyncRunRepository,
+        ProductSyncSnapshotRepository,
+        ProductService<
+            ProductRepository,
+            StockMovementRepository,
+            AuditLogService<AuditLogRepository>,
+            SettingsRepository,
+            CategoryService<CategoryRepository, AuditLogService<AuditLogRepository>>,
+        >,
+        CategoryService<CategoryRepository, AuditLogService<AuditLogRepository>>,
+    >;
+
+    fn build_service(pool: &PgPool, provider: Arc<FakeProvider>) -> ProductSyncServiceShort {
+        build_service_with_rules(pool, provider, ProductSyncRules::parse("", "fake=10", ""))
+    }
+
+    fn build_service_with_rules(
+        pool: &PgPool,
+        provider: Arc<FakeProvider>,
+        rules: ProductSyncRules,
+    ) -> ProductSyncServiceShort {
+        let pool = Arc::new(pool.clone());
+        let audit_log_service = Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+            pool.clone(),
+        ))));
+        let category_service = Arc::new(CategoryService::new(
+            Arc::new(CategoryRepository::new(pool.clone())),
+            audit_log_service.clone(),
+        ));
+        let product_service = Arc::new(ProductService::new(
+            Arc::new(ProductRepository::new(pool.clone())),
+            Arc::new(StockMovementRepository::new(pool.clone())),
+            Arc::new(SettingsRepository::new(pool.clone())),
+            audit_log_service,
+            category_service.clone(),
+        ));
+        let mut providers = ExternalProductProviderRegistry::new();
+        providers.register(provider);
+
+        ProductSyncService::new(
+            Arc::new(ProductSyncRunRepository::new(pool.clone())),
+            Arc::new(ProductSyncSnapshotRepository::new(pool.clone())),
+            product_service,
+            category_service,
+            Arc::new(providers),
+            pool,
+            rules,
+        )
+    }
+
+    fn manual_sync() -> RunProductSyncCommand {
+        RunProductSyncCommand {
+            provider_name: Some("fake".to_string()),
+            trigger: ProductSyncTrigger::Manual,
+            triggered_by: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_category_path_uses_most_specific_template() {
+        let provider = FakeProvider {
+            catalog: Mutex::new(vec![]),
+        };
+        let rules = ProductSyncRules::parse(
+            "fake=Shop/{category}, fake/Vpn=VPN/{country},broken",
+            "",
+            "",
+        );
+
+        assert_eq!(
+            rules.category_path(&provider, &catalog_item("1", dec!(1), Some("DE"))),
+            "Shop/Proxy"
+        );
+        let mut vpn = catalog_item("2", dec!(1), Some("NL"));
+        vpn.category = "Vpn".to_string();
+        assert_eq!(rules.category_path(&provider, &vpn), "VPN/NL");
+
+        let defaults = ProductSyncRules::default();
+        assert_eq!(
+            defaults.category_path(&provider, &catalog_item("3", dec!(1), Some("US"))),
+            "Proxy/US"
+        );
+        // Segments with missing attributes are dropped
+        assert_eq!(
+            defaults.category_path(&provider, &catalog_item("4", dec!(1), None)),
+            "Proxy"
+        );
+    }
+
+    #[test]
+    fn test_base_price_applies_provider_markup() {
+        let rules = ProductSyncRules::parse("", "fake=12.5, other=oops", "fake=99");
+
+        assert_eq!(rules.base_price("fake", dec!(10.01)), dec!(11.26));
+        assert_eq!(rules.base_price("other", dec!(10)), dec!(10));
+        assert_eq!(rules.default_price("fake"), Some(dec!(99)));
+        assert_eq!(rules.default_price("other"), None);
+    }
+
+    #[sqlx::test]
+    async fn test_sync_adds_updates_hides_and_restores_products(pool: PgPool) {
+        let provider = Arc::new(FakeProvider {
+            catalog: Mutex::new(vec![
+                catalog_item("a", dec!(100), Some("DE")),
+                catalog_item("b", dec!(50), Some("US")),
+            ]),
+        });
+        let service = build_service(&pool, provider.clone());
+
+        let runs = service.run(manual_sync()).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].status, ProductSyncStatus::Succeeded);
+        assert_eq!(runs[0].added, 2);
+
+        let product_a = sqlx::query!(
+            r#"SELECT p.base_price, c.name AS category, p.hidden_at
+            FROM products p JOIN categories c ON c.id = p.category_id
+            WHERE p.external_id = 'a'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(product_a.base_price, dec!(110.00));
+        assert_eq!(product_a.category, "DE");
+
+        // `b` disappears and `a` gets cheaper
+        *provider.catalog.lock().unwrap() = vec![catalog_item("a", dec!(90), Some("DE"))];
+        let runs = service.run(manual_sync()).await.unwrap();
+        assert_eq!((runs[0].added, runs[0].updated, runs[0].hidden), (0, 1, 1));
+
+        let hidden_b = sqlx::query_scalar!(
+            "SELECT hidden_at FROM products WHERE external_id = 'b' AND deleted_at IS NULL"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(hidden_b.is_some());
+
+        // `b` comes back unchanged
+        *provider.catalog.lock().unwrap() = vec![
+            catalog_item("a", dec!(90), Some("DE")),
+            catalog_item("b", dec!(50), Some("US")),
+        ];
+        let runs = service.run(manual_sync()).await.unwrap();
+        assert_eq!(
+            (
+                runs[0].added,
+                runs[0].updated,
+                runs[0].hidden,
+                runs[0].restored
+            ),
+            (0, 0, 0, 1)
+        );
+
+        let list = service
+            .get_runs(ProductSyncRunListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(list.total, 3);
+    }
+
+    #[sqlx::test]
+    async fn test_sync_rejects_unknown_provider_and_busy_lock(pool: PgPool) {
+        let provider = Arc::new(FakeProvider {
+            catalog: Mutex::new(vec![]),
+        });
+        let service = build_service(&pool, provider);
+
+        let err = service
+            .run(RunProductSyncCommand {
+                provider_name: Some("missing".to_string()),
+                ..manual_sync()
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::NotFound(_)));
+
+        let lock = AdvisoryLock::try_acquire(&pool, "product_sync:fake")
+            .await
+            .unwrap()
+            .unwrap();
+        let err = service.run(manual_sync()).await.unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(_)));
+
+        // Scheduled syncs skip busy providers instead of failing
+        let runs = service
+            .run(RunProductSyncCommand {
+                provider_name: None,
+                trigger: ProductSyncTrigger::Schedule,
+                triggered_by: None,
+            })
+            .await
+            .unwrap();
+        assert!(runs.is_empty());
+        lock.release().await.unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_sync_keeps_fields_changed_by_admin(pool: PgPool) {
+        let provider = Arc::new(FakeProvider {
+            catalog: Mutex::new(vec![catalog_item("a", dec!(100), Some("DE"))]),
+        });
+        let service = build_service(&pool, provider.clone());
+        service.run(manual_sync()).await.unwrap();
+
+        // The admin renames the product and sets their own price
+        sqlx::query!(
+            "UPDATE products SET name = 'Fast proxy', base_price = 150 WHERE external_id = 'a'"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let mut item = catalog_item("a", dec!(80), Some("NL"));
+        item.name = "Proxy a v2".to_string();
+        *provider.catalog.lock().unwrap() = vec![item];
+        let runs = service.run(manual_sync()).await.unwrap();
+        assert_eq!(runs[0].updated, 1);
+
+        let product = sqlx::query!(
+            r#"SELECT p.name, p.base_price, c.name AS category
+            FROM products p JOIN categories c ON c.id = p.category_id
+            WHERE p.external_id = 'a'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(product.name, "Fast proxy");
+        assert_eq!(product.base_price, dec!(150));
+        // Fields the admin didn't touch still follow the catalog
+        assert_eq!(product.category, "NL");
+    }
+
+    #[sqlx::test]
+    async fn test_sync_without_supplier_price(pool: PgPool) {
+        let mut item = catalog_item("a", dec!(1), Some("DE"));
+        item.base_price = None;
+        let provider = Arc::new(FakeProvider {
+            catalog: Mutex::new(vec![item]),
+        });
+
+        // Nothing to price new products with
+        let runs = build_service(&pool, provider.clone())
+            .run(manual_sync())
+            .await
+            .unwrap();
+        assert_eq!(runs[0].status, ProductSyncStatus::Partial);
+        assert_eq!(runs[0].added, 0);
+
+        let service = build_service_with_rules(
+            &pool,
+            provider.clone(),
+            ProductSyncRules::parse("", "fake=10", "fake=99"),
+        );
+        let runs = service.run(manual_sync()).await.unwrap();
+        assert_eq!(runs[0].added, 1);
+        let price = || {
+            sqlx::query_scalar!("SELECT base_price FROM products WHERE external_id = 'a'")
+                .fetch_one(&pool)
+        };
+        assert_eq!(price().await.unwrap(), dec!(99));
+
+        // Admin prices are never replaced while the supplier publishes none
+        sqlx::query!("UPDATE products SET base_price = 120 WHERE external_id = 'a'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let runs = service.run(manual_sync()).await.unwrap();
+        assert_eq!((runs[0].added, runs[0].updated), (0, 0));
+        assert_eq!(price().await.unwrap(), dec!(120));
+    }
+
+    #[sqlx::test]
+    async fn test_queued_sync_is_run_by_worker(pool: PgPool) {
+        let provider = Arc::new(FakeProvider {
+            catalog: Mutex::new(vec![catalog_item("a", dec!(100), Some("DE"))]),
+        });
+        let service = build_service(&pool, provider);
+
+        let queued = service.queue(manual_sync()).await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].status, ProductSyncStatus::Queued);
+        // Requests made before the worker gets to it share the run
+        assert_eq!(
+            service.queue(manual_sync()).await.unwrap()[0].id,
+            queued[0].id
+        );
+
+        // Waits while another instance syncs the provider
+        let lock = AdvisoryLock::try_acquire(&pool, "product_sync:fake")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(service.process_queued().await.unwrap().is_none());
+        lock.release().await.unwrap();
+
+        let run = service.process_queued().await.unwrap().unwrap();
+        assert_eq!(run.id, queued[0].id);
+        assert_eq!(run.status, ProductSyncStatus::Succeeded);
+        assert_eq!(run.added, 1);
+        assert!(service.process_queued().await.unwrap().is_none());
+    }
+}
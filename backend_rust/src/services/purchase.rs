@@ -1,22 +1,22 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{Duration, Utc};
 use rust_decimal::{Decimal, prelude::ToPrimitive};
 use rust_decimal_macros::dec;
 use serde::Deserialize;
 use shared_dtos::{
+    currency::Currency,
     order::{OrderStatus, PurchaseDetails},
-    product::{ProductDetails, ProductType},
+    product::ProductType,
     transaction::TransactionType,
-    user_subscription::UserSubscriptionDetails,
 };
 use uuid::Uuid;
 
 use crate::{
     errors::api::{ApiError, ApiResult},
     infrastructure::{
-        external::products::contms::{ContmsProductsProvider, ContmsProductsProviderTrait},
+        external::products::{ExternalProductProvider, ExternalProductProviderRegistry},
         repositories::{
             audit_log::AuditLogRepository, bot::BotRepository, category::CategoryRepository,
             customer::CustomerRepository, order::OrderRepository, order_item::OrderItemRepository,
@@ -26,7 +26,11 @@ use crate::{
         },
     },
     models::{
-        order::NewOrder, order_item::NewOrderItem, transaction::NewTransaction,
+        bot::BotRow,
+        customer::CustomerRow,
+        order::{NewOrder, OrderRow},
+        order_item::NewOrderItem,
+        transaction::{NewTransaction, TransactionRow},
         user_subscription::NewUserSubscription,
     },
     services::{
@@ -36,7 +40,7 @@ use crate::{
         customer::{CustomerService, CustomerServiceTrait},
         order::{OrderService, OrderServiceTrait},
         order_item::{OrderItemService, OrderItemServiceTrait},
-        product::{ProductService, ProductServiceTrait},
+        product::{Product, ProductService, ProductServiceTrait},
         transaction::{TransactionService, TransactionServiceTrait},
         user_subscription::{UserSubscriptionService, UserSubscriptionServiceTrait},
     },
@@ -60,6 +64,23 @@ pub struct PurchaseProductCommand {
     pub bot_id: i64,
 }
 
+#[derive(Debug)]
+pub struct CreateCheckoutOrderCommand {
+    pub product_id: i64,
+    pub telegram_id: i64,
+    pub bot_id: i64,
+    /// Charge the whole price instead of the part the balance doesn't cover
+    pub pay_full_price: bool,
+}
+
+/// Order waiting for its invoice to be paid
+#[derive(Debug, Clone)]
+pub struct CheckoutOrder {
+    pub order: OrderRow,
+    /// What the invoice has to bring to the balance, in the base currency
+    pub amount_to_pay: Decimal,
+}
+
 #[derive(Debug, Clone)]
 pub struct PurchaseResult {
     pub product_name: String,
@@ -73,27 +94,39 @@ pub struct PurchaseResult {
 #[async_trait]
 pub trait PurchaseServiceTrait: Send + Sync {
     async fn purchase_product(&self, command: PurchaseProductCommand) -> ApiResult<PurchaseResult>;
+    /// Creates a `created` order for one product, stock is taken until the order is
+    /// completed or cancelled
+    async fn create_checkout_order(
+        &self,
+        command: CreateCheckoutOrderCommand,
+    ) -> ApiResult<CheckoutOrder>;
+    /// Buys a checkout order from the balance once its invoice has been credited. An order
+    /// that can't be bought any more is cancelled and the money stays on the balance.
+    async fn complete_checkout_order(&self, order_id: i64) -> ApiResult<PurchaseResult>;
+    /// Cancels a checkout order that is still waiting for payment, no-op otherwise
+    async fn cancel_checkout_order(&self, order_id: i64) -> ApiResult<()>;
 }
 
-pub struct PurchaseService<T, C, OI, O, P, CMS, US, B> {
+pub struct PurchaseService<T, C, OI, O, P, US, B> {
     pub transactions_service: Arc<T>,
     pub customer_service: Arc<C>,
     pub order_service: Arc<O>,
     pub order_item_service: Arc<OI>,
     pub product_service: Arc<P>,
-    pub contms_provider: Arc<CMS>,
+    pub external_providers: Arc<ExternalProductProviderRegistry>,
     pub user_subscription_service: Arc<US>,
     pub bot_service: Arc<B>,
+    /// Prices and balances are in it, so are orders
+    pub base_currency: Currency,
 }
 
-impl<T, C, OI, O, P, CMS, US, B> PurchaseService<T, C, OI, O, P, CMS, US, B>
+impl<T, C, OI, O, P, US, B> PurchaseService<T, C, OI, O, P, US, B>
 where
     T: TransactionServiceTrait + Send + Sync,
     C: CustomerServiceTrait + Send + Sync,
     OI: OrderItemServiceTrait + Send + Sync,
     O: OrderServiceTrait + Send + Sync,
     P: ProductServiceTrait + Send + Sync,
-    CMS: ContmsProductsProviderTrait + Send + Sync,
     US: UserSubscriptionServiceTrait + Send + Sync,
     B: BotServiceTrait + Send + Sync,
 {
@@ -104,9 +137,10 @@ where
         product_service: Arc<P>,
         order_service: Arc<O>,
         order_item_service: Arc<OI>,
-        contms_provider: Arc<CMS>,
+        external_providers: Arc<ExternalProductProviderRegistry>,
         user_subscription_service: Arc<US>,
         bot_service: Arc<B>,
+        base_currency: Currency,
     ) -> Self {
         Self {
             transactions_service,
@@ -114,67 +148,49 @@ where
             product_service,
             order_item_service,
             order_service,
-            contms_provider,
+            external_providers,
             user_subscription_service,
             bot_service,
+            base_currency,
         }
     }
-}
 
-#[async_trait]
-impl PurchaseServiceTrait
-    for PurchaseService<
-        TransactionService<TransactionRepository>,
-        CustomerService<CustomerRepository, AuditLogServiceShort>,
-        OrderItemService<OrderItemRepository, StockMovementRepository>,
-        OrderService<OrderRepository, OrderItemRepository>,
-        ProductServiceShort,
-        ContmsProductsProvider,
-        UserSubscriptionService<UserSubscriptionRepository>,
-        BotService<
-            BotRepository,
-            SettingsRepository,
-            AuditLogService<AuditLogRepository>,
-            TransactionRepository,
-        >,
-    >
-{
-    async fn purchase_product(&self, command: PurchaseProductCommand) -> ApiResult<PurchaseResult> {
-        let bot = self.bot_service.get_by_id(command.bot_id).await?;
-        // TODO Refactor this function
-        let product = self.product_service.get_by_id(command.product_id).await?;
+    /// Checks the product can be sold `amount` times and returns its external provider
+    fn check_available(
+        &self,
+        product: &Product,
+        amount: i64,
+    ) -> ApiResult<Option<Arc<dyn ExternalProductProvider>>> {
+        if product.hidden_at.is_some() {
+            return Err(ApiError::BadRequest("Product is not available".to_string()));
+        }
         // We should check if there is enough stock only for internal products
-        if product.stock < command.amount as i32
+        if product.stock < amount as i32
             && product.r#type != ProductType::Subscription
             && product.external_id.is_none()
         {
             return Err(ApiError::BadRequest("Not enough stock".to_string()));
         }
-        let customer = self
-            .customer_service
-            .get_by_telegram_id(command.telegram_id)
-            .await?;
-        let is_subscription = product.r#type == ProductType::Subscription;
-        let amount = if is_subscription { 1 } else { command.amount };
-        let total_price = product.price * Decimal::from(amount);
-        if customer.balance < total_price {
-            return Err(ApiError::BadRequest("Not enough balance".to_string()));
+        // External products are fulfilled by their provider, which must be enabled in this build
+        let external_provider = self.external_providers.get(&product.provider_name);
+        if product.external_id.is_some() && external_provider.is_none() {
+            return Err(ApiError::BadRequest(format!(
+                "Product provider {} is not available",
+                product.provider_name
+            )));
         }
-        let order = self
-            .order_service
-            .create(NewOrder {
-                amount: total_price,
-                bot_id: command.bot_id,
-                currency: "RUB".to_string(),
-                customer_id: customer.id,
-                paid_at: Some(Utc::now()),      // As it buy from balance
-                fulfilled_at: Some(Utc::now()), // We send fulfillment immediately
-                status: OrderStatus::Fulfilled,
-            })
-            .await?;
+        Ok(external_provider)
+    }
+
+    async fn create_order_item(
+        &self,
+        order_id: i64,
+        product: &Product,
+        amount: i64,
+    ) -> ApiResult<()> {
         self.order_item_service
             .create(NewOrderItem {
-                order_id: order.id,
+                order_id,
                 product_id: product.id,
                 details: product
                     .details
@@ -192,12 +208,24 @@ impl PurchaseServiceTrait
                 quantity: amount as i16,
             })
             .await?;
+        Ok(())
+    }
+
+    /// Debits the balance and pays the referral share of `order_id`. Returns the created
+    /// transactions, the customer's debit first.
+    async fn charge(
+        &self,
+        bot: &BotRow,
+        customer: &CustomerRow,
+        order_id: i64,
+        total_price: Decimal,
+    ) -> ApiResult<Vec<TransactionRow>> {
         let transaction = self
             .transactions_service
             .create(NewTransaction {
                 amount: -total_price,
                 customer_id: Some(customer.id),
-                order_id: Some(order.id),
+                order_id: Some(order_id),
                 r#type: TransactionType::Purchase,
                 store_balance_delta: dec!(0), // There is no comission for purchase
                 platform_commission: dec!(0), // There is no comission for purchase
@@ -206,19 +234,22 @@ impl PurchaseServiceTrait
                 payment_gateway: None,
                 details: None,
                 bot_id: None,
+                payment_invoice_id: None,
             })
             .await?;
+        let mut charges = vec![transaction];
 
         // If customer buy from self bot, there is no need to create referral payout
         if let Some(owner_id) = bot.owner_id
             && customer.id != owner_id
         {
             // Add balance to referral owner
-            self.transactions_service
+            let payout = self
+                .transactions_service
                 .create(NewTransaction {
                     amount: total_price * bot.referral_percentage / dec!(100),
                     customer_id: Some(owner_id),
-                    order_id: Some(order.id),
+                    order_id: Some(order_id),
                     r#type: TransactionType::ReferralPayout,
                     store_balance_delta: dec!(0),
                     platform_commission: dec!(0),
@@ -226,40 +257,97 @@ impl PurchaseServiceTrait
                     description: None,
                     payment_gateway: None,
                     details: None,
-                    bot_id: Some(command.bot_id),
+                    bot_id: Some(bot.id),
+                    payment_invoice_id: None,
+                })
+                .await;
+            match payout {
+                Ok(payout) => charges.push(payout),
+                Err(e) => {
+                    self.refund(&charges).await?;
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(charges)
+    }
+
+    /// Reverses `charges` with refund transactions
+    async fn refund(&self, charges: &[TransactionRow]) -> ApiResult<()> {
+        for charge in charges {
+            self.transactions_service
+                .create(NewTransaction {
+                    amount: -charge.amount,
+                    customer_id: charge.customer_id,
+                    order_id: charge.order_id,
+                    r#type: TransactionType::Refund,
+                    store_balance_delta: -charge.store_balance_delta,
+                    platform_commission: dec!(0),
+                    gateway_commission: dec!(0),
+                    description: None,
+                    payment_gateway: None,
+                    details: None,
+                    bot_id: charge.bot_id,
+                    payment_invoice_id: None,
                 })
                 .await?;
         }
+        Ok(())
+    }
 
-        if is_subscription
-            && product.provider_name == "contms"
-            && let Some(external_id) = product.external_id
+    /// Debits the balance, pays the referral share and delivers the product of `order_id`
+    async fn charge_and_fulfill(
+        &self,
+        bot: &BotRow,
+        customer: &CustomerRow,
+        product: Product,
+        external_provider: Option<Arc<dyn ExternalProductProvider>>,
+        order_id: i64,
+        total_price: Decimal,
+    ) -> ApiResult<PurchaseResult> {
+        let charges = self.charge(bot, customer, order_id, total_price).await?;
+        self.fulfill(
+            customer,
+            product,
+            external_provider,
+            order_id,
+            total_price,
+            &charges[0],
+        )
+        .await
+    }
+
+    /// Delivers the product of `order_id` once `transaction` has debited the balance
+    async fn fulfill(
+        &self,
+        customer: &CustomerRow,
+        product: Product,
+        external_provider: Option<Arc<dyn ExternalProductProvider>>,
+        order_id: i64,
+        total_price: Decimal,
+        transaction: &TransactionRow,
+    ) -> ApiResult<PurchaseResult> {
+        let balance = transaction
+            .user_balance_after
+            .unwrap_or_default()
+            .to_f64()
+            .unwrap_or_default();
+
+        if product.r#type == ProductType::Subscription
+            && let Some(external_id) = product.external_id.as_deref()
+            && let Some(provider) = external_provider
         {
-            let proxy = self
-                .contms_provider
-                .subscribe_to_proxy(
-                    &external_id,
+            let subscription = provider
+                .fulfill_purchase(
+                    external_id,
+                    product.details.as_ref(),
                     Duration::days(product.subscription_period_days as i64),
                 )
                 .await
                 .map_err(ApiError::InternalServerError)?;
-            let expiration_date = DateTime::from_timestamp_millis(proxy.expires).ok_or(
-                ApiError::InternalServerError("Failed to parse proxy expires".to_string()),
-            )?;
-            let (host, port) = match product.details {
-                Some(value) => match value {
-                    ProductDetails::ContMs { host, port } => (host, port),
-                },
-                None => {
-                    return Err(ApiError::BadRequest("Invalid product details".to_string()));
-                }
-            };
-            let subscription_details = UserSubscriptionDetails::ContMs {
-                host,
-                port,
-                username: proxy.name,
-                password: proxy.pass,
-            };
+            let expiration_date = subscription.expires_at;
+            let subscription_details = subscription.details;
             self.user_subscription_service
                 .create(NewUserSubscription {
                     customer_id: customer.id,
@@ -268,7 +356,7 @@ impl PurchaseServiceTrait
                     )?),
                     expires_at: expiration_date,
                     next_charge_at: Some(expiration_date),
-                    order_id: order.id,
+                    order_id,
                     period_days: product.subscription_period_days,
                     price_at_subscription: product.price,
                     product_id: Some(product.id),
@@ -277,11 +365,7 @@ impl PurchaseServiceTrait
                 .await?;
 
             return Ok(PurchaseResult {
-                balance: transaction
-                    .user_balance_after
-                    .unwrap_or_default()
-                    .to_f64()
-                    .unwrap_or_default(),
+                balance,
                 details: Some(PurchaseDetails::UserSubscriptionDetails(
                     subscription_details,
                 )),
@@ -293,11 +377,7 @@ impl PurchaseServiceTrait
         };
 
         Ok(PurchaseResult {
-            balance: transaction
-                .user_balance_after
-                .unwrap_or_default()
-                .to_f64()
-                .unwrap_or_default(),
+            balance,
             details: product.details.map(PurchaseDetails::ProductDetails),
             fulfilled_image_id: product.fulfillment_image_id,
             fulfilled_text: product.fulfillment_text,
@@ -305,6 +385,202 @@ impl PurchaseServiceTrait
             price: total_price.to_f64().unwrap_or_default(),
         })
     }
+
+    /// Undoes a paid checkout order that couldn't be delivered: refunds `charges`, cancels
+    /// the order and returns its stock. The paid invoice stays on the balance.
+    async fn roll_back_checkout_order(&self, order_id: i64, charges: &[TransactionRow]) {
+        let rolled_back = async {
+            self.refund(charges).await?;
+            self.order_service
+                .transition(order_id, OrderStatus::Cancelled)
+                .await?;
+            self.order_item_service.return_stock(order_id).await
+        }
+        .await;
+        if let Err(e) = rolled_back {
+            tracing::error!("Failed to roll back checkout order {order_id}: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl PurchaseServiceTrait
+    for PurchaseService<
+        TransactionService<TransactionRepository>,
+        CustomerService<CustomerRepository, AuditLogServiceShort>,
+        OrderItemService<OrderItemRepository, StockMovementRepository>,
+        OrderService<OrderRepository, OrderItemRepository>,
+        ProductServiceShort,
+        UserSubscriptionService<UserSubscriptionRepository>,
+        BotService<
+            BotRepository,
+            SettingsRepository,
+            AuditLogService<AuditLogRepository>,
+            TransactionRepository,
+        >,
+    >
+{
+    async fn purchase_product(&self, command: PurchaseProductCommand) -> ApiResult<PurchaseResult> {
+        let bot = self.bot_service.get_by_id(command.bot_id).await?;
+        let product = self.product_service.get_by_id(command.product_id).await?;
+        let external_provider = self.check_available(&product, command.amount)?;
+        let customer = self
+            .customer_service
+            .get_by_telegram_id(command.telegram_id)
+            .await?;
+        let is_subscription = product.r#type == ProductType::Subscription;
+        let amount = if is_subscription { 1 } else { command.amount };
+        let total_price = product.price * Decimal::from(amount);
+        if customer.balance < total_price {
+            return Err(ApiError::BadRequest("Not enough balance".to_string()));
+        }
+        let order = self
+            .order_service
+            .create(NewOrder {
+                amount: total_price,
+                bot_id: command.bot_id,
+                currency: self.base_currency.code().to_string(),
+                customer_id: customer.id,
+                paid_at: Some(Utc::now()),      // As it buy from balance
+                fulfilled_at: Some(Utc::now()), // We send fulfillment immediately
+                status: OrderStatus::Fulfilled,
+            })
+            .await?;
+        self.create_order_item(order.id, &product, amount).await?;
+
+        self.charge_and_fulfill(
+            &bot,
+            &customer,
+            product,
+            external_provider,
+            order.id,
+            total_price,
+        )
+        .await
+    }
+
+    async fn create_checkout_order(
+        &self,
+        command: CreateCheckoutOrderCommand,
+    ) -> ApiResult<CheckoutOrder> {
+        let product = self.product_service.get_by_id(command.product_id).await?;
+        self.check_available(&product, 1)?;
+        let customer = self
+            .customer_service
+            .get_by_telegram_id(command.telegram_id)
+            .await?;
+        let total_price = product.price;
+        let amount_to_pay = if command.pay_full_price {
+            total_price
+        } else {
+            total_price - customer.balance.max(Decimal::ZERO)
+        };
+        if amount_to_pay <= Decimal::ZERO {
+            return Err(ApiError::BadRequest(
+                "Balance covers the price, buy from balance".to_string(),
+            ));
+        }
+        let order = self
+            .order_service
+            .create(NewOrder {
+                amount: total_price,
+                bot_id: command.bot_id,
+                currency: self.base_currency.code().to_string(),
+                customer_id: customer.id,
+                paid_at: None,
+                fulfilled_at: None,
+                status: OrderStatus::Created,
+            })
+            .await?;
+        self.create_order_item(order.id, &product, 1).await?;
+
+        Ok(CheckoutOrder {
+            order,
+            amount_to_pay,
+        })
+    }
+
+    async fn complete_checkout_order(&self, order_id: i64) -> ApiResult<PurchaseResult> {
+        let order = self.order_service.get_by_id(order_id).await?;
+        if order.status != OrderStatus::Created {
+            return Err(ApiError::Conflict(format!(
+                "Order {order_id} is not awaiting payment"
+            )));
+        }
+        let item = order
+            .order_items
+            .first()
+            .ok_or(ApiError::InternalServerError(format!(
+                "Order {order_id} has no items"
+            )))?;
+        let product = self.product_service.get_by_id(item.product_id).await?;
+        let customer = self.customer_service.get_by_id(order.customer_id).await?;
+        let bot = self.bot_service.get_by_id(order.bot_id).await?;
+
+        // Stock was taken by the order item, only the product and the balance can fail here
+        let external_provider = self.external_providers.get(&product.provider_name);
+        let check = if product.hidden_at.is_some() {
+            Err(ApiError::BadRequest("Product is not available".to_string()))
+        } else if product.external_id.is_some() && external_provider.is_none() {
+            Err(ApiError::BadRequest(format!(
+                "Product provider {} is not available",
+                product.provider_name
+            )))
+        } else if customer.balance < order.amount {
+            Err(ApiError::BadRequest("Not enough balance".to_string()))
+        } else {
+            Ok(())
+        };
+        if let Err(e) = check {
+            self.cancel_checkout_order(order_id).await?;
+            return Err(e);
+        }
+
+        // Claims the order, so a second completion of the same invoice can't charge twice
+        self.order_service
+            .transition(order_id, OrderStatus::Paid)
+            .await?;
+        let charges = match self.charge(&bot, &customer, order_id, order.amount).await {
+            Ok(charges) => charges,
+            Err(e) => {
+                self.roll_back_checkout_order(order_id, &[]).await;
+                return Err(e);
+            }
+        };
+        let result = match self
+            .fulfill(
+                &customer,
+                product,
+                external_provider,
+                order_id,
+                order.amount,
+                &charges[0],
+            )
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                self.roll_back_checkout_order(order_id, &charges).await;
+                return Err(e);
+            }
+        };
+        self.order_service
+            .transition(order_id, OrderStatus::Fulfilled)
+            .await?;
+
+        Ok(result)
+    }
+
+    async fn cancel_checkout_order(&self, order_id: i64) -> ApiResult<()> {
+        let order = self.order_service.get_by_id(order_id).await?;
+        if order.status != OrderStatus::Created {
+            return Ok(());
+        }
+        self.order_service
+            .transition(order_id, OrderStatus::Cancelled)
+            .await?;
+        self.order_item_service.return_stock(order_id).await
+    }
 }
 
 #[cfg(test)]
@@ -312,6 +588,9 @@ mod tests {
     use super::*;
     use crate::{
         errors::api::ApiError,
+        infrastructure::external::products::{
+            ExternalCatalogItem, ExternalSubscription, ExternalSubscriptionStatus,
+        },
         infrastructure::repositories::{
             audit_log::AuditLogRepository,
             bot::BotRepository,
@@ -329,12 +608,14 @@ mod tests {
         services::{
             bot::BotService, category::CategoryService, customer::CustomerService,
             order::OrderService, order_item::OrderItemService, product::ProductService,
-            transaction::TransactionService, user_subscription::UserSubscriptionService,
+            status_transition::TransitionPublisher, transaction::TransactionService,
+            user_subscription::UserSubscriptionService,
         },
     };
-    use chrono::Utc;
+    use chrono::{DateTime, Utc};
     use rust_decimal::Decimal;
     use rust_decimal_macros::dec;
+    use shared_dtos::{product::ProductDetails, user_subscription::UserSubscriptionDetails};
     use sqlx::PgPool;
     use std::str::FromStr;
     use std::sync::Arc;
@@ -360,7 +641,6 @@ mod tests {
         OrderItemService<OrderItemRepository, StockMovementRepository>,
         OrderService<OrderRepository, OrderItemRepository>,
         ProductServiceShort,
-        ContmsProductsProvider,
         UserSubscriptionService<UserSubscriptionRepository>,
         BotServiceShort,
     >;
@@ -372,13 +652,58 @@ mod tests {
         bot_id: Option<i64>,
     }
 
+    /// Supplier that is down, every call fails
+    struct FailingProvider;
+
+    #[async_trait]
+    impl ExternalProductProvider for FailingProvider {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn list_catalog(&self) -> Result<Vec<ExternalCatalogItem>, String> {
+            Err("supplier is down".to_string())
+        }
+
+        async fn fulfill_purchase(
+            &self,
+            _external_id: &str,
+            _details: Option<&ProductDetails>,
+            _period: Duration,
+        ) -> Result<ExternalSubscription, String> {
+            Err("supplier is down".to_string())
+        }
+
+        async fn renew(
+            &self,
+            _subscription: &UserSubscriptionDetails,
+            _period: Duration,
+        ) -> Result<DateTime<Utc>, String> {
+            Err("supplier is down".to_string())
+        }
+
+        async fn revoke(&self, _subscription: &UserSubscriptionDetails) -> Result<(), String> {
+            Err("supplier is down".to_string())
+        }
+
+        async fn check_status(
+            &self,
+            _subscription: &UserSubscriptionDetails,
+        ) -> Result<ExternalSubscriptionStatus, String> {
+            Err("supplier is down".to_string())
+        }
+    }
+
     async fn create_customer(pool: &PgPool, telegram_id: i64, balance: &str) -> CustomerRow {
         sqlx::query_as!(
             CustomerRow,
             r#"
             INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot, balance)
             VALUES ($1, 1, 1, $2)
-            RETURNING *
+            RETURNING
+                id, telegram_id, balance, is_blocked, blocked_until, bot_is_blocked_by_user, has_passed_captcha,
+                registered_with_bot, last_seen_with_bot, last_seen_at, created_at, updated_at,
+                display_currency as "display_currency: _"
             "#,
             telegram_id,
             Decimal::from_str(balance).unwrap()
@@ -464,6 +789,13 @@ mod tests {
     }
 
     fn build_service(pool: &PgPool) -> PurchaseServiceShort {
+        build_service_with_providers(pool, ExternalProductProviderRegistry::new())
+    }
+
+    fn build_service_with_providers(
+        pool: &PgPool,
+        external_providers: ExternalProductProviderRegistry,
+    ) -> PurchaseServiceShort {
         let pool = Arc::new(pool.clone());
 
         let audit_log_service = Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
@@ -488,6 +820,7 @@ mod tests {
         let order_service = Arc::new(OrderService::new(
             Arc::new(OrderRepository::new(pool.clone())),
             Arc::new(OrderItemRepository::new(pool.clone())),
+            Arc::new(TransitionPublisher::default()),
         ));
         let transaction_service = Arc::new(TransactionService::new(Arc::new(
             TransactionRepository::new(pool.clone()),
@@ -506,10 +839,6 @@ mod tests {
             audit_log_service,
             Arc::new(reqwest::Client::new()),
         ));
-        let contms_provider = Arc::new(ContmsProductsProvider::new(
-            Arc::new(reqwest::Client::new()),
-            "http://localhost".to_string(),
-        ));
 
         PurchaseService::new(
             transaction_service,
@@ -517,9 +846,10 @@ mod tests {
             product_service,
             order_service,
             order_item_service,
-            contms_provider,
+            Arc::new(external_providers),
             user_subscription_service,
             bot_service,
+            Currency::Rub,
         )
     }
 
@@ -670,4 +1000,204 @@ mod tests {
 
         assert!(matches!(err, ApiError::BadRequest(_)));
     }
+
+    #[sqlx::test]
+    async fn test_purchase_rejects_product_of_disabled_provider(pool: PgPool) {
+        let service = build_service(&pool);
+        let buyer = create_customer(&pool, 501, "0.00").await;
+        credit_customer(&pool, buyer.id, "500.00").await;
+        let bot_id = create_bot(&pool, None, "provider_bot", "provider_bot", "0").await;
+        let product_id = create_product(&pool, "Supplier product", "100.00", 0).await;
+        sqlx::query!(
+            "UPDATE products SET provider_name = 'supplier', external_id = 'ext-1', type = 'subscription', subscription_period_days = 30 WHERE id = $1",
+            product_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = service
+            .purchase_product(PurchaseProductCommand {
+                product_id,
+                amount: 1,
+                telegram_id: buyer.telegram_id,
+                bot_id,
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ApiError::BadRequest(_)));
+        let orders_count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM orders WHERE customer_id = $1",
+            buyer.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(orders_count, Some(0));
+    }
+
+    #[sqlx::test]
+    async fn test_checkout_invoices_shortfall_and_completes_after_deposit(pool: PgPool) {
+        let service = build_service(&pool);
+        let buyer = create_customer(&pool, 601, "0.00").await;
+        credit_customer(&pool, buyer.id, "30.00").await;
+        let bot_id = create_bot(&pool, None, "checkout_bot", "checkout_bot", "0").await;
+        let product_id = create_product(&pool, "Checkout product", "100.00", 10).await;
+
+        let checkout = service
+            .create_checkout_order(CreateCheckoutOrderCommand {
+                product_id,
+                telegram_id: buyer.telegram_id,
+                bot_id,
+                pay_full_price: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(checkout.amount_to_pay, dec!(70.00));
+        assert_eq!(checkout.order.status, OrderStatus::Created);
+
+        // The paid invoice lands on the balance before the order is completed
+        credit_customer(&pool, buyer.id, "70.00").await;
+        let result = service
+            .complete_checkout_order(checkout.order.id)
+            .await
+            .unwrap();
+        assert_eq!(result.price, 100.0);
+
+        let status =
+            sqlx::query_scalar!("SELECT status FROM orders WHERE id = $1", checkout.order.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(status, "fulfilled");
+        let balance = sqlx::query_scalar!("SELECT balance FROM customers WHERE id = $1", buyer.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(balance, Decimal::ZERO);
+
+        let err = service
+            .complete_checkout_order(checkout.order.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(_)));
+    }
+
+    #[sqlx::test]
+    async fn test_cancel_checkout_order_returns_stock(pool: PgPool) {
+        let service = build_service(&pool);
+        let buyer = create_customer(&pool, 701, "0.00").await;
+        let bot_id = create_bot(&pool, None, "cancel_bot", "cancel_bot", "0").await;
+        let product_id = create_product(&pool, "Cancelled product", "100.00", 10).await;
+
+        let checkout = service
+            .create_checkout_order(CreateCheckoutOrderCommand {
+                product_id,
+                telegram_id: buyer.telegram_id,
+                bot_id,
+                pay_full_price: true,
+            })
+            .await
+            .unwrap();
+        assert_eq!(checkout.amount_to_pay, dec!(100.00));
+
+        service
+            .cancel_checkout_order(checkout.order.id)
+            .await
+            .unwrap();
+        // Cancelling twice is a no-op, the stock is only returned once
+        service
+            .cancel_checkout_order(checkout.order.id)
+            .await
+            .unwrap();
+
+        let (returns, net_quantity) = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE type = 'return') as "returns!",
+                COALESCE(SUM(quantity), 0)::BIGINT as "net_quantity!"
+            FROM stock_movements
+            WHERE order_id = $1
+            "#,
+            checkout.order.id
+        )
+        .fetch_one(&pool)
+        .await
+        .map(|r| (r.returns, r.net_quantity))
+        .unwrap();
+        assert_eq!(returns, 1);
+        assert_eq!(net_quantity, 0);
+        let err = service
+            .complete_checkout_order(checkout.order.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(_)));
+    }
+
+    #[sqlx::test]
+    async fn test_checkout_rolls_back_when_provider_fails(pool: PgPool) {
+        let mut providers = ExternalProductProviderRegistry::new();
+        providers.register(Arc::new(FailingProvider));
+        let service = build_service_with_providers(&pool, providers);
+        let owner = create_customer(&pool, 801, "0.00").await;
+        let buyer = create_customer(&pool, 802, "0.00").await;
+        let bot_id = create_bot(&pool, Some(owner.id), "failing_bot", "failing_bot", "10").await;
+        let product_id = create_product(&pool, "Supplier product", "100.00", 10).await;
+        sqlx::query!(
+            "UPDATE products SET provider_name = 'failing', external_id = 'ext-1', type = 'subscription', subscription_period_days = 30 WHERE id = $1",
+            product_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let checkout = service
+            .create_checkout_order(CreateCheckoutOrderCommand {
+                product_id,
+                telegram_id: buyer.telegram_id,
+                bot_id,
+                pay_full_price: true,
+            })
+            .await
+            .unwrap();
+        credit_customer(&pool, buyer.id, "100.00").await;
+
+        let err = service
+            .complete_checkout_order(checkout.order.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::InternalServerError(_)));
+
+        let status =
+            sqlx::query_scalar!("SELECT status FROM orders WHERE id = $1", checkout.order.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(status, "cancelled");
+        // The debit and the referral payout are refunded, the deposit stays on the balance
+        let balances = sqlx::query!(
+            "SELECT id, balance FROM customers WHERE id = ANY($1) ORDER BY id",
+            &[owner.id, buyer.id]
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(balances[0].balance, Decimal::ZERO);
+        assert_eq!(balances[1].balance, dec!(100.00));
+        let net_quantity = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(quantity), 0)::BIGINT as "net_quantity!" FROM stock_movements WHERE order_id = $1"#,
+            checkout.order.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(net_quantity, 0);
+
+        let err = service
+            .complete_checkout_order(checkout.order.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Conflict(_)));
+    }
 }
@@ -0,0 +1,256 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use shared_dtos::{notification::DispatchAdminMessage, reconciliation::ReconciliationKindCount};
+
+use crate::{
+    errors::api::{ApiError, ApiResult},
+    infrastructure::repositories::reconciliation::ReconciliationRepositoryTrait,
+    models::{
+        common::PaginatedResult,
+        reconciliation::{
+            NewReconciliationReport, ReconciliationReportListQuery, ReconciliationReportRow,
+        },
+    },
+    services::notification_service::NotificationServiceTrait,
+};
+
+#[derive(Debug)]
+pub struct RunReconciliationCommand {
+    /// UTC day to check
+    pub day: NaiveDate,
+    pub triggered_by: Option<i64>,
+    /// Alert the manager group when discrepancies are found
+    pub notify: bool,
+}
+
+#[async_trait]
+pub trait ReconciliationServiceTrait: Send + Sync {
+    async fn get_list(
+        &self,
+        query: ReconciliationReportListQuery,
+    ) -> ApiResult<PaginatedResult<ReconciliationReportRow>>;
+    async fn get_by_day(&self, day: NaiveDate) -> ApiResult<ReconciliationReportRow>;
+    async fn run(&self, command: RunReconciliationCommand) -> ApiResult<ReconciliationReportRow>;
+}
+
+pub struct ReconciliationService<R, N> {
+    repo: Arc<R>,
+    notification_service: Arc<N>,
+}
+
+impl<R, N> ReconciliationService<R, N>
+where
+    R: ReconciliationRepositoryTrait + Send + Sync,
+    N: NotificationServiceTrait,
+{
+    pub fn new(repo: Arc<R>, notification_service: Arc<N>) -> Self {
+        Self {
+            repo,
+            notification_service,
+        }
+    }
+}
+
+#[async_trait]
+impl<R, N> ReconciliationServiceTrait for ReconciliationService<R, N>
+where
+    R: ReconciliationRepositoryTrait + Send + Sync,
+    N: NotificationServiceTrait,
+{
+    async fn get_list(
+        &self,
+        query: ReconciliationReportListQuery,
+    ) -> ApiResult<PaginatedResult<ReconciliationReportRow>> {
+        Ok(self.repo.get_list(query).await?)
+    }
+
+    async fn get_by_day(&self, day: NaiveDate) -> ApiResult<ReconciliationReportRow> {
+        self.repo
+            .get_by_day(day)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("No reconciliation report for {day}")))
+    }
+
+    async fn run(&self, command: RunReconciliationCommand) -> ApiResult<ReconciliationReportRow> {
+        if command.day > Utc::now().date_naive() {
+            return Err(ApiError::BadRequest(
+                "Cannot reconcile a day in the future".to_string(),
+            ));
+        }
+
+        let from = command.day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let to = from + Duration::days(1);
+        let discrepancies = self.repo.find_discrepancies(from, to).await?;
+        let counts = self.repo.count_checked(from, to).await?;
+
+        let report = self
+            .repo
+            .save(NewReconciliationReport {
+                day: command.day,
+                counts,
+                discrepancies,
+                triggered_by: command.triggered_by,
+            })
+            .await?;
+
+        if command.notify && !report.discrepancies.is_empty() {
+            let mut counts = BTreeMap::new();
+            for discrepancy in report.discrepancies.iter() {
+                *counts.entry(discrepancy.kind).or_insert(0) += 1;
+            }
+            let payload = DispatchAdminMessage::ReconciliationDiscrepanciesNotification {
+                report_id: report.id,
+                day: report.day,
+                counts: counts
+                    .into_iter()
+                    .map(|(kind, count)| ReconciliationKindCount { kind, count })
+                    .collect(),
+            };
+            // The report is stored either way, a failed alert must not fail the run
+            if let Err(e) = self
+                .notification_service
+                .dispatch_admin_message(payload)
+                .await
+            {
+                tracing::error!(
+                    "Failed to send reconciliation alert for {}: {e}",
+                    report.day
+                );
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use shared_dtos::{
+        notification::DispatchMessagePayload, reconciliation::ReconciliationDiscrepancyKind,
+    };
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::infrastructure::repositories::reconciliation::ReconciliationRepository;
+
+    #[derive(Default)]
+    struct MockNotificationService {
+        admin_messages: Mutex<Vec<DispatchAdminMessage>>,
+    }
+
+    #[async_trait]
+    impl NotificationServiceTrait for MockNotificationService {
+        async fn dispatch_message(&self, _payload: DispatchMessagePayload) -> ApiResult<()> {
+            Ok(())
+        }
+
+        async fn dispatch_admin_message(&self, payload: DispatchAdminMessage) -> ApiResult<()> {
+            self.admin_messages.lock().unwrap().push(payload);
+            Ok(())
+        }
+    }
+
+    fn build_service(
+        pool: &PgPool,
+    ) -> ReconciliationService<ReconciliationRepository, MockNotificationService> {
+        ReconciliationService::new(
+            Arc::new(ReconciliationRepository::new(Arc::new(pool.clone()))),
+            Arc::new(MockNotificationService::default()),
+        )
+    }
+
+    #[sqlx::test]
+    async fn test_run_alerts_managers_about_discrepancies(pool: PgPool) {
+        let service = build_service(&pool);
+        let customer_id = sqlx::query_scalar!(
+            "INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot) VALUES (2001, 1, 1) RETURNING id"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO payment_invoices (
+                customer_id, original_amount, amount, amount_in_usdt, status, expires_at,
+                gateway, gateway_invoice_id, finished_at
+            )
+            VALUES ($1, 100, 100, 1, 'completed', NOW() + INTERVAL '1 hour', 'mock', gen_random_uuid()::TEXT, NOW())
+            "#,
+            customer_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let today = Utc::now().date_naive();
+        let report = service
+            .run(RunReconciliationCommand {
+                day: today,
+                triggered_by: None,
+                notify: true,
+            })
+            .await
+            .unwrap();
+        assert_eq!(report.invoices_checked, 1);
+        assert_eq!(report.discrepancies.len(), 1);
+
+        let messages = service.notification_service.admin_messages.lock().unwrap();
+        match messages.as_slice() {
+            [
+                DispatchAdminMessage::ReconciliationDiscrepanciesNotification {
+                    report_id,
+                    day,
+                    counts,
+                },
+            ] => {
+                assert_eq!(*report_id, report.id);
+                assert_eq!(*day, today);
+                assert_eq!(
+                    counts,
+                    &vec![ReconciliationKindCount {
+                        kind: ReconciliationDiscrepancyKind::InvoiceMissingDeposit,
+                        count: 1,
+                    }]
+                );
+            }
+            other => panic!("unexpected admin messages: {other:?}"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_run_without_discrepancies_stays_silent(pool: PgPool) {
+        let service = build_service(&pool);
+
+        let report = service
+            .run(RunReconciliationCommand {
+                day: Utc::now().date_naive(),
+                triggered_by: None,
+                notify: true,
+            })
+            .await
+            .unwrap();
+        assert!(report.discrepancies.is_empty());
+        assert!(
+            service
+                .notification_service
+                .admin_messages
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+
+        let err = service
+            .run(RunReconciliationCommand {
+                day: Utc::now().date_naive() + Duration::days(1),
+                triggered_by: None,
+                notify: false,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+    }
+}
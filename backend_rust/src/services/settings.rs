@@ -258,6 +258,7 @@ mod tests {
             Ok(PaginatedResult {
                 items: vec![],
                 total: 0,
+                next_cursor: None,
             })
         }
 
@@ -266,6 +267,14 @@ mod tests {
                 "not used in this test".to_string(),
             ))
         }
+
+        async fn get_for_target(
+            &self,
+            _target_table: &str,
+            _target_id: &str,
+        ) -> ApiResult<Vec<AuditLogRow>> {
+            Ok(vec![])
+        }
     }
 
     #[tokio::test]
@@ -0,0 +1,385 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Duration;
+use serde_json::json;
+use shared_dtos::{
+    audit_log::{AuditAction, AuditStatus},
+    invoice::InvoiceStatus,
+    notification::{DispatchMessage, DispatchMessagePayload},
+};
+
+use crate::{
+    errors::api::ApiResult,
+    infrastructure::repositories::{
+        customer::CustomerRepositoryTrait,
+        payment_invoice_event::PaymentInvoiceEventRepositoryTrait,
+    },
+    models::{
+        audit_log::NewAuditLog,
+        payment_invoice_event::NewPaymentInvoiceEvent,
+        status_transition::{InvoiceTransition, OrderTransition, StatusMachine, StatusTransition},
+    },
+    services::{
+        audit_log::AuditLogServiceTrait, notification_service::NotificationServiceTrait,
+        payment_processing_service::PAYMENT_TIMEOUT_MINUTES, purchase::PurchaseServiceTrait,
+    },
+};
+
+#[async_trait]
+pub trait TransitionSubscriber<E>: Send + Sync {
+    async fn on_transition(&self, event: &E) -> ApiResult<()>;
+}
+
+/// Fans applied status transitions out to subscribers. The transition is already committed,
+/// so a failing subscriber is logged and doesn't stop the others.
+pub struct TransitionPublisher<E> {
+    subscribers: Vec<Arc<dyn TransitionSubscriber<E>>>,
+}
+
+impl<E> Default for TransitionPublisher<E> {
+    fn default() -> Self {
+        Self {
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl<S, R> TransitionPublisher<StatusTransition<S, R>>
+where
+    S: StatusMachine,
+    R: Send + Sync,
+{
+    pub fn subscribe(
+        mut self,
+        subscriber: Arc<dyn TransitionSubscriber<StatusTransition<S, R>>>,
+    ) -> Self {
+        self.subscribers.push(subscriber);
+        self
+    }
+
+    pub async fn publish(&self, event: &StatusTransition<S, R>) {
+        for subscriber in &self.subscribers {
+            if let Err(e) = subscriber.on_transition(event).await {
+                tracing::error!(
+                    "Transition subscriber failed on {:?} -> {:?}: {e}",
+                    event.from,
+                    event.to
+                );
+            }
+        }
+    }
+}
+
+/// Tells the customer what to do next when the gateway moves their invoice
+pub struct InvoiceNotificationSubscriber<N, C, E> {
+    notification_service: Arc<N>,
+    customers_repo: Arc<C>,
+    events_repo: Arc<E>,
+}
+
+impl<N, C, E> InvoiceNotificationSubscriber<N, C, E>
+where
+    N: NotificationServiceTrait + Send + Sync,
+    C: CustomerRepositoryTrait + Send + Sync,
+    E: PaymentInvoiceEventRepositoryTrait + Send + Sync,
+{
+    pub fn new(notification_service: Arc<N>, customers_repo: Arc<C>, events_repo: Arc<E>) -> Self {
+        Self {
+            notification_service,
+            customers_repo,
+            events_repo,
+        }
+    }
+}
+
+#[async_trait]
+impl<N, C, E> TransitionSubscriber<InvoiceTransition> for InvoiceNotificationSubscriber<N, C, E>
+where
+    N: NotificationServiceTrait + Send + Sync,
+    C: CustomerRepositoryTrait + Send + Sync,
+    E: PaymentInvoiceEventRepositoryTrait + Send + Sync,
+{
+    async fn on_transition(&self, event: &InvoiceTransition) -> ApiResult<()> {
+        let invoice = &event.row;
+        let (name, message) = match (event.from, event.to) {
+            (_, InvoiceStatus::AwaitingReceipt) => (
+                "request_receipt",
+                DispatchMessage::RequestReceiptNotification {
+                    invoice_id: invoice.id,
+                    is_first_time: true,
+                    expired_at: invoice.receipt_requested_at.unwrap_or(invoice.updated_at)
+                        + Duration::minutes(PAYMENT_TIMEOUT_MINUTES),
+                },
+            ),
+            (InvoiceStatus::ReceiptSubmitted, InvoiceStatus::Disputed) => (
+                "contact_support",
+                DispatchMessage::ContactSupportNotification,
+            ),
+            (InvoiceStatus::Disputed, InvoiceStatus::Failed) => {
+                ("dispute_failed", DispatchMessage::DisputeFailedNotification)
+            }
+            _ => return Ok(()),
+        };
+
+        let customer = self.customers_repo.get_by_id(invoice.customer_id).await?;
+        let payload = serde_json::to_value(&message).unwrap_or_default();
+        self.notification_service
+            .dispatch_message(DispatchMessagePayload {
+                message,
+                telegram_id: customer.telegram_id,
+                bot_id: customer.last_seen_with_bot,
+            })
+            .await?;
+        self.events_repo
+            .create(NewPaymentInvoiceEvent::notification(
+                invoice.id,
+                format!("{name}:{}", invoice.updated_at.timestamp_micros()),
+                payload,
+            ))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Cancels the checkout order of an invoice that won't be paid, giving its stock back. A
+/// late manual completion still credits the deposit, it just doesn't buy the order.
+pub struct CheckoutOrderSubscriber<U> {
+    purchase_service: Arc<U>,
+}
+
+impl<U> CheckoutOrderSubscriber<U>
+where
+    U: PurchaseServiceTrait + Send + Sync,
+{
+    pub fn new(purchase_service: Arc<U>) -> Self {
+        Self { purchase_service }
+    }
+}
+
+#[async_trait]
+impl<U> TransitionSubscriber<InvoiceTransition> for CheckoutOrderSubscriber<U>
+where
+    U: PurchaseServiceTrait + Send + Sync,
+{
+    async fn on_transition(&self, event: &InvoiceTransition) -> ApiResult<()> {
+        let Some(order_id) = event.row.checkout_order_id else {
+            return Ok(());
+        };
+        match event.to {
+            InvoiceStatus::Failed | InvoiceStatus::Expired | InvoiceStatus::Cancelled => {
+                self.purchase_service.cancel_checkout_order(order_id).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Writes every invoice and order status change to the audit log on behalf of the customer
+pub struct TransitionAuditSubscriber<A> {
+    audit_log_service: Arc<A>,
+}
+
+impl<A> TransitionAuditSubscriber<A>
+where
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    pub fn new(audit_log_service: Arc<A>) -> Self {
+        Self { audit_log_service }
+    }
+
+    async fn log<S: StatusMachine + serde::Serialize>(
+        &self,
+        action: AuditAction,
+        target_table: &str,
+        target_id: i64,
+        customer_id: i64,
+        from: S,
+        to: S,
+    ) -> ApiResult<()> {
+        self.audit_log_service
+            .create(NewAuditLog {
+                action,
+                status: AuditStatus::Success,
+                admin_user_id: None,
+                customer_id: Some(customer_id),
+                error_message: None,
+                old_values: Some(json!({ "status": from })),
+                new_values: Some(json!({ "status": to })),
+                target_id: target_id.to_string(),
+                target_table: target_table.to_string(),
+                ip_address: None,
+                user_agent: None,
+                request_id: None,
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<A> TransitionSubscriber<InvoiceTransition> for TransitionAuditSubscriber<A>
+where
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    async fn on_transition(&self, event: &InvoiceTransition) -> ApiResult<()> {
+        self.log(
+            AuditAction::InvoiceStatusChange,
+            "payment_invoices",
+            event.row.id,
+            event.row.customer_id,
+            event.from,
+            event.to,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<A> TransitionSubscriber<OrderTransition> for TransitionAuditSubscriber<A>
+where
+    A: AuditLogServiceTrait + Send + Sync,
+{
+    async fn on_transition(&self, event: &OrderTransition) -> ApiResult<()> {
+        self.log(
+            AuditAction::OrderStatusChange,
+            "orders",
+            event.row.id,
+            event.row.customer_id,
+            event.from,
+            event.to,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use shared_dtos::{invoice::PaymentInvoiceEventKind, notification::DispatchAdminMessage};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        errors::api::ApiError,
+        infrastructure::repositories::{
+            audit_log::AuditLogRepository,
+            customer::CustomerRepository,
+            payment_invoice::{PaymentInvoiceRepository, PaymentInvoiceRepositoryTrait},
+            payment_invoice_event::PaymentInvoiceEventRepository,
+        },
+        services::audit_log::AuditLogService,
+    };
+
+    #[derive(Default)]
+    struct FakeNotificationService {
+        sent: Mutex<Vec<DispatchMessagePayload>>,
+    }
+
+    #[async_trait]
+    impl NotificationServiceTrait for FakeNotificationService {
+        async fn dispatch_message(&self, payload: DispatchMessagePayload) -> ApiResult<()> {
+            self.sent.lock().unwrap().push(payload);
+            Ok(())
+        }
+
+        async fn dispatch_admin_message(&self, _payload: DispatchAdminMessage) -> ApiResult<()> {
+            Err(ApiError::InternalServerError("not used".to_string()))
+        }
+    }
+
+    struct FailingSubscriber;
+
+    #[async_trait]
+    impl TransitionSubscriber<InvoiceTransition> for FailingSubscriber {
+        async fn on_transition(&self, _event: &InvoiceTransition) -> ApiResult<()> {
+            Err(ApiError::InternalServerError("boom".to_string()))
+        }
+    }
+
+    async fn create_test_invoice(pool: &PgPool, status: &str) -> i64 {
+        let customer_id = sqlx::query_scalar!(
+            "INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot) VALUES (555, 1, 9) RETURNING id"
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO payment_invoices (
+                customer_id, original_amount, amount, amount_in_usdt, status, expires_at, gateway,
+                gateway_invoice_id, order_id, payment_details
+            )
+            VALUES ($1, 100, 100, 1, $2, NOW() + INTERVAL '1 hour', 'platform_card', 'gw-1', gen_random_uuid(), '{}')
+            RETURNING id
+            "#,
+            customer_id,
+            status
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_publish_notifies_and_audits_despite_failing_subscriber(pool: PgPool) {
+        let pool = Arc::new(pool);
+        let invoice_id = create_test_invoice(&pool, "pending").await;
+        let notification_service = Arc::new(FakeNotificationService::default());
+        let events_repo = Arc::new(PaymentInvoiceEventRepository::new(pool.clone()));
+        let audit_log_service = Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+            pool.clone(),
+        ))));
+        let publisher = TransitionPublisher::default()
+            .subscribe(Arc::new(FailingSubscriber))
+            .subscribe(Arc::new(InvoiceNotificationSubscriber::new(
+                notification_service.clone(),
+                Arc::new(CustomerRepository::new(pool.clone())),
+                events_repo.clone(),
+            )))
+            .subscribe(Arc::new(TransitionAuditSubscriber::new(
+                audit_log_service.clone(),
+            )));
+        let invoice = PaymentInvoiceRepository::new(pool.clone())
+            .get_by_id(invoice_id)
+            .await
+            .unwrap();
+
+        publisher
+            .publish(&StatusTransition {
+                from: InvoiceStatus::Pending,
+                to: InvoiceStatus::AwaitingReceipt,
+                row: invoice,
+            })
+            .await;
+
+        {
+            let sent = notification_service.sent.lock().unwrap();
+            assert_eq!(sent.len(), 1);
+            assert_eq!((sent[0].telegram_id, sent[0].bot_id), (555, 9));
+            assert!(matches!(
+                sent[0].message,
+                DispatchMessage::RequestReceiptNotification {
+                    is_first_time: true,
+                    ..
+                }
+            ));
+        }
+        let events = events_repo.get_for_invoice(invoice_id).await.unwrap();
+        assert_eq!(
+            events.last().map(|e| e.kind),
+            Some(PaymentInvoiceEventKind::NotificationSent)
+        );
+        let actions = audit_log_service
+            .get_for_target("payment_invoices", &invoice_id.to_string())
+            .await
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].action, AuditAction::InvoiceStatusChange);
+        assert_eq!(
+            actions[0].new_values,
+            Some(json!({ "status": "awaiting_receipt" }))
+        );
+    }
+}
@@ -154,6 +154,7 @@ impl StoreBalanceRequestServiceTrait
                         amount: dec!(0), // Store balance
                         store_balance_delta: -cmd.amount,
                         bot_id: None,
+                        payment_invoice_id: None,
                         customer_id: None,
                         description: None,
                         details: None,
@@ -237,6 +238,7 @@ impl StoreBalanceRequestServiceTrait
                             amount: dec!(0),
                             store_balance_delta: prev.amount,
                             bot_id: None,
+                            payment_invoice_id: None,
                             customer_id: None,
                             description: None,
                             details: None,
@@ -290,6 +292,7 @@ impl StoreBalanceRequestServiceTrait
                             amount: dec!(0),
                             store_balance_delta: prev.amount,
                             bot_id: None,
+                            payment_invoice_id: None,
                             customer_id: None,
                             description: None,
                             details: None,
@@ -97,6 +97,7 @@ mod tests {
                 payment_gateway: None,
                 details: None,
                 bot_id: None,
+                payment_invoice_id: None,
             })
             .await
             .unwrap();
@@ -114,6 +115,7 @@ mod tests {
                 payment_gateway: None,
                 details: None,
                 bot_id: None,
+                payment_invoice_id: None,
             })
             .await
             .unwrap();
@@ -141,6 +143,7 @@ mod tests {
                 payment_gateway: None,
                 details: None,
                 bot_id: None,
+                payment_invoice_id: None,
             })
             .await
             .unwrap();
@@ -8,28 +8,61 @@ use crate::infrastructure::external::payment::mock::MockPaymentsProvider;
 #[cfg(feature = "contms-provider")]
 use crate::infrastructure::external::products::contms::ContmsProductsProvider;
 use crate::{
-    config::{self, Config},
+    config::{self, Config, ExchangeRateSourceKind},
     db,
     infrastructure::{
-        external::payment::autosales_platform::AutosalesPlatformPaymentsProvider,
+        external::{
+            exchange_rates::{
+                ExchangeRateSource, StaticExchangeRateSource, cbr::CbrExchangeRateSource,
+            },
+            payment::autosales_platform::AutosalesPlatformPaymentsProvider,
+            products::ExternalProductProviderRegistry,
+        },
         repositories::{
-            active_token::ActiveTokenRepository, admin_user::AdminUserRepository,
-            analytics::AnalyticsRepository, audit_log::AuditLogRepository, bot::BotRepository,
-            broadcast::BroadcastRepository, category::CategoryRepository,
-            customer::CustomerRepository, dashboard::DashboardRepository,
-            effective_permission::EffectivePermissionRepository, image::ImageRepository,
-            order::OrderRepository, order_item::OrderItemRepository,
-            payment_invoice::PaymentInvoiceRepository, permission::PermissionRepository,
-            products::ProductRepository, role::RoleRepository,
-            role_permission::RolePermissionRepository, settings::SettingsRepository,
+            accounting_export::AccountingExportRepository,
+            active_token::ActiveTokenRepository,
+            admin_api_key::AdminApiKeyRepository,
+            admin_user::AdminUserRepository,
+            admin_user_recovery_code::AdminUserRecoveryCodeRepository,
+            analytics::AnalyticsRepository,
+            audit_log::AuditLogRepository,
+            bot::BotRepository,
+            broadcast::BroadcastRepository,
+            category::CategoryRepository,
+            customer::CustomerRepository,
+            customer_segment::CustomerSegmentRepository,
+            dashboard::DashboardRepository,
+            effective_permission::EffectivePermissionRepository,
+            exchange_rate::ExchangeRateRepository,
+            gateway_attempt::GatewayAttemptRepository,
+            idempotency_key::IdempotencyKeyRepository,
+            image::ImageRepository,
+            order::OrderRepository,
+            order_item::OrderItemRepository,
+            payment_gateway_settings::PaymentGatewaySettingsRepository,
+            payment_invoice::PaymentInvoiceRepository,
+            payment_invoice_event::PaymentInvoiceEventRepository,
+            payment_webhook_event::PaymentWebhookEventRepository,
+            permission::PermissionRepository,
+            product_sync::{ProductSyncRunRepository, ProductSyncSnapshotRepository},
+            products::ProductRepository,
+            reconciliation::ReconciliationRepository,
+            role::RoleRepository,
+            role_permission::RolePermissionRepository,
+            settings::SettingsRepository,
             stock_movement::StockMovementRepository,
             store_balance_request::StoreBalanceRequestRepository,
-            temporary_token::TemporaryTokenRepository, transaction::TransactionRepository,
-            user_permission::UserPermissionRepository, user_role::UserRoleRepository,
+            temporary_token::TemporaryTokenRepository,
+            transaction::TransactionRepository,
+            user_permission::UserPermissionRepository,
+            user_role::UserRoleRepository,
             user_subscription::UserSubscriptionRepository,
+            worker_heartbeat::WorkerHeartbeatRepository,
         },
     },
     services::{
+        accounting_export::AccountingExportService,
+        admin_api_key::AdminApiKeyService,
         admin_user::AdminUserService,
         analytics::AnalyticsService,
         audit_log::AuditLogService,
@@ -38,20 +71,33 @@ use crate::{
         broadcast::BroadcastService,
         captcha::CaptchaService,
         category::CategoryService,
+        currency::CurrencyService,
         customer::CustomerService,
+        customer_segment::CustomerSegmentService,
         dashboard::DashboardService,
+        gateway_health::GatewayHealthService,
+        health::HealthService,
+        idempotency::IdempotencyService,
         image::ImageService,
         notification_service::NotificationService,
         order::OrderService,
         order_item::OrderItemService,
+        payment_gateway_settings::PaymentGatewaySettingsService,
         payment_invoice::PaymentInvoiceService,
+        payment_invoice_resolution::PaymentInvoiceResolutionService,
         payment_processing_service::PaymentProcessingService,
         permission::PermissionService,
         product::ProductService,
+        product_sync::{ProductSyncRules, ProductSyncService},
         purchase::PurchaseService,
+        reconciliation::ReconciliationService,
         role::RoleService,
         role_permission::RolePermissionService,
         settings::SettingsService,
+        status_transition::{
+            CheckoutOrderSubscriber, InvoiceNotificationSubscriber, TransitionAuditSubscriber,
+            TransitionPublisher,
+        },
         stock_movement::StockMovementService,
         store_balance_request::StoreBalanceRequestService,
         topt_encryptor::TotpEncryptor,
@@ -60,6 +106,13 @@ use crate::{
     },
 };
 
+/// Requests holding an idempotency key longer than this are considered dead and can be retried
+const IDEMPOTENCY_KEY_LOCK_TIMEOUT_SECONDS: i64 = 60;
+/// Per-dependency limit of a readiness check
+const HEALTH_CHECK_TIMEOUT_SECONDS: u64 = 3;
+/// Readiness results are shared by probes and `can-operate` for this long
+const READINESS_CACHE_SECONDS: u64 = 10;
+
 type AuditLogShortType = AuditLogService<AuditLogRepository>;
 
 type CategoryServiceShortType = CategoryService<CategoryRepository, AuditLogShortType>;
@@ -83,6 +136,18 @@ type PaymentInvoiceShortType = PaymentInvoiceService<
     SettingsRepository,
     AutosalesPlatformPaymentsProvider,
     CustomerRepository,
+    GatewayHealthService<GatewayAttemptRepository>,
+    PaymentGatewaySettingsRepository,
+    PaymentInvoiceEventRepository,
+    CurrencyService<ExchangeRateRepository>,
+>;
+
+type PaymentProcessingServiceShortType = PaymentProcessingService<
+    PaymentInvoiceShortType,
+    NotificationService,
+    CustomerServiceShortType,
+    PaymentWebhookEventRepository,
+    PurchaseServiceShortType,
 >;
 
 type OrderItemServiceShortType = OrderItemService<OrderItemRepository, StockMovementRepository>;
@@ -96,11 +161,19 @@ type PurchaseServiceShortType = PurchaseService<
     OrderItemServiceShortType,
     OrderService<OrderRepository, OrderItemRepository>,
     ProductServiceShortType,
-    ContmsProductsProvider,
     UserSubscriptionService<UserSubscriptionRepository>,
     BotServiceShortType,
 >;
 
+type AccountingExportServiceShortType = AccountingExportService<
+    AccountingExportRepository,
+    TransactionRepository,
+    OrderRepository,
+    OrderItemRepository,
+    PaymentInvoiceRepository,
+    StockMovementRepository,
+>;
+
 type StoreBalanceRequestServiceShortType = StoreBalanceRequestService<
     StoreBalanceRequestRepository,
     AuditLogShortType,
@@ -118,11 +191,22 @@ pub struct AppState {
             TemporaryTokenRepository,
             AdminUserRepository,
             EffectivePermissionRepository,
+            AdminUserRecoveryCodeRepository,
         >,
     >,
+    pub admin_api_key_service: Arc<
+        AdminApiKeyService<AdminApiKeyRepository, EffectivePermissionRepository, AuditLogShortType>,
+    >,
     pub category_service: Arc<CategoryServiceShortType>,
-    pub admin_user_service:
-        Arc<AdminUserService<AdminUserRepository, UserRoleRepository, AuditLogShortType>>,
+    pub admin_user_service: Arc<
+        AdminUserService<
+            AdminUserRepository,
+            UserRoleRepository,
+            AuditLogShortType,
+            TemporaryTokenRepository,
+            AdminUserRecoveryCodeRepository,
+        >,
+    >,
     pub role_service: Arc<RoleService<RoleRepository>>,
     pub permission_service: Arc<PermissionService<PermissionRepository, UserPermissionRepository>>,
     pub role_permission_service: Arc<RolePermissionService<RolePermissionRepository>>,
@@ -139,26 +223,45 @@ pub struct AppState {
     pub payment_invoice_service: Arc<PaymentInvoiceShortType>,
     pub notification_service: Arc<NotificationService>,
     pub broadcast_service: Arc<BroadcastService<BroadcastRepository, AuditLogShortType>>,
-    pub payment_processing_service: Arc<
-        PaymentProcessingService<
-            TransactionServiceShortType,
+    pub customer_segment_service:
+        Arc<CustomerSegmentService<CustomerSegmentRepository, AuditLogShortType>>,
+    pub payment_processing_service: Arc<PaymentProcessingServiceShortType>,
+    pub payment_invoice_resolution_service: Arc<
+        PaymentInvoiceResolutionService<
             PaymentInvoiceShortType,
-            NotificationService,
-            CustomerServiceShortType,
+            PaymentProcessingServiceShortType,
+            PaymentWebhookEventRepository,
+            AuditLogShortType,
         >,
     >,
     pub order_item_service: Arc<OrderItemServiceShortType>,
     pub user_subscription_service: Arc<UserSubscriptionService<UserSubscriptionRepository>>,
     pub purchase_service: Arc<PurchaseServiceShortType>,
     pub client: Arc<reqwest::Client>,
-    #[cfg(feature = "contms-provider")]
-    pub contms_products_provider: Arc<ContmsProductsProvider>,
+    pub external_product_providers: Arc<ExternalProductProviderRegistry>,
+    pub product_sync_service: Arc<
+        ProductSyncService<
+            ProductSyncRunRepository,
+            ProductSyncSnapshotRepository,
+            ProductServiceShortType,
+            CategoryServiceShortType,
+        >,
+    >,
     #[cfg(feature = "mock-payments-provider")]
     pub mock_payments_provider: Arc<MockPaymentsProvider>,
     pub platform_payments_provider: Arc<AutosalesPlatformPaymentsProvider>,
     pub analytics_service: Arc<AnalyticsService<AnalyticsRepository>>,
     pub dashboard_service: Arc<DashboardService<DashboardRepository>>,
     pub store_balance_request_service: Arc<StoreBalanceRequestServiceShortType>,
+    pub idempotency_service: Arc<IdempotencyService<IdempotencyKeyRepository>>,
+    pub reconciliation_service:
+        Arc<ReconciliationService<ReconciliationRepository, NotificationService>>,
+    pub accounting_export_service: Arc<AccountingExportServiceShortType>,
+    pub health_service: Arc<HealthService<WorkerHeartbeatRepository>>,
+    pub gateway_health_service: Arc<GatewayHealthService<GatewayAttemptRepository>>,
+    pub payment_gateway_settings_service:
+        Arc<PaymentGatewaySettingsService<PaymentGatewaySettingsRepository, AuditLogShortType>>,
+    pub currency_service: Arc<CurrencyService<ExchangeRateRepository>>,
 }
 
 impl AppState {
@@ -172,15 +275,17 @@ impl AppState {
         let admin_user_repo = Arc::new(AdminUserRepository::new(db_pool.clone()));
         let effective_permission_repo =
             Arc::new(EffectivePermissionRepository::new(db_pool.clone()));
+        let recovery_code_repo = Arc::new(AdminUserRecoveryCodeRepository::new(db_pool.clone()));
         let totp_encryptor = Arc::new(
             TotpEncryptor::new(&config.totp_encode_secret.clone())
                 .expect("Failed to init totp_encryptor"),
         );
         let auth_service = Arc::new(AuthService::new(
             active_token_repo,
-            temp_token_repo,
+            temp_token_repo.clone(),
             admin_user_repo.clone(),
-            effective_permission_repo,
+            effective_permission_repo.clone(),
+            recovery_code_repo.clone(),
             totp_encryptor.clone(),
             AuthServiceConfig {
                 jwt_secret: config.jwt_secret.clone(),
@@ -194,6 +299,11 @@ impl AppState {
                 refresh_token_ttl: Duration::minutes(config.refresh_token_ttl_minutes),
             },
         ));
+        let admin_api_key_service = Arc::new(AdminApiKeyService::new(
+            Arc::new(AdminApiKeyRepository::new(db_pool.clone())),
+            effective_permission_repo,
+            audit_logs_service.clone(),
+        ));
         let category_repo = Arc::new(CategoryRepository::new(db_pool.clone()));
         let category_service = Arc::new(CategoryService::new(
             category_repo,
@@ -204,6 +314,8 @@ impl AppState {
             db_pool.clone(),
             admin_user_repo,
             user_role_repo,
+            temp_token_repo,
+            recovery_code_repo,
             totp_encryptor,
             audit_logs_service.clone(),
         ));
@@ -253,19 +365,67 @@ impl AppState {
             audit_logs_service.clone(),
             client.clone(),
         ));
+        let notification_service = Arc::new(NotificationService::new(
+            client.as_ref().clone(),
+            config.bot_dispatcher_webhook_url.clone(),
+            config.bot_admin_dispatcher_webhook_url.clone(),
+            config.service_api_key.clone(),
+        ));
+        let payment_invoice_event_repo =
+            Arc::new(PaymentInvoiceEventRepository::new(db_pool.clone()));
+        let transition_audit_subscriber =
+            Arc::new(TransitionAuditSubscriber::new(audit_logs_service.clone()));
+        let order_transitions =
+            Arc::new(TransitionPublisher::default().subscribe(transition_audit_subscriber.clone()));
         let order_item_repo = Arc::new(OrderItemRepository::new(db_pool.clone()));
         let order_service = Arc::new(OrderService::new(
             Arc::new(OrderRepository::new(db_pool.clone())),
             order_item_repo.clone(),
-        ));
-        let captcha_service = Arc::new(CaptchaService::new(
-            client.clone(),
-            config.captcha_api_url.clone(),
+            order_transitions,
         ));
         let order_item_service = Arc::new(OrderItemService::new(
             order_item_repo.clone(),
             stock_movement_repo.clone(),
         ));
+        // Every supplier is behind its own cargo feature and registered by `provider_name`
+        #[cfg_attr(not(feature = "contms-provider"), allow(unused_mut))]
+        let mut external_product_providers = ExternalProductProviderRegistry::new();
+        #[cfg(feature = "contms-provider")]
+        external_product_providers.register(Arc::new(ContmsProductsProvider::new(
+            client.clone(),
+            config.contms_api_url.clone(),
+        )));
+        let external_product_providers = Arc::new(external_product_providers);
+        let user_subscription_service = Arc::new(UserSubscriptionService::new(Arc::new(
+            UserSubscriptionRepository::new(db_pool.clone()),
+        )));
+        let purchase_service = Arc::new(PurchaseService::new(
+            transaction_service.clone(),
+            customer_service.clone(),
+            product_service.clone(),
+            order_service.clone(),
+            order_item_service.clone(),
+            external_product_providers.clone(),
+            user_subscription_service.clone(),
+            bot_service.clone(),
+            config.store_base_currency,
+        ));
+        let invoice_transitions = Arc::new(
+            TransitionPublisher::default()
+                .subscribe(Arc::new(InvoiceNotificationSubscriber::new(
+                    notification_service.clone(),
+                    customer_repo.clone(),
+                    payment_invoice_event_repo.clone(),
+                )))
+                .subscribe(Arc::new(CheckoutOrderSubscriber::new(
+                    purchase_service.clone(),
+                )))
+                .subscribe(transition_audit_subscriber),
+        );
+        let captcha_service = Arc::new(CaptchaService::new(
+            client.clone(),
+            config.captcha_api_url.clone(),
+        ));
         #[cfg(feature = "mock-payments-provider")]
         let mock_payments_provider = Arc::new(MockPaymentsProvider::new(
             client.clone(),
@@ -277,6 +437,33 @@ impl AppState {
             config.platform_payment_system_login.clone(),
             config.platform_payment_system_password.clone(),
             config.platform_payment_system_2fa_key.clone(),
+            config.platform_payment_system_webhook_secret.clone(),
+        ));
+        let gateway_health_service = Arc::new(GatewayHealthService::new(
+            Arc::new(GatewayAttemptRepository::new(db_pool.clone())),
+            chrono::Duration::minutes(config.gateway_circuit_window_minutes),
+            config.gateway_circuit_min_attempts,
+            config.gateway_circuit_failure_rate_percent,
+            chrono::Duration::seconds(config.gateway_circuit_cooldown_seconds),
+            chrono::Duration::days(config.gateway_attempts_retention_days),
+        ));
+        let payment_gateway_settings_repo =
+            Arc::new(PaymentGatewaySettingsRepository::new(db_pool.clone()));
+        let exchange_rates_fallback = StaticExchangeRateSource::parse(
+            config.exchange_rates_static.as_deref().unwrap_or_default(),
+        );
+        let exchange_rate_source: Arc<dyn ExchangeRateSource> = match config.exchange_rate_source {
+            ExchangeRateSourceKind::Static => Arc::new(exchange_rates_fallback.clone()),
+            ExchangeRateSourceKind::Cbr => Arc::new(CbrExchangeRateSource::new(
+                client.clone(),
+                config.exchange_rates_cbr_url.clone(),
+            )),
+        };
+        let currency_service = Arc::new(CurrencyService::new(
+            Arc::new(ExchangeRateRepository::new(db_pool.clone())),
+            config.store_base_currency,
+            exchange_rates_fallback,
+            exchange_rate_source,
         ));
         let payment_invoice_service = Arc::new(PaymentInvoiceService::new(
             Arc::new(PaymentInvoiceRepository::new(db_pool.clone())),
@@ -285,42 +472,50 @@ impl AppState {
             audit_logs_service.clone(),
             platform_payments_provider.clone(),
             customer_repo.clone(),
+            gateway_health_service.clone(),
+            payment_gateway_settings_repo.clone(),
+            payment_invoice_event_repo,
+            invoice_transitions,
+            currency_service.clone(),
         ));
-        #[cfg(feature = "contms-provider")]
-        let contms_products_provider = Arc::new(ContmsProductsProvider::new(
-            client.clone(),
-            config.contms_api_url.clone(),
+        let payment_gateway_settings_service = Arc::new(PaymentGatewaySettingsService::new(
+            payment_gateway_settings_repo,
+            audit_logs_service.clone(),
         ));
-
-        let notification_service = Arc::new(NotificationService::new(
-            client.as_ref().clone(),
-            config.bot_dispatcher_webhook_url.clone(),
-            config.bot_admin_dispatcher_webhook_url.clone(),
-            config.service_api_key.clone(),
+        let product_sync_service = Arc::new(ProductSyncService::new(
+            Arc::new(ProductSyncRunRepository::new(db_pool.clone())),
+            Arc::new(ProductSyncSnapshotRepository::new(db_pool.clone())),
+            product_service.clone(),
+            category_service.clone(),
+            external_product_providers.clone(),
+            db_pool.clone(),
+            ProductSyncRules::from_config(&config),
         ));
+
+        let payment_webhook_event_repo =
+            Arc::new(PaymentWebhookEventRepository::new(db_pool.clone()));
         let payment_processing_service = Arc::new(PaymentProcessingService::new(
-            transaction_service.clone(),
             payment_invoice_service.clone(),
             notification_service.clone(),
             customer_service.clone(),
+            payment_webhook_event_repo.clone(),
+            purchase_service.clone(),
+            config.store_base_currency,
         ));
-        let user_subscription_service = Arc::new(UserSubscriptionService::new(Arc::new(
-            UserSubscriptionRepository::new(db_pool.clone()),
-        )));
-        let purchase_service = Arc::new(PurchaseService::new(
-            transaction_service.clone(),
-            customer_service.clone(),
-            product_service.clone(),
-            order_service.clone(),
-            order_item_service.clone(),
-            contms_products_provider.clone(),
-            user_subscription_service.clone(),
-            bot_service.clone(),
+        let payment_invoice_resolution_service = Arc::new(PaymentInvoiceResolutionService::new(
+            payment_invoice_service.clone(),
+            payment_processing_service.clone(),
+            payment_webhook_event_repo,
+            audit_logs_service.clone(),
         ));
         let broadcast_service = Arc::new(BroadcastService::new(
             Arc::new(BroadcastRepository::new(db_pool.clone())),
             audit_logs_service.clone(),
         ));
+        let customer_segment_service = Arc::new(CustomerSegmentService::new(
+            Arc::new(CustomerSegmentRepository::new(db_pool.clone())),
+            audit_logs_service.clone(),
+        ));
         let analytics_service = Arc::new(AnalyticsService::new(Arc::new(
             AnalyticsRepository::new(db_pool.clone()),
         )));
@@ -333,11 +528,50 @@ impl AppState {
             transaction_service.clone(),
             notification_service.clone(),
         ));
+        let idempotency_service = Arc::new(IdempotencyService::new(
+            Arc::new(IdempotencyKeyRepository::new(db_pool.clone())),
+            Duration::hours(config.idempotency_key_ttl_hours),
+            Duration::seconds(IDEMPOTENCY_KEY_LOCK_TIMEOUT_SECONDS),
+        ));
+        let reconciliation_service = Arc::new(ReconciliationService::new(
+            Arc::new(ReconciliationRepository::new(db_pool.clone())),
+            notification_service.clone(),
+        ));
+        let accounting_export_service = Arc::new(AccountingExportService::new(
+            Arc::new(AccountingExportRepository::new(db_pool.clone())),
+            transaction_repo.clone(),
+            Arc::new(OrderRepository::new(db_pool.clone())),
+            order_item_repo.clone(),
+            Arc::new(PaymentInvoiceRepository::new(db_pool.clone())),
+            stock_movement_repo.clone(),
+            Duration::hours(config.accounting_export_ttl_hours),
+            config.accounting_export_sync_row_limit,
+            config.accounting_export_path.clone().into(),
+        ));
+        let redis_pool = match (&config.redis_host, config.redis_port) {
+            (Some(host), Some(port)) => {
+                deadpool_redis::Config::from_url(format!("redis://{host}:{port}"))
+                    .create_pool(Some(deadpool_redis::Runtime::Tokio1))
+                    .inspect_err(|e| tracing::error!("Failed to create Redis pool: {e}"))
+                    .ok()
+            }
+            _ => None,
+        };
+        let health_service = Arc::new(HealthService::new(
+            Arc::new(WorkerHeartbeatRepository::new(db_pool.clone())),
+            client.clone(),
+            redis_pool,
+            config.captcha_api_url.clone(),
+            config.platform_payment_system_base_url.clone(),
+            std::time::Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECONDS),
+            std::time::Duration::from_secs(READINESS_CACHE_SECONDS),
+        ));
 
         Self {
             db,
             config,
             auth_service,
+            admin_api_key_service,
             category_service,
             admin_user_service,
             role_service,
@@ -357,18 +591,27 @@ impl AppState {
             payment_invoice_service,
             notification_service,
             payment_processing_service,
+            payment_invoice_resolution_service,
             order_item_service,
             purchase_service,
             user_subscription_service,
             broadcast_service,
-            #[cfg(feature = "contms-provider")]
-            contms_products_provider,
+            customer_segment_service,
+            external_product_providers,
+            product_sync_service,
             #[cfg(feature = "mock-payments-provider")]
             mock_payments_provider,
             platform_payments_provider,
             analytics_service,
             dashboard_service,
             store_balance_request_service,
+            idempotency_service,
+            reconciliation_service,
+            accounting_export_service,
+            health_service,
+            gateway_health_service,
+            payment_gateway_settings_service,
+            currency_service,
         }
     }
 }
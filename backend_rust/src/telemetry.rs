@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex, OnceLock},
+    time::Instant,
+};
+
+use axum::{
+    Router,
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{
+    Unit, counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::services::health::HealthServiceTrait;
+
+/// Latency buckets shared by every `*_seconds` histogram, from fast DB-bound handlers
+/// up to minute-long worker runs
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+/// Errors per worker since start, lets a run tell whether it logged any
+static WORKER_ERRORS: LazyLock<Mutex<HashMap<&'static str, u64>>> = LazyLock::new(Default::default);
+
+/// Installs the process-wide Prometheus recorder. Metrics recorded before this call, or in
+/// tests that never call it, are dropped.
+pub fn install() {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), DURATION_BUCKETS)
+        .expect("duration buckets are not empty")
+        .install_recorder()
+        .expect("failed to install Prometheus recorder");
+
+    describe_histogram!(
+        "http_request_duration_seconds",
+        Unit::Seconds,
+        "HTTP request latency by method, route and status"
+    );
+    describe_histogram!(
+        "worker_run_duration_seconds",
+        Unit::Seconds,
+        "Duration of a single worker loop iteration"
+    );
+    describe_counter!("worker_errors_total", "Errors logged by background workers");
+    describe_histogram!(
+        "gateway_request_duration_seconds",
+        Unit::Seconds,
+        "Latency of calls to payment gateways and product providers"
+    );
+    describe_gauge!(
+        "payment_invoices_pending",
+        "Payment invoices waiting for the gateway, refreshed on scrape"
+    );
+
+    let _ = PROMETHEUS.set(handle);
+}
+
+/// Renders the Prometheus text exposition, empty when the recorder isn't installed
+pub fn render() -> String {
+    match PROMETHEUS.get() {
+        Some(handle) => {
+            handle.run_upkeep();
+            handle.render()
+        }
+        None => String::new(),
+    }
+}
+
+/// Serves `router` on the internal metrics port, the public listener doesn't expose it
+pub async fn serve_metrics(port: u16, router: Router) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    axum::serve(listener, router).await
+}
+
+pub fn set_pending_invoices(count: i64) {
+    gauge!("payment_invoices_pending").set(count as f64);
+}
+
+/// Records request latency labelled with the route template rather than the raw path,
+/// so ids in paths don't blow up label cardinality
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let started_at = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    histogram!(
+        "http_request_duration_seconds",
+        "method" => method,
+        "route" => route,
+        "status" => response.status().as_u16().to_string(),
+    )
+    .record(started_at.elapsed().as_secs_f64());
+
+    response
+}
+
+/// Times one worker loop iteration. When dropped it records the duration and stores a
+/// heartbeat, successful if no `worker_error` was reported during the run.
+pub struct WorkerRun {
+    worker: &'static str,
+    started_at: Instant,
+    errors_at_start: u64,
+    health_service: Arc<dyn HealthServiceTrait>,
+}
+
+impl WorkerRun {
+    pub fn start(worker: &'static str, health_service: Arc<dyn HealthServiceTrait>) -> Self {
+        Self {
+            worker,
+            started_at: Instant::now(),
+            errors_at_start: worker_errors(worker),
+            health_service,
+        }
+    }
+}
+
+impl Drop for WorkerRun {
+    fn drop(&mut self) {
+        histogram!("worker_run_duration_seconds", "worker" => self.worker)
+            .record(self.started_at.elapsed().as_secs_f64());
+
+        let worker = self.worker;
+        let errors = (worker_errors(worker) - self.errors_at_start) as i32;
+        let health_service = self.health_service.clone();
+        tokio::spawn(async move {
+            if let Err(e) = health_service.record_worker_run(worker, errors).await {
+                tracing::error!("Failed to record {worker} worker heartbeat: {e}");
+            }
+        });
+    }
+}
+
+pub fn worker_error(worker: &'static str) {
+    counter!("worker_errors_total", "worker" => worker).increment(1);
+    *WORKER_ERRORS.lock().unwrap().entry(worker).or_default() += 1;
+}
+
+fn worker_errors(worker: &'static str) -> u64 {
+    WORKER_ERRORS
+        .lock()
+        .unwrap()
+        .get(worker)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn record_gateway_request(
+    gateway: &'static str,
+    endpoint: &str,
+    started_at: Instant,
+    ok: bool,
+) {
+    histogram!(
+        "gateway_request_duration_seconds",
+        "gateway" => gateway,
+        "endpoint" => endpoint.to_string(),
+        "outcome" => if ok { "ok" } else { "error" },
+    )
+    .record(started_at.elapsed().as_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_exposes_recorded_metrics() {
+        install();
+        set_pending_invoices(3);
+
+        let output = render();
+
+        assert!(output.contains("# HELP payment_invoices_pending"));
+        assert!(output.contains("payment_invoices_pending 3"));
+    }
+}
@@ -1,4 +1,9 @@
+pub mod accounting_exports;
 pub mod broadcasts;
-pub mod contms_products_sync;
+pub mod exchange_rates;
+pub mod external_products_sync;
+pub mod idempotency_keys_cleanup;
+pub mod leader;
 pub mod pending_payments;
+pub mod reconciliation;
 pub mod subscription_expiry_notifications;
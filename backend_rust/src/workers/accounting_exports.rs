@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use tokio::time::{Duration, interval};
+
+use crate::{
+    services::accounting_export::AccountingExportServiceTrait, state::AppState, telemetry,
+};
+
+pub async fn accounting_exports_task(app_state: Arc<AppState>) {
+    tracing::info!("[Accounting exports task] Starting");
+    let mut interval = interval(Duration::from_secs(10));
+
+    loop {
+        interval.tick().await;
+        let _run =
+            telemetry::WorkerRun::start("accounting_exports", app_state.health_service.clone());
+
+        // Drain the queue before waiting for the next tick
+        loop {
+            match app_state.accounting_export_service.process_next().await {
+                Ok(Some(export)) => tracing::info!(
+                    "[Accounting exports task] Export {} finished with status {:?}",
+                    export.id,
+                    export.status
+                ),
+                Ok(None) => break,
+                Err(e) => {
+                    telemetry::worker_error("accounting_exports");
+                    tracing::error!("[Accounting exports task] Error: {e}");
+                    break;
+                }
+            }
+        }
+
+        match app_state.accounting_export_service.delete_expired().await {
+            Ok(0) => {}
+            Ok(deleted) => {
+                tracing::info!("[Accounting exports task] Deleted {deleted} expired exports")
+            }
+            Err(e) => {
+                telemetry::worker_error("accounting_exports");
+                tracing::error!("[Accounting exports task] Error: {e}");
+            }
+        }
+    }
+}
@@ -1,179 +1,625 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use chrono::Utc;
-use serde_json::json;
 use shared_dtos::{
-    broadcast::{BroadcastStatus, JsonRawListQuery},
+    broadcast::{BroadcastContent, BroadcastRecipientStatus, BroadcastStatus, JsonRawListQuery},
     notification::{DispatchMessage, DispatchMessagePayload},
 };
 use tokio::time::{Duration, interval};
 
 use crate::{
-    models::customer::CustomerListQuery,
+    models::{broadcast::BroadcastRow, customer::CustomerListQuery},
     services::{
         broadcast::{BroadcastServiceTrait, UpdateBroadcastCommand},
-        customer::CustomerServiceTrait,
         notification_service::NotificationServiceTrait,
     },
     state::AppState,
+    telemetry,
 };
 
+/// Recipients the bot hasn't reported on after this long count as failed
+const DELIVERY_REPORT_TIMEOUT: chrono::Duration = chrono::Duration::minutes(10);
+
 pub async fn broadcasts_task(app_state: Arc<AppState>) {
     tracing::info!("[Broadcasts task] Starting...");
-    let mut interval = interval(Duration::from_mins(1));
+    // Each tick delivers at most one batch per broadcast, see `deliver_broadcast`
+    let mut interval = interval(Duration::from_secs(10));
+    let batch_size = app_state.config.broadcast_batch_size;
+    let send_interval = Duration::from_millis(app_state.config.broadcast_send_interval_ms);
 
     loop {
         interval.tick().await;
+        let _run = telemetry::WorkerRun::start("broadcasts", app_state.health_service.clone());
         tracing::info!("[Broadcasts task] Running...");
-        let ready_broadcasts = match app_state.broadcast_service.get_ready_broadcasts().await {
-            Ok(broadcasts) => broadcasts,
-            Err(e) => {
-                tracing::error!("[Broadcasts task] Error getting ready broadcasts: {}", e);
-                continue;
-            }
-        };
+        start_ready_broadcasts(app_state.broadcast_service.as_ref()).await;
+        run_in_progress_broadcasts(
+            app_state.broadcast_service.as_ref(),
+            app_state.notification_service.as_ref(),
+            batch_size,
+            send_interval,
+        )
+        .await;
+    }
+}
 
-        for broadcast in ready_broadcasts {
-            let json_val = broadcast.filters.unwrap_or_default();
-            let raw_query: JsonRawListQuery = match serde_json::from_value(json_val) {
-                Ok(q) => q,
-                Err(e) => {
-                    tracing::error!("[Broadcasts task] Error parsing raw broadcast filters: {e}");
-                    mark_broadcast_as_failed(broadcast.id, &app_state).await;
-                    continue;
-                }
-            };
-
-            let mut list_query = match CustomerListQuery::try_from_json(raw_query) {
-                Ok(q) => q,
-                Err(e) => {
-                    tracing::error!("[Broadcasts task] Error parsing broadcast filters: {e}");
-                    mark_broadcast_as_failed(broadcast.id, &app_state).await;
-                    continue;
-                }
-            };
-
-            list_query.pagination.page = 1;
-            list_query.pagination.page_size = 100000; // TODO: Make this configurable
-            let customers = match app_state.customer_service.get_list(list_query).await {
-                Ok(customers) => customers,
-                Err(e) => {
-                    tracing::error!("[Broadcasts task] Error getting broadcast customers: {e}");
-                    mark_broadcast_as_failed(broadcast.id, &app_state).await;
-                    continue;
+// Materializes recipients of due broadcasts; delivery happens in `run_in_progress_broadcasts`
+async fn start_ready_broadcasts(broadcast_service: &dyn BroadcastServiceTrait) {
+    let ready_broadcasts = match broadcast_service.get_ready_broadcasts().await {
+        Ok(broadcasts) => broadcasts,
+        Err(e) => {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error getting ready broadcasts: {}", e);
+            return;
+        }
+    };
+
+    for broadcast in ready_broadcasts {
+        // No filters means the broadcast goes to every customer
+        let audience = match broadcast.filters {
+            None | Some(serde_json::Value::Null) => CustomerListQuery::default(),
+            Some(json_val) => {
+                let raw_query: JsonRawListQuery = match serde_json::from_value(json_val) {
+                    Ok(q) => q,
+                    Err(e) => {
+                        telemetry::worker_error("broadcasts");
+                        tracing::error!(
+                            "[Broadcasts task] Error parsing raw broadcast filters: {e}"
+                        );
+                        mark_broadcast_as_failed(broadcast.id, broadcast_service).await;
+                        continue;
+                    }
+                };
+
+                match CustomerListQuery::try_from_json(raw_query) {
+                    Ok(q) => q,
+                    Err(e) => {
+                        telemetry::worker_error("broadcasts");
+                        tracing::error!("[Broadcasts task] Error parsing broadcast filters: {e}");
+                        mark_broadcast_as_failed(broadcast.id, broadcast_service).await;
+                        continue;
+                    }
                 }
-            };
+            }
+        };
 
-            if customers.items.is_empty() {
-                mark_broadcast_as_failed(broadcast.id, &app_state).await;
+        match broadcast_service.start(broadcast.id, &audience).await {
+            Ok(Some((_, 0))) => {
+                telemetry::worker_error("broadcasts");
                 tracing::error!("[Broadcasts task] No customers found for broadcast");
-                continue;
+                mark_broadcast_as_failed(broadcast.id, broadcast_service).await;
+            }
+            Ok(Some((_, recipients))) => {
+                tracing::info!(
+                    "[Broadcasts task] Broadcast {} started for {} customers",
+                    broadcast.id,
+                    recipients
+                );
             }
+            // Paused or cancelled in the meantime
+            Ok(None) => {}
+            Err(e) => {
+                telemetry::worker_error("broadcasts");
+                tracing::error!("[Broadcasts task] Error starting broadcast: {e}");
+            }
+        }
+    }
+}
 
-            if let Err(e) = app_state
-                .broadcast_service
-                .update(UpdateBroadcastCommand {
-                    id: broadcast.id,
-                    status: Some(BroadcastStatus::InProgress),
-                    updated_by: Some(1), // System
-                    started_at: Some(Some(Utc::now())),
-                    content_image_id: None,
-                    content_text: None,
-                    ctx: None,
-                    filters: None,
-                    scheduled_for: None,
-                    finished_at: None,
-                    statistics: None,
-                })
+// Also picks up broadcasts interrupted by a restart, already handled recipients are skipped
+async fn run_in_progress_broadcasts(
+    broadcast_service: &dyn BroadcastServiceTrait,
+    notification_service: &dyn NotificationServiceTrait,
+    batch_size: i64,
+    send_interval: Duration,
+) {
+    let broadcasts = match broadcast_service.get_in_progress_broadcasts().await {
+        Ok(broadcasts) => broadcasts,
+        Err(e) => {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error getting in progress broadcasts: {e}");
+            return;
+        }
+    };
+
+    for broadcast in broadcasts {
+        deliver_broadcast(
+            broadcast_service,
+            notification_service,
+            &broadcast,
+            batch_size,
+            send_interval,
+        )
+        .await;
+    }
+}
+
+async fn deliver_broadcast(
+    broadcast_service: &dyn BroadcastServiceTrait,
+    notification_service: &dyn NotificationServiceTrait,
+    broadcast: &BroadcastRow,
+    batch_size: i64,
+    send_interval: Duration,
+) {
+    let variants = match broadcast_service.get_variants(broadcast.id).await {
+        Ok(variants) => variants,
+        Err(e) => {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error getting broadcast variants: {e}");
+            return;
+        }
+    };
+    // Recipients without a variant, or whose variant was deleted, get the broadcast's own content
+    let base_content = broadcast.content();
+    let contents: HashMap<i64, BroadcastContent> = variants
+        .iter()
+        .map(|variant| (variant.id, variant.content()))
+        .collect();
+
+    // Pause and cancel take effect between batches
+    match broadcast_service.get_by_id(broadcast.id).await {
+        Ok(current) if current.status == BroadcastStatus::InProgress => {}
+        Ok(_) => return,
+        Err(e) => {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error getting broadcast: {e}");
+            return;
+        }
+    }
+
+    let recipients = match broadcast_service
+        .get_pending_recipients(broadcast.id, batch_size)
+        .await
+    {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error getting broadcast recipients: {e}");
+            return;
+        }
+    };
+
+    if recipients.is_empty() {
+        finish_broadcast(broadcast.id, broadcast_service).await;
+        return;
+    }
+
+    for recipient in recipients {
+        // Marked before sending so a crash or failover in between never resends: such a
+        // recipient isn't pending anymore and fails once the delivery report times out
+        if let Err(e) = broadcast_service
+            .update_recipient_status(recipient.id, BroadcastRecipientStatus::Dispatched, None)
+            .await
+        {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error updating broadcast recipient: {e}");
+            return;
+        }
+
+        let content = recipient
+            .variant_id
+            .and_then(|variant_id| contents.get(&variant_id))
+            .unwrap_or(&base_content);
+        // The bot reports the Telegram answer later, see `report_delivery`
+        if let Err(e) = notification_service
+            .dispatch_message(DispatchMessagePayload {
+                // TODO Last seen with bot may be old if we created new bot
+                bot_id: recipient.last_seen_with_bot,
+                telegram_id: recipient.telegram_id,
+                message: DispatchMessage::BroadcastRecipientMessage {
+                    recipient_id: recipient.id,
+                    content: content.clone(),
+                },
+            })
+            .await
+        {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error sending broadcast message: {e}");
+            if let Err(e) = broadcast_service
+                .update_recipient_status(
+                    recipient.id,
+                    BroadcastRecipientStatus::Failed,
+                    Some(e.to_string()),
+                )
                 .await
             {
-                tracing::error!("[Broadcasts task] Error updating broadcast status: {e}");
-                continue;
+                telemetry::worker_error("broadcasts");
+                tracing::error!("[Broadcasts task] Error updating broadcast recipient: {e}");
             }
+        }
 
-            let mut sent = 0;
-            let mut failed = 0;
-
-            tracing::info!(
-                "[Broadcasts task] Sending broadcast to {} customers",
-                customers.items.len()
-            );
-
-            for customer in customers.items {
-                match app_state
-                    .notification_service
-                    .dispatch_message(DispatchMessagePayload {
-                        // TODO Last seen with bot may be old if we created new bot
-                        bot_id: customer.last_seen_with_bot,
-                        telegram_id: customer.telegram_id,
-                        message: DispatchMessage::GenericMessage {
-                            message: broadcast.content_text.clone().unwrap_or_default(),
-                            image_id: broadcast.content_image_id,
-                        },
-                    })
-                    .await
-                {
-                    Ok(_) => sent += 1,
-                    Err(e) => {
-                        tracing::error!("[Broadcasts task] Error sending broadcast message: {e}");
-                        failed += 1;
-                    }
-                }
-                // Because of the telegram rate limit https://core.telegram.org/bots/faq#my-bot-is-hitting-limits-how-do-i-avoid-this
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
+        tokio::time::sleep(send_interval).await;
+    }
+}
 
-            tracing::info!("[Broadcasts task] Broadcast sent to {} customers", sent);
-            tracing::info!(
-                "[Broadcasts task] Broadcast failed to send to {} customers",
-                failed
-            );
-
-            if let Err(e) = app_state
-                .broadcast_service
-                .update(UpdateBroadcastCommand {
-                    id: broadcast.id,
-                    status: Some(BroadcastStatus::Completed),
-                    updated_by: Some(1), // System
-                    finished_at: Some(Some(Utc::now())),
-                    statistics: Some(Some(json!({
-                        "sent": sent,
-                        "failed": failed,
-                    }))),
-                    content_image_id: None,
-                    content_text: None,
-                    ctx: None,
-                    filters: None,
-                    scheduled_for: None,
-                    started_at: None,
-                })
-                .await
-            {
-                tracing::error!("[Broadcasts task] Error updating broadcast status: {e}");
-                continue;
-            };
+// Completes the broadcast once the bot has reported on every dispatched recipient
+async fn finish_broadcast(broadcast_id: i64, broadcast_service: &dyn BroadcastServiceTrait) {
+    let dispatched_before = Utc::now() - DELIVERY_REPORT_TIMEOUT;
+    if let Err(e) = broadcast_service
+        .fail_undelivered_recipients(broadcast_id, dispatched_before)
+        .await
+    {
+        telemetry::worker_error("broadcasts");
+        tracing::error!("[Broadcasts task] Error failing undelivered recipients: {e}");
+        return;
+    }
+
+    let statistics = match broadcast_service.get_recipient_stats(broadcast_id).await {
+        Ok(statistics) => statistics,
+        Err(e) => {
+            telemetry::worker_error("broadcasts");
+            tracing::error!("[Broadcasts task] Error getting broadcast statistics: {e}");
+            return;
         }
+    };
+    if statistics.dispatched > 0 {
+        return;
     }
+
+    tracing::info!(
+        "[Broadcasts task] Broadcast {} finished: {} sent, {} blocked, {} failed",
+        broadcast_id,
+        statistics.sent,
+        statistics.blocked,
+        statistics.failed
+    );
+
+    if let Err(e) = broadcast_service
+        .update(UpdateBroadcastCommand {
+            id: broadcast_id,
+            status: Some(BroadcastStatus::Completed),
+            updated_by: Some(1), // System
+            finished_at: Some(Some(Utc::now())),
+            statistics: Some(serde_json::to_value(statistics).ok()),
+            // Paused or cancelled by an admin in the meantime
+            expected_status: Some(vec![BroadcastStatus::InProgress]),
+            content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
+            content_text: None,
+            ctx: None,
+            filters: None,
+            scheduled_for: None,
+            started_at: None,
+        })
+        .await
+    {
+        telemetry::worker_error("broadcasts");
+        tracing::error!("[Broadcasts task] Error updating broadcast status: {e}");
+    };
 }
 
-pub async fn mark_broadcast_as_failed(broadcast_id: i64, app_state: &Arc<AppState>) {
-    if let Err(e) = app_state
-        .broadcast_service
+pub async fn mark_broadcast_as_failed(
+    broadcast_id: i64,
+    broadcast_service: &dyn BroadcastServiceTrait,
+) {
+    if let Err(e) = broadcast_service
         .update(UpdateBroadcastCommand {
             id: broadcast_id,
             status: Some(BroadcastStatus::Failed),
             updated_by: Some(1), // System
             finished_at: Some(Some(Utc::now())),
             content_image_id: None,
+            content_entities: None,
+            content_media: None,
+            content_buttons: None,
             content_text: None,
             ctx: None,
             filters: None,
             scheduled_for: None,
             started_at: None,
             statistics: None,
+            expected_status: Some(vec![
+                BroadcastStatus::Pending,
+                BroadcastStatus::Scheduled,
+                BroadcastStatus::InProgress,
+            ]),
         })
         .await
     {
+        telemetry::worker_error("broadcasts");
         tracing::error!("[Broadcasts task] Error marking broadcast as failed: {e}");
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use shared_dtos::notification::{DispatchAdminMessage, DispatchMessagePayload};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        errors::api::{ApiError, ApiResult},
+        infrastructure::repositories::{
+            audit_log::AuditLogRepository, broadcast::BroadcastRepository,
+        },
+        models::broadcast::BroadcastRecipientListQuery,
+        services::{
+            audit_log::AuditLogService,
+            broadcast::{BroadcastService, CreateBroadcastCommand},
+        },
+    };
+
+    #[derive(Default)]
+    struct MockNotificationService {
+        sent_to: Mutex<Vec<i64>>,
+        recipient_ids: Mutex<Vec<(i64, i64)>>,
+        fail_for_telegram_ids: Vec<i64>,
+        // Records the recipient's stored status at the moment the message goes out
+        pool: Option<PgPool>,
+        statuses_at_dispatch: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl NotificationServiceTrait for MockNotificationService {
+        async fn dispatch_message(&self, payload: DispatchMessagePayload) -> ApiResult<()> {
+            self.sent_to.lock().unwrap().push(payload.telegram_id);
+            if let DispatchMessage::BroadcastRecipientMessage { recipient_id, .. } = payload.message
+            {
+                self.recipient_ids
+                    .lock()
+                    .unwrap()
+                    .push((payload.telegram_id, recipient_id));
+                if let Some(pool) = &self.pool {
+                    let status = sqlx::query_scalar!(
+                        "SELECT status FROM broadcast_recipients WHERE id = $1",
+                        recipient_id
+                    )
+                    .fetch_one(pool)
+                    .await
+                    .unwrap();
+                    self.statuses_at_dispatch.lock().unwrap().push(status);
+                }
+            }
+            if self.fail_for_telegram_ids.contains(&payload.telegram_id) {
+                return Err(ApiError::InternalServerError(
+                    "simulated dispatch error".to_string(),
+                ));
+            }
+            Ok(())
+        }
+
+        async fn dispatch_admin_message(&self, _payload: DispatchAdminMessage) -> ApiResult<()> {
+            Ok(())
+        }
+    }
+
+    fn build_service(
+        pool: &PgPool,
+    ) -> BroadcastService<BroadcastRepository, AuditLogService<AuditLogRepository>> {
+        let pool = Arc::new(pool.clone());
+        let audit_log_service = Arc::new(AuditLogService::new(Arc::new(AuditLogRepository::new(
+            pool.clone(),
+        ))));
+        BroadcastService::new(Arc::new(BroadcastRepository::new(pool)), audit_log_service)
+    }
+
+    async fn create_customer(pool: &PgPool, telegram_id: i64) -> i64 {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO customers (telegram_id, registered_with_bot, last_seen_with_bot)
+            VALUES ($1, 1, 1)
+            RETURNING id
+            "#,
+            telegram_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn create_broadcast(service: &dyn BroadcastServiceTrait) -> i64 {
+        service
+            .create(CreateBroadcastCommand {
+                content_text: Some("Hello".to_string()),
+                content_image_id: None,
+                content_entities: vec![],
+                content_media: vec![],
+                content_buttons: vec![],
+                filters: None,
+                scheduled_for: None,
+                created_by: 1,
+                ctx: None,
+                segment_id: None,
+                variants: vec![],
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    #[sqlx::test]
+    async fn test_broadcast_delivery_tracks_each_recipient(pool: PgPool) {
+        let service = build_service(&pool);
+        let notification = MockNotificationService {
+            fail_for_telegram_ids: vec![7003],
+            ..Default::default()
+        };
+        create_customer(&pool, 7001).await;
+        create_customer(&pool, 7002).await;
+        create_customer(&pool, 7003).await;
+        let broadcast_id = create_broadcast(&service).await;
+
+        start_ready_broadcasts(&service).await;
+        // One batch per tick
+        run_in_progress_broadcasts(&service, &notification, 2, Duration::ZERO).await;
+        assert_eq!(notification.sent_to.lock().unwrap().len(), 2);
+        let stats = service.get_recipient_stats(broadcast_id).await.unwrap();
+        assert_eq!(stats.pending, 1);
+        run_in_progress_broadcasts(&service, &notification, 2, Duration::ZERO).await;
+
+        let mut sent_to = notification.sent_to.lock().unwrap().clone();
+        sent_to.sort();
+        assert_eq!(sent_to, vec![7001, 7002, 7003]);
+        // Waits for the bot to report on the dispatched recipients
+        let broadcast = service.get_by_id(broadcast_id).await.unwrap();
+        assert_eq!(broadcast.status, BroadcastStatus::InProgress);
+
+        let recipient_ids = notification.recipient_ids.lock().unwrap().clone();
+        let recipient_of = |telegram_id: i64| {
+            recipient_ids
+                .iter()
+                .find(|(t, _)| *t == telegram_id)
+                .map(|(_, id)| *id)
+                .unwrap()
+        };
+        service
+            .report_delivery(recipient_of(7001), BroadcastRecipientStatus::Sent, None)
+            .await
+            .unwrap();
+        service
+            .report_delivery(
+                recipient_of(7002),
+                BroadcastRecipientStatus::Blocked,
+                Some("Forbidden: bot was blocked by the user".to_string()),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .report_delivery(recipient_of(7003), BroadcastRecipientStatus::Sent, None)
+                .await,
+            Err(ApiError::Conflict(_))
+        ));
+        run_in_progress_broadcasts(&service, &notification, 2, Duration::ZERO).await;
+
+        let broadcast = service.get_by_id(broadcast_id).await.unwrap();
+        assert_eq!(broadcast.status, BroadcastStatus::Completed);
+        assert!(broadcast.finished_at.is_some());
+        assert_eq!(notification.sent_to.lock().unwrap().len(), 3);
+
+        let recipients = service
+            .get_recipients(broadcast_id, BroadcastRecipientListQuery::default())
+            .await
+            .unwrap();
+        let status_of = |telegram_id: i64| {
+            recipients
+                .items
+                .iter()
+                .find(|r| r.telegram_id == telegram_id)
+                .map(|r| r.status)
+                .unwrap()
+        };
+        assert_eq!(status_of(7001), BroadcastRecipientStatus::Sent);
+        assert_eq!(status_of(7002), BroadcastRecipientStatus::Blocked);
+        assert_eq!(status_of(7003), BroadcastRecipientStatus::Failed);
+
+        let stats = service.get_recipient_stats(broadcast_id).await.unwrap();
+        assert_eq!(broadcast.statistics, serde_json::to_value(stats).ok());
+    }
+
+    #[sqlx::test]
+    async fn test_interrupted_broadcast_resumes_without_resending(pool: PgPool) {
+        let service = build_service(&pool);
+        let notification = MockNotificationService::default();
+        for telegram_id in 8001..8005 {
+            create_customer(&pool, telegram_id).await;
+        }
+        let broadcast_id = create_broadcast(&service).await;
+        start_ready_broadcasts(&service).await;
+
+        // Simulate a run that was interrupted after the first two recipients
+        let delivered = service
+            .get_pending_recipients(broadcast_id, 2)
+            .await
+            .unwrap();
+        for recipient in &delivered {
+            service
+                .update_recipient_status(recipient.id, BroadcastRecipientStatus::Sent, None)
+                .await
+                .unwrap();
+        }
+
+        run_in_progress_broadcasts(&service, &notification, 10, Duration::ZERO).await;
+
+        let sent_to = notification.sent_to.lock().unwrap().clone();
+        assert_eq!(sent_to.len(), 2);
+        assert!(
+            delivered
+                .iter()
+                .all(|recipient| !sent_to.contains(&recipient.telegram_id))
+        );
+        let stats = service.get_recipient_stats(broadcast_id).await.unwrap();
+        assert_eq!(stats.sent, 2);
+        assert_eq!(stats.dispatched, 2);
+        assert_eq!(stats.pending, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_unreported_recipients_fail_after_timeout(pool: PgPool) {
+        let service = build_service(&pool);
+        let notification = MockNotificationService::default();
+        create_customer(&pool, 8101).await;
+        let broadcast_id = create_broadcast(&service).await;
+        start_ready_broadcasts(&service).await;
+        run_in_progress_broadcasts(&service, &notification, 10, Duration::ZERO).await;
+
+        // The report never arrives, e.g. the bot restarted with the message still queued
+        sqlx::query!(
+            "ALTER TABLE broadcast_recipients DISABLE TRIGGER set_updated_at_broadcast_recipients"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "UPDATE broadcast_recipients SET updated_at = NOW() - INTERVAL '1 hour' WHERE broadcast_id = $1",
+            broadcast_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        run_in_progress_broadcasts(&service, &notification, 10, Duration::ZERO).await;
+
+        let broadcast = service.get_by_id(broadcast_id).await.unwrap();
+        assert_eq!(broadcast.status, BroadcastStatus::Completed);
+        let stats = service.get_recipient_stats(broadcast_id).await.unwrap();
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.dispatched, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_paused_broadcast_is_not_delivered(pool: PgPool) {
+        let service = build_service(&pool);
+        let notification = MockNotificationService::default();
+        create_customer(&pool, 9001).await;
+        let broadcast_id = create_broadcast(&service).await;
+        start_ready_broadcasts(&service).await;
+
+        service
+            .pause(crate::services::broadcast::BroadcastActionCommand {
+                id: broadcast_id,
+                updated_by: 1,
+                ctx: None,
+            })
+            .await
+            .unwrap();
+        run_in_progress_broadcasts(&service, &notification, 10, Duration::ZERO).await;
+
+        assert!(notification.sent_to.lock().unwrap().is_empty());
+        let stats = service.get_recipient_stats(broadcast_id).await.unwrap();
+        assert_eq!(stats.pending, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_recipient_is_marked_dispatched_before_sending(pool: PgPool) {
+        let service = build_service(&pool);
+        let notification = MockNotificationService {
+            pool: Some(pool.clone()),
+            ..Default::default()
+        };
+        create_customer(&pool, 9101).await;
+        create_customer(&pool, 9102).await;
+        create_broadcast(&service).await;
+        start_ready_broadcasts(&service).await;
+
+        run_in_progress_broadcasts(&service, &notification, 10, Duration::ZERO).await;
+
+        assert_eq!(
+            *notification.statuses_at_dispatch.lock().unwrap(),
+            vec!["dispatched".to_string(), "dispatched".to_string()]
+        );
+    }
+}
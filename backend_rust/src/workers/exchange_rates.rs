@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use tokio::time::{Duration, interval};
+
+use crate::{services::currency::CurrencyServiceTrait, state::AppState, telemetry};
+
+pub async fn exchange_rates_task(app_state: Arc<AppState>) {
+    tracing::info!("[Exchange rates task] Starting");
+    let mut interval = interval(Duration::from_secs(
+        app_state.config.exchange_rates_refresh_interval_seconds,
+    ));
+
+    loop {
+        interval.tick().await;
+        let _run = telemetry::WorkerRun::start("exchange_rates", app_state.health_service.clone());
+        match app_state.currency_service.refresh_rates().await {
+            Ok(updated) => tracing::info!("[Exchange rates task] Updated {updated} rates"),
+            Err(e) => {
+                telemetry::worker_error("exchange_rates");
+                tracing::error!("[Exchange rates task] Error: {e}");
+            }
+        }
+    }
+}
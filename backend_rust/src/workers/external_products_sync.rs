@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use shared_dtos::product_sync::ProductSyncTrigger;
+use tokio::time::{Duration, interval};
+
+use crate::{
+    models::product_sync::ProductSyncRunRow,
+    services::product_sync::{ProductSyncServiceTrait, RunProductSyncCommand},
+    state::AppState,
+    telemetry,
+};
+
+/// How often syncs requested from the admin panel are picked up
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+pub async fn external_products_sync_task(app_state: Arc<AppState>) {
+    tracing::info!("[External products sync task] Starting");
+    let mut schedule = interval(Duration::from_mins(
+        app_state.config.external_products_sync_interval_minutes,
+    ));
+    let mut queue = interval(QUEUE_POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = schedule.tick() => run_scheduled(&app_state).await,
+            _ = queue.tick() => run_queued(&app_state).await,
+        }
+    }
+}
+
+async fn run_scheduled(app_state: &AppState) {
+    let _run =
+        telemetry::WorkerRun::start("external_products_sync", app_state.health_service.clone());
+    tracing::info!("[External products sync task] Running...");
+    match app_state
+        .product_sync_service
+        .run(RunProductSyncCommand {
+            provider_name: None,
+            trigger: ProductSyncTrigger::Schedule,
+            triggered_by: None,
+        })
+        .await
+    {
+        Ok(runs) => {
+            for run in runs {
+                log_run(&run);
+            }
+        }
+        Err(e) => {
+            telemetry::worker_error("external_products_sync");
+            tracing::error!("[External products sync task] Error: {e}");
+        }
+    }
+}
+
+async fn run_queued(app_state: &AppState) {
+    // Drain the queue before waiting for the next tick
+    loop {
+        match app_state.product_sync_service.process_queued().await {
+            Ok(Some(run)) => log_run(&run),
+            Ok(None) => break,
+            Err(e) => {
+                telemetry::worker_error("external_products_sync");
+                tracing::error!("[External products sync task] Error: {e}");
+                break;
+            }
+        }
+    }
+}
+
+fn log_run(run: &ProductSyncRunRow) {
+    tracing::info!(
+        "[External products sync task] {} sync {:?}: {} added, {} updated, {} hidden, {} restored, {} errors",
+        run.provider_name,
+        run.status,
+        run.added,
+        run.updated,
+        run.hidden,
+        run.restored,
+        run.errors.len()
+    );
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use tokio::time::{Duration, interval};
+
+use crate::{
+    services::{gateway_health::GatewayHealthServiceTrait, idempotency::IdempotencyServiceTrait},
+    state::AppState,
+    telemetry,
+};
+
+pub async fn idempotency_keys_cleanup_task(app_state: Arc<AppState>) {
+    tracing::info!("[Idempotency keys cleanup task] Starting");
+    let mut interval = interval(Duration::from_hours(1));
+
+    loop {
+        interval.tick().await;
+        let _run = telemetry::WorkerRun::start(
+            "idempotency_keys_cleanup",
+            app_state.health_service.clone(),
+        );
+        match app_state.idempotency_service.delete_expired().await {
+            Ok(0) => {}
+            Ok(deleted) => {
+                tracing::info!("[Idempotency keys cleanup task] Deleted {deleted} expired keys")
+            }
+            Err(e) => {
+                telemetry::worker_error("idempotency_keys_cleanup");
+                tracing::error!("[Idempotency keys cleanup task] Error: {e}");
+            }
+        }
+        // Gateway attempts piggyback on this hourly loop, they'd grow unbounded otherwise
+        match app_state.gateway_health_service.delete_expired().await {
+            Ok(0) => {}
+            Ok(deleted) => {
+                tracing::info!(
+                    "[Idempotency keys cleanup task] Deleted {deleted} old gateway attempts"
+                )
+            }
+            Err(e) => {
+                telemetry::worker_error("idempotency_keys_cleanup");
+                tracing::error!("[Idempotency keys cleanup task] Gateway attempts error: {e}");
+            }
+        }
+    }
+}
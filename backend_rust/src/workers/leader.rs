@@ -0,0 +1,58 @@
+use std::{future::Future, sync::Arc};
+
+use tokio::time::{Duration, sleep};
+
+use crate::{infrastructure::lib::advisory_lock::AdvisoryLock, state::AppState};
+
+/// Runs `task` only on the replica that holds the `name` lock. Other replicas keep
+/// retrying and take over once the holder goes away.
+pub async fn run_as_leader<F, Fut>(app_state: Arc<AppState>, name: &'static str, task: F)
+where
+    F: Fn(Arc<AppState>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let retry_interval = Duration::from_secs(app_state.config.worker_lock_retry_interval_seconds);
+    let renew_interval = Duration::from_secs(app_state.config.worker_lock_renew_interval_seconds);
+
+    loop {
+        let mut lock = match AdvisoryLock::try_acquire(app_state.db.get_pool(), name).await {
+            Ok(Some(lock)) => lock,
+            Ok(None) => {
+                tracing::debug!("[Worker {name}] Lock is held by another instance");
+                sleep(retry_interval).await;
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("[Worker {name}] Failed to acquire lock: {e}");
+                sleep(retry_interval).await;
+                continue;
+            }
+        };
+
+        tracing::info!("[Worker {name}] Lock acquired, running on this instance");
+        let mut handle = tokio::spawn(task(app_state.clone()));
+
+        loop {
+            tokio::select! {
+                res = &mut handle => {
+                    if let Err(e) = res {
+                        tracing::error!("[Worker {name}] Task stopped: {e}");
+                    }
+                    if let Err(e) = lock.release().await {
+                        tracing::error!("[Worker {name}] Failed to release lock: {e}");
+                    }
+                    break;
+                }
+                _ = sleep(renew_interval) => {
+                    if let Err(e) = lock.renew().await {
+                        tracing::error!("[Worker {name}] Lost lock connection, stopping task: {e}");
+                        handle.abort();
+                        break;
+                    }
+                }
+            }
+        }
+
+        sleep(retry_interval).await;
+    }
+}
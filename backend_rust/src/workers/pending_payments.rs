@@ -1,37 +1,39 @@
 use chrono::Utc;
-use rust_decimal::prelude::ToPrimitive;
-use shared_dtos::{
-    invoice::InvoiceStatus,
-    notification::{DispatchMessage, DispatchMessagePayload},
-};
+use shared_dtos::invoice::InvoiceStatus;
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
-use tokio::time::{Duration, interval};
+use tokio::time::{Duration, Instant, interval};
 
 use crate::{
     infrastructure::external::payment::autosales_platform::{
-        AutosalesPlatformPaymentsProviderTrait, dto::AutosalesPlatformOrderStatusType,
+        AutosalesPlatformPaymentsProviderTrait, dto::is_fake_appeal,
     },
-    models::{customer::CustomerRow, payment_invoice::PaymentInvoiceRow},
+    models::{payment_invoice::PaymentInvoiceRow, payment_invoice_event::NewPaymentInvoiceEvent},
     services::{
-        customer::{CustomerServiceTrait, UpdateCustomerCommand},
-        notification_service::NotificationServiceTrait,
-        payment_invoice::{PaymentInvoiceServiceTrait, UpdatePaymentInvoiceCommand},
-        payment_processing_service::PaymentProcessingServiceTrait,
+        customer::CustomerServiceTrait,
+        payment_invoice::PaymentInvoiceServiceTrait,
+        payment_processing_service::{GatewayStatusUpdate, PaymentProcessingServiceTrait},
     },
     state::AppState,
+    telemetry,
 };
 
-// TODO Need to refactor this task
-
 pub async fn pending_payments_task(app_state: Arc<AppState>) {
     tracing::info!("[Pending payments task]: Starting");
     let mut interval = interval(Duration::from_secs(10));
+    // With webhooks enabled the platform pushes status changes, polling only reconciles missed ones
+    let poll_interval = match app_state.config.platform_payment_system_webhook_secret {
+        Some(_) => Duration::from_secs(app_state.config.payment_status_poll_interval_seconds),
+        None => Duration::ZERO,
+    };
+    let mut last_polled_at: Option<Instant> = None;
 
     loop {
         interval.tick().await;
+        let _run =
+            telemetry::WorkerRun::start("pending_payments", app_state.health_service.clone());
         tracing::info!("[Pending payments task]: Running...");
         match app_state
             .payment_invoice_service
@@ -44,6 +46,7 @@ pub async fn pending_payments_task(app_state: Arc<AppState>) {
                 }
             }
             Err(e) => {
+                telemetry::worker_error("pending_payments");
                 tracing::error!("[Pending payments task]: Failed to expire old payments: {e}")
             }
         };
@@ -56,6 +59,7 @@ pub async fn pending_payments_task(app_state: Arc<AppState>) {
         {
             Ok(res) => res,
             Err(e) => {
+                telemetry::worker_error("pending_payments");
                 tracing::error!("[Pending payments task]: Failed to get pending payments: {e}");
                 continue;
             }
@@ -78,6 +82,7 @@ pub async fn pending_payments_task(app_state: Arc<AppState>) {
                 .map(|c| (c.id, c))
                 .collect::<HashMap<_, _>>(),
             Err(e) => {
+                telemetry::worker_error("pending_payments");
                 tracing::error!("[Pending payments task]: Failed to get customer by ids: {e}");
                 continue;
             }
@@ -88,553 +93,84 @@ pub async fn pending_payments_task(app_state: Arc<AppState>) {
             pending_invoices.len()
         );
 
-        let mut fraud_invoices = Vec::new();
-        let polled_statuses = {
-            let mut statuses = HashMap::with_capacity(pending_invoices.len());
-            for invoice in pending_invoices.iter() {
-                match app_state
-                    .platform_payments_provider
-                    .get_order_status(invoice.gateway_invoice_id.clone())
-                    .await
-                {
-                    Ok(order) => {
-                        statuses.insert(invoice.id, InvoiceStatus::from(order.status));
-                        if let Some(fake_status) = order.appeal_fake_status
-                            && !fake_status.is_empty()
-                            && fake_status != "0"
-                        {
-                            tracing::info!(
-                                "[Pending payments task]: Fraud user detected: invoice_id: {}, status: {}",
-                                invoice.id,
-                                fake_status
-                            );
-                            fraud_invoices.push(invoice.clone());
-                        }
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to get order status: {e}");
-                    }
-                }
-            }
-            statuses
+        let polled_statuses = if last_polled_at.is_none_or(|t| t.elapsed() >= poll_interval) {
+            last_polled_at = Some(Instant::now());
+            poll_statuses(&app_state, &pending_invoices).await
+        } else {
+            HashMap::new()
         };
 
-        tracing::info!(
-            "[Pending payments task]: Polled {} pending payments",
-            polled_statuses.len()
-        );
-
-        notify_completed_payments(&app_state, &pending_invoices, &polled_statuses).await;
-        notify_pending_payments(
-            &app_state,
-            &pending_invoices,
-            &polled_statuses,
-            &customers_by_id,
-        )
-        .await;
-        request_receipts(
-            &app_state,
-            &pending_invoices,
-            &polled_statuses,
-            &customers_by_id,
-        )
-        .await;
-        notify_request_receipts(
-            &app_state,
-            &pending_invoices,
-            &polled_statuses,
-            &customers_by_id,
-        )
-        .await;
-        notify_contact_with_support(
-            &app_state,
-            &pending_invoices,
-            &polled_statuses,
-            &customers_by_id,
-        )
-        .await;
-        notify_dispute_failed(
-            &app_state,
-            &pending_invoices,
-            &polled_statuses,
-            &customers_by_id,
-        )
-        .await;
-        block_fraud(&app_state, &fraud_invoices).await;
-        update_invoices(&app_state, &pending_invoices, &polled_statuses).await;
-
-        tracing::info!("[Pending payments task]: Finished");
-    }
-}
-
-async fn notify_completed_payments(
-    app_state: &Arc<AppState>,
-    pending_invoices: &[PaymentInvoiceRow],
-    polled_statuses: &HashMap<i64, InvoiceStatus>,
-) {
-    let completed_invoices = pending_invoices
-        .iter()
-        .filter(|i| {
-            polled_statuses
-                .get(&i.id)
-                .unwrap_or(&InvoiceStatus::Pending)
-                == &InvoiceStatus::Completed
-        })
-        .collect::<Vec<_>>();
-
-    let mut notifications_sent = Vec::with_capacity(completed_invoices.len());
-    for invoice in completed_invoices {
-        match app_state
-            .payment_processing_service
-            .handle_payment_success(invoice.order_id)
-            .await
-        {
-            Ok(_) => {
-                notifications_sent.push(invoice.id);
-            }
-            Err(e) => {
-                tracing::error!("[Pending payments task]: Failed to handle payment success: {e}");
-            }
-        }
-    }
-    if !notifications_sent.is_empty() {
-        tracing::info!(
-            "[Pending payments task]: Sent {} payment success notifications",
-            notifications_sent.len()
-        );
-    }
-}
-
-async fn notify_pending_payments(
-    app_state: &Arc<AppState>,
-    pending_invoices: &[PaymentInvoiceRow],
-    polled_statuses: &HashMap<i64, InvoiceStatus>,
-    customers_by_id: &HashMap<i64, CustomerRow>,
-) {
-    let invoices_to_notify_about_troubles = pending_invoices
-        .iter()
-        .filter(|i| {
-            let last_notification_sent_at = match i.notification_sent_at {
-                None => i.created_at,
-                Some(notification_sent_at) => notification_sent_at,
+        for invoice in &pending_invoices {
+            let Some(customer) = customers_by_id.get(&invoice.customer_id) else {
+                continue;
             };
-            // TODO Should be configurable. Not just 5 mins
-            let is_notification_required =
-                last_notification_sent_at + Duration::from_mins(5) < Utc::now();
-            let is_pending_expired = i.created_at + Duration::from_mins(30) < Utc::now();
-            is_notification_required
-                && !is_pending_expired
-                && i.status == InvoiceStatus::Pending
-                && polled_statuses
-                    .get(&i.id)
-                    .unwrap_or(&InvoiceStatus::Pending)
-                    == &InvoiceStatus::Pending
-        })
-        .collect::<Vec<_>>();
-
-    let mut notifications_sent = Vec::with_capacity(invoices_to_notify_about_troubles.len());
-    for invoice in invoices_to_notify_about_troubles {
-        let customer = match customers_by_id.get(&invoice.customer_id) {
-            Some(c) => c,
-            None => continue,
-        };
-        match app_state
-            .notification_service
-            .dispatch_message(DispatchMessagePayload {
-                message: DispatchMessage::InvoiceTroublesNotification {
-                    invoice_id: invoice.id,
-                    amount: invoice.original_amount.to_f64().unwrap_or_default(),
-                    expired_at: invoice.created_at + Duration::from_mins(30), // TODO should be configurable.
-                },
-                telegram_id: customer.telegram_id,
-                bot_id: customer.last_seen_with_bot,
-            })
-            .await
-        {
-            Ok(_) => notifications_sent.push(invoice.id),
-            Err(e) => {
-                tracing::error!(
-                    "[Pending payments task]: Failed to send payment notification: {e}"
-                );
-            }
-        }
-    }
-    if !notifications_sent.is_empty() {
-        tracing::info!(
-            "[Pending payments task]: Sent {} payment notifications",
-            notifications_sent.len()
-        );
-    }
-    match app_state
-        .payment_invoice_service
-        .mark_invoices_notified(&notifications_sent)
-        .await
-    {
-        Ok(res) => {
-            if res > 0 {
-                tracing::info!("[Pending payments task]: Marked {} payments as sent", res)
-            }
-        }
-        Err(e) => {
-            tracing::error!("[Pending payments task]: Failed to mark payments as sent: {e}")
-        }
-    }
-}
-
-async fn request_receipts(
-    app_state: &Arc<AppState>,
-    pending_invoices: &[PaymentInvoiceRow],
-    polled_statuses: &HashMap<i64, InvoiceStatus>,
-    customers_by_id: &HashMap<i64, CustomerRow>,
-) {
-    let to_request_receipt = pending_invoices
-        .iter()
-        .filter(|i| {
-            i.status != InvoiceStatus::AwaitingReceipt
-                && i.status != InvoiceStatus::ReceiptSubmitted
-                && polled_statuses
-                    .get(&i.id)
-                    .unwrap_or(&InvoiceStatus::Pending)
-                    == &InvoiceStatus::AwaitingReceipt
-        })
-        .collect::<Vec<_>>();
-    let mut notifications_sent = Vec::with_capacity(to_request_receipt.len());
-    for invoice in to_request_receipt {
-        let customer = match customers_by_id.get(&invoice.customer_id) {
-            Some(c) => c,
-            None => continue,
-        };
-        match app_state
-            .notification_service
-            .dispatch_message(DispatchMessagePayload {
-                message: DispatchMessage::RequestReceiptNotification {
-                    invoice_id: invoice.id,
-                    is_first_time: true,
-                    expired_at: Utc::now() + Duration::from_mins(30), // TODO should be configurable
-                },
-                telegram_id: customer.telegram_id,
-                bot_id: customer.last_seen_with_bot,
-            })
-            .await
-        {
-            Ok(_) => notifications_sent.push(invoice.id),
-            Err(e) => {
-                tracing::error!(
-                    "[Pending payments task]: Failed to send request receipt notification: {e}"
-                );
-            }
-        }
-    }
-    if !notifications_sent.is_empty() {
-        tracing::info!(
-            "[Pending payments task]: Sent {} request receipt notifications",
-            notifications_sent.len()
-        );
-    }
-    for invoice_id in notifications_sent {
-        if let Err(e) = app_state
-            .payment_invoice_service
-            .update(UpdatePaymentInvoiceCommand {
-                id: invoice_id,
-                status: Some(InvoiceStatus::AwaitingReceipt),
-                receipt_requested_at: Some(Utc::now()),
-                notification_sent_at: Some(Some(Utc::now())),
-                ..Default::default()
-            })
-            .await
-        {
-            tracing::error!("[Pending payments task]: Failed to update payment invoice: {e}")
-        }
-    }
-}
-
-async fn notify_request_receipts(
-    app_state: &Arc<AppState>,
-    pending_invoices: &[PaymentInvoiceRow],
-    polled_statuses: &HashMap<i64, InvoiceStatus>,
-    customers_by_id: &HashMap<i64, CustomerRow>,
-) {
-    let to_request_receipt = pending_invoices
-        .iter()
-        .filter(|i| {
-            if let Some(notification_sent_at) = i.notification_sent_at
-                && let Some(receipt_requested_at) = i.receipt_requested_at
+            // Not polled this time: the stored status is the latest one we know
+            let update = polled_statuses
+                .get(&invoice.id)
+                .copied()
+                .unwrap_or(GatewayStatusUpdate {
+                    status: invoice.status,
+                    is_fraud: false,
+                });
+            if let Err(e) = app_state
+                .payment_processing_service
+                .handle_gateway_status(invoice, customer, update)
+                .await
             {
-                let is_request_not_expired =
-                    receipt_requested_at + Duration::from_mins(30) > Utc::now();
-                let is_time_to_notify = notification_sent_at + Duration::from_mins(5) < Utc::now();
-                return i.status == InvoiceStatus::AwaitingReceipt
-                    && is_time_to_notify
-                    && is_request_not_expired
-                    && polled_statuses
-                        .get(&i.id)
-                        .unwrap_or(&InvoiceStatus::Pending)
-                        == &InvoiceStatus::AwaitingReceipt;
-            }
-            false
-        })
-        .collect::<Vec<_>>();
-
-    let mut notifications_sent = Vec::with_capacity(to_request_receipt.len());
-    for invoice in to_request_receipt {
-        let customer = match customers_by_id.get(&invoice.customer_id) {
-            Some(c) => c,
-            None => continue,
-        };
-        match app_state
-            .notification_service
-            .dispatch_message(DispatchMessagePayload {
-                message: DispatchMessage::RequestReceiptNotification {
-                    invoice_id: invoice.id,
-                    is_first_time: false,
-                    expired_at: invoice.receipt_requested_at.unwrap_or(Utc::now())
-                        + Duration::from_mins(30), // TODO should be configurable
-                },
-                telegram_id: customer.telegram_id,
-                bot_id: customer.last_seen_with_bot,
-            })
-            .await
-        {
-            Ok(_) => notifications_sent.push(invoice.id),
-            Err(e) => {
+                telemetry::worker_error("pending_payments");
                 tracing::error!(
-                    "[Pending payments task]: Failed to send request receipt notification: {e}"
+                    "[Pending payments task]: Failed to handle invoice {} status: {e}",
+                    invoice.id
                 );
             }
         }
-    }
-    if !notifications_sent.is_empty() {
-        tracing::info!(
-            "[Pending payments task]: Sent {} request receipt notifications",
-            notifications_sent.len()
-        );
-    }
-    for invoice_id in notifications_sent {
-        if let Err(e) = app_state
-            .payment_invoice_service
-            .update(UpdatePaymentInvoiceCommand {
-                id: invoice_id,
-                notification_sent_at: Some(Some(Utc::now())),
-                ..Default::default()
-            })
-            .await
-        {
-            tracing::error!("[Pending payments task]: Failed to update payment invoice: {e}")
-        }
+
+        tracing::info!("[Pending payments task]: Finished");
     }
 }
 
-async fn notify_contact_with_support(
+async fn poll_statuses(
     app_state: &Arc<AppState>,
     pending_invoices: &[PaymentInvoiceRow],
-    polled_statuses: &HashMap<i64, InvoiceStatus>,
-    customers_by_id: &HashMap<i64, CustomerRow>,
-) {
-    let to_notify = pending_invoices
-        .iter()
-        .filter(|i| {
-            i.status == InvoiceStatus::ReceiptSubmitted
-                && polled_statuses
-                    .get(&i.id)
-                    .unwrap_or(&InvoiceStatus::ReceiptSubmitted)
-                    == &InvoiceStatus::Disputed
-        })
-        .collect::<Vec<_>>();
-    let mut notifications_sent = Vec::with_capacity(to_notify.len());
-    for invoice in to_notify {
-        let customer = match customers_by_id.get(&invoice.customer_id) {
-            Some(c) => c,
-            None => continue,
-        };
+) -> HashMap<i64, GatewayStatusUpdate> {
+    let mut statuses = HashMap::with_capacity(pending_invoices.len());
+    for invoice in pending_invoices {
         match app_state
-            .notification_service
-            .dispatch_message(DispatchMessagePayload {
-                message: DispatchMessage::ContactSupportNotification,
-                telegram_id: customer.telegram_id,
-                bot_id: customer.last_seen_with_bot,
-            })
+            .platform_payments_provider
+            .get_order_status(invoice.gateway_invoice_id.clone())
             .await
         {
-            Ok(_) => notifications_sent.push(invoice.id),
-            Err(e) => {
-                tracing::error!(
-                    "[Pending payments task]: Failed to send contact support notification: {e}"
+            Ok(order) => {
+                if let Err(e) = app_state
+                    .payment_invoice_service
+                    .record_event_if_changed(NewPaymentInvoiceEvent::gateway_response(
+                        invoice.id, &order,
+                    ))
+                    .await
+                {
+                    tracing::error!(
+                        "[Pending payments task]: Failed to record invoice {} gateway response: {e}",
+                        invoice.id
+                    );
+                }
+                statuses.insert(
+                    invoice.id,
+                    GatewayStatusUpdate {
+                        status: InvoiceStatus::from(order.status),
+                        is_fraud: is_fake_appeal(order.appeal_fake_status.as_deref()),
+                    },
                 );
             }
-        }
-    }
-    if !notifications_sent.is_empty() {
-        tracing::info!(
-            "[Pending payments task]: Sent {} contact support notifications",
-            notifications_sent.len()
-        );
-    }
-    for invoice_id in notifications_sent {
-        if let Err(e) = app_state
-            .payment_invoice_service
-            .update(UpdatePaymentInvoiceCommand {
-                id: invoice_id,
-                status: Some(InvoiceStatus::Disputed),
-                dispute_opened_at: Some(Utc::now()),
-                ..Default::default()
-            })
-            .await
-        {
-            tracing::error!("[Pending payments task]: Failed to update payment invoice: {e}")
-        }
-    }
-}
-
-async fn update_invoices(
-    app_state: &Arc<AppState>,
-    pending_invoices: &[PaymentInvoiceRow],
-    polled_statuses: &HashMap<i64, InvoiceStatus>,
-) {
-    let to_update = pending_invoices
-        .iter()
-        .map(|i| {
-            (
-                i.id,
-                i.status,
-                polled_statuses
-                    .get(&i.id)
-                    .copied()
-                    .unwrap_or(InvoiceStatus::Pending),
-            )
-        })
-        .filter(|(_, status, polled_status)| {
-            polled_status != status
-                && (polled_status == &InvoiceStatus::Failed
-                    || polled_status == &InvoiceStatus::Expired
-                    || polled_status == &InvoiceStatus::Cancelled)
-        })
-        .collect::<Vec<_>>();
-    for (invoice_id, _, polled_status) in to_update {
-        if let Err(e) = app_state
-            .payment_invoice_service
-            .update(UpdatePaymentInvoiceCommand {
-                id: invoice_id,
-                status: Some(polled_status),
-                ..Default::default()
-            })
-            .await
-        {
-            tracing::error!("[Pending payments task]: Failed to update payment invoice: {e}")
-        }
-    }
-}
-
-async fn notify_dispute_failed(
-    app_state: &Arc<AppState>,
-    pending_invoices: &[PaymentInvoiceRow],
-    polled_statuses: &HashMap<i64, InvoiceStatus>,
-    customers_by_id: &HashMap<i64, CustomerRow>,
-) {
-    let to_notify = pending_invoices
-        .iter()
-        .filter(|i| {
-            i.status == InvoiceStatus::Disputed
-                && polled_statuses
-                    .get(&i.id)
-                    .unwrap_or(&InvoiceStatus::Disputed)
-                    == &InvoiceStatus::Failed
-        })
-        .collect::<Vec<_>>();
-    let mut notifications_sent = Vec::with_capacity(to_notify.len());
-    for invoice in to_notify {
-        let customer = match customers_by_id.get(&invoice.customer_id) {
-            Some(c) => c,
-            None => continue,
-        };
-        match app_state
-            .notification_service
-            .dispatch_message(DispatchMessagePayload {
-                message: DispatchMessage::DisputeFailedNotification,
-                telegram_id: customer.telegram_id,
-                bot_id: customer.last_seen_with_bot,
-            })
-            .await
-        {
-            Ok(_) => notifications_sent.push(invoice.id),
             Err(e) => {
-                tracing::error!(
-                    "[Pending payments task]: Failed to send dispute failed notification: {e}"
-                );
+                telemetry::worker_error("pending_payments");
+                tracing::error!("Failed to get order status: {e}");
             }
         }
     }
-    if !notifications_sent.is_empty() {
-        tracing::info!(
-            "[Pending payments task]: Sent {} dispute failed notifications",
-            notifications_sent.len()
-        );
-    }
-    for invoice_id in notifications_sent {
-        if let Err(e) = app_state
-            .payment_invoice_service
-            .update(UpdatePaymentInvoiceCommand {
-                id: invoice_id,
-                status: Some(InvoiceStatus::Disputed),
-                dispute_opened_at: Some(Utc::now()),
-                ..Default::default()
-            })
-            .await
-        {
-            tracing::error!("[Pending payments task]: Failed to update payment invoice: {e}")
-        }
-    }
-}
 
-async fn block_fraud(app_state: &Arc<AppState>, fraud_invoices: &[PaymentInvoiceRow]) {
-    for invoice in fraud_invoices {
-        if let Err(e) = app_state
-            .payment_invoice_service
-            .update(UpdatePaymentInvoiceCommand {
-                id: invoice.id,
-                status: Some(InvoiceStatus::Failed),
-                finished_at: Some(Utc::now()),
-                ..Default::default()
-            })
-            .await
-        {
-            tracing::error!("[Pending payments task]: Failed to update payment invoice: {e}")
-        }
-        if let Err(e) = app_state
-            .customer_service
-            .update(UpdateCustomerCommand {
-                id: invoice.customer_id,
-                is_blocked: Some(true),
-                ..Default::default()
-            })
-            .await
-        {
-            tracing::error!("[Pending payments task]: Failed to update customer: {e}")
-        }
-    }
-}
-
-impl From<AutosalesPlatformOrderStatusType> for InvoiceStatus {
-    fn from(status: AutosalesPlatformOrderStatusType) -> Self {
-        match status {
-            AutosalesPlatformOrderStatusType::MerchInitialized
-            | AutosalesPlatformOrderStatusType::MerchProcess => InvoiceStatus::Pending,
-            AutosalesPlatformOrderStatusType::TraderSuccess
-            | AutosalesPlatformOrderStatusType::MerchSuccess
-            | AutosalesPlatformOrderStatusType::SystemTimerEndMerchProcessSuccess
-            | AutosalesPlatformOrderStatusType::SystemTimerEndMerchCheckDownSuccess
-            | AutosalesPlatformOrderStatusType::AdminAppealSuccess => InvoiceStatus::Completed,
-            AutosalesPlatformOrderStatusType::MerchCheckDown => InvoiceStatus::ReceiptSubmitted,
-            AutosalesPlatformOrderStatusType::TraderCheckQuery => InvoiceStatus::AwaitingReceipt,
-            AutosalesPlatformOrderStatusType::TraderAppeal => InvoiceStatus::Disputed,
-            AutosalesPlatformOrderStatusType::SystemTimerEndMerchInitializedCancel => {
-                InvoiceStatus::Cancelled
-            }
-            AutosalesPlatformOrderStatusType::OrderCancel => InvoiceStatus::Cancelled,
-            AutosalesPlatformOrderStatusType::MerchCancel => InvoiceStatus::Cancelled,
-            AutosalesPlatformOrderStatusType::SystemTimerEndTraderCheckQueryCancel => {
-                InvoiceStatus::Cancelled
-            }
-            AutosalesPlatformOrderStatusType::AdminAppealCancel => InvoiceStatus::Failed,
-        }
-    }
+    tracing::info!(
+        "[Pending payments task]: Polled {} pending payments",
+        statuses.len()
+    );
+    statuses
 }
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use tokio::time::{Duration, interval};
+
+use crate::{
+    errors::api::ApiError,
+    services::reconciliation::{ReconciliationServiceTrait, RunReconciliationCommand},
+    state::AppState,
+    telemetry,
+};
+
+/// Reconciles the previous UTC day once it's over. Checks hourly, so a missed run (restart,
+/// failover) is caught up; reports made before the day ended don't count.
+pub async fn reconciliation_task(app_state: Arc<AppState>) {
+    tracing::info!("[Reconciliation task] Starting");
+    let mut interval = interval(Duration::from_hours(1));
+
+    loop {
+        interval.tick().await;
+        let _run = telemetry::WorkerRun::start("reconciliation", app_state.health_service.clone());
+        let day = Utc::now().date_naive() - ChronoDuration::days(1);
+        let day_end = (day + ChronoDuration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        match app_state.reconciliation_service.get_by_day(day).await {
+            Ok(report) if report.created_at >= day_end => continue,
+            Ok(_) | Err(ApiError::NotFound(_)) => {}
+            Err(e) => {
+                telemetry::worker_error("reconciliation");
+                tracing::error!("[Reconciliation task] Failed to load report for {day}: {e}");
+                continue;
+            }
+        }
+
+        match app_state
+            .reconciliation_service
+            .run(RunReconciliationCommand {
+                day,
+                triggered_by: None,
+                notify: true,
+            })
+            .await
+        {
+            Ok(report) => tracing::info!(
+                "[Reconciliation task] {day}: {} transactions, {} invoices, {} discrepancies",
+                report.transactions_checked,
+                report.invoices_checked,
+                report.discrepancies.len()
+            ),
+            Err(e) => {
+                telemetry::worker_error("reconciliation");
+                tracing::error!("[Reconciliation task] Failed to reconcile {day}: {e}");
+            }
+        }
+    }
+}
@@ -9,6 +9,7 @@ use crate::{
         user_subscription::UserSubscriptionServiceTrait,
     },
     state::AppState,
+    telemetry,
 };
 
 async fn run_subscription_expiry_notifications_once(
@@ -29,6 +30,7 @@ async fn run_subscription_expiry_notifications_once(
     {
         Ok(rows) => rows,
         Err(err) => {
+            telemetry::worker_error("subscription_expiry_notifications");
             tracing::error!(
                 "[Subscription expiry notifications task]: Failed to load expiring subscriptions: {err}"
             );
@@ -59,6 +61,7 @@ async fn run_subscription_expiry_notifications_once(
                 sent_ids.push(sub.id);
             }
             Err(err) => {
+                telemetry::worker_error("subscription_expiry_notifications");
                 tracing::error!(
                     "[Subscription expiry notifications task]: Failed to dispatch notification for subscription {}: {}",
                     sub.id,
@@ -73,6 +76,7 @@ async fn run_subscription_expiry_notifications_once(
             .mark_expiry_notification_sent(&sent_ids)
             .await
         {
+            telemetry::worker_error("subscription_expiry_notifications");
             tracing::error!(
                 "[Subscription expiry notifications task]: Failed to mark notifications sent: {}",
                 err
@@ -94,6 +98,10 @@ pub async fn subscription_expiry_notifications_task(app_state: Arc<AppState>) {
     ));
     loop {
         interval.tick().await;
+        let _run = telemetry::WorkerRun::start(
+            "subscription_expiry_notifications",
+            app_state.health_service.clone(),
+        );
         tracing::info!("[Subscription expiry notifications task]: Running...");
         run_subscription_expiry_notifications_once(
             app_state.user_subscription_service.as_ref(),
@@ -149,8 +149,10 @@ export const BotUsersTable = ({
         onSortModelChange={onSortModelChange}
         paginationMode="server"
         filterMode="server"
+        showToolbar
         localeText={ruRU.components.MuiDataGrid.defaultProps.localeText}
         slotProps={{
+          toolbar: { showQuickFilter: true },
           filterPanel: {
             filterFormProps: {
               logicOperatorInputProps: {
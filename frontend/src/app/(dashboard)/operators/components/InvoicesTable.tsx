@@ -114,8 +114,10 @@ export const InvoicesTable = ({
         onSortModelChange={onSortModelChange}
         paginationMode="server"
         filterMode="server"
+        showToolbar
         localeText={ruRU.components.MuiDataGrid.defaultProps.localeText}
         slotProps={{
+          toolbar: { showQuickFilter: true },
           filterPanel: {
             filterFormProps: {
               logicOperatorInputProps: {
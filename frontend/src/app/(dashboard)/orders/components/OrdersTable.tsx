@@ -103,8 +103,10 @@ export const OrdersTable = ({
         onSortModelChange={onSortModelChange}
         paginationMode="server"
         filterMode="server"
+        showToolbar
         localeText={ruRU.components.MuiDataGrid.defaultProps.localeText}
         slotProps={{
+          toolbar: { showQuickFilter: true },
           filterPanel: {
             filterFormProps: {
               logicOperatorInputProps: {
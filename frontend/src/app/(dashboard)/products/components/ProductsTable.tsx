@@ -190,8 +190,10 @@ export const ProductsTable = ({
         onSortModelChange={onSortModelChange}
         paginationMode="server"
         filterMode="server"
+        showToolbar
         localeText={ruRU.components.MuiDataGrid.defaultProps.localeText}
         slotProps={{
+          toolbar: { showQuickFilter: true },
           filterPanel: {
             filterFormProps: {
               logicOperatorInputProps: {
@@ -6,6 +6,12 @@ export const AUDIT_ACTIONS_TRANSLATIONS = {
   user_create: "Создание пользователя",
   user_update: "Обновление пользователя",
   user_delete: "Удаление пользователя",
+  user_password_change: "Смена пароля",
+  user_two_fa_rotate: "Перевыпуск 2FA",
+  user_two_fa_reset: "Сброс 2FA",
+  user_recovery_codes_regenerate: "Перевыпуск резервных кодов",
+  api_key_create: "Создание API-ключа",
+  api_key_revoke: "Отзыв API-ключа",
   role_grant: "Назначение роли",
   role_revoke: "Отзыв роли",
   permission_grant: "Предоставление разрешения",
@@ -36,4 +42,12 @@ export const AUDIT_ACTIONS_TRANSLATIONS = {
   system_settings_update: "Обновление системных настроек",
   broadcast_create: "Создание рассылки",
   broadcast_update: "Обновление рассылки",
+  customer_segment_create: "Создание сегмента клиентов",
+  customer_segment_update: "Обновление сегмента клиентов",
+  customer_segment_delete: "Удаление сегмента клиентов",
+  invoice_manual_complete: "Ручное зачисление счёта",
+  invoice_manual_fail: "Ручная отмена счёта",
+  invoice_receipt_resend: "Повторная отправка чека",
+  invoice_status_change: "Смена статуса счёта",
+  order_status_change: "Смена статуса заказа",
 } as const satisfies Record<AuditAction, string>;
@@ -27,6 +27,7 @@ export const useDataGrid = <T>(
   const formattedFilters = filterModel.items
     .filter((item) => item.value !== undefined && item.value !== "")
     .map(({ field, operator: op, value }) => ({ field, op, value }));
+  const search = filterModel.quickFilterValues?.join(" ").trim();
 
   const queryFilter = {
     page: paginationModel.page + 1, // MUI is 0-indexed, backend is 1-indexed
@@ -34,6 +35,7 @@ export const useDataGrid = <T>(
     filters: formattedFilters.length > 0 ? formattedFilters : undefined,
     order_by: sortModel[0]?.field,
     order_dir: sortModel[0]?.sort,
+    search: search || undefined,
     ...options?.filters,
   };
 
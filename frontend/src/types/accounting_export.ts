@@ -0,0 +1,29 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Export too large to be streamed right away, built in the background
+ */
+export type AccountingExport = { id: number, entity: AccountingExportEntity, format: AccountingExportFormat, status: AccountingExportStatus, date_from: string, date_to: string, rows_count: number | null, error: string | null, 
+/**
+ * Download link that works without authentication until `expires_at`
+ */
+download_url: string | null, expires_at: string | null, created_by: number, created_at: string, finished_at: string | null, };
+
+export type AccountingExportEntity = "transactions" | "orders" | "payment_invoices" | "stock_movements";
+
+export type AccountingExportFormat = "csv" | "xlsx";
+
+/**
+ * Export parameters, passed in the query string next to the list filters
+ */
+export type AccountingExportQuery = { format?: AccountingExportFormat, 
+/**
+ * Inclusive start of the `created_at` range
+ */
+from: string, 
+/**
+ * Exclusive end of the `created_at` range
+ */
+to: string, };
+
+export type AccountingExportStatus = "pending" | "processing" | "completed" | "failed";
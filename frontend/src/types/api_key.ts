@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ApiKey = { id: number, name: string, key_prefix: string, permissions: Array<string>, allowed_ips: Array<string>, expires_at: string | null, last_used_at: string | null, last_used_ip: string | null, created_by: number, created_at: string, revoked_at: string | null, };
+
+export type CreatedApiKey = { key: string, api_key: ApiKey, };
+
+export type NewApiKey = { name: string, permissions: Array<string>, allowed_ips: Array<string>, expires_at?: string, };
@@ -1,5 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
+export type ChangePassword = { current_password: string, new_password: string, };
+
+export type CompleteTwoFaEnrollment = { enrollment_token: string, password: string, };
+
+export type ConfirmTwoFaRotation = { code: string, };
+
+export type LoginRecovery = { temp_token: string, recovery_code: string, };
+
 export type LoginStep1 = { login: string, password: string, };
 
 export type LoginStep1Response = { temp_token: string, };
@@ -14,8 +22,18 @@ export type PermissionEffect = "allow" | "deny";
 
 export type PermissionResponse = { id: number, name: string, group: string, description: string | null, };
 
+export type Reauthenticate = { password: string, code: string, };
+
+export type RecoveryCodes = { recovery_codes: Array<string>, };
+
 export type Role = { id: number, name: string, description: string | null, created_at: string, updated_at: string, created_by: number, };
 
+export type TwoFaEnrollment = { two_fa_secret: string, two_fa_qr_code: string, recovery_codes: Array<string>, };
+
+export type TwoFaReset = { enrollment_token: string, expires_at: string, };
+
+export type TwoFaRotation = { two_fa_secret: string, two_fa_qr_code: string, };
+
 export type UpdateRole = { name?: string, description?: string | null, };
 
 export type UpdateRolePermissions = { added: Array<number>, removed: Array<number>, };
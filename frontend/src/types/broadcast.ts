@@ -1,10 +1,75 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Customer } from "./customer";
 import type { JsonValue } from "./serde_json/JsonValue";
 
-export type Broadcast = { id: number, status: BroadcastStatus, content_text: string | null, content_image_id: string | null, filters: JsonValue | null, statistics: JsonValue | null, created_by: number, scheduled_for: string | null, started_at: string | null, finished_at: string | null, created_at: string, updated_at: string, };
+export type Broadcast = { id: number, status: BroadcastStatus, content_text: string | null, content_image_id: string | null, content_entities: Array<BroadcastTextEntity>, content_media: Array<BroadcastMedia>, content_buttons: Array<Array<BroadcastButton>>, filters: JsonValue | null, segment_id: number | null, statistics: JsonValue | null, created_by: number, scheduled_for: string | null, started_at: string | null, finished_at: string | null, created_at: string, updated_at: string, };
 
-export type BroadcastStatus = "pending" | "scheduled" | "in_progress" | "completed" | "failed";
+export type BroadcastAudiencePreview = { total: number, sample: Array<Customer>, };
 
-export type NewBroadcast = { content_text?: string, content_image_id?: string, filters?: any, scheduled_for?: string, };
+export type BroadcastButton = { text: string, action: BroadcastButtonAction, };
 
-export type UpdateBroadcast = { content_text?: string | null, content_image_id?: string | null, filters?: any, scheduled_for?: string | null, };
+export type BroadcastButtonAction = { "type": "url", url: string, } | { "type": "product", product_id: number, } | { "type": "category", category_id: number | null, } | { "type": "main_menu" } | { "type": "balance" } | { "type": "support" };
+
+/**
+ * Attachment taken either from uploaded images (photos only) or from a public URL
+ */
+export type BroadcastMedia = { kind: BroadcastMediaKind, image_id?: string, url?: string, };
+
+export type BroadcastMediaKind = "photo" | "video" | "document";
+
+export type BroadcastRecipient = { id: number, broadcast_id: number, customer_id: number, telegram_id: number, variant_id: number | null, status: BroadcastRecipientStatus, error_message: string | null, sent_at: string | null, created_at: string, updated_at: string, };
+
+export type BroadcastRecipientStatus = "pending" | "dispatched" | "sent" | "blocked" | "failed";
+
+export type BroadcastStatus = "pending" | "scheduled" | "in_progress" | "paused" | "completed" | "cancelled" | "failed";
+
+/**
+ * Telegram formatting entity, `offset` and `length` are in UTF-16 code units as in the Bot API
+ */
+export type BroadcastTextEntity = { kind: BroadcastTextEntityKind, offset: number, length: number, 
+/**
+ * Required for `text_link`
+ */
+url?: string, 
+/**
+ * Optional language of a `pre` block
+ */
+language?: string, };
+
+export type BroadcastTextEntityKind = "bold" | "italic" | "underline" | "strikethrough" | "spoiler" | "code" | "pre" | "text_link" | "blockquote";
+
+export type BroadcastVariant = { id: number, broadcast_id: number, name: string, content_text: string | null, content_image_id: string | null, content_entities: Array<BroadcastTextEntity>, content_media: Array<BroadcastMedia>, content_buttons: Array<Array<BroadcastButton>>, created_at: string, };
+
+/**
+ * Delivery and conversion numbers of one variant, `variant_id` is null for the base content
+ */
+export type BroadcastVariantStats = { variant_id: number | null, name: string, recipients: number, sent: number, 
+/**
+ * Recipients who paid for an order within the conversion window after delivery
+ */
+conversions: number, conversion_rate: number, };
+
+export type NewBroadcast = { content_text?: string, content_image_id?: string, content_entities?: Array<BroadcastTextEntity>, content_media?: Array<BroadcastMedia>, content_buttons?: Array<Array<BroadcastButton>>, filters?: any, 
+/**
+ * Saved segment whose filters are combined with `filters`
+ */
+segment_id?: number, 
+/**
+ * Alternative contents for A/B testing, the broadcast's own content is variant "A"
+ */
+variants?: Array<NewBroadcastVariant>, scheduled_for?: string, };
+
+export type NewBroadcastVariant = { name: string, content_text?: string, content_image_id?: string, content_entities?: Array<BroadcastTextEntity>, content_media?: Array<BroadcastMedia>, content_buttons?: Array<Array<BroadcastButton>>, };
+
+export type PreviewBroadcastAudience = { filters?: any, segment_id?: number, sample_size?: number, };
+
+/**
+ * Sends broadcast content to a single chat before scheduling the real broadcast
+ */
+export type TestBroadcast = { telegram_id: number, 
+/**
+ * Defaults to the bot the recipient was last seen with
+ */
+bot_id?: number, content_text?: string, content_image_id?: string, content_entities?: Array<BroadcastTextEntity>, content_media?: Array<BroadcastMedia>, content_buttons?: Array<Array<BroadcastButton>>, };
+
+export type UpdateBroadcast = { content_text?: string | null, content_image_id?: string | null, content_entities?: Array<BroadcastTextEntity>, content_media?: Array<BroadcastMedia>, content_buttons?: Array<Array<BroadcastButton>>, filters?: any, scheduled_for?: string | null, };
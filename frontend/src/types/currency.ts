@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Currency = "RUB" | "USD" | "EUR" | "USDT";
+
+export type ExchangeRate = { currency: Currency, 
+/**
+ * Units of `currency` for one unit of the base currency
+ */
+rate: number, 
+/**
+ * `base`, the rate source name or `static` for the configured fallback
+ */
+source: string, updated_at: string | null, };
+
+export type ExchangeRates = { base_currency: Currency, rates: Array<ExchangeRate>, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Saved customer filters, reusable as a broadcast audience
+ */
+export type CustomerSegment = { id: number, name: string, description: string | null, filters: any, created_by: number, created_at: string, updated_at: string, };
+
+export type NewCustomerSegment = { name: string, description?: string, filters: any, };
+
+export type UpdateCustomerSegment = { name?: string, description?: string | null, filters?: any, };
@@ -1,4 +1,5 @@
 export * from "./common";
+export * from "./accounting_export";
 export * from "./permissions";
 export * from "./routing";
 export * from "./auth";
@@ -7,26 +8,43 @@ export * from "./category";
 export * from "./bot";
 export * from "./audit_log";
 export * from "./customer";
+export * from "./customer_segment";
 export * from "./image";
 export * from "./invoice";
 export * from "./order";
 export * from "./product";
+export * from "./product_sync";
+export * from "./reconciliation";
 export * from "./settings";
 export * from "./stock_movement";
 export * from "./transaction";
 export * from "./store_balance";
 export * from "./balance_request";
 export * from "./broadcast";
+export * from "./api_key";
+
+export type IFilterGroup =
+  | { and: (IFilterLeaf | IFilterGroup)[] }
+  | { or: (IFilterLeaf | IFilterGroup)[] }
+  | { not: IFilterLeaf | IFilterGroup };
+
+export interface IFilterLeaf {
+  field: string;
+  op: string;
+  value?: any;
+}
 
 export interface IFilter {
   page?: number;
   page_size?: number;
   order_by?: string;
   order?: string;
+  search?: string;
   filters?: {
     field: string;
     op: string;
     value: any;
   }[];
+  groups?: IFilterGroup[];
   [key: string]: any;
 }
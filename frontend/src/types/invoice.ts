@@ -1,6 +1,62 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuditLog } from "./audit_log";
+import type { Currency } from "./currency";
 import type { PaymentSystem } from "./payment";
+import type { JsonValue } from "./serde_json/JsonValue";
+
+export type GatewayCircuitState = "closed" | "open" | "half_open";
+
+export type GatewayHealth = { gateway: PaymentSystem, attempts: number, successes: number, 
+/**
+ * Gateway errors and "no requisites" answers together
+ */
+failures: number, no_requisites: number, 
+/**
+ * Share of successful attempts, `None` without attempts in the window
+ */
+success_rate: number | null, avg_latency_ms: number | null, p95_latency_ms: number | null, last_success_at: string | null, last_failure_at: string | null, circuit: GatewayCircuitState, open_until: string | null, };
 
 export type InvoiceStatus = "pending" | "processing" | "awaiting_receipt" | "receipt_submitted" | "disputed" | "completed" | "failed" | "expired" | "cancelled" | "refunded";
 
-export type PaymentInvoice = { id: number, customer_id: number, original_amount: number, amount: number, status: InvoiceStatus, created_at: string, updated_at: string, expires_at: string, gateway: PaymentSystem, gateway_invoice_id: string, };
+export type PaymentInvoice = { id: number, customer_id: number, original_amount: number, amount: number, status: InvoiceStatus, created_at: string, updated_at: string, expires_at: string, gateway: PaymentSystem, gateway_invoice_id: string, 
+/**
+ * Currency of `amount`, `original_amount` is in the store base currency
+ */
+currency: Currency, 
+/**
+ * Order bought with this invoice once it is paid (pay-for-product checkout)
+ */
+checkout_order_id: number | null, };
+
+export type PaymentInvoiceEvent = { id: number, kind: PaymentInvoiceEventKind, from_status: InvoiceStatus | null, to_status: InvoiceStatus | null, 
+/**
+ * Gateway response or webhook body with requisites redacted, notification contents
+ */
+payload: JsonValue | null, created_at: string, };
+
+export type PaymentInvoiceEventKind = "status_changed" | "gateway_response" | "webhook" | "notification_sent";
+
+export type PaymentInvoiceHistory = { invoice: PaymentInvoice, order_id: string, payment_details: JsonValue, notification_sent_at: string | null, receipt_requested_at: string | null, receipt_submitted_at: string | null, receipt_url: string | null, dispute_opened_at: string | null, finished_at: string | null, 
+/**
+ * Append-only log of status transitions, gateway responses and notifications
+ */
+events: Array<PaymentInvoiceEvent>, webhook_events: Array<PaymentWebhookEvent>, 
+/**
+ * Audit log of the invoice: operator resolutions and other recorded changes
+ */
+actions: Array<AuditLog>, };
+
+/**
+ * Status event pushed by the gateway for an invoice, as received
+ */
+export type PaymentWebhookEvent = { id: number, event_id: string, payload: JsonValue, received_at: string, processed_at: string | null, 
+/**
+ * Why processing failed, the event is retried on redelivery
+ */
+error: string | null, };
+
+export type ResolvePaymentInvoice = { 
+/**
+ * Required when failing an invoice
+ */
+reason: string | null, };
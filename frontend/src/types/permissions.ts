@@ -48,6 +48,7 @@ export enum PermissionName {
 
   // 📋 Инвойсы
   InvoicesRead = "invoices:read",
+  InvoicesResolve = "invoices:resolve",
 
   // 🤖 Боты
   BotsCreate = "bots:create",
@@ -67,4 +68,7 @@ export enum PermissionName {
 
   // 📝 Аудит
   AuditLogRead = "audit_log:read",
+
+  // 🔑 API-ключи
+  ApiKeysManage = "api_keys:manage",
 }
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One catalog sync of an external product provider
+ */
+export type ProductSyncRun = { id: number, provider_name: string, trigger: ProductSyncTrigger, status: ProductSyncStatus, added: number, updated: number, hidden: number, restored: number, errors: Array<string>, triggered_by: number | null, started_at: string, finished_at: string | null, };
+
+export type ProductSyncStatus = "queued" | "running" | "succeeded" | "partial" | "failed";
+
+export type ProductSyncTrigger = "schedule" | "manual";
+
+export type RunProductSync = { 
+/**
+ * Sync a single provider; all enabled providers when omitted
+ */
+provider_name?: string, };
@@ -0,0 +1,37 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ReconciliationDiscrepancy = { kind: ReconciliationDiscrepancyKind, entity: ReconciliationEntity, entity_id: number, 
+/**
+ * Balance or deposit count the row should have
+ */
+expected: number, actual: number, 
+/**
+ * Admin API request returning the offending row
+ */
+link: string, };
+
+export type ReconciliationDiscrepancyKind = "customer_balance_mismatch" | "user_balance_chain_broken" | "store_balance_chain_broken" | "invoice_missing_deposit" | "invoice_duplicate_deposits";
+
+export type ReconciliationEntity = "customer" | "transaction" | "payment_invoice";
+
+export type ReconciliationKindCount = { kind: ReconciliationDiscrepancyKind, count: number, };
+
+export type ReconciliationReport = { id: number, 
+/**
+ * UTC day whose transactions and completed invoices were checked
+ */
+day: string, transactions_checked: number, invoices_checked: number, customers_checked: number, discrepancies: Array<ReconciliationDiscrepancy>, 
+/**
+ * `None` for scheduled runs
+ */
+triggered_by: number | null, created_at: string, };
+
+export type RunReconciliation = { 
+/**
+ * Defaults to yesterday (UTC)
+ */
+day?: string, 
+/**
+ * Alert the manager group when discrepancies are found
+ */
+notify?: boolean, };
@@ -1,9 +1,31 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Currency } from "./currency";
+import type { PaymentSystem } from "./payment";
 
 export type BotSettings = { bot_messages_support: string, bot_messages_support_image_id: string | null, bot_messages_new_user_welcome: string, bot_messages_new_user_welcome_image_id: string | null, bot_messages_returning_user_welcome: string, bot_messages_returning_user_welcome_image_id: string | null, bot_payment_system_support_operators: Array<string>, bot_store_support_operators: Array<string>, bot_description: string, bot_about: string, };
 
+export type PaymentGatewaySettings = { gateway: PaymentSystem, invoice_ttl_minutes: number, min_amount: number, max_amount: number, 
+/**
+ * Deposits must be a multiple of it
+ */
+amount_step: number, 
+/**
+ * Open invoices a customer may have at once on this gateway, unlimited when `null`
+ */
+max_open_invoices: number | null, 
+/**
+ * Deposits per customer and UTC day on this gateway, unlimited when `null`
+ */
+daily_deposit_limit: number | null, 
+/**
+ * Currency the gateway charges in, amounts and limits above are in it
+ */
+currency: Currency, updated_at: string, };
+
 export type PricingSettings = { pricing_global_markup: number, pricing_platform_commission: number, pricing_gateway_markup: number, pricing_gateway_bonus_mock_provider: number, pricing_gateway_bonus_platform_card: number, pricing_gateway_bonus_platform_sbp: number, referral_program_enabled: boolean, referral_percentage: number, };
 
 export type UpdateBotSettings = { bot_messages_support?: string, bot_messages_support_image_id?: string | null, bot_messages_new_user_welcome?: string, bot_messages_new_user_welcome_image_id?: string | null, bot_messages_returning_user_welcome?: string, bot_messages_returning_user_welcome_image_id?: string | null, bot_payment_system_support_operators?: Array<string>, bot_store_support_operators?: Array<string>, bot_description?: string, bot_about?: string, };
 
+export type UpdatePaymentGatewaySettings = { invoice_ttl_minutes?: number, min_amount?: number, max_amount?: number, amount_step?: number, max_open_invoices?: number | null, daily_deposit_limit?: number | null, };
+
 export type UpdatePricingSettings = { pricing_global_markup?: number, pricing_platform_commission?: number, pricing_gateway_markup?: number, pricing_gateway_bonus_mock_provider?: number, pricing_gateway_bonus_platform_card?: number, pricing_gateway_bonus_platform_sbp?: number, referral_program_enabled?: boolean, referral_percentage?: number, };
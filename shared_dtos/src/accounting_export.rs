@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "accounting_export.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountingExportEntity {
+    Transactions,
+    /// One row per order item, orders without items get a single row
+    Orders,
+    PaymentInvoices,
+    StockMovements,
+}
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "accounting_export.ts"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountingExportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "accounting_export.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountingExportStatus {
+    Pending,
+    Processing,
+    Completed,
+    Failed,
+}
+
+/// Export parameters, passed in the query string next to the list filters
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "accounting_export.ts",
+        rename = "AccountingExportQuery"
+    )
+)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountingExportParams {
+    #[cfg_attr(feature = "ts", ts(as = "Option<AccountingExportFormat>", optional))]
+    #[serde(default)]
+    pub format: AccountingExportFormat,
+    /// Inclusive start of the `created_at` range
+    pub from: DateTime<Utc>,
+    /// Exclusive end of the `created_at` range
+    pub to: DateTime<Utc>,
+}
+
+/// Export too large to be streamed right away, built in the background
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "accounting_export.ts",
+        rename = "AccountingExport"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingExportResponse {
+    pub id: i64,
+    pub entity: AccountingExportEntity,
+    pub format: AccountingExportFormat,
+    pub status: AccountingExportStatus,
+    pub date_from: DateTime<Utc>,
+    pub date_to: DateTime<Utc>,
+    pub rows_count: Option<i64>,
+    pub error: Option<String>,
+    /// Download link that works without authentication until `expires_at`
+    pub download_url: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "api_key.ts", rename = "ApiKey")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyAdminResponse {
+    pub id: i64,
+    pub name: String,
+    pub key_prefix: String,
+    pub permissions: Vec<String>,
+    pub allowed_ips: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "api_key.ts", rename = "NewApiKey")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewApiKeyAdminRequest {
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(
+            min = 3,
+            max = 64,
+            message = "Name must be at least 3 characters long and at most 64 characters long"
+        ))
+    )]
+    pub name: String,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(min = 1, message = "At least one permission is required"))
+    )]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "api_key.ts", rename = "CreatedApiKey")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedApiKeyAdminResponse {
+    pub key: String,
+    pub api_key: ApiKeyAdminResponse,
+}
@@ -14,6 +14,12 @@ pub enum AuditAction {
     UserCreate,
     UserUpdate,
     UserDelete,
+    UserPasswordChange,
+    UserTwoFaRotate,
+    UserTwoFaReset,
+    UserRecoveryCodesRegenerate,
+    ApiKeyCreate,
+    ApiKeyRevoke,
     RoleGrant,
     RoleRevoke,
     PermissionGrant,
@@ -44,6 +50,14 @@ pub enum AuditAction {
     SystemSettingsUpdate,
     BroadcastCreate,
     BroadcastUpdate,
+    CustomerSegmentCreate,
+    CustomerSegmentUpdate,
+    CustomerSegmentDelete,
+    InvoiceManualComplete,
+    InvoiceManualFail,
+    InvoiceReceiptResend,
+    InvoiceStatusChange,
+    OrderStatusChange,
 }
 
 #[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -68,3 +69,139 @@ pub struct LoginStep2AdminRequest {
 pub struct LoginStep2AdminResponse {
     pub token: Uuid,
 }
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "LoginRecovery")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRecoveryAdminRequest {
+    pub temp_token: Uuid,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(
+            min = 10,
+            max = 32,
+            message = "Recovery code must be between 10 and 32 characters"
+        ))
+    )]
+    pub recovery_code: String,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "ChangePassword")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangePasswordAdminRequest {
+    pub current_password: String,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(
+            min = 8,
+            max = 20,
+            message = "Password must be at least 8 characters long and at most 20 characters long"
+        ))
+    )]
+    pub new_password: String,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "Reauthenticate")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReauthenticateAdminRequest {
+    pub password: String,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(min = 6, max = 6, message = "Code must be 6 characters"))
+    )]
+    pub code: String,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "TwoFaEnrollment")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFaEnrollmentAdminResponse {
+    pub two_fa_secret: String,
+    pub two_fa_qr_code: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "TwoFaRotation")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFaRotationAdminResponse {
+    pub two_fa_secret: String,
+    pub two_fa_qr_code: String,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "ConfirmTwoFaRotation")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfirmTwoFaRotationAdminRequest {
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(min = 6, max = 6, message = "Code must be 6 characters"))
+    )]
+    pub code: String,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "RecoveryCodes")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryCodesAdminResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "TwoFaReset")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFaResetAdminResponse {
+    pub enrollment_token: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "auth.ts", rename = "CompleteTwoFaEnrollment")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteTwoFaEnrollmentAdminRequest {
+    pub enrollment_token: Uuid,
+    pub password: String,
+}
@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 use serde_with::rust::double_option;
 use uuid::Uuid;
 
-use crate::list_query::{FilterValue, Operator, ScalarValue};
+use crate::{
+    customer::CustomerAdminResponse,
+    list_query::{FilterValue, Operator, RawFilter, RawFilterGroup, ScalarValue},
+};
 
 #[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
 #[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
@@ -16,11 +19,135 @@ pub enum BroadcastStatus {
     Pending,
     Scheduled,
     InProgress,
+    Paused,
     Completed,
+    Cancelled,
     Failed,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "broadcast.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastRecipientStatus {
+    Pending,
+    /// Handed to the bot, waiting for its delivery report
+    Dispatched,
+    Sent,
+    /// Telegram refused the message because the customer has blocked the bot
+    Blocked,
+    Failed,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "broadcast.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastTextEntityKind {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Spoiler,
+    Code,
+    Pre,
+    TextLink,
+    Blockquote,
+}
+
+/// Telegram formatting entity, `offset` and `length` are in UTF-16 code units as in the Bot API
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "broadcast.ts"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastTextEntity {
+    pub kind: BroadcastTextEntityKind,
+    pub offset: u32,
+    pub length: u32,
+    /// Required for `text_link`
+    #[cfg_attr(feature = "ts", ts(optional))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Optional language of a `pre` block
+    #[cfg_attr(feature = "ts", ts(optional))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "broadcast.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastMediaKind {
+    Photo,
+    Video,
+    Document,
+}
+
+/// Attachment taken either from uploaded images (photos only) or from a public URL
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "broadcast.ts"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastMedia {
+    pub kind: BroadcastMediaKind,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_id: Option<Uuid>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "broadcast.ts"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BroadcastButtonAction {
+    /// External link or a Telegram deep link (`https://t.me/...`, `tg://...`)
+    Url {
+        url: String,
+    },
+    Product {
+        product_id: i64,
+    },
+    Category {
+        category_id: Option<i64>,
+    },
+    MainMenu,
+    Balance,
+    Support,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "broadcast.ts"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastButton {
+    pub text: String,
+    pub action: BroadcastButtonAction,
+}
+
+/// Everything the bot needs to render a broadcast message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastContent {
+    pub text: Option<String>,
+    #[serde(default)]
+    pub entities: Vec<BroadcastTextEntity>,
+    #[serde(default)]
+    pub media: Vec<BroadcastMedia>,
+    /// Inline keyboard rows
+    #[serde(default)]
+    pub buttons: Vec<Vec<BroadcastButton>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum JsonScalarValue {
     Int(i64),
@@ -31,23 +158,41 @@ pub enum JsonScalarValue {
     Text(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum JsonFilterValue {
     Scalar(JsonScalarValue),
     Array(Vec<JsonScalarValue>),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Default for JsonFilterValue {
+    fn default() -> Self {
+        JsonFilterValue::Array(Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRawFilter {
     pub field: String,
     pub op: Operator,
+    #[serde(default)]
     pub value: JsonFilterValue,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRawListQuery {
     pub filters: Vec<JsonRawFilter>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<RawFilterGroup<JsonRawFilter>>,
+}
+
+impl JsonRawListQuery {
+    /// Combines two queries, a customer has to match both
+    pub fn and(mut self, other: JsonRawListQuery) -> Self {
+        self.filters.extend(other.filters);
+        self.groups.extend(other.groups);
+        self
+    }
 }
 
 impl From<JsonScalarValue> for ScalarValue {
@@ -74,6 +219,16 @@ impl From<JsonFilterValue> for FilterValue {
     }
 }
 
+impl From<JsonRawFilter> for RawFilter {
+    fn from(val: JsonRawFilter) -> Self {
+        RawFilter {
+            field: val.field,
+            op: val.op,
+            value: val.value.into(),
+        }
+    }
+}
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "validate", derive(validator::Validate))]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
@@ -91,14 +246,70 @@ pub struct NewBroadcastRequest {
     pub content_text: Option<String>,
     #[cfg_attr(feature = "ts", ts(optional))]
     pub content_image_id: Option<Uuid>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<BroadcastTextEntity>>", optional))]
+    #[serde(default)]
+    pub content_entities: Vec<BroadcastTextEntity>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<BroadcastMedia>>", optional))]
+    #[serde(default)]
+    pub content_media: Vec<BroadcastMedia>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<Vec<BroadcastButton>>>", optional))]
+    #[serde(default)]
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
     #[cfg_attr(feature = "openapi", schema(value_type = Object))]
     #[cfg_attr(feature = "ts", ts(type = "any"))]
     #[cfg_attr(feature = "ts", ts(optional))]
     pub filters: Option<JsonRawListQuery>,
+    /// Saved segment whose filters are combined with `filters`
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub segment_id: Option<i64>,
+    /// Alternative contents for A/B testing, the broadcast's own content is variant "A"
+    #[cfg_attr(
+        feature = "ts",
+        ts(as = "Option<Vec<NewBroadcastVariantRequest>>", optional)
+    )]
+    #[serde(default)]
+    pub variants: Vec<NewBroadcastVariantRequest>,
     #[cfg_attr(feature = "ts", ts(optional))]
     pub scheduled_for: Option<DateTime<Utc>>,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "broadcast.ts", rename = "NewBroadcastVariant")
+)]
+#[derive(Debug, Deserialize)]
+pub struct NewBroadcastVariantRequest {
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(
+            min = 1,
+            max = 32,
+            message = "Variant name must be at least 1 character and at most 32 characters long"
+        ))
+    )]
+    pub name: String,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(max = 1024, message = "Content text is too long"))
+    )]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub content_text: Option<String>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub content_image_id: Option<Uuid>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<BroadcastTextEntity>>", optional))]
+    #[serde(default)]
+    pub content_entities: Vec<BroadcastTextEntity>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<BroadcastMedia>>", optional))]
+    #[serde(default)]
+    pub content_media: Vec<BroadcastMedia>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<Vec<BroadcastButton>>>", optional))]
+    #[serde(default)]
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
+}
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "validate", derive(validator::Validate))]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
@@ -120,6 +331,12 @@ pub struct UpdateBroadcastRequest {
     #[cfg_attr(feature = "ts", ts(type = "string | null"))]
     #[serde(default, with = "double_option")]
     pub content_image_id: Option<Option<Uuid>>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub content_entities: Option<Vec<BroadcastTextEntity>>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub content_media: Option<Vec<BroadcastMedia>>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub content_buttons: Option<Vec<Vec<BroadcastButton>>>,
     #[cfg_attr(feature = "openapi", schema(value_type = Object))]
     #[cfg_attr(feature = "ts", ts(type = "any"))]
     #[cfg_attr(feature = "ts", ts(optional))]
@@ -142,7 +359,11 @@ pub struct BroadcastResponse {
     pub status: BroadcastStatus,
     pub content_text: Option<String>,
     pub content_image_id: Option<Uuid>,
+    pub content_entities: Vec<BroadcastTextEntity>,
+    pub content_media: Vec<BroadcastMedia>,
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
     pub filters: Option<serde_json::Value>,
+    pub segment_id: Option<i64>,
     pub statistics: Option<serde_json::Value>,
     pub created_by: i64,
     pub scheduled_for: Option<DateTime<Utc>>,
@@ -151,3 +372,146 @@ pub struct BroadcastResponse {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "broadcast.ts", rename = "BroadcastRecipient")
+)]
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastRecipientResponse {
+    pub id: i64,
+    pub broadcast_id: i64,
+    pub customer_id: i64,
+    pub telegram_id: i64,
+    pub variant_id: Option<i64>,
+    pub status: BroadcastRecipientStatus,
+    pub error_message: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What Telegram answered when the bot delivered a broadcast to one recipient
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastDeliveryReportBotRequest {
+    /// `sent`, `blocked` or `failed`
+    pub status: BroadcastRecipientStatus,
+    pub error_message: Option<String>,
+}
+
+/// Sends broadcast content to a single chat before scheduling the real broadcast
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "broadcast.ts", rename = "TestBroadcast")
+)]
+#[derive(Debug, Deserialize)]
+pub struct TestBroadcastRequest {
+    pub telegram_id: i64,
+    /// Defaults to the bot the recipient was last seen with
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub bot_id: Option<i64>,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(max = 1024, message = "Content text is too long"))
+    )]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub content_text: Option<String>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub content_image_id: Option<Uuid>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<BroadcastTextEntity>>", optional))]
+    #[serde(default)]
+    pub content_entities: Vec<BroadcastTextEntity>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<BroadcastMedia>>", optional))]
+    #[serde(default)]
+    pub content_media: Vec<BroadcastMedia>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<Vec<BroadcastButton>>>", optional))]
+    #[serde(default)]
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "broadcast.ts", rename = "BroadcastVariant")
+)]
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastVariantResponse {
+    pub id: i64,
+    pub broadcast_id: i64,
+    pub name: String,
+    pub content_text: Option<String>,
+    pub content_image_id: Option<Uuid>,
+    pub content_entities: Vec<BroadcastTextEntity>,
+    pub content_media: Vec<BroadcastMedia>,
+    pub content_buttons: Vec<Vec<BroadcastButton>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Delivery and conversion numbers of one variant, `variant_id` is null for the base content
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "broadcast.ts", rename = "BroadcastVariantStats")
+)]
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastVariantStatsResponse {
+    pub variant_id: Option<i64>,
+    pub name: String,
+    pub recipients: i64,
+    pub sent: i64,
+    /// Recipients who paid for an order within the conversion window after delivery
+    pub conversions: i64,
+    pub conversion_rate: f64,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "broadcast.ts",
+        rename = "PreviewBroadcastAudience"
+    )
+)]
+#[derive(Debug, Deserialize)]
+pub struct BroadcastAudiencePreviewRequest {
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    #[cfg_attr(feature = "ts", ts(type = "any"))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub filters: Option<JsonRawListQuery>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub segment_id: Option<i64>,
+    #[cfg_attr(
+        feature = "validate",
+        validate(range(min = 1, max = 50, message = "Sample size must be between 1 and 50"))
+    )]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub sample_size: Option<i64>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "broadcast.ts",
+        rename = "BroadcastAudiencePreview"
+    )
+)]
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastAudiencePreviewResponse {
+    pub total: i64,
+    pub sample: Vec<CustomerAdminResponse>,
+}
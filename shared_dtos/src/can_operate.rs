@@ -4,4 +4,6 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanOperateBotResponse {
     pub can_operate: bool,
+    /// False while the payment platform is unreachable; deposits and invoices should not be offered
+    pub payments_available: bool,
 }
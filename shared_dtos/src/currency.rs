@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "UPPERCASE"))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "currency.ts"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Rub,
+    Usd,
+    Eur,
+    Usdt,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 4] = [Currency::Rub, Currency::Usd, Currency::Eur, Currency::Usdt];
+
+    /// ISO 4217 code, `USDT` for Tether
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Rub => "RUB",
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Usdt => "USDT",
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Currency::Rub => "₽",
+            Currency::Usd => "$",
+            Currency::Eur => "€",
+            Currency::Usdt => "USDT",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.code().eq_ignore_ascii_case(code.trim()))
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "currency.ts", rename = "ExchangeRate")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRateResponse {
+    pub currency: Currency,
+    /// Units of `currency` for one unit of the base currency
+    pub rate: f64,
+    /// `base`, the rate source name or `static` for the configured fallback
+    pub source: String,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "currency.ts", rename = "ExchangeRates")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRatesResponse {
+    pub base_currency: Currency,
+    pub rates: Vec<ExchangeRateResponse>,
+}
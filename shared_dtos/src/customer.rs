@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::currency::Currency;
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomerBotResponse {
@@ -11,6 +13,10 @@ pub struct CustomerBotResponse {
     pub bot_is_blocked_by_user: bool,
     pub has_passed_captcha: bool,
     pub blocked_until: Option<DateTime<Utc>>,
+    /// Currency the bot shows amounts in, the store base currency unless chosen
+    pub display_currency: Currency,
+    /// Units of `display_currency` for one unit of `balance`
+    pub display_rate: f64,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -26,6 +32,7 @@ pub struct NewCustomerBotRequest {
 pub struct UpdateCustomerBotRequest {
     pub bot_is_blocked_by_user: Option<bool>,
     pub has_passed_captcha: Option<bool>,
+    pub display_currency: Option<Currency>,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -47,6 +54,8 @@ pub struct CustomerAdminResponse {
     pub last_seen_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// `null` while the customer uses the store base currency
+    pub display_currency: Option<Currency>,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
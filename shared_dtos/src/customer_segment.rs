@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
+
+use crate::broadcast::JsonRawListQuery;
+
+/// Saved customer filters, reusable as a broadcast audience
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "customer_segment.ts", rename = "CustomerSegment")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerSegmentResponse {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    #[cfg_attr(feature = "ts", ts(type = "any"))]
+    pub filters: serde_json::Value,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "customer_segment.ts",
+        rename = "NewCustomerSegment"
+    )
+)]
+#[derive(Debug, Deserialize)]
+pub struct NewCustomerSegmentRequest {
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(
+            min = 2,
+            max = 255,
+            message = "Segment name must be at least 2 characters and at most 255 characters long"
+        ))
+    )]
+    pub name: String,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(max = 1024, message = "Description is too long"))
+    )]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub description: Option<String>,
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    #[cfg_attr(feature = "ts", ts(type = "any"))]
+    pub filters: JsonRawListQuery,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "customer_segment.ts",
+        rename = "UpdateCustomerSegment"
+    )
+)]
+#[derive(Debug, Deserialize)]
+pub struct UpdateCustomerSegmentRequest {
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(
+            min = 2,
+            max = 255,
+            message = "Segment name must be at least 2 characters and at most 255 characters long"
+        ))
+    )]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub name: Option<String>,
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(max = 1024, message = "Description is too long"))
+    )]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    #[cfg_attr(feature = "ts", ts(type = "string | null"))]
+    #[serde(default, with = "double_option")]
+    pub description: Option<Option<String>>,
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    #[cfg_attr(feature = "ts", ts(type = "any"))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub filters: Option<JsonRawListQuery>,
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ok,
+    /// A non-critical dependency is down, the affected features are switched off
+    Degraded,
+    /// A critical dependency is down, the instance can't serve requests
+    Unavailable,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentStatus {
+    Up,
+    Down,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentHealthResponse {
+    pub name: String,
+    pub status: ComponentStatus,
+    pub critical: bool,
+    pub latency_ms: i64,
+    pub error: Option<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerHealthResponse {
+    pub worker: String,
+    pub last_run_at: DateTime<Utc>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_run_errors: i32,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub status: ReadinessStatus,
+    pub payments_available: bool,
+    pub components: Vec<ComponentHealthResponse>,
+    pub workers: Vec<WorkerHealthResponse>,
+    pub checked_at: DateTime<Utc>,
+}
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::{audit_log::AuditLogAdminResponse, currency::Currency};
+
 #[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
 #[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
@@ -59,6 +61,12 @@ pub enum PaymentDetails {
 pub struct GatewayBotResponse {
     pub name: PaymentSystem,
     pub display_name: String,
+    /// Deposit limits of the gateway, the amount keyboard only offers amounts within them
+    pub min_amount: f64,
+    pub max_amount: f64,
+    pub amount_step: f64,
+    /// Currency of the limits and of invoice amounts on this gateway
+    pub currency: Currency,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -74,6 +82,10 @@ pub struct PaymentInvoiceBotResponse {
     pub status: InvoiceStatus,
     pub created_at: DateTime<Utc>,
     pub gateway_invoice_id: String,
+    /// Currency of `amount`, `original_amount` is in the store base currency
+    pub currency: Currency,
+    /// Order bought with this invoice once it is paid (pay-for-product checkout)
+    pub checkout_order_id: Option<i64>,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -91,6 +103,9 @@ pub struct NewPaymentInvoiceBotRequest {
     )]
     pub amount: f64,
     pub gateway: PaymentSystem,
+    /// Currency of `amount`, the gateway currency when omitted
+    #[serde(default)]
+    pub currency: Option<Currency>,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -119,4 +134,136 @@ pub struct PaymentInvoiceAdminResponse {
     pub expires_at: DateTime<Utc>,
     pub gateway: PaymentSystem,
     pub gateway_invoice_id: String,
+    /// Currency of `amount`, `original_amount` is in the store base currency
+    pub currency: Currency,
+    /// Order bought with this invoice once it is paid (pay-for-product checkout)
+    pub checkout_order_id: Option<i64>,
+}
+
+/// Status event pushed by the gateway for an invoice, as received
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "invoice.ts", rename = "PaymentWebhookEvent")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentWebhookEventAdminResponse {
+    pub id: i64,
+    pub event_id: String,
+    pub payload: serde_json::Value,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+    /// Why processing failed, the event is retried on redelivery
+    pub error: Option<String>,
+}
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "invoice.ts"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentInvoiceEventKind {
+    StatusChanged,
+    GatewayResponse,
+    Webhook,
+    NotificationSent,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "invoice.ts", rename = "PaymentInvoiceEvent")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentInvoiceEventAdminResponse {
+    pub id: i64,
+    pub kind: PaymentInvoiceEventKind,
+    pub from_status: Option<InvoiceStatus>,
+    pub to_status: Option<InvoiceStatus>,
+    /// Gateway response or webhook body with requisites redacted, notification contents
+    pub payload: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "invoice.ts", rename = "PaymentInvoiceHistory")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentInvoiceHistoryAdminResponse {
+    pub invoice: PaymentInvoiceAdminResponse,
+    pub order_id: uuid::Uuid,
+    pub payment_details: serde_json::Value,
+    pub notification_sent_at: Option<DateTime<Utc>>,
+    pub receipt_requested_at: Option<DateTime<Utc>>,
+    pub receipt_submitted_at: Option<DateTime<Utc>>,
+    pub receipt_url: Option<String>,
+    pub dispute_opened_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Append-only log of status transitions, gateway responses and notifications
+    pub events: Vec<PaymentInvoiceEventAdminResponse>,
+    pub webhook_events: Vec<PaymentWebhookEventAdminResponse>,
+    /// Audit log of the invoice: operator resolutions and other recorded changes
+    pub actions: Vec<AuditLogAdminResponse>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "invoice.ts", rename = "ResolvePaymentInvoice")
+)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResolvePaymentInvoiceAdminRequest {
+    /// Required when failing an invoice
+    #[cfg_attr(
+        feature = "validate",
+        validate(length(max = 1000, message = "Reason must be at most 1000 characters"))
+    )]
+    pub reason: Option<String>,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "invoice.ts"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayCircuitState {
+    /// Gateway is offered to customers
+    Closed,
+    /// Gateway keeps failing and is hidden from the bot until `open_until`
+    Open,
+    /// Cooldown elapsed, the gateway is offered again and the next attempts decide
+    HalfOpen,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "invoice.ts", rename = "GatewayHealth")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatewayHealthAdminResponse {
+    pub gateway: PaymentSystem,
+    pub attempts: i64,
+    pub successes: i64,
+    /// Gateway errors and "no requisites" answers together
+    pub failures: i64,
+    pub no_requisites: i64,
+    /// Share of successful attempts, `None` without attempts in the window
+    pub success_rate: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub circuit: GatewayCircuitState,
+    pub open_until: Option<DateTime<Utc>>,
 }
@@ -1,5 +1,7 @@
+pub mod accounting_export;
 pub mod admin_user;
 pub mod analytics;
+pub mod api_key;
 pub mod audit_log;
 pub mod auth;
 pub mod balance_request;
@@ -8,9 +10,12 @@ pub mod broadcast;
 pub mod can_operate;
 pub mod captcha;
 pub mod category;
+pub mod currency;
 pub mod customer;
+pub mod customer_segment;
 pub mod dashboard;
 pub mod error;
+pub mod health;
 pub mod image;
 pub mod invoice;
 pub mod list_query;
@@ -19,6 +24,8 @@ pub mod notification;
 pub mod order;
 pub mod permission;
 pub mod product;
+pub mod product_sync;
+pub mod reconciliation;
 pub mod role;
 pub mod role_permission;
 pub mod settings;
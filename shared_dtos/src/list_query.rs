@@ -7,25 +7,65 @@ use serde_with::serde_as;
 use uuid::Uuid;
 
 /// A raw version of Filter used for initial deserialization
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RawFilter {
     pub field: String,
     pub op: Operator,
+    /// Not needed for `is_null`/`not_null`
+    #[serde(default)]
     pub value: FilterValue,
 }
 
+/// A boolean group of filters, e.g. `{"or": [{"field": ..}, {"not": {..}}]}`.
+/// Generic over the leaf so query-string and JSON filters share the shape.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawFilterGroup<T> {
+    And(Vec<RawFilterNode<T>>),
+    Or(Vec<RawFilterNode<T>>),
+    Not(Box<RawFilterNode<T>>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RawFilterNode<T> {
+    Filter(T),
+    Group(RawFilterGroup<T>),
+}
+
 /// A raw version of ListQuery for initial deserialization from a query string.
 /// Fields that require validation (filters, order_by) are taken as strings.
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct RawListQuery {
     #[serde(default)]
     pub filters: Vec<RawFilter>,
+    /// Nested groups, AND-ed with `filters`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<RawFilterGroup<RawFilter>>,
     #[serde(flatten)]
     pub pagination: Pagination,
     #[serde(default)]
     pub order_by: Option<String>,
     #[serde(default)]
     pub order_dir: OrderDir,
+    /// Opaque keyset cursor; an empty value requests the first page in cursor mode
+    #[serde(
+        default,
+        deserialize_with = "deserialize_cursor",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cursor: Option<String>,
+    /// Free-text search over the list's search columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+}
+
+// Query string parsers turn `cursor=` into `None`; keep it as an empty cursor instead
+fn deserialize_cursor<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -75,6 +115,11 @@ pub enum Operator {
     Like,
     Contains,
     In,
+    #[serde(rename = "is_null")]
+    IsNull,
+    #[serde(rename = "not_null")]
+    NotNull,
+    Between,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,6 +129,12 @@ pub enum FilterValue {
     Array(Vec<ScalarValue>),
 }
 
+impl Default for FilterValue {
+    fn default() -> Self {
+        FilterValue::Array(Vec::new())
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub enum ScalarValue {
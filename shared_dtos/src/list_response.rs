@@ -8,6 +8,8 @@ where
 {
     pub items: Vec<T>,
     pub total: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 #[cfg(not(feature = "openapi"))]
@@ -15,6 +17,8 @@ where
 pub struct ListResponse<T> {
     pub items: Vec<T>,
     pub total: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 #[cfg(test)]
@@ -44,7 +48,11 @@ mod tests {
         ];
         let total = 2;
 
-        let list_response = ListResponse { items, total };
+        let list_response = ListResponse {
+            items,
+            total,
+            next_cursor: None,
+        };
 
         let serialized = serde_json::to_string(&list_response).unwrap();
         let expected = r#"{"items":[{"id":1,"name":"Item 1"},{"id":2,"name":"Item 2"}],"total":2}"#;
@@ -1,8 +1,11 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::balance_request::StoreBalanceRequestType;
+use crate::{
+    balance_request::StoreBalanceRequestType, broadcast::BroadcastContent, currency::Currency,
+    order::PurchaseBotResponse, reconciliation::ReconciliationKindCount,
+};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum DispatchMessage {
@@ -10,6 +13,12 @@ pub enum DispatchMessage {
         message: String,
         image_id: Option<Uuid>,
     },
+    BroadcastMessage(BroadcastContent),
+    /// Broadcast delivery the bot reports back for `recipient_id`
+    BroadcastRecipientMessage {
+        recipient_id: i64,
+        content: BroadcastContent,
+    },
     ContactSupportNotification,
     DisputeFailedNotification,
     SubscriptionExpiringNotification {
@@ -19,6 +28,9 @@ pub enum DispatchMessage {
     InvoiceTroublesNotification {
         invoice_id: i64,
         amount: f64,
+        /// Currency of `amount`
+        #[serde(default)]
+        currency: Currency,
         expired_at: DateTime<Utc>,
     },
     RequestReceiptNotification {
@@ -26,6 +38,11 @@ pub enum DispatchMessage {
         is_first_time: bool,
         expired_at: DateTime<Utc>,
     },
+    /// A checkout order was bought with its paid invoice
+    CheckoutOrderFulfilledNotification {
+        order_id: i64,
+        purchase: PurchaseBotResponse,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -35,6 +52,11 @@ pub enum DispatchAdminMessage {
         amount: f64,
         r#type: StoreBalanceRequestType,
     },
+    ReconciliationDiscrepanciesNotification {
+        report_id: i64,
+        day: NaiveDate,
+        counts: Vec<ReconciliationKindCount>,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize)]
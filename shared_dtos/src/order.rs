@@ -2,7 +2,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{product::ProductDetails, user_subscription::UserSubscriptionDetails};
+use crate::{
+    invoice::PaymentSystem, product::ProductDetails, user_subscription::UserSubscriptionDetails,
+};
 
 #[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
 #[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
@@ -38,6 +40,19 @@ pub struct PurchaseBotRequest {
     pub telegram_id: i64,
 }
 
+/// Pays for a product through a gateway invoice instead of the balance
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckoutBotRequest {
+    pub product_id: i64,
+    pub telegram_id: i64,
+    pub gateway: PaymentSystem,
+    /// Invoice the full price instead of only the shortfall over the balance
+    #[serde(default)]
+    pub pay_full_price: bool,
+}
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderItemBotResponse {
@@ -62,6 +62,8 @@ pub struct ProductAdminResponse {
     pub fulfillment_image_id: Option<Uuid>,
     pub provider_name: String,
     pub external_id: Option<String>,
+    /// Set while an external product is missing from its supplier catalog
+    pub hidden_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: i64,
@@ -167,6 +169,8 @@ pub struct UpdateProductAdminRequest {
     pub external_id: Option<Option<String>>,
     #[cfg_attr(feature = "ts", ts(optional))]
     pub stock: Option<i64>,
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub hidden: Option<bool>,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "product_sync.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSyncTrigger {
+    Schedule,
+    Manual,
+}
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "product_sync.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSyncStatus {
+    // Requested from the admin panel, waiting for the sync worker
+    Queued,
+    Running,
+    Succeeded,
+    // Catalog was synced, but some products failed to update
+    Partial,
+    Failed,
+}
+
+/// One catalog sync of an external product provider
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "product_sync.ts", rename = "ProductSyncRun")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductSyncRunResponse {
+    pub id: i64,
+    pub provider_name: String,
+    pub trigger: ProductSyncTrigger,
+    pub status: ProductSyncStatus,
+    pub added: i32,
+    pub updated: i32,
+    pub hidden: i32,
+    pub restored: i32,
+    pub errors: Vec<String>,
+    pub triggered_by: Option<i64>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "product_sync.ts", rename = "RunProductSync")
+)]
+#[derive(Debug, Default, Deserialize)]
+pub struct RunProductSyncRequest {
+    /// Sync a single provider; all enabled providers when omitted
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub provider_name: Option<String>,
+}
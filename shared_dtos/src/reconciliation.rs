@@ -0,0 +1,104 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "reconciliation.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationDiscrepancyKind {
+    /// `customers.balance` differs from the sum of the customer's transactions
+    CustomerBalanceMismatch,
+    /// `user_balance_after` is not the previous customer balance plus `amount`
+    UserBalanceChainBroken,
+    /// `store_balance_after` is not the previous store balance plus `store_balance_delta`
+    StoreBalanceChainBroken,
+    /// Completed invoice without a deposit transaction
+    InvoiceMissingDeposit,
+    /// Completed invoice credited more than once
+    InvoiceDuplicateDeposits,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "reconciliation.ts"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationEntity {
+    Customer,
+    Transaction,
+    PaymentInvoice,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "reconciliation.ts",
+        rename = "ReconciliationDiscrepancy"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationDiscrepancyResponse {
+    pub kind: ReconciliationDiscrepancyKind,
+    pub entity: ReconciliationEntity,
+    pub entity_id: i64,
+    /// Balance or deposit count the row should have
+    pub expected: f64,
+    pub actual: f64,
+    /// Admin API request returning the offending row
+    pub link: String,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "reconciliation.ts",
+        rename = "ReconciliationReport"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReportResponse {
+    pub id: i64,
+    /// UTC day whose transactions and completed invoices were checked
+    pub day: NaiveDate,
+    pub transactions_checked: i32,
+    pub invoices_checked: i32,
+    pub customers_checked: i32,
+    pub discrepancies: Vec<ReconciliationDiscrepancyResponse>,
+    /// `None` for scheduled runs
+    pub triggered_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "reconciliation.ts", rename = "RunReconciliation")
+)]
+#[derive(Debug, Default, Deserialize)]
+pub struct RunReconciliationRequest {
+    /// Defaults to yesterday (UTC)
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub day: Option<NaiveDate>,
+    /// Alert the manager group when discrepancies are found
+    #[cfg_attr(feature = "ts", ts(as = "Option<bool>", optional))]
+    #[serde(default)]
+    pub notify: bool,
+}
+
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(export, export_to = "reconciliation.ts"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationKindCount {
+    pub kind: ReconciliationDiscrepancyKind,
+    pub count: i64,
+}
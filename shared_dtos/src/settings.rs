@@ -1,7 +1,10 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::rust::double_option;
 use uuid::Uuid;
 
+use crate::{currency::Currency, invoice::PaymentSystem};
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SettingsBotResponse {
@@ -170,3 +173,63 @@ pub struct UpdateBotSettingsAdminRequest {
     #[cfg_attr(feature = "ts", ts(optional))]
     pub bot_about: Option<String>,
 }
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(export, export_to = "settings.ts", rename = "PaymentGatewaySettings")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentGatewaySettingsAdminResponse {
+    pub gateway: PaymentSystem,
+    pub invoice_ttl_minutes: i32,
+    pub min_amount: f64,
+    pub max_amount: f64,
+    /// Deposits must be a multiple of it
+    pub amount_step: f64,
+    /// Open invoices a customer may have at once on this gateway, unlimited when `null`
+    pub max_open_invoices: Option<i32>,
+    /// Deposits per customer and UTC day on this gateway, unlimited when `null`
+    pub daily_deposit_limit: Option<f64>,
+    /// Currency the gateway charges in, amounts and limits above are in it
+    pub currency: Currency,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(
+    feature = "ts",
+    ts(
+        export,
+        export_to = "settings.ts",
+        rename = "UpdatePaymentGatewaySettings"
+    )
+)]
+#[cfg_attr(feature = "validate", derive(validator::Validate))]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UpdatePaymentGatewaySettingsAdminRequest {
+    #[cfg_attr(feature = "validate", validate(range(min = 1, max = 43200)))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub invoice_ttl_minutes: Option<i32>,
+    #[cfg_attr(feature = "validate", validate(range(min = 1.0, max = 1000000.0)))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub min_amount: Option<f64>,
+    #[cfg_attr(feature = "validate", validate(range(min = 1.0, max = 1000000.0)))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub max_amount: Option<f64>,
+    #[cfg_attr(feature = "validate", validate(range(min = 1.0, max = 100000.0)))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub amount_step: Option<f64>,
+    #[cfg_attr(feature = "validate", validate(range(min = 1, max = 100)))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    #[cfg_attr(feature = "ts", ts(type = "number | null"))]
+    #[serde(default, with = "double_option")]
+    pub max_open_invoices: Option<Option<i32>>,
+    #[cfg_attr(feature = "validate", validate(range(min = 1.0)))]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    #[cfg_attr(feature = "ts", ts(type = "number | null"))]
+    #[serde(default, with = "double_option")]
+    pub daily_deposit_limit: Option<Option<f64>>,
+}
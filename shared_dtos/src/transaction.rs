@@ -33,6 +33,8 @@ pub struct TransactionAdminResponse {
     pub id: i64,
     pub customer_id: Option<i64>,
     pub order_id: Option<i64>,
+    /// Invoice credited by a deposit
+    pub payment_invoice_id: Option<i64>,
     pub r#type: TransactionType,
     pub amount: f64,
     pub store_balance_delta: f64,
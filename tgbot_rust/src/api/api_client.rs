@@ -1,10 +1,11 @@
 use axum::http::HeaderMap;
 use bytes::Bytes;
-use reqwest::{Client, Method, RequestBuilder, Response, Url, multipart};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode, Url, header, multipart};
 use serde::{Serialize, de::DeserializeOwned};
 use std::time::Instant;
 
 use super::api_errors::{ApiClientError, ApiClientResult};
+use crate::telemetry;
 
 pub struct ApiClient {
     base_url: Url,
@@ -12,6 +13,7 @@ pub struct ApiClient {
 }
 
 const SLOW_BACKEND_REQUEST_WARN_MS: u128 = 1500;
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
 
 impl ApiClient {
     pub fn new(base_url: &str, headers: HeaderMap) -> ApiClientResult<Self> {
@@ -49,6 +51,38 @@ impl ApiClient {
         response.bytes().await.map_err(Into::into)
     }
 
+    /// Conditional GET, `None` when the resource still matches `etag`.
+    /// Returns the body together with its current ETag.
+    pub async fn get_bytes_if_none_match(
+        &self,
+        endpoint: &str,
+        etag: Option<&str>,
+    ) -> ApiClientResult<Option<(Bytes, Option<String>)>> {
+        let url = self.base_url.join(endpoint)?;
+        let mut request = self.client.get(url);
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        let response = Self::send_with_timing(request, "GET", endpoint).await?;
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let url = response.url().to_string();
+            let body = response.text().await?;
+            return Err(ApiClientError::Unsuccessful(format!(
+                "Request to {url} failed with status code: {status}, body: {body}",
+            )));
+        }
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(Some((response.bytes().await?, etag)))
+    }
+
     pub async fn post_with_multipart<T>(
         &self,
         endpoint: &str,
@@ -74,6 +108,27 @@ impl ApiClient {
         Self::parse_response(response).await
     }
 
+    /// Same as `post_with_body`, but the backend replays the first response for repeated keys
+    pub async fn post_with_body_idempotent<T, B>(
+        &self,
+        endpoint: &str,
+        body: &B,
+        idempotency_key: &str,
+    ) -> ApiClientResult<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+        B: Serialize + ?Sized,
+    {
+        let url = self.base_url.join(endpoint)?;
+        let request = self
+            .client
+            .post(url)
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key)
+            .json(body);
+        let response = Self::send_with_timing(request, "POST", endpoint).await?;
+        Self::parse_response(response).await
+    }
+
     pub async fn post<T>(&self, endpoint: &str) -> ApiClientResult<T>
     where
         T: DeserializeOwned + Send + 'static,
@@ -123,6 +178,7 @@ impl ApiClient {
         let response = request.send().await?;
         let elapsed_ms = started_at.elapsed().as_millis();
         let status = response.status().as_u16();
+        telemetry::record_backend_request(method, endpoint, status, started_at);
 
         if elapsed_ms >= SLOW_BACKEND_REQUEST_WARN_MS {
             tracing::warn!(
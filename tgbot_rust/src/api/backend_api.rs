@@ -6,28 +6,47 @@ use shared_dtos::{
     analytics::BotAnalyticsBotResponse,
     balance_request::{CompleteStoreBalanceRequestBotRequest, RejectStoreBalanceRequestBotRequest},
     bot::{BotBotResponse, NewBotBotRequest, UpdateBotBotRequest},
+    broadcast::{BroadcastDeliveryReportBotRequest, BroadcastRecipientStatus},
+    can_operate::CanOperateBotResponse,
     captcha::CaptchaBotResponse,
     category::CategoryBotResponse,
+    currency::{Currency, ExchangeRatesResponse},
     customer::{CustomerBotResponse, NewCustomerBotRequest, UpdateCustomerBotRequest},
     invoice::{
         GatewayBotResponse, NewPaymentInvoiceBotRequest, PaymentInvoiceBotResponse, PaymentSystem,
     },
     list_query::{FilterValue, Operator, RawFilter, RawListQuery, ScalarValue},
     list_response::ListResponse,
-    order::{EnrichedOrderBotResponse, PurchaseBotRequest, PurchaseBotResponse},
+    order::{
+        CheckoutBotRequest, EnrichedOrderBotResponse, PurchaseBotRequest, PurchaseBotResponse,
+    },
     product::ProductBotResponse,
     settings::{SettingsBotResponse, UpdateBotManagedSettingsBotRequest},
     user_subscription::UserSubscriptionBotResponse,
 };
 use uuid::Uuid;
 
-use crate::api::{
-    api_client::ApiClient,
-    api_errors::{ApiClientError, ApiClientResult},
+use crate::{
+    api::{
+        api_client::ApiClient,
+        api_errors::{ApiClientError, ApiClientResult},
+    },
+    file_id_cache::FileIdCache,
 };
 
 pub struct BackendApi {
     api_client: ApiClient,
+    file_id_cache: Option<FileIdCache>,
+}
+
+/// Result of fetching an image the caller may already have
+pub enum ImageDownload {
+    /// Content still matches the hash passed in
+    NotModified,
+    Downloaded {
+        bytes: Bytes,
+        hash: Option<String>,
+    },
 }
 
 impl BackendApi {
@@ -42,7 +61,20 @@ impl BackendApi {
             HeaderValue::from_static("application/json"),
         );
         let api_client = ApiClient::new(base_url, headers)?;
-        Ok(Self { api_client })
+        Ok(Self {
+            api_client,
+            file_id_cache: None,
+        })
+    }
+
+    /// Reuses Telegram file ids of images uploaded by this bot, see `bot::utils::send_msg`
+    pub fn with_file_id_cache(mut self, file_id_cache: FileIdCache) -> Self {
+        self.file_id_cache = Some(file_id_cache);
+        self
+    }
+
+    pub fn file_id_cache(&self) -> Option<&FileIdCache> {
+        self.file_id_cache.as_ref()
     }
 
     pub async fn register_user(&self, telegram_id: i64) -> ApiClientResult<CustomerBotResponse> {
@@ -92,6 +124,27 @@ impl BackendApi {
         Ok(res)
     }
 
+    pub async fn get_exchange_rates(&self) -> ApiClientResult<ExchangeRatesResponse> {
+        self.api_client
+            .get::<ExchangeRatesResponse>("bot/settings/exchange-rates")
+            .await
+    }
+
+    pub async fn set_display_currency(
+        &self,
+        telegram_id: i64,
+        currency: Currency,
+    ) -> ApiClientResult<CustomerBotResponse> {
+        self.update_customer(
+            telegram_id,
+            &UpdateCustomerBotRequest {
+                display_currency: Some(currency),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
     pub async fn update_manager_group_chat_id(
         &self,
         chat_id: i64,
@@ -160,6 +213,12 @@ impl BackendApi {
             .map(|settings| settings.bot_messages_returning_user_welcome)
     }
 
+    pub async fn can_operate(&self) -> ApiClientResult<CanOperateBotResponse> {
+        self.api_client
+            .get::<CanOperateBotResponse>("bot/can-operate")
+            .await
+    }
+
     pub async fn get_payment_gateways(&self) -> ApiClientResult<ListResponse<GatewayBotResponse>> {
         self.api_client
             .get::<ListResponse<GatewayBotResponse>>("bot/gateways")
@@ -200,15 +259,18 @@ impl BackendApi {
         gateway: &PaymentSystem,
         amount: f64,
         telegram_id: i64,
+        idempotency_key: &str,
     ) -> ApiClientResult<PaymentInvoiceBotResponse> {
         self.api_client
-            .post_with_body::<PaymentInvoiceBotResponse, _>(
+            .post_with_body_idempotent::<PaymentInvoiceBotResponse, _>(
                 "bot/invoices",
                 &NewPaymentInvoiceBotRequest {
                     gateway: *gateway,
                     amount,
                     telegram_id,
+                    currency: None,
                 },
+                idempotency_key,
             )
             .await
     }
@@ -263,6 +325,18 @@ impl BackendApi {
             .await
     }
 
+    pub async fn search_products(
+        &self,
+        search: &str,
+    ) -> ApiClientResult<ListResponse<ProductBotResponse>> {
+        self.api_client
+            .get_with_qs::<ListResponse<ProductBotResponse>>(
+                "bot/products",
+                &[("search", search), ("page_size", "20")],
+            )
+            .await
+    }
+
     pub async fn get_product(&self, product_id: i64) -> ApiClientResult<ProductBotResponse> {
         self.api_client
             .get::<ProductBotResponse>(&format!("bot/products/{product_id}"))
@@ -273,18 +347,62 @@ impl BackendApi {
         self.api_client.get_bytes(&format!("images/{id}")).await
     }
 
+    /// Downloads an image unless its content hash is still `hash`
+    pub async fn get_image_if_changed(
+        &self,
+        id: &Uuid,
+        hash: Option<&str>,
+    ) -> ApiClientResult<ImageDownload> {
+        let etag = hash.map(|hash| format!("\"{hash}\""));
+        let response = self
+            .api_client
+            .get_bytes_if_none_match(&format!("images/{id}"), etag.as_deref())
+            .await?;
+        Ok(match response {
+            None => ImageDownload::NotModified,
+            Some((bytes, etag)) => ImageDownload::Downloaded {
+                bytes,
+                hash: etag.map(|etag| etag.trim_matches('"').to_string()),
+            },
+        })
+    }
+
     pub async fn buy_product(
         &self,
         telegram_id: i64,
         product_id: i64,
+        idempotency_key: &str,
     ) -> ApiClientResult<PurchaseBotResponse> {
         self.api_client
-            .post_with_body::<PurchaseBotResponse, _>(
+            .post_with_body_idempotent::<PurchaseBotResponse, _>(
                 "bot/orders",
                 &PurchaseBotRequest {
                     telegram_id,
                     product_id,
                 },
+                idempotency_key,
+            )
+            .await
+    }
+
+    /// Creates a pending order for the product and an invoice paying for it
+    pub async fn checkout_product(
+        &self,
+        telegram_id: i64,
+        product_id: i64,
+        gateway: PaymentSystem,
+        idempotency_key: &str,
+    ) -> ApiClientResult<PaymentInvoiceBotResponse> {
+        self.api_client
+            .post_with_body_idempotent::<PaymentInvoiceBotResponse, _>(
+                "bot/orders/checkout",
+                &CheckoutBotRequest {
+                    telegram_id,
+                    product_id,
+                    gateway,
+                    pay_full_price: false,
+                },
+                idempotency_key,
             )
             .await
     }
@@ -391,6 +509,23 @@ impl BackendApi {
             .await
     }
 
+    pub async fn report_broadcast_delivery(
+        &self,
+        recipient_id: i64,
+        status: BroadcastRecipientStatus,
+        error_message: Option<String>,
+    ) -> ApiClientResult<()> {
+        self.api_client
+            .post_with_body::<(), _>(
+                &format!("bot/broadcasts/recipients/{recipient_id}/delivery"),
+                &BroadcastDeliveryReportBotRequest {
+                    status,
+                    error_message,
+                },
+            )
+            .await
+    }
+
     pub async fn update_customer(
         &self,
         telegram_id: i64,
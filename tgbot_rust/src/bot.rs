@@ -10,6 +10,8 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use shared_dtos::{
     bot::UpdateBotBotRequest,
+    broadcast::BroadcastRecipientStatus,
+    currency::Currency,
     customer::UpdateCustomerBotRequest,
     invoice::{PaymentDetails, PaymentSystem},
     notification::{DispatchMessage, DispatchMessagePayload},
@@ -43,9 +45,10 @@ use crate::{
             add_bot_handler::add_bot_handler, amount_input_handler::amount_input_handler,
             balance::balance_handler, bot_stats_handler::bot_stats_handler, buy::buy_handler,
             cancel_invoice::cancel_invoice_handler, captcha_answer::captcha_answer_handler,
-            catalog::catalog_handler, confirm_invoice::confirm_invoice_handler,
-            delete_bot_handler::delete_bot_handler, deposit_amount::deposit_amount_handler,
-            deposit_confirm::deposit_confirm_handler, deposit_gateway::deposit_gateway_handler,
+            catalog::catalog_handler, checkout::checkout_handler,
+            confirm_invoice::confirm_invoice_handler, delete_bot_handler::delete_bot_handler,
+            deposit_amount::deposit_amount_handler, deposit_confirm::deposit_confirm_handler,
+            deposit_gateway::deposit_gateway_handler, display_currency::select_currency_handler,
             fallback_bot_msg::fallback_bot_msg, main_menu::main_menu_handler,
             main_menu::main_menu_text_handler, my_orders::my_orders_handler,
             my_payments::my_payments_handler, my_subscriptions::my_subscriptions_handler,
@@ -53,17 +56,19 @@ use crate::{
             receipt_requested_screen_handler::receipt_requested_screen_handler,
             receipt_submitted_handler::receipt_submitted_handler,
             referral_bot_token_handler::referral_bot_token_handler,
-            referral_program_handler::referral_program_handler,
+            referral_program_handler::referral_program_handler, search::search_handler,
             show_bot_info_handler::show_bot_info_handler, start::start_handler,
             support::support_handler,
         },
         keyboards::back_to_main_menu::back_to_main_menu_inline_keyboard,
         utils::{
             MessageImage, MsgBy, build_invoice_payment_text, build_receipt_upload_instruction_text,
-            edit_msg, invoice_troubles_paragraph, send_msg, support_operator_buttons,
+            edit_msg, invoice_troubles_paragraph, purchase_success_text, send_broadcast_msg,
+            send_msg, support_operator_buttons,
         },
     },
     errors::{AppError, AppResult},
+    telemetry,
 };
 
 pub mod handlers;
@@ -89,6 +94,8 @@ pub struct InvoiceData {
     pub id: i64,
     pub details: Option<PaymentDetails>,
     pub gateway_invoice_id: String,
+    #[serde(default)]
+    pub currency: Currency,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -210,6 +217,10 @@ pub enum CallbackData {
     Buy {
         id: i64,
     },
+    #[serde(rename = "co")]
+    Checkout {
+        id: i64,
+    },
     ConfirmPayment {
         id: i64,
     },
@@ -228,6 +239,12 @@ pub enum CallbackData {
         id: i64,
     },
     IncreaseAmountBy10,
+    ToSelectCurrency,
+    #[serde(rename = "sdc")]
+    SetDisplayCurrency {
+        #[serde(rename = "c")]
+        currency: Currency,
+    },
 }
 
 impl CallbackData {
@@ -263,6 +280,7 @@ impl From<String> for CallbackData {
 #[command(rename_rule = "lowercase")]
 pub enum Command {
     Start,
+    Search(String),
 }
 
 type MyDialogue = Dialogue<BotState, RedisStorage<Json>>;
@@ -290,6 +308,7 @@ fn callback_kind(data: &CallbackData) -> &'static str {
         CallbackData::ToReceiptRequested { .. } => "to_receipt_requested",
         CallbackData::ToOrderDetails { .. } => "to_order_details",
         CallbackData::Buy { .. } => "buy",
+        CallbackData::Checkout { .. } => "checkout",
         CallbackData::ConfirmPayment { .. } => "confirm_payment",
         CallbackData::CancelPayment { .. } => "cancel_payment",
         CallbackData::AddBot => "add_bot",
@@ -298,12 +317,16 @@ fn callback_kind(data: &CallbackData) -> &'static str {
         CallbackData::SetBotPrimary { .. } => "set_bot_primary",
         CallbackData::DeleteBot { .. } => "delete_bot",
         CallbackData::IncreaseAmountBy10 => "increase_amount_by_10",
+        CallbackData::ToSelectCurrency => "to_select_currency",
+        CallbackData::SetDisplayCurrency { .. } => "set_display_currency",
     }
 }
 
 fn dispatch_message_kind(message: &DispatchMessage) -> &'static str {
     match message {
         DispatchMessage::GenericMessage { .. } => "generic_message",
+        DispatchMessage::BroadcastMessage(_)
+        | DispatchMessage::BroadcastRecipientMessage { .. } => "broadcast_message",
         DispatchMessage::ContactSupportNotification => "contact_support_notification",
         DispatchMessage::DisputeFailedNotification => "dispute_failed_notification",
         DispatchMessage::SubscriptionExpiringNotification { .. } => {
@@ -311,6 +334,9 @@ fn dispatch_message_kind(message: &DispatchMessage) -> &'static str {
         }
         DispatchMessage::InvoiceTroublesNotification { .. } => "invoice_troubles_notification",
         DispatchMessage::RequestReceiptNotification { .. } => "request_receipt_notification",
+        DispatchMessage::CheckoutOrderFulfilledNotification { .. } => {
+            "checkout_order_fulfilled_notification"
+        }
     }
 }
 
@@ -349,8 +375,11 @@ pub async fn run_bot(
         )
         .await;
     }
-    bot.set_my_commands(vec![BotCommand::new("start", "Начать")])
-        .await?;
+    bot.set_my_commands(vec![
+        BotCommand::new("start", "Начать"),
+        BotCommand::new("search", "Поиск товаров"),
+    ])
+    .await?;
     let redis_url = format!(
         "redis://{}:{}",
         app_state.config.redis_host, app_state.config.redis_port
@@ -514,6 +543,7 @@ pub async fn run_bot(
                         &MsgBy::CallbackQuery(&q),
                         api_client,
                         bot_state,
+                        gateway,
                     )
                     .await?;
                 }
@@ -724,6 +754,7 @@ pub async fn run_bot(
                                 id,
                                 details: invoice.payment_details,
                                 gateway_invoice_id: invoice.gateway_invoice_id,
+                                currency: invoice.currency,
                             }),
                         },
                         ..bot_state
@@ -754,6 +785,10 @@ pub async fn run_bot(
                 CallbackData::Buy { id } => {
                     buy_handler(bot, dialogue, q, api_client, id).await?;
                 }
+                CallbackData::Checkout { id } => {
+                    checkout_handler(bot, dialogue, q, api_client, bot_state, app_state, id)
+                        .await?;
+                }
                 CallbackData::CancelPayment { id } => {
                     cancel_invoice_handler(bot, dialogue, q, api_client, id).await?;
                 }
@@ -784,10 +819,20 @@ pub async fn run_bot(
                 CallbackData::ShowBotInfo { id } => {
                     show_bot_info_handler(bot, dialogue, q, api_client, id).await?;
                 }
+                CallbackData::ToSelectCurrency => {
+                    select_currency_handler(bot, dialogue, q, api_client).await?;
+                }
+                CallbackData::SetDisplayCurrency { currency } => {
+                    api_client
+                        .set_display_currency(telegram_id.0 as i64, currency)
+                        .await?;
+                    balance_handler(bot, dialogue, q, api_client).await?;
+                }
             }
 
             let handler_elapsed_ms = handler_started.elapsed().as_millis();
             let total_elapsed_ms = started_at.elapsed().as_millis();
+            telemetry::record_callback(callback_type, started_at);
             if total_elapsed_ms >= SLOW_CALLBACK_WARN_MS {
                 tracing::warn!(
                     callback_type,
@@ -913,6 +958,20 @@ async fn command_handler(
 ) -> AppResult<()> {
     match cmd {
         Command::Start => handle_start(bot, msg, dialogue, api_client, fallback_bot_username).await,
+        Command::Search(query) => {
+            let prev_state = dialogue.get_or_default().await.unwrap_or_default();
+            // Customers who have not started the bot yet go through the captcha first
+            if prev_state.step == BotStep::Initial {
+                return handle_start(bot, msg, dialogue, api_client, fallback_bot_username).await;
+            }
+            dialogue
+                .update(BotState {
+                    step: BotStep::MainMenu,
+                    ..prev_state
+                })
+                .await?;
+            search_handler(bot, dialogue, msg, api_client, query).await
+        }
     }
 }
 
@@ -971,6 +1030,7 @@ async fn start_redis_listener(
 
     tokio::spawn(async move {
         while let Some(queued) = rx.recv().await {
+            telemetry::dispatch_queue_depth(bot_id, -1.0);
             let queue_wait_ms = queued.enqueued_at.elapsed().as_millis();
             let msg_type = dispatch_message_kind(&queued.payload.message);
             let started_at = Instant::now();
@@ -982,6 +1042,7 @@ async fn start_redis_listener(
             )
             .await;
             let handle_elapsed_ms = started_at.elapsed().as_millis();
+            telemetry::record_dispatch(msg_type, queued.enqueued_at, started_at);
 
             if let Err(e) = res {
                 tracing::error!(
@@ -1015,12 +1076,15 @@ async fn start_redis_listener(
     while let Some(msg) = msg_stream.next().await {
         if let Ok(payload_str) = msg.get_payload::<String>()
             && let Ok(parsed) = serde_json::from_str::<DispatchMessagePayload>(&payload_str)
-            && let Err(e) = tx.send(QueuedDispatchMessage {
+        {
+            telemetry::dispatch_queue_depth(bot_id, 1.0);
+            if let Err(e) = tx.send(QueuedDispatchMessage {
                 payload: parsed,
                 enqueued_at: Instant::now(),
-            })
-        {
-            tracing::error!("Error sending message: {e}");
+            }) {
+                telemetry::dispatch_queue_depth(bot_id, -1.0);
+                tracing::error!("Error sending message: {e}");
+            }
         }
     }
 
@@ -1052,139 +1116,189 @@ async fn handle_msg(
 
     let support_operator_rows = support_operator_buttons(&support_operators);
 
-    let (msg, img, keyboard) = match payload.message {
-        DispatchMessage::GenericMessage { image_id, message } => (
-            message,
-            image_id.map(MessageImage::Uuid),
-            back_to_main_menu_inline_keyboard(),
-        ),
-        DispatchMessage::ContactSupportNotification => (
-            "Мы не смогли увидеть Ваш платеж.\nПожалуйста, свяжитесь с оператором.".to_string(),
-            None,
-            InlineKeyboardMarkup::new(
-                [vec![InlineKeyboardButton::callback(
-                    "⬅️ Главное меню",
-                    CallbackData::ToMainMenu,
-                )]]
-                .into_iter()
-                .chain(support_operator_rows.clone().into_iter())
-                .collect::<Vec<_>>(),
-            ),
-        ),
-        DispatchMessage::DisputeFailedNotification => (
-            "Мы не смогли проверить ваш платеж.\nПожалуйста, свяжитесь с оператором.".to_string(),
-            None,
-            InlineKeyboardMarkup::new(vec![
-                vec![InlineKeyboardButton::callback(
-                    "Оператор",
-                    CallbackData::ToSupport,
-                )],
-                vec![InlineKeyboardButton::callback(
-                    "⬅️ Главное меню",
-                    CallbackData::ToMainMenu,
-                )],
-            ]),
-        ),
-        DispatchMessage::SubscriptionExpiringNotification {
-            expires_at,
-            product_name,
-        } => {
-            let product_suffix = product_name
-                .as_ref()
-                .map(|name| format!(" \"{name}\""))
-                .unwrap_or_default();
-            let expires_at_text = expires_at.format("%d.%m.%Y %H:%M UTC").to_string();
-            (
-                format!(
-                    "Ваша подписка {product_suffix} скоро закончится.\n\
-                     Дата окончания: {expires_at_text}.\n\
-                     Чтобы не потерять доступ, продлите подписку заранее."
-                ),
-                None,
-                back_to_main_menu_inline_keyboard(),
-            )
+    let mut broadcast_recipient_id = None;
+    let send_result = match payload.message {
+        DispatchMessage::BroadcastMessage(content) => {
+            send_broadcast_msg(&api_client, &dialogue, &bot, content).await
         }
-        DispatchMessage::InvoiceTroublesNotification {
-            amount,
-            invoice_id,
-            expired_at,
+        DispatchMessage::BroadcastRecipientMessage {
+            recipient_id,
+            content,
         } => {
-            let seconds_left = (expired_at - Utc::now()).num_seconds().max(0);
-            let minutes_left = (seconds_left as f64 / 60.0).ceil() as i64;
-            let rounded_up_to_5 = ((minutes_left + 4) / 5) * 5;
-            let text = match &state.step {
-                BotStep::DepositConfirm {
-                    amount: saved_amount,
-                    invoice: Some(invoice_data),
-                    ..
-                } if invoice_data.id == invoice_id => {
-                    build_invoice_payment_text(invoice_data, *saved_amount, Some(rounded_up_to_5))
+            broadcast_recipient_id = Some(recipient_id);
+            send_broadcast_msg(&api_client, &dialogue, &bot, content).await
+        }
+        message => {
+            let (msg, img, keyboard) = match message {
+                DispatchMessage::BroadcastMessage(_)
+                | DispatchMessage::BroadcastRecipientMessage { .. } => {
+                    unreachable!("broadcasts are sent above")
                 }
-                _ => invoice_troubles_paragraph(amount, rounded_up_to_5),
-            };
-            (
-                text,
-                None,
-                InlineKeyboardMarkup::new(
-                    [
+                DispatchMessage::GenericMessage { image_id, message } => (
+                    message,
+                    image_id.map(MessageImage::Uuid),
+                    back_to_main_menu_inline_keyboard(),
+                ),
+                DispatchMessage::ContactSupportNotification => (
+                    "Мы не смогли увидеть Ваш платеж.\nПожалуйста, свяжитесь с оператором."
+                        .to_string(),
+                    None,
+                    InlineKeyboardMarkup::new(
+                        [vec![InlineKeyboardButton::callback(
+                            "⬅️ Главное меню",
+                            CallbackData::ToMainMenu,
+                        )]]
+                        .into_iter()
+                        .chain(support_operator_rows.clone().into_iter())
+                        .collect::<Vec<_>>(),
+                    ),
+                ),
+                DispatchMessage::DisputeFailedNotification => (
+                    "Мы не смогли проверить ваш платеж.\nПожалуйста, свяжитесь с оператором."
+                        .to_string(),
+                    None,
+                    InlineKeyboardMarkup::new(vec![
                         vec![InlineKeyboardButton::callback(
-                            "Оплатил",
-                            CallbackData::ConfirmPayment { id: invoice_id },
+                            "Оператор",
+                            CallbackData::ToSupport,
                         )],
                         vec![InlineKeyboardButton::callback(
-                            "Отменить платеж",
-                            CallbackData::CancelPayment { id: invoice_id },
+                            "⬅️ Главное меню",
+                            CallbackData::ToMainMenu,
                         )],
-                    ]
-                    .into_iter()
-                    .chain(support_operator_rows.clone().into_iter())
-                    .collect::<Vec<_>>(),
-                ),
-            )
-        }
-        DispatchMessage::RequestReceiptNotification {
-            invoice_id,
-            is_first_time,
-            expired_at,
-        } => {
-            dialogue
-                .update(BotState {
-                    step: BotStep::ReceiptRequested { invoice_id },
-                    ..state
-                })
-                .await?;
-            let seconds_left = (expired_at - Utc::now()).num_seconds().max(0);
-            let minutes_left = (seconds_left as f64 / 60.0).ceil() as i64;
-            let rounded_up_to_5 = ((minutes_left + 4) / 5) * 5;
-            (
-                build_receipt_upload_instruction_text(
-                    (!is_first_time).then_some(rounded_up_to_5),
-                    false,
+                    ]),
                 ),
-                None,
-                InlineKeyboardMarkup::new(
-                    [vec![InlineKeyboardButton::callback(
-                        "⬅️ Главное меню",
-                        CallbackData::ToMainMenu,
-                    )]]
-                    .into_iter()
-                    .chain(support_operator_rows.into_iter())
-                    .collect::<Vec<_>>(),
+                DispatchMessage::SubscriptionExpiringNotification {
+                    expires_at,
+                    product_name,
+                } => {
+                    let product_suffix = product_name
+                        .as_ref()
+                        .map(|name| format!(" \"{name}\""))
+                        .unwrap_or_default();
+                    let expires_at_text = expires_at.format("%d.%m.%Y %H:%M UTC").to_string();
+                    (
+                        format!(
+                            "Ваша подписка {product_suffix} скоро закончится.\n\
+                     Дата окончания: {expires_at_text}.\n\
+                     Чтобы не потерять доступ, продлите подписку заранее."
+                        ),
+                        None,
+                        back_to_main_menu_inline_keyboard(),
+                    )
+                }
+                DispatchMessage::InvoiceTroublesNotification {
+                    amount,
+                    currency,
+                    invoice_id,
+                    expired_at,
+                } => {
+                    let seconds_left = (expired_at - Utc::now()).num_seconds().max(0);
+                    let minutes_left = (seconds_left as f64 / 60.0).ceil() as i64;
+                    let rounded_up_to_5 = ((minutes_left + 4) / 5) * 5;
+                    let text = match &state.step {
+                        BotStep::DepositConfirm {
+                            amount: saved_amount,
+                            invoice: Some(invoice_data),
+                            ..
+                        } if invoice_data.id == invoice_id => build_invoice_payment_text(
+                            invoice_data,
+                            *saved_amount,
+                            Some(rounded_up_to_5),
+                        ),
+                        _ => invoice_troubles_paragraph(amount, currency, rounded_up_to_5),
+                    };
+                    (
+                        text,
+                        None,
+                        InlineKeyboardMarkup::new(
+                            [
+                                vec![InlineKeyboardButton::callback(
+                                    "Оплатил",
+                                    CallbackData::ConfirmPayment { id: invoice_id },
+                                )],
+                                vec![InlineKeyboardButton::callback(
+                                    "Отменить платеж",
+                                    CallbackData::CancelPayment { id: invoice_id },
+                                )],
+                            ]
+                            .into_iter()
+                            .chain(support_operator_rows.clone().into_iter())
+                            .collect::<Vec<_>>(),
+                        ),
+                    )
+                }
+                DispatchMessage::CheckoutOrderFulfilledNotification { order_id, purchase } => (
+                    format!(
+                        "Оплата заказа #{order_id} получена.\n\n{}",
+                        purchase_success_text(&purchase)
+                    ),
+                    purchase.fulfilled_image_id.map(MessageImage::Uuid),
+                    back_to_main_menu_inline_keyboard(),
                 ),
+                DispatchMessage::RequestReceiptNotification {
+                    invoice_id,
+                    is_first_time,
+                    expired_at,
+                } => {
+                    dialogue
+                        .update(BotState {
+                            step: BotStep::ReceiptRequested { invoice_id },
+                            ..state
+                        })
+                        .await?;
+                    let seconds_left = (expired_at - Utc::now()).num_seconds().max(0);
+                    let minutes_left = (seconds_left as f64 / 60.0).ceil() as i64;
+                    let rounded_up_to_5 = ((minutes_left + 4) / 5) * 5;
+                    (
+                        build_receipt_upload_instruction_text(
+                            (!is_first_time).then_some(rounded_up_to_5),
+                            false,
+                        ),
+                        None,
+                        InlineKeyboardMarkup::new(
+                            [vec![InlineKeyboardButton::callback(
+                                "⬅️ Главное меню",
+                                CallbackData::ToMainMenu,
+                            )]]
+                            .into_iter()
+                            .chain(support_operator_rows.into_iter())
+                            .collect::<Vec<_>>(),
+                        ),
+                    )
+                }
+            };
+
+            send_msg(
+                &api_client,
+                &dialogue,
+                &bot,
+                &msg,
+                img,
+                ReplyMarkup::InlineKeyboard(keyboard),
             )
+            .await
         }
     };
 
-    if let Err(err) = send_msg(
-        &api_client,
-        &dialogue,
-        &bot,
-        &msg,
-        img,
-        ReplyMarkup::InlineKeyboard(keyboard),
-    )
-    .await
-    {
+    // The backend only knows the broadcast was handed over, the outcome comes from Telegram
+    if let Some(recipient_id) = broadcast_recipient_id {
+        let (status, error_message) = match &send_result {
+            Ok(_) => (BroadcastRecipientStatus::Sent, None),
+            Err(err @ AppError::RequestError(RequestError::Api(ApiError::BotBlocked))) => {
+                (BroadcastRecipientStatus::Blocked, Some(err.to_string()))
+            }
+            Err(err) => (BroadcastRecipientStatus::Failed, Some(err.to_string())),
+        };
+        if let Err(e) = api_client
+            .report_broadcast_delivery(recipient_id, status, error_message)
+            .await
+        {
+            tracing::error!(recipient_id, "Error reporting broadcast delivery: {e}");
+        }
+    }
+
+    if let Err(err) = send_result {
         match err {
             AppError::RequestError(RequestError::Api(ApiError::BotBlocked)) => {
                 tracing::info!("Bot is blocked by user: {}", payload.telegram_id);
@@ -1,4 +1,5 @@
 pub mod add_bot_handler;
+pub mod alternative_gateways;
 pub mod amount_input_handler;
 pub mod balance;
 pub mod bot_stats_handler;
@@ -6,11 +7,13 @@ pub mod buy;
 pub mod cancel_invoice;
 pub mod captcha_answer;
 pub mod catalog;
+pub mod checkout;
 pub mod confirm_invoice;
 pub mod delete_bot_handler;
 pub mod deposit_amount;
 pub mod deposit_confirm;
 pub mod deposit_gateway;
+pub mod display_currency;
 pub mod fallback_bot_msg;
 pub mod increase_amount_by_10;
 pub mod main_menu;
@@ -24,6 +27,7 @@ pub mod receipt_requested_screen_handler;
 pub mod receipt_submitted_handler;
 pub mod referral_bot_token_handler;
 pub mod referral_program_handler;
+pub mod search;
 pub mod show_bot_info_handler;
 pub mod start;
 pub mod support;
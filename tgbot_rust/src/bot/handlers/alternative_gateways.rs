@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use shared_dtos::invoice::PaymentSystem;
+use teloxide::{
+    Bot,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+};
+
+use crate::{
+    api::backend_api::BackendApi,
+    bot::{
+        CallbackData, MyDialogue,
+        utils::{MsgBy, edit_msg},
+    },
+    errors::AppResult,
+};
+
+/// Offers the same deposit through the other gateways when `failed` can't take it.
+/// Returns `false` without touching the message when there is nothing to offer.
+pub async fn alternative_gateways_handler(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg_by: &MsgBy<'_>,
+    api_client: Arc<BackendApi>,
+    failed: PaymentSystem,
+    amount: i64,
+) -> AppResult<bool> {
+    let gateways = match api_client.get_payment_gateways().await {
+        Ok(res) => res.items,
+        Err(err) => {
+            tracing::error!("Error loading alternative gateways: {err}");
+            return Ok(false);
+        }
+    };
+    let mut buttons: Vec<_> = gateways
+        .into_iter()
+        .filter(|gateway| gateway.name != failed)
+        .map(|gateway| {
+            [InlineKeyboardButton::callback(
+                format!(
+                    "{} — {amount} {}",
+                    gateway.display_name,
+                    gateway.currency.symbol()
+                ),
+                CallbackData::SelectGatewayAndAmount {
+                    gateway: gateway.name,
+                    amount,
+                },
+            )]
+        })
+        .collect();
+    if buttons.is_empty() {
+        return Ok(false);
+    }
+    buttons.push([InlineKeyboardButton::callback(
+        "⬅️ Главное меню",
+        CallbackData::ToMainMenu,
+    )]);
+
+    edit_msg(
+        &api_client,
+        &dialogue,
+        &bot,
+        msg_by,
+        "😔 Этот способ пополнения сейчас недоступен.\nПопробуйте другой:",
+        None,
+        InlineKeyboardMarkup::new(buttons),
+    )
+    .await?;
+
+    Ok(true)
+}
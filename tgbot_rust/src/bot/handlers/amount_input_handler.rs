@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
 use crate::AppState;
+use crate::bot::handlers::deposit_amount::load_gateway_limits;
 use crate::bot::handlers::deposit_confirm::deposit_confirm_handler;
 use crate::bot::keyboards::back_to_main_menu::back_to_main_menu_inline_keyboard;
 use crate::bot::keyboards::deposit_amount_menu::deposit_amount_menu;
-use crate::bot::utils::{MsgBy, edit_msg};
+use crate::bot::utils::{MsgBy, amount_fits_gateway, edit_msg, gateway_limits_text};
 use crate::bot::{BotState, BotStep};
 use crate::{api::backend_api::BackendApi, bot::MyDialogue, errors::AppResult};
 use teloxide::Bot;
@@ -25,20 +26,28 @@ pub async fn amount_input_handler(
         } => gateway,
         _ => return Ok(()),
     };
+    let limits = load_gateway_limits(&api_client, gateway).await;
     let amount = if let Some(amount) = msg.text()
-        && let Ok(amount) = amount.parse::<i64>()
+        && let Ok(amount) = amount.trim().parse::<i64>()
         && amount > 0
+        && limits
+            .as_ref()
+            .is_none_or(|limits| amount_fits_gateway(limits, amount))
     {
         amount
     } else {
+        let text = match &limits {
+            Some(limits) => format!("Неверная сумма. {}.", gateway_limits_text(limits)),
+            None => "Неверная сумма. Введите число.".to_string(),
+        };
         edit_msg(
             &api_client,
             &dialogue,
             &bot,
             &MsgBy::Message(&msg),
-            "Неверная сумма. Введите число.",
+            &text,
             None,
-            deposit_amount_menu(),
+            deposit_amount_menu(limits.as_ref()),
         )
         .await?;
 
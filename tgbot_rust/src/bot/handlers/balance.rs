@@ -12,7 +12,7 @@ use crate::{
     bot::{
         MyDialogue,
         keyboards::balance_menu::balance_menu_inline_keyboard,
-        utils::{MsgBy, edit_msg},
+        utils::{MsgBy, display_amount_text, edit_msg},
     },
     errors::{AppError, AppResult},
 };
@@ -64,8 +64,8 @@ async fn balance_handler_impl(
 ) -> AppResult<()> {
     let text = match api_client.get_user(telegram_id).await {
         Ok(customer) => format!(
-            "💳 Ваш текущий баланс: {} ₽",
-            bold(&customer.balance.to_string())
+            "💳 Ваш текущий баланс: {}",
+            bold(&display_amount_text(customer.balance, &customer))
         ),
         Err(err) => {
             tracing::error!("Error getting balance: {err}");
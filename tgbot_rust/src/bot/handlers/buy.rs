@@ -1,19 +1,14 @@
 use std::sync::Arc;
 
 use shared_dtos::invoice::PaymentSystem;
-use shared_dtos::order::PurchaseDetails;
-use shared_dtos::product::ProductDetails;
-use shared_dtos::user_subscription::UserSubscriptionDetails;
 use teloxide::dispatching::dialogue::GetChatId;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
-use teloxide::{
-    Bot,
-    types::CallbackQuery,
-    utils::html::{bold, code_block},
-};
+use teloxide::{Bot, types::CallbackQuery};
 
 use crate::api::api_errors::ApiClientError;
-use crate::bot::utils::{MessageImage, MsgBy, edit_msg};
+use crate::bot::utils::{
+    MessageImage, MsgBy, callback_idempotency_key, edit_msg, purchase_success_text,
+};
 use crate::bot::{CallbackData, MyDialogue};
 use crate::{
     api::backend_api::BackendApi,
@@ -32,56 +27,13 @@ pub async fn buy_handler(
         None => return Ok(()),
     };
 
-    let buy_result = api_client.buy_product(chat_id.0, product_id).await;
+    let buy_result = api_client
+        .buy_product(chat_id.0, product_id, &callback_idempotency_key(&q))
+        .await;
 
     let (msg, img, keyboard) = match buy_result {
         Ok(response) => {
-            let price = format!("{:.2}", response.price);
-            let balance = format!("{:.2}", response.balance);
-            let mut success_message = format!(
-                "{}\n\n{} {}\n{} {} ₽\n{} {} ₽",
-                bold("✅ Покупка успешна"),
-                bold("Товар:"),
-                response.product_name,
-                bold("Цена:"),
-                price,
-                bold("Баланс:"),
-                balance,
-            );
-
-            if let Some(fulfilled_content) = response.fulfilled_text {
-                success_message.push_str(&format!(
-                    "\n\n{}{}\n{}",
-                    bold("📦 Ваш товар"),
-                    ":",
-                    code_block(&fulfilled_content)
-                ));
-            }
-            if let Some(details) = response.details {
-                match details {
-                    PurchaseDetails::ProductDetails(details) => match details {
-                        ProductDetails::ContMs { host: _, port: _ } => {}
-                    },
-                    PurchaseDetails::UserSubscriptionDetails(details) => match details {
-                        UserSubscriptionDetails::ContMs {
-                            host,
-                            port,
-                            username,
-                            password,
-                        } => {
-                            let address = format!("{}:{}", host, port);
-                            let access =
-                                format!("{}\nlogin: {}\npassword: {}", address, username, password);
-                            success_message.push_str(&format!(
-                                "\n\n{}{}\n{}",
-                                bold("🔐 Доступ"),
-                                ":",
-                                code_block(&access)
-                            ));
-                        }
-                    },
-                }
-            }
+            let success_message = purchase_success_text(&response);
             (
                 success_message,
                 response.fulfilled_image_id.map(MessageImage::Uuid),
@@ -104,13 +56,17 @@ pub async fn buy_handler(
                                     amount: to_pay,
                                 },
                             )],
+                            [InlineKeyboardButton::callback(
+                                "💳 Оплатить заказ",
+                                CallbackData::Checkout { id: product_id },
+                            )],
                             [InlineKeyboardButton::callback(
                                 "⬅️ Главное меню",
                                 CallbackData::ToMainMenu,
                             )],
                         ];
 
-                        ("😔 Недостаточно средств на балансе для совершения покупки. Пожалуйста, пополните баланс.".to_string(), InlineKeyboardMarkup::new(buttons))
+                        ("😔 Недостаточно средств на балансе для совершения покупки. Пополните баланс или оплатите заказ напрямую.".to_string(), InlineKeyboardMarkup::new(buttons))
                     } else if msg.contains("Not enough stock") {
                         (
                             "😔 К сожалению, этот товар закончился.".to_string(),
@@ -67,19 +67,7 @@ async fn catalog_handler_impl(
         .cloned()
         .collect::<Vec<CategoryBotResponse>>();
 
-    let image_bytes = match category {
-        Some(category) => match &category.image_id {
-            Some(image_id) => match api_client.get_image_bytes(image_id).await {
-                Ok(image_bytes) => Some(MessageImage::Bytes(image_bytes)),
-                Err(err) => {
-                    tracing::error!("Error getting image: {}", err);
-                    None
-                }
-            },
-            None => None,
-        },
-        None => None,
-    };
+    let image = category.and_then(|c| c.image_id).map(MessageImage::Uuid);
 
     let products = match category_id {
         None => vec![],
@@ -100,7 +88,7 @@ async fn catalog_handler_impl(
         &bot,
         &msg_by,
         caption,
-        image_bytes,
+        image,
         reply_markup,
     )
     .await?;
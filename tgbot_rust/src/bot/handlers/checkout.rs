@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use shared_dtos::invoice::PaymentSystem;
+use teloxide::Bot;
+use teloxide::dispatching::dialogue::GetChatId;
+use teloxide::types::CallbackQuery;
+
+use crate::AppState;
+use crate::api::api_errors::ApiClientError;
+use crate::api::backend_api::BackendApi;
+use crate::bot::handlers::deposit_confirm::deposit_confirm_handler;
+use crate::bot::keyboards::back_to_main_menu::back_to_main_menu_inline_keyboard;
+use crate::bot::utils::{MsgBy, callback_idempotency_key, edit_msg};
+use crate::bot::{BotState, BotStep, InvoiceData, MyDialogue};
+use crate::errors::AppResult;
+
+pub async fn checkout_handler(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    api_client: Arc<BackendApi>,
+    bot_state: BotState,
+    app_state: AppState,
+    product_id: i64,
+) -> AppResult<()> {
+    let chat_id = match q.chat_id() {
+        Some(chat_id) => chat_id,
+        None => return Ok(()),
+    };
+    // TODO For now only platform card supported
+    let gateway = PaymentSystem::PlatformCard;
+
+    let response = match api_client
+        .checkout_product(
+            chat_id.0,
+            product_id,
+            gateway,
+            &callback_idempotency_key(&q),
+        )
+        .await
+    {
+        Ok(response) => response,
+        Err(err) => {
+            tracing::error!("Error creating checkout invoice: {err}");
+            let msg = match err {
+                ApiClientError::Unsuccessful(err) if err.contains("Not enough stock") => {
+                    "😔 К сожалению, этот товар закончился."
+                }
+                ApiClientError::Unsuccessful(err) if err.contains("Balance covers the price") => {
+                    "✅ Средств на балансе уже достаточно, купите товар с баланса."
+                }
+                ApiClientError::Unsuccessful(err) if err.contains("Too many open invoices") => {
+                    "⚠️ У вас слишком много неоплаченных счетов. Оплатите или отмените их, прежде чем создавать новый."
+                }
+                _ => "Что-то пошло не так. Попробуйте ещё раз.",
+            };
+            edit_msg(
+                &api_client,
+                &dialogue,
+                &bot,
+                &MsgBy::CallbackQuery(&q),
+                msg,
+                None,
+                back_to_main_menu_inline_keyboard(),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    // The invoice already exists, the confirm screen only renders its payment details
+    let new_state = BotState {
+        step: BotStep::DepositConfirm {
+            gateway,
+            amount: response.amount.ceil() as i64,
+            invoice: Some(InvoiceData {
+                id: response.id,
+                details: response.payment_details,
+                gateway_invoice_id: response.gateway_invoice_id,
+                currency: response.currency,
+            }),
+        },
+        ..bot_state
+    };
+    dialogue.update(new_state.clone()).await?;
+    deposit_confirm_handler(
+        bot,
+        &MsgBy::CallbackQuery(&q),
+        dialogue,
+        api_client,
+        new_state,
+        app_state,
+    )
+    .await
+}
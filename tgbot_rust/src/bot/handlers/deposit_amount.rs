@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use shared_dtos::invoice::{GatewayBotResponse, PaymentSystem};
 use teloxide::Bot;
 
 use crate::{
@@ -7,26 +8,47 @@ use crate::{
     bot::{
         BotState, MyDialogue,
         keyboards::deposit_amount_menu::deposit_amount_menu,
-        utils::{MsgBy, edit_msg},
+        utils::{MsgBy, edit_msg, gateway_limits_text},
     },
     errors::AppResult,
 };
 
+/// Limits of `gateway`; without them the backend still validates the amount
+pub async fn load_gateway_limits(
+    api_client: &BackendApi,
+    gateway: PaymentSystem,
+) -> Option<GatewayBotResponse> {
+    match api_client.get_payment_gateways().await {
+        Ok(res) => res.items.into_iter().find(|g| g.name == gateway),
+        Err(err) => {
+            tracing::error!("Error loading gateway limits: {err}");
+            None
+        }
+    }
+}
+
 pub async fn deposit_amount_handler(
     bot: Bot,
     dialogue: MyDialogue,
     msg_by: &MsgBy<'_>,
     api_client: Arc<BackendApi>,
     _bot_state: BotState,
+    gateway: PaymentSystem,
 ) -> AppResult<()> {
+    let limits = load_gateway_limits(&api_client, gateway).await;
+    let mut text = "Выберите сумму для пополнения или введите ее вручную:".to_string();
+    if let Some(limits) = &limits {
+        text.push_str(&format!("\n{}", gateway_limits_text(limits)));
+    }
+
     edit_msg(
         &api_client,
         &dialogue,
         &bot,
         msg_by,
-        "Выберите сумму для пополнения или введите ее вручную:",
+        &text,
         None,
-        deposit_amount_menu(),
+        deposit_amount_menu(limits.as_ref()),
     )
     .await?;
 
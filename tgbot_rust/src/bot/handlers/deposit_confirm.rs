@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::AppState;
 use crate::api::api_errors::ApiClientError;
 use crate::api::backend_api::BackendApi;
+use crate::bot::handlers::alternative_gateways::alternative_gateways_handler;
 use crate::bot::handlers::increase_amount_by_10::increase_amount_by_10_handler;
 use crate::bot::handlers::no_suitable_requisites::no_suitable_requisites_handler;
 use crate::bot::keyboards::back_to_main_menu::back_to_main_menu_inline_keyboard;
@@ -39,7 +40,12 @@ pub async fn deposit_confirm_handler(
         None => {
             let telegram_id = dialogue.chat_id().0;
             let response = match api_client
-                .create_deposit_invoice(&gateway, amount as f64, telegram_id)
+                .create_deposit_invoice(
+                    &gateway,
+                    amount as f64,
+                    telegram_id,
+                    &msg_by.idempotency_key(),
+                )
                 .await
             {
                 Ok(res) => res,
@@ -53,7 +59,63 @@ pub async fn deposit_confirm_handler(
                             .await?;
                             return Ok(());
                         }
-                        if err.contains("No suitable requisites") {
+                        let limit_message = if err.contains("Amount must be") {
+                            Some(
+                                "⚠️ Сумма не подходит для этого способа пополнения. Выберите другую сумму.",
+                            )
+                        } else if err.contains("Too many open invoices") {
+                            Some(
+                                "⚠️ У вас слишком много неоплаченных счетов. Оплатите или отмените их, прежде чем создавать новый.",
+                            )
+                        } else if err.contains("Daily deposit limit exceeded") {
+                            Some(
+                                "⚠️ Превышен дневной лимит пополнения для этого способа. Попробуйте другой способ или повторите завтра.",
+                            )
+                        } else {
+                            None
+                        };
+                        if let Some(limit_message) = limit_message {
+                            edit_msg(
+                                &api_client,
+                                &dialogue,
+                                &bot,
+                                msg_by,
+                                limit_message,
+                                None,
+                                back_to_main_menu_inline_keyboard(),
+                            )
+                            .await?;
+                            return Ok(());
+                        }
+                        let gateway_unavailable = err.contains("Gateway temporarily unavailable");
+                        let no_requisites = err.contains("No suitable requisites");
+                        if (gateway_unavailable || no_requisites)
+                            && alternative_gateways_handler(
+                                bot.clone(),
+                                dialogue.clone(),
+                                msg_by,
+                                api_client.clone(),
+                                gateway,
+                                amount,
+                            )
+                            .await?
+                        {
+                            return Ok(());
+                        }
+                        if gateway_unavailable {
+                            edit_msg(
+                                &api_client,
+                                &dialogue,
+                                &bot,
+                                msg_by,
+                                "⚠️ Пополнение временно недоступно. Попробуйте позже.",
+                                None,
+                                back_to_main_menu_inline_keyboard(),
+                            )
+                            .await?;
+                            return Ok(());
+                        }
+                        if no_requisites {
                             no_suitable_requisites_handler(
                                 bot,
                                 dialogue,
@@ -83,6 +145,7 @@ pub async fn deposit_confirm_handler(
                 id: response.id,
                 details: response.payment_details,
                 gateway_invoice_id: response.gateway_invoice_id,
+                currency: response.currency,
             }
         }
     };
@@ -6,7 +6,10 @@ use crate::{
     api::backend_api::BackendApi,
     bot::{
         MyDialogue,
-        keyboards::payment_gateways_menu::payment_gateways_menu,
+        keyboards::{
+            back_to_main_menu::back_to_main_menu_inline_keyboard,
+            payment_gateways_menu::payment_gateways_menu,
+        },
         utils::{MsgBy, edit_msg},
     },
     errors::AppResult,
@@ -18,7 +21,31 @@ pub async fn deposit_gateway_handler(
     q: CallbackQuery,
     api_client: Arc<BackendApi>,
 ) -> AppResult<()> {
+    // The backend reports degraded mode while the payment platform is unreachable; if the
+    // check itself fails, let the customer try and surface the invoice error instead
+    let payments_available = match api_client.can_operate().await {
+        Ok(res) => res.payments_available,
+        Err(err) => {
+            tracing::error!("Error checking payments availability: {err}");
+            true
+        }
+    };
+    // Gateways tripped by the backend circuit breaker aren't listed
     let payment_gateways = api_client.get_payment_gateways().await?;
+    if !payments_available || payment_gateways.items.is_empty() {
+        edit_msg(
+            &api_client,
+            &dialogue,
+            &bot,
+            &MsgBy::CallbackQuery(&q),
+            "⚠️ Пополнение временно недоступно. Попробуйте позже.",
+            None,
+            back_to_main_menu_inline_keyboard(),
+        )
+        .await?;
+        return Ok(());
+    }
+
     let settings = api_client.get_settings().await?;
 
     edit_msg(
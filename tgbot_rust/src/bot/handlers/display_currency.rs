@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use teloxide::{Bot, types::CallbackQuery};
+
+use crate::{
+    api::backend_api::BackendApi,
+    bot::{
+        MyDialogue,
+        keyboards::{
+            back_to_main_menu::back_to_main_menu_inline_keyboard,
+            display_currency_menu::display_currency_menu_inline_keyboard,
+        },
+        utils::{MsgBy, edit_msg},
+    },
+    errors::AppResult,
+};
+
+pub async fn select_currency_handler(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    api_client: Arc<BackendApi>,
+) -> AppResult<()> {
+    let (text, keyboard) = match api_client.get_exchange_rates().await {
+        Ok(res) => (
+            "💱 Выберите валюту, в которой показывать баланс и цены.\n\
+             Оплата и списания по-прежнему проводятся в валюте магазина."
+                .to_string(),
+            display_currency_menu_inline_keyboard(&res.rates),
+        ),
+        Err(err) => {
+            tracing::error!("Error getting exchange rates: {err}");
+            (
+                "Не удалось загрузить курсы валют. Попробуйте позже.".to_string(),
+                back_to_main_menu_inline_keyboard(),
+            )
+        }
+    };
+
+    edit_msg(
+        &api_client,
+        &dialogue,
+        &bot,
+        &MsgBy::CallbackQuery(&q),
+        &text,
+        None,
+        keyboard,
+    )
+    .await?;
+    Ok(())
+}
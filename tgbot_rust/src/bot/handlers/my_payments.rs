@@ -85,12 +85,13 @@ pub async fn my_payments_handler(
 fn format_payment_info(payment: &PaymentInvoiceBotResponse) -> String {
     let token = escape(&payment.gateway_invoice_id);
     format!(
-        "<b>Платеж #{}:</b> <code>{}</code> ₽\n\
+        "<b>Платеж #{}:</b> <code>{}</code> {}\n\
          <b>Статус:</b> {}\n\
          <b>Дата:</b> {}\n\
          <b>Токен:</b> <code>{}</code>",
         payment.id,
         payment.amount,
+        payment.currency.symbol(),
         invoice_status_label(payment.status),
         payment.created_at.format("%d.%m.%Y"),
         token
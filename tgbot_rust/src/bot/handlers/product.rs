@@ -1,10 +1,10 @@
 use std::sync::Arc;
 
-use shared_dtos::product::ProductBotResponse;
+use shared_dtos::{customer::CustomerBotResponse, product::ProductBotResponse};
 use teloxide::{Bot, types::CallbackQuery};
 
 use crate::bot::MyDialogue;
-use crate::bot::utils::{MessageImage, MsgBy, edit_msg};
+use crate::bot::utils::{MessageImage, MsgBy, display_amount_text, edit_msg};
 use crate::{
     api::backend_api::BackendApi,
     bot::keyboards::{
@@ -25,7 +25,9 @@ pub async fn product_handler(
 
     match product_result {
         Ok(product) => {
-            display_product(bot, dialogue, q, api_client, &product).await?;
+            // The price is still shown without a conversion if the customer lookup fails
+            let customer = api_client.get_user(q.from.id.0 as i64).await.ok();
+            display_product(bot, dialogue, q, api_client, &product, customer.as_ref()).await?;
         }
         Err(err) => {
             tracing::error!("Error getting product: {}", err);
@@ -51,28 +53,25 @@ async fn display_product(
     q: CallbackQuery,
     api_client: Arc<BackendApi>,
     product: &ProductBotResponse,
+    customer: Option<&CustomerBotResponse>,
 ) -> AppResult<()> {
-    let caption = format!(
+    let mut caption = format!(
         "<b>{}</b>
 
 <i>Цена:</i> {} ₽",
         product.name,
         product.price.ceil()
     );
+    if let Some(customer) = customer.filter(|c| c.display_rate != 1.0) {
+        caption.push_str(&format!(
+            " (≈ {})",
+            display_amount_text(product.price, customer)
+        ));
+    }
 
     let reply_markup = product_card_inline_keyboard(product);
 
-    let image_bytes = if let Some(image_id) = &product.image_id {
-        match api_client.get_image_bytes(image_id).await {
-            Ok(bytes) => Some(MessageImage::Bytes(bytes)),
-            Err(e) => {
-                tracing::error!("Failed to get image bytes: {}", e);
-                None
-            }
-        }
-    } else {
-        None
-    };
+    let image = product.image_id.map(MessageImage::Uuid);
 
     edit_msg(
         &api_client,
@@ -80,7 +79,7 @@ async fn display_product(
         &bot,
         &MsgBy::CallbackQuery(&q),
         &caption,
-        image_bytes,
+        image,
         reply_markup,
     )
     .await?;
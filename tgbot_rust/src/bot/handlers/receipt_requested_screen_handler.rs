@@ -36,6 +36,7 @@ pub async fn receipt_requested_screen_handler(
                     id: invoice_id,
                     details: invoice.payment_details,
                     gateway_invoice_id: invoice.gateway_invoice_id,
+                    currency: invoice.currency,
                 }),
             },
             ..bot_state
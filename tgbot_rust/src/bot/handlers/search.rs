@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use teloxide::{Bot, types::Message};
+
+use crate::{
+    api::backend_api::BackendApi,
+    bot::{
+        MyDialogue,
+        keyboards::{
+            back_to_main_menu::back_to_main_menu_inline_keyboard,
+            catalog_menu::catalog_menu_inline_keyboard,
+        },
+        utils::{MsgBy, edit_msg},
+    },
+    errors::AppResult,
+};
+
+pub async fn search_handler(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    api_client: Arc<BackendApi>,
+    query: String,
+) -> AppResult<()> {
+    let query = query.trim();
+    if query.is_empty() {
+        edit_msg(
+            &api_client,
+            &dialogue,
+            &bot,
+            &MsgBy::Message(&msg),
+            "🔎 Отправьте /search и название товара, например: /search netflix",
+            None,
+            back_to_main_menu_inline_keyboard(),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let products = api_client.search_products(query).await?.items;
+    let caption = if products.is_empty() {
+        format!("🔎 По запросу «{query}» ничего не найдено")
+    } else {
+        format!("🔎 Результаты поиска по запросу «{query}»:")
+    };
+
+    edit_msg(
+        &api_client,
+        &dialogue,
+        &bot,
+        &MsgBy::Message(&msg),
+        &caption,
+        None,
+        catalog_menu_inline_keyboard(&[], &products, None, None),
+    )
+    .await?;
+
+    Ok(())
+}
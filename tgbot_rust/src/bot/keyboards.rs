@@ -1,8 +1,10 @@
 pub mod back_to_main_menu;
 pub mod balance_menu;
+pub mod broadcast;
 pub mod captcha;
 pub mod catalog_menu;
 pub mod deposit_amount_menu;
+pub mod display_currency_menu;
 pub mod main_menu;
 pub mod main_menu_reply;
 pub mod my_bots_menu;
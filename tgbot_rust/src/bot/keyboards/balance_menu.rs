@@ -8,6 +8,10 @@ pub fn balance_menu_inline_keyboard() -> InlineKeyboardMarkup {
             "💰 Пополнить баланс",
             CallbackData::ToDepositSelectGateway,
         )],
+        vec![InlineKeyboardButton::callback(
+            "💱 Валюта отображения",
+            CallbackData::ToSelectCurrency,
+        )],
         vec![InlineKeyboardButton::callback(
             "⬅️ Назад",
             CallbackData::ToMainMenu,
@@ -0,0 +1,43 @@
+use shared_dtos::broadcast::{BroadcastButton, BroadcastButtonAction};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use url::Url;
+
+use crate::bot::{CallbackData, keyboards::back_to_main_menu::back_to_main_menu_inline_keyboard};
+
+pub fn broadcast_inline_keyboard(rows: &[Vec<BroadcastButton>]) -> InlineKeyboardMarkup {
+    if rows.is_empty() {
+        return back_to_main_menu_inline_keyboard();
+    }
+
+    let buttons = rows
+        .iter()
+        .map(|row| row.iter().filter_map(broadcast_button).collect::<Vec<_>>())
+        .filter(|row| !row.is_empty())
+        .collect::<Vec<_>>();
+    InlineKeyboardMarkup::new(buttons)
+}
+
+fn broadcast_button(button: &BroadcastButton) -> Option<InlineKeyboardButton> {
+    let text = button.text.clone();
+    let callback = match &button.action {
+        BroadcastButtonAction::Url { url } => {
+            return match Url::parse(url) {
+                Ok(url) => Some(InlineKeyboardButton::url(text, url)),
+                Err(err) => {
+                    tracing::warn!("Skipping broadcast button with invalid url {url}: {err}");
+                    None
+                }
+            };
+        }
+        BroadcastButtonAction::Product { product_id } => {
+            CallbackData::ToProduct { id: *product_id }
+        }
+        BroadcastButtonAction::Category { category_id } => CallbackData::ToCategory {
+            category_id: *category_id,
+        },
+        BroadcastButtonAction::MainMenu => CallbackData::ToMainMenu,
+        BroadcastButtonAction::Balance => CallbackData::ToBalance,
+        BroadcastButtonAction::Support => CallbackData::ToSupport,
+    };
+    Some(InlineKeyboardButton::callback(text, callback))
+}
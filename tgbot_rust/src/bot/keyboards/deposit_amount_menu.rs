@@ -1,15 +1,31 @@
+use shared_dtos::invoice::GatewayBotResponse;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
 
-use crate::bot::CallbackData;
+use crate::bot::{CallbackData, utils::amount_fits_gateway};
 
-pub fn deposit_amount_menu() -> InlineKeyboardMarkup {
-    let amounts = [500, 1000, 1500];
+/// Preset amounts are rounded up to the gateway step, the ones outside its limits are dropped
+pub fn deposit_amount_menu(gateway: Option<&GatewayBotResponse>) -> InlineKeyboardMarkup {
+    let mut amounts: Vec<i64> = [500, 1000, 1500]
+        .into_iter()
+        .map(|amount| match gateway {
+            Some(gateway) if gateway.amount_step > 1.0 => {
+                ((amount as f64 / gateway.amount_step).ceil() * gateway.amount_step) as i64
+            }
+            _ => amount,
+        })
+        .filter(|&amount| gateway.is_none_or(|gateway| amount_fits_gateway(gateway, amount)))
+        .collect();
+    amounts.dedup();
+    let symbol = gateway
+        .map(|gateway| gateway.currency)
+        .unwrap_or_default()
+        .symbol();
 
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = amounts
         .iter()
         .map(|&amount| {
             vec![InlineKeyboardButton::callback(
-                format!("{amount} ₽"),
+                format!("{amount} {symbol}"),
                 CallbackData::SelectAmount { amount },
             )]
         })
@@ -0,0 +1,27 @@
+use shared_dtos::currency::ExchangeRateResponse;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+use crate::bot::CallbackData;
+
+pub fn display_currency_menu_inline_keyboard(
+    rates: &[ExchangeRateResponse],
+) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = rates
+        .iter()
+        .map(|rate| {
+            vec![InlineKeyboardButton::callback(
+                format!("{} ({})", rate.currency.code(), rate.currency.symbol()),
+                CallbackData::SetDisplayCurrency {
+                    currency: rate.currency,
+                },
+            )]
+        })
+        .collect();
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "⬅️ Назад",
+        CallbackData::ToBalance,
+    )]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
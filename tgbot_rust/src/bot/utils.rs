@@ -1,23 +1,36 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
-use shared_dtos::invoice::PaymentDetails;
+use shared_dtos::broadcast::{
+    BroadcastContent, BroadcastMedia, BroadcastMediaKind, BroadcastTextEntity,
+    BroadcastTextEntityKind,
+};
+use shared_dtos::currency::Currency;
+use shared_dtos::customer::CustomerBotResponse;
+use shared_dtos::invoice::{GatewayBotResponse, PaymentDetails};
+use shared_dtos::order::{PurchaseBotResponse, PurchaseDetails};
+use shared_dtos::product::ProductDetails;
+use shared_dtos::user_subscription::UserSubscriptionDetails;
 use teloxide::dispatching::dialogue::GetChatId;
 use teloxide::payloads::{
-    EditMessageMediaSetters, EditMessageTextSetters, SendMessageSetters, SendPhotoSetters,
+    EditMessageMediaSetters, EditMessageTextSetters, SendDocumentSetters, SendMessageSetters,
+    SendPhotoSetters, SendVideoSetters,
 };
 use teloxide::prelude::{Request, Requester};
 use teloxide::types::{
-    CallbackQuery, ChatId, InlineKeyboardMarkup, InputFile, InputMedia, InputMediaPhoto,
-    MaybeInaccessibleMessage, Message, MessageId, ParseMode, ReplyMarkup,
+    CallbackQuery, ChatId, FileId, InlineKeyboardMarkup, InputFile, InputMedia, InputMediaDocument,
+    InputMediaPhoto, InputMediaVideo, MaybeInaccessibleMessage, Message, MessageEntity,
+    MessageEntityKind, MessageId, ParseMode, ReplyMarkup,
 };
-use teloxide::utils::html::escape;
+use teloxide::utils::html::{bold, code_block, escape};
 use url::Url;
 use uuid::Uuid;
 
-use crate::api::backend_api::BackendApi;
+use crate::api::backend_api::{BackendApi, ImageDownload};
+use crate::bot::keyboards::broadcast::broadcast_inline_keyboard;
 use crate::bot::{BotState, InvoiceData, MyDialogue};
 use crate::errors::{AppError, AppResult};
+use crate::file_id_cache::{CachedFileId, FileIdCache};
 
 use teloxide::Bot;
 
@@ -26,6 +39,20 @@ pub enum MsgBy<'a> {
     CallbackQuery(&'a CallbackQuery),
 }
 
+impl MsgBy<'_> {
+    /// Stable per user action, so Telegram retries and double taps hit the same backend key
+    pub fn idempotency_key(&self) -> String {
+        match self {
+            MsgBy::Message(msg) => format!("msg:{}:{}", msg.chat.id, msg.id),
+            MsgBy::CallbackQuery(q) => callback_idempotency_key(q),
+        }
+    }
+}
+
+pub fn callback_idempotency_key(q: &CallbackQuery) -> String {
+    format!("cb:{}", q.id.0)
+}
+
 #[derive(Debug)]
 pub enum MessageImage {
     Uuid(Uuid),
@@ -34,10 +61,12 @@ pub enum MessageImage {
 
 pub fn invoice_troubles_paragraph(
     amount: impl std::fmt::Display,
+    currency: Currency,
     rounded_minutes_left: i64,
 ) -> String {
+    let symbol = currency.symbol();
     format!(
-        "<b>Вы недавно пытались пополнить баланс на {amount} ₽.</b>\nВозникли ли у вас какие-либо проблемы с оплатой?\n\
+        "<b>Вы недавно пытались пополнить баланс на {amount} {symbol}.</b>\nВозникли ли у вас какие-либо проблемы с оплатой?\n\
          <u>У вас осталось {rounded_minutes_left} минут на оплату</u>"
     )
 }
@@ -47,12 +76,12 @@ pub fn build_invoice_payment_text(
     requested_amount: i64,
     troubles_minutes_left: Option<i64>,
 ) -> String {
+    let symbol = invoice_data.currency.symbol();
     let mut text = match &invoice_data.details {
         None => "Не удалось получить реквизиты для оплаты. Попробуйте другой способ.".to_string(),
         Some(details) => match details {
             PaymentDetails::Mock { .. } => format!(
-                "✅ Ваш счет на {} ₽ создан.\n\nНажмите на кнопку ниже, чтобы перейти к оплате.",
-                requested_amount
+                "✅ Ваш счет на {requested_amount} {symbol} создан.\n\nНажмите на кнопку ниже, чтобы перейти к оплате."
             ),
             PaymentDetails::PlatformCard {
                 bank_name,
@@ -69,7 +98,7 @@ pub fn build_invoice_payment_text(
                      <b>Банк:</b> {bank_name}\n\
                      <b>Номер карты:</b> <code>{card_number}</code>\n\
                      <b>Получатель:</b> {account_name}\n\
-                     <b>Сумма:</b> <code>{amount}</code> ₽\n\n\
+                     <b>Сумма:</b> <code>{amount}</code> {symbol}\n\n\
                      <b>Токен:</b> <code>{token}</code>\n\n\
                      <u>На оплату дается 30 минут!</u>\n\
                      В случае, если вы не оплатите в течении 30 минут, платеж не будет зачислен!\n\
@@ -91,7 +120,7 @@ pub fn build_invoice_payment_text(
                      <b>Банк:</b> {bank_name}\n\
                      <b>Номер СБП:</b> <code>{sbp_number}</code>\n\
                      <b>Получатель:</b> {account_name}\n\
-                     <b>Сумма:</b> <code>{amount} ₽</code>\n\n\
+                     <b>Сумма:</b> <code>{amount} {symbol}</code>\n\n\
                      <b>Токен:</b> <code>{token}</code>\n\n\
                      <u>На оплату дается 30 минут!</u>\n\
                      В случае, если вы не оплатите в течении 30 минут, платеж не будет зачислен!\n\
@@ -103,7 +132,11 @@ pub fn build_invoice_payment_text(
 
     if let Some(minutes_left) = troubles_minutes_left {
         text.push_str("\n\n");
-        text.push_str(&invoice_troubles_paragraph(requested_amount, minutes_left));
+        text.push_str(&invoice_troubles_paragraph(
+            requested_amount,
+            invoice_data.currency,
+            minutes_left,
+        ));
     }
 
     text
@@ -147,6 +180,86 @@ pub fn build_receipt_upload_instruction_text(
     message
 }
 
+/// Whether `amount` is within the gateway limits and a multiple of its step
+pub fn amount_fits_gateway(gateway: &GatewayBotResponse, amount: i64) -> bool {
+    let amount = amount as f64;
+    let steps = amount / gateway.amount_step;
+    amount >= gateway.min_amount
+        && amount <= gateway.max_amount
+        && (gateway.amount_step <= 0.0 || (steps - steps.round()).abs() < 1e-9)
+}
+
+pub fn gateway_limits_text(gateway: &GatewayBotResponse) -> String {
+    let symbol = gateway.currency.symbol();
+    let mut text = format!(
+        "Сумма от {} до {} {symbol}",
+        gateway.min_amount, gateway.max_amount
+    );
+    if gateway.amount_step > 1.0 {
+        text.push_str(&format!(", кратно {} {symbol}", gateway.amount_step));
+    }
+    text
+}
+
+/// Base currency `amount` in the customer's display currency
+pub fn display_amount_text(amount: f64, customer: &CustomerBotResponse) -> String {
+    format!(
+        "{:.2} {}",
+        amount * customer.display_rate,
+        customer.display_currency.symbol()
+    )
+}
+
+pub fn purchase_success_text(response: &PurchaseBotResponse) -> String {
+    let price = format!("{:.2}", response.price);
+    let balance = format!("{:.2}", response.balance);
+    let mut text = format!(
+        "{}\n\n{} {}\n{} {} ₽\n{} {} ₽",
+        bold("✅ Покупка успешна"),
+        bold("Товар:"),
+        response.product_name,
+        bold("Цена:"),
+        price,
+        bold("Баланс:"),
+        balance,
+    );
+
+    if let Some(fulfilled_content) = &response.fulfilled_text {
+        text.push_str(&format!(
+            "\n\n{}{}\n{}",
+            bold("📦 Ваш товар"),
+            ":",
+            code_block(fulfilled_content)
+        ));
+    }
+    if let Some(details) = &response.details {
+        match details {
+            PurchaseDetails::ProductDetails(details) => match details {
+                ProductDetails::ContMs { host: _, port: _ } => {}
+            },
+            PurchaseDetails::UserSubscriptionDetails(details) => match details {
+                UserSubscriptionDetails::ContMs {
+                    host,
+                    port,
+                    username,
+                    password,
+                } => {
+                    let address = format!("{}:{}", host, port);
+                    let access =
+                        format!("{}\nlogin: {}\npassword: {}", address, username, password);
+                    text.push_str(&format!(
+                        "\n\n{}{}\n{}",
+                        bold("🔐 Доступ"),
+                        ":",
+                        code_block(&access)
+                    ));
+                }
+            },
+        }
+    }
+    text
+}
+
 pub fn support_operator_buttons(
     operators: &[String],
 ) -> Vec<Vec<teloxide::types::InlineKeyboardButton>> {
@@ -173,16 +286,90 @@ pub async fn send_msg(
     image: Option<MessageImage>,
     reply_keyboard: ReplyMarkup,
 ) -> AppResult<Message> {
-    let image_bytes = match image {
-        Some(MessageImage::Uuid(uuid)) => {
-            let bytes = api_client.get_image_bytes(&uuid).await?;
-            Some(bytes)
+    let chat_id = dialogue.chat_id();
+    let msg = send_with_image(api_client, image, |image| {
+        send_msg_impl(bot, chat_id, text, image, reply_keyboard.clone())
+    })
+    .await?;
+
+    let prev_state = dialogue.get_or_default().await.unwrap_or_default();
+    dialogue
+        .update(BotState {
+            last_bot_msg_id: Some(msg.id.0 as i64),
+            ..prev_state
+        })
+        .await?;
+
+    Ok(msg)
+}
+
+/// Send broadcast content as a text message, a single media with caption,
+/// or an album followed by a text message carrying the buttons
+pub async fn send_broadcast_msg(
+    api_client: &Arc<BackendApi>,
+    dialogue: &MyDialogue,
+    bot: &Bot,
+    content: BroadcastContent,
+) -> AppResult<Message> {
+    let chat_id = dialogue.chat_id();
+    let text = content.text.unwrap_or_default();
+    // Without explicit entities the text keeps using HTML markup like other bot messages
+    let entities = (!content.entities.is_empty()).then(|| {
+        content
+            .entities
+            .iter()
+            .filter_map(message_entity)
+            .collect::<Vec<_>>()
+    });
+    let keyboard = broadcast_inline_keyboard(&content.buttons);
+
+    let mut media = Vec::with_capacity(content.media.len());
+    for item in &content.media {
+        media.push((item.kind, broadcast_input_file(api_client, item).await?));
+    }
+
+    let msg = match media.len() {
+        0 => {
+            let request = bot.send_message(chat_id, &text).reply_markup(keyboard);
+            match entities {
+                Some(entities) => request.entities(entities).send().await?,
+                None => request.parse_mode(ParseMode::Html).send().await?,
+            }
         }
-        Some(MessageImage::Bytes(bytes)) => Some(bytes),
-        None => None,
-    };
+        1 => {
+            let (kind, file) = media.remove(0);
+            send_broadcast_single_media(bot, chat_id, kind, file, &text, entities, keyboard).await?
+        }
+        _ => {
+            // Albums can't carry an inline keyboard, so buttons go with a separate text message
+            let caption_on_album = content.buttons.is_empty();
+            let group = media
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (kind, file))| {
+                    let caption = (idx == 0 && caption_on_album && !text.is_empty())
+                        .then(|| (text.clone(), entities.clone()));
+                    broadcast_input_media(kind, file, caption)
+                })
+                .collect::<Vec<_>>();
+            let album = bot.send_media_group(chat_id, group).send().await?;
 
-    let msg = send_msg_impl(bot, dialogue.chat_id(), text, image_bytes, reply_keyboard).await?;
+            if caption_on_album {
+                album
+                    .into_iter()
+                    .last()
+                    .ok_or(AppError::InternalServerError(
+                        "Telegram returned an empty media group".to_string(),
+                    ))?
+            } else {
+                let request = bot.send_message(chat_id, &text).reply_markup(keyboard);
+                match entities {
+                    Some(entities) => request.entities(entities).send().await?,
+                    None => request.parse_mode(ParseMode::Html).send().await?,
+                }
+            }
+        }
+    };
 
     let prev_state = dialogue.get_or_default().await.unwrap_or_default();
     dialogue
@@ -195,6 +382,113 @@ pub async fn send_msg(
     Ok(msg)
 }
 
+async fn send_broadcast_single_media(
+    bot: &Bot,
+    chat_id: ChatId,
+    kind: BroadcastMediaKind,
+    file: InputFile,
+    text: &str,
+    entities: Option<Vec<MessageEntity>>,
+    keyboard: InlineKeyboardMarkup,
+) -> AppResult<Message> {
+    let msg = match kind {
+        BroadcastMediaKind::Photo => {
+            let request = bot
+                .send_photo(chat_id, file)
+                .caption(text)
+                .reply_markup(keyboard);
+            match entities {
+                Some(entities) => request.caption_entities(entities).send().await?,
+                None => request.parse_mode(ParseMode::Html).send().await?,
+            }
+        }
+        BroadcastMediaKind::Video => {
+            let request = bot
+                .send_video(chat_id, file)
+                .caption(text)
+                .reply_markup(keyboard);
+            match entities {
+                Some(entities) => request.caption_entities(entities).send().await?,
+                None => request.parse_mode(ParseMode::Html).send().await?,
+            }
+        }
+        BroadcastMediaKind::Document => {
+            let request = bot
+                .send_document(chat_id, file)
+                .caption(text)
+                .reply_markup(keyboard);
+            match entities {
+                Some(entities) => request.caption_entities(entities).send().await?,
+                None => request.parse_mode(ParseMode::Html).send().await?,
+            }
+        }
+    };
+    Ok(msg)
+}
+
+fn broadcast_input_media(
+    kind: BroadcastMediaKind,
+    file: InputFile,
+    caption: Option<(String, Option<Vec<MessageEntity>>)>,
+) -> InputMedia {
+    macro_rules! with_caption {
+        ($media:expr) => {
+            match caption {
+                Some((text, Some(entities))) => $media.caption(text).caption_entities(entities),
+                Some((text, None)) => $media.caption(text).parse_mode(ParseMode::Html),
+                None => $media,
+            }
+        };
+    }
+
+    match kind {
+        BroadcastMediaKind::Photo => InputMedia::Photo(with_caption!(InputMediaPhoto::new(file))),
+        BroadcastMediaKind::Video => InputMedia::Video(with_caption!(InputMediaVideo::new(file))),
+        BroadcastMediaKind::Document => {
+            InputMedia::Document(with_caption!(InputMediaDocument::new(file)))
+        }
+    }
+}
+
+async fn broadcast_input_file(
+    api_client: &Arc<BackendApi>,
+    media: &BroadcastMedia,
+) -> AppResult<InputFile> {
+    if let Some(image_id) = media.image_id {
+        let bytes = api_client.get_image_bytes(&image_id).await?;
+        return Ok(InputFile::memory(bytes));
+    }
+
+    let url = media.url.as_deref().unwrap_or_default();
+    let url = Url::parse(url).map_err(|err| {
+        AppError::InternalServerError(format!("Invalid broadcast media url {url}: {err}"))
+    })?;
+    Ok(InputFile::url(url))
+}
+
+fn message_entity(entity: &BroadcastTextEntity) -> Option<MessageEntity> {
+    let kind = match entity.kind {
+        BroadcastTextEntityKind::Bold => MessageEntityKind::Bold,
+        BroadcastTextEntityKind::Italic => MessageEntityKind::Italic,
+        BroadcastTextEntityKind::Underline => MessageEntityKind::Underline,
+        BroadcastTextEntityKind::Strikethrough => MessageEntityKind::Strikethrough,
+        BroadcastTextEntityKind::Spoiler => MessageEntityKind::Spoiler,
+        BroadcastTextEntityKind::Code => MessageEntityKind::Code,
+        BroadcastTextEntityKind::Blockquote => MessageEntityKind::Blockquote,
+        BroadcastTextEntityKind::Pre => MessageEntityKind::Pre {
+            language: entity.language.clone(),
+        },
+        BroadcastTextEntityKind::TextLink => MessageEntityKind::TextLink {
+            url: Url::parse(entity.url.as_deref()?).ok()?,
+        },
+    };
+    Some(MessageEntity {
+        kind,
+        offset: entity.offset as usize,
+        length: entity.length as usize,
+    })
+}
+
 /// Edit msg, if edit is failed, send new message
 /// Automatically detect type of message
 pub async fn edit_msg(
@@ -225,23 +519,17 @@ pub async fn edit_msg(
         msg_id = None;
         has_photo = false;
     };
-    let image_bytes = match image {
-        Some(MessageImage::Uuid(uuid)) => {
-            let bytes = api_client.get_image_bytes(&uuid).await?;
-            Some(bytes)
-        }
-        Some(MessageImage::Bytes(bytes)) => Some(bytes),
-        None => None,
-    };
-    let msg = edit_msg_impl(
-        bot,
-        chat_id,
-        msg_id,
-        has_photo,
-        text,
-        image_bytes,
-        reply_keyboard,
-    )
+    let msg = send_with_image(api_client, image, |image| {
+        edit_msg_impl(
+            bot,
+            chat_id,
+            msg_id,
+            has_photo,
+            text,
+            image,
+            reply_keyboard.clone(),
+        )
+    })
     .await?;
 
     dialogue
@@ -259,13 +547,13 @@ async fn edit_msg_impl(
     msg_id: Option<MessageId>,
     has_photo: bool,
     text: &str,
-    image_bytes: Option<Bytes>,
+    image: Option<InputFile>,
     reply_keyboard: InlineKeyboardMarkup,
 ) -> AppResult<Message> {
     // Msg id found, try to edit it
     if let Some(msg_id) = msg_id {
         // Telegram does not allow to change type of message
-        let is_type_changed = image_bytes.is_some() != has_photo;
+        let is_type_changed = image.is_some() != has_photo;
         if is_type_changed {
             // We can safely ignore error as it expected behavior
             let _ = bot.delete_message(chat_id, msg_id).await;
@@ -273,22 +561,14 @@ async fn edit_msg_impl(
                 bot,
                 chat_id,
                 text,
-                image_bytes,
+                image,
                 ReplyMarkup::InlineKeyboard(reply_keyboard),
             )
             .await;
         }
-        return match image_bytes {
-            Some(image_bytes) => {
-                edit_media_msg(
-                    bot,
-                    chat_id,
-                    Some(msg_id),
-                    text,
-                    image_bytes,
-                    reply_keyboard,
-                )
-                .await
+        return match image {
+            Some(image) => {
+                edit_media_msg(bot, chat_id, Some(msg_id), text, image, reply_keyboard).await
             }
             None => edit_text_msg(bot, chat_id, Some(msg_id), text, reply_keyboard).await,
         };
@@ -298,7 +578,7 @@ async fn edit_msg_impl(
         bot,
         chat_id,
         text,
-        image_bytes,
+        image,
         ReplyMarkup::InlineKeyboard(reply_keyboard),
     )
     .await
@@ -309,15 +589,152 @@ async fn send_msg_impl(
     bot: &Bot,
     chat_id: ChatId,
     text: &str,
-    image_bytes: Option<Bytes>,
+    image: Option<InputFile>,
     reply_keyboard: ReplyMarkup,
 ) -> AppResult<Message> {
-    match image_bytes {
-        Some(image_bytes) => send_media_msg(bot, chat_id, text, image_bytes, reply_keyboard).await,
+    match image {
+        Some(image) => send_media_msg(bot, chat_id, text, image, reply_keyboard).await,
         None => send_text_msg(bot, chat_id, text, reply_keyboard).await,
     }
 }
 
+/// Photo ready to be sent
+struct PreparedImage {
+    file: InputFile,
+    /// Backend image and its content hash, known for images fetched by id
+    source: Option<(Uuid, String)>,
+    /// `file` is a Telegram file id from the cache rather than uploaded bytes
+    from_cache: bool,
+}
+
+/// Sends a message with an optional image, reusing the Telegram file id cached for the image.
+/// A cached file id that Telegram rejects is dropped and the image is uploaded again.
+async fn send_with_image<F, Fut>(
+    api_client: &BackendApi,
+    image: Option<MessageImage>,
+    send: F,
+) -> AppResult<Message>
+where
+    F: Fn(Option<InputFile>) -> Fut,
+    Fut: Future<Output = AppResult<Message>>,
+{
+    let cache = api_client.file_id_cache();
+    let mut prepared = match image {
+        Some(image) => prepare_image(api_client, cache, image).await,
+        None => None,
+    };
+
+    let msg = match send(prepared.as_ref().map(|image| image.file.clone())).await {
+        Ok(msg) => msg,
+        Err(err) => {
+            let Some(PreparedImage {
+                source: Some((image_id, hash)),
+                from_cache: true,
+                ..
+            }) = prepared
+            else {
+                return Err(err);
+            };
+            tracing::warn!(%image_id, error = %err, "Cached Telegram file id failed, uploading image again");
+            if let Some(cache) = cache
+                && let Err(err) = cache.invalidate(&image_id, &hash).await
+            {
+                tracing::error!(%image_id, error = %err, "Failed to drop cached file id");
+            }
+            prepared = prepare_image(api_client, None, MessageImage::Uuid(image_id)).await;
+            send(prepared.as_ref().map(|image| image.file.clone())).await?
+        }
+    };
+
+    if let Some(cache) = cache
+        && let Some(PreparedImage {
+            source: Some((image_id, hash)),
+            from_cache: false,
+            ..
+        }) = prepared
+        && let Some(photo) = msg.photo().and_then(|sizes| sizes.last())
+    {
+        let cached = CachedFileId {
+            hash,
+            file_id: photo.file.id.0.clone(),
+        };
+        if let Err(err) = cache.set(&image_id, &cached).await {
+            tracing::error!(%image_id, error = %err, "Failed to cache Telegram file id");
+        }
+    }
+
+    Ok(msg)
+}
+
+/// Resolves an image to a cached Telegram file id or to its bytes.
+/// Images the backend fails to return are logged and left out of the message.
+async fn prepare_image(
+    api_client: &BackendApi,
+    cache: Option<&FileIdCache>,
+    image: MessageImage,
+) -> Option<PreparedImage> {
+    let image_id = match image {
+        MessageImage::Bytes(bytes) => {
+            return Some(PreparedImage {
+                file: InputFile::memory(bytes),
+                source: None,
+                from_cache: false,
+            });
+        }
+        MessageImage::Uuid(image_id) => image_id,
+    };
+
+    let cached = match cache {
+        Some(cache) => cache.get(&image_id).await.unwrap_or_else(|err| {
+            tracing::error!(%image_id, error = %err, "Failed to read cached file id");
+            None
+        }),
+        None => None,
+    };
+    // The backend answers "not modified" while the image keeps the cached content hash
+    let download = api_client
+        .get_image_if_changed(
+            &image_id,
+            cached.as_ref().map(|cached| cached.hash.as_str()),
+        )
+        .await;
+
+    match download {
+        Ok(ImageDownload::NotModified) => {
+            let cached = cached?;
+            Some(PreparedImage {
+                file: InputFile::file_id(FileId(cached.file_id)),
+                source: Some((image_id, cached.hash)),
+                from_cache: true,
+            })
+        }
+        Ok(ImageDownload::Downloaded { bytes, hash }) => {
+            // The same content may have been uploaded under another image id
+            let file_id = match (cache, &hash) {
+                (Some(cache), Some(hash)) => cache.get_by_hash(hash).await.unwrap_or_else(|err| {
+                    tracing::error!(%image_id, error = %err, "Failed to read cached file id");
+                    None
+                }),
+                _ => None,
+            };
+            let from_cache = file_id.is_some();
+            let file = match file_id {
+                Some(file_id) => InputFile::file_id(FileId(file_id)),
+                None => InputFile::memory(bytes),
+            };
+            Some(PreparedImage {
+                file,
+                source: hash.map(|hash| (image_id, hash)),
+                from_cache,
+            })
+        }
+        Err(err) => {
+            tracing::error!(%image_id, error = %err, "Failed to get image");
+            None
+        }
+    }
+}
+
 /// Check if message has photo
 fn has_photo(msg_by: &MsgBy) -> bool {
     match msg_by {
@@ -350,11 +767,11 @@ async fn edit_media_msg(
     chat_id: ChatId,
     msg_id: Option<MessageId>,
     text: &str,
-    image_bytes: Bytes,
+    image: InputFile,
     reply_keyboard: InlineKeyboardMarkup,
 ) -> AppResult<Message> {
     if let Some(msg_id) = msg_id {
-        let input_media = InputMediaPhoto::new(InputFile::memory(image_bytes.clone()))
+        let input_media = InputMediaPhoto::new(image.clone())
             .caption(text)
             .parse_mode(ParseMode::Html);
         if let Ok(msg) = bot
@@ -370,7 +787,7 @@ async fn edit_media_msg(
         bot,
         chat_id,
         text,
-        image_bytes,
+        image,
         ReplyMarkup::InlineKeyboard(reply_keyboard),
     )
     .await
@@ -408,11 +825,11 @@ async fn send_media_msg(
     bot: &Bot,
     chat_id: ChatId,
     text: &str,
-    image_bytes: Bytes,
+    image: InputFile,
     reply_keyboard: ReplyMarkup,
 ) -> AppResult<Message> {
     Ok(bot
-        .send_photo(chat_id, InputFile::memory(image_bytes))
+        .send_photo(chat_id, image)
         .caption(text)
         .parse_mode(ParseMode::Html)
         .reply_markup(reply_keyboard)
@@ -2,6 +2,7 @@ use crate::AppState;
 use crate::api::backend_api::BackendApi;
 use crate::bot::{BotUsername, run_bot};
 use crate::errors::AppError;
+use crate::file_id_cache::FileIdCache;
 use shared_dtos::list_query::{Pagination, RawListQuery};
 use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
@@ -110,7 +111,8 @@ impl BotManager {
                         &app_state.config.service_api_key,
                         Some(bot_id),
                     )
-                    .expect("Failed to create BackendApi"),
+                    .expect("Failed to create BackendApi")
+                    .with_file_id_cache(FileIdCache::new(app_state.redis_pool.clone(), bot_id)),
                 );
                 let fallback_bot_name = fallback_bot_name.clone();
                 match run_bot(
@@ -11,10 +11,17 @@ pub struct Config {
     pub backend_api_url: String,
     pub webhook_host: String,
     pub webhook_port: u16,
+    /// Internal port of `/metrics`, kept off `webhook_port` so it isn't public
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
     pub payment_instructions_url: String,
     pub manager_bot_token: Option<String>,
 }
 
+fn default_metrics_port() -> u16 {
+    9100
+}
+
 impl Config {
     pub fn from_env() -> Self {
         dotenv().ok();
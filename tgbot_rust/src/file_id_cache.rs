@@ -0,0 +1,90 @@
+use deadpool_redis::{
+    Pool,
+    redis::{self, AsyncCommands},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+
+/// Telegram keeps uploaded files around, an unused entry just expires
+const FILE_ID_TTL_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// Image already uploaded to Telegram by this bot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedFileId {
+    /// blake3 hash of the uploaded content, the backend's image ETag
+    pub hash: String,
+    pub file_id: String,
+}
+
+/// Per-bot Redis cache of Telegram `file_id`s of backend images.
+/// File ids are only valid for the bot that uploaded the file.
+#[derive(Clone)]
+pub struct FileIdCache {
+    redis_pool: Pool,
+    bot_id: i64,
+}
+
+impl FileIdCache {
+    pub fn new(redis_pool: Pool, bot_id: i64) -> Self {
+        Self { redis_pool, bot_id }
+    }
+
+    fn image_key(&self, image_id: &Uuid) -> String {
+        format!("tg-file-id:{}:image:{image_id}", self.bot_id)
+    }
+
+    fn hash_key(&self, hash: &str) -> String {
+        format!("tg-file-id:{}:hash:{hash}", self.bot_id)
+    }
+
+    pub async fn get(&self, image_id: &Uuid) -> AppResult<Option<CachedFileId>> {
+        let mut conn = self.redis_pool.get().await?;
+        let value: Option<String> = conn
+            .get(self.image_key(image_id))
+            .await
+            .map_err(redis_error)?;
+        value
+            .map(|value| serde_json::from_str(&value))
+            .transpose()
+            .map_err(|err| AppError::InternalServerError(format!("Invalid cached file id: {err}")))
+    }
+
+    /// File id of the same content uploaded under another image id
+    pub async fn get_by_hash(&self, hash: &str) -> AppResult<Option<String>> {
+        let mut conn = self.redis_pool.get().await?;
+        conn.get(self.hash_key(hash)).await.map_err(redis_error)
+    }
+
+    pub async fn set(&self, image_id: &Uuid, cached: &CachedFileId) -> AppResult<()> {
+        let value = serde_json::to_string(cached).map_err(|err| {
+            AppError::InternalServerError(format!("Failed to serialize file id: {err}"))
+        })?;
+        let mut conn = self.redis_pool.get().await?;
+        let _: () = redis::pipe()
+            .set_ex(self.image_key(image_id), value, FILE_ID_TTL_SECONDS)
+            .set_ex(
+                self.hash_key(&cached.hash),
+                &cached.file_id,
+                FILE_ID_TTL_SECONDS,
+            )
+            .query_async(&mut *conn)
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    pub async fn invalidate(&self, image_id: &Uuid, hash: &str) -> AppResult<()> {
+        let mut conn = self.redis_pool.get().await?;
+        let _: () = conn
+            .del(&[self.image_key(image_id), self.hash_key(hash)])
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+}
+
+fn redis_error(err: redis::RedisError) -> AppError {
+    AppError::InternalServerError(format!("Redis file id cache error: {err}"))
+}
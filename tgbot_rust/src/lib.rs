@@ -4,7 +4,9 @@ pub mod bot_father;
 pub mod bot_manager;
 pub mod config;
 pub mod errors;
+pub mod file_id_cache;
 pub mod manager_bot;
+pub mod telemetry;
 pub mod webhook;
 
 use std::{io, sync::Arc};
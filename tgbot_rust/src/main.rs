@@ -1,16 +1,18 @@
 use std::sync::Arc;
 
 use anyhow::Context;
+use axum::{Router, routing::get};
 use tgbot_rust::bot_manager::BotManager;
 use tgbot_rust::config::Config;
 use tgbot_rust::manager_bot::spawn_manager_bot_supervisor;
 use tgbot_rust::webhook::create_webhook_service;
-use tgbot_rust::{AppState, create_redis_pool, init_logging};
+use tgbot_rust::{AppState, create_redis_pool, init_logging, telemetry};
 use tokio::signal;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_logging();
+    telemetry::install();
     tracing::info!("Starting application");
     let config = Arc::new(Config::from_env());
     let redis_pool = create_redis_pool(&config).await;
@@ -34,6 +36,13 @@ async fn main() -> anyhow::Result<()> {
 
     spawn_manager_bot_supervisor(config.clone());
 
+    let metrics_service = Router::new().route("/metrics", get(telemetry::metrics));
+    tokio::spawn(async move {
+        if let Err(e) = telemetry::serve_metrics(config.metrics_port, metrics_service).await {
+            tracing::error!(error = %e, "Metrics server error");
+        }
+    });
+
     if let Err(e) = server.await {
         tracing::error!(error = %e, "Axum server error");
     }
@@ -1,6 +1,11 @@
 use std::sync::Arc;
 
-use shared_dtos::{balance_request::StoreBalanceRequestType, notification::DispatchAdminMessage};
+use chrono::NaiveDate;
+use shared_dtos::{
+    balance_request::StoreBalanceRequestType,
+    notification::DispatchAdminMessage,
+    reconciliation::{ReconciliationDiscrepancyKind, ReconciliationKindCount},
+};
 use teloxide::{ApiError, RequestError};
 use teloxide::{
     Bot,
@@ -210,25 +215,66 @@ impl ManagerCallbackAction {
     }
 }
 
-fn build_admin_request_message(payload: &DispatchAdminMessage) -> (String, InlineKeyboardMarkup) {
-    let (request_id, amount, request_type, action_text) = match payload {
+fn build_admin_message(payload: &DispatchAdminMessage) -> (String, Option<InlineKeyboardMarkup>) {
+    match payload {
         DispatchAdminMessage::StoreBalanceRequestNotification {
             store_balance_request_id,
             amount,
             r#type,
-        } => (
-            *store_balance_request_id,
-            *amount,
-            *r#type,
-            match r#type {
-                StoreBalanceRequestType::Withdrawal => {
-                    "Подтвердите, что выплата отправлена клиенту."
+        } => {
+            let (message, keyboard) =
+                build_admin_request_message(*store_balance_request_id, *amount, *r#type);
+            (message, Some(keyboard))
+        }
+        DispatchAdminMessage::ReconciliationDiscrepanciesNotification {
+            report_id,
+            day,
+            counts,
+        } => (build_reconciliation_message(*report_id, day, counts), None),
+    }
+}
+
+fn build_reconciliation_message(
+    report_id: i64,
+    day: &NaiveDate,
+    counts: &[ReconciliationKindCount],
+) -> String {
+    let lines = counts
+        .iter()
+        .map(|c| {
+            let kind_text = match c.kind {
+                ReconciliationDiscrepancyKind::CustomerBalanceMismatch => {
+                    "Баланс клиента не совпадает с суммой транзакций"
                 }
-                StoreBalanceRequestType::Deposit => {
-                    "Подтвердите, что средства от клиента получены."
+                ReconciliationDiscrepancyKind::UserBalanceChainBroken => {
+                    "Нарушена цепочка баланса клиента"
                 }
-            },
-        ),
+                ReconciliationDiscrepancyKind::StoreBalanceChainBroken => {
+                    "Нарушена цепочка баланса магазина"
+                }
+                ReconciliationDiscrepancyKind::InvoiceMissingDeposit => {
+                    "Оплаченный счёт без зачисления"
+                }
+                ReconciliationDiscrepancyKind::InvoiceDuplicateDeposits => {
+                    "Повторное зачисление по счёту"
+                }
+            };
+            format!("• {kind_text}: {}", c.count)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("⚠️ Сверка за {day} (отчёт #{report_id}) нашла расхождения:\n{lines}")
+}
+
+fn build_admin_request_message(
+    request_id: i64,
+    amount: f64,
+    request_type: StoreBalanceRequestType,
+) -> (String, InlineKeyboardMarkup) {
+    let action_text = match request_type {
+        StoreBalanceRequestType::Withdrawal => "Подтвердите, что выплата отправлена клиенту.",
+        StoreBalanceRequestType::Deposit => "Подтвердите, что средства от клиента получены.",
     };
 
     let request_type_text = match request_type {
@@ -275,9 +321,11 @@ pub async fn dispatch_admin_message(
     };
 
     let bot = Bot::new(manager_bot_token);
-    let (text, keyboard) = build_admin_request_message(&payload);
-    bot.send_message(ChatId(chat_id), text)
-        .reply_markup(keyboard)
-        .await?;
+    let (text, keyboard) = build_admin_message(&payload);
+    let request = bot.send_message(ChatId(chat_id), text);
+    match keyboard {
+        Some(keyboard) => request.reply_markup(keyboard).await?,
+        None => request.await?,
+    };
     Ok(())
 }
@@ -0,0 +1,147 @@
+use std::{sync::OnceLock, time::Instant};
+
+use axum::{
+    Router,
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{Unit, describe_gauge, describe_histogram, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use uuid::Uuid;
+
+/// Buckets of every `*_seconds` histogram, the `SLOW_*` log thresholds fall on bucket bounds
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 1.5, 2.5, 5.0, 10.0, 30.0,
+];
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Installs the process-wide Prometheus recorder
+pub fn install() {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), DURATION_BUCKETS)
+        .expect("duration buckets are not empty")
+        .install_recorder()
+        .expect("failed to install Prometheus recorder");
+
+    describe_histogram!(
+        "http_request_duration_seconds",
+        Unit::Seconds,
+        "Webhook server request latency by method, route and status"
+    );
+    describe_histogram!(
+        "backend_request_duration_seconds",
+        Unit::Seconds,
+        "Backend API call latency by method, endpoint and status"
+    );
+    describe_histogram!(
+        "callback_handling_duration_seconds",
+        Unit::Seconds,
+        "Callback query handling time by callback type"
+    );
+    describe_histogram!(
+        "dispatch_message_duration_seconds",
+        Unit::Seconds,
+        "Dispatched message handling time by message type"
+    );
+    describe_histogram!(
+        "dispatch_queue_wait_seconds",
+        Unit::Seconds,
+        "Time dispatched messages wait in the per-bot queue"
+    );
+    describe_gauge!(
+        "dispatch_queue_depth",
+        "Dispatched messages waiting in the per-bot queue"
+    );
+
+    let _ = PROMETHEUS.set(handle);
+}
+
+pub fn render() -> String {
+    match PROMETHEUS.get() {
+        Some(handle) => {
+            handle.run_upkeep();
+            handle.render()
+        }
+        None => String::new(),
+    }
+}
+
+pub async fn metrics() -> String {
+    render()
+}
+
+/// Serves `router` on the internal metrics port, the webhook listener doesn't expose it
+pub async fn serve_metrics(port: u16, router: Router) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    axum::serve(listener, router).await
+}
+
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let started_at = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    histogram!(
+        "http_request_duration_seconds",
+        "method" => method,
+        "route" => route,
+        "status" => response.status().as_u16().to_string(),
+    )
+    .record(started_at.elapsed().as_secs_f64());
+
+    response
+}
+
+pub fn record_backend_request(
+    method: &'static str,
+    endpoint: &str,
+    status: u16,
+    started_at: Instant,
+) {
+    histogram!(
+        "backend_request_duration_seconds",
+        "method" => method,
+        "endpoint" => endpoint_label(endpoint),
+        "status" => status.to_string(),
+    )
+    .record(started_at.elapsed().as_secs_f64());
+}
+
+pub fn record_callback(callback_type: &'static str, started_at: Instant) {
+    histogram!("callback_handling_duration_seconds", "callback_type" => callback_type)
+        .record(started_at.elapsed().as_secs_f64());
+}
+
+pub fn record_dispatch(msg_type: &'static str, enqueued_at: Instant, started_at: Instant) {
+    histogram!("dispatch_queue_wait_seconds", "msg_type" => msg_type)
+        .record(started_at.duration_since(enqueued_at).as_secs_f64());
+    histogram!("dispatch_message_duration_seconds", "msg_type" => msg_type)
+        .record(started_at.elapsed().as_secs_f64());
+}
+
+pub fn dispatch_queue_depth(bot_id: i64, delta: f64) {
+    gauge!("dispatch_queue_depth", "bot_id" => bot_id.to_string()).increment(delta);
+}
+
+/// Endpoint with the query string dropped and ids replaced, keeping label cardinality bounded
+fn endpoint_label(endpoint: &str) -> String {
+    let path = endpoint.split('?').next().unwrap_or(endpoint);
+    path.split('/')
+        .map(|segment| {
+            if segment.parse::<i64>().is_ok() || segment.parse::<Uuid>().is_ok() {
+                ":id"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
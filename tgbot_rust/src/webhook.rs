@@ -3,7 +3,7 @@ use tracing::Level;
 
 mod dispatch_admin_message;
 mod dispatch_message;
-use crate::AppState;
+use crate::{AppState, telemetry};
 use dispatch_admin_message::dispatch_admin_message_handler;
 use dispatch_message::dispatch_message;
 use tower_http::trace::TraceLayer;
@@ -15,6 +15,7 @@ pub fn create_webhook_service(app_state: AppState) -> Router {
             "/webhook/dispatch-admin-message",
             post(dispatch_admin_message_handler),
         )
+        .route_layer(axum::middleware::from_fn(telemetry::track_http))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(tower_http::trace::DefaultMakeSpan::new().level(Level::INFO))